@@ -81,6 +81,9 @@ enum class ErrorCode : score::result::ErrorCode {
 
     /* Invalid value type*/
     InvalidValueType,
+
+    /* Unsupported format version*/
+    UnsupportedVersion,
 };
 
 class MyErrorDomain final : public score::result::ErrorDomain
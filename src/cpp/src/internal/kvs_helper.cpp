@@ -11,6 +11,7 @@
 * SPDX-License-Identifier: Apache-2.0
 ********************************************************************************/
 #include "kvs_helper.hpp"
+#include <charconv>
 
 namespace score::mw::per::kvs {
 
@@ -90,6 +91,14 @@ bool check_hash(const std::string& data_calculate, std::istream& data_parse){
 
 /*********************** Standalone Helper Functions *********************/
 
+/* Helper Function for parsing a complete decimal integer string */
+template <typename T>
+static bool parse_integer(const std::string& str, T& value) {
+    const char* end = str.data() + str.size();
+    auto [ptr, ec] = std::from_chars(str.data(), end, value);
+    return ec == std::errc() && ptr == end;
+}
+
 /* Helper Function for Any -> KVSValue conversion */
 score::Result<KvsValue> any_to_kvsvalue(const score::json::Any& any){
     score::Result<KvsValue> result = score::MakeUnexpected(ErrorCode::UnmappedError);
@@ -119,17 +128,26 @@ score::Result<KvsValue> any_to_kvsvalue(const score::json::Any& any){
                     }
                 }
                 else if (typeStrV == "i64") {
+                    int64_t parsed{};
                     if (auto n = valueAny.As<int64_t>(); n.has_value()) {
                         result = KvsValue(static_cast<int64_t>(n.value()));
                     }
+                    /* Rust implementation stores 64-bit integers as strings */
+                    else if (auto s = valueAny.As<std::string>(); s.has_value() && parse_integer(s.value().get(), parsed)) {
+                        result = KvsValue(parsed);
+                    }
                     else {
                         result = score::MakeUnexpected(ErrorCode::InvalidValueType);
                     }
                 }
                 else if (typeStrV == "u64") {
+                    uint64_t parsed{};
                     if (auto n = valueAny.As<uint64_t>(); n.has_value()) {
                         result = KvsValue(static_cast<uint64_t>(n.value()));
                     }
+                    else if (auto s = valueAny.As<std::string>(); s.has_value() && parse_integer(s.value().get(), parsed)) {
+                        result = KvsValue(parsed);
+                    }
                     else {
                         result = score::MakeUnexpected(ErrorCode::InvalidValueType);
                     }
@@ -25,6 +25,13 @@ using namespace std;
 
 namespace score::mw::per::kvs {
 
+/* Format version field written into the root object by the Rust implementation */
+constexpr std::string_view kVersionField = "__kvs_version";
+/* Newest format version that can be read, see Rust `kvs_migration::KVS_FORMAT_VERSION` */
+constexpr uint32_t kMaxFormatVersion = 2;
+/* Files without version field use format version 1 */
+constexpr uint32_t kMinFormatVersion = 1;
+
 /*********************** KVS Implementation *********************/
 Kvs::~Kvs(){
     if (flush_on_exit.load(std::memory_order_relaxed)) {
@@ -105,18 +112,49 @@ score::Result<std::unordered_map<std::string, KvsValue>> Kvs::parse_json_data(co
         std::unordered_map<std::string, KvsValue> result_value;
 
         if (auto obj = root.As<score::json::Object>(); obj.has_value()) {
-            bool error = false;
-            for (const auto& element : obj.value().get()) {
-                auto sv = element.first.GetAsStringView();
-                std::string key(sv.data(), sv.size());
+            /* Rust implementation stores the data in a type-tagged root object: {"t":"obj","v":{...}} */
+            const score::json::Object* members = &obj.value().get();
+            auto type  = members->find("t");
+            auto value = members->find("v");
+            if (type != members->end() && value != members->end()) {
+                auto type_str = type->second.As<std::string>();
+                auto inner = value->second.As<score::json::Object>();
+                if (type_str.has_value() && std::string_view(type_str.value().get()) == "obj" && inner.has_value()) {
+                    members = &inner.value().get();
+                }
+            }
 
-                auto conv = any_to_kvsvalue(element.second);
-                if (!conv) {
-                    result = score::MakeUnexpected(static_cast<ErrorCode>(*conv.error()));
+            bool error = false;
+            /* Validate format version, stored next to the type tag of the root object */
+            if (auto version = obj.value().get().find(kVersionField.data()); version != obj.value().get().end()) {
+                auto n = version->second.As<uint32_t>();
+                if (!n.has_value()) {
+                    logger->LogError() << "error: invalid " << kVersionField << " field";
+                    result = score::MakeUnexpected(ErrorCode::JsonParserError);
                     error = true;
-                    break;
-                }else{
-                    result_value.emplace(std::move(key), std::move(conv.value()));
+                } else if ((n.value() < kMinFormatVersion) || (n.value() > kMaxFormatVersion)) {
+                    logger->LogError() << "error: unsupported format version " << n.value();
+                    result = score::MakeUnexpected(ErrorCode::UnsupportedVersion);
+                    error = true;
+                }
+            }
+
+            if (!error) {
+                for (const auto& element : *members) {
+                    auto sv = element.first.GetAsStringView();
+                    std::string key(sv.data(), sv.size());
+                    if (key == kVersionField) {
+                        continue;
+                    }
+
+                    auto conv = any_to_kvsvalue(element.second);
+                    if (!conv) {
+                        result = score::MakeUnexpected(static_cast<ErrorCode>(*conv.error()));
+                        error = true;
+                        break;
+                    }else{
+                        result_value.emplace(std::move(key), std::move(conv.value()));
+                    }
                 }
             }
             if (!error) {
@@ -104,6 +104,92 @@ TEST(kvs_TEST, parse_json_data_sucess) {
     cleanup_environment();
 }
 
+TEST(kvs_TEST, parse_json_data_tagged_root) {
+    /* Type-tagged root object written by Rust implementation */
+    prepare_environment();
+
+    auto kvs = Kvs::open(InstanceId(instance_id), OpenNeedDefaults::Optional, OpenNeedKvs::Optional, std::string(data_dir));
+    ASSERT_TRUE(kvs);
+
+    auto mock_parser = std::make_unique<score::json::IJsonParserMock>();
+    score::json::Object root;
+    score::json::Object members;
+    score::json::Object inner_obj;
+    inner_obj.emplace("t", score::json::Any(std::string("i32")));
+    inner_obj.emplace("v", score::json::Any(42));
+    members.emplace("kvs", score::json::Any(std::move(inner_obj)));
+    root.emplace("t", score::json::Any(std::string("obj")));
+    root.emplace("v", score::json::Any(std::move(members)));
+    score::json::Any any_obj(std::move(root));
+
+    EXPECT_CALL(*mock_parser, FromBuffer(::testing::_))
+        .WillOnce(::testing::Return(score::Result<score::json::Any>(std::move(any_obj))));
+
+    kvs->parser = std::move(mock_parser);
+
+    auto result = kvs->parse_json_data("data_not_used_in_mocking");
+    ASSERT_TRUE(result);
+    EXPECT_EQ(result.value().size(), 1U);
+    ASSERT_TRUE(result.value().count("kvs"));
+    EXPECT_EQ(result.value().at("kvs").getType(), KvsValue::Type::i32);
+    EXPECT_EQ(std::get<int32_t>(result.value().at("kvs").getValue()), 42);
+
+    cleanup_environment();
+}
+
+TEST(kvs_TEST, parse_json_data_format_version) {
+    prepare_environment();
+
+    auto kvs = Kvs::open(InstanceId(instance_id), OpenNeedDefaults::Optional, OpenNeedKvs::Optional, std::string(data_dir));
+    ASSERT_TRUE(kvs);
+
+    auto make_root = [](score::json::Any version) {
+        score::json::Object root;
+        score::json::Object members;
+        score::json::Object inner_obj;
+        inner_obj.emplace("t", score::json::Any(std::string("i32")));
+        inner_obj.emplace("v", score::json::Any(42));
+        members.emplace("kvs", score::json::Any(std::move(inner_obj)));
+        root.emplace("t", score::json::Any(std::string("obj")));
+        root.emplace("v", score::json::Any(std::move(members)));
+        root.emplace("__kvs_version", std::move(version));
+        return score::json::Any(std::move(root));
+    };
+
+    /* Supported version is skipped */
+    auto mock_parser = std::make_unique<score::json::IJsonParserMock>();
+    EXPECT_CALL(*mock_parser, FromBuffer(::testing::_))
+        .WillOnce(::testing::Return(score::Result<score::json::Any>(make_root(score::json::Any(2)))));
+    kvs->parser = std::move(mock_parser);
+
+    auto result = kvs->parse_json_data("data_not_used_in_mocking");
+    ASSERT_TRUE(result);
+    EXPECT_EQ(result.value().size(), 1U);
+    EXPECT_FALSE(result.value().count("__kvs_version"));
+
+    /* Newer version is rejected */
+    mock_parser = std::make_unique<score::json::IJsonParserMock>();
+    EXPECT_CALL(*mock_parser, FromBuffer(::testing::_))
+        .WillOnce(::testing::Return(score::Result<score::json::Any>(make_root(score::json::Any(3)))));
+    kvs->parser = std::move(mock_parser);
+
+    result = kvs->parse_json_data("data_not_used_in_mocking");
+    EXPECT_FALSE(result.has_value());
+    EXPECT_EQ(result.error(), ErrorCode::UnsupportedVersion);
+
+    /* Invalid version is rejected */
+    mock_parser = std::make_unique<score::json::IJsonParserMock>();
+    EXPECT_CALL(*mock_parser, FromBuffer(::testing::_))
+        .WillOnce(::testing::Return(score::Result<score::json::Any>(make_root(score::json::Any(std::string("2"))))));
+    kvs->parser = std::move(mock_parser);
+
+    result = kvs->parse_json_data("data_not_used_in_mocking");
+    EXPECT_FALSE(result.has_value());
+    EXPECT_EQ(result.error(), ErrorCode::JsonParserError);
+
+    cleanup_environment();
+}
+
 TEST(kvs_TEST, parse_json_data_failure) {
 
     prepare_environment();
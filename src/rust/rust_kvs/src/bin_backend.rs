@@ -0,0 +1,594 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::error_code::ErrorCode;
+use crate::kvs_api::{FloatFormat, InstanceId, SnapshotId};
+use crate::kvs_backend::{
+    check_extension, validate_hash, write_hash, KvsBackend, KvsPathResolver, MAX_NESTING_DEPTH,
+};
+use crate::kvs_fs::StdFs;
+use crate::kvs_log::kvs_error;
+use crate::kvs_value::{KvsMap, KvsValue};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// File starts with the magic `KVSB` and a format version byte, followed by the root map.
+// All integers are little-endian, lengths and counts are `u32`:
+//   * map -> count, then per entry the key as string followed by the value
+//   * string -> byte length, then UTF-8 bytes
+//   * value -> type byte, then the payload:
+//     * `TYPE_I32`, `TYPE_U32` -> 4 bytes, `TYPE_I64`, `TYPE_U64` -> 8 bytes
+//     * `TYPE_F64` -> 8 bytes IEEE 754 bit pattern, stored exactly
+//     * `TYPE_BOOL` -> 1 byte, `0` or `1`
+//     * `TYPE_STR` -> string, `TYPE_NULL` -> no payload
+//     * `TYPE_ARR` -> count, then the values
+//     * `TYPE_OBJ` -> map
+//
+// Arrays and maps nested deeper than `MAX_NESTING_DEPTH` levels are rejected when decoding.
+
+/// Magic at the start of every file.
+const MAGIC: &[u8; 4] = b"KVSB";
+
+/// Version of the format.
+const VERSION: u8 = 1;
+
+const TYPE_I32: u8 = 0;
+const TYPE_U32: u8 = 1;
+const TYPE_I64: u8 = 2;
+const TYPE_U64: u8 = 3;
+const TYPE_F64: u8 = 4;
+const TYPE_BOOL: u8 = 5;
+const TYPE_STR: u8 = 6;
+const TYPE_NULL: u8 = 7;
+const TYPE_ARR: u8 = 8;
+const TYPE_OBJ: u8 = 9;
+
+/// Reader over encoded data.
+struct Decoder<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl<'a> Decoder<'a> {
+    /// Take the next `len` bytes.
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ErrorCode> {
+        let Some(bytes) = self
+            .pos
+            .checked_add(len)
+            .and_then(|end| self.buf.get(self.pos..end))
+        else {
+            kvs_error!("binary KVS data is truncated");
+            return Err(ErrorCode::SerializationFailed);
+        };
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    /// Take the next `N` bytes as array.
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N], ErrorCode> {
+        let mut bytes = [0; N];
+        bytes.copy_from_slice(self.take(N)?);
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, ErrorCode> {
+        Ok(self.take_array::<1>()?[0])
+    }
+
+    fn len(&mut self) -> Result<usize, ErrorCode> {
+        let len = u32::from_le_bytes(self.take_array()?);
+        usize::try_from(len).map_err(|_| ErrorCode::SerializationFailed)
+    }
+
+    /// Remaining bytes, used to limit preallocation by untrusted counts.
+    fn remaining(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+
+    /// Enter a nested array or map.
+    fn enter(&mut self) -> Result<(), ErrorCode> {
+        if self.depth == MAX_NESTING_DEPTH {
+            kvs_error!("binary KVS data nested deeper than {MAX_NESTING_DEPTH} levels");
+            return Err(ErrorCode::SerializationFailed);
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn string(&mut self) -> Result<String, ErrorCode> {
+        let len = self.len()?;
+        let bytes = self.take(len)?;
+        match std::str::from_utf8(bytes) {
+            Ok(s) => Ok(s.to_string()),
+            Err(e) => {
+                kvs_error!("binary KVS string is not valid UTF-8: {e}");
+                Err(ErrorCode::SerializationFailed)
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<KvsValue, ErrorCode> {
+        let value = match self.u8()? {
+            TYPE_I32 => KvsValue::I32(i32::from_le_bytes(self.take_array()?)),
+            TYPE_U32 => KvsValue::U32(u32::from_le_bytes(self.take_array()?)),
+            TYPE_I64 => KvsValue::I64(i64::from_le_bytes(self.take_array()?)),
+            TYPE_U64 => KvsValue::U64(u64::from_le_bytes(self.take_array()?)),
+            TYPE_F64 => KvsValue::F64(f64::from_le_bytes(self.take_array()?)),
+            TYPE_BOOL => match self.u8()? {
+                0 => KvsValue::Boolean(false),
+                1 => KvsValue::Boolean(true),
+                b => {
+                    kvs_error!("invalid binary KVS bool: {b}");
+                    return Err(ErrorCode::SerializationFailed);
+                }
+            },
+            TYPE_STR => KvsValue::String(self.string()?),
+            TYPE_NULL => KvsValue::Null,
+            TYPE_ARR => {
+                self.enter()?;
+                let len = self.len()?;
+                // Every value takes at least one byte.
+                let mut arr = Vec::with_capacity(len.min(self.remaining()));
+                for _ in 0..len {
+                    arr.push(self.value()?);
+                }
+                self.depth -= 1;
+                KvsValue::Array(arr)
+            }
+            TYPE_OBJ => KvsValue::Object(self.map()?),
+            type_id => {
+                kvs_error!("unsupported binary KVS value type: {type_id}");
+                return Err(ErrorCode::SerializationFailed);
+            }
+        };
+        Ok(value)
+    }
+
+    fn map(&mut self) -> Result<KvsMap, ErrorCode> {
+        self.enter()?;
+        let len = self.len()?;
+        // Every entry takes at least five bytes.
+        let mut map = KvsMap::with_capacity(len.min(self.remaining() / 5));
+        for _ in 0..len {
+            let key = self.string()?;
+            let value = self.value()?;
+            map.insert(key, value);
+        }
+        self.depth -= 1;
+        Ok(map)
+    }
+}
+
+/// Decode binary data into `KvsMap`.
+fn decode(buf: &[u8]) -> Result<KvsMap, ErrorCode> {
+    let mut decoder = Decoder {
+        buf,
+        pos: 0,
+        depth: 0,
+    };
+    if decoder.take(MAGIC.len())? != MAGIC {
+        kvs_error!("binary KVS data has no valid magic");
+        return Err(ErrorCode::SerializationFailed);
+    }
+    let version = decoder.u8()?;
+    if version != VERSION {
+        kvs_error!("unsupported binary KVS format version: {version}");
+        return Err(ErrorCode::SerializationFailed);
+    }
+    let kvs_map = decoder.map()?;
+    if decoder.remaining() != 0 {
+        kvs_error!("binary KVS data contains trailing bytes");
+        return Err(ErrorCode::SerializationFailed);
+    }
+    Ok(kvs_map)
+}
+
+/// Encode length or count.
+fn encode_len(buf: &mut Vec<u8>, len: usize) -> Result<(), ErrorCode> {
+    let len = u32::try_from(len).map_err(|_| {
+        kvs_error!("binary KVS length {len} exceeds the format limit");
+        ErrorCode::SerializationFailed
+    })?;
+    buf.extend_from_slice(&len.to_le_bytes());
+    Ok(())
+}
+
+fn encode_string(buf: &mut Vec<u8>, s: &str) -> Result<(), ErrorCode> {
+    encode_len(buf, s.len())?;
+    buf.extend_from_slice(s.as_bytes());
+    Ok(())
+}
+
+fn encode_value(buf: &mut Vec<u8>, value: &KvsValue) -> Result<(), ErrorCode> {
+    match value {
+        KvsValue::I32(n) => {
+            buf.push(TYPE_I32);
+            buf.extend_from_slice(&n.to_le_bytes());
+        }
+        KvsValue::U32(n) => {
+            buf.push(TYPE_U32);
+            buf.extend_from_slice(&n.to_le_bytes());
+        }
+        KvsValue::I64(n) => {
+            buf.push(TYPE_I64);
+            buf.extend_from_slice(&n.to_le_bytes());
+        }
+        KvsValue::U64(n) => {
+            buf.push(TYPE_U64);
+            buf.extend_from_slice(&n.to_le_bytes());
+        }
+        KvsValue::F64(n) => {
+            buf.push(TYPE_F64);
+            buf.extend_from_slice(&n.to_le_bytes());
+        }
+        KvsValue::Boolean(b) => buf.extend_from_slice(&[TYPE_BOOL, u8::from(*b)]),
+        KvsValue::String(s) => {
+            buf.push(TYPE_STR);
+            encode_string(buf, s)?;
+        }
+        KvsValue::Null => buf.push(TYPE_NULL),
+        KvsValue::Array(arr) => {
+            buf.push(TYPE_ARR);
+            encode_len(buf, arr.len())?;
+            for element in arr {
+                encode_value(buf, element)?;
+            }
+        }
+        KvsValue::Object(map) => {
+            buf.push(TYPE_OBJ);
+            encode_map(buf, map)?;
+        }
+    }
+    Ok(())
+}
+
+fn encode_map(buf: &mut Vec<u8>, kvs_map: &KvsMap) -> Result<(), ErrorCode> {
+    encode_len(buf, kvs_map.len())?;
+    for (key, value) in kvs_map {
+        encode_string(buf, key)?;
+        encode_value(buf, value)?;
+    }
+    Ok(())
+}
+
+/// Encode `KvsMap` into binary data.
+fn encode(kvs_map: &KvsMap) -> Result<Vec<u8>, ErrorCode> {
+    let mut buf = Vec::new();
+    buf.extend_from_slice(MAGIC);
+    buf.push(VERSION);
+    encode_map(&mut buf, kvs_map)?;
+    Ok(buf)
+}
+
+/// KVS backend implementation based on a compact length-prefixed binary format.
+///
+/// Values are stored without text formatting, `F64` values keep their exact bit pattern and the
+/// float format is ignored.
+pub struct BinBackend;
+
+impl KvsBackend for BinBackend {
+    fn load_kvs(kvs_path: &Path, hash_path: Option<&PathBuf>) -> Result<KvsMap, ErrorCode> {
+        if !check_extension(kvs_path, "bin") {
+            return Err(ErrorCode::KvsFileReadError);
+        }
+        if hash_path.is_some_and(|p| !check_extension(p, "hash")) {
+            return Err(ErrorCode::KvsHashFileReadError);
+        }
+
+        // Load KVS file.
+        let bytes = fs::read(kvs_path)?;
+
+        // Perform hash check before decoding.
+        if let Some(hash_path) = hash_path {
+            validate_hash::<StdFs>(&bytes, hash_path)?;
+        }
+
+        decode(&bytes)
+    }
+
+    fn load_kvs_from_bytes(bytes: &[u8]) -> Result<KvsMap, ErrorCode> {
+        decode(bytes)
+    }
+
+    fn save_kvs(
+        kvs_map: &KvsMap,
+        kvs_path: &Path,
+        hash_path: Option<&PathBuf>,
+    ) -> Result<(), ErrorCode> {
+        // Validate extensions.
+        if !check_extension(kvs_path, "bin") {
+            return Err(ErrorCode::KvsFileReadError);
+        }
+        if hash_path.is_some_and(|p| !check_extension(p, "hash")) {
+            return Err(ErrorCode::KvsHashFileReadError);
+        }
+
+        // Encode and save to KVS file.
+        let bytes = encode(kvs_map)?;
+        fs::write(kvs_path, &bytes)?;
+
+        // Generate hash and save to hash file.
+        if let Some(hash_path) = hash_path {
+            write_hash::<StdFs>(&bytes, hash_path)?;
+        }
+
+        Ok(())
+    }
+
+    fn serialized_size(kvs_map: &KvsMap, _float_format: &FloatFormat) -> Result<usize, ErrorCode> {
+        Ok(encode(kvs_map)?.len())
+    }
+}
+
+/// KVS backend path resolver for `BinBackend`.
+impl KvsPathResolver for BinBackend {
+    fn kvs_file_name(instance_id: InstanceId, snapshot_id: SnapshotId) -> String {
+        format!("kvs_{instance_id}_{snapshot_id}.bin")
+    }
+
+    fn kvs_file_path(
+        working_dir: &Path,
+        instance_id: InstanceId,
+        snapshot_id: SnapshotId,
+    ) -> PathBuf {
+        working_dir.join(Self::kvs_file_name(instance_id, snapshot_id))
+    }
+
+    fn hash_file_name(instance_id: InstanceId, snapshot_id: SnapshotId) -> String {
+        format!("kvs_{instance_id}_{snapshot_id}.hash")
+    }
+
+    fn hash_file_path(
+        working_dir: &Path,
+        instance_id: InstanceId,
+        snapshot_id: SnapshotId,
+    ) -> PathBuf {
+        working_dir.join(Self::hash_file_name(instance_id, snapshot_id))
+    }
+
+    fn defaults_file_name(instance_id: InstanceId) -> String {
+        format!("kvs_{instance_id}_default.bin")
+    }
+
+    fn defaults_file_path(working_dir: &Path, instance_id: InstanceId) -> PathBuf {
+        working_dir.join(Self::defaults_file_name(instance_id))
+    }
+
+    fn global_defaults_file_name() -> String {
+        "kvs_global_default.bin".to_string()
+    }
+
+    fn global_defaults_file_path(working_dir: &Path) -> PathBuf {
+        working_dir.join(Self::global_defaults_file_name())
+    }
+}
+
+#[cfg(test)]
+mod bin_conversion_tests {
+    use crate::bin_backend::{decode, encode, TYPE_ARR, TYPE_BOOL, TYPE_I32, TYPE_NULL, TYPE_STR};
+    use crate::error_code::ErrorCode;
+    use crate::kvs_backend::MAX_NESTING_DEPTH;
+    use crate::kvs_value::{KvsMap, KvsValue};
+
+    fn roundtrip(kvs_map: &KvsMap) -> KvsMap {
+        decode(&encode(kvs_map).unwrap()).unwrap()
+    }
+
+    /// Header and root map with one entry with a one byte key.
+    fn single_entry(key: u8) -> Vec<u8> {
+        let mut bytes = b"KVSB\x01".to_vec();
+        bytes.extend_from_slice(&[1, 0, 0, 0, 1, 0, 0, 0, key]);
+        bytes
+    }
+
+    #[test]
+    fn test_roundtrip_all_types() {
+        let kvs_map = KvsMap::from([
+            ("i32".to_string(), KvsValue::I32(i32::MIN)),
+            ("u32".to_string(), KvsValue::U32(u32::MAX)),
+            ("i64".to_string(), KvsValue::I64(i64::MIN)),
+            ("u64".to_string(), KvsValue::U64(u64::MAX)),
+            ("f64".to_string(), KvsValue::F64(0.1 + 0.2)),
+            ("bool".to_string(), KvsValue::Boolean(true)),
+            ("str".to_string(), KvsValue::from("\"escaped\"\n ✓")),
+            ("null".to_string(), KvsValue::Null),
+            (
+                "arr".to_string(),
+                KvsValue::Array(vec![KvsValue::I32(1), KvsValue::from("two")]),
+            ),
+            (
+                "obj".to_string(),
+                KvsValue::Object(KvsMap::from([("x".to_string(), KvsValue::U64(0))])),
+            ),
+        ]);
+        assert_eq!(roundtrip(&kvs_map), kvs_map);
+    }
+
+    #[test]
+    fn test_encode_compact() {
+        let bytes = encode(&KvsMap::from([("a".to_string(), KvsValue::I32(5))])).unwrap();
+        let mut expected = single_entry(b'a');
+        expected.extend_from_slice(&[TYPE_I32, 5, 0, 0, 0]);
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn test_decode_invalid_magic() {
+        let mut bytes = encode(&KvsMap::new()).unwrap();
+        bytes[0] = b'{';
+        assert!(decode(&bytes).is_err_and(|e| e == ErrorCode::SerializationFailed));
+    }
+
+    #[test]
+    fn test_decode_unsupported_version() {
+        let mut bytes = encode(&KvsMap::new()).unwrap();
+        bytes[4] = 2;
+        assert!(decode(&bytes).is_err_and(|e| e == ErrorCode::SerializationFailed));
+    }
+
+    #[test]
+    fn test_decode_invalid_type() {
+        let mut bytes = single_entry(b'a');
+        bytes.push(0xff);
+        assert!(decode(&bytes).is_err_and(|e| e == ErrorCode::SerializationFailed));
+    }
+
+    #[test]
+    fn test_decode_invalid_bool() {
+        let mut bytes = single_entry(b'a');
+        bytes.extend_from_slice(&[TYPE_BOOL, 2]);
+        assert!(decode(&bytes).is_err_and(|e| e == ErrorCode::SerializationFailed));
+    }
+
+    #[test]
+    fn test_decode_invalid_utf8() {
+        let mut bytes = single_entry(b'a');
+        bytes.extend_from_slice(&[TYPE_STR, 1, 0, 0, 0, 0xff]);
+        assert!(decode(&bytes).is_err_and(|e| e == ErrorCode::SerializationFailed));
+    }
+
+    #[test]
+    fn test_decode_truncated() {
+        let bytes = encode(&KvsMap::from([("a".to_string(), KvsValue::from("text"))])).unwrap();
+        assert!(
+            decode(&bytes[..bytes.len() - 1]).is_err_and(|e| e == ErrorCode::SerializationFailed)
+        );
+    }
+
+    #[test]
+    fn test_decode_count_exceeds_data() {
+        // Root map claims `u32::MAX` entries.
+        let bytes = b"KVSB\x01\xff\xff\xff\xff".to_vec();
+        assert!(decode(&bytes).is_err_and(|e| e == ErrorCode::SerializationFailed));
+    }
+
+    #[test]
+    fn test_decode_nesting_depth() {
+        // Value of the root entry is nested in single element arrays.
+        let nested = |levels: usize| {
+            let mut bytes = single_entry(b'a');
+            for _ in 0..levels {
+                bytes.extend_from_slice(&[TYPE_ARR, 1, 0, 0, 0]);
+            }
+            bytes.push(TYPE_NULL);
+            bytes
+        };
+        assert!(decode(&nested(MAX_NESTING_DEPTH - 1)).is_ok());
+        assert!(
+            decode(&nested(MAX_NESTING_DEPTH)).is_err_and(|e| e == ErrorCode::SerializationFailed)
+        );
+        assert!(decode(&nested(1 << 18)).is_err_and(|e| e == ErrorCode::SerializationFailed));
+    }
+
+    #[test]
+    fn test_decode_trailing_bytes() {
+        let mut bytes = encode(&KvsMap::new()).unwrap();
+        bytes.push(0);
+        assert!(decode(&bytes).is_err_and(|e| e == ErrorCode::SerializationFailed));
+    }
+}
+
+#[cfg(all(test, feature = "json-backend"))]
+mod backend_tests {
+    use crate::bin_backend::BinBackend;
+    use crate::error_code::ErrorCode;
+    use crate::json_backend::JsonBackend;
+    use crate::kvs_backend::KvsBackend;
+    use crate::kvs_value::{KvsMap, KvsValue};
+    use std::path::{Path, PathBuf};
+    use tempfile::tempdir;
+
+    fn kvs_map() -> KvsMap {
+        KvsMap::from([
+            ("k1".to_string(), KvsValue::from("v1")),
+            ("k2".to_string(), KvsValue::from(true)),
+            ("k3".to_string(), KvsValue::from(123.4)),
+            ("k4".to_string(), KvsValue::U32(4000)),
+        ])
+    }
+
+    fn create_kvs_files(working_dir: &Path) -> (PathBuf, PathBuf) {
+        let kvs_path = working_dir.join("kvs.bin");
+        let hash_path = working_dir.join("kvs.hash");
+        BinBackend::save_kvs(&kvs_map(), &kvs_path, Some(&hash_path)).unwrap();
+        (kvs_path, hash_path)
+    }
+
+    #[test]
+    fn test_load_kvs_hash_path_some_ok() {
+        let dir = tempdir().unwrap();
+        let (kvs_path, hash_path) = create_kvs_files(dir.path());
+
+        let kvs_map = BinBackend::load_kvs(&kvs_path, Some(&hash_path)).unwrap();
+        assert_eq!(kvs_map, self::kvs_map());
+    }
+
+    #[test]
+    fn test_save_kvs_smaller_than_json() {
+        let dir = tempdir().unwrap();
+        let (kvs_path, _hash_path) = create_kvs_files(dir.path());
+        let json_path = dir.path().join("kvs.json");
+        JsonBackend::save_kvs(&kvs_map(), &json_path, None).unwrap();
+
+        let bin_size = std::fs::metadata(kvs_path).unwrap().len();
+        let json_size = std::fs::metadata(json_path).unwrap().len();
+        assert!(bin_size < json_size);
+    }
+
+    #[test]
+    fn test_load_kvs_invalid_extension() {
+        let dir = tempdir().unwrap();
+        let kvs_path = dir.path().join("kvs.json");
+
+        assert!(
+            BinBackend::load_kvs(&kvs_path, None).is_err_and(|e| e == ErrorCode::KvsFileReadError)
+        );
+    }
+
+    #[test]
+    fn test_load_kvs_invalid_hash_content() {
+        let dir = tempdir().unwrap();
+        let (kvs_path, hash_path) = create_kvs_files(dir.path());
+        std::fs::write(&hash_path, vec![0x12, 0x34, 0x56, 0x78]).unwrap();
+
+        assert!(BinBackend::load_kvs(&kvs_path, Some(&hash_path))
+            .is_err_and(|e| e == ErrorCode::ValidationFailed));
+    }
+
+    #[test]
+    fn test_save_kvs_invalid_extension() {
+        let dir = tempdir().unwrap();
+        let kvs_path = dir.path().join("kvs.invalid_ext");
+
+        assert!(BinBackend::save_kvs(&KvsMap::new(), &kvs_path, None)
+            .is_err_and(|e| e == ErrorCode::KvsFileReadError));
+    }
+}
+
+#[cfg(test)]
+mod path_resolver_tests {
+    use crate::bin_backend::BinBackend;
+    use crate::kvs_api::{InstanceId, SnapshotId};
+    use crate::kvs_backend::KvsPathResolver;
+
+    #[test]
+    fn test_kvs_file_name() {
+        let act_name = BinBackend::kvs_file_name(InstanceId(123), SnapshotId(2));
+        assert_eq!(act_name, "kvs_123_2.bin");
+    }
+
+    #[test]
+    fn test_defaults_file_name() {
+        let act_name = BinBackend::defaults_file_name(InstanceId(123));
+        assert_eq!(act_name, "kvs_123_default.bin");
+    }
+}
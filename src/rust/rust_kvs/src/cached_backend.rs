@@ -0,0 +1,371 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Read-through caching backend.
+//!
+//! [`CachedBackend`] keeps a local copy of the KVS files and mirrors writes to a remote (primary)
+//! location, e.g. a different partition or a mounted network share:
+//!   * Load: local copy is used if it can be loaded, otherwise the remote files are loaded and the
+//!     local copy is refreshed.
+//!   * Save: data is written to the remote location first, then to the local copy.
+//!
+//! Snapshot rotation is performed on the local working directory only. The remote location always
+//! holds the latest flushed state.
+
+use crate::error_code::ErrorCode;
+use crate::kvs_api::{DuplicateKeyPolicy, FloatFormat, InstanceId, SnapshotId, SyncPolicy};
+use crate::kvs_backend::{KvsBackend, KvsPathResolver};
+use crate::kvs_log::kvs_error;
+use crate::kvs_value::KvsMap;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+/// Remote (primary) location of a cached KVS.
+pub trait KvsRemote {
+    /// Backend used to access remote files.
+    type Backend: KvsBackend;
+
+    /// Map local file path to remote file path.
+    fn remote_path(local_path: &Path) -> PathBuf;
+}
+
+/// Backend reading from a local copy and writing through to a remote location.
+///
+/// File names and paths are resolved by `Local`.
+pub struct CachedBackend<Remote: KvsRemote, Local: KvsBackend> {
+    _remote_marker: PhantomData<Remote>,
+    _local_marker: PhantomData<Local>,
+}
+
+impl<Remote: KvsRemote, Local: KvsBackend> CachedBackend<Remote, Local> {
+    /// Load KvsMap from remote location and refresh local copy.
+    fn load_remote(
+        kvs_path: &Path,
+        hash_path: Option<&PathBuf>,
+        duplicate_keys: DuplicateKeyPolicy,
+    ) -> Result<KvsMap, ErrorCode> {
+        let remote_kvs_path = Remote::remote_path(kvs_path);
+        let remote_hash_path = hash_path.map(|p| Remote::remote_path(p));
+        let kvs_map = Remote::Backend::load_kvs_with_policy(
+            &remote_kvs_path,
+            remote_hash_path.as_ref(),
+            duplicate_keys,
+        )?;
+
+        // Failing to refresh local copy doesn't affect the loaded data.
+        if let Err(e) = Local::save_kvs(&kvs_map, kvs_path, hash_path) {
+            kvs_error!("refreshing local copy failed: {e}");
+        }
+
+        Ok(kvs_map)
+    }
+}
+
+impl<Remote: KvsRemote, Local: KvsBackend> KvsBackend for CachedBackend<Remote, Local> {
+    fn load_kvs(kvs_path: &Path, hash_path: Option<&PathBuf>) -> Result<KvsMap, ErrorCode> {
+        Self::load_kvs_with_policy(kvs_path, hash_path, DuplicateKeyPolicy::default())
+    }
+
+    fn load_kvs_with_policy(
+        kvs_path: &Path,
+        hash_path: Option<&PathBuf>,
+        duplicate_keys: DuplicateKeyPolicy,
+    ) -> Result<KvsMap, ErrorCode> {
+        match Local::load_kvs_with_policy(kvs_path, hash_path, duplicate_keys) {
+            Ok(kvs_map) => Ok(kvs_map),
+            Err(_) => Self::load_remote(kvs_path, hash_path, duplicate_keys),
+        }
+    }
+
+    fn load_kvs_from_bytes(bytes: &[u8]) -> Result<KvsMap, ErrorCode> {
+        Local::load_kvs_from_bytes(bytes)
+    }
+
+    fn save_kvs(
+        kvs_map: &KvsMap,
+        kvs_path: &Path,
+        hash_path: Option<&PathBuf>,
+    ) -> Result<(), ErrorCode> {
+        // Remote location is authoritative and written first.
+        let remote_kvs_path = Remote::remote_path(kvs_path);
+        let remote_hash_path = hash_path.map(|p| Remote::remote_path(p));
+        Remote::Backend::save_kvs(kvs_map, &remote_kvs_path, remote_hash_path.as_ref())?;
+
+        Local::save_kvs(kvs_map, kvs_path, hash_path)
+    }
+
+    fn save_kvs_formatted(
+        kvs_map: &KvsMap,
+        kvs_path: &Path,
+        hash_path: Option<&PathBuf>,
+        float_format: &FloatFormat,
+    ) -> Result<(), ErrorCode> {
+        let remote_kvs_path = Remote::remote_path(kvs_path);
+        let remote_hash_path = hash_path.map(|p| Remote::remote_path(p));
+        Remote::Backend::save_kvs_formatted(
+            kvs_map,
+            &remote_kvs_path,
+            remote_hash_path.as_ref(),
+            float_format,
+        )?;
+
+        Local::save_kvs_formatted(kvs_map, kvs_path, hash_path, float_format)
+    }
+
+    fn serialized_size(kvs_map: &KvsMap, float_format: &FloatFormat) -> Result<usize, ErrorCode> {
+        Local::serialized_size(kvs_map, float_format)
+    }
+
+    fn exists(path: &Path) -> bool {
+        Local::exists(path)
+    }
+
+    fn locks_working_dir() -> bool {
+        Local::locks_working_dir()
+    }
+
+    fn sync_kvs(
+        kvs_path: &Path,
+        hash_path: &Path,
+        sync_policy: SyncPolicy,
+    ) -> Result<(), ErrorCode> {
+        Local::sync_kvs(kvs_path, hash_path, sync_policy)
+    }
+
+    fn move_kvs(
+        old_kvs_path: &Path,
+        old_hash_path: &Path,
+        new_kvs_path: &Path,
+        new_hash_path: &Path,
+    ) -> Result<(), ErrorCode> {
+        Local::move_kvs(old_kvs_path, old_hash_path, new_kvs_path, new_hash_path)
+    }
+
+    fn remove_kvs(kvs_path: &Path, hash_path: &Path) -> Result<(), ErrorCode> {
+        Local::remove_kvs(kvs_path, hash_path)
+    }
+}
+
+impl<Remote: KvsRemote, Local: KvsBackend + KvsPathResolver> KvsPathResolver
+    for CachedBackend<Remote, Local>
+{
+    fn kvs_file_name(instance_id: InstanceId, snapshot_id: SnapshotId) -> String {
+        Local::kvs_file_name(instance_id, snapshot_id)
+    }
+
+    fn kvs_file_path(
+        working_dir: &Path,
+        instance_id: InstanceId,
+        snapshot_id: SnapshotId,
+    ) -> PathBuf {
+        Local::kvs_file_path(working_dir, instance_id, snapshot_id)
+    }
+
+    fn hash_file_name(instance_id: InstanceId, snapshot_id: SnapshotId) -> String {
+        Local::hash_file_name(instance_id, snapshot_id)
+    }
+
+    fn hash_file_path(
+        working_dir: &Path,
+        instance_id: InstanceId,
+        snapshot_id: SnapshotId,
+    ) -> PathBuf {
+        Local::hash_file_path(working_dir, instance_id, snapshot_id)
+    }
+
+    fn defaults_file_name(instance_id: InstanceId) -> String {
+        Local::defaults_file_name(instance_id)
+    }
+
+    fn defaults_file_path(working_dir: &Path, instance_id: InstanceId) -> PathBuf {
+        Local::defaults_file_path(working_dir, instance_id)
+    }
+
+    fn global_defaults_file_name() -> String {
+        Local::global_defaults_file_name()
+    }
+
+    fn global_defaults_file_path(working_dir: &Path) -> PathBuf {
+        Local::global_defaults_file_path(working_dir)
+    }
+}
+
+#[cfg(all(test, feature = "json-backend"))]
+mod cached_backend_tests {
+    use crate::cached_backend::{CachedBackend, KvsRemote};
+    use crate::error_code::ErrorCode;
+    use crate::json_backend::JsonBackend;
+    use crate::kvs::{GenericKvs, KvsParameters};
+    use crate::kvs_api::{InstanceId, KvsApi, SnapshotId};
+    use crate::kvs_backend::{KvsBackend, KvsPathResolver};
+    use crate::kvs_builder::KvsData;
+    use crate::kvs_value::{KvsMap, KvsValue};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex};
+    use tempfile::tempdir;
+
+    /// Remote location in `remote` subdirectory of the local working directory.
+    struct TestRemote;
+
+    impl KvsRemote for TestRemote {
+        type Backend = JsonBackend;
+
+        fn remote_path(local_path: &Path) -> PathBuf {
+            let file_name = local_path.file_name().unwrap();
+            local_path.parent().unwrap().join("remote").join(file_name)
+        }
+    }
+
+    type TestBackend = CachedBackend<TestRemote, JsonBackend>;
+
+    fn paths(working_dir: &Path) -> (PathBuf, PathBuf) {
+        (
+            TestBackend::kvs_file_path(working_dir, InstanceId(1), SnapshotId(0)),
+            TestBackend::hash_file_path(working_dir, InstanceId(1), SnapshotId(0)),
+        )
+    }
+
+    fn kvs_map(value: i32) -> KvsMap {
+        KvsMap::from([("key".to_string(), KvsValue::I32(value))])
+    }
+
+    #[test]
+    fn test_save_writes_both() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("remote")).unwrap();
+        let (kvs_path, hash_path) = paths(dir.path());
+
+        TestBackend::save_kvs(&kvs_map(1), &kvs_path, Some(&hash_path)).unwrap();
+
+        let remote_kvs_path = TestRemote::remote_path(&kvs_path);
+        let remote_hash_path = TestRemote::remote_path(&hash_path);
+        assert_eq!(
+            JsonBackend::load_kvs(&kvs_path, Some(&hash_path)).unwrap(),
+            kvs_map(1)
+        );
+        assert_eq!(
+            JsonBackend::load_kvs(&remote_kvs_path, Some(&remote_hash_path)).unwrap(),
+            kvs_map(1)
+        );
+    }
+
+    #[test]
+    fn test_save_remote_unavailable() {
+        let dir = tempdir().unwrap();
+        let (kvs_path, hash_path) = paths(dir.path());
+
+        let result = TestBackend::save_kvs(&kvs_map(1), &kvs_path, Some(&hash_path));
+        assert!(result.is_err_and(|e| e == ErrorCode::FileNotFound));
+        assert!(!kvs_path.exists());
+    }
+
+    #[test]
+    fn test_load_prefers_local() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("remote")).unwrap();
+        let (kvs_path, hash_path) = paths(dir.path());
+        let remote_kvs_path = TestRemote::remote_path(&kvs_path);
+        let remote_hash_path = TestRemote::remote_path(&hash_path);
+        JsonBackend::save_kvs(&kvs_map(1), &kvs_path, Some(&hash_path)).unwrap();
+        JsonBackend::save_kvs(&kvs_map(2), &remote_kvs_path, Some(&remote_hash_path)).unwrap();
+
+        let loaded = TestBackend::load_kvs(&kvs_path, Some(&hash_path)).unwrap();
+        assert_eq!(loaded, kvs_map(1));
+    }
+
+    #[test]
+    fn test_load_falls_back_to_remote() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("remote")).unwrap();
+        let (kvs_path, hash_path) = paths(dir.path());
+        let remote_kvs_path = TestRemote::remote_path(&kvs_path);
+        let remote_hash_path = TestRemote::remote_path(&hash_path);
+        JsonBackend::save_kvs(&kvs_map(2), &remote_kvs_path, Some(&remote_hash_path)).unwrap();
+
+        let loaded = TestBackend::load_kvs(&kvs_path, Some(&hash_path)).unwrap();
+        assert_eq!(loaded, kvs_map(2));
+
+        // Local copy is refreshed.
+        assert_eq!(
+            JsonBackend::load_kvs(&kvs_path, Some(&hash_path)).unwrap(),
+            kvs_map(2)
+        );
+    }
+
+    #[test]
+    fn test_load_local_corrupted() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("remote")).unwrap();
+        let (kvs_path, hash_path) = paths(dir.path());
+        let remote_kvs_path = TestRemote::remote_path(&kvs_path);
+        let remote_hash_path = TestRemote::remote_path(&hash_path);
+        JsonBackend::save_kvs(&kvs_map(1), &kvs_path, Some(&hash_path)).unwrap();
+        JsonBackend::save_kvs(&kvs_map(2), &remote_kvs_path, Some(&remote_hash_path)).unwrap();
+        fs::write(&hash_path, [0u8; 4]).unwrap();
+
+        let loaded = TestBackend::load_kvs(&kvs_path, Some(&hash_path)).unwrap();
+        assert_eq!(loaded, kvs_map(2));
+        assert_eq!(
+            JsonBackend::load_kvs(&kvs_path, Some(&hash_path)).unwrap(),
+            kvs_map(2)
+        );
+    }
+
+    #[test]
+    fn test_load_not_found() {
+        let dir = tempdir().unwrap();
+        let (kvs_path, hash_path) = paths(dir.path());
+
+        let result = TestBackend::load_kvs(&kvs_path, Some(&hash_path));
+        assert!(result.is_err_and(|e| e == ErrorCode::FileNotFound));
+    }
+
+    #[test]
+    fn test_path_resolver_delegates_to_local() {
+        let dir = tempdir().unwrap();
+        assert_eq!(
+            TestBackend::kvs_file_path(dir.path(), InstanceId(1), SnapshotId(2)),
+            JsonBackend::kvs_file_path(dir.path(), InstanceId(1), SnapshotId(2))
+        );
+        assert_eq!(
+            TestBackend::defaults_file_name(InstanceId(1)),
+            JsonBackend::defaults_file_name(InstanceId(1))
+        );
+        assert_eq!(
+            TestBackend::global_defaults_file_name(),
+            JsonBackend::global_defaults_file_name()
+        );
+    }
+
+    #[test]
+    fn test_flush_writes_through() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("remote")).unwrap();
+        let data = Arc::new(Mutex::new(KvsData::new(KvsMap::new(), KvsMap::new())));
+        let parameters = KvsParameters {
+            working_dir: dir.path().to_path_buf(),
+            ..KvsParameters::new(InstanceId(1))
+        };
+        let kvs = GenericKvs::<TestBackend>::new(data, parameters);
+
+        kvs.set_value("key", 3).unwrap();
+        kvs.flush().unwrap();
+
+        let (kvs_path, hash_path) = paths(dir.path());
+        let remote_kvs_path = TestRemote::remote_path(&kvs_path);
+        let remote_hash_path = TestRemote::remote_path(&hash_path);
+        assert_eq!(
+            JsonBackend::load_kvs(&remote_kvs_path, Some(&remote_hash_path)).unwrap(),
+            kvs_map(3)
+        );
+    }
+}
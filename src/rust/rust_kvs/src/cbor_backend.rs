@@ -0,0 +1,544 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::error_code::ErrorCode;
+use crate::kvs_api::{FloatFormat, InstanceId, SnapshotId};
+use crate::kvs_backend::{
+    check_extension, validate_hash, write_hash, KvsBackend, KvsPathResolver, MAX_NESTING_DEPTH,
+};
+use crate::kvs_fs::StdFs;
+use crate::kvs_log::kvs_error;
+use crate::kvs_value::{KvsMap, KvsValue};
+use minicbor::data::Type;
+use minicbor::decode::Error as DecodeError;
+use minicbor::encode::Error as EncodeError;
+use minicbor::{Decoder, Encoder};
+use std::convert::Infallible;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// KvsValue is stored t-tagged as in the JSON backend, every value is a CBOR map with a type name
+// `t` and the value `v`. The root is an `obj` value:
+//   * `i32`, `u32`, `i64`, `u64` -> CBOR integer, range is checked on read
+//   * `f64` -> CBOR float (single precision is accepted on read)
+//   * `bool` -> CBOR bool, `str` -> CBOR text string, `null` -> CBOR null
+//   * `arr` -> CBOR array of tagged values
+//   * `obj` -> CBOR map with text keys and tagged values
+//
+// Indefinite-length items, byte strings and semantic tags are not supported. Arrays and objects
+// nested deeper than `MAX_NESTING_DEPTH` levels are rejected when reading.
+
+/// minicbor::decode::Error -> ErrorCode::SerializationFailed
+impl From<DecodeError> for ErrorCode {
+    fn from(cause: DecodeError) -> Self {
+        kvs_error!("CBOR parser error: {cause}");
+        ErrorCode::SerializationFailed
+    }
+}
+
+/// minicbor::encode::Error -> ErrorCode::SerializationFailed
+impl From<EncodeError<Infallible>> for ErrorCode {
+    fn from(cause: EncodeError<Infallible>) -> Self {
+        kvs_error!("CBOR generator error: {cause}");
+        ErrorCode::SerializationFailed
+    }
+}
+
+/// Read length of a definite-length array or map.
+fn definite_len(len: Option<u64>) -> Result<usize, ErrorCode> {
+    match len {
+        Some(len) => usize::try_from(len).map_err(|_| ErrorCode::SerializationFailed),
+        None => {
+            kvs_error!("indefinite-length CBOR items are not supported");
+            Err(ErrorCode::SerializationFailed)
+        }
+    }
+}
+
+/// Get the nesting depth of the elements of an array or object.
+fn nested(depth: usize) -> Result<usize, ErrorCode> {
+    if depth == MAX_NESTING_DEPTH {
+        kvs_error!("CBOR data nested deeper than {MAX_NESTING_DEPTH} levels");
+        return Err(ErrorCode::SerializationFailed);
+    }
+    Ok(depth + 1)
+}
+
+/// Decode t-tagged value nested in `depth` arrays and objects.
+fn decode_value(decoder: &mut Decoder, depth: usize) -> Result<KvsValue, ErrorCode> {
+    let len = definite_len(decoder.map()?)?;
+
+    // Type might follow the value, only the position of the value is recorded.
+    let mut type_name = None;
+    let mut value_pos = None;
+    for _ in 0..len {
+        match decoder.str()? {
+            "t" => type_name = Some(decoder.str()?),
+            "v" => {
+                value_pos = Some(decoder.position());
+                decoder.skip()?;
+            }
+            key => {
+                kvs_error!("unexpected CBOR key in tagged value: {key}");
+                return Err(ErrorCode::SerializationFailed);
+            }
+        }
+    }
+    let (Some(type_name), Some(value_pos)) = (type_name, value_pos) else {
+        kvs_error!("CBOR tagged value is incomplete");
+        return Err(ErrorCode::SerializationFailed);
+    };
+
+    let end_pos = decoder.position();
+    decoder.set_position(value_pos);
+    let value = match type_name {
+        "i32" => KvsValue::I32(decoder.i32()?),
+        "u32" => KvsValue::U32(decoder.u32()?),
+        "i64" => KvsValue::I64(decoder.i64()?),
+        "u64" => KvsValue::U64(decoder.u64()?),
+        "f64" => match decoder.datatype()? {
+            Type::F32 => KvsValue::F64(f64::from(decoder.f32()?)),
+            _ => KvsValue::F64(decoder.f64()?),
+        },
+        "bool" => KvsValue::Boolean(decoder.bool()?),
+        "str" => KvsValue::String(decoder.str()?.to_string()),
+        "null" => {
+            decoder.null()?;
+            KvsValue::Null
+        }
+        "arr" => {
+            let depth = nested(depth)?;
+            let len = definite_len(decoder.array()?)?;
+            // Length is not trusted for preallocation, every element takes at least one byte.
+            let mut arr = Vec::with_capacity(len.min(decoder.input().len()));
+            for _ in 0..len {
+                arr.push(decode_value(decoder, depth)?);
+            }
+            KvsValue::Array(arr)
+        }
+        "obj" => KvsValue::Object(decode_map(decoder, nested(depth)?)?),
+        type_name => {
+            kvs_error!("unsupported CBOR value type: {type_name}");
+            return Err(ErrorCode::SerializationFailed);
+        }
+    };
+    decoder.set_position(end_pos);
+    Ok(value)
+}
+
+/// Decode map with text keys and t-tagged values nested in `depth` arrays and objects.
+fn decode_map(decoder: &mut Decoder, depth: usize) -> Result<KvsMap, ErrorCode> {
+    let len = definite_len(decoder.map()?)?;
+    let mut map = KvsMap::with_capacity(len.min(decoder.input().len() / 2));
+    for _ in 0..len {
+        let key = decoder.str()?.to_string();
+        let value = decode_value(decoder, depth)?;
+        map.insert(key, value);
+    }
+    Ok(map)
+}
+
+/// Decode CBOR data containing an `obj` value into `KvsMap`.
+fn decode(buf: &[u8]) -> Result<KvsMap, ErrorCode> {
+    let mut decoder = Decoder::new(buf);
+    let kvs_map = match decode_value(&mut decoder, 0)? {
+        KvsValue::Object(kvs_map) => kvs_map,
+        _ => {
+            kvs_error!("CBOR root is not an object");
+            return Err(ErrorCode::SerializationFailed);
+        }
+    };
+    if decoder.position() != buf.len() {
+        kvs_error!("CBOR data contains trailing bytes");
+        return Err(ErrorCode::SerializationFailed);
+    }
+    Ok(kvs_map)
+}
+
+/// Encode `KvsValue` as t-tagged value.
+fn encode_value(encoder: &mut Encoder<Vec<u8>>, value: &KvsValue) -> Result<(), ErrorCode> {
+    encoder.map(2)?.str("t")?;
+    match value {
+        KvsValue::I32(n) => encoder.str("i32")?.str("v")?.i32(*n)?,
+        KvsValue::U32(n) => encoder.str("u32")?.str("v")?.u32(*n)?,
+        KvsValue::I64(n) => encoder.str("i64")?.str("v")?.i64(*n)?,
+        KvsValue::U64(n) => encoder.str("u64")?.str("v")?.u64(*n)?,
+        KvsValue::F64(n) => encoder.str("f64")?.str("v")?.f64(*n)?,
+        KvsValue::Boolean(b) => encoder.str("bool")?.str("v")?.bool(*b)?,
+        KvsValue::String(s) => encoder.str("str")?.str("v")?.str(s)?,
+        KvsValue::Null => encoder.str("null")?.str("v")?.null()?,
+        KvsValue::Array(arr) => {
+            encoder.str("arr")?.str("v")?.array(arr.len() as u64)?;
+            for element in arr {
+                encode_value(encoder, element)?;
+            }
+            encoder
+        }
+        KvsValue::Object(map) => {
+            encoder.str("obj")?.str("v")?;
+            encode_map(encoder, map)?;
+            encoder
+        }
+    };
+    Ok(())
+}
+
+/// Encode `KvsMap` into CBOR map.
+fn encode_map(encoder: &mut Encoder<Vec<u8>>, kvs_map: &KvsMap) -> Result<(), ErrorCode> {
+    encoder.map(kvs_map.len() as u64)?;
+    for (key, value) in kvs_map {
+        encoder.str(key)?;
+        encode_value(encoder, value)?;
+    }
+    Ok(())
+}
+
+/// Encode `KvsMap` into CBOR data containing an `obj` value.
+fn encode(kvs_map: &KvsMap) -> Result<Vec<u8>, ErrorCode> {
+    let mut encoder = Encoder::new(Vec::new());
+    encoder.map(2)?.str("t")?.str("obj")?.str("v")?;
+    encode_map(&mut encoder, kvs_map)?;
+    Ok(encoder.into_writer())
+}
+
+/// KVS backend implementation based on CBOR.
+pub struct CborBackend;
+
+impl KvsBackend for CborBackend {
+    fn load_kvs(kvs_path: &Path, hash_path: Option<&PathBuf>) -> Result<KvsMap, ErrorCode> {
+        if !check_extension(kvs_path, "cbor") {
+            return Err(ErrorCode::KvsFileReadError);
+        }
+        if hash_path.is_some_and(|p| !check_extension(p, "hash")) {
+            return Err(ErrorCode::KvsHashFileReadError);
+        }
+
+        // Load KVS file.
+        let bytes = fs::read(kvs_path)?;
+
+        // Perform hash check before decoding.
+        if let Some(hash_path) = hash_path {
+            validate_hash::<StdFs>(&bytes, hash_path)?;
+        }
+
+        decode(&bytes)
+    }
+
+    fn load_kvs_from_bytes(bytes: &[u8]) -> Result<KvsMap, ErrorCode> {
+        decode(bytes)
+    }
+
+    fn save_kvs(
+        kvs_map: &KvsMap,
+        kvs_path: &Path,
+        hash_path: Option<&PathBuf>,
+    ) -> Result<(), ErrorCode> {
+        // Validate extensions.
+        if !check_extension(kvs_path, "cbor") {
+            return Err(ErrorCode::KvsFileReadError);
+        }
+        if hash_path.is_some_and(|p| !check_extension(p, "hash")) {
+            return Err(ErrorCode::KvsHashFileReadError);
+        }
+
+        // Encode and save to KVS file.
+        let bytes = encode(kvs_map)?;
+        fs::write(kvs_path, &bytes)?;
+
+        // Generate hash and save to hash file.
+        if let Some(hash_path) = hash_path {
+            write_hash::<StdFs>(&bytes, hash_path)?;
+        }
+
+        Ok(())
+    }
+
+    fn serialized_size(kvs_map: &KvsMap, _float_format: &FloatFormat) -> Result<usize, ErrorCode> {
+        Ok(encode(kvs_map)?.len())
+    }
+}
+
+/// KVS backend path resolver for `CborBackend`.
+impl KvsPathResolver for CborBackend {
+    fn kvs_file_name(instance_id: InstanceId, snapshot_id: SnapshotId) -> String {
+        format!("kvs_{instance_id}_{snapshot_id}.cbor")
+    }
+
+    fn kvs_file_path(
+        working_dir: &Path,
+        instance_id: InstanceId,
+        snapshot_id: SnapshotId,
+    ) -> PathBuf {
+        working_dir.join(Self::kvs_file_name(instance_id, snapshot_id))
+    }
+
+    fn hash_file_name(instance_id: InstanceId, snapshot_id: SnapshotId) -> String {
+        format!("kvs_{instance_id}_{snapshot_id}.hash")
+    }
+
+    fn hash_file_path(
+        working_dir: &Path,
+        instance_id: InstanceId,
+        snapshot_id: SnapshotId,
+    ) -> PathBuf {
+        working_dir.join(Self::hash_file_name(instance_id, snapshot_id))
+    }
+
+    fn defaults_file_name(instance_id: InstanceId) -> String {
+        format!("kvs_{instance_id}_default.cbor")
+    }
+
+    fn defaults_file_path(working_dir: &Path, instance_id: InstanceId) -> PathBuf {
+        working_dir.join(Self::defaults_file_name(instance_id))
+    }
+
+    fn global_defaults_file_name() -> String {
+        "kvs_global_default.cbor".to_string()
+    }
+
+    fn global_defaults_file_path(working_dir: &Path) -> PathBuf {
+        working_dir.join(Self::global_defaults_file_name())
+    }
+}
+
+#[cfg(test)]
+mod cbor_conversion_tests {
+    use crate::cbor_backend::{decode, encode};
+    use crate::error_code::ErrorCode;
+    use crate::kvs_backend::MAX_NESTING_DEPTH;
+    use crate::kvs_value::{KvsMap, KvsValue};
+
+    fn roundtrip(kvs_map: &KvsMap) -> KvsMap {
+        decode(&encode(kvs_map).unwrap()).unwrap()
+    }
+
+    /// Tagged value header: map(2), "t", type name, "v".
+    fn tagged(type_name: &str) -> Vec<u8> {
+        let mut bytes = vec![0xa2, 0x61, b't', 0x60 + type_name.len() as u8];
+        bytes.extend_from_slice(type_name.as_bytes());
+        bytes.extend_from_slice(&[0x61, b'v']);
+        bytes
+    }
+
+    #[test]
+    fn test_roundtrip_all_types() {
+        let kvs_map = KvsMap::from([
+            ("i32".to_string(), KvsValue::I32(i32::MIN)),
+            ("u32".to_string(), KvsValue::U32(1)),
+            ("i64".to_string(), KvsValue::I64(i64::MIN)),
+            ("u64".to_string(), KvsValue::U64(u64::MAX)),
+            ("f64".to_string(), KvsValue::F64(-432.1)),
+            ("bool".to_string(), KvsValue::Boolean(true)),
+            ("str".to_string(), KvsValue::from("example")),
+            ("null".to_string(), KvsValue::Null),
+            (
+                "arr".to_string(),
+                KvsValue::Array(vec![KvsValue::I32(1), KvsValue::from("two")]),
+            ),
+            (
+                "obj".to_string(),
+                KvsValue::Object(KvsMap::from([("x".to_string(), KvsValue::U64(0))])),
+            ),
+        ]);
+        assert_eq!(roundtrip(&kvs_map), kvs_map);
+    }
+
+    #[test]
+    fn test_encode_compact() {
+        let bytes = encode(&KvsMap::from([("a".to_string(), KvsValue::I32(5))])).unwrap();
+        let mut expected = tagged("obj");
+        expected.extend_from_slice(&[0xa1, 0x61, b'a']);
+        expected.extend(tagged("i32"));
+        expected.push(0x05);
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn test_decode_value_before_type() {
+        // Root: map(2), "v" -> map(0), "t" -> "obj".
+        let bytes = vec![0xa2, 0x61, b'v', 0xa0, 0x61, b't', 0x63, b'o', b'b', b'j'];
+        assert_eq!(decode(&bytes).unwrap(), KvsMap::new());
+    }
+
+    #[test]
+    fn test_decode_single_precision_float() {
+        let mut bytes = tagged("obj");
+        bytes.extend_from_slice(&[0xa1, 0x61, b'f']);
+        bytes.extend(tagged("f64"));
+        bytes.extend_from_slice(&[0xfa, 0x3f, 0xc0, 0x00, 0x00]);
+        assert_eq!(decode(&bytes).unwrap()["f"], KvsValue::F64(1.5));
+    }
+
+    #[test]
+    fn test_decode_out_of_range() {
+        // "i32" with uint 64 value.
+        let mut bytes = tagged("obj");
+        bytes.extend_from_slice(&[0xa1, 0x61, b'a']);
+        bytes.extend(tagged("i32"));
+        bytes.extend_from_slice(&[0x1b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+        assert!(decode(&bytes).is_err_and(|e| e == ErrorCode::SerializationFailed));
+    }
+
+    #[test]
+    fn test_decode_root_not_object() {
+        let mut bytes = tagged("null");
+        bytes.push(0xf6);
+        assert!(decode(&bytes).is_err_and(|e| e == ErrorCode::SerializationFailed));
+    }
+
+    #[test]
+    fn test_decode_untagged() {
+        // Root: map(1), "a" -> 1.
+        let bytes = vec![0xa1, 0x61, b'a', 0x01];
+        assert!(decode(&bytes).is_err_and(|e| e == ErrorCode::SerializationFailed));
+    }
+
+    #[test]
+    fn test_decode_truncated() {
+        let bytes = encode(&KvsMap::from([("a".to_string(), KvsValue::from("text"))])).unwrap();
+        assert!(
+            decode(&bytes[..bytes.len() - 1]).is_err_and(|e| e == ErrorCode::SerializationFailed)
+        );
+    }
+
+    #[test]
+    fn test_decode_trailing_bytes() {
+        let mut bytes = encode(&KvsMap::new()).unwrap();
+        bytes.push(0xf6);
+        assert!(decode(&bytes).is_err_and(|e| e == ErrorCode::SerializationFailed));
+    }
+
+    #[test]
+    fn test_decode_nesting_depth() {
+        // Value of the root entry is nested in single element arrays.
+        let nested = |levels: usize| {
+            let mut bytes = tagged("obj");
+            bytes.extend_from_slice(&[0xa1, 0x61, b'a']);
+            for _ in 0..levels {
+                bytes.extend(tagged("arr"));
+                bytes.push(0x81);
+            }
+            bytes.extend(tagged("null"));
+            bytes.push(0xf6);
+            bytes
+        };
+        assert!(decode(&nested(MAX_NESTING_DEPTH - 1)).is_ok());
+        assert!(
+            decode(&nested(MAX_NESTING_DEPTH)).is_err_and(|e| e == ErrorCode::SerializationFailed)
+        );
+        assert!(decode(&nested(1 << 12)).is_err_and(|e| e == ErrorCode::SerializationFailed));
+    }
+
+    #[test]
+    fn test_decode_indefinite_length() {
+        let mut bytes = tagged("obj");
+        bytes.extend_from_slice(&[0xbf, 0xff]);
+        assert!(decode(&bytes).is_err_and(|e| e == ErrorCode::SerializationFailed));
+    }
+}
+
+#[cfg(test)]
+mod backend_tests {
+    use crate::cbor_backend::CborBackend;
+    use crate::error_code::ErrorCode;
+    #[cfg(feature = "json-backend")]
+    use crate::json_backend::JsonBackend;
+    use crate::kvs_backend::KvsBackend;
+    use crate::kvs_value::{KvsMap, KvsValue};
+    use std::path::{Path, PathBuf};
+    use tempfile::tempdir;
+
+    fn kvs_map() -> KvsMap {
+        KvsMap::from([
+            ("k1".to_string(), KvsValue::from("v1")),
+            ("k2".to_string(), KvsValue::from(true)),
+            ("k3".to_string(), KvsValue::from(123.4)),
+            ("k4".to_string(), KvsValue::U32(4000)),
+        ])
+    }
+
+    fn create_kvs_files(working_dir: &Path) -> (PathBuf, PathBuf) {
+        let kvs_path = working_dir.join("kvs.cbor");
+        let hash_path = working_dir.join("kvs.hash");
+        CborBackend::save_kvs(&kvs_map(), &kvs_path, Some(&hash_path)).unwrap();
+        (kvs_path, hash_path)
+    }
+
+    #[test]
+    fn test_load_kvs_hash_path_some_ok() {
+        let dir = tempdir().unwrap();
+        let (kvs_path, hash_path) = create_kvs_files(dir.path());
+
+        let kvs_map = CborBackend::load_kvs(&kvs_path, Some(&hash_path)).unwrap();
+        assert_eq!(kvs_map, self::kvs_map());
+    }
+
+    #[cfg(feature = "json-backend")]
+    #[test]
+    fn test_save_kvs_smaller_than_json() {
+        let dir = tempdir().unwrap();
+        let (kvs_path, _hash_path) = create_kvs_files(dir.path());
+        let json_path = dir.path().join("kvs.json");
+        JsonBackend::save_kvs(&kvs_map(), &json_path, None).unwrap();
+
+        let cbor_size = std::fs::metadata(kvs_path).unwrap().len();
+        let json_size = std::fs::metadata(json_path).unwrap().len();
+        assert!(cbor_size < json_size);
+    }
+
+    #[test]
+    fn test_load_kvs_invalid_extension() {
+        let dir = tempdir().unwrap();
+        let kvs_path = dir.path().join("kvs.json");
+
+        assert!(
+            CborBackend::load_kvs(&kvs_path, None).is_err_and(|e| e == ErrorCode::KvsFileReadError)
+        );
+    }
+
+    #[test]
+    fn test_load_kvs_invalid_hash_content() {
+        let dir = tempdir().unwrap();
+        let (kvs_path, hash_path) = create_kvs_files(dir.path());
+        std::fs::write(&hash_path, vec![0x12, 0x34, 0x56, 0x78]).unwrap();
+
+        assert!(CborBackend::load_kvs(&kvs_path, Some(&hash_path))
+            .is_err_and(|e| e == ErrorCode::ValidationFailed));
+    }
+
+    #[test]
+    fn test_save_kvs_invalid_extension() {
+        let dir = tempdir().unwrap();
+        let kvs_path = dir.path().join("kvs.invalid_ext");
+
+        assert!(CborBackend::save_kvs(&KvsMap::new(), &kvs_path, None)
+            .is_err_and(|e| e == ErrorCode::KvsFileReadError));
+    }
+}
+
+#[cfg(test)]
+mod path_resolver_tests {
+    use crate::cbor_backend::CborBackend;
+    use crate::kvs_api::{InstanceId, SnapshotId};
+    use crate::kvs_backend::KvsPathResolver;
+
+    #[test]
+    fn test_kvs_file_name() {
+        let act_name = CborBackend::kvs_file_name(InstanceId(123), SnapshotId(2));
+        assert_eq!(act_name, "kvs_123_2.cbor");
+    }
+
+    #[test]
+    fn test_defaults_file_name() {
+        let act_name = CborBackend::defaults_file_name(InstanceId(123));
+        assert_eq!(act_name, "kvs_123_default.cbor");
+    }
+}
@@ -0,0 +1,416 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Hot-reload of defaults files.
+//!
+//! [`GenericKvs::watch_defaults`](crate::kvs::GenericKvs::watch_defaults) starts a
+//! [`DefaultsWatcher`] polling the modification time and size of the instance, global and layered
+//! defaults files. When any changes, all files are reloaded and the defaults of the instance are
+//! replaced at once. [`KvsEvent::DefaultsReloaded`] is emitted after a successful reload, on
+//! failure the previous defaults are kept.
+//!
+//! [`GenericKvs::reload_defaults`](crate::kvs::GenericKvs::reload_defaults) performs a single
+//! reload on request, e.g. after an update installed new defaults files.
+
+use crate::error_code::ErrorCode;
+use crate::kvs::KvsParameters;
+use crate::kvs_api::{DuplicateKeyPolicy, KvsDefaults};
+use crate::kvs_backend::{KvsBackend, KvsPathResolver};
+use crate::kvs_builder::KvsData;
+use crate::kvs_event::{self, KvsEvent};
+use crate::kvs_log::kvs_error;
+use crate::kvs_resolver::KvsFile;
+use crate::kvs_value::KvsMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+/// File state used for change detection, `None` if the file doesn't exist.
+type FileState = Option<(SystemTime, u64)>;
+
+/// Get modification time and size of a file.
+fn file_state(path: &Path) -> FileState {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Defaults files of an instance.
+struct DefaultsFiles {
+    /// Instance defaults file path.
+    defaults_path: PathBuf,
+
+    /// Global defaults file path.
+    global_defaults_path: PathBuf,
+
+    /// Layered defaults file paths.
+    layer_paths: Vec<PathBuf>,
+
+    /// Defaults handling mode.
+    mode: KvsDefaults,
+
+    /// Handling of duplicate keys.
+    duplicate_keys: DuplicateKeyPolicy,
+}
+
+impl DefaultsFiles {
+    fn new<PathResolver: KvsPathResolver>(parameters: &KvsParameters) -> Self {
+        Self {
+            defaults_path: parameters.file_path::<PathResolver>(KvsFile::Defaults),
+            global_defaults_path: parameters.file_path::<PathResolver>(KvsFile::GlobalDefaults),
+            layer_paths: parameters.defaults_files.clone(),
+            mode: parameters.defaults.clone(),
+            duplicate_keys: parameters.duplicate_keys,
+        }
+    }
+
+    /// Current state of all files.
+    fn state(&self) -> Vec<FileState> {
+        [&self.defaults_path, &self.global_defaults_path]
+            .into_iter()
+            .chain(&self.layer_paths)
+            .map(|path| file_state(path))
+            .collect()
+    }
+
+    /// Load defaults as done when opening the instance.
+    fn load<Backend: KvsBackend>(&self) -> Result<KvsMap, ErrorCode> {
+        if self.mode == KvsDefaults::Ignored {
+            return Ok(KvsMap::new());
+        }
+
+        let mut defaults_map = if Backend::exists(&self.global_defaults_path) {
+            Backend::load_kvs_with_policy(&self.global_defaults_path, None, self.duplicate_keys)?
+        } else {
+            KvsMap::new()
+        };
+        if self.mode == KvsDefaults::Required || Backend::exists(&self.defaults_path) {
+            defaults_map.extend(Backend::load_kvs_with_policy(
+                &self.defaults_path,
+                None,
+                self.duplicate_keys,
+            )?);
+        }
+        for layer_path in &self.layer_paths {
+            defaults_map.extend(Backend::load_kvs_with_policy(
+                layer_path,
+                None,
+                self.duplicate_keys,
+            )?);
+        }
+        Ok(defaults_map)
+    }
+}
+
+/// Load defaults of an instance as done when opening it.
+///
+/// # Parameters
+///   * `parameters`: Instance parameters
+///
+/// # Return Values
+///   * Ok: Global defaults overridden by instance defaults and layered defaults
+///   * Err: Loading a defaults file failed
+pub(crate) fn load_defaults<Backend: KvsBackend, PathResolver: KvsPathResolver>(
+    parameters: &KvsParameters,
+) -> Result<KvsMap, ErrorCode> {
+    DefaultsFiles::new::<PathResolver>(parameters).load::<Backend>()
+}
+
+/// Running defaults watcher.
+///
+/// Watching stops when the watcher is stopped or dropped.
+pub struct DefaultsWatcher {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl DefaultsWatcher {
+    /// Start polling the defaults files of an instance.
+    pub(crate) fn start<Backend: KvsBackend + 'static, PathResolver: KvsPathResolver + 'static>(
+        data: Arc<Mutex<KvsData>>,
+        parameters: KvsParameters,
+        interval: Duration,
+    ) -> Self {
+        let files = DefaultsFiles::new::<PathResolver>(&parameters);
+        let instance_id = parameters.instance_id;
+        let (stop, stop_rx) = mpsc::channel();
+
+        // Changes made after this call are detected.
+        let mut last_state = files.state();
+        let thread = thread::spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = stop_rx.recv_timeout(interval) {
+                let state = files.state();
+                if state == last_state {
+                    continue;
+                }
+                last_state = state;
+
+                let defaults_map = match files.load::<Backend>() {
+                    Ok(defaults_map) => defaults_map,
+                    Err(e) => {
+                        kvs_error!(
+                            instance_id = instance_id,
+                            "reloading defaults of instance {instance_id} failed: {e}"
+                        );
+                        continue;
+                    }
+                };
+                match data.lock() {
+                    Ok(mut data) => data.set_defaults(defaults_map),
+                    Err(_) => {
+                        kvs_error!(
+                            instance_id = instance_id,
+                            "instance {instance_id} mutex poisoned"
+                        );
+                        continue;
+                    }
+                }
+                kvs_event::emit(KvsEvent::DefaultsReloaded { instance_id });
+            }
+        });
+
+        Self {
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+
+    /// Stop watching and wait for the polling thread to finish.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        // Dropping the sender wakes up the polling thread.
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for DefaultsWatcher {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[cfg(all(test, feature = "json-backend"))]
+mod defaults_watcher_tests {
+    use crate::error_code::ErrorCode;
+    use crate::json_backend::JsonBackend;
+    use crate::kvs::{GenericKvs, KvsParameters};
+    use crate::kvs_api::{InstanceId, KvsApi};
+    use crate::kvs_backend::{KvsBackend, KvsPathResolver};
+    use crate::kvs_builder::KvsData;
+    use crate::kvs_event::{clear_event_sink, set_event_sink, KvsEvent, KvsEventSink};
+    use crate::kvs_value::{KvsMap, KvsValue};
+    use std::fs::File;
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::{Duration, Instant, SystemTime};
+    use tempfile::tempdir;
+
+    #[derive(Default)]
+    struct RecordingSink {
+        events: Mutex<Vec<KvsEvent>>,
+    }
+
+    impl KvsEventSink for RecordingSink {
+        fn on_event(&self, event: &KvsEvent) {
+            self.events.lock().unwrap().push(event.clone());
+        }
+    }
+
+    fn get_kvs(working_dir: &Path) -> GenericKvs<JsonBackend> {
+        let data = Arc::new(Mutex::new(KvsData::new(
+            KvsMap::new(),
+            KvsMap::from([("key".to_string(), KvsValue::I32(1))]),
+        )));
+        let parameters = KvsParameters {
+            working_dir: working_dir.to_path_buf(),
+            ..KvsParameters::new(InstanceId(3))
+        };
+        GenericKvs::new(data, parameters)
+    }
+
+    /// Write file with distinct modification time to ensure change detection.
+    fn write_defaults(path: &Path, kvs_map: &KvsMap, modified: SystemTime) {
+        JsonBackend::save_kvs(kvs_map, path, None).unwrap();
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    /// Wait until `get_value` returns expected value.
+    fn wait_for(kvs: &GenericKvs<JsonBackend>, key: &str, expected: KvsValue) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline {
+            if kvs.get_value(key).ok() == Some(expected.clone()) {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        false
+    }
+
+    #[test]
+    fn test_reload_on_change() {
+        let dir = tempdir().unwrap();
+        let kvs = get_kvs(dir.path());
+        let defaults_path = JsonBackend::defaults_file_path(dir.path(), InstanceId(3));
+        let global_path = JsonBackend::global_defaults_file_path(dir.path());
+
+        let sink = Arc::new(RecordingSink::default());
+        set_event_sink(sink.clone());
+        let watcher = kvs.watch_defaults(Duration::from_millis(5));
+
+        write_defaults(
+            &defaults_path,
+            &KvsMap::from([("key".to_string(), KvsValue::I32(2))]),
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1),
+        );
+        assert!(wait_for(&kvs, "key", KvsValue::I32(2)));
+
+        // Global defaults are merged, instance defaults take precedence.
+        write_defaults(
+            &global_path,
+            &KvsMap::from([
+                ("key".to_string(), KvsValue::I32(5)),
+                ("global".to_string(), KvsValue::Boolean(true)),
+            ]),
+            SystemTime::UNIX_EPOCH + Duration::from_secs(2),
+        );
+        assert!(wait_for(&kvs, "global", KvsValue::Boolean(true)));
+        assert_eq!(kvs.get_value("key").unwrap(), KvsValue::I32(2));
+
+        watcher.stop();
+        clear_event_sink();
+
+        // Other tests might emit events concurrently.
+        let events = sink.events.lock().unwrap();
+        assert!(events.contains(&KvsEvent::DefaultsReloaded {
+            instance_id: InstanceId(3)
+        }));
+    }
+
+    #[test]
+    fn test_reload_failure_keeps_defaults() {
+        let dir = tempdir().unwrap();
+        let kvs = get_kvs(dir.path());
+        let defaults_path = JsonBackend::defaults_file_path(dir.path(), InstanceId(3));
+        let watcher = kvs.watch_defaults(Duration::from_millis(5));
+
+        std::fs::write(&defaults_path, "{ invalid").unwrap();
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(kvs.get_value("key").unwrap(), KvsValue::I32(1));
+
+        write_defaults(
+            &defaults_path,
+            &KvsMap::from([("key".to_string(), KvsValue::I32(3))]),
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1),
+        );
+        assert!(wait_for(&kvs, "key", KvsValue::I32(3)));
+        drop(watcher);
+    }
+
+    #[test]
+    fn test_reload_defaults() {
+        let dir = tempdir().unwrap();
+        let kvs = get_kvs(dir.path());
+        let defaults_path = JsonBackend::defaults_file_path(dir.path(), InstanceId(3));
+        JsonBackend::save_kvs(
+            &KvsMap::from([("other".to_string(), KvsValue::I32(2))]),
+            &defaults_path,
+            None,
+        )
+        .unwrap();
+
+        // Previous defaults are replaced, not merged.
+        kvs.reload_defaults().unwrap();
+        assert_eq!(kvs.get_value("other").unwrap(), KvsValue::I32(2));
+        assert!(!kvs.key_exists("key").unwrap());
+        assert!(kvs.get_default_value("key").is_err());
+    }
+
+    #[test]
+    fn test_reload_defaults_failure() {
+        let dir = tempdir().unwrap();
+        let kvs = get_kvs(dir.path());
+        let defaults_path = JsonBackend::defaults_file_path(dir.path(), InstanceId(3));
+        std::fs::write(&defaults_path, "{ invalid").unwrap();
+
+        assert!(kvs.reload_defaults().is_err());
+        assert_eq!(kvs.get_value("key").unwrap(), KvsValue::I32(1));
+    }
+
+    #[test]
+    fn test_runtime_defaults() {
+        let dir = tempdir().unwrap();
+        let kvs = get_kvs(dir.path());
+        let defaults_path = JsonBackend::defaults_file_path(dir.path(), InstanceId(3));
+        JsonBackend::save_kvs(
+            &KvsMap::from([
+                ("key".to_string(), KvsValue::I32(1)),
+                ("stored".to_string(), KvsValue::I32(2)),
+            ]),
+            &defaults_path,
+            None,
+        )
+        .unwrap();
+
+        kvs.set_default_value("gain", 0.5).unwrap();
+        kvs.remove_default("key").unwrap();
+        assert!(kvs
+            .remove_default("key")
+            .is_err_and(|e| e == ErrorCode::KeyDefaultNotFound));
+        assert_eq!(kvs.get_value("gain").unwrap(), KvsValue::F64(0.5));
+        assert!(!kvs.is_dirty().unwrap());
+        assert!(kvs.get_all_keys().unwrap().is_empty());
+
+        // Unsaved changes survive reloading.
+        kvs.reload_defaults().unwrap();
+        assert_eq!(kvs.get_value("stored").unwrap(), KvsValue::I32(2));
+        assert_eq!(kvs.get_value("gain").unwrap(), KvsValue::F64(0.5));
+        assert!(kvs.get_default_value("key").is_err());
+
+        kvs.save_defaults().unwrap();
+        assert_eq!(
+            JsonBackend::load_kvs(&defaults_path, None).unwrap(),
+            KvsMap::from([
+                ("stored".to_string(), KvsValue::I32(2)),
+                ("gain".to_string(), KvsValue::F64(0.5)),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_stopped_watcher_ignores_changes() {
+        let dir = tempdir().unwrap();
+        let kvs = get_kvs(dir.path());
+        let defaults_path = JsonBackend::defaults_file_path(dir.path(), InstanceId(3));
+        kvs.watch_defaults(Duration::from_millis(5)).stop();
+
+        write_defaults(
+            &defaults_path,
+            &KvsMap::from([("key".to_string(), KvsValue::I32(2))]),
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1),
+        );
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(kvs.get_value("key").unwrap(), KvsValue::I32(1));
+    }
+}
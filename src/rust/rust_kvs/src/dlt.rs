@@ -0,0 +1,267 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! DLT (Diagnostic Log and Trace) adapter for KVS events.
+//!
+//! [`DltLogger`] encodes [`KvsEvent`]s as verbose DLT log messages (AUTOSAR DLT protocol
+//! version 1) and writes them to any `Write` sink, e.g. a DLT file or a connection to a DLT
+//! collector. Each event is sent as a single UTF-8 string argument.
+//!
+//! ```
+//! use rust_kvs::dlt::DltLogger;
+//! use rust_kvs::kvs_event::set_event_sink;
+//! use std::sync::Arc;
+//!
+//! let logger = DltLogger::new(Vec::new(), "APP1")
+//!     .context_id("KVS")
+//!     .storage_header(true);
+//! set_event_sink(Arc::new(logger));
+//! ```
+
+use crate::kvs_event::{KvsEvent, KvsEventSink};
+use crate::kvs_log::kvs_error;
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Header type: use extended header.
+const HTYP_UEH: u8 = 0x01;
+
+/// Header type: with ECU ID.
+const HTYP_WEID: u8 = 0x04;
+
+/// Header type: with timestamp.
+const HTYP_WTMS: u8 = 0x10;
+
+/// Header type: protocol version 1.
+const HTYP_VERSION_1: u8 = 0x20;
+
+/// Message info: verbose mode.
+const MSIN_VERB: u8 = 0x01;
+
+/// Type info: UTF-8 encoded string.
+const TYPE_INFO_STRING_UTF8: u32 = 0x0000_8200;
+
+/// DLT log level.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DltLogLevel {
+    Fatal = 1,
+    Error = 2,
+    Warn = 3,
+    Info = 4,
+    Debug = 5,
+    Verbose = 6,
+}
+
+impl From<&KvsEvent> for DltLogLevel {
+    fn from(event: &KvsEvent) -> Self {
+        match event {
+            KvsEvent::FlushSucceeded { .. }
+            | KvsEvent::SnapshotRestored { .. }
+            | KvsEvent::DefaultsReloaded { .. } => DltLogLevel::Info,
+            KvsEvent::RecoveredFromSnapshot { .. } => DltLogLevel::Warn,
+            KvsEvent::FlushFailed { .. } | KvsEvent::IntegrityFailure { .. } => DltLogLevel::Error,
+        }
+    }
+}
+
+/// Convert ID to 4 bytes, truncated or padded with zeros.
+fn dlt_id(id: &str) -> [u8; 4] {
+    let mut out = [0u8; 4];
+    for (dst, src) in out.iter_mut().zip(id.bytes()) {
+        *dst = src;
+    }
+    out
+}
+
+/// Writer state guarded by a single lock.
+struct DltOutput<W: Write + Send> {
+    writer: W,
+    counter: u8,
+}
+
+/// KVS event sink writing DLT messages.
+pub struct DltLogger<W: Write + Send> {
+    output: Mutex<DltOutput<W>>,
+    ecu_id: [u8; 4],
+    app_id: [u8; 4],
+    context_id: [u8; 4],
+    storage_header: bool,
+    start: Instant,
+}
+
+impl<W: Write + Send> DltLogger<W> {
+    /// Create logger with application ID, ECU ID `ECU1` and context ID `KVS`.
+    ///
+    /// IDs longer than 4 bytes are truncated.
+    pub fn new(writer: W, app_id: &str) -> Self {
+        Self {
+            output: Mutex::new(DltOutput { writer, counter: 0 }),
+            ecu_id: dlt_id("ECU1"),
+            app_id: dlt_id(app_id),
+            context_id: dlt_id("KVS"),
+            storage_header: false,
+            start: Instant::now(),
+        }
+    }
+
+    /// Set ECU ID.
+    pub fn ecu_id(mut self, ecu_id: &str) -> Self {
+        self.ecu_id = dlt_id(ecu_id);
+        self
+    }
+
+    /// Set context ID.
+    pub fn context_id(mut self, context_id: &str) -> Self {
+        self.context_id = dlt_id(context_id);
+        self
+    }
+
+    /// Prefix messages with storage header, as required for DLT files.
+    pub fn storage_header(mut self, storage_header: bool) -> Self {
+        self.storage_header = storage_header;
+        self
+    }
+
+    /// Encode verbose log message with single string argument.
+    ///
+    /// # Parameters
+    ///   * `level`: Log level
+    ///   * `text`: Message text
+    ///   * `counter`: Message counter
+    ///   * `timestamp`: Time since startup in 0.1 ms
+    pub(crate) fn encode(
+        &self,
+        level: DltLogLevel,
+        text: &str,
+        counter: u8,
+        timestamp: u32,
+    ) -> Vec<u8> {
+        // Payload: type info, length including terminator, string, terminator.
+        let mut payload = Vec::with_capacity(text.len() + 7);
+        payload.extend_from_slice(&TYPE_INFO_STRING_UTF8.to_le_bytes());
+        payload.extend_from_slice(&((text.len() + 1) as u16).to_le_bytes());
+        payload.extend_from_slice(text.as_bytes());
+        payload.push(0);
+
+        // Standard header (12 bytes) and extended header (10 bytes).
+        let len = (22 + payload.len()) as u16;
+        let mut msg = Vec::with_capacity(16 + len as usize);
+        if self.storage_header {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default();
+            msg.extend_from_slice(b"DLT\x01");
+            msg.extend_from_slice(&(now.as_secs() as u32).to_le_bytes());
+            msg.extend_from_slice(&(now.subsec_micros() as i32).to_le_bytes());
+            msg.extend_from_slice(&self.ecu_id);
+        }
+        msg.push(HTYP_UEH | HTYP_WEID | HTYP_WTMS | HTYP_VERSION_1);
+        msg.push(counter);
+        msg.extend_from_slice(&len.to_be_bytes());
+        msg.extend_from_slice(&self.ecu_id);
+        msg.extend_from_slice(&timestamp.to_be_bytes());
+        msg.push(MSIN_VERB | ((level as u8) << 4));
+        msg.push(1);
+        msg.extend_from_slice(&self.app_id);
+        msg.extend_from_slice(&self.context_id);
+        msg.extend_from_slice(&payload);
+        msg
+    }
+
+    /// Write log message.
+    pub fn log(&self, level: DltLogLevel, text: &str) {
+        let timestamp = (self.start.elapsed().as_micros() / 100) as u32;
+        let Ok(mut output) = self.output.lock() else {
+            return;
+        };
+        let msg = self.encode(level, text, output.counter, timestamp);
+        output.counter = output.counter.wrapping_add(1);
+        if let Err(e) = output
+            .writer
+            .write_all(&msg)
+            .and_then(|_| output.writer.flush())
+        {
+            kvs_error!("writing DLT message failed: {e}");
+        }
+    }
+
+    /// Get writer back.
+    pub fn into_inner(self) -> Option<W> {
+        self.output.into_inner().ok().map(|output| output.writer)
+    }
+}
+
+impl<W: Write + Send> KvsEventSink for DltLogger<W> {
+    fn on_event(&self, event: &KvsEvent) {
+        self.log(DltLogLevel::from(event), &event.to_string());
+    }
+}
+
+#[cfg(test)]
+mod dlt_tests {
+    use crate::dlt::{DltLogLevel, DltLogger};
+    use crate::error_code::ErrorCode;
+    use crate::kvs_api::InstanceId;
+    use crate::kvs_event::{KvsEvent, KvsEventSink};
+
+    #[test]
+    fn test_encode() {
+        let logger = DltLogger::new(Vec::new(), "APP1").ecu_id("ECU2");
+        let msg = logger.encode(DltLogLevel::Info, "ok", 7, 0x0102_0304);
+        assert_eq!(
+            msg,
+            [
+                0x35, 7, 0, 31, // HTYP, MCNT, LEN
+                b'E', b'C', b'U', b'2', // ECU ID
+                1, 2, 3, 4, // Timestamp
+                0x41, 1, // MSIN (verbose, log info), NOAR
+                b'A', b'P', b'P', b'1', // APID
+                b'K', b'V', b'S', 0, // CTID
+                0x00, 0x82, 0x00, 0x00, // Type info (UTF-8 string)
+                3, 0, b'o', b'k', 0, // Length, string
+            ]
+        );
+    }
+
+    #[test]
+    fn test_encode_storage_header() {
+        let logger = DltLogger::new(Vec::new(), "APP1").storage_header(true);
+        let msg = logger.encode(DltLogLevel::Error, "", 0, 0);
+        assert_eq!(&msg[0..4], b"DLT\x01");
+        assert_eq!(&msg[12..16], b"ECU1");
+        assert_eq!(msg.len(), 16 + 29);
+        assert_eq!(msg[16 + 12], 0x21);
+    }
+
+    #[test]
+    fn test_on_event() {
+        let logger = DltLogger::new(Vec::new(), "APPLICATION");
+        logger.on_event(&KvsEvent::FlushSucceeded {
+            instance_id: InstanceId(1),
+        });
+        logger.on_event(&KvsEvent::FlushFailed {
+            instance_id: InstanceId(1),
+            error: ErrorCode::OutOfStorageSpace,
+        });
+
+        let out = logger.into_inner().unwrap();
+        let first_len = u16::from_be_bytes([out[2], out[3]]) as usize;
+        let (first, second) = out.split_at(first_len);
+        assert_eq!(first[1], 0);
+        assert_eq!(&first[14..18], b"APPL");
+        assert_eq!(first[12], 0x41);
+        assert!(first.ends_with(b"KVS 1: flush succeeded\0"));
+        assert_eq!(second[1], 1);
+        assert_eq!(second[12], 0x21);
+        assert!(second.ends_with(b"KVS 1: flush failed: OutOfStorageSpace\0"));
+    }
+}
@@ -0,0 +1,295 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Flat `.env`-style representation of scalar KVS entries.
+//!
+//! Only scalar values are represented, `Null`, `Array` and `Object` entries are skipped on export.
+//! Strings are always written double-quoted, numbers and booleans are written bare, which allows
+//! the importer to restore the value kind:
+//!
+//! ```text
+//! # comment
+//! my_int=42
+//! my_float=3.1415
+//! my_bool=true
+//! my_string="hello"
+//! ```
+//!
+//! Bare integers are imported as `I32` if they fit, then `I64`, then `U64`. Bare numbers with a
+//! fraction or exponent are imported as `F64`. Other bare values are imported as `String`. The
+//! integer type isn't stored, `U32` values and `I64`/`U64` values in `I32` range are imported as
+//! `I32`. With [`strict_types`](crate::kvs_builder::GenericKvsBuilder::strict_types) importing
+//! them fails with `ErrorCode::TypeMismatch` if the key already has a value of the original type.
+//!
+//! Keys are written unquoted. Keys which can't be read back, i.e. empty keys, keys containing `=`
+//! or line breaks, keys with leading or trailing whitespace and keys starting with `#` or
+//! `export `, are rejected on export.
+
+use crate::error_code::ErrorCode;
+use crate::kvs_log::kvs_error;
+use crate::kvs_value::{KvsMap, KvsValue};
+
+/// Export scalar entries of a `KvsMap` into `.env` representation.
+///
+/// Entries are sorted by key to provide a stable output.
+///
+/// # Parameters
+///   * `kvs_map`: Map to export
+///
+/// # Return Values
+///   * Ok: `.env` formatted string
+///   * `ErrorCode::InvalidKey`: Key of a scalar entry can't be represented
+pub fn to_dotenv(kvs_map: &KvsMap) -> Result<String, ErrorCode> {
+    let mut keys: Vec<&String> = kvs_map.keys().collect();
+    keys.sort();
+
+    let mut output = String::new();
+    for key in keys {
+        let value = match &kvs_map[key] {
+            KvsValue::I32(v) => v.to_string(),
+            KvsValue::U32(v) => v.to_string(),
+            KvsValue::I64(v) => v.to_string(),
+            KvsValue::U64(v) => v.to_string(),
+            // `Debug` always emits a fraction or exponent, so the value is imported as `F64`.
+            KvsValue::F64(v) => format!("{v:?}"),
+            KvsValue::Boolean(v) => v.to_string(),
+            KvsValue::String(v) => quote(v),
+            KvsValue::Null | KvsValue::Array(_) | KvsValue::Object(_) => continue,
+        };
+        if !is_valid_key(key) {
+            kvs_error!(
+                key = key,
+                "key {key:?} can't be represented in dotenv format"
+            );
+            return Err(ErrorCode::InvalidKey);
+        }
+        output.push_str(key);
+        output.push('=');
+        output.push_str(&value);
+        output.push('\n');
+    }
+
+    Ok(output)
+}
+
+/// Import `.env` representation into a `KvsMap`.
+///
+/// Empty lines and lines starting with `#` are ignored, an optional `export ` prefix is accepted.
+///
+/// # Parameters
+///   * `s`: `.env` formatted string
+///
+/// # Return Values
+///   * Ok: Parsed map
+///   * `ErrorCode::ConversionFailed`: Malformed line or quoted value
+///   * `ErrorCode::InvalidKey`: Empty key
+pub fn from_dotenv(s: &str) -> Result<KvsMap, ErrorCode> {
+    let mut kvs_map = KvsMap::new();
+
+    for (line_no, line) in s.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+
+        let (key, raw_value) = match line.split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => {
+                kvs_error!("dotenv line {} is missing '='", line_no + 1);
+                return Err(ErrorCode::ConversionFailed);
+            }
+        };
+        if key.is_empty() {
+            kvs_error!("dotenv line {} has an empty key", line_no + 1);
+            return Err(ErrorCode::InvalidKey);
+        }
+
+        let value = if raw_value.starts_with('"') || raw_value.starts_with('\'') {
+            KvsValue::String(unquote(raw_value).ok_or_else(|| {
+                kvs_error!("dotenv line {} has malformed quoting", line_no + 1);
+                ErrorCode::ConversionFailed
+            })?)
+        } else {
+            infer_bare(raw_value)
+        };
+
+        kvs_map.insert(key.to_string(), value);
+    }
+
+    Ok(kvs_map)
+}
+
+/// Whether a key is read back unchanged by [`from_dotenv`].
+fn is_valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && key.trim() == key
+        && !key.contains(['=', '\n', '\r'])
+        && !key.starts_with('#')
+        && !key.starts_with("export ")
+}
+
+/// Quote and escape string value.
+fn quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Remove quotes from value. Escape sequences are only processed in double-quoted values.
+fn unquote(raw_value: &str) -> Option<String> {
+    let quote_char = raw_value.chars().next()?;
+    let inner = raw_value.get(1..)?.strip_suffix(quote_char)?;
+
+    if quote_char == '\'' {
+        return Some(inner.to_string());
+    }
+
+    let mut value = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => value.push('\n'),
+            'r' => value.push('\r'),
+            't' => value.push('\t'),
+            other => value.push(other),
+        }
+    }
+
+    Some(value)
+}
+
+/// Infer value kind of unquoted value.
+fn infer_bare(raw_value: &str) -> KvsValue {
+    match raw_value {
+        "true" => return KvsValue::Boolean(true),
+        "false" => return KvsValue::Boolean(false),
+        _ => {}
+    }
+
+    if let Ok(v) = raw_value.parse::<i32>() {
+        KvsValue::I32(v)
+    } else if let Ok(v) = raw_value.parse::<i64>() {
+        KvsValue::I64(v)
+    } else if let Ok(v) = raw_value.parse::<u64>() {
+        KvsValue::U64(v)
+    } else if let Ok(v) = raw_value.parse::<f64>() {
+        KvsValue::F64(v)
+    } else {
+        KvsValue::String(raw_value.to_string())
+    }
+}
+
+#[cfg(test)]
+mod dotenv_tests {
+    use crate::dotenv::{from_dotenv, to_dotenv};
+    use crate::error_code::ErrorCode;
+    use crate::kvs_value::{KvsMap, KvsValue};
+
+    #[test]
+    fn test_to_dotenv_scalars() {
+        let kvs_map = KvsMap::from([
+            ("b".to_string(), KvsValue::Boolean(true)),
+            ("f".to_string(), KvsValue::F64(5.0)),
+            ("i".to_string(), KvsValue::I32(-7)),
+            ("s".to_string(), KvsValue::from("a \"quoted\"\nline")),
+        ]);
+        assert_eq!(
+            to_dotenv(&kvs_map).unwrap(),
+            "b=true\nf=5.0\ni=-7\ns=\"a \\\"quoted\\\"\\nline\"\n"
+        );
+    }
+
+    #[test]
+    fn test_to_dotenv_skips_non_scalars() {
+        let kvs_map = KvsMap::from([
+            ("null".to_string(), KvsValue::Null),
+            ("arr".to_string(), KvsValue::Array(vec![])),
+            ("obj".to_string(), KvsValue::Object(KvsMap::new())),
+        ]);
+        assert_eq!(to_dotenv(&kvs_map).unwrap(), "");
+    }
+
+    #[test]
+    fn test_to_dotenv_invalid_key() {
+        for key in ["", "a=b", "a\nb", "a\rb", " a", "a ", "#a", "export a"] {
+            let kvs_map = KvsMap::from([(key.to_string(), KvsValue::I32(1))]);
+            assert!(to_dotenv(&kvs_map).is_err_and(|e| e == ErrorCode::InvalidKey));
+        }
+
+        // Keys of skipped entries aren't checked.
+        let kvs_map = KvsMap::from([("a=b".to_string(), KvsValue::Null)]);
+        assert_eq!(to_dotenv(&kvs_map).unwrap(), "");
+    }
+
+    #[test]
+    fn test_integer_type_not_preserved() {
+        let kvs_map = KvsMap::from([
+            ("u32".to_string(), KvsValue::U32(7)),
+            ("i64".to_string(), KvsValue::I64(-7)),
+        ]);
+        let imported = from_dotenv(&to_dotenv(&kvs_map).unwrap()).unwrap();
+        assert_eq!(imported["u32"], KvsValue::I32(7));
+        assert_eq!(imported["i64"], KvsValue::I32(-7));
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let kvs_map = KvsMap::from([
+            ("bool".to_string(), KvsValue::Boolean(false)),
+            ("float".to_string(), KvsValue::F64(-1.5)),
+            ("int".to_string(), KvsValue::I32(42)),
+            ("big".to_string(), KvsValue::I64(-(1 << 40))),
+            ("huge".to_string(), KvsValue::U64(u64::MAX)),
+            ("str".to_string(), KvsValue::from("true")),
+        ]);
+        assert_eq!(from_dotenv(&to_dotenv(&kvs_map).unwrap()).unwrap(), kvs_map);
+    }
+
+    #[test]
+    fn test_from_dotenv_comments_and_export() {
+        let input = "# comment\n\nexport KEY_A = 'single \\n'\nKEY_B=plain text\n";
+        let kvs_map = from_dotenv(input).unwrap();
+        assert_eq!(kvs_map.len(), 2);
+        assert_eq!(kvs_map["KEY_A"], KvsValue::from("single \\n"));
+        assert_eq!(kvs_map["KEY_B"], KvsValue::from("plain text"));
+    }
+
+    #[test]
+    fn test_from_dotenv_missing_separator() {
+        assert!(from_dotenv("KEY").is_err_and(|e| e == ErrorCode::ConversionFailed));
+    }
+
+    #[test]
+    fn test_from_dotenv_empty_key() {
+        assert!(from_dotenv("=value").is_err_and(|e| e == ErrorCode::InvalidKey));
+    }
+
+    #[test]
+    fn test_from_dotenv_unterminated_quote() {
+        assert!(from_dotenv("KEY=\"value").is_err_and(|e| e == ErrorCode::ConversionFailed));
+    }
+}
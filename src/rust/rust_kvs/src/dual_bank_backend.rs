@@ -0,0 +1,463 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Dual-bank storage for wear leveling.
+//!
+//! [`DualBankBackend`] wraps a file-based backend and stores every file in two alternating banks,
+//! e.g. `kvs_1_0.a.json` / `kvs_1_0.a.hash` and `kvs_1_0.b.json` / `kvs_1_0.b.hash`. A pointer
+//! file, `kvs_1_0.bank`, names the valid bank. Saving writes and syncs the other bank and then
+//! switches the pointer by atomically replacing it, so:
+//!   * repeated writes of a file alternate between two paths instead of rewriting the same blocks,
+//!   * a torn write never touches the valid bank, the pointer still names it.
+//!
+//! The strategy is selected per instance by its backend type:
+//!
+//! ```
+//! use rust_kvs::dual_bank_backend::DualBankBackend;
+//! use rust_kvs::kvs_fs::MemoryFs;
+//! use rust_kvs::prelude::*;
+//! use rust_kvs::GenericJsonBackend;
+//!
+//! type Backend = DualBankBackend<GenericJsonBackend<MemoryFs>, MemoryFs>;
+//! let kvs = GenericKvsBuilder::<Backend>::new(InstanceId(0))
+//!     .dir("banked")
+//!     .build()?;
+//! kvs.set_value("counter", 1)?;
+//! kvs.flush()?;
+//! # Ok::<(), ErrorCode>(())
+//! ```
+//!
+//! Snapshot rotation moves the valid bank with its pointer. Files without a pointer, e.g. written
+//! before switching to this backend or defaults written by hand, are read from their plain path
+//! and replaced by banks on the next save. Banked files aren't found by
+//! [`GenericKvs::discover`](crate::kvs::GenericKvs::discover).
+
+use crate::error_code::ErrorCode;
+use crate::kvs_api::{
+    DuplicateKeyPolicy, FloatFormat, InstanceId, KvsCompression, SnapshotId, SyncPolicy,
+};
+use crate::kvs_backend::{sync_files, KvsBackend, KvsPathResolver};
+use crate::kvs_fs::{KvsFs, StdFs};
+use crate::kvs_lazy::LazyKvsMap;
+use crate::kvs_log::{kvs_error, kvs_warn};
+use crate::kvs_value::KvsMap;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Bank of a stored file.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Bank {
+    A,
+    B,
+}
+
+impl Bank {
+    /// Name used in file names and the pointer file.
+    fn name(self) -> &'static str {
+        match self {
+            Bank::A => "a",
+            Bank::B => "b",
+        }
+    }
+
+    /// Bank written next.
+    fn other(self) -> Self {
+        match self {
+            Bank::A => Bank::B,
+            Bank::B => Bank::A,
+        }
+    }
+}
+
+/// Path of a file in a bank, the bank name is inserted before the extension.
+fn bank_path(path: &Path, bank: Bank) -> PathBuf {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) => path.with_extension(format!("{}.{ext}", bank.name())),
+        None => path.with_extension(bank.name()),
+    }
+}
+
+/// Path of the pointer file naming the valid bank.
+fn pointer_path(path: &Path) -> PathBuf {
+    path.with_extension("bank")
+}
+
+/// KVS backend storing files of `Inner` in two alternating banks, see
+/// [`dual_bank_backend`](crate::dual_bank_backend). Pointer files are accessed through `Fs`.
+pub struct DualBankBackend<Inner: KvsBackend, Fs: KvsFs = StdFs> {
+    _inner_marker: PhantomData<Inner>,
+    _fs_marker: PhantomData<Fs>,
+}
+
+impl<Inner: KvsBackend, Fs: KvsFs> DualBankBackend<Inner, Fs> {
+    /// Read the valid bank of a file, `None` if the file isn't banked.
+    fn current_bank(path: &Path) -> Option<Bank> {
+        let pointer = pointer_path(path);
+        let content = Fs::read(&pointer).ok()?;
+        match content.as_slice() {
+            b"a" => Some(Bank::A),
+            b"b" => Some(Bank::B),
+            _ => {
+                kvs_warn!("invalid bank pointer {}", pointer.display());
+                None
+            }
+        }
+    }
+
+    /// Resolve the stored paths of a file and its hash.
+    fn resolve(kvs_path: &Path, hash_path: &Path) -> (PathBuf, PathBuf, Option<Bank>) {
+        match Self::current_bank(kvs_path) {
+            Some(bank) => (
+                bank_path(kvs_path, bank),
+                bank_path(hash_path, bank),
+                Some(bank),
+            ),
+            None => (kvs_path.to_path_buf(), hash_path.to_path_buf(), None),
+        }
+    }
+
+    /// Make a bank valid by atomically replacing the pointer file.
+    fn write_pointer(path: &Path, bank: Bank) -> Result<(), ErrorCode> {
+        let pointer = pointer_path(path);
+        let tmp = pointer.with_extension("bank.tmp");
+        Fs::write(&tmp, bank.name().as_bytes())
+            .and_then(|()| Fs::rename(&tmp, &pointer))
+            .map_err(|e| {
+                kvs_error!("switching bank pointer {} failed: {e}", pointer.display());
+                ErrorCode::from(e).with_path(&pointer)
+            })
+    }
+
+    /// Save a file to the bank which isn't valid and switch to it.
+    ///
+    /// # Parameters
+    ///   * `kvs_path`: Plain path of the file
+    ///   * `hash_path`: Plain path of the hash file
+    ///   * `save`: Save function of `Inner`, called with the bank paths
+    fn save_bank<F>(kvs_path: &Path, hash_path: Option<&PathBuf>, save: F) -> Result<(), ErrorCode>
+    where
+        F: FnOnce(&Path, Option<&PathBuf>) -> Result<(), ErrorCode>,
+    {
+        let bank = Self::current_bank(kvs_path).map_or(Bank::A, Bank::other);
+        let bank_kvs_path = bank_path(kvs_path, bank);
+        let bank_hash_path = hash_path.map(|path| bank_path(path, bank));
+        save(&bank_kvs_path, bank_hash_path.as_ref())?;
+        // The bank must be stored before it becomes valid.
+        Inner::sync_kvs(
+            &bank_kvs_path,
+            bank_hash_path.as_deref().unwrap_or(&bank_kvs_path),
+            SyncPolicy::DataOnly,
+        )?;
+        Self::write_pointer(kvs_path, bank)?;
+
+        // Plain files are replaced by the banks.
+        let plain_hash_path =
+            hash_path.map_or_else(|| kvs_path.with_extension("hash"), Clone::clone);
+        if Inner::exists(kvs_path) {
+            Inner::remove_kvs(kvs_path, &plain_hash_path)?;
+        }
+        Ok(())
+    }
+}
+
+impl<Inner: KvsBackend, Fs: KvsFs> KvsBackend for DualBankBackend<Inner, Fs> {
+    fn load_kvs(kvs_path: &Path, hash_path: Option<&PathBuf>) -> Result<KvsMap, ErrorCode> {
+        Self::load_kvs_with_policy(kvs_path, hash_path, DuplicateKeyPolicy::default())
+    }
+
+    fn load_kvs_with_policy(
+        kvs_path: &Path,
+        hash_path: Option<&PathBuf>,
+        duplicate_keys: DuplicateKeyPolicy,
+    ) -> Result<KvsMap, ErrorCode> {
+        match Self::current_bank(kvs_path) {
+            Some(bank) => Inner::load_kvs_with_policy(
+                &bank_path(kvs_path, bank),
+                hash_path.map(|path| bank_path(path, bank)).as_ref(),
+                duplicate_keys,
+            ),
+            None => Inner::load_kvs_with_policy(kvs_path, hash_path, duplicate_keys),
+        }
+    }
+
+    fn load_kvs_lazy(
+        kvs_path: &Path,
+        hash_path: Option<&PathBuf>,
+        duplicate_keys: DuplicateKeyPolicy,
+    ) -> Result<Option<LazyKvsMap>, ErrorCode> {
+        match Self::current_bank(kvs_path) {
+            Some(bank) => Inner::load_kvs_lazy(
+                &bank_path(kvs_path, bank),
+                hash_path.map(|path| bank_path(path, bank)).as_ref(),
+                duplicate_keys,
+            ),
+            None => Inner::load_kvs_lazy(kvs_path, hash_path, duplicate_keys),
+        }
+    }
+
+    fn load_kvs_from_bytes(bytes: &[u8]) -> Result<KvsMap, ErrorCode> {
+        Inner::load_kvs_from_bytes(bytes)
+    }
+
+    fn save_kvs(
+        kvs_map: &KvsMap,
+        kvs_path: &Path,
+        hash_path: Option<&PathBuf>,
+    ) -> Result<(), ErrorCode> {
+        Self::save_bank(kvs_path, hash_path, |kvs_path, hash_path| {
+            Inner::save_kvs(kvs_map, kvs_path, hash_path)
+        })
+    }
+
+    fn save_kvs_formatted(
+        kvs_map: &KvsMap,
+        kvs_path: &Path,
+        hash_path: Option<&PathBuf>,
+        float_format: &FloatFormat,
+    ) -> Result<(), ErrorCode> {
+        Self::save_bank(kvs_path, hash_path, |kvs_path, hash_path| {
+            Inner::save_kvs_formatted(kvs_map, kvs_path, hash_path, float_format)
+        })
+    }
+
+    fn save_kvs_compressed(
+        kvs_map: &KvsMap,
+        kvs_path: &Path,
+        hash_path: Option<&PathBuf>,
+        float_format: &FloatFormat,
+        compression: KvsCompression,
+    ) -> Result<(), ErrorCode> {
+        Self::save_bank(kvs_path, hash_path, |kvs_path, hash_path| {
+            Inner::save_kvs_compressed(kvs_map, kvs_path, hash_path, float_format, compression)
+        })
+    }
+
+    fn serialized_size(kvs_map: &KvsMap, float_format: &FloatFormat) -> Result<usize, ErrorCode> {
+        Inner::serialized_size(kvs_map, float_format)
+    }
+
+    fn exists(path: &Path) -> bool {
+        match Self::current_bank(path) {
+            Some(bank) => Inner::exists(&bank_path(path, bank)),
+            None => Inner::exists(path),
+        }
+    }
+
+    fn modified(path: &Path) -> Option<SystemTime> {
+        match Self::current_bank(path) {
+            Some(bank) => Inner::modified(&bank_path(path, bank)),
+            None => Inner::modified(path),
+        }
+    }
+
+    fn locks_working_dir() -> bool {
+        Inner::locks_working_dir()
+    }
+
+    fn sync_kvs(
+        kvs_path: &Path,
+        hash_path: &Path,
+        sync_policy: SyncPolicy,
+    ) -> Result<(), ErrorCode> {
+        let (stored_kvs_path, stored_hash_path, bank) = Self::resolve(kvs_path, hash_path);
+        Inner::sync_kvs(&stored_kvs_path, &stored_hash_path, sync_policy)?;
+        if bank.is_some() {
+            sync_files::<Fs>(&[&pointer_path(kvs_path)], sync_policy)?;
+        }
+        Ok(())
+    }
+
+    fn move_kvs(
+        old_kvs_path: &Path,
+        old_hash_path: &Path,
+        new_kvs_path: &Path,
+        new_hash_path: &Path,
+    ) -> Result<(), ErrorCode> {
+        let (stored_kvs_path, stored_hash_path, bank) = Self::resolve(old_kvs_path, old_hash_path);
+        let Some(bank) = bank else {
+            return Inner::move_kvs(old_kvs_path, old_hash_path, new_kvs_path, new_hash_path);
+        };
+        // Pointer of a moved bank is left behind, it names a bank which no longer exists.
+        let moved = Inner::exists(&stored_kvs_path);
+        Inner::move_kvs(
+            &stored_kvs_path,
+            &stored_hash_path,
+            &bank_path(new_kvs_path, bank),
+            &bank_path(new_hash_path, bank),
+        )?;
+        if moved {
+            Self::write_pointer(new_kvs_path, bank)?;
+        }
+        Ok(())
+    }
+
+    fn remove_kvs(kvs_path: &Path, hash_path: &Path) -> Result<(), ErrorCode> {
+        for bank in [Bank::A, Bank::B] {
+            Inner::remove_kvs(&bank_path(kvs_path, bank), &bank_path(hash_path, bank))?;
+        }
+        Inner::remove_kvs(kvs_path, hash_path)?;
+        match Fs::remove_file(&pointer_path(kvs_path)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<Inner: KvsBackend + KvsPathResolver, Fs: KvsFs> KvsPathResolver
+    for DualBankBackend<Inner, Fs>
+{
+    fn kvs_file_name(instance_id: InstanceId, snapshot_id: SnapshotId) -> String {
+        Inner::kvs_file_name(instance_id, snapshot_id)
+    }
+
+    fn kvs_file_path(
+        working_dir: &Path,
+        instance_id: InstanceId,
+        snapshot_id: SnapshotId,
+    ) -> PathBuf {
+        Inner::kvs_file_path(working_dir, instance_id, snapshot_id)
+    }
+
+    fn hash_file_name(instance_id: InstanceId, snapshot_id: SnapshotId) -> String {
+        Inner::hash_file_name(instance_id, snapshot_id)
+    }
+
+    fn hash_file_path(
+        working_dir: &Path,
+        instance_id: InstanceId,
+        snapshot_id: SnapshotId,
+    ) -> PathBuf {
+        Inner::hash_file_path(working_dir, instance_id, snapshot_id)
+    }
+
+    fn defaults_file_name(instance_id: InstanceId) -> String {
+        Inner::defaults_file_name(instance_id)
+    }
+
+    fn defaults_file_path(working_dir: &Path, instance_id: InstanceId) -> PathBuf {
+        Inner::defaults_file_path(working_dir, instance_id)
+    }
+
+    fn global_defaults_file_name() -> String {
+        Inner::global_defaults_file_name()
+    }
+
+    fn global_defaults_file_path(working_dir: &Path) -> PathBuf {
+        Inner::global_defaults_file_path(working_dir)
+    }
+}
+
+#[cfg(all(test, feature = "json-backend"))]
+mod dual_bank_backend_tests {
+    use crate::dual_bank_backend::DualBankBackend;
+    use crate::json_backend::{GenericJsonBackend, JsonBackend};
+    #[cfg(feature = "snapshots")]
+    use crate::kvs_api::{InstanceId, KvsApi, KvsLoad, SnapshotId};
+    use crate::kvs_backend::KvsBackend;
+    #[cfg(feature = "snapshots")]
+    use crate::kvs_builder::kvs_builder_tests::lock_and_reset;
+    #[cfg(feature = "snapshots")]
+    use crate::kvs_builder::GenericKvsBuilder;
+    use crate::kvs_fs::{KvsFs, MemoryFs};
+    use crate::kvs_value::{KvsMap, KvsValue};
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    type MemoryBackend = DualBankBackend<GenericJsonBackend<MemoryFs>, MemoryFs>;
+
+    fn kvs_map(value: i32) -> KvsMap {
+        KvsMap::from([("key".to_string(), KvsValue::I32(value))])
+    }
+
+    #[test]
+    fn test_save_alternates_banks() {
+        let dir = Path::new("dual_bank_backend_tests/test_save_alternates_banks");
+        let kvs_path = dir.join("kvs_1_0.json");
+        let hash_path = dir.join("kvs_1_0.hash");
+
+        MemoryBackend::save_kvs(&kvs_map(1), &kvs_path, Some(&hash_path)).unwrap();
+        assert_eq!(MemoryFs::read(&dir.join("kvs_1_0.bank")).unwrap(), b"a");
+        assert!(MemoryFs::exists(&dir.join("kvs_1_0.a.json")));
+        assert!(MemoryFs::exists(&dir.join("kvs_1_0.a.hash")));
+
+        MemoryBackend::save_kvs(&kvs_map(2), &kvs_path, Some(&hash_path)).unwrap();
+        assert_eq!(MemoryFs::read(&dir.join("kvs_1_0.bank")).unwrap(), b"b");
+        assert_eq!(
+            MemoryBackend::load_kvs(&kvs_path, Some(&hash_path)).unwrap(),
+            kvs_map(2)
+        );
+
+        // A torn write of the next bank leaves the valid bank untouched.
+        MemoryFs::write(&dir.join("kvs_1_0.a.json"), b"{\"tor").unwrap();
+        assert_eq!(
+            MemoryBackend::load_kvs(&kvs_path, Some(&hash_path)).unwrap(),
+            kvs_map(2)
+        );
+
+        MemoryBackend::remove_kvs(&kvs_path, &hash_path).unwrap();
+        assert!(!MemoryBackend::exists(&kvs_path));
+        MemoryFs::clear_dir(dir).unwrap();
+    }
+
+    #[cfg(feature = "snapshots")]
+    #[test]
+    fn test_flush_and_reopen() {
+        let _lock = lock_and_reset();
+        let dir = "dual_bank_backend_tests/test_flush_and_reopen";
+        let kvs = GenericKvsBuilder::<MemoryBackend>::new(InstanceId(1))
+            .dir(dir)
+            .build()
+            .unwrap();
+        for value in 0..3 {
+            kvs.set_value("key", value).unwrap();
+            kvs.flush().unwrap();
+        }
+        assert_eq!(kvs.snapshot_count(), 3);
+        kvs.snapshot_restore(SnapshotId(2)).unwrap();
+        assert_eq!(kvs.get_value("key").unwrap(), KvsValue::I32(0));
+        kvs.set_value("key", 5).unwrap();
+        kvs.flush().unwrap();
+        drop(kvs);
+
+        let kvs = GenericKvsBuilder::<MemoryBackend>::new(InstanceId(1))
+            .dir(dir)
+            .kvs_load(KvsLoad::Required)
+            .force_reopen()
+            .build()
+            .unwrap();
+        assert_eq!(kvs.get_value("key").unwrap(), KvsValue::I32(5));
+        MemoryFs::clear_dir(Path::new(dir)).unwrap();
+    }
+
+    #[test]
+    fn test_plain_file_migrated() {
+        let dir = tempdir().unwrap();
+        let kvs_path = dir.path().join("kvs_1_0.json");
+        let hash_path = dir.path().join("kvs_1_0.hash");
+        JsonBackend::save_kvs(&kvs_map(1), &kvs_path, Some(&hash_path)).unwrap();
+
+        type Backend = DualBankBackend<JsonBackend>;
+        assert!(Backend::exists(&kvs_path));
+        assert_eq!(
+            Backend::load_kvs(&kvs_path, Some(&hash_path)).unwrap(),
+            kvs_map(1)
+        );
+        Backend::save_kvs(&kvs_map(2), &kvs_path, Some(&hash_path)).unwrap();
+        assert!(!kvs_path.exists());
+        assert!(!hash_path.exists());
+        assert!(dir.path().join("kvs_1_0.a.json").exists());
+        assert_eq!(
+            Backend::load_kvs(&kvs_path, Some(&hash_path)).unwrap(),
+            kvs_map(2)
+        );
+    }
+}
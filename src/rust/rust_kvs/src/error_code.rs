@@ -9,13 +9,25 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
-extern crate alloc;
-
-use alloc::string::FromUtf8Error;
+use crate::kvs_log::kvs_error;
+use alloc::boxed::Box;
+use alloc::string::{FromUtf8Error, String};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
 use core::array::TryFromSliceError;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::path::PathBuf;
 
 /// Runtime Error Codes
-#[derive(Debug, PartialEq)]
+///
+/// Each variant has a stable numeric code, see [`ErrorCode::code`]. New variants are only
+/// appended, existing codes are never reused.
+///
+/// Errors may carry context like the accessed key or file, see [`ErrorCode::Context`]. Errors
+/// compare equal if their codes are equal, context is ignored.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
 pub enum ErrorCode {
     /// Error that was not yet mapped
     UnmappedError,
@@ -82,59 +94,396 @@ pub enum ErrorCode {
 
     /// Instance parameters mismatch
     InstanceParametersMismatch,
+
+    /// Stored data was modified concurrently
+    ConcurrentModification,
+
+    /// Permission denied
+    PermissionDenied,
+
+    /// Storage is read-only
+    ReadOnly,
+
+    /// Invalid key
+    InvalidKey,
+
+    /// Operation timed out
+    Timeout,
+
+    /// Stored data format version not supported
+    UnsupportedVersion,
+
+    /// Value type differs from the type of the existing value
+    TypeMismatch,
+
+    /// Error with context, has the code of the contained error
+    Context(Box<KvsError>),
+}
+
+/// Error with context of the failed operation, see [`ErrorCode::context`].
+#[derive(Clone, Debug)]
+pub struct KvsError {
+    /// Error code, never [`ErrorCode::Context`].
+    pub code: ErrorCode,
+
+    /// Key accessed by the operation.
+    pub key: Option<String>,
+
+    /// File accessed by the operation.
+    #[cfg(feature = "std")]
+    pub path: Option<PathBuf>,
+
+    /// Underlying error.
+    pub source: Option<Arc<dyn core::error::Error + Send + Sync>>,
+}
+
+impl ErrorCode {
+    /// All variants, ordered by numeric code.
+    const ALL: [ErrorCode; 29] = [
+        ErrorCode::UnmappedError,
+        ErrorCode::FileNotFound,
+        ErrorCode::KvsFileReadError,
+        ErrorCode::KvsHashFileReadError,
+        ErrorCode::JsonParserError,
+        ErrorCode::JsonGeneratorError,
+        ErrorCode::PhysicalStorageFailure,
+        ErrorCode::IntegrityCorrupted,
+        ErrorCode::ValidationFailed,
+        ErrorCode::EncryptionFailed,
+        ErrorCode::ResourceBusy,
+        ErrorCode::OutOfStorageSpace,
+        ErrorCode::QuotaExceeded,
+        ErrorCode::AuthenticationFailed,
+        ErrorCode::KeyNotFound,
+        ErrorCode::KeyDefaultNotFound,
+        ErrorCode::SerializationFailed,
+        ErrorCode::InvalidSnapshotId,
+        ErrorCode::InvalidInstanceId,
+        ErrorCode::ConversionFailed,
+        ErrorCode::MutexLockFailed,
+        ErrorCode::InstanceParametersMismatch,
+        ErrorCode::ConcurrentModification,
+        ErrorCode::PermissionDenied,
+        ErrorCode::ReadOnly,
+        ErrorCode::InvalidKey,
+        ErrorCode::Timeout,
+        ErrorCode::UnsupportedVersion,
+        ErrorCode::TypeMismatch,
+    ];
+
+    /// Stable numeric code, used for FFI and as process exit code.
+    ///
+    /// Codes start at 1, 0 is reserved for success.
+    pub fn code(&self) -> u32 {
+        match self {
+            ErrorCode::UnmappedError => 1,
+            ErrorCode::FileNotFound => 2,
+            ErrorCode::KvsFileReadError => 3,
+            ErrorCode::KvsHashFileReadError => 4,
+            ErrorCode::JsonParserError => 5,
+            ErrorCode::JsonGeneratorError => 6,
+            ErrorCode::PhysicalStorageFailure => 7,
+            ErrorCode::IntegrityCorrupted => 8,
+            ErrorCode::ValidationFailed => 9,
+            ErrorCode::EncryptionFailed => 10,
+            ErrorCode::ResourceBusy => 11,
+            ErrorCode::OutOfStorageSpace => 12,
+            ErrorCode::QuotaExceeded => 13,
+            ErrorCode::AuthenticationFailed => 14,
+            ErrorCode::KeyNotFound => 15,
+            ErrorCode::KeyDefaultNotFound => 16,
+            ErrorCode::SerializationFailed => 17,
+            ErrorCode::InvalidSnapshotId => 18,
+            ErrorCode::InvalidInstanceId => 19,
+            ErrorCode::ConversionFailed => 20,
+            ErrorCode::MutexLockFailed => 21,
+            ErrorCode::InstanceParametersMismatch => 22,
+            ErrorCode::ConcurrentModification => 23,
+            ErrorCode::PermissionDenied => 24,
+            ErrorCode::ReadOnly => 25,
+            ErrorCode::InvalidKey => 26,
+            ErrorCode::Timeout => 27,
+            ErrorCode::UnsupportedVersion => 28,
+            ErrorCode::TypeMismatch => 29,
+            ErrorCode::Context(error) => error.code.code(),
+        }
+    }
+
+    /// Get the error without context.
+    pub fn kind(&self) -> &ErrorCode {
+        match self {
+            ErrorCode::Context(error) => &error.code,
+            code => code,
+        }
+    }
+
+    /// Get the context of the error, `None` if the error has no context.
+    pub fn context(&self) -> Option<&KvsError> {
+        match self {
+            ErrorCode::Context(error) => Some(error),
+            _ => None,
+        }
+    }
+
+    /// Add the key accessed by the failed operation to the context.
+    pub fn with_key(self, key: impl Into<String>) -> ErrorCode {
+        let mut error = self.into_context();
+        error.key = Some(key.into());
+        ErrorCode::Context(Box::new(error))
+    }
+
+    /// Add the file accessed by the failed operation to the context.
+    #[cfg(feature = "std")]
+    pub fn with_path(self, path: impl Into<PathBuf>) -> ErrorCode {
+        let mut error = self.into_context();
+        error.path = Some(path.into());
+        ErrorCode::Context(Box::new(error))
+    }
+
+    /// Add the underlying error to the context.
+    pub fn with_source(self, source: impl core::error::Error + Send + Sync + 'static) -> ErrorCode {
+        let mut error = self.into_context();
+        error.source = Some(Arc::new(source));
+        ErrorCode::Context(Box::new(error))
+    }
+
+    /// Get the context, empty if the error has no context.
+    fn into_context(self) -> KvsError {
+        match self {
+            ErrorCode::Context(error) => *error,
+            code => KvsError {
+                code,
+                key: None,
+                #[cfg(feature = "std")]
+                path: None,
+                source: None,
+            },
+        }
+    }
+
+    /// Get variant from numeric code.
+    ///
+    /// # Return Values
+    ///   * Some: Variant with given code
+    ///   * None: Unknown code
+    pub fn from_code(code: u32) -> Option<ErrorCode> {
+        ErrorCode::ALL.into_iter().find(|e| e.code() == code)
+    }
 }
 
+impl PartialEq for ErrorCode {
+    fn eq(&self, other: &Self) -> bool {
+        self.code() == other.code()
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorCode::Context(error) => error.fmt(f),
+            code => write!(f, "{code:?} ({})", code.code()),
+        }
+    }
+}
+
+impl core::error::Error for ErrorCode {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        self.context().and_then(|error| error.source())
+    }
+}
+
+impl fmt::Display for KvsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.code.fmt(f)?;
+        if let Some(key) = &self.key {
+            write!(f, ", key {key:?}")?;
+        }
+        #[cfg(feature = "std")]
+        if let Some(path) = &self.path {
+            write!(f, ", file {}", path.display())?;
+        }
+        if let Some(source) = &self.source {
+            write!(f, ": {source}")?;
+        }
+        Ok(())
+    }
+}
+
+impl core::error::Error for KvsError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        self.source
+            .as_deref()
+            .map(|source| source as &(dyn core::error::Error + 'static))
+    }
+}
+
+impl From<KvsError> for ErrorCode {
+    fn from(error: KvsError) -> Self {
+        ErrorCode::Context(Box::new(error))
+    }
+}
+
+/// The underlying `io::Error` is kept as source, see [`ErrorCode::context`].
+#[cfg(feature = "std")]
 impl From<std::io::Error> for ErrorCode {
     fn from(cause: std::io::Error) -> Self {
         let kind = cause.kind();
-        match kind {
+        let code = match kind {
             std::io::ErrorKind::NotFound => ErrorCode::FileNotFound,
+            std::io::ErrorKind::PermissionDenied => ErrorCode::PermissionDenied,
+            std::io::ErrorKind::ReadOnlyFilesystem => ErrorCode::ReadOnly,
+            std::io::ErrorKind::StorageFull => ErrorCode::OutOfStorageSpace,
+            std::io::ErrorKind::QuotaExceeded => ErrorCode::QuotaExceeded,
+            std::io::ErrorKind::ResourceBusy | std::io::ErrorKind::WouldBlock => {
+                ErrorCode::ResourceBusy
+            }
+            std::io::ErrorKind::TimedOut => ErrorCode::Timeout,
+            std::io::ErrorKind::InvalidData | std::io::ErrorKind::UnexpectedEof => {
+                ErrorCode::KvsFileReadError
+            }
             _ => {
-                eprintln!("error: unmapped error: {kind}");
+                kvs_error!("unmapped error: {kind}");
                 ErrorCode::UnmappedError
             }
-        }
+        };
+        code.with_source(cause)
     }
 }
 
 impl From<FromUtf8Error> for ErrorCode {
     fn from(cause: FromUtf8Error) -> Self {
-        eprintln!("error: UTF-8 conversion failed: {cause:#?}");
+        kvs_error!("UTF-8 conversion failed: {cause:#?}");
         ErrorCode::ConversionFailed
     }
 }
 
 impl From<TryFromSliceError> for ErrorCode {
     fn from(cause: TryFromSliceError) -> Self {
-        eprintln!("error: try_into from slice failed: {cause:#?}");
+        kvs_error!("try_into from slice failed: {cause:#?}");
         ErrorCode::ConversionFailed
     }
 }
 
 impl From<Vec<u8>> for ErrorCode {
     fn from(cause: Vec<u8>) -> Self {
-        eprintln!("error: try_into from u8 vector failed: {cause:#?}");
+        kvs_error!("try_into from u8 vector failed: {cause:#?}");
         ErrorCode::ConversionFailed
     }
 }
 
+/// ureq::Error -> ErrorCode
+#[cfg(any(feature = "http-backend", feature = "s3-backend"))]
+impl From<ureq::Error> for ErrorCode {
+    fn from(cause: ureq::Error) -> Self {
+        match cause {
+            ureq::Error::Status(status, _) => match status {
+                401 | 403 => ErrorCode::AuthenticationFailed,
+                404 => ErrorCode::FileNotFound,
+                409 | 412 => ErrorCode::ConcurrentModification,
+                413 => ErrorCode::QuotaExceeded,
+                423 | 429 | 503 => ErrorCode::ResourceBusy,
+                507 => ErrorCode::OutOfStorageSpace,
+                _ => {
+                    kvs_error!("unexpected HTTP status: {status}");
+                    ErrorCode::UnmappedError
+                }
+            },
+            ureq::Error::Transport(transport) => {
+                kvs_error!("HTTP transport error: {transport}");
+                ErrorCode::PhysicalStorageFailure
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod error_code_tests {
     use crate::error_code::ErrorCode;
+    #[cfg(feature = "std")]
     use std::io::{Error, ErrorKind};
+    #[cfg(feature = "std")]
+    use std::path::PathBuf;
 
+    #[cfg(feature = "std")]
     #[test]
     fn test_from_io_error_to_file_not_found() {
         let error = Error::new(ErrorKind::NotFound, "File not found");
         assert_eq!(ErrorCode::from(error), ErrorCode::FileNotFound);
     }
 
+    #[cfg(feature = "std")]
     #[test]
     fn test_from_io_error_to_unmapped_error() {
         let error = std::io::Error::new(std::io::ErrorKind::InvalidInput, "Invalid input provided");
         assert_eq!(ErrorCode::from(error), ErrorCode::UnmappedError);
     }
 
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_from_io_error_mapped_kinds() {
+        for (kind, expected) in [
+            (ErrorKind::PermissionDenied, ErrorCode::PermissionDenied),
+            (ErrorKind::ReadOnlyFilesystem, ErrorCode::ReadOnly),
+            (ErrorKind::StorageFull, ErrorCode::OutOfStorageSpace),
+            (ErrorKind::QuotaExceeded, ErrorCode::QuotaExceeded),
+            (ErrorKind::ResourceBusy, ErrorCode::ResourceBusy),
+            (ErrorKind::WouldBlock, ErrorCode::ResourceBusy),
+            (ErrorKind::TimedOut, ErrorCode::Timeout),
+            (ErrorKind::UnexpectedEof, ErrorCode::KvsFileReadError),
+        ] {
+            assert_eq!(ErrorCode::from(Error::from(kind)), expected);
+        }
+    }
+
+    #[test]
+    fn test_code_stable() {
+        assert_eq!(ErrorCode::UnmappedError.code(), 1);
+        assert_eq!(ErrorCode::KeyNotFound.code(), 15);
+        assert_eq!(ErrorCode::ConcurrentModification.code(), 23);
+        assert_eq!(ErrorCode::Timeout.code(), 27);
+        assert_eq!(ErrorCode::UnsupportedVersion.code(), 28);
+        assert_eq!(ErrorCode::TypeMismatch.code(), 29);
+    }
+
+    #[test]
+    fn test_code_roundtrip() {
+        for (i, error) in ErrorCode::ALL.iter().enumerate() {
+            assert_eq!(error.code(), i as u32 + 1);
+            assert_eq!(ErrorCode::from_code(error.code()).as_ref(), Some(error));
+        }
+        assert_eq!(ErrorCode::from_code(0), None);
+        assert_eq!(ErrorCode::from_code(ErrorCode::ALL.len() as u32 + 1), None);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_context() {
+        let error = ErrorCode::from(Error::new(ErrorKind::NotFound, "missing"))
+            .with_key("key")
+            .with_path("kvs_0_0.json");
+        assert_eq!(error, ErrorCode::FileNotFound);
+        assert_eq!(error.kind(), &ErrorCode::FileNotFound);
+        assert_eq!(error.code(), ErrorCode::FileNotFound.code());
+        let context = error.context().unwrap();
+        assert_eq!(context.key.as_deref(), Some("key"));
+        assert_eq!(context.path, Some(PathBuf::from("kvs_0_0.json")));
+        assert_eq!(
+            error.to_string(),
+            "FileNotFound (2), key \"key\", file kvs_0_0.json: missing"
+        );
+        assert_eq!(
+            std::error::Error::source(&error).unwrap().to_string(),
+            "missing"
+        );
+
+        assert!(ErrorCode::KeyNotFound.context().is_none());
+        assert_eq!(ErrorCode::KeyNotFound.to_string(), "KeyNotFound (15)");
+        assert_ne!(
+            ErrorCode::KeyNotFound.with_key("key"),
+            ErrorCode::InvalidKey
+        );
+    }
+
     #[test]
     fn test_from_utf8_error_to_conversion_failed() {
         // test from: https://doc.rust-lang.org/std/string/struct.FromUtf8Error.html
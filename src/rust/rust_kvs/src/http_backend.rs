@@ -0,0 +1,385 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! HTTP(S) remote backend.
+//!
+//! [`HttpBackend`] stores the serialized KVS on a backend service:
+//!   * Load: `GET` of the KVS and hash resources, the returned `ETag` is remembered.
+//!   * Save: `PUT` of the KVS resource with `If-Match: <ETag>` of the last load or save.
+//!     If the resource wasn't seen before, `If-None-Match: *` is sent instead.
+//!     Server response `412 Precondition Failed` is reported as
+//!     `ErrorCode::ConcurrentModification`.
+//!
+//! Data is stored in the same format as the JSON backend. File paths resolved for the instance are
+//! mapped to URLs by [`KvsHttpEndpoint`]. Snapshots aren't rotated on the server, the remote
+//! resources always hold the latest flushed state.
+
+use crate::error_code::ErrorCode;
+use crate::json_backend::JsonBackend;
+use crate::kvs_api::{InstanceId, SnapshotId};
+use crate::kvs_backend::{KvsBackend, KvsPathResolver};
+use crate::kvs_value::{KvsMap, KvsValue};
+use std::collections::HashMap;
+use std::io::Read;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex, MutexGuard, PoisonError};
+use tinyjson::JsonValue;
+
+/// Last known `ETag` of each URL.
+static ETAGS: LazyLock<Mutex<HashMap<String, String>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+impl From<PoisonError<MutexGuard<'_, HashMap<String, String>>>> for ErrorCode {
+    fn from(_cause: PoisonError<MutexGuard<'_, HashMap<String, String>>>) -> Self {
+        ErrorCode::MutexLockFailed
+    }
+}
+
+/// Service endpoint of an HTTP backend.
+pub trait KvsHttpEndpoint {
+    /// Map file path resolved for an instance to resource URL.
+    fn url(path: &Path) -> String;
+}
+
+/// KVS backend storing data on an HTTP(S) service.
+pub struct HttpBackend<Endpoint: KvsHttpEndpoint> {
+    _endpoint_marker: PhantomData<Endpoint>,
+}
+
+impl<Endpoint: KvsHttpEndpoint> HttpBackend<Endpoint> {
+    /// Get resource body and remember its `ETag`.
+    fn get(url: &str) -> Result<Vec<u8>, ErrorCode> {
+        let response = ureq::get(url).call()?;
+        let etag = response.header("ETag").map(str::to_string);
+
+        let mut body = Vec::new();
+        response.into_reader().read_to_end(&mut body)?;
+
+        let mut etags = ETAGS.lock()?;
+        match etag {
+            Some(etag) => etags.insert(url.to_string(), etag),
+            None => etags.remove(url),
+        };
+        Ok(body)
+    }
+
+    /// Put resource body conditionally on the last known `ETag`.
+    fn put(url: &str, body: &[u8], conditional: bool) -> Result<(), ErrorCode> {
+        let mut request = ureq::put(url);
+        if conditional {
+            request = match ETAGS.lock()?.get(url) {
+                Some(etag) => request.set("If-Match", etag),
+                None => request.set("If-None-Match", "*"),
+            };
+        }
+        let response = request.send_bytes(body)?;
+
+        let mut etags = ETAGS.lock()?;
+        match response.header("ETag") {
+            Some(etag) => etags.insert(url.to_string(), etag.to_string()),
+            None => etags.remove(url),
+        };
+        Ok(())
+    }
+}
+
+impl<Endpoint: KvsHttpEndpoint> KvsBackend for HttpBackend<Endpoint> {
+    fn load_kvs(kvs_path: &Path, hash_path: Option<&PathBuf>) -> Result<KvsMap, ErrorCode> {
+        let json_bytes = Self::get(&Endpoint::url(kvs_path))?;
+
+        // Perform hash check.
+        if let Some(hash_path) = hash_path {
+            let hash_bytes = Self::get(&Endpoint::url(hash_path))
+                .map_err(|_| ErrorCode::KvsHashFileReadError)?;
+            let hash_kvs = adler32::RollingAdler32::from_buffer(&json_bytes).hash();
+            if hash_bytes != hash_kvs.to_be_bytes() {
+                return Err(ErrorCode::ValidationFailed);
+            }
+        }
+
+        // Parse and cast from `JsonValue` to `KvsValue`.
+        let json_value: JsonValue = String::from_utf8(json_bytes)?.parse()?;
+        if let KvsValue::Object(kvs_map) = KvsValue::from(json_value) {
+            Ok(kvs_map)
+        } else {
+            Err(ErrorCode::JsonParserError)
+        }
+    }
+
+    fn save_kvs(
+        kvs_map: &KvsMap,
+        kvs_path: &Path,
+        hash_path: Option<&PathBuf>,
+    ) -> Result<(), ErrorCode> {
+        let json_value = JsonValue::from(KvsValue::Object(kvs_map.clone()));
+        let json_str = json_value.stringify()?;
+        Self::put(&Endpoint::url(kvs_path), json_str.as_bytes(), true)?;
+
+        // Hash resource follows KVS resource and is written unconditionally.
+        if let Some(hash_path) = hash_path {
+            let hash = adler32::RollingAdler32::from_buffer(json_str.as_bytes()).hash();
+            Self::put(&Endpoint::url(hash_path), &hash.to_be_bytes(), false)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Resource names are equal to `JsonBackend` file names.
+impl<Endpoint: KvsHttpEndpoint> KvsPathResolver for HttpBackend<Endpoint> {
+    fn kvs_file_name(instance_id: InstanceId, snapshot_id: SnapshotId) -> String {
+        JsonBackend::kvs_file_name(instance_id, snapshot_id)
+    }
+
+    fn kvs_file_path(
+        working_dir: &Path,
+        instance_id: InstanceId,
+        snapshot_id: SnapshotId,
+    ) -> PathBuf {
+        JsonBackend::kvs_file_path(working_dir, instance_id, snapshot_id)
+    }
+
+    fn hash_file_name(instance_id: InstanceId, snapshot_id: SnapshotId) -> String {
+        JsonBackend::hash_file_name(instance_id, snapshot_id)
+    }
+
+    fn hash_file_path(
+        working_dir: &Path,
+        instance_id: InstanceId,
+        snapshot_id: SnapshotId,
+    ) -> PathBuf {
+        JsonBackend::hash_file_path(working_dir, instance_id, snapshot_id)
+    }
+
+    fn defaults_file_name(instance_id: InstanceId) -> String {
+        JsonBackend::defaults_file_name(instance_id)
+    }
+
+    fn defaults_file_path(working_dir: &Path, instance_id: InstanceId) -> PathBuf {
+        JsonBackend::defaults_file_path(working_dir, instance_id)
+    }
+
+    fn global_defaults_file_name() -> String {
+        JsonBackend::global_defaults_file_name()
+    }
+
+    fn global_defaults_file_path(working_dir: &Path) -> PathBuf {
+        JsonBackend::global_defaults_file_path(working_dir)
+    }
+}
+
+#[cfg(test)]
+mod http_backend_tests {
+    use crate::error_code::ErrorCode;
+    use crate::http_backend::{HttpBackend, KvsHttpEndpoint};
+    use crate::kvs_api::{InstanceId, SnapshotId};
+    use crate::kvs_backend::{KvsBackend, KvsPathResolver};
+    use crate::kvs_value::{KvsMap, KvsValue};
+    use std::collections::HashMap;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex, OnceLock};
+    use std::thread;
+
+    /// Resources stored by test server, with version used as `ETag`.
+    type Resources = Arc<Mutex<HashMap<String, (Vec<u8>, u32)>>>;
+
+    /// Handle single request of a minimal HTTP server supporting conditional `GET` and `PUT`.
+    fn handle(stream: TcpStream, resources: &Resources) {
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).unwrap();
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next().unwrap_or_default().to_string();
+        let path = parts.next().unwrap_or_default().to_string();
+
+        let mut headers = HashMap::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+            }
+        }
+        let content_length: usize = headers
+            .get("content-length")
+            .map_or(0, |v| v.parse().unwrap());
+        let mut body = vec![0u8; content_length];
+        reader.read_exact(&mut body).unwrap();
+
+        let mut resources = resources.lock().unwrap();
+        let current = resources.get(&path).cloned();
+        let (status, etag, response_body) = match method.as_str() {
+            "GET" => match current {
+                Some((data, version)) => ("200 OK", Some(version), data),
+                None => ("404 Not Found", None, Vec::new()),
+            },
+            "PUT" => {
+                let current_etag = current.as_ref().map(|(_, v)| format!("\"{v}\""));
+                let precondition_ok = match (headers.get("if-match"), headers.get("if-none-match"))
+                {
+                    (Some(etag), _) => current_etag.as_ref() == Some(etag),
+                    (None, Some(_)) => current_etag.is_none(),
+                    (None, None) => true,
+                };
+                if precondition_ok {
+                    let version = current.map_or(1, |(_, v)| v + 1);
+                    resources.insert(path, (body, version));
+                    ("204 No Content", Some(version), Vec::new())
+                } else {
+                    ("412 Precondition Failed", None, Vec::new())
+                }
+            }
+            _ => ("405 Method Not Allowed", None, Vec::new()),
+        };
+
+        let mut response = format!(
+            "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n",
+            response_body.len()
+        );
+        if let Some(version) = etag {
+            response.push_str(&format!("ETag: \"{version}\"\r\n"));
+        }
+        response.push_str("\r\n");
+        let mut stream = stream;
+        stream.write_all(response.as_bytes()).unwrap();
+        stream.write_all(&response_body).unwrap();
+    }
+
+    /// Start test server once and return its base URL and resources.
+    fn server() -> &'static (String, Resources) {
+        static SERVER: OnceLock<(String, Resources)> = OnceLock::new();
+        SERVER.get_or_init(|| {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let base_url = format!("http://{}", listener.local_addr().unwrap());
+            let resources: Resources = Arc::new(Mutex::new(HashMap::new()));
+            let server_resources = resources.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    handle(stream.unwrap(), &server_resources);
+                }
+            });
+            (base_url, resources)
+        })
+    }
+
+    struct TestEndpoint;
+
+    impl KvsHttpEndpoint for TestEndpoint {
+        fn url(path: &Path) -> String {
+            let file_name = path.file_name().unwrap().to_string_lossy();
+            format!("{}/{file_name}", server().0)
+        }
+    }
+
+    type TestBackend = HttpBackend<TestEndpoint>;
+
+    fn paths(instance_id: usize) -> (PathBuf, PathBuf) {
+        (
+            TestBackend::kvs_file_path(Path::new(""), InstanceId(instance_id), SnapshotId(0)),
+            TestBackend::hash_file_path(Path::new(""), InstanceId(instance_id), SnapshotId(0)),
+        )
+    }
+
+    fn kvs_map(value: i32) -> KvsMap {
+        KvsMap::from([("key".to_string(), KvsValue::I32(value))])
+    }
+
+    #[test]
+    fn test_save_load() {
+        let (kvs_path, hash_path) = paths(1);
+        TestBackend::save_kvs(&kvs_map(1), &kvs_path, Some(&hash_path)).unwrap();
+
+        let loaded = TestBackend::load_kvs(&kvs_path, Some(&hash_path)).unwrap();
+        assert_eq!(loaded, kvs_map(1));
+
+        // Subsequent save is conditional on `ETag` of the last save.
+        TestBackend::save_kvs(&kvs_map(2), &kvs_path, Some(&hash_path)).unwrap();
+        let loaded = TestBackend::load_kvs(&kvs_path, Some(&hash_path)).unwrap();
+        assert_eq!(loaded, kvs_map(2));
+    }
+
+    #[test]
+    fn test_load_not_found() {
+        let (kvs_path, hash_path) = paths(2);
+        let result = TestBackend::load_kvs(&kvs_path, Some(&hash_path));
+        assert!(result.is_err_and(|e| e == ErrorCode::FileNotFound));
+    }
+
+    #[test]
+    fn test_load_hash_mismatch() {
+        let (kvs_path, hash_path) = paths(3);
+        TestBackend::save_kvs(&kvs_map(1), &kvs_path, Some(&hash_path)).unwrap();
+        let hash_url = format!("/{}", hash_path.file_name().unwrap().to_string_lossy());
+        server()
+            .1
+            .lock()
+            .unwrap()
+            .insert(hash_url, (vec![0, 0, 0, 0], 100));
+
+        let result = TestBackend::load_kvs(&kvs_path, Some(&hash_path));
+        assert!(result.is_err_and(|e| e == ErrorCode::ValidationFailed));
+    }
+
+    #[test]
+    fn test_save_concurrent_modification() {
+        let (kvs_path, hash_path) = paths(4);
+        TestBackend::save_kvs(&kvs_map(1), &kvs_path, Some(&hash_path)).unwrap();
+
+        // Another client modifies the resource.
+        let kvs_url = format!("/{}", kvs_path.file_name().unwrap().to_string_lossy());
+        server()
+            .1
+            .lock()
+            .unwrap()
+            .entry(kvs_url)
+            .and_modify(|(_, version)| *version += 10);
+
+        let result = TestBackend::save_kvs(&kvs_map(2), &kvs_path, Some(&hash_path));
+        assert!(result.is_err_and(|e| e == ErrorCode::ConcurrentModification));
+
+        // Reloading picks up the current `ETag`.
+        TestBackend::load_kvs(&kvs_path, Some(&hash_path)).unwrap();
+        TestBackend::save_kvs(&kvs_map(2), &kvs_path, Some(&hash_path)).unwrap();
+    }
+
+    #[test]
+    fn test_save_create_existing_resource() {
+        let (kvs_path, _) = paths(5);
+        let kvs_url = format!("/{}", kvs_path.file_name().unwrap().to_string_lossy());
+        server()
+            .1
+            .lock()
+            .unwrap()
+            .insert(kvs_url, (b"{}".to_vec(), 1));
+
+        // Resource exists but was never loaded.
+        let result = TestBackend::save_kvs(&kvs_map(1), &kvs_path, None);
+        assert!(result.is_err_and(|e| e == ErrorCode::ConcurrentModification));
+    }
+
+    #[test]
+    fn test_status_mapping() {
+        let response = ureq::Response::new(401, "Unauthorized", "").unwrap();
+        let error = ureq::Error::Status(401, response);
+        assert_eq!(ErrorCode::from(error), ErrorCode::AuthenticationFailed);
+
+        let response = ureq::Response::new(507, "Insufficient Storage", "").unwrap();
+        let error = ureq::Error::Status(507, response);
+        assert_eq!(ErrorCode::from(error), ErrorCode::OutOfStorageSpace);
+    }
+}
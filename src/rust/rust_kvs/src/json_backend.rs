@@ -10,712 +10,930 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::error_code::ErrorCode;
-use crate::kvs_api::{InstanceId, SnapshotId};
-use crate::kvs_backend::{KvsBackend, KvsPathResolver};
+use crate::kvs_api::{
+    DuplicateKeyPolicy, FloatFormat, InstanceId, KvsCompression, SnapshotId, SyncPolicy,
+};
+use crate::kvs_backend::{
+    check_extension, sync_files, validate_hash, write_hash_value, KvsBackend, KvsPathResolver,
+};
+use crate::kvs_fs::{KvsFs, StdFs};
+use crate::kvs_lazy::LazyKvsMap;
+use crate::kvs_log::{kvs_error, kvs_warn};
+use crate::kvs_migration::{self, KVS_FORMAT_VERSION, VERSION_FIELD};
 use crate::kvs_value::{KvsMap, KvsValue};
-use std::collections::HashMap;
-use std::fs;
+use adler32::RollingAdler32;
+use std::collections::{HashMap, HashSet};
+#[cfg(feature = "gzip")]
+use std::io::Read;
+use std::io::{self, Write};
+use std::marker::PhantomData;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
-use tinyjson::{JsonGenerateError, JsonParseError, JsonValue};
-
-// Example of how KvsValue is stored in the JSON file (t-tagged format):
-// {
-//   "my_int": { "t": "i32", "v": 42 },
-//   "my_float": { "t": "f64", "v": 3.1415 },
-//   "my_bool": { "t": "bool", "v": true },
-//   "my_string": { "t": "str", "v": "hello" },
-//   "my_array": { "t": "arr", "v": [ ... ] },
-//   "my_object": { "t": "obj", "v": { ... } },
-//   "my_null": { "t": "null", "v": null }
-// }
-
-/// Backend-specific JsonValue -> KvsValue conversion.
-impl From<JsonValue> for KvsValue {
-    fn from(val: JsonValue) -> KvsValue {
-        match val {
-            JsonValue::Object(mut obj) => {
-                // Type-tagged: { "t": ..., "v": ... }
-                if let (Some(JsonValue::String(type_str)), Some(value)) =
-                    (obj.remove("t"), obj.remove("v"))
-                {
-                    return match (type_str.as_str(), value) {
-                        ("i32", JsonValue::Number(v)) => KvsValue::I32(v as i32),
-                        ("u32", JsonValue::Number(v)) => KvsValue::U32(v as u32),
-                        ("i64", JsonValue::Number(v)) => KvsValue::I64(v as i64),
-                        ("u64", JsonValue::Number(v)) => KvsValue::U64(v as u64),
-                        ("f64", JsonValue::Number(v)) => KvsValue::F64(v),
-                        ("bool", JsonValue::Boolean(v)) => KvsValue::Boolean(v),
-                        ("str", JsonValue::String(v)) => KvsValue::String(v),
-                        ("null", JsonValue::Null) => KvsValue::Null,
-                        ("arr", JsonValue::Array(v)) => {
-                            KvsValue::Array(v.into_iter().map(KvsValue::from).collect())
-                        }
-                        ("obj", JsonValue::Object(v)) => KvsValue::Object(
-                            v.into_iter().map(|(k, v)| (k, KvsValue::from(v))).collect(),
-                        ),
-                        // Remaining types can be handled with Null.
-                        _ => KvsValue::Null,
-                    };
-                }
-                // If not a t-tagged object, treat as a map of key-value pairs (KvsMap)
-                let map: KvsMap = obj
-                    .into_iter()
-                    .map(|(k, v)| (k, KvsValue::from(v)))
-                    .collect();
-                KvsValue::Object(map)
-            }
-            // Remaining types can be handled with Null.
-            _ => KvsValue::Null,
-        }
-    }
+use std::time::SystemTime;
+use tinyjson::JsonValue;
+
+/// Scanner resolving duplicate object keys in JSON text.
+///
+/// TinyJSON silently keeps the last occurrence of a key. The scanner is run on text already
+/// accepted by the parser and rewrites it according to the policy.
+struct DuplicateKeyScanner<'a> {
+    text: &'a str,
+    pos: usize,
+    policy: DuplicateKeyPolicy,
+    out: String,
+    duplicates: usize,
+    depth: usize,
+    members: HashMap<String, Range<usize>>,
 }
 
-/// Backend-specific KvsValue -> JsonValue conversion.
-impl From<KvsValue> for JsonValue {
-    fn from(val: KvsValue) -> JsonValue {
-        let mut obj = HashMap::new();
-        match val {
-            KvsValue::I32(n) => {
-                obj.insert("t".to_string(), JsonValue::String("i32".to_string()));
-                obj.insert("v".to_string(), JsonValue::Number(n as f64));
-            }
-            KvsValue::U32(n) => {
-                obj.insert("t".to_string(), JsonValue::String("u32".to_string()));
-                obj.insert("v".to_string(), JsonValue::Number(n as f64));
-            }
-            KvsValue::I64(n) => {
-                obj.insert("t".to_string(), JsonValue::String("i64".to_string()));
-                obj.insert("v".to_string(), JsonValue::Number(n as f64));
-            }
-            KvsValue::U64(n) => {
-                obj.insert("t".to_string(), JsonValue::String("u64".to_string()));
-                obj.insert("v".to_string(), JsonValue::Number(n as f64));
-            }
-            KvsValue::F64(n) => {
-                obj.insert("t".to_string(), JsonValue::String("f64".to_string()));
-                obj.insert("v".to_string(), JsonValue::Number(n));
-            }
-            KvsValue::Boolean(b) => {
-                obj.insert("t".to_string(), JsonValue::String("bool".to_string()));
-                obj.insert("v".to_string(), JsonValue::Boolean(b));
-            }
-            KvsValue::String(s) => {
-                obj.insert("t".to_string(), JsonValue::String("str".to_string()));
-                obj.insert("v".to_string(), JsonValue::String(s));
-            }
-            KvsValue::Null => {
-                obj.insert("t".to_string(), JsonValue::String("null".to_string()));
-                obj.insert("v".to_string(), JsonValue::Null);
-            }
-            KvsValue::Array(arr) => {
-                obj.insert("t".to_string(), JsonValue::String("arr".to_string()));
-                obj.insert(
-                    "v".to_string(),
-                    JsonValue::Array(arr.into_iter().map(JsonValue::from).collect()),
-                );
-            }
-            KvsValue::Object(map) => {
-                obj.insert("t".to_string(), JsonValue::String("obj".to_string()));
-                obj.insert(
-                    "v".to_string(),
-                    JsonValue::Object(
-                        map.into_iter()
-                            .map(|(k, v)| (k, JsonValue::from(v)))
-                            .collect(),
-                    ),
-                );
-            }
+impl<'a> DuplicateKeyScanner<'a> {
+    /// Resolve duplicate keys, returns `None` if the text doesn't contain any.
+    fn resolve(text: &'a str, policy: DuplicateKeyPolicy) -> Result<Option<String>, ErrorCode> {
+        let mut scanner = Self::new(text, policy);
+        scanner.value()?;
+        if scanner.duplicates == 0 {
+            Ok(None)
+        } else {
+            Ok(Some(scanner.out))
         }
-        JsonValue::Object(obj)
     }
-}
 
-/// tinyjson::JsonParseError -> ErrorCode::JsonParseError
-impl From<JsonParseError> for ErrorCode {
-    fn from(cause: JsonParseError) -> Self {
-        eprintln!(
-            "error: JSON parser error: line = {}, column = {}",
-            cause.line(),
-            cause.column()
-        );
-        ErrorCode::JsonParserError
+    /// Locate the value text of each member of the root object, returns `None` if the root is no
+    /// object or contains duplicate keys.
+    fn members(text: &'a str) -> Result<Option<HashMap<String, Range<usize>>>, ErrorCode> {
+        let mut scanner = Self::new(text, DuplicateKeyPolicy::FirstWins);
+        scanner.skip_whitespace();
+        if scanner.peek() != Some(b'{') {
+            return Ok(None);
+        }
+        scanner.value()?;
+        scanner.skip_whitespace();
+        if scanner.duplicates > 0 || scanner.peek().is_some() {
+            return Ok(None);
+        }
+        Ok(Some(scanner.members))
+    }
+
+    fn new(text: &'a str, policy: DuplicateKeyPolicy) -> Self {
+        Self {
+            text,
+            pos: 0,
+            policy,
+            out: String::with_capacity(text.len()),
+            duplicates: 0,
+            depth: 0,
+            members: HashMap::new(),
+        }
     }
-}
 
-/// tinyjson::JsonGenerateError -> ErrorCode::JsonGenerateError
-impl From<JsonGenerateError> for ErrorCode {
-    fn from(cause: JsonGenerateError) -> Self {
-        eprintln!("error: JSON generator error: msg = {}", cause.message());
-        ErrorCode::JsonGeneratorError
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
     }
-}
 
-/// KVS backend implementation based on TinyJSON.
-pub struct JsonBackend;
-
-impl JsonBackend {
-    fn parse(s: &str) -> Result<JsonValue, ErrorCode> {
-        s.parse().map_err(ErrorCode::from)
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|b| b.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
     }
 
-    fn stringify(val: &JsonValue) -> Result<String, ErrorCode> {
-        val.stringify().map_err(ErrorCode::from)
+    fn expect(&mut self, expected: u8) -> Result<(), ErrorCode> {
+        self.skip_whitespace();
+        if self.peek() != Some(expected) {
+            return Err(ErrorCode::JsonParserError);
+        }
+        self.pos += 1;
+        self.out.push(expected as char);
+        Ok(())
     }
 
-    /// Check path have correct extension.
-    fn check_extension(path: &Path, extension: &str) -> bool {
-        let ext = path.extension();
-        ext.is_some_and(|ep| ep.to_str().is_some_and(|es| es == extension))
+    fn value(&mut self) -> Result<(), ErrorCode> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => self.object(),
+            Some(b'[') => self.array(),
+            Some(b'"') => self.string().map(|_| ()),
+            Some(_) => {
+                // Number, boolean or null.
+                let start = self.pos;
+                while self
+                    .peek()
+                    .is_some_and(|b| !matches!(b, b',' | b']' | b'}') && !b.is_ascii_whitespace())
+                {
+                    self.pos += 1;
+                }
+                if self.pos == start {
+                    return Err(ErrorCode::JsonParserError);
+                }
+                self.out.push_str(&self.text[start..self.pos]);
+                Ok(())
+            }
+            None => Err(ErrorCode::JsonParserError),
+        }
     }
-}
 
-impl KvsBackend for JsonBackend {
-    fn load_kvs(kvs_path: &Path, hash_path: Option<&PathBuf>) -> Result<KvsMap, ErrorCode> {
-        if !Self::check_extension(kvs_path, "json") {
-            return Err(ErrorCode::KvsFileReadError);
+    /// Copy string literal, returns its unescaped content.
+    fn string(&mut self) -> Result<String, ErrorCode> {
+        let start = self.pos;
+        self.pos += 1;
+        loop {
+            match self.peek() {
+                Some(b'"') => break,
+                Some(b'\\') => self.pos += 2,
+                Some(_) => self.pos += 1,
+                None => return Err(ErrorCode::JsonParserError),
+            }
         }
-        if hash_path.is_some_and(|p| !Self::check_extension(p, "hash")) {
-            return Err(ErrorCode::KvsHashFileReadError);
+        self.pos += 1;
+
+        let literal = &self.text[start..self.pos];
+        self.out.push_str(literal);
+        match JsonBackend::parse(literal)? {
+            JsonValue::String(s) => Ok(s),
+            _ => Err(ErrorCode::JsonParserError),
         }
+    }
 
-        // Load KVS file and parse from string to `JsonValue`.
-        let json_str = fs::read_to_string(kvs_path)?;
-        let json_value = Self::parse(&json_str)?;
+    fn object(&mut self) -> Result<(), ErrorCode> {
+        self.expect(b'{')?;
+        self.depth += 1;
+        let mut keys = HashSet::new();
+        let mut empty = true;
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b'}') => break,
+                Some(b',') => self.pos += 1,
+                _ => {}
+            }
+            self.skip_whitespace();
 
-        // Perform hash check.
-        if let Some(hash_path) = hash_path {
-            match fs::read(hash_path) {
-                Ok(hash_bytes) => {
-                    let hash_kvs = adler32::RollingAdler32::from_buffer(json_str.as_bytes()).hash();
-                    if hash_bytes.len() == 4 {
-                        let file_hash = u32::from_be_bytes([
-                            hash_bytes[0],
-                            hash_bytes[1],
-                            hash_bytes[2],
-                            hash_bytes[3],
-                        ]);
-                        if hash_kvs != file_hash {
-                            return Err(ErrorCode::ValidationFailed);
-                        }
-                    } else {
-                        return Err(ErrorCode::ValidationFailed);
-                    }
+            let member_start = self.out.len();
+            if !empty {
+                self.out.push(',');
+            }
+            let key = self.string()?;
+            self.expect(b':')?;
+            self.skip_whitespace();
+            let value_start = self.pos;
+            self.value()?;
+
+            if keys.insert(key.clone()) {
+                if self.depth == 1 {
+                    self.members.insert(key, value_start..self.pos);
                 }
-                Err(_) => return Err(ErrorCode::KvsHashFileReadError),
-            };
+                empty = false;
+                continue;
+            }
+            self.duplicates += 1;
+            match self.policy {
+                DuplicateKeyPolicy::Reject => {
+                    kvs_error!("duplicate key \"{key}\"");
+                    return Err(ErrorCode::JsonParserError);
+                }
+                DuplicateKeyPolicy::FirstWins => self.out.truncate(member_start),
+                DuplicateKeyPolicy::LastWinsWithWarning => {
+                    kvs_warn!("duplicate key \"{key}\", last occurrence is used");
+                }
+            }
         }
-
-        // Cast from `JsonValue` to `KvsValue`.
-        let kvs_value = KvsValue::from(json_value);
-        if let KvsValue::Object(kvs_map) = kvs_value {
-            Ok(kvs_map)
-        } else {
-            Err(ErrorCode::JsonParserError)
+        self.depth -= 1;
+        self.expect(b'}')
+    }
+
+    fn array(&mut self) -> Result<(), ErrorCode> {
+        self.expect(b'[')?;
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b']') => break,
+                Some(b',') => {
+                    self.pos += 1;
+                    self.out.push(',');
+                }
+                _ => self.value()?,
+            }
         }
+        self.expect(b']')
     }
+}
 
-    fn save_kvs(
-        kvs_map: &KvsMap,
-        kvs_path: &Path,
-        hash_path: Option<&PathBuf>,
-    ) -> Result<(), ErrorCode> {
-        // Validate extensions.
-        if !Self::check_extension(kvs_path, "json") {
-            return Err(ErrorCode::KvsFileReadError);
-        }
-        if hash_path.is_some_and(|p| !Self::check_extension(p, "hash")) {
-            return Err(ErrorCode::KvsHashFileReadError);
+// Compressed KVS files are stored with the compression extension appended, e.g. `kvs_1_0.json.gz`.
+// Paths passed to the backend always use the `.json` name, the stored file is looked up in the
+// order of `COMPRESSIONS`. The hash is computed over the uncompressed JSON.
+
+/// Compressions of stored files, in lookup order.
+const COMPRESSIONS: &[KvsCompression] = &[
+    KvsCompression::None,
+    #[cfg(feature = "gzip")]
+    KvsCompression::Gzip,
+    #[cfg(feature = "zstd")]
+    KvsCompression::Zstd,
+];
+
+/// Path of a file stored with given compression.
+fn compressed_path(path: &Path, compression: KvsCompression) -> PathBuf {
+    match compression.extension() {
+        Some(extension) => {
+            let mut path = path.as_os_str().to_owned();
+            path.push(".");
+            path.push(extension);
+            PathBuf::from(path)
         }
+        None => path.to_path_buf(),
+    }
+}
 
-        // Cast from `KvsValue` to `JsonValue`.
-        let kvs_value = KvsValue::Object(kvs_map.clone());
-        let json_value = JsonValue::from(kvs_value);
-
-        // Stringify `JsonValue` and save to KVS file.
-        let json_str = Self::stringify(&json_value)?;
-        fs::write(kvs_path, &json_str)?;
+/// Find file stored for given path and its compression.
+fn find_stored<Fs: KvsFs>(path: &Path) -> Option<(PathBuf, KvsCompression)> {
+    COMPRESSIONS
+        .iter()
+        .map(|c| (compressed_path(path, *c), *c))
+        .find(|(p, _)| Fs::exists(p))
+}
 
-        // Generate hash and save to hash file.
-        if let Some(hash_path) = hash_path {
-            let hash = adler32::RollingAdler32::from_buffer(json_str.as_bytes()).hash();
-            fs::write(hash_path, hash.to_be_bytes())?
+/// Remove files stored for given path, except the one with compression `keep`.
+fn remove_stored<Fs: KvsFs>(path: &Path, keep: Option<KvsCompression>) -> Result<(), ErrorCode> {
+    for compression in COMPRESSIONS.iter().filter(|c| Some(**c) != keep) {
+        match Fs::remove_file(&compressed_path(path, *compression)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
         }
-
-        Ok(())
     }
+    Ok(())
 }
 
-/// KVS backend path resolver for `JsonBackend`.
-impl KvsPathResolver for JsonBackend {
-    fn kvs_file_name(instance_id: InstanceId, snapshot_id: SnapshotId) -> String {
-        format!("kvs_{instance_id}_{snapshot_id}.json")
-    }
+fn decompress(data: Vec<u8>, compression: KvsCompression) -> Result<Vec<u8>, ErrorCode> {
+    let decoded: std::io::Result<Vec<u8>> = match compression {
+        KvsCompression::None => Ok(data),
+        #[cfg(feature = "gzip")]
+        KvsCompression::Gzip => {
+            let mut decoded = Vec::new();
+            flate2::read::GzDecoder::new(data.as_slice())
+                .read_to_end(&mut decoded)
+                .map(|_| decoded)
+        }
+        #[cfg(feature = "zstd")]
+        KvsCompression::Zstd => zstd::decode_all(data.as_slice()),
+    };
+    decoded.map_err(|e| {
+        kvs_error!("decompressing KVS file failed: {e}");
+        ErrorCode::KvsFileReadError
+    })
+}
 
-    fn kvs_file_path(
-        working_dir: &Path,
-        instance_id: InstanceId,
-        snapshot_id: SnapshotId,
-    ) -> PathBuf {
-        working_dir.join(Self::kvs_file_name(instance_id, snapshot_id))
-    }
+/// Writer computing the hash of the written data.
+struct HashingWriter<W: Write> {
+    inner: W,
+    hash: RollingAdler32,
+}
 
-    fn hash_file_name(instance_id: InstanceId, snapshot_id: SnapshotId) -> String {
-        format!("kvs_{instance_id}_{snapshot_id}.hash")
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hash.update_buffer(&buf[..written]);
+        Ok(written)
     }
 
-    fn hash_file_path(
-        working_dir: &Path,
-        instance_id: InstanceId,
-        snapshot_id: SnapshotId,
-    ) -> PathBuf {
-        working_dir.join(Self::hash_file_name(instance_id, snapshot_id))
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
     }
+}
 
-    fn defaults_file_name(instance_id: InstanceId) -> String {
-        format!("kvs_{instance_id}_default.json")
+/// Writer only counting the written bytes.
+struct CountingWriter(usize);
+
+impl Write for CountingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
     }
 
-    fn defaults_file_path(working_dir: &Path, instance_id: InstanceId) -> PathBuf {
-        working_dir.join(Self::defaults_file_name(instance_id))
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
     }
 }
 
-#[cfg(test)]
-mod json_value_to_kvs_value_conversion_tests {
-    use std::collections::HashMap;
-    use tinyjson::JsonValue;
+/// KVS backend implementation based on TinyJSON, accessing files through a
+/// [`KvsFs`](crate::kvs_fs).
+pub struct GenericJsonBackend<Fs: KvsFs = StdFs>(PhantomData<Fs>);
 
-    use crate::prelude::{KvsMap, KvsValue};
+/// KVS backend implementation based on TinyJSON, storing files in the filesystem of the platform.
+pub type JsonBackend = GenericJsonBackend<StdFs>;
 
-    #[test]
-    fn test_i32_ok() {
-        let jv = JsonValue::from(HashMap::from([
-            ("t".to_string(), JsonValue::String("i32".to_string())),
-            ("v".to_string(), JsonValue::Number(-123.0)),
-        ]));
-        let kv = KvsValue::from(jv);
-        assert_eq!(kv, KvsValue::I32(-123));
-    }
-
-    #[test]
-    fn test_i32_invalid_type() {
-        let jv = JsonValue::from(HashMap::from([
-            ("t".to_string(), JsonValue::String("i32".to_string())),
-            ("v".to_string(), JsonValue::String("-123.0".to_string())),
-        ]));
-        let kv = KvsValue::from(jv);
-        assert_eq!(kv, KvsValue::Null);
+impl<Fs: KvsFs> GenericJsonBackend<Fs> {
+    fn parse(s: &str) -> Result<JsonValue, ErrorCode> {
+        s.parse().map_err(ErrorCode::from)
     }
 
-    #[test]
-    fn test_u32_ok() {
-        let jv = JsonValue::from(HashMap::from([
-            ("t".to_string(), JsonValue::String("u32".to_string())),
-            ("v".to_string(), JsonValue::Number(123.0)),
-        ]));
-        let kv = KvsValue::from(jv);
-        assert_eq!(kv, KvsValue::U32(123));
-    }
+    /// Parse the content of a KVS file.
+    ///
+    /// # Parameters
+    ///   * `json_str`: Content of a KVS file
+    ///   * `duplicate_keys`: Handling of duplicate keys
+    ///
+    /// # Return Values
+    ///   * Ok: Parsed data
+    ///   * `ErrorCode::JsonParserError`: Content isn't a JSON object or has duplicate keys
+    ///   * `ErrorCode::UnsupportedVersion`: Content was written in an unsupported format version
+    pub(crate) fn kvs_map_from_str(
+        json_str: &str,
+        duplicate_keys: DuplicateKeyPolicy,
+    ) -> Result<KvsMap, ErrorCode> {
+        let json_value = Self::parse(json_str)?;
+        Self::kvs_map_from_parsed(json_str.to_string(), json_value, duplicate_keys)
+    }
+
+    /// Render the content of a KVS file.
+    ///
+    /// # Parameters
+    ///   * `kvs_map`: Data to render
+    ///   * `float_format`: Rendering of `F64` values
+    ///
+    /// # Return Values
+    ///   * Ok: KVS file content, including the format version
+    ///   * `ErrorCode::JsonGeneratorError`: Data couldn't be rendered
+    #[cfg(feature = "json-backend")]
+    pub(crate) fn kvs_map_to_string(
+        kvs_map: &KvsMap,
+        float_format: &FloatFormat,
+    ) -> Result<String, ErrorCode> {
+        let mut out = Vec::new();
+        Self::write_root(kvs_map, float_format, &mut out)?;
+        Ok(String::from_utf8(out)?)
+    }
+
+    /// Convert parsed KVS file content, upgrading older formats.
+    fn kvs_map_from_parsed(
+        json_str: String,
+        mut json_value: JsonValue,
+        duplicate_keys: DuplicateKeyPolicy,
+    ) -> Result<KvsMap, ErrorCode> {
+        // Upgrade files written in an older format.
+        let version = Self::format_version(&json_value)?;
+        let json_str = if version == KVS_FORMAT_VERSION {
+            json_str
+        } else {
+            let json_str = kvs_migration::upgrade(json_str, version)?;
+            json_value = Self::parse(&json_str)?;
+            json_str
+        };
+
+        // Handle duplicate keys, parsed value already uses last occurrence.
+        if let Some(resolved_str) = DuplicateKeyScanner::resolve(&json_str, duplicate_keys)? {
+            if duplicate_keys == DuplicateKeyPolicy::FirstWins {
+                json_value = Self::parse(&resolved_str)?;
+            }
+        }
 
-    #[test]
-    fn test_u32_invalid_type() {
-        let jv = JsonValue::from(HashMap::from([
-            ("t".to_string(), JsonValue::String("u32".to_string())),
-            ("v".to_string(), JsonValue::String("123.0".to_string())),
-        ]));
-        let kv = KvsValue::from(jv);
-        assert_eq!(kv, KvsValue::Null);
+        // Cast from `JsonValue` to `KvsValue`.
+        if let JsonValue::Object(obj) = &mut json_value {
+            obj.remove(VERSION_FIELD);
+        }
+        if Self::is_untagged(&json_value) {
+            kvs_warn!("loading JSON without type tags, value types are inferred");
+            return match Self::from_untagged(json_value) {
+                KvsValue::Object(kvs_map) => Ok(kvs_map),
+                _ => Err(ErrorCode::JsonParserError),
+            };
+        }
+        let kvs_value = KvsValue::from(json_value);
+        if let KvsValue::Object(kvs_map) = kvs_value {
+            Ok(kvs_map)
+        } else {
+            Err(ErrorCode::JsonParserError)
+        }
     }
 
-    #[test]
-    fn test_i64_ok() {
-        let jv = JsonValue::from(HashMap::from([
-            ("t".to_string(), JsonValue::String("i64".to_string())),
-            ("v".to_string(), JsonValue::Number(-123.0)),
-        ]));
-        let kv = KvsValue::from(jv);
-        assert_eq!(kv, KvsValue::I64(-123));
+    /// Check if a value is type-tagged: `{"t": ..., "v": ...}`.
+    fn is_tagged(json_value: &JsonValue) -> bool {
+        matches!(json_value, JsonValue::Object(obj)
+            if matches!(obj.get("t"), Some(JsonValue::String(_))) && obj.contains_key("v"))
+    }
+
+    /// Check if parsed KVS file content is a legacy file without type tags, e.g. `{"key": 1}`.
+    ///
+    /// A root object is untagged if it isn't tagged itself and has a member which isn't tagged.
+    fn is_untagged(json_value: &JsonValue) -> bool {
+        let JsonValue::Object(obj) = json_value else {
+            return false;
+        };
+        !Self::is_tagged(json_value)
+            && obj
+                .iter()
+                .any(|(key, value)| key != VERSION_FIELD && !Self::is_tagged(value))
+    }
+
+    /// Convert a value without type tags, inferring the types.
+    ///
+    /// Numbers are converted to `F64`, objects to `Object` without interpreting type tags.
+    fn from_untagged(json_value: JsonValue) -> KvsValue {
+        match json_value {
+            JsonValue::Number(n) => KvsValue::F64(n),
+            JsonValue::Boolean(b) => KvsValue::Boolean(b),
+            JsonValue::String(s) => KvsValue::String(s),
+            JsonValue::Null => KvsValue::Null,
+            JsonValue::Array(arr) => {
+                KvsValue::Array(arr.into_iter().map(Self::from_untagged).collect())
+            }
+            JsonValue::Object(obj) => KvsValue::Object(
+                obj.into_iter()
+                    .map(|(k, v)| (k, Self::from_untagged(v)))
+                    .collect(),
+            ),
+        }
     }
 
-    #[test]
-    fn test_i64_invalid_type() {
-        let jv = JsonValue::from(HashMap::from([
-            ("t".to_string(), JsonValue::String("i64".to_string())),
-            ("v".to_string(), JsonValue::String("-123.0".to_string())),
-        ]));
-        let kv = KvsValue::from(jv);
-        assert_eq!(kv, KvsValue::Null);
+    /// Convert a value of a JSON document, type-tagged or without type tags.
+    ///
+    /// Values without type tags are converted like by [`Self::from_untagged`].
+    pub(crate) fn value_from_json(json_value: JsonValue) -> KvsValue {
+        if Self::is_tagged(&json_value) {
+            KvsValue::from(json_value)
+        } else {
+            Self::from_untagged(json_value)
+        }
     }
 
-    #[test]
-    fn test_u64_ok() {
-        let jv = JsonValue::from(HashMap::from([
-            ("t".to_string(), JsonValue::String("u64".to_string())),
-            ("v".to_string(), JsonValue::Number(123.0)),
-        ]));
-        let kv = KvsValue::from(jv);
-        assert_eq!(kv, KvsValue::U64(123));
+    /// Rewrite a legacy KVS file without type tags in the tagged format
+    ///
+    /// Value types are inferred like when loading such a file, numbers become `F64`. The file is
+    /// not validated against a hash, legacy stores may not have one. A new hash file is written.
+    ///
+    /// # Parameters
+    ///   * `kvs_path`: KVS file to migrate
+    ///   * `hash_path`: Hash file to write
+    ///
+    /// # Return Values
+    ///   * Ok(true): File was rewritten
+    ///   * Ok(false): File is already tagged, nothing was changed
+    ///   * `ErrorCode::FileNotFound`: KVS file not found
+    ///   * `ErrorCode::JsonParserError`: File isn't a JSON object
+    ///   * Err: Writing the files failed
+    #[cfg(feature = "json-backend")]
+    pub fn migrate_untagged(kvs_path: &Path, hash_path: &Path) -> Result<bool, ErrorCode> {
+        let json_str = Self::read(kvs_path)?;
+        let json_value = Self::parse(&json_str)?;
+        if !Self::is_untagged(&json_value) {
+            return Ok(false);
+        }
+        let kvs_map =
+            Self::kvs_map_from_parsed(json_str, json_value, DuplicateKeyPolicy::default())?;
+        Self::save_kvs(&kvs_map, kvs_path, Some(&hash_path.to_path_buf()))?;
+        Ok(true)
+    }
+
+    /// Read stored KVS file, decompressed if stored compressed.
+    fn read(kvs_path: &Path) -> Result<String, ErrorCode> {
+        let (stored_path, compression) =
+            find_stored::<Fs>(kvs_path).unwrap_or((kvs_path.to_path_buf(), KvsCompression::None));
+        let data =
+            Fs::read(&stored_path).map_err(|e| ErrorCode::from(e).with_path(&stored_path))?;
+        Ok(String::from_utf8(decompress(data, compression)?)?)
+    }
+
+    /// Get format version of root `JsonValue`, files without version use format version 1.
+    fn format_version(json_value: &JsonValue) -> Result<u32, ErrorCode> {
+        let JsonValue::Object(obj) = json_value else {
+            return Ok(1);
+        };
+        match obj.get(VERSION_FIELD) {
+            None => Ok(1),
+            Some(JsonValue::Number(n))
+                if n.fract() == 0.0 && (0.0..=u32::MAX as f64).contains(n) =>
+            {
+                Ok(*n as u32)
+            }
+            Some(_) => {
+                kvs_error!("invalid {VERSION_FIELD} field");
+                Err(ErrorCode::JsonParserError)
+            }
+        }
     }
 
-    #[test]
-    fn test_u64_invalid_type() {
-        let jv = JsonValue::from(HashMap::from([
-            ("t".to_string(), JsonValue::String("u64".to_string())),
-            ("v".to_string(), JsonValue::String("123.0".to_string())),
-        ]));
-        let kv = KvsValue::from(jv);
-        assert_eq!(kv, KvsValue::Null);
+    /// Write the content of a KVS file: the tagged root object containing the format version.
+    ///
+    /// The content is generated directly from the data while writing, without an intermediate
+    /// `JsonValue` or string.
+    fn write_root(
+        kvs_map: &KvsMap,
+        float_format: &FloatFormat,
+        out: &mut dyn Write,
+    ) -> Result<(), ErrorCode> {
+        out.write_all(br#"{"t":"obj","v":"#)?;
+        Self::write_members(kvs_map, float_format, out)?;
+        write!(out, ",\"{VERSION_FIELD}\":{KVS_FORMAT_VERSION}}}")?;
+        Ok(())
     }
 
-    #[test]
-    fn test_f64_ok() {
-        let jv = JsonValue::from(HashMap::from([
-            ("t".to_string(), JsonValue::String("f64".to_string())),
-            ("v".to_string(), JsonValue::Number(-432.1)),
-        ]));
-        let kv = KvsValue::from(jv);
-        assert_eq!(kv, KvsValue::F64(-432.1));
+    /// Write the members of an object.
+    fn write_members(
+        kvs_map: &KvsMap,
+        float_format: &FloatFormat,
+        out: &mut dyn Write,
+    ) -> Result<(), ErrorCode> {
+        out.write_all(b"{")?;
+        for (i, (key, value)) in kvs_map.iter().enumerate() {
+            if i > 0 {
+                out.write_all(b",")?;
+            }
+            Self::write_string(key, out)?;
+            out.write_all(b":")?;
+            Self::write_value(value, float_format, out)?;
+        }
+        out.write_all(b"}")?;
+        Ok(())
     }
 
-    #[test]
-    fn test_f64_invalid_type() {
-        let jv = JsonValue::from(HashMap::from([
-            ("t".to_string(), JsonValue::String("f64".to_string())),
-            ("v".to_string(), JsonValue::String("-432.1".to_string())),
-        ]));
-        let kv = KvsValue::from(jv);
-        assert_eq!(kv, KvsValue::Null);
+    /// Write a type-tagged value, `F64` values rendered with given format.
+    fn write_value(
+        value: &KvsValue,
+        float_format: &FloatFormat,
+        out: &mut dyn Write,
+    ) -> Result<(), ErrorCode> {
+        let tag = match value {
+            KvsValue::I32(_) => "i32",
+            KvsValue::U32(_) => "u32",
+            KvsValue::I64(_) => "i64",
+            KvsValue::U64(_) => "u64",
+            KvsValue::F64(_) => "f64",
+            KvsValue::Boolean(_) => "bool",
+            KvsValue::String(_) => "str",
+            KvsValue::Null => "null",
+            KvsValue::Array(_) => "arr",
+            KvsValue::Object(_) => "obj",
+        };
+        write!(out, r#"{{"t":"{tag}","v":"#)?;
+        match value {
+            KvsValue::I32(n) => write!(out, "{n}")?,
+            KvsValue::U32(n) => write!(out, "{n}")?,
+            // Stored as string, `f64` can't represent all values.
+            KvsValue::I64(n) => write!(out, "\"{n}\"")?,
+            KvsValue::U64(n) => write!(out, "\"{n}\"")?,
+            KvsValue::F64(n) => out.write_all(Self::format_float(*n, float_format)?.as_bytes())?,
+            KvsValue::Boolean(b) => write!(out, "{b}")?,
+            KvsValue::String(s) => Self::write_string(s, out)?,
+            KvsValue::Null => out.write_all(b"null")?,
+            KvsValue::Array(values) => {
+                out.write_all(b"[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        out.write_all(b",")?;
+                    }
+                    Self::write_value(value, float_format, out)?;
+                }
+                out.write_all(b"]")?;
+            }
+            KvsValue::Object(map) => Self::write_members(map, float_format, out)?,
+        }
+        out.write_all(b"}")?;
+        Ok(())
     }
 
-    #[test]
-    fn test_bool_ok() {
-        let jv = JsonValue::from(HashMap::from([
-            ("t".to_string(), JsonValue::String("bool".to_string())),
-            ("v".to_string(), JsonValue::Boolean(true)),
-        ]));
-        let kv = KvsValue::from(jv);
-        assert_eq!(kv, KvsValue::Boolean(true));
+    /// Write a quoted string, escaped like TinyJSON does.
+    fn write_string(s: &str, out: &mut dyn Write) -> Result<(), ErrorCode> {
+        out.write_all(b"\"")?;
+        let mut start = 0;
+        for (i, c) in s.char_indices() {
+            let escaped = match c {
+                '"' => Some("\\\""),
+                '\\' => Some("\\\\"),
+                '\u{08}' => Some("\\b"),
+                '\t' => Some("\\t"),
+                '\n' => Some("\\n"),
+                '\u{0c}' => Some("\\f"),
+                '\r' => Some("\\r"),
+                // Other control characters.
+                c if (c as u32) < 0x20 => None,
+                _ => continue,
+            };
+            out.write_all(&s.as_bytes()[start..i])?;
+            match escaped {
+                Some(escaped) => out.write_all(escaped.as_bytes())?,
+                None => write!(out, "\\u{:04x}", c as u32)?,
+            }
+            start = i + c.len_utf8();
+        }
+        out.write_all(&s.as_bytes()[start..])?;
+        out.write_all(b"\"")?;
+        Ok(())
     }
 
-    #[test]
-    fn test_bool_invalid_type() {
-        let jv = JsonValue::from(HashMap::from([
-            ("t".to_string(), JsonValue::String("bool".to_string())),
-            ("v".to_string(), JsonValue::String("true".to_string())),
-        ]));
-        let kv = KvsValue::from(jv);
-        assert_eq!(kv, KvsValue::Null);
+    /// Write the content of a KVS file with given compression.
+    ///
+    /// # Return Values
+    ///   * Ok: Hash of the uncompressed content
+    ///   * Err: Content couldn't be generated or written
+    fn write_compressed(
+        kvs_map: &KvsMap,
+        float_format: &FloatFormat,
+        compression: KvsCompression,
+        out: &mut dyn Write,
+    ) -> Result<u32, ErrorCode> {
+        match compression {
+            KvsCompression::None => Self::write_hashed(kvs_map, float_format, out),
+            #[cfg(feature = "gzip")]
+            KvsCompression::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(out, flate2::Compression::default());
+                let hash = Self::write_hashed(kvs_map, float_format, &mut encoder)?;
+                encoder.finish()?;
+                Ok(hash)
+            }
+            #[cfg(feature = "zstd")]
+            KvsCompression::Zstd => {
+                let mut encoder = zstd::stream::write::Encoder::new(out, 0)?;
+                let hash = Self::write_hashed(kvs_map, float_format, &mut encoder)?;
+                encoder.finish()?;
+                Ok(hash)
+            }
+        }
     }
 
-    #[test]
-    fn test_string_ok() {
-        let jv = JsonValue::from(HashMap::from([
-            ("t".to_string(), JsonValue::String("str".to_string())),
-            ("v".to_string(), JsonValue::String("example".to_string())),
-        ]));
-        let kv = KvsValue::from(jv);
-        assert_eq!(kv, KvsValue::String("example".to_string()));
-    }
+    /// Write the content of a KVS file, computing its hash on the way.
+    fn write_hashed(
+        kvs_map: &KvsMap,
+        float_format: &FloatFormat,
+        out: &mut dyn Write,
+    ) -> Result<u32, ErrorCode> {
+        let mut out = HashingWriter {
+            inner: out,
+            hash: RollingAdler32::new(),
+        };
+        Self::write_root(kvs_map, float_format, &mut out)?;
+        Ok(out.hash.hash())
+    }
+
+    fn format_float(n: f64, float_format: &FloatFormat) -> Result<String, ErrorCode> {
+        if !n.is_finite() {
+            kvs_error!("JSON cannot represent {n}");
+            return Err(ErrorCode::JsonGeneratorError);
+        }
 
-    #[test]
-    fn test_string_invalid_type() {
-        let jv = JsonValue::from(HashMap::from([
-            ("t".to_string(), JsonValue::String("str".to_string())),
-            ("v".to_string(), JsonValue::Number(123.4)),
-        ]));
-        let kv = KvsValue::from(jv);
-        assert_eq!(kv, KvsValue::Null);
+        Ok(match float_format {
+            FloatFormat::Plain => format!("{n}"),
+            FloatFormat::Shortest => {
+                let plain = format!("{n}");
+                let scientific = format!("{n:e}");
+                if scientific.len() < plain.len() {
+                    scientific
+                } else {
+                    plain
+                }
+            }
+            FloatFormat::MaxDecimals(decimals) => {
+                let fixed = format!("{n:.*}", *decimals as usize);
+                let trimmed = if fixed.contains('.') {
+                    fixed.trim_end_matches('0').trim_end_matches('.')
+                } else {
+                    &fixed
+                };
+                // Avoid negative zero after rounding.
+                if trimmed == "-0" {
+                    "0".to_string()
+                } else {
+                    trimmed.to_string()
+                }
+            }
+        })
     }
+}
 
-    #[test]
-    fn test_null_ok() {
-        let jv = JsonValue::from(HashMap::from([
-            ("t".to_string(), JsonValue::String("null".to_string())),
-            ("v".to_string(), JsonValue::Null),
-        ]));
-        let kv = KvsValue::from(jv);
-        assert_eq!(kv, KvsValue::Null);
+impl<Fs: KvsFs> KvsBackend for GenericJsonBackend<Fs> {
+    fn load_kvs(kvs_path: &Path, hash_path: Option<&PathBuf>) -> Result<KvsMap, ErrorCode> {
+        Self::load_kvs_with_policy(kvs_path, hash_path, DuplicateKeyPolicy::default())
     }
 
-    #[test]
-    fn test_null_invalid_type() {
-        let jv = JsonValue::from(HashMap::from([
-            ("t".to_string(), JsonValue::String("null".to_string())),
-            ("v".to_string(), JsonValue::Number(123.4)),
-        ]));
-        let kv = KvsValue::from(jv);
-        assert_eq!(kv, KvsValue::Null);
-    }
+    fn load_kvs_with_policy(
+        kvs_path: &Path,
+        hash_path: Option<&PathBuf>,
+        duplicate_keys: DuplicateKeyPolicy,
+    ) -> Result<KvsMap, ErrorCode> {
+        if !check_extension(kvs_path, "json") {
+            return Err(ErrorCode::KvsFileReadError);
+        }
+        if hash_path.is_some_and(|p| !check_extension(p, "hash")) {
+            return Err(ErrorCode::KvsHashFileReadError);
+        }
 
-    #[test]
-    fn test_array_ok() {
-        let entry1 = JsonValue::from(HashMap::from([
-            ("t".to_string(), JsonValue::String("i32".to_string())),
-            ("v".to_string(), JsonValue::Number(-123.0)),
-        ]));
-        let entry2 = JsonValue::from(HashMap::from([
-            ("t".to_string(), JsonValue::String("f64".to_string())),
-            ("v".to_string(), JsonValue::Number(555.5)),
-        ]));
-        let jv = JsonValue::from(HashMap::from([
-            ("t".to_string(), JsonValue::String("arr".to_string())),
-            ("v".to_string(), JsonValue::Array(vec![entry1, entry2])),
-        ]));
-        let kv = KvsValue::from(jv);
-        assert_eq!(
-            kv,
-            KvsValue::Array(vec![KvsValue::I32(-123), KvsValue::F64(555.5)])
-        );
-    }
+        // Load KVS file and parse from string to `JsonValue`.
+        let json_str = Self::read(kvs_path)?;
+        let json_value = Self::parse(&json_str)?;
 
-    #[test]
-    fn test_array_invalid_type() {
-        let jv = JsonValue::from(HashMap::from([
-            ("t".to_string(), JsonValue::String("arr".to_string())),
-            ("v".to_string(), JsonValue::String("example".to_string())),
-        ]));
-        let kv = KvsValue::from(jv);
-        assert_eq!(kv, KvsValue::Null);
-    }
+        // Perform hash check.
+        if let Some(hash_path) = hash_path {
+            validate_hash::<Fs>(json_str.as_bytes(), hash_path)?;
+        }
 
-    #[test]
-    fn test_object_ok() {
-        let entry1 = JsonValue::from(HashMap::from([
-            ("t".to_string(), JsonValue::String("i32".to_string())),
-            ("v".to_string(), JsonValue::Number(-123.0)),
-        ]));
-        let entry2 = JsonValue::from(HashMap::from([
-            ("t".to_string(), JsonValue::String("f64".to_string())),
-            ("v".to_string(), JsonValue::Number(555.5)),
-        ]));
-        let jv = JsonValue::from(HashMap::from([
-            ("t".to_string(), JsonValue::String("obj".to_string())),
-            (
-                "v".to_string(),
-                JsonValue::Object(HashMap::from([
-                    ("entry1".to_string(), entry1.clone()),
-                    ("entry2".to_string(), entry2.clone()),
-                ])),
-            ),
-        ]));
-        let kv = KvsValue::from(jv);
-        assert_eq!(
-            kv,
-            KvsValue::Object(KvsMap::from([
-                ("entry1".to_string(), KvsValue::from(entry1)),
-                ("entry2".to_string(), KvsValue::from(entry2))
-            ]))
-        );
+        Self::kvs_map_from_parsed(json_str, json_value, duplicate_keys)
     }
 
-    #[test]
-    fn test_object_invalid_type() {
-        let jv = JsonValue::from(HashMap::from([
-            ("t".to_string(), JsonValue::String("obj".to_string())),
-            ("v".to_string(), JsonValue::String("example".to_string())),
-        ]));
-        let kv = KvsValue::from(jv);
-        assert_eq!(kv, KvsValue::Null);
+    fn load_kvs_from_bytes(bytes: &[u8]) -> Result<KvsMap, ErrorCode> {
+        let json_str = String::from_utf8(bytes.to_vec())?;
+        Self::kvs_map_from_str(&json_str, DuplicateKeyPolicy::default())
     }
 
-    #[test]
-    fn test_non_json_value_object() {
-        let jv = JsonValue::Number(123.0);
-        let kv = KvsValue::from(jv);
-        assert_eq!(kv, KvsValue::Null);
-    }
-}
+    fn load_kvs_lazy(
+        kvs_path: &Path,
+        hash_path: Option<&PathBuf>,
+        _duplicate_keys: DuplicateKeyPolicy,
+    ) -> Result<Option<LazyKvsMap>, ErrorCode> {
+        if !check_extension(kvs_path, "json") {
+            return Err(ErrorCode::KvsFileReadError);
+        }
+        if hash_path.is_some_and(|p| !check_extension(p, "hash")) {
+            return Err(ErrorCode::KvsHashFileReadError);
+        }
 
-#[cfg(test)]
-mod kvs_value_to_json_value_conversion_tests {
-    use crate::kvs_value::{KvsMap, KvsValue};
-    use std::collections::HashMap;
-    use tinyjson::JsonValue;
+        let json_str = Self::read(kvs_path)?;
+        if let Some(hash_path) = hash_path {
+            validate_hash::<Fs>(json_str.as_bytes(), hash_path)?;
+        }
 
-    #[test]
-    fn test_i32_ok() {
-        let kv = KvsValue::I32(-123);
-        let jv = JsonValue::from(kv);
+        // Files with duplicate keys or written in an older format are loaded eagerly.
+        let Some(root) = DuplicateKeyScanner::members(&json_str)? else {
+            return Ok(None);
+        };
+        let version = match root.get(VERSION_FIELD) {
+            Some(span) => Self::format_version(&JsonValue::Object(HashMap::from([(
+                VERSION_FIELD.to_string(),
+                Self::parse(&json_str[span.clone()])?,
+            )])))?,
+            None => 1,
+        };
+        if version != KVS_FORMAT_VERSION {
+            return Ok(None);
+        }
 
-        assert_eq!(
-            jv,
-            JsonValue::Object(HashMap::from([
-                ("t".to_string(), JsonValue::String("i32".to_string())),
-                ("v".to_string(), JsonValue::Number(-123.0))
-            ]))
-        );
+        // Values are members of the tagged root object.
+        let (Some(tag), Some(map)) = (root.get("t"), root.get("v")) else {
+            return Ok(None);
+        };
+        if Self::parse(&json_str[tag.clone()])? != JsonValue::String("obj".to_string()) {
+            return Ok(None);
+        }
+        let Some(spans) = DuplicateKeyScanner::members(&json_str[map.clone()])? else {
+            return Ok(None);
+        };
+        let spans = spans
+            .into_iter()
+            .map(|(key, span)| (key, map.start + span.start..map.start + span.end))
+            .collect();
+        Ok(Some(LazyKvsMap::new(json_str, spans, |s| {
+            Self::parse(s).map(KvsValue::from)
+        })))
     }
 
-    #[test]
-    fn test_u32_ok() {
-        let kv = KvsValue::U32(123);
-        let jv = JsonValue::from(kv);
+    fn save_kvs(
+        kvs_map: &KvsMap,
+        kvs_path: &Path,
+        hash_path: Option<&PathBuf>,
+    ) -> Result<(), ErrorCode> {
+        Self::save_kvs_formatted(kvs_map, kvs_path, hash_path, &FloatFormat::Plain)
+    }
 
-        assert_eq!(
-            jv,
-            JsonValue::Object(HashMap::from([
-                ("t".to_string(), JsonValue::String("u32".to_string())),
-                ("v".to_string(), JsonValue::Number(123.0))
-            ]))
-        );
+    fn save_kvs_formatted(
+        kvs_map: &KvsMap,
+        kvs_path: &Path,
+        hash_path: Option<&PathBuf>,
+        float_format: &FloatFormat,
+    ) -> Result<(), ErrorCode> {
+        Self::save_kvs_compressed(
+            kvs_map,
+            kvs_path,
+            hash_path,
+            float_format,
+            KvsCompression::None,
+        )
     }
 
-    #[test]
-    fn test_i64_ok() {
-        let kv = KvsValue::I64(-123);
-        let jv = JsonValue::from(kv);
+    fn save_kvs_compressed(
+        kvs_map: &KvsMap,
+        kvs_path: &Path,
+        hash_path: Option<&PathBuf>,
+        float_format: &FloatFormat,
+        compression: KvsCompression,
+    ) -> Result<(), ErrorCode> {
+        // Validate extensions.
+        if !check_extension(kvs_path, "json") {
+            return Err(ErrorCode::KvsFileReadError);
+        }
+        if hash_path.is_some_and(|p| !check_extension(p, "hash")) {
+            return Err(ErrorCode::KvsHashFileReadError);
+        }
 
-        assert_eq!(
-            jv,
-            JsonValue::Object(HashMap::from([
-                ("t".to_string(), JsonValue::String("i64".to_string())),
-                ("v".to_string(), JsonValue::Number(-123.0)),
-            ]))
-        );
-    }
+        // Stream the content to the KVS file, hashing it on the way.
+        let stored_path = compressed_path(kvs_path, compression);
+        let mut hash = 0;
+        Fs::write_with(&stored_path, |out| {
+            hash = Self::write_compressed(kvs_map, float_format, compression, out)?;
+            Ok::<(), ErrorCode>(())
+        })
+        .map_err(|e| e.with_path(&stored_path))?;
+        remove_stored::<Fs>(kvs_path, Some(compression))?;
 
-    #[test]
-    fn test_u64_ok() {
-        let kv = KvsValue::U64(123);
-        let jv = JsonValue::from(kv);
+        if let Some(hash_path) = hash_path {
+            write_hash_value::<Fs>(hash, hash_path)?;
+        }
 
-        assert_eq!(
-            jv,
-            JsonValue::Object(HashMap::from([
-                ("t".to_string(), JsonValue::String("u64".to_string())),
-                ("v".to_string(), JsonValue::Number(123.0))
-            ]))
-        );
+        Ok(())
     }
 
-    #[test]
-    fn test_f64_ok() {
-        let kv = KvsValue::F64(-432.1);
-        let jv = JsonValue::from(kv);
+    fn serialized_size(kvs_map: &KvsMap, float_format: &FloatFormat) -> Result<usize, ErrorCode> {
+        let mut out = CountingWriter(0);
+        Self::write_root(kvs_map, float_format, &mut out)?;
+        Ok(out.0)
+    }
 
-        assert_eq!(
-            jv,
-            JsonValue::Object(HashMap::from([
-                ("t".to_string(), JsonValue::String("f64".to_string())),
-                ("v".to_string(), JsonValue::Number(-432.1)),
-            ]))
-        );
+    fn exists(path: &Path) -> bool {
+        find_stored::<Fs>(path).is_some()
     }
 
-    #[test]
-    fn test_bool_ok() {
-        let kv = KvsValue::Boolean(true);
-        let jv = JsonValue::from(kv);
+    fn modified(path: &Path) -> Option<SystemTime> {
+        let (stored_path, _) = find_stored::<Fs>(path)?;
+        Fs::modified(&stored_path).ok()
+    }
 
-        assert_eq!(
-            jv,
-            JsonValue::Object(HashMap::from([
-                ("t".to_string(), JsonValue::String("bool".to_string())),
-                ("v".to_string(), JsonValue::Boolean(true)),
-            ]))
-        );
+    fn locks_working_dir() -> bool {
+        Fs::locks_working_dir()
     }
 
-    #[test]
-    fn test_string_ok() {
-        let kv = KvsValue::String("example".to_string());
-        let jv = JsonValue::from(kv);
+    fn sync_kvs(
+        kvs_path: &Path,
+        hash_path: &Path,
+        sync_policy: SyncPolicy,
+    ) -> Result<(), ErrorCode> {
+        let stored_path =
+            find_stored::<Fs>(kvs_path).map_or(kvs_path.to_path_buf(), |(path, _)| path);
+        sync_files::<Fs>(&[&stored_path, hash_path], sync_policy)
+    }
 
-        assert_eq!(
-            jv,
-            JsonValue::Object(HashMap::from([
-                ("t".to_string(), JsonValue::String("str".to_string())),
-                ("v".to_string(), JsonValue::String("example".to_string())),
-            ]))
-        );
+    fn move_kvs(
+        old_kvs_path: &Path,
+        old_hash_path: &Path,
+        new_kvs_path: &Path,
+        new_hash_path: &Path,
+    ) -> Result<(), ErrorCode> {
+        match (find_stored::<Fs>(old_kvs_path), Fs::exists(old_hash_path)) {
+            (Some((stored_path, compression)), true) => {
+                Fs::rename(old_hash_path, new_hash_path)?;
+                Fs::rename(&stored_path, &compressed_path(new_kvs_path, compression))?;
+                remove_stored::<Fs>(new_kvs_path, Some(compression))
+            }
+            (None, false) => Ok(()),
+            // Either snapshot or hash file got removed.
+            _ => Err(ErrorCode::IntegrityCorrupted),
+        }
     }
 
-    #[test]
-    fn test_null_ok() {
-        let kv = KvsValue::Null;
-        let jv = JsonValue::from(kv);
-
-        assert_eq!(
-            jv,
-            JsonValue::Object(HashMap::from([
-                ("t".to_string(), JsonValue::String("null".to_string())),
-                ("v".to_string(), JsonValue::Null),
-            ]))
-        );
+    fn remove_kvs(kvs_path: &Path, hash_path: &Path) -> Result<(), ErrorCode> {
+        match Fs::remove_file(hash_path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+        remove_stored::<Fs>(kvs_path, None)
     }
+}
 
-    #[test]
-    fn test_array_ok() {
-        let kv = KvsValue::Array(vec![KvsValue::I32(-123), KvsValue::F64(555.5)]);
-        let jv = JsonValue::from(kv);
+/// KVS backend path resolver for `GenericJsonBackend`.
+impl<Fs: KvsFs> KvsPathResolver for GenericJsonBackend<Fs> {
+    fn kvs_file_name(instance_id: InstanceId, snapshot_id: SnapshotId) -> String {
+        format!("kvs_{instance_id}_{snapshot_id}.json")
+    }
 
-        let exp_entry1 = JsonValue::from(HashMap::from([
-            ("t".to_string(), JsonValue::String("i32".to_string())),
-            ("v".to_string(), JsonValue::Number(-123.0)),
-        ]));
-        let exp_entry2 = JsonValue::from(HashMap::from([
-            ("t".to_string(), JsonValue::String("f64".to_string())),
-            ("v".to_string(), JsonValue::Number(555.5)),
-        ]));
-        let exp_jv = JsonValue::from(HashMap::from([
-            ("t".to_string(), JsonValue::String("arr".to_string())),
-            (
-                "v".to_string(),
-                JsonValue::Array(vec![exp_entry1, exp_entry2]),
-            ),
-        ]));
-        assert_eq!(jv, exp_jv);
+    fn kvs_file_path(
+        working_dir: &Path,
+        instance_id: InstanceId,
+        snapshot_id: SnapshotId,
+    ) -> PathBuf {
+        working_dir.join(Self::kvs_file_name(instance_id, snapshot_id))
     }
 
-    #[test]
-    fn test_object_ok() {
-        let entry1 = JsonValue::from(HashMap::from([
-            ("t".to_string(), JsonValue::String("i32".to_string())),
-            ("v".to_string(), JsonValue::Number(-123.0)),
-        ]));
-        let entry2 = JsonValue::from(HashMap::from([
-            ("t".to_string(), JsonValue::String("f64".to_string())),
-            ("v".to_string(), JsonValue::Number(555.5)),
-        ]));
+    fn hash_file_name(instance_id: InstanceId, snapshot_id: SnapshotId) -> String {
+        format!("kvs_{instance_id}_{snapshot_id}.hash")
+    }
 
-        let kv = KvsValue::Object(KvsMap::from([
-            ("entry1".to_string(), KvsValue::from(entry1.clone())),
-            ("entry2".to_string(), KvsValue::from(entry2.clone())),
-        ]));
-        let jv = JsonValue::from(kv);
+    fn hash_file_path(
+        working_dir: &Path,
+        instance_id: InstanceId,
+        snapshot_id: SnapshotId,
+    ) -> PathBuf {
+        working_dir.join(Self::hash_file_name(instance_id, snapshot_id))
+    }
 
-        let exp_jv = JsonValue::from(HashMap::from([
-            ("t".to_string(), JsonValue::String("obj".to_string())),
-            (
-                "v".to_string(),
-                JsonValue::Object(HashMap::from([
-                    ("entry1".to_string(), entry1),
-                    ("entry2".to_string(), entry2),
-                ])),
-            ),
-        ]));
-        assert_eq!(jv, exp_jv);
+    fn defaults_file_name(instance_id: InstanceId) -> String {
+        format!("kvs_{instance_id}_default.json")
     }
-}
 
-#[cfg(test)]
-mod error_code_tests {
-    use crate::error_code::ErrorCode;
-    use tinyjson::JsonValue;
+    fn defaults_file_path(working_dir: &Path, instance_id: InstanceId) -> PathBuf {
+        working_dir.join(Self::defaults_file_name(instance_id))
+    }
 
-    #[test]
-    fn test_from_json_parse_error_to_json_parser_error() {
-        let error = tinyjson::JsonParser::new("[1, 2, 3".chars())
-            .parse()
-            .unwrap_err();
-        assert_eq!(ErrorCode::from(error), ErrorCode::JsonParserError);
+    fn global_defaults_file_name() -> String {
+        "kvs_global_default.json".to_string()
     }
 
-    #[test]
-    fn test_from_json_generate_error_to_json_generate_error() {
-        let data: JsonValue = JsonValue::Number(f64::INFINITY);
-        let error = data.stringify().unwrap_err();
-        assert_eq!(ErrorCode::from(error), ErrorCode::JsonGeneratorError);
+    fn global_defaults_file_path(working_dir: &Path) -> PathBuf {
+        working_dir.join(Self::global_defaults_file_name())
     }
 }
 
@@ -723,9 +941,15 @@ mod error_code_tests {
 mod backend_tests {
     use crate::error_code::ErrorCode;
     use crate::json_backend::JsonBackend;
+    use crate::kvs_api::{DuplicateKeyPolicy, FloatFormat};
     use crate::kvs_backend::KvsBackend;
+    use crate::kvs_migration::kvs_migration_tests::lock_and_clear;
+    use crate::kvs_migration::{
+        clear_migrations, register_migration, KvsMigration, KVS_FORMAT_VERSION, VERSION_FIELD,
+    };
     use crate::kvs_value::{KvsMap, KvsValue};
     use std::path::{Path, PathBuf};
+    use std::sync::Arc;
     use tempfile::tempdir;
 
     fn create_kvs_files(working_dir: &Path) -> (PathBuf, PathBuf) {
@@ -916,6 +1140,524 @@ mod backend_tests {
         assert!(JsonBackend::save_kvs(&kvs_map, &kvs_path, None)
             .is_err_and(|e| e == ErrorCode::JsonGeneratorError));
     }
+
+    fn write_duplicates(working_dir: &Path) -> PathBuf {
+        let kvs_path = working_dir.join("kvs.json");
+        std::fs::write(
+            &kvs_path,
+            r#"{"t":"obj","v":{
+                "k1": {"t":"i32","v":1},
+                "k2": {"t":"arr","v":[{"t":"obj","v":{"a":{"t":"str","v":"x,}"},"a":{"t":"null","v":null}}}]},
+                "k\u0031": {"t":"i32","v":2}
+            }}"#,
+        )
+        .unwrap();
+        kvs_path
+    }
+
+    #[test]
+    fn test_load_kvs_duplicate_keys_reject() {
+        let dir = tempdir().unwrap();
+        let kvs_path = write_duplicates(dir.path());
+        assert!(
+            JsonBackend::load_kvs_with_policy(&kvs_path, None, DuplicateKeyPolicy::Reject)
+                .is_err_and(|e| e == ErrorCode::JsonParserError)
+        );
+    }
+
+    #[test]
+    fn test_load_kvs_duplicate_keys_first_wins() {
+        let dir = tempdir().unwrap();
+        let kvs_path = write_duplicates(dir.path());
+        let kvs_map =
+            JsonBackend::load_kvs_with_policy(&kvs_path, None, DuplicateKeyPolicy::FirstWins)
+                .unwrap();
+        assert_eq!(kvs_map["k1"], KvsValue::I32(1));
+        assert_eq!(
+            kvs_map["k2"],
+            KvsValue::from(vec![KvsValue::from(KvsMap::from([(
+                "a".to_string(),
+                KvsValue::from("x,}")
+            )]))])
+        );
+    }
+
+    #[test]
+    fn test_load_kvs_duplicate_keys_last_wins() {
+        let dir = tempdir().unwrap();
+        let kvs_path = write_duplicates(dir.path());
+        let kvs_map = JsonBackend::load_kvs_with_policy(
+            &kvs_path,
+            None,
+            DuplicateKeyPolicy::LastWinsWithWarning,
+        )
+        .unwrap();
+        assert_eq!(kvs_map["k1"], KvsValue::I32(2));
+        assert_eq!(
+            kvs_map["k2"],
+            KvsValue::from(vec![KvsValue::from(KvsMap::from([(
+                "a".to_string(),
+                KvsValue::Null
+            )]))])
+        );
+    }
+
+    #[test]
+    fn test_load_kvs_duplicate_keys_reject_unique() {
+        let dir = tempdir().unwrap();
+        let (kvs_path, hash_path) = create_kvs_files(dir.path());
+        let kvs_map = JsonBackend::load_kvs_with_policy(
+            &kvs_path,
+            Some(&hash_path),
+            DuplicateKeyPolicy::Reject,
+        )
+        .unwrap();
+        assert_eq!(kvs_map.len(), 3);
+    }
+
+    #[test]
+    fn test_load_kvs_lazy() {
+        let dir = tempdir().unwrap();
+        let (kvs_path, hash_path) = create_kvs_files(dir.path());
+        let mut lazy =
+            JsonBackend::load_kvs_lazy(&kvs_path, Some(&hash_path), DuplicateKeyPolicy::Reject)
+                .unwrap()
+                .unwrap();
+        assert_eq!(lazy.len(), 3);
+        assert!(!lazy.contains_key(VERSION_FIELD));
+        assert_eq!(
+            lazy.take_all().unwrap(),
+            JsonBackend::load_kvs(&kvs_path, Some(&hash_path)).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_load_kvs_lazy_eager_fallback() {
+        let dir = tempdir().unwrap();
+        let kvs_path = write_duplicates(dir.path());
+        assert!(
+            JsonBackend::load_kvs_lazy(&kvs_path, None, DuplicateKeyPolicy::FirstWins)
+                .unwrap()
+                .is_none()
+        );
+
+        // Format version 1 without version field.
+        std::fs::write(&kvs_path, r#"{"t":"obj","v":{"k":{"t":"i32","v":1}}}"#).unwrap();
+        assert!(
+            JsonBackend::load_kvs_lazy(&kvs_path, None, DuplicateKeyPolicy::default())
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    fn save_formatted(working_dir: &Path, value: KvsValue, float_format: FloatFormat) -> String {
+        let kvs_map = KvsMap::from([("k".to_string(), value)]);
+        let kvs_path = working_dir.join("kvs.json");
+        JsonBackend::save_kvs_formatted(&kvs_map, &kvs_path, None, &float_format).unwrap();
+        std::fs::read_to_string(kvs_path).unwrap()
+    }
+
+    #[test]
+    fn test_save_kvs_formatted_plain() {
+        let dir = tempdir().unwrap();
+        let json_str = save_formatted(dir.path(), KvsValue::F64(1e21), FloatFormat::Plain);
+        assert!(json_str.contains("1000000000000000000000"));
+    }
+
+    #[test]
+    fn test_save_kvs_formatted_shortest() {
+        let dir = tempdir().unwrap();
+        let json_str = save_formatted(dir.path(), KvsValue::F64(1e21), FloatFormat::Shortest);
+        assert!(json_str.contains("1e21"));
+
+        let json_str = save_formatted(dir.path(), KvsValue::F64(0.5), FloatFormat::Shortest);
+        assert!(json_str.contains("0.5"));
+        assert!(!json_str.contains("5e-1"));
+    }
+
+    #[test]
+    fn test_save_kvs_formatted_max_decimals() {
+        let dir = tempdir().unwrap();
+        let json_str = save_formatted(
+            dir.path(),
+            KvsValue::F64(1.0 / 3.0),
+            FloatFormat::MaxDecimals(3),
+        );
+        assert!(json_str.contains("0.333"));
+        assert!(!json_str.contains("0.3333"));
+
+        let json_str = save_formatted(dir.path(), KvsValue::F64(2.5), FloatFormat::MaxDecimals(3));
+        assert!(json_str.contains(":2.5"));
+
+        let json_str = save_formatted(
+            dir.path(),
+            KvsValue::F64(-0.0001),
+            FloatFormat::MaxDecimals(2),
+        );
+        assert!(json_str.contains(":0"));
+        assert!(!json_str.contains("-0"));
+    }
+
+    #[test]
+    fn test_serialized_size_formatted() {
+        let dir = tempdir().unwrap();
+        let kvs_map = KvsMap::from([("k".to_string(), KvsValue::F64(0.1))]);
+        for float_format in [FloatFormat::Plain, FloatFormat::MaxDecimals(20)] {
+            let json_str = save_formatted(dir.path(), KvsValue::F64(0.1), float_format.clone());
+            assert_eq!(
+                JsonBackend::serialized_size(&kvs_map, &float_format).unwrap(),
+                json_str.len()
+            );
+        }
+    }
+
+    #[test]
+    fn test_save_kvs_streamed() {
+        let dir = tempdir().unwrap();
+        let kvs_path = dir.path().join("kvs.json");
+        let hash_path = dir.path().join("kvs.hash");
+        let kvs_map = KvsMap::from([
+            (
+                "quote\"\\\u{1}".to_string(),
+                KvsValue::from("tab\tline\nü\u{1f}"),
+            ),
+            ("i64".to_string(), KvsValue::I64(i64::MIN)),
+            (
+                "nested".to_string(),
+                KvsValue::from(vec![KvsValue::Null, KvsValue::U32(7)]),
+            ),
+        ]);
+        JsonBackend::save_kvs(&kvs_map, &kvs_path, Some(&hash_path)).unwrap();
+
+        // Streamed content is valid JSON, hashed as written.
+        let json_str = std::fs::read_to_string(&kvs_path).unwrap();
+        assert!(json_str.parse::<tinyjson::JsonValue>().is_ok());
+        assert!(json_str.contains(r#"\u0001"#));
+        assert_eq!(
+            std::fs::read(&hash_path).unwrap(),
+            crate::kvs_storage::kvs_hash(json_str.as_bytes()).to_be_bytes()
+        );
+        assert_eq!(
+            JsonBackend::serialized_size(&kvs_map, &FloatFormat::Plain).unwrap(),
+            json_str.len()
+        );
+        assert_eq!(
+            JsonBackend::load_kvs(&kvs_path, Some(&hash_path)).unwrap(),
+            kvs_map
+        );
+    }
+
+    #[test]
+    fn test_save_kvs_formatted_nested_roundtrip() {
+        let dir = tempdir().unwrap();
+        let value = KvsValue::from(vec![
+            KvsValue::F64(1.23456),
+            KvsValue::I32(1234567),
+            KvsValue::from(KvsMap::from([("f".to_string(), KvsValue::F64(9.87654))])),
+            KvsValue::from("text \"quoted\""),
+        ]);
+        save_formatted(dir.path(), value, FloatFormat::MaxDecimals(2));
+
+        let kvs_map = JsonBackend::load_kvs(&dir.path().join("kvs.json"), None).unwrap();
+        let expected = KvsValue::from(vec![
+            KvsValue::F64(1.23),
+            KvsValue::I32(1234567),
+            KvsValue::from(KvsMap::from([("f".to_string(), KvsValue::F64(9.88))])),
+            KvsValue::from("text \"quoted\""),
+        ]);
+        assert_eq!(kvs_map["k"], expected);
+    }
+
+    #[test]
+    fn test_save_kvs_formatted_impossible_str() {
+        let dir = tempdir().unwrap();
+        let kvs_map = KvsMap::from([("nan".to_string(), KvsValue::from(f64::NAN))]);
+        let kvs_path = dir.path().join("kvs.json");
+        assert!(
+            JsonBackend::save_kvs_formatted(&kvs_map, &kvs_path, None, &FloatFormat::Shortest)
+                .is_err_and(|e| e == ErrorCode::JsonGeneratorError)
+        );
+    }
+
+    #[test]
+    fn test_save_kvs_failure_keeps_file() {
+        let dir = tempdir().unwrap();
+        let kvs_path = dir.path().join("kvs.json");
+        let hash_path = dir.path().join("kvs.hash");
+        let kvs_map = KvsMap::from([("key".to_string(), KvsValue::I32(1))]);
+        JsonBackend::save_kvs(&kvs_map, &kvs_path, Some(&hash_path)).unwrap();
+
+        let nan_map = KvsMap::from([("nan".to_string(), KvsValue::from(f64::NAN))]);
+        assert!(JsonBackend::save_kvs(&nan_map, &kvs_path, Some(&hash_path))
+            .is_err_and(|e| e == ErrorCode::JsonGeneratorError));
+
+        // Previous content and hash are untouched, no temporary file is left.
+        let loaded = JsonBackend::load_kvs(&kvs_path, Some(&hash_path)).unwrap();
+        assert_eq!(loaded.get("key"), Some(&KvsValue::I32(1)));
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_save_kvs_format_version() {
+        let dir = tempdir().unwrap();
+        let (kvs_path, _hash_path) = create_kvs_files(dir.path());
+
+        let json_str = std::fs::read_to_string(&kvs_path).unwrap();
+        assert!(json_str.contains(&format!(r#""__kvs_version":{KVS_FORMAT_VERSION}"#)));
+        assert!(!JsonBackend::load_kvs(&kvs_path, None)
+            .unwrap()
+            .contains_key("__kvs_version"));
+    }
+
+    #[test]
+    fn test_load_kvs_without_format_version() {
+        let dir = tempdir().unwrap();
+        let kvs_path = dir.path().join("kvs.json");
+        std::fs::write(&kvs_path, r#"{"t":"obj","v":{"k":{"t":"i32","v":1}}}"#).unwrap();
+
+        let kvs_map = JsonBackend::load_kvs(&kvs_path, None).unwrap();
+        assert_eq!(kvs_map, KvsMap::from([("k".to_string(), KvsValue::I32(1))]));
+    }
+
+    /// Migration from an untagged version 0 format.
+    struct UntaggedMigration;
+
+    impl KvsMigration for UntaggedMigration {
+        fn source_version(&self) -> u32 {
+            0
+        }
+
+        fn migrate(&self, json: &str) -> Result<String, ErrorCode> {
+            let value = json.replace(r#""__kvs_version":0,"k":1"#, r#""k":{"t":"i32","v":1}"#);
+            Ok(format!(r#"{{"__kvs_version":1,"t":"obj","v":{value}}}"#))
+        }
+    }
+
+    #[test]
+    fn test_load_kvs_migration() {
+        let _lock = lock_and_clear();
+        let dir = tempdir().unwrap();
+        let kvs_path = dir.path().join("kvs.json");
+        let hash_path = dir.path().join("kvs.hash");
+        let json_str = r#"{"__kvs_version":0,"k":1}"#;
+        std::fs::write(&kvs_path, json_str).unwrap();
+        crate::kvs_backend::write_hash::<crate::kvs_fs::StdFs>(json_str.as_bytes(), &hash_path)
+            .unwrap();
+
+        assert!(JsonBackend::load_kvs(&kvs_path, Some(&hash_path))
+            .is_err_and(|e| e == ErrorCode::UnsupportedVersion));
+
+        register_migration(Arc::new(UntaggedMigration));
+        let kvs_map = JsonBackend::load_kvs(&kvs_path, Some(&hash_path)).unwrap();
+        clear_migrations();
+        assert_eq!(kvs_map, KvsMap::from([("k".to_string(), KvsValue::I32(1))]));
+    }
+
+    #[test]
+    fn test_load_kvs_newer_format_version() {
+        let dir = tempdir().unwrap();
+        let kvs_path = dir.path().join("kvs.json");
+        let json_str = format!(
+            r#"{{"__kvs_version":{},"t":"obj","v":{{}}}}"#,
+            KVS_FORMAT_VERSION + 1
+        );
+        std::fs::write(&kvs_path, json_str).unwrap();
+
+        assert!(JsonBackend::load_kvs(&kvs_path, None)
+            .is_err_and(|e| e == ErrorCode::UnsupportedVersion));
+    }
+
+    #[test]
+    fn test_load_kvs_invalid_format_version() {
+        let dir = tempdir().unwrap();
+        let kvs_path = dir.path().join("kvs.json");
+        std::fs::write(&kvs_path, r#"{"__kvs_version":"1","t":"obj","v":{}}"#).unwrap();
+
+        assert!(
+            JsonBackend::load_kvs(&kvs_path, None).is_err_and(|e| e == ErrorCode::JsonParserError)
+        );
+    }
+
+    #[test]
+    fn test_load_kvs_untagged() {
+        let dir = tempdir().unwrap();
+        let kvs_path = dir.path().join("kvs.json");
+        std::fs::write(
+            &kvs_path,
+            r#"{"count":3,"name":"x","flags":[true,null],"nested":{"t":1}}"#,
+        )
+        .unwrap();
+
+        let kvs_map = JsonBackend::load_kvs(&kvs_path, None).unwrap();
+        assert_eq!(kvs_map["count"], KvsValue::F64(3.0));
+        assert_eq!(kvs_map["name"], KvsValue::from("x"));
+        assert_eq!(
+            kvs_map["flags"],
+            KvsValue::Array(vec![KvsValue::Boolean(true), KvsValue::Null])
+        );
+        assert_eq!(
+            kvs_map["nested"],
+            KvsValue::Object(KvsMap::from([("t".to_string(), KvsValue::F64(1.0))]))
+        );
+    }
+
+    #[test]
+    fn test_migrate_untagged() {
+        let dir = tempdir().unwrap();
+        let kvs_path = dir.path().join("kvs.json");
+        let hash_path = dir.path().join("kvs.hash");
+        std::fs::write(&kvs_path, r#"{"count":3,"name":"x"}"#).unwrap();
+
+        assert!(JsonBackend::migrate_untagged(&kvs_path, &hash_path).unwrap());
+        let kvs_map = JsonBackend::load_kvs(&kvs_path, Some(&hash_path)).unwrap();
+        assert_eq!(kvs_map["count"], KvsValue::F64(3.0));
+        assert_eq!(kvs_map["name"], KvsValue::from("x"));
+        assert!(!JsonBackend::migrate_untagged(&kvs_path, &hash_path).unwrap());
+
+        let (kvs_path, hash_path) = create_kvs_files(dir.path());
+        let content = std::fs::read(&kvs_path).unwrap();
+        assert!(!JsonBackend::migrate_untagged(&kvs_path, &hash_path).unwrap());
+        assert_eq!(std::fs::read(&kvs_path).unwrap(), content);
+    }
+}
+
+#[cfg(all(test, feature = "gzip"))]
+mod compression_tests {
+    use crate::error_code::ErrorCode;
+    use crate::json_backend::JsonBackend;
+    use crate::kvs_api::{FloatFormat, KvsCompression};
+    #[cfg(feature = "snapshots")]
+    use crate::kvs_api::{InstanceId, KvsApi, SnapshotId};
+    use crate::kvs_backend::KvsBackend;
+    #[cfg(feature = "snapshots")]
+    use crate::kvs_backend::KvsPathResolver;
+    #[cfg(feature = "snapshots")]
+    use crate::kvs_builder::kvs_builder_tests::lock_and_reset;
+    #[cfg(feature = "snapshots")]
+    use crate::kvs_builder::GenericKvsBuilder;
+    use crate::kvs_value::{KvsMap, KvsValue};
+    use std::path::{Path, PathBuf};
+    use tempfile::tempdir;
+
+    fn kvs_map() -> KvsMap {
+        KvsMap::from([
+            ("k1".to_string(), KvsValue::from("v1".repeat(100))),
+            ("k2".to_string(), KvsValue::U64(u64::MAX)),
+        ])
+    }
+
+    fn save(working_dir: &Path, compression: KvsCompression) -> (PathBuf, PathBuf) {
+        let kvs_path = working_dir.join("kvs.json");
+        let hash_path = working_dir.join("kvs.hash");
+        JsonBackend::save_kvs_compressed(
+            &kvs_map(),
+            &kvs_path,
+            Some(&hash_path),
+            &FloatFormat::Plain,
+            compression,
+        )
+        .unwrap();
+        (kvs_path, hash_path)
+    }
+
+    #[test]
+    fn test_save_load_gzip() {
+        let dir = tempdir().unwrap();
+        let (kvs_path, hash_path) = save(dir.path(), KvsCompression::Gzip);
+
+        let gz_path = dir.path().join("kvs.json.gz");
+        assert!(!kvs_path.exists());
+        assert!(JsonBackend::exists(&kvs_path));
+        assert!(std::fs::metadata(&gz_path).unwrap().len() < 200);
+        assert_eq!(
+            JsonBackend::load_kvs(&kvs_path, Some(&hash_path)).unwrap(),
+            kvs_map()
+        );
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_save_load_zstd() {
+        let dir = tempdir().unwrap();
+        let (kvs_path, hash_path) = save(dir.path(), KvsCompression::Zstd);
+
+        assert!(dir.path().join("kvs.json.zst").exists());
+        assert_eq!(
+            JsonBackend::load_kvs(&kvs_path, Some(&hash_path)).unwrap(),
+            kvs_map()
+        );
+    }
+
+    #[test]
+    fn test_save_replaces_other_compression() {
+        let dir = tempdir().unwrap();
+        save(dir.path(), KvsCompression::Gzip);
+        let (kvs_path, _hash_path) = save(dir.path(), KvsCompression::None);
+
+        assert!(kvs_path.exists());
+        assert!(!dir.path().join("kvs.json.gz").exists());
+    }
+
+    #[test]
+    fn test_load_corrupted() {
+        let dir = tempdir().unwrap();
+        let (kvs_path, hash_path) = save(dir.path(), KvsCompression::Gzip);
+        std::fs::write(dir.path().join("kvs.json.gz"), b"not gzip").unwrap();
+
+        assert!(JsonBackend::load_kvs(&kvs_path, Some(&hash_path))
+            .is_err_and(|e| e == ErrorCode::KvsFileReadError));
+    }
+
+    #[test]
+    fn test_move_kvs_compressed() {
+        let dir = tempdir().unwrap();
+        let (kvs_path, hash_path) = save(dir.path(), KvsCompression::Gzip);
+        let new_kvs_path = dir.path().join("kvs_1.json");
+        let new_hash_path = dir.path().join("kvs_1.hash");
+        std::fs::write(&new_kvs_path, "stale").unwrap();
+
+        JsonBackend::move_kvs(&kvs_path, &hash_path, &new_kvs_path, &new_hash_path).unwrap();
+        assert!(!JsonBackend::exists(&kvs_path));
+        assert!(!new_kvs_path.exists());
+        assert_eq!(
+            JsonBackend::load_kvs(&new_kvs_path, Some(&new_hash_path)).unwrap(),
+            kvs_map()
+        );
+    }
+
+    #[cfg(feature = "snapshots")]
+    #[test]
+    fn test_flush_compressed_snapshots() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+
+        let kvs = GenericKvsBuilder::<JsonBackend>::new(InstanceId(1))
+            .dir(dir_string.clone())
+            .compression(KvsCompression::Gzip)
+            .build()
+            .unwrap();
+        for idx in 0..2 {
+            kvs.set_value("counter", idx).unwrap();
+            kvs.flush().unwrap();
+        }
+        assert_eq!(kvs.snapshot_count(), 2);
+        let snapshot_path = JsonBackend::kvs_file_path(dir.path(), InstanceId(1), SnapshotId(1));
+        assert!(!snapshot_path.exists());
+        kvs.snapshot_restore(SnapshotId(1)).unwrap();
+        assert_eq!(kvs.get_value_as::<i32>("counter").unwrap(), 0);
+        drop(kvs);
+
+        // Compressed files are loaded without configuring compression, the restored snapshot was
+        // flushed on drop.
+        drop(_lock);
+        let _lock = lock_and_reset();
+        let kvs = GenericKvsBuilder::<JsonBackend>::new(InstanceId(1))
+            .dir(dir_string)
+            .build()
+            .unwrap();
+        assert_eq!(kvs.get_value_as::<i32>("counter").unwrap(), 0);
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,637 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Conversion between [`KvsValue`] and the JSON document model of TinyJSON.
+//!
+//! The t-tagged JSON representation is shared by the JSON backend, the journal and the default
+//! [`KvsBackend::serialized_size`](crate::kvs_backend::KvsBackend::serialized_size), so it's
+//! available without the `json-backend` feature.
+
+use crate::error_code::ErrorCode;
+use crate::kvs_log::kvs_error;
+use crate::kvs_value::{KvsMap, KvsValue};
+use std::collections::HashMap;
+use tinyjson::{JsonGenerateError, JsonParseError, JsonValue};
+
+// Example of how KvsValue is stored in the JSON file (t-tagged format):
+// {
+//   "my_int": { "t": "i32", "v": 42 },
+//   "my_long": { "t": "i64", "v": "-9007199254740993" },
+//   "my_float": { "t": "f64", "v": 3.1415 },
+//   "my_bool": { "t": "bool", "v": true },
+//   "my_string": { "t": "str", "v": "hello" },
+//   "my_array": { "t": "arr", "v": [ ... ] },
+//   "my_object": { "t": "obj", "v": { ... } },
+//   "my_null": { "t": "null", "v": null }
+// }
+
+/// Backend-specific JsonValue -> KvsValue conversion.
+impl From<JsonValue> for KvsValue {
+    fn from(val: JsonValue) -> KvsValue {
+        match val {
+            JsonValue::Object(mut obj) => {
+                // Type-tagged: { "t": ..., "v": ... }
+                if let (Some(JsonValue::String(type_str)), Some(value)) =
+                    (obj.remove("t"), obj.remove("v"))
+                {
+                    return match (type_str.as_str(), value) {
+                        ("i32", JsonValue::Number(v)) => KvsValue::I32(v as i32),
+                        ("u32", JsonValue::Number(v)) => KvsValue::U32(v as u32),
+                        // Format version 1 stored 64-bit integers as numbers.
+                        ("i64", JsonValue::Number(v)) => KvsValue::I64(v as i64),
+                        ("u64", JsonValue::Number(v)) => KvsValue::U64(v as u64),
+                        ("i64", JsonValue::String(v)) => {
+                            v.parse().map_or(KvsValue::Null, KvsValue::I64)
+                        }
+                        ("u64", JsonValue::String(v)) => {
+                            v.parse().map_or(KvsValue::Null, KvsValue::U64)
+                        }
+                        ("f64", JsonValue::Number(v)) => KvsValue::F64(v),
+                        ("bool", JsonValue::Boolean(v)) => KvsValue::Boolean(v),
+                        ("str", JsonValue::String(v)) => KvsValue::String(v),
+                        ("null", JsonValue::Null) => KvsValue::Null,
+                        ("arr", JsonValue::Array(v)) => {
+                            KvsValue::Array(v.into_iter().map(KvsValue::from).collect())
+                        }
+                        ("obj", JsonValue::Object(v)) => KvsValue::Object(
+                            v.into_iter().map(|(k, v)| (k, KvsValue::from(v))).collect(),
+                        ),
+                        // Remaining types can be handled with Null.
+                        _ => KvsValue::Null,
+                    };
+                }
+                // If not a t-tagged object, treat as a map of key-value pairs (KvsMap)
+                let map: KvsMap = obj
+                    .into_iter()
+                    .map(|(k, v)| (k, KvsValue::from(v)))
+                    .collect();
+                KvsValue::Object(map)
+            }
+            // Remaining types can be handled with Null.
+            _ => KvsValue::Null,
+        }
+    }
+}
+
+/// Backend-specific KvsValue -> JsonValue conversion.
+impl From<KvsValue> for JsonValue {
+    fn from(val: KvsValue) -> JsonValue {
+        let mut obj = HashMap::new();
+        match val {
+            KvsValue::I32(n) => {
+                obj.insert("t".to_string(), JsonValue::String("i32".to_string()));
+                obj.insert("v".to_string(), JsonValue::Number(n as f64));
+            }
+            KvsValue::U32(n) => {
+                obj.insert("t".to_string(), JsonValue::String("u32".to_string()));
+                obj.insert("v".to_string(), JsonValue::Number(n as f64));
+            }
+            KvsValue::I64(n) => {
+                // Stored as string, `f64` can't represent all values.
+                obj.insert("t".to_string(), JsonValue::String("i64".to_string()));
+                obj.insert("v".to_string(), JsonValue::String(n.to_string()));
+            }
+            KvsValue::U64(n) => {
+                obj.insert("t".to_string(), JsonValue::String("u64".to_string()));
+                obj.insert("v".to_string(), JsonValue::String(n.to_string()));
+            }
+            KvsValue::F64(n) => {
+                obj.insert("t".to_string(), JsonValue::String("f64".to_string()));
+                obj.insert("v".to_string(), JsonValue::Number(n));
+            }
+            KvsValue::Boolean(b) => {
+                obj.insert("t".to_string(), JsonValue::String("bool".to_string()));
+                obj.insert("v".to_string(), JsonValue::Boolean(b));
+            }
+            KvsValue::String(s) => {
+                obj.insert("t".to_string(), JsonValue::String("str".to_string()));
+                obj.insert("v".to_string(), JsonValue::String(s));
+            }
+            KvsValue::Null => {
+                obj.insert("t".to_string(), JsonValue::String("null".to_string()));
+                obj.insert("v".to_string(), JsonValue::Null);
+            }
+            KvsValue::Array(arr) => {
+                obj.insert("t".to_string(), JsonValue::String("arr".to_string()));
+                obj.insert(
+                    "v".to_string(),
+                    JsonValue::Array(arr.into_iter().map(JsonValue::from).collect()),
+                );
+            }
+            KvsValue::Object(map) => {
+                obj.insert("t".to_string(), JsonValue::String("obj".to_string()));
+                obj.insert(
+                    "v".to_string(),
+                    JsonValue::Object(
+                        map.into_iter()
+                            .map(|(k, v)| (k, JsonValue::from(v)))
+                            .collect(),
+                    ),
+                );
+            }
+        }
+        JsonValue::Object(obj)
+    }
+}
+
+/// tinyjson::JsonParseError -> ErrorCode::JsonParseError
+impl From<JsonParseError> for ErrorCode {
+    fn from(cause: JsonParseError) -> Self {
+        kvs_error!(
+            "JSON parser error: line = {}, column = {}",
+            cause.line(),
+            cause.column()
+        );
+        ErrorCode::JsonParserError
+    }
+}
+
+/// tinyjson::JsonGenerateError -> ErrorCode::JsonGenerateError
+impl From<JsonGenerateError> for ErrorCode {
+    fn from(cause: JsonGenerateError) -> Self {
+        kvs_error!("JSON generator error: msg = {}", cause.message());
+        ErrorCode::JsonGeneratorError
+    }
+}
+
+#[cfg(test)]
+mod json_value_to_kvs_value_conversion_tests {
+    use std::collections::HashMap;
+    use tinyjson::JsonValue;
+
+    use crate::prelude::{KvsMap, KvsValue};
+
+    #[test]
+    fn test_i32_ok() {
+        let jv = JsonValue::from(HashMap::from([
+            ("t".to_string(), JsonValue::String("i32".to_string())),
+            ("v".to_string(), JsonValue::Number(-123.0)),
+        ]));
+        let kv = KvsValue::from(jv);
+        assert_eq!(kv, KvsValue::I32(-123));
+    }
+
+    #[test]
+    fn test_i32_invalid_type() {
+        let jv = JsonValue::from(HashMap::from([
+            ("t".to_string(), JsonValue::String("i32".to_string())),
+            ("v".to_string(), JsonValue::String("-123.0".to_string())),
+        ]));
+        let kv = KvsValue::from(jv);
+        assert_eq!(kv, KvsValue::Null);
+    }
+
+    #[test]
+    fn test_u32_ok() {
+        let jv = JsonValue::from(HashMap::from([
+            ("t".to_string(), JsonValue::String("u32".to_string())),
+            ("v".to_string(), JsonValue::Number(123.0)),
+        ]));
+        let kv = KvsValue::from(jv);
+        assert_eq!(kv, KvsValue::U32(123));
+    }
+
+    #[test]
+    fn test_u32_invalid_type() {
+        let jv = JsonValue::from(HashMap::from([
+            ("t".to_string(), JsonValue::String("u32".to_string())),
+            ("v".to_string(), JsonValue::String("123.0".to_string())),
+        ]));
+        let kv = KvsValue::from(jv);
+        assert_eq!(kv, KvsValue::Null);
+    }
+
+    #[test]
+    fn test_i64_ok() {
+        let jv = JsonValue::from(HashMap::from([
+            ("t".to_string(), JsonValue::String("i64".to_string())),
+            ("v".to_string(), JsonValue::Number(-123.0)),
+        ]));
+        let kv = KvsValue::from(jv);
+        assert_eq!(kv, KvsValue::I64(-123));
+    }
+
+    #[test]
+    fn test_i64_string_ok() {
+        let jv = JsonValue::from(HashMap::from([
+            ("t".to_string(), JsonValue::String("i64".to_string())),
+            ("v".to_string(), JsonValue::String(i64::MIN.to_string())),
+        ]));
+        let kv = KvsValue::from(jv);
+        assert_eq!(kv, KvsValue::I64(i64::MIN));
+    }
+
+    #[test]
+    fn test_i64_invalid_type() {
+        let jv = JsonValue::from(HashMap::from([
+            ("t".to_string(), JsonValue::String("i64".to_string())),
+            ("v".to_string(), JsonValue::String("-123.0".to_string())),
+        ]));
+        let kv = KvsValue::from(jv);
+        assert_eq!(kv, KvsValue::Null);
+    }
+
+    #[test]
+    fn test_u64_ok() {
+        let jv = JsonValue::from(HashMap::from([
+            ("t".to_string(), JsonValue::String("u64".to_string())),
+            ("v".to_string(), JsonValue::Number(123.0)),
+        ]));
+        let kv = KvsValue::from(jv);
+        assert_eq!(kv, KvsValue::U64(123));
+    }
+
+    #[test]
+    fn test_u64_string_ok() {
+        let jv = JsonValue::from(HashMap::from([
+            ("t".to_string(), JsonValue::String("u64".to_string())),
+            ("v".to_string(), JsonValue::String(u64::MAX.to_string())),
+        ]));
+        let kv = KvsValue::from(jv);
+        assert_eq!(kv, KvsValue::U64(u64::MAX));
+    }
+
+    #[test]
+    fn test_u64_invalid_type() {
+        let jv = JsonValue::from(HashMap::from([
+            ("t".to_string(), JsonValue::String("u64".to_string())),
+            ("v".to_string(), JsonValue::String("123.0".to_string())),
+        ]));
+        let kv = KvsValue::from(jv);
+        assert_eq!(kv, KvsValue::Null);
+    }
+
+    #[test]
+    fn test_f64_ok() {
+        let jv = JsonValue::from(HashMap::from([
+            ("t".to_string(), JsonValue::String("f64".to_string())),
+            ("v".to_string(), JsonValue::Number(-432.1)),
+        ]));
+        let kv = KvsValue::from(jv);
+        assert_eq!(kv, KvsValue::F64(-432.1));
+    }
+
+    #[test]
+    fn test_f64_invalid_type() {
+        let jv = JsonValue::from(HashMap::from([
+            ("t".to_string(), JsonValue::String("f64".to_string())),
+            ("v".to_string(), JsonValue::String("-432.1".to_string())),
+        ]));
+        let kv = KvsValue::from(jv);
+        assert_eq!(kv, KvsValue::Null);
+    }
+
+    #[test]
+    fn test_bool_ok() {
+        let jv = JsonValue::from(HashMap::from([
+            ("t".to_string(), JsonValue::String("bool".to_string())),
+            ("v".to_string(), JsonValue::Boolean(true)),
+        ]));
+        let kv = KvsValue::from(jv);
+        assert_eq!(kv, KvsValue::Boolean(true));
+    }
+
+    #[test]
+    fn test_bool_invalid_type() {
+        let jv = JsonValue::from(HashMap::from([
+            ("t".to_string(), JsonValue::String("bool".to_string())),
+            ("v".to_string(), JsonValue::String("true".to_string())),
+        ]));
+        let kv = KvsValue::from(jv);
+        assert_eq!(kv, KvsValue::Null);
+    }
+
+    #[test]
+    fn test_string_ok() {
+        let jv = JsonValue::from(HashMap::from([
+            ("t".to_string(), JsonValue::String("str".to_string())),
+            ("v".to_string(), JsonValue::String("example".to_string())),
+        ]));
+        let kv = KvsValue::from(jv);
+        assert_eq!(kv, KvsValue::String("example".to_string()));
+    }
+
+    #[test]
+    fn test_string_invalid_type() {
+        let jv = JsonValue::from(HashMap::from([
+            ("t".to_string(), JsonValue::String("str".to_string())),
+            ("v".to_string(), JsonValue::Number(123.4)),
+        ]));
+        let kv = KvsValue::from(jv);
+        assert_eq!(kv, KvsValue::Null);
+    }
+
+    #[test]
+    fn test_null_ok() {
+        let jv = JsonValue::from(HashMap::from([
+            ("t".to_string(), JsonValue::String("null".to_string())),
+            ("v".to_string(), JsonValue::Null),
+        ]));
+        let kv = KvsValue::from(jv);
+        assert_eq!(kv, KvsValue::Null);
+    }
+
+    #[test]
+    fn test_null_invalid_type() {
+        let jv = JsonValue::from(HashMap::from([
+            ("t".to_string(), JsonValue::String("null".to_string())),
+            ("v".to_string(), JsonValue::Number(123.4)),
+        ]));
+        let kv = KvsValue::from(jv);
+        assert_eq!(kv, KvsValue::Null);
+    }
+
+    #[test]
+    fn test_array_ok() {
+        let entry1 = JsonValue::from(HashMap::from([
+            ("t".to_string(), JsonValue::String("i32".to_string())),
+            ("v".to_string(), JsonValue::Number(-123.0)),
+        ]));
+        let entry2 = JsonValue::from(HashMap::from([
+            ("t".to_string(), JsonValue::String("f64".to_string())),
+            ("v".to_string(), JsonValue::Number(555.5)),
+        ]));
+        let jv = JsonValue::from(HashMap::from([
+            ("t".to_string(), JsonValue::String("arr".to_string())),
+            ("v".to_string(), JsonValue::Array(vec![entry1, entry2])),
+        ]));
+        let kv = KvsValue::from(jv);
+        assert_eq!(
+            kv,
+            KvsValue::Array(vec![KvsValue::I32(-123), KvsValue::F64(555.5)])
+        );
+    }
+
+    #[test]
+    fn test_array_invalid_type() {
+        let jv = JsonValue::from(HashMap::from([
+            ("t".to_string(), JsonValue::String("arr".to_string())),
+            ("v".to_string(), JsonValue::String("example".to_string())),
+        ]));
+        let kv = KvsValue::from(jv);
+        assert_eq!(kv, KvsValue::Null);
+    }
+
+    #[test]
+    fn test_object_ok() {
+        let entry1 = JsonValue::from(HashMap::from([
+            ("t".to_string(), JsonValue::String("i32".to_string())),
+            ("v".to_string(), JsonValue::Number(-123.0)),
+        ]));
+        let entry2 = JsonValue::from(HashMap::from([
+            ("t".to_string(), JsonValue::String("f64".to_string())),
+            ("v".to_string(), JsonValue::Number(555.5)),
+        ]));
+        let jv = JsonValue::from(HashMap::from([
+            ("t".to_string(), JsonValue::String("obj".to_string())),
+            (
+                "v".to_string(),
+                JsonValue::Object(HashMap::from([
+                    ("entry1".to_string(), entry1.clone()),
+                    ("entry2".to_string(), entry2.clone()),
+                ])),
+            ),
+        ]));
+        let kv = KvsValue::from(jv);
+        assert_eq!(
+            kv,
+            KvsValue::Object(KvsMap::from([
+                ("entry1".to_string(), KvsValue::from(entry1)),
+                ("entry2".to_string(), KvsValue::from(entry2))
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_object_invalid_type() {
+        let jv = JsonValue::from(HashMap::from([
+            ("t".to_string(), JsonValue::String("obj".to_string())),
+            ("v".to_string(), JsonValue::String("example".to_string())),
+        ]));
+        let kv = KvsValue::from(jv);
+        assert_eq!(kv, KvsValue::Null);
+    }
+
+    #[test]
+    fn test_non_json_value_object() {
+        let jv = JsonValue::Number(123.0);
+        let kv = KvsValue::from(jv);
+        assert_eq!(kv, KvsValue::Null);
+    }
+}
+
+#[cfg(test)]
+mod kvs_value_to_json_value_conversion_tests {
+    use crate::kvs_value::{KvsMap, KvsValue};
+    use std::collections::HashMap;
+    use tinyjson::JsonValue;
+
+    #[test]
+    fn test_i32_ok() {
+        let kv = KvsValue::I32(-123);
+        let jv = JsonValue::from(kv);
+
+        assert_eq!(
+            jv,
+            JsonValue::Object(HashMap::from([
+                ("t".to_string(), JsonValue::String("i32".to_string())),
+                ("v".to_string(), JsonValue::Number(-123.0))
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_u32_ok() {
+        let kv = KvsValue::U32(123);
+        let jv = JsonValue::from(kv);
+
+        assert_eq!(
+            jv,
+            JsonValue::Object(HashMap::from([
+                ("t".to_string(), JsonValue::String("u32".to_string())),
+                ("v".to_string(), JsonValue::Number(123.0))
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_i64_ok() {
+        let kv = KvsValue::I64(-123);
+        let jv = JsonValue::from(kv);
+
+        assert_eq!(
+            jv,
+            JsonValue::Object(HashMap::from([
+                ("t".to_string(), JsonValue::String("i64".to_string())),
+                ("v".to_string(), JsonValue::String("-123".to_string())),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_u64_ok() {
+        let kv = KvsValue::U64(123);
+        let jv = JsonValue::from(kv);
+
+        assert_eq!(
+            jv,
+            JsonValue::Object(HashMap::from([
+                ("t".to_string(), JsonValue::String("u64".to_string())),
+                ("v".to_string(), JsonValue::String("123".to_string()))
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_64_bit_round_trip() {
+        for kv in [
+            KvsValue::I64(i64::MIN),
+            KvsValue::I64(i64::MAX),
+            KvsValue::I64((1 << 53) + 1),
+            KvsValue::U64(u64::MAX),
+            KvsValue::U64((1 << 53) + 1),
+        ] {
+            let json_str = JsonValue::from(kv.clone()).stringify().unwrap();
+            let jv: JsonValue = json_str.parse().unwrap();
+            assert_eq!(KvsValue::from(jv), kv);
+        }
+    }
+
+    #[test]
+    fn test_f64_ok() {
+        let kv = KvsValue::F64(-432.1);
+        let jv = JsonValue::from(kv);
+
+        assert_eq!(
+            jv,
+            JsonValue::Object(HashMap::from([
+                ("t".to_string(), JsonValue::String("f64".to_string())),
+                ("v".to_string(), JsonValue::Number(-432.1)),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_bool_ok() {
+        let kv = KvsValue::Boolean(true);
+        let jv = JsonValue::from(kv);
+
+        assert_eq!(
+            jv,
+            JsonValue::Object(HashMap::from([
+                ("t".to_string(), JsonValue::String("bool".to_string())),
+                ("v".to_string(), JsonValue::Boolean(true)),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_string_ok() {
+        let kv = KvsValue::String("example".to_string());
+        let jv = JsonValue::from(kv);
+
+        assert_eq!(
+            jv,
+            JsonValue::Object(HashMap::from([
+                ("t".to_string(), JsonValue::String("str".to_string())),
+                ("v".to_string(), JsonValue::String("example".to_string())),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_null_ok() {
+        let kv = KvsValue::Null;
+        let jv = JsonValue::from(kv);
+
+        assert_eq!(
+            jv,
+            JsonValue::Object(HashMap::from([
+                ("t".to_string(), JsonValue::String("null".to_string())),
+                ("v".to_string(), JsonValue::Null),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_array_ok() {
+        let kv = KvsValue::Array(vec![KvsValue::I32(-123), KvsValue::F64(555.5)]);
+        let jv = JsonValue::from(kv);
+
+        let exp_entry1 = JsonValue::from(HashMap::from([
+            ("t".to_string(), JsonValue::String("i32".to_string())),
+            ("v".to_string(), JsonValue::Number(-123.0)),
+        ]));
+        let exp_entry2 = JsonValue::from(HashMap::from([
+            ("t".to_string(), JsonValue::String("f64".to_string())),
+            ("v".to_string(), JsonValue::Number(555.5)),
+        ]));
+        let exp_jv = JsonValue::from(HashMap::from([
+            ("t".to_string(), JsonValue::String("arr".to_string())),
+            (
+                "v".to_string(),
+                JsonValue::Array(vec![exp_entry1, exp_entry2]),
+            ),
+        ]));
+        assert_eq!(jv, exp_jv);
+    }
+
+    #[test]
+    fn test_object_ok() {
+        let entry1 = JsonValue::from(HashMap::from([
+            ("t".to_string(), JsonValue::String("i32".to_string())),
+            ("v".to_string(), JsonValue::Number(-123.0)),
+        ]));
+        let entry2 = JsonValue::from(HashMap::from([
+            ("t".to_string(), JsonValue::String("f64".to_string())),
+            ("v".to_string(), JsonValue::Number(555.5)),
+        ]));
+
+        let kv = KvsValue::Object(KvsMap::from([
+            ("entry1".to_string(), KvsValue::from(entry1.clone())),
+            ("entry2".to_string(), KvsValue::from(entry2.clone())),
+        ]));
+        let jv = JsonValue::from(kv);
+
+        let exp_jv = JsonValue::from(HashMap::from([
+            ("t".to_string(), JsonValue::String("obj".to_string())),
+            (
+                "v".to_string(),
+                JsonValue::Object(HashMap::from([
+                    ("entry1".to_string(), entry1),
+                    ("entry2".to_string(), entry2),
+                ])),
+            ),
+        ]));
+        assert_eq!(jv, exp_jv);
+    }
+}
+
+#[cfg(test)]
+mod error_code_tests {
+    use crate::error_code::ErrorCode;
+    use tinyjson::JsonValue;
+
+    #[test]
+    fn test_from_json_parse_error_to_json_parser_error() {
+        let error = tinyjson::JsonParser::new("[1, 2, 3".chars())
+            .parse()
+            .unwrap_err();
+        assert_eq!(ErrorCode::from(error), ErrorCode::JsonParserError);
+    }
+
+    #[test]
+    fn test_from_json_generate_error_to_json_generate_error() {
+        let data: JsonValue = JsonValue::Number(f64::INFINITY);
+        let error = data.stringify().unwrap_err();
+        assert_eq!(ErrorCode::from(error), ErrorCode::JsonGeneratorError);
+    }
+}
@@ -9,20 +9,60 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
+#[cfg(feature = "defaults")]
+use crate::defaults_watcher::{self, DefaultsWatcher};
+#[cfg(feature = "tooling")]
+use crate::dotenv;
 use crate::error_code::ErrorCode;
-use crate::kvs_api::{InstanceId, KvsApi, KvsDefaults, KvsLoad, SnapshotId};
+use crate::kvs_api::{
+    glob_match, DuplicateKeyPolicy, FloatFormat, FlushOnExit, InstanceId, KeyScope, KvsApi,
+    KvsCompression, KvsDefaults, KvsEmbeddedDefaults, KvsKeyPolicy, KvsLoad, SnapshotId,
+    SyncPolicy,
+};
 use crate::kvs_backend::{KvsBackend, KvsPathResolver};
-use crate::kvs_builder::KvsData;
-use crate::kvs_value::{KvsMap, KvsValue};
-use std::fs;
+use crate::kvs_builder::{self, KvsData};
+use crate::kvs_checked;
+use crate::kvs_clock::KvsSharedClock;
+#[cfg(feature = "tooling")]
+use crate::kvs_discovery::{self, InstanceInfo};
+use crate::kvs_event::{self, KvsEvent};
+use crate::kvs_lock::KvsDirLock;
+use crate::kvs_log::{kvs_debug, kvs_error, kvs_warn};
+use crate::kvs_merge::{self, KvsMergePolicy};
+use crate::kvs_metrics::KvsMetrics;
+use crate::kvs_migration;
+#[cfg(feature = "defaults")]
+use crate::kvs_resolver::KvsFile;
+use crate::kvs_resolver::KvsPathOverride;
+#[cfg(feature = "snapshots")]
+use crate::kvs_retention::{KvsRetention, KvsSnapshotDiscardFn};
+use crate::kvs_transaction::{self, KvsTransaction};
+use crate::kvs_validator::KvsValidator;
+use crate::kvs_value::{
+    self, KvsDeserialize, KvsMap, KvsSerialize, KvsValue, KvsValueKind, ValueModel,
+};
+#[cfg(feature = "tooling")]
+use crate::protobuf::ProtoSchema;
+use std::collections::{HashMap, HashSet};
 use std::marker::PhantomData;
+#[cfg(any(feature = "snapshots", feature = "tooling"))]
+use std::path::Path;
 use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+#[cfg(feature = "defaults")]
+use std::time::Duration;
+use std::time::{Instant, SystemTime};
 
 /// Maximum number of snapshots
 ///
 /// Feature: `FEAT_REQ__KVS__snapshots`
-const KVS_MAX_SNAPSHOTS: usize = 3;
+#[cfg(feature = "snapshots")]
+pub(crate) const KVS_MAX_SNAPSHOTS: usize = 3;
+
+/// Maximum number of snapshots, only the current KVS is stored without feature `snapshots`.
+#[cfg(not(feature = "snapshots"))]
+pub(crate) const KVS_MAX_SNAPSHOTS: usize = 1;
 
 /// KVS instance parameters.
 #[derive(Clone, PartialEq)]
@@ -38,37 +78,1709 @@ pub struct KvsParameters {
 
     /// Working directory.
     pub working_dir: PathBuf,
+
+    /// Per-key access statistics are tracked.
+    pub access_stats: bool,
+
+    /// Rendering of `F64` values in stored files.
+    pub float_format: FloatFormat,
+
+    /// Handling of duplicate keys in loaded files.
+    pub duplicate_keys: DuplicateKeyPolicy,
+
+    /// Maximum serialized size of the instance in bytes, unlimited if `None`.
+    pub max_size: Option<usize>,
+
+    /// Compression of stored KVS files.
+    pub compression: KvsCompression,
+
+    /// Validation of written keys.
+    pub key_policy: KvsKeyPolicy,
+
+    /// Defaults files layered over the instance defaults, later files override earlier ones.
+    pub defaults_files: Vec<PathBuf>,
+
+    /// Handling of changes flushed by other processes.
+    pub merge_policy: KvsMergePolicy,
+
+    /// Values are parsed on first access.
+    pub lazy_load: bool,
+
+    /// Maximum count of keys.
+    pub max_keys: Option<usize>,
+
+    /// Maximum size of a single value in bytes.
+    pub max_value_bytes: Option<usize>,
+
+    /// File paths resolved at runtime, overriding the `KvsPathResolver` type parameter.
+    pub path_override: Option<KvsPathOverride>,
+
+    /// Writes must keep the value type of existing keys.
+    pub strict_types: bool,
+
+    /// Default values used if the instance defaults file doesn't exist.
+    pub embedded_defaults: Option<KvsEmbeddedDefaults>,
+
+    /// New key by legacy key, values stored under legacy keys are moved when loading.
+    pub key_aliases: HashMap<String, String>,
+
+    /// Syncing of stored files on flush.
+    pub sync_policy: SyncPolicy,
+
+    /// Changes are appended to a journal between flushes, see [`kvs_journal`](crate::kvs_journal).
+    pub journal: bool,
+
+    /// Policy discarding snapshots on rotation, `None` keeps all snapshot slots.
+    #[cfg(feature = "snapshots")]
+    pub retention: Option<KvsRetention>,
+
+    /// Publish a read-only view for other processes, requires the `shm-cache` feature.
+    pub shared_view: bool,
+
+    /// Keys whose values are redacted in diagnostics, see [`kvs_redact`](crate::kvs_redact).
+    pub sensitive_keys: HashSet<String>,
+
+    /// Clock of timestamps, `None` uses the system time, see [`kvs_clock`](crate::kvs_clock).
+    pub clock: Option<KvsSharedClock>,
+
+    /// Validators of written values, see [`kvs_validator`](crate::kvs_validator).
+    pub validators: Vec<KvsValidator>,
+}
+
+impl KvsParameters {
+    /// Create parameters with default settings
+    ///
+    /// # Parameters
+    ///   * `instance_id`: Instance ID
+    ///
+    /// # Return Values
+    ///   * Parameters of an unnamed instance in the current working directory
+    pub(crate) fn new(instance_id: InstanceId) -> Self {
+        Self {
+            instance_id,
+            defaults: KvsDefaults::Optional,
+            kvs_load: KvsLoad::Optional,
+            working_dir: PathBuf::new(),
+            access_stats: false,
+            float_format: FloatFormat::default(),
+            duplicate_keys: DuplicateKeyPolicy::default(),
+            max_size: None,
+            compression: KvsCompression::default(),
+            key_policy: KvsKeyPolicy::default(),
+            defaults_files: Vec::new(),
+            merge_policy: KvsMergePolicy::default(),
+            lazy_load: false,
+            max_keys: None,
+            max_value_bytes: None,
+            path_override: None,
+            strict_types: false,
+            embedded_defaults: None,
+            key_aliases: HashMap::new(),
+            sync_policy: SyncPolicy::default(),
+            journal: false,
+            #[cfg(feature = "snapshots")]
+            retention: None,
+            shared_view: false,
+            sensitive_keys: HashSet::new(),
+            clock: None,
+            validators: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl KvsData {
+    /// Create unmodified data without load, flush or recovery state.
+    ///
+    /// # Parameters
+    ///   * `kvs_map`: Storage data
+    ///   * `defaults_map`: Default values
+    pub(crate) fn new(kvs_map: KvsMap, defaults_map: KvsMap) -> Self {
+        Self {
+            kvs_map,
+            defaults_map,
+            dirty: false,
+            access_stats: HashMap::new(),
+            recovery: None,
+            verification: None,
+            autoflush: None,
+            merge_base: None,
+            lazy: None,
+            poison_recoveries: 0,
+            flush_hooks: FlushHooks::default(),
+            last_flush: None,
+            #[cfg(feature = "defaults")]
+            default_changes: HashMap::new(),
+        }
+    }
+}
+
+/// Access statistics of a key.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct KeyAccessStats {
+    /// Number of successful reads, including reads returning the default value.
+    pub reads: u64,
+
+    /// Number of writes and removals.
+    pub writes: u64,
+}
+
+/// Recovery of a corrupted KVS performed when loading.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KvsRecoveryInfo {
+    /// Snapshot the data was loaded from.
+    pub snapshot_id: SnapshotId,
+
+    /// Error returned when loading the current KVS.
+    pub error: ErrorCode,
+}
+
+/// Health of an instance, see [`GenericKvs::health`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KvsHealth {
+    /// No thread panicked while holding the instance lock.
+    Healthy,
+
+    /// Data was reloaded from storage after threads panicked while holding the instance lock.
+    /// Changes not flushed before were lost.
+    Recovered {
+        /// Number of reloads.
+        recoveries: usize,
+    },
+
+    /// A thread panicked while holding the instance lock and reloading the data failed.
+    /// Operations fail until reloading succeeds.
+    Poisoned,
+}
+
+/// Integrity of a stored snapshot, see [`GenericKvs::verify_integrity`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum KvsSnapshotStatus {
+    /// Snapshot matches its hash and can be loaded.
+    Valid,
+
+    /// Snapshot doesn't match its hash.
+    HashMismatch,
+
+    /// Snapshot can't be loaded, e.g. a file is missing or malformed.
+    Unreadable(ErrorCode),
+}
+
+/// Repair of a stored snapshot, see [`GenericKvs::repair`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KvsRepairAction {
+    /// Current KVS was rewritten from the in-memory data.
+    Regenerated,
+
+    /// Snapshot was removed.
+    Removed,
+}
+
+/// Statistics of an instance, see [`GenericKvs::stats`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct KvsStats {
+    /// Number of stored keys, defaults not included.
+    pub key_count: usize,
+
+    /// Estimated size of the stored keys in bytes, see
+    /// [`KvsValue::serialized_size_hint`](crate::kvs_value::KvsValue::serialized_size_hint).
+    pub size_hint: usize,
+
+    /// Number of stored keys by value type.
+    pub kind_counts: HashMap<KvsValueKind, usize>,
+
+    /// Number of stored keys also having a default value.
+    pub shadowed_defaults: usize,
+
+    /// Time of the last successful flush by this process, `None` if not flushed yet.
+    pub last_flush: Option<SystemTime>,
+}
+
+/// Keys changed by restoring a snapshot, see [`GenericKvs::snapshot_diff`].
+#[cfg(feature = "snapshots")]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct KvsSnapshotDiff {
+    /// Keys only stored in the snapshot, sorted.
+    pub added: Vec<String>,
+
+    /// Keys missing in the snapshot, sorted.
+    pub removed: Vec<String>,
+
+    /// Keys stored with a different value in the snapshot, sorted.
+    pub changed: Vec<String>,
+}
+
+/// Function called before writing the KVS, see [`GenericKvs::on_before_flush`].
+pub type KvsBeforeFlushFn = dyn Fn(InstanceId) + Send + Sync;
+
+/// Function called after writing the KVS, see [`GenericKvs::on_after_flush`].
+pub type KvsAfterFlushFn = dyn Fn(InstanceId, &Result<(), ErrorCode>) + Send + Sync;
+
+/// Registered flush hooks of an instance.
+#[derive(Clone, Default)]
+pub(crate) struct FlushHooks {
+    pub(crate) before: Vec<Arc<KvsBeforeFlushFn>>,
+    pub(crate) after: Vec<Arc<KvsAfterFlushFn>>,
+    #[cfg(feature = "snapshots")]
+    pub(crate) discard: Vec<Arc<KvsSnapshotDiscardFn>>,
 }
 
 /// Key-value-storage data
-pub struct GenericKvs<Backend: KvsBackend, PathResolver: KvsPathResolver = Backend> {
+///
+/// `Model` is the value type exposed by [`KvsApi::get_model_value`] and
+/// [`KvsApi::set_model_value`], see [`GenericKvs::with_model`].
+pub struct GenericKvs<
+    Backend: KvsBackend,
+    PathResolver: KvsPathResolver = Backend,
+    Model: ValueModel = KvsValue,
+> {
     /// KVS instance data.
-    data: Arc<Mutex<KvsData>>,
+    pub(crate) data: Arc<Mutex<KvsData>>,
 
     /// KVS instance parameters.
     parameters: KvsParameters,
 
+    /// Flush changes when dropped.
+    flush_on_exit: FlushOnExit,
+
+    /// Receiver of measurements.
+    pub(crate) metrics: Option<Arc<dyn KvsMetrics>>,
+
     /// Marker for `Backend`.
     _backend_marker: PhantomData<Backend>,
 
     /// Marker for `PathResolver`.
     _path_resolver_marker: PhantomData<PathResolver>,
+
+    /// Marker for `Model`.
+    _model_marker: PhantomData<Model>,
 }
 
-impl<Backend: KvsBackend, PathResolver: KvsPathResolver> GenericKvs<Backend, PathResolver> {
+impl<Backend: KvsBackend, PathResolver: KvsPathResolver, Model: ValueModel>
+    GenericKvs<Backend, PathResolver, Model>
+{
     pub(crate) fn new(data: Arc<Mutex<KvsData>>, parameters: KvsParameters) -> Self {
         Self {
             data,
             parameters,
+            flush_on_exit: FlushOnExit::No,
+            metrics: None,
             _backend_marker: PhantomData,
             _path_resolver_marker: PhantomData,
+            _model_marker: PhantomData,
         }
     }
 
+    /// Convert into an instance exposing another value model
+    ///
+    /// Both instances share the same data, the flush on exit setting is moved to the returned
+    /// instance.
+    ///
+    /// # Return Values
+    ///   * Instance with value model `Other`
+    pub fn with_model<Other: ValueModel>(mut self) -> GenericKvs<Backend, PathResolver, Other> {
+        let mut kvs = GenericKvs::new(self.data.clone(), self.parameters.clone());
+        kvs.flush_on_exit = std::mem::replace(&mut self.flush_on_exit, FlushOnExit::No);
+        kvs.metrics = self.metrics.take();
+        kvs
+    }
+
     pub fn parameters(&self) -> &KvsParameters {
         &self.parameters
     }
 
+    /// Set receiver of measurements.
+    pub(crate) fn set_metrics(&mut self, metrics: Option<Arc<dyn KvsMetrics>>) {
+        self.metrics = metrics;
+    }
+
+    /// Count key access if access statistics are enabled.
+    pub(crate) fn record_access(&self, data: &mut KvsData, key: &str, write: bool) {
+        if !self.parameters.access_stats {
+            return;
+        }
+        let stats = data.access_stats.entry(key.to_string()).or_default();
+        if write {
+            stats.writes += 1;
+        } else {
+            stats.reads += 1;
+        }
+    }
+
+    /// Get per-key access statistics
+    ///
+    /// Contains all keys stored or having a default value, keys never accessed are reported with
+    /// zero counts. Statistics of removed keys are kept. Empty if statistics are not enabled, see
+    /// [`GenericKvsBuilder::access_stats`](crate::kvs_builder::GenericKvsBuilder::access_stats).
+    ///
+    /// # Return Values
+    ///   * Ok: Access statistics by key
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    pub fn access_stats(&self) -> Result<HashMap<String, KeyAccessStats>, ErrorCode> {
+        let data = self.lock()?;
+        if !self.parameters.access_stats {
+            return Ok(HashMap::new());
+        }
+        let mut stats = data.access_stats.clone();
+        for key in data.kvs_map.keys().chain(data.defaults_map.keys()) {
+            stats.entry(key.clone()).or_default();
+        }
+        Ok(stats)
+    }
+
+    /// Get recovery performed when loading
+    ///
+    /// Only set when opened with `KvsLoad::RecoverFromSnapshot` and the current KVS was corrupted.
+    ///
+    /// # Return Values
+    ///   * Ok: Recovery info, `None` if no recovery was performed
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    pub fn recovery_info(&self) -> Result<Option<KvsRecoveryInfo>, ErrorCode> {
+        Ok(self.lock()?.recovery.clone())
+    }
+
+    /// Get the snapshot verification performed when opening
+    ///
+    /// Only set when opened with
+    /// [`verify_snapshots`](crate::kvs_builder::GenericKvsBuilder::verify_snapshots).
+    ///
+    /// # Return Values
+    ///   * Ok: Status of every stored snapshot as returned by [`GenericKvs::verify_integrity`],
+    ///     `None` if snapshots weren't verified
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    pub fn verification_info(
+        &self,
+    ) -> Result<Option<Vec<(SnapshotId, KvsSnapshotStatus)>>, ErrorCode> {
+        Ok(self.lock()?.verification.clone())
+    }
+
+    /// Check for changes not yet flushed
+    ///
+    /// # Return Values
+    ///   * Ok: `true` if data changed since it was loaded or last flushed
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    pub fn is_dirty(&self) -> Result<bool, ErrorCode> {
+        Ok(self.lock()?.dirty)
+    }
+
+    /// Create a snapshot of the current data
+    ///
+    /// Snapshots are rotated and the current data is flushed, also if nothing changed since the
+    /// last flush. Afterwards snapshot 1 equals the current KVS.
+    ///
+    /// # Features
+    ///   * `FEAT_REQ__KVS__snapshots`
+    ///
+    /// # Return Values
+    ///   * Ok: Snapshot created
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    ///   * Err: Flush failed, see [`KvsApi::flush`]
+    #[cfg(feature = "snapshots")]
+    pub fn snapshot_create(&self) -> Result<(), ErrorCode> {
+        let mut data = self.lock()?;
+        self.flush_data(&mut data)
+    }
+
+    /// Remove old snapshots
+    ///
+    /// Snapshots with an ID greater than `keep` are removed, the current KVS is never removed.
+    ///
+    /// # Features
+    ///   * `FEAT_REQ__KVS__snapshots`
+    ///
+    /// # Parameters
+    ///   * `keep`: Count of snapshots to keep besides the current KVS
+    ///
+    /// # Return Values
+    ///   * Ok: Count of removed snapshots
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    ///   * `ErrorCode::UnmappedError`: Snapshot couldn't be removed
+    #[cfg(feature = "snapshots")]
+    pub fn snapshot_prune(&self, keep: usize) -> Result<usize, ErrorCode> {
+        // Data lock serializes against flushes of this process, directory lock against others.
+        let _data = self.lock()?;
+        let _dir_lock = if Backend::locks_working_dir() {
+            Some(KvsDirLock::acquire(&self.parameters.working_dir)?)
+        } else {
+            None
+        };
+
+        let mut removed = 0;
+        for idx in (keep + 1)..=KVS_MAX_SNAPSHOTS {
+            let snapshot_id = SnapshotId(idx);
+            let kvs_path = self.parameters.kvs_file_path::<PathResolver>(snapshot_id);
+            let hash_path = self.parameters.hash_file_path::<PathResolver>(snapshot_id);
+            if !Backend::exists(&kvs_path) && !Backend::exists(&hash_path) {
+                continue;
+            }
+            kvs_debug!(
+                instance_id = self.parameters.instance_id,
+                "removing snapshot {snapshot_id}"
+            );
+            Backend::remove_kvs(&kvs_path, &hash_path)?;
+            removed += 1;
+        }
+        Ok(removed)
+    }
+
+    /// Export a stored snapshot to an external location
+    ///
+    /// The snapshot is validated against its hash and written to `dest_path` with a new hash
+    /// file next to it, using the `hash` extension, e.g. `backup.json` and `backup.hash`. The
+    /// stored snapshot is exported, changes not flushed yet aren't included.
+    ///
+    /// # Features
+    ///   * `FEAT_REQ__KVS__snapshots`
+    ///
+    /// # Parameters
+    ///   * `snapshot_id`: Snapshot to export, `SnapshotId(0)` for the current KVS
+    ///   * `dest_path`: Path of the exported KVS file
+    ///
+    /// # Return Values
+    ///   * Ok: Snapshot exported
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    ///   * `ErrorCode::FileNotFound`: Snapshot doesn't exist
+    ///   * `ErrorCode::ValidationFailed`: Snapshot hash validation failed
+    ///   * Err: Loading the snapshot or writing the export failed
+    #[cfg(feature = "snapshots")]
+    pub fn export_snapshot(
+        &self,
+        snapshot_id: SnapshotId,
+        dest_path: &Path,
+    ) -> Result<(), ErrorCode> {
+        // Data lock serializes against flushes of this process, directory lock against others.
+        let _data = self.lock()?;
+        let _dir_lock = if Backend::locks_working_dir() {
+            Some(KvsDirLock::acquire(&self.parameters.working_dir)?)
+        } else {
+            None
+        };
+
+        let kvs_path = self.parameters.kvs_file_path::<PathResolver>(snapshot_id);
+        let hash_path = self.parameters.hash_file_path::<PathResolver>(snapshot_id);
+        if !Backend::exists(&kvs_path) {
+            kvs_error!(
+                instance_id = self.parameters.instance_id,
+                "snapshot {snapshot_id} to export not found"
+            );
+            return Err(ErrorCode::FileNotFound);
+        }
+        let kvs_map = kvs_event::check_integrity(
+            Backend::load_kvs_with_policy(
+                &kvs_path,
+                Some(&hash_path),
+                self.parameters.duplicate_keys,
+            ),
+            self.parameters.instance_id,
+            snapshot_id,
+            self.metrics.as_deref(),
+        )?;
+        Backend::save_kvs_compressed(
+            &kvs_map,
+            dest_path,
+            Some(&dest_path.with_extension("hash")),
+            &self.parameters.float_format,
+            self.parameters.compression,
+        )
+        .inspect_err(|e| {
+            kvs_error!(
+                instance_id = self.parameters.instance_id,
+                "export of snapshot {snapshot_id} to {} failed: {e}",
+                dest_path.display()
+            )
+        })
+    }
+
+    /// Get the data a snapshot restore would set, without replacing the current data
+    ///
+    /// # Features
+    ///   * `FEAT_REQ__KVS__snapshots`
+    ///
+    /// # Parameters
+    ///   * `snapshot_id`: Snapshot ID
+    ///
+    /// # Return Values
+    ///   * Ok: Data of the snapshot
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    ///   * Err: Loading the snapshot failed, see [`KvsApi::snapshot_restore`]
+    #[cfg(feature = "snapshots")]
+    pub fn snapshot_preview(&self, snapshot_id: SnapshotId) -> Result<KvsMap, ErrorCode> {
+        // Data lock serializes against snapshot rotation.
+        let _data = self.lock()?;
+        self.load_snapshot(snapshot_id)
+    }
+
+    /// Compare the current data with a snapshot
+    ///
+    /// Changes are reported as a restore of the snapshot would apply them, e.g. keys only stored
+    /// in the snapshot are added. Unflushed changes are part of the current data, defaults are not
+    /// compared.
+    ///
+    /// # Features
+    ///   * `FEAT_REQ__KVS__snapshots`
+    ///
+    /// # Parameters
+    ///   * `snapshot_id`: Snapshot ID
+    ///
+    /// # Return Values
+    ///   * Ok: Changed keys
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    ///   * Err: Loading the snapshot failed, see [`KvsApi::snapshot_restore`]
+    #[cfg(feature = "snapshots")]
+    pub fn snapshot_diff(&self, snapshot_id: SnapshotId) -> Result<KvsSnapshotDiff, ErrorCode> {
+        let data = self.lock_data()?;
+        let snapshot = self.load_snapshot(snapshot_id)?;
+        let mut diff = KvsSnapshotDiff::default();
+        for (key, value) in &snapshot {
+            match data.kvs_map.get(key) {
+                None => diff.added.push(key.clone()),
+                Some(current) if current != value => diff.changed.push(key.clone()),
+                Some(_) => {}
+            }
+        }
+        diff.removed = data
+            .kvs_map
+            .keys()
+            .filter(|key| !snapshot.contains_key(*key))
+            .cloned()
+            .collect();
+        diff.added.sort();
+        diff.removed.sort();
+        diff.changed.sort();
+        Ok(diff)
+    }
+
+    /// Get the values of a key stored in the snapshots, without restoring them
+    ///
+    /// Shows how a key evolved across flush generations, the current value isn't part of the
+    /// history.
+    ///
+    /// # Features
+    ///   * `FEAT_REQ__KVS__snapshots`
+    ///
+    /// # Parameters
+    ///   * `key`: Key to look up
+    ///
+    /// # Return Values
+    ///   * Ok: Value of the key per snapshot, newest snapshot first, `None` if the snapshot
+    ///     doesn't contain the key
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    ///   * Err: Loading a snapshot failed, see [`KvsApi::snapshot_restore`]
+    #[cfg(feature = "snapshots")]
+    pub fn value_history(
+        &self,
+        key: &str,
+    ) -> Result<Vec<(SnapshotId, Option<KvsValue>)>, ErrorCode> {
+        // Data lock serializes against snapshot rotation.
+        let _data = self.lock()?;
+        let mut history = Vec::new();
+        for snapshot_id in (1..=KVS_MAX_SNAPSHOTS).map(SnapshotId) {
+            let snapshot_path = self.parameters.kvs_file_path::<PathResolver>(snapshot_id);
+            if !Backend::exists(&snapshot_path) {
+                break;
+            }
+            let mut snapshot = self.load_snapshot(snapshot_id)?;
+            history.push((snapshot_id, snapshot.remove(key)));
+        }
+        Ok(history)
+    }
+
+    /// Load a snapshot with integrity check, legacy keys renamed.
+    ///
+    /// # Return Values
+    ///   * Ok: Data of the snapshot
+    ///   * `ErrorCode::InvalidSnapshotId`: Current KVS or snapshot doesn't exist
+    ///   * Err: Loading the snapshot failed
+    fn load_snapshot(&self, snapshot_id: SnapshotId) -> Result<KvsMap, ErrorCode> {
+        // fail if the snapshot ID is the current KVS
+        if snapshot_id == SnapshotId(0) {
+            kvs_error!(
+                instance_id = self.parameters.instance_id,
+                "tried to load current KVS as snapshot"
+            );
+            return Err(ErrorCode::InvalidSnapshotId);
+        }
+
+        if self.snapshot_count() < snapshot_id.0 {
+            kvs_error!(
+                instance_id = self.parameters.instance_id,
+                "tried to load a non-existing snapshot"
+            );
+            return Err(ErrorCode::InvalidSnapshotId);
+        }
+
+        let kvs_path = self.parameters.kvs_file_path::<PathResolver>(snapshot_id);
+        let hash_path = self.parameters.hash_file_path::<PathResolver>(snapshot_id);
+        let mut kvs_map = kvs_event::check_integrity(
+            Backend::load_kvs_with_policy(
+                &kvs_path,
+                Some(&hash_path),
+                self.parameters.duplicate_keys,
+            ),
+            self.parameters.instance_id,
+            snapshot_id,
+            self.metrics.as_deref(),
+        )?;
+        kvs_migration::rename_keys(&mut kvs_map, None, &self.parameters.key_aliases)?;
+        Ok(kvs_map)
+    }
+
+    /// Import a KVS file exported by [`GenericKvs::export_snapshot`]
+    ///
+    /// The file is validated against the hash file next to it, using the `hash` extension. The
+    /// imported data replaces the current data and is written on next flush, like a restored
+    /// snapshot.
+    ///
+    /// # Features
+    ///   * `FEAT_REQ__KVS__snapshots`
+    ///
+    /// # Parameters
+    ///   * `src_path`: Path of the exported KVS file
+    ///
+    /// # Return Values
+    ///   * Ok: Data imported
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    ///   * `ErrorCode::ValidationFailed`: Hash validation failed, nothing was changed
+    ///   * Err: Loading the file failed, nothing was changed
+    #[cfg(feature = "snapshots")]
+    pub fn import_snapshot(&self, src_path: &Path) -> Result<(), ErrorCode> {
+        let kvs_map = Backend::load_kvs_with_policy(
+            src_path,
+            Some(&src_path.with_extension("hash")),
+            self.parameters.duplicate_keys,
+        )
+        .inspect_err(|e| {
+            kvs_error!(
+                instance_id = self.parameters.instance_id,
+                "import of {} failed: {e}",
+                src_path.display()
+            )
+        })?;
+        let mut kvs_map = kvs_map;
+        kvs_migration::rename_keys(&mut kvs_map, None, &self.parameters.key_aliases)?;
+        let mut data = self.lock()?;
+        data.kvs_map = kvs_map;
+        data.lazy = None;
+        data.dirty = true;
+        self.journal_reset(&data)
+    }
+
+    /// Close the instance and remove it from the instance pool
+    ///
+    /// Periodic flushing is stopped and changes not yet flushed are flushed first. Afterwards the
+    /// instance can be opened again
+    /// with different parameters. Other handles of the instance stay usable, but are detached
+    /// from the instance pool.
+    ///
+    /// # Return Values
+    ///   * Ok: Instance closed
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    ///   * Err: Flush failed, the instance is closed nevertheless and changes not flushed are only
+    ///     kept by other handles
+    pub fn close(mut self) -> Result<(), ErrorCode> {
+        self.cancel_autoflush()?;
+        let result = if self.is_dirty()? {
+            self.flush()
+        } else {
+            Ok(())
+        };
+        if result.is_err() {
+            // Failure is reported to the caller, not retried on drop.
+            self.flush_on_exit = FlushOnExit::No;
+        }
+        kvs_builder::remove_instance(self.parameters.instance_id, &self.data)?;
+        result
+    }
+
+    /// Get current storage usage
+    ///
+    /// Size of the instance as it would be stored on flush, defaults not included.
+    ///
+    /// # Features
+    ///   * `FEAT_REQ__KVS__maximum_size`
+    ///
+    /// # Return Values
+    ///   * Ok: Serialized size in bytes
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    ///   * `ErrorCode::JsonGeneratorError`: Failed to serialize
+    pub fn storage_usage(&self) -> Result<usize, ErrorCode> {
+        let data = self.lock_data()?;
+        Backend::serialized_size(&data.kvs_map, &self.parameters.float_format)
+    }
+
+    /// Get statistics of the stored data
+    ///
+    /// The size is estimated from the values without serializing the instance, see
+    /// [`GenericKvs::storage_usage`] for the exact size.
+    ///
+    /// # Return Values
+    ///   * Ok: Statistics
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    ///   * `ErrorCode::JsonParserError`: A lazily loaded value couldn't be parsed
+    pub fn stats(&self) -> Result<KvsStats, ErrorCode> {
+        let data = self.lock_data()?;
+        let mut kind_counts = HashMap::new();
+        for value in data.kvs_map.values() {
+            *kind_counts.entry(value.kind()).or_default() += 1;
+        }
+        Ok(KvsStats {
+            key_count: data.kvs_map.len(),
+            size_hint: kvs_value::map_size_hint(&data.kvs_map),
+            kind_counts,
+            shadowed_defaults: data
+                .kvs_map
+                .keys()
+                .filter(|key| data.defaults_map.contains_key(*key))
+                .count(),
+            last_flush: data.last_flush,
+        })
+    }
+
+    /// Check that a map fits into the configured maximum size.
+    ///
+    /// # Return Values
+    ///   * Ok: No limit configured or map fits
+    ///   * `ErrorCode::QuotaExceeded`: Serialized map exceeds the limit
+    pub(crate) fn check_size(&self, kvs_map: &KvsMap) -> Result<(), ErrorCode> {
+        let Some(max_size) = self.parameters.max_size else {
+            return Ok(());
+        };
+        let size = Backend::serialized_size(kvs_map, &self.parameters.float_format)?;
+        if size > max_size {
+            kvs_error!(
+                instance_id = self.parameters.instance_id,
+                "instance {} size {size} exceeds maximum size {max_size}",
+                self.parameters.instance_id
+            );
+            return Err(ErrorCode::QuotaExceeded);
+        }
+        Ok(())
+    }
+
+    /// Check that changed values fit into the configured value size and key count limits.
+    ///
+    /// The key count is only checked if it grows, instances loaded with more keys can still be
+    /// updated.
+    ///
+    /// # Parameters
+    ///   * `kvs_map`: Map with the changes applied
+    ///   * `keys`: Changed keys
+    ///   * `previous_len`: Key count before the changes
+    ///
+    /// # Return Values
+    ///   * Ok: No limit configured or changes fit
+    ///   * `ErrorCode::QuotaExceeded`: A value or the key count exceeds the limit
+    pub(crate) fn check_limits(
+        &self,
+        kvs_map: &KvsMap,
+        keys: &[&str],
+        previous_len: usize,
+    ) -> Result<(), ErrorCode> {
+        let instance_id = self.parameters.instance_id;
+        if let Some(max_keys) = self.parameters.max_keys {
+            if kvs_map.len() > max_keys && kvs_map.len() > previous_len {
+                kvs_error!(
+                    instance_id = instance_id,
+                    "instance {instance_id} key count {} exceeds maximum key count {max_keys}",
+                    kvs_map.len()
+                );
+                return Err(ErrorCode::QuotaExceeded);
+            }
+        }
+        if let Some(max_value_bytes) = self.parameters.max_value_bytes {
+            for key in keys {
+                let Some(value) = kvs_map.get(*key) else {
+                    continue;
+                };
+                let size = value.serialized_size_hint();
+                if size > max_value_bytes {
+                    kvs_error!(
+                        instance_id = instance_id,
+                        key = key,
+                        "value of key {key} size {size} exceeds maximum value size {max_value_bytes}"
+                    );
+                    return Err(ErrorCode::QuotaExceeded);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that a write keeps the value type of a key.
+    ///
+    /// # Parameters
+    ///   * `key`: Written key
+    ///   * `current`: Stored or default value of the key, `None` if the key doesn't exist
+    ///   * `value`: Written value
+    ///
+    /// # Return Values
+    ///   * Ok: Strict typing disabled, new key or same type
+    ///   * `ErrorCode::TypeMismatch`: Type differs from the existing value
+    pub(crate) fn check_type(
+        &self,
+        key: &str,
+        current: Option<&KvsValue>,
+        value: &KvsValue,
+    ) -> Result<(), ErrorCode> {
+        if !self.parameters.strict_types {
+            return Ok(());
+        }
+        let value = kvs_checked::plain(value);
+        match current.map(kvs_checked::plain) {
+            Some(current) if current.kind() != value.kind() => {
+                kvs_error!(
+                    instance_id = self.parameters.instance_id,
+                    key = key,
+                    "type of key {key} can't change from {} to {}",
+                    current.kind(),
+                    value.kind()
+                );
+                Err(ErrorCode::TypeMismatch)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether writes are checked against size or count limits.
+    pub(crate) fn has_limits(&self) -> bool {
+        self.parameters.max_size.is_some()
+            || self.parameters.max_keys.is_some()
+            || self.parameters.max_value_bytes.is_some()
+    }
+
+    /// Get list of keys of a scope
+    ///
+    /// # Parameters
+    ///   * `scope`: Stored keys, keys with a default or both
+    ///
+    /// # Return Values
+    ///   * Ok: List of keys, stored keys first for `KeyScope::All`
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    pub fn get_keys(&self, scope: KeyScope) -> Result<Vec<String>, ErrorCode> {
+        let data = self.lock()?;
+        let keys = match scope {
+            KeyScope::Stored => data.keys().cloned().collect(),
+            KeyScope::Defaults => data.defaults_map.keys().cloned().collect(),
+            KeyScope::All => data
+                .keys()
+                .chain(
+                    data.defaults_map
+                        .keys()
+                        .filter(|key| !data.contains_key(key)),
+                )
+                .cloned()
+                .collect(),
+        };
+        Ok(keys)
+    }
+
+    /// Get list of all keys including keys only having a default value
+    ///
+    /// # Return Values
+    ///   * Ok: List of keys, see [`GenericKvs::get_keys`]
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    pub fn get_all_keys_with_defaults(&self) -> Result<Vec<String>, ErrorCode> {
+        self.get_keys(KeyScope::All)
+    }
+
+    /// Get list of keys having a default value
+    ///
+    /// # Return Values
+    ///   * Ok: List of keys
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    pub fn get_default_keys(&self) -> Result<Vec<String>, ErrorCode> {
+        self.get_keys(KeyScope::Defaults)
+    }
+
+    /// Get the assigned values for multiple keys with a result per key
+    ///
+    /// Like [`KvsApi::get_values`], but a failing key doesn't abort the other keys. The instance is
+    /// locked once for all keys.
+    ///
+    /// # Parameters
+    ///   * `keys`: Keys to retrieve the values from
+    ///
+    /// # Return Values
+    ///   * Key and result in the order of `keys`, a result is one of:
+    ///     * Ok: Stored or default value
+    ///     * `ErrorCode::KeyNotFound`: Key wasn't found in KVS nor in defaults
+    ///     * `ErrorCode::ValidationFailed`: Value doesn't match its checksum
+    ///     * `ErrorCode::MutexLockFailed`: Mutex locking failed, for all keys
+    pub fn try_get_many(&self, keys: &[&str]) -> Vec<(String, Result<KvsValue, ErrorCode>)> {
+        let mut data = match self.lock() {
+            Ok(data) => data,
+            Err(e) => {
+                return keys
+                    .iter()
+                    .map(|key| (key.to_string(), Err(e.clone())))
+                    .collect()
+            }
+        };
+        let mut results = Vec::with_capacity(keys.len());
+        for key in keys {
+            let value = data.materialize_key(key).and_then(|()| {
+                if let Some(value) = data.kvs_map.get(*key) {
+                    kvs_checked::verify(value).cloned()
+                } else if let Some(value) = data.defaults_map.get(*key) {
+                    Ok(value.clone())
+                } else {
+                    Err(ErrorCode::KeyNotFound)
+                }
+            });
+            if value.is_ok() {
+                self.record_access(&mut data, key, false);
+            }
+            results.push((key.to_string(), value));
+        }
+        results
+    }
+
+    /// Reset a key to its default value or remove it if it has none
+    ///
+    /// Like [`KvsApi::reset_key`], but keys without a default value are removed instead of failing.
+    /// Keys which aren't stored are left unchanged.
+    ///
+    /// # Parameters
+    ///   * `key`: Key being reset
+    ///
+    /// # Return Values
+    ///   * Ok: `true` if the key has a default value, `false` if it was removed
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    pub fn reset_key_or_remove(&self, key: &str) -> Result<bool, ErrorCode> {
+        let mut data = self.lock_keys([key])?;
+        let has_default = data.defaults_map.contains_key(key);
+        if data.kvs_map.remove(key).is_some() {
+            data.dirty = true;
+            self.record_access(&mut data, key, true);
+            self.journal_keys(&data, [key])?;
+        }
+        Ok(has_default)
+    }
+
+    /// Discover instances stored in a working directory
+    ///
+    /// Instances are found by their snapshot and defaults files, no instance is opened.
+    ///
+    /// # Parameters
+    ///   * `working_dir`: Directory to scan
+    ///
+    /// # Return Values
+    ///   * Ok: Discovered instances ordered by instance ID
+    ///   * `ErrorCode::FileNotFound`: Working directory not found
+    ///   * `ErrorCode::UnmappedError`: Directory couldn't be read
+    #[cfg(feature = "tooling")]
+    pub fn discover_instances(working_dir: &Path) -> Result<Vec<InstanceInfo>, ErrorCode> {
+        kvs_discovery::discover_instances::<PathResolver>(working_dir, KVS_MAX_SNAPSHOTS)
+    }
+
+    /// Watch defaults files and reload defaults when they change
+    ///
+    /// The instance, global and layered defaults files are polled with the given interval, see
+    /// [`defaults_watcher`](crate::defaults_watcher). Values set in the KVS are not affected.
+    ///
+    /// # Parameters
+    ///   * `interval`: Polling interval
+    ///
+    /// # Return Values
+    ///   * Watcher, watching stops when it is dropped
+    #[cfg(feature = "defaults")]
+    pub fn watch_defaults(&self, interval: Duration) -> DefaultsWatcher
+    where
+        Backend: 'static,
+        PathResolver: 'static,
+    {
+        DefaultsWatcher::start::<Backend, PathResolver>(
+            self.data.clone(),
+            self.parameters.clone(),
+            interval,
+        )
+    }
+
+    /// Reload defaults files and replace the defaults of the instance
+    ///
+    /// The instance, global and layered defaults files are loaded as done when opening the instance.
+    /// Values set in the KVS and unsaved changes of defaults, see
+    /// [`set_default_value`](Self::set_default_value), are not affected. On failure the previous
+    /// defaults are kept.
+    ///
+    /// # Features
+    ///   * `FEAT_REQ__KVS__default_values`
+    ///
+    /// # Return Values
+    ///   * Ok: Defaults reloaded
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    ///   * `ErrorCode::KvsFileReadError`: Required defaults file not found or not readable
+    ///   * `ErrorCode::JsonParserError`: JSON parser error
+    #[cfg(feature = "defaults")]
+    pub fn reload_defaults(&self) -> Result<(), ErrorCode> {
+        let defaults_map =
+            defaults_watcher::load_defaults::<Backend, PathResolver>(&self.parameters)?;
+        self.lock()?.set_defaults(defaults_map);
+        kvs_event::emit(KvsEvent::DefaultsReloaded {
+            instance_id: self.parameters.instance_id,
+        });
+        Ok(())
+    }
+
+    /// Set the default value of a key at runtime
+    ///
+    /// Only the defaults of the instance are changed, the value is never flushed into the KVS.
+    /// The change is kept when defaults are reloaded until it's written to the instance defaults
+    /// file with [`save_defaults`](Self::save_defaults) or the instance is closed.
+    ///
+    /// # Features
+    ///   * `FEAT_REQ__KVS__default_values`
+    ///
+    /// # Parameters
+    ///   * `key`: Key to set the default value of
+    ///   * `value`: Default value
+    ///
+    /// # Return Values
+    ///   * Ok: Default value set
+    ///   * `ErrorCode::InvalidKey`: Key violates the key policy
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    #[cfg(feature = "defaults")]
+    pub fn set_default_value<S: Into<String>, V: KvsSerialize>(
+        &self,
+        key: S,
+        value: V,
+    ) -> Result<(), ErrorCode> {
+        let key = key.into();
+        self.parameters.key_policy.validate(&key)?;
+        let value = value.to_kvs_value();
+        let mut data = self.lock()?;
+        data.defaults_map.insert(key.clone(), value.clone());
+        data.default_changes.insert(key, Some(value));
+        Ok(())
+    }
+
+    /// Remove the default value of a key at runtime
+    ///
+    /// Like [`set_default_value`](Self::set_default_value), only the defaults of the instance are
+    /// changed. A value set in the KVS is kept.
+    ///
+    /// # Features
+    ///   * `FEAT_REQ__KVS__default_values`
+    ///
+    /// # Parameters
+    ///   * `key`: Key to remove the default value of
+    ///
+    /// # Return Values
+    ///   * Ok: Default value removed
+    ///   * `ErrorCode::KeyDefaultNotFound`: Key has no default value
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    #[cfg(feature = "defaults")]
+    pub fn remove_default(&self, key: &str) -> Result<(), ErrorCode> {
+        let mut data = self.lock()?;
+        if data.defaults_map.remove(key).is_none() {
+            kvs_error!(
+                instance_id = self.parameters.instance_id,
+                key = key,
+                "remove_default could not find default of key: {key}"
+            );
+            return Err(ErrorCode::KeyDefaultNotFound.with_key(key));
+        }
+        data.default_changes.insert(key.to_string(), None);
+        Ok(())
+    }
+
+    /// Write defaults changed at runtime to the instance defaults file
+    ///
+    /// The changes are applied to the stored instance defaults file, which is created if it
+    /// doesn't exist. Global and layered defaults files are not changed, so a default removed at
+    /// runtime is back after reloading if one of them contains it.
+    ///
+    /// # Features
+    ///   * `FEAT_REQ__KVS__default_values`
+    ///
+    /// # Return Values
+    ///   * Ok: Changes written, also if nothing changed
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    ///   * Err: Loading or writing the defaults file failed
+    #[cfg(feature = "defaults")]
+    pub fn save_defaults(&self) -> Result<(), ErrorCode> {
+        let mut data = self.lock()?;
+        if data.default_changes.is_empty() {
+            return Ok(());
+        }
+        let defaults_path = self.parameters.file_path::<PathResolver>(KvsFile::Defaults);
+        let mut defaults_map = if Backend::exists(&defaults_path) {
+            Backend::load_kvs_with_policy(&defaults_path, None, self.parameters.duplicate_keys)?
+        } else {
+            KvsMap::new()
+        };
+        for (key, value) in &data.default_changes {
+            match value {
+                Some(value) => defaults_map.insert(key.clone(), value.clone()),
+                None => defaults_map.remove(key),
+            };
+        }
+        Backend::save_kvs_formatted(
+            &defaults_map,
+            &defaults_path,
+            None,
+            &self.parameters.float_format,
+        )
+        .inspect_err(|e| {
+            kvs_error!(
+                instance_id = self.parameters.instance_id,
+                "writing defaults file {} failed: {e}",
+                defaults_path.display()
+            )
+        })?;
+        data.default_changes.clear();
+        Ok(())
+    }
+
+    /// Export scalar entries of the key-value-storage in `.env` format
+    ///
+    /// Defaults are not exported. See [`dotenv`](crate::dotenv) for the format description.
+    ///
+    /// # Return Values
+    ///   * Ok: `.env` formatted string
+    ///   * `ErrorCode::InvalidKey`: Key of a scalar entry can't be represented
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    #[cfg(feature = "tooling")]
+    pub fn export_dotenv(&self) -> Result<String, ErrorCode> {
+        let data = self.lock_data()?;
+        dotenv::to_dotenv(&data.kvs_map)
+    }
+
+    /// Import entries in `.env` format into the key-value-storage
+    ///
+    /// Imported entries are set with [`KvsApi::set_values`], existing keys are overwritten.
+    /// The import is atomic - either all entries are set or none.
+    ///
+    /// # Parameters
+    ///   * `s`: `.env` formatted string
+    ///
+    /// # Return Values
+    ///   * Ok: Entries imported
+    ///   * `ErrorCode::ConversionFailed`: Malformed input
+    ///   * `ErrorCode::InvalidKey`: Empty key
+    ///   * Other errors of [`KvsApi::set_values`], nothing was changed
+    #[cfg(feature = "tooling")]
+    pub fn import_dotenv(&self, s: &str) -> Result<(), ErrorCode> {
+        self.set_values(dotenv::from_dotenv(s)?)
+    }
+
+    /// Derive protobuf message schema from the defaults
+    ///
+    /// See [`protobuf`](crate::protobuf) for the type mapping.
+    ///
+    /// # Parameters
+    ///   * `message_name`: Name of the message
+    ///
+    /// # Return Values
+    ///   * Ok: Derived schema
+    ///   * `ErrorCode::ConversionFailed`: Defaults can't be mapped to a message
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    #[cfg(feature = "tooling")]
+    pub fn protobuf_schema(&self, message_name: &str) -> Result<ProtoSchema, ErrorCode> {
+        let data = self.lock_data()?;
+        ProtoSchema::from_defaults(message_name, &data.defaults_map)
+    }
+
+    /// Export current values as protobuf message
+    ///
+    /// Keys without a stored value are exported with their default value.
+    ///
+    /// # Parameters
+    ///   * `schema`: Message schema
+    ///
+    /// # Return Values
+    ///   * Ok: Encoded message
+    ///   * `ErrorCode::ConversionFailed`: Value doesn't match the field type
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    #[cfg(feature = "tooling")]
+    pub fn export_protobuf(&self, schema: &ProtoSchema) -> Result<Vec<u8>, ErrorCode> {
+        let data = self.lock_data()?;
+        let mut values = data.defaults_map.clone();
+        values.extend(data.kvs_map.iter().map(|(k, v)| (k.clone(), v.clone())));
+        schema.encode(&values)
+    }
+
+    /// Import protobuf message into the key-value-storage
+    ///
+    /// Decoded entries are set with [`KvsApi::set_values`], existing keys are overwritten.
+    /// The import is atomic - either all entries are set or none.
+    ///
+    /// # Parameters
+    ///   * `schema`: Message schema
+    ///   * `buf`: Encoded message
+    ///
+    /// # Return Values
+    ///   * Ok: Entries imported
+    ///   * `ErrorCode::SerializationFailed`: Malformed message
+    ///   * Other errors of [`KvsApi::set_values`], nothing was changed
+    #[cfg(feature = "tooling")]
+    pub fn import_protobuf(&self, schema: &ProtoSchema, buf: &[u8]) -> Result<(), ErrorCode> {
+        self.set_values(schema.decode(buf)?)
+    }
+
+    /// Lock instance data.
+    ///
+    /// If a thread panicked while holding the lock, the data might be partially changed. It is
+    /// reloaded from storage like when opening the instance and the lock is usable again. The
+    /// lock stays poisoned if reloading fails, so the next call retries.
+    ///
+    /// # Return Values
+    ///   * Ok: Locked data
+    ///   * Err: Reloading the data after a panic failed
+    pub(crate) fn lock(&self) -> Result<MutexGuard<'_, KvsData>, ErrorCode> {
+        let poisoned = match self.data.lock() {
+            Ok(data) => return Ok(data),
+            Err(poisoned) => poisoned,
+        };
+        let instance_id = self.parameters.instance_id;
+        let mut data = poisoned.into_inner();
+        self.reload(&mut data).inspect_err(|e| {
+            kvs_error!(
+                instance_id = instance_id,
+                "instance {instance_id} lock poisoned, reloading failed: {e}"
+            )
+        })?;
+        self.data.clear_poison();
+        data.poison_recoveries += 1;
+        kvs_warn!(
+            instance_id = instance_id,
+            "instance {instance_id} lock poisoned, data reloaded from storage"
+        );
+        Ok(data)
+    }
+
+    /// Replace the data by the stored KVS, handling missing files as configured by `KvsLoad`.
+    fn reload(&self, data: &mut KvsData) -> Result<(), ErrorCode> {
+        let kvs_path = self.parameters.kvs_file_path::<PathResolver>(SnapshotId(0));
+        let hash_path = self
+            .parameters
+            .hash_file_path::<PathResolver>(SnapshotId(0));
+        let kvs_map = match self.parameters.kvs_load {
+            KvsLoad::Ignored => KvsMap::new(),
+            KvsLoad::Optional | KvsLoad::RecoverFromSnapshot if !Backend::exists(&kvs_path) => {
+                KvsMap::new()
+            }
+            _ => kvs_event::check_integrity(
+                Backend::load_kvs_with_policy(
+                    &kvs_path,
+                    Some(&hash_path),
+                    self.parameters.duplicate_keys,
+                ),
+                self.parameters.instance_id,
+                SnapshotId(0),
+                self.metrics.as_deref(),
+            )?,
+        };
+        data.merge_base =
+            (self.parameters.merge_policy != KvsMergePolicy::Overwrite).then(|| kvs_map.clone());
+        let mut kvs_map = kvs_map;
+        data.dirty = kvs_migration::rename_keys(&mut kvs_map, None, &self.parameters.key_aliases)?;
+        data.kvs_map = kvs_map;
+        data.lazy = None;
+        Ok(())
+    }
+
+    /// Get health of the instance
+    ///
+    /// Data is reloaded if a thread panicked while holding the instance lock, see
+    /// [`KvsHealth`].
+    ///
+    /// # Return Values
+    ///   * Ok: Health of the instance
+    pub fn health(&self) -> Result<KvsHealth, ErrorCode> {
+        match self.lock() {
+            Ok(data) if data.poison_recoveries == 0 => Ok(KvsHealth::Healthy),
+            Ok(data) => Ok(KvsHealth::Recovered {
+                recoveries: data.poison_recoveries,
+            }),
+            Err(_) => Ok(KvsHealth::Poisoned),
+        }
+    }
+
+    /// Check the current KVS and all snapshots against their hashes
+    ///
+    /// Every stored snapshot is loaded, failures are reported as
+    /// [`KvsEvent::IntegrityFailure`](crate::kvs_event::KvsEvent::IntegrityFailure) like when
+    /// loading. Nothing is changed, see [`GenericKvs::repair`].
+    ///
+    /// # Features
+    ///   * `FEAT_REQ__KVS__integrity_check`
+    ///
+    /// # Return Values
+    ///   * Ok: Status of every stored snapshot, ordered by snapshot ID
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    pub fn verify_integrity(&self) -> Result<Vec<(SnapshotId, KvsSnapshotStatus)>, ErrorCode> {
+        // Data lock serializes against flushes of this process, directory lock against others.
+        let _data = self.lock()?;
+        let _dir_lock = if Backend::locks_working_dir() {
+            Some(KvsDirLock::acquire(&self.parameters.working_dir)?)
+        } else {
+            None
+        };
+        Ok(self.scan_snapshots())
+    }
+
+    /// Repair broken snapshots found by [`GenericKvs::verify_integrity`]
+    ///
+    /// A broken current KVS is rewritten from the in-memory data, including changes not flushed
+    /// yet. Broken snapshots are removed and the remaining snapshots are renumbered, so snapshot
+    /// IDs stay contiguous.
+    ///
+    /// # Features
+    ///   * `FEAT_REQ__KVS__integrity_check`
+    ///
+    /// # Return Values
+    ///   * Ok: Performed actions by original snapshot ID, empty if nothing was broken
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    ///   * Err: Writing, removing or moving a snapshot failed
+    pub fn repair(&self) -> Result<Vec<(SnapshotId, KvsRepairAction)>, ErrorCode> {
+        let mut data = self.lock_data()?;
+        let _dir_lock = if Backend::locks_working_dir() {
+            Some(KvsDirLock::acquire(&self.parameters.working_dir)?)
+        } else {
+            None
+        };
+
+        let instance_id = self.parameters.instance_id;
+        let mut actions = Vec::new();
+        let mut next_id = 1;
+        for (snapshot_id, status) in self.scan_snapshots() {
+            let kvs_path = self.parameters.kvs_file_path::<PathResolver>(snapshot_id);
+            let hash_path = self.parameters.hash_file_path::<PathResolver>(snapshot_id);
+            if snapshot_id == SnapshotId(0) {
+                if status != KvsSnapshotStatus::Valid {
+                    kvs_warn!(
+                        instance_id = instance_id,
+                        "regenerating current KVS: {status:?}"
+                    );
+                    Backend::save_kvs_compressed(
+                        &data.kvs_map,
+                        &kvs_path,
+                        Some(&hash_path),
+                        &self.parameters.float_format,
+                        self.parameters.compression,
+                    )
+                    .and_then(|()| {
+                        Backend::sync_kvs(&kvs_path, &hash_path, self.parameters.sync_policy)
+                    })?;
+                    if data.merge_base.is_some() {
+                        data.merge_base = Some(data.kvs_map.clone());
+                    }
+                    data.dirty = false;
+                    data.last_flush = Some(self.parameters().now());
+                    actions.push((snapshot_id, KvsRepairAction::Regenerated));
+                }
+                continue;
+            }
+            if status != KvsSnapshotStatus::Valid {
+                kvs_warn!(
+                    instance_id = instance_id,
+                    "removing snapshot {snapshot_id}: {status:?}"
+                );
+                Backend::remove_kvs(&kvs_path, &hash_path)?;
+                actions.push((snapshot_id, KvsRepairAction::Removed));
+                continue;
+            }
+            if snapshot_id.0 != next_id {
+                let new_id = SnapshotId(next_id);
+                kvs_debug!(
+                    instance_id = instance_id,
+                    "moving snapshot {snapshot_id} to {new_id}"
+                );
+                Backend::move_kvs(
+                    &kvs_path,
+                    &hash_path,
+                    &self.parameters.kvs_file_path::<PathResolver>(new_id),
+                    &self.parameters.hash_file_path::<PathResolver>(new_id),
+                )?;
+            }
+            next_id += 1;
+        }
+        Ok(actions)
+    }
+
+    /// Load all stored snapshots, the instance and working directory must be locked.
+    ///
+    /// Snapshots are loaded concurrently, one thread per snapshot.
+    fn scan_snapshots(&self) -> Vec<(SnapshotId, KvsSnapshotStatus)> {
+        let parameters = &self.parameters;
+        let metrics = self.metrics.as_deref();
+        let scan = |snapshot_id: SnapshotId, kvs_path: PathBuf, hash_path: PathBuf| {
+            let result = kvs_event::check_integrity(
+                Backend::load_kvs_with_policy(
+                    &kvs_path,
+                    Some(&hash_path),
+                    parameters.duplicate_keys,
+                ),
+                parameters.instance_id,
+                snapshot_id,
+                metrics,
+            );
+            match result {
+                Ok(_) => KvsSnapshotStatus::Valid,
+                Err(e) if e.kind() == &ErrorCode::ValidationFailed => {
+                    KvsSnapshotStatus::HashMismatch
+                }
+                Err(e) => KvsSnapshotStatus::Unreadable(e),
+            }
+        };
+        thread::scope(|scope| {
+            let scans: Vec<_> = (0..=KVS_MAX_SNAPSHOTS)
+                .map(SnapshotId)
+                .filter_map(|snapshot_id| {
+                    let kvs_path = parameters.kvs_file_path::<PathResolver>(snapshot_id);
+                    let hash_path = parameters.hash_file_path::<PathResolver>(snapshot_id);
+                    if !Backend::exists(&kvs_path) && !Backend::exists(&hash_path) {
+                        return None;
+                    }
+                    let scan = &scan;
+                    Some((
+                        snapshot_id,
+                        scope.spawn(move || scan(snapshot_id, kvs_path, hash_path)),
+                    ))
+                })
+                .collect();
+            scans
+                .into_iter()
+                .map(|(snapshot_id, handle)| {
+                    let status = handle.join().unwrap_or_else(|_| {
+                        KvsSnapshotStatus::Unreadable(ErrorCode::UnmappedError)
+                    });
+                    (snapshot_id, status)
+                })
+                .collect()
+        })
+    }
+
+    /// Register a function called before the KVS is written
+    ///
+    /// Hooks are shared by all handles of the instance and called in registration order on every
+    /// write, including flushes by snapshot creation, periodic flushing, shutdown and commits of
+    /// [`MultiKvsWrite`](crate::kvs_multi_write::MultiKvsWrite). Nothing is called if a flush has
+    /// nothing to write. Hooks run while the instance is locked and must not access the instance.
+    ///
+    /// # Parameters
+    ///   * `hook`: Function called with the instance ID
+    ///
+    /// # Return Values
+    ///   * Ok: Hook registered
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    pub fn on_before_flush<F>(&self, hook: F) -> Result<(), ErrorCode>
+    where
+        F: Fn(InstanceId) + Send + Sync + 'static,
+    {
+        self.lock()?.flush_hooks.before.push(Arc::new(hook));
+        Ok(())
+    }
+
+    /// Register a function called after the KVS was written or writing failed
+    ///
+    /// Called for every write like the hooks of [`GenericKvs::on_before_flush`].
+    ///
+    /// # Parameters
+    ///   * `hook`: Function called with the instance ID and the result of the write
+    ///
+    /// # Return Values
+    ///   * Ok: Hook registered
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    pub fn on_after_flush<F>(&self, hook: F) -> Result<(), ErrorCode>
+    where
+        F: Fn(InstanceId, &Result<(), ErrorCode>) + Send + Sync + 'static,
+    {
+        self.lock()?.flush_hooks.after.push(Arc::new(hook));
+        Ok(())
+    }
+
+    /// Lock instance data with all values parsed.
+    ///
+    /// Used by operations working on all values, lazily loaded values are parsed first.
+    pub(crate) fn lock_data(&self) -> Result<MutexGuard<'_, KvsData>, ErrorCode> {
+        let mut data = self.lock()?;
+        data.materialize()?;
+        Ok(data)
+    }
+
+    /// Lock instance data with the values of given keys parsed.
+    ///
+    /// Used by writes of single keys, other lazily loaded values stay unparsed. All values are
+    /// parsed if size or count limits are configured, checking them requires all values.
+    pub(crate) fn lock_keys<'a>(
+        &self,
+        keys: impl IntoIterator<Item = &'a str>,
+    ) -> Result<MutexGuard<'_, KvsData>, ErrorCode> {
+        if self.has_limits() {
+            return self.lock_data();
+        }
+        let mut data = self.lock()?;
+        for key in keys {
+            data.materialize_key(key)?;
+        }
+        Ok(data)
+    }
+
+    /// Flush already locked data.
+    ///
+    /// Allows flushing while the caller holds the data lock, e.g. when the lock was acquired with
+    /// `try_lock`.
+    pub(crate) fn flush_data(&self, data: &mut KvsData) -> Result<(), ErrorCode> {
+        let instance_id = self.parameters.instance_id;
+        let hooks = data.flush_hooks.clone();
+        for hook in &hooks.before {
+            hook(instance_id);
+        }
+        let result = self.write_data(data);
+        for hook in &hooks.after {
+            hook(instance_id, &result);
+        }
+        result
+    }
+
+    /// Flush already locked data if it changed or the current KVS wasn't stored yet, see
+    /// [`KvsApi::flush`].
+    pub(crate) fn flush_changed(&self, data: &mut KvsData) -> Result<(), ErrorCode> {
+        let kvs_path = self.parameters.kvs_file_path::<PathResolver>(SnapshotId(0));
+        if !data.dirty && Backend::exists(&kvs_path) {
+            return Ok(());
+        }
+        self.flush_data(data)
+    }
+
+    /// Write already locked data, see [`GenericKvs::flush_data`].
+    fn write_data(&self, data: &mut KvsData) -> Result<(), ErrorCode> {
+        let start = Instant::now();
+        data.materialize()?;
+        let instance_id = self.parameters.instance_id;
+        let _dir_lock = if Backend::locks_working_dir() {
+            Some(
+                KvsDirLock::acquire(&self.parameters.working_dir).map_err(|e| {
+                    kvs_error!(
+                        instance_id = instance_id,
+                        "locking working directory failed: {e}"
+                    );
+                    kvs_event::emit(KvsEvent::FlushFailed {
+                        instance_id,
+                        error: e.clone(),
+                    });
+                    e
+                })?,
+            )
+        } else {
+            None
+        };
+        // Stored KVS is merged while the working directory is locked.
+        let merged = self.merge_stored(data);
+        let kvs_map = merged.as_ref().unwrap_or(&data.kvs_map);
+        self.check_size(kvs_map).inspect_err(|e| {
+            kvs_event::emit(KvsEvent::FlushFailed {
+                instance_id,
+                error: e.clone(),
+            });
+        })?;
+        #[cfg(feature = "snapshots")]
+        self.snapshot_rotate(&data.flush_hooks).map_err(|e| {
+            kvs_error!(instance_id = instance_id, "snapshot_rotate failed: {e}");
+            if e == ErrorCode::IntegrityCorrupted {
+                kvs_event::emit(KvsEvent::IntegrityFailure {
+                    instance_id,
+                    snapshot_id: SnapshotId(0),
+                    error: e.clone(),
+                });
+            }
+            kvs_event::emit(KvsEvent::FlushFailed {
+                instance_id,
+                error: e.clone(),
+            });
+            e
+        })?;
+        #[cfg(feature = "snapshots")]
+        if let Some(metrics) = &self.metrics {
+            metrics.on_snapshot_rotation(instance_id);
+        }
+        let snapshot_id = SnapshotId(0);
+        let kvs_path = self.parameters.kvs_file_path::<PathResolver>(snapshot_id);
+        let hash_path = self.parameters.hash_file_path::<PathResolver>(snapshot_id);
+        Backend::save_kvs_compressed(
+            kvs_map,
+            &kvs_path,
+            Some(&hash_path),
+            &self.parameters.float_format,
+            self.parameters.compression,
+        )
+        .and_then(|()| Backend::sync_kvs(&kvs_path, &hash_path, self.parameters.sync_policy))
+        // Journaled changes are contained in the written snapshot.
+        .and_then(|()| self.remove_journal())
+        .map_err(|e| {
+            kvs_error!(instance_id = instance_id, "save_kvs failed: {e}");
+            kvs_event::emit(KvsEvent::FlushFailed {
+                instance_id,
+                error: e.clone(),
+            });
+            e
+        })?;
+        if let Some(merged) = merged {
+            data.kvs_map = merged;
+        }
+        if data.merge_base.is_some() {
+            data.merge_base = Some(data.kvs_map.clone());
+        }
+        data.dirty = false;
+        data.last_flush = Some(self.parameters().now());
+        #[cfg(feature = "shm-cache")]
+        self.publish_shared_view(data);
+        kvs_event::emit(KvsEvent::FlushSucceeded { instance_id });
+        if let Some(metrics) = &self.metrics {
+            // Size is only determined if measured.
+            let bytes_written =
+                Backend::serialized_size(&data.kvs_map, &self.parameters.float_format)
+                    .unwrap_or_default();
+            metrics.on_flush(instance_id, start.elapsed(), bytes_written);
+        }
+        Ok(())
+    }
+
+    /// Merge the stored KVS with the data of this instance.
+    ///
+    /// A stored KVS which is missing or can't be loaded is treated as unchanged, so local data is
+    /// kept.
+    ///
+    /// # Return Values
+    ///   * Some: Merged data
+    ///   * None: Changes are not merged
+    fn merge_stored(&self, data: &KvsData) -> Option<KvsMap> {
+        if self.parameters.merge_policy == KvsMergePolicy::Overwrite {
+            return None;
+        }
+        let base = data.merge_base.as_ref()?;
+        let kvs_path = self.parameters.kvs_file_path::<PathResolver>(SnapshotId(0));
+        let hash_path = self
+            .parameters
+            .hash_file_path::<PathResolver>(SnapshotId(0));
+        let stored = if Backend::exists(&kvs_path) {
+            match Backend::load_kvs_with_policy(
+                &kvs_path,
+                Some(&hash_path),
+                self.parameters.duplicate_keys,
+            ) {
+                Ok(stored) => stored,
+                Err(e) => {
+                    kvs_warn!(
+                        instance_id = self.parameters.instance_id,
+                        "stored KVS not merged, loading failed: {e}"
+                    );
+                    base.clone()
+                }
+            }
+        } else {
+            base.clone()
+        };
+        Some(kvs_merge::merge(
+            base,
+            &data.kvs_map,
+            &stored,
+            &self.parameters.merge_policy,
+        ))
+    }
+
     /// Rotate snapshots
     ///
     /// # Features
@@ -77,74 +1789,69 @@ impl<Backend: KvsBackend, PathResolver: KvsPathResolver> GenericKvs<Backend, Pat
     /// # Return Values
     ///   * Ok: Rotation successful, also if no rotation was needed
     ///   * `ErrorCode::UnmappedError`: Unmapped error
-    fn snapshot_rotate(&self) -> Result<(), ErrorCode> {
+    #[cfg(feature = "snapshots")]
+    pub(crate) fn snapshot_rotate(&self, hooks: &FlushHooks) -> Result<(), ErrorCode> {
+        if Backend::exists(
+            &self
+                .parameters
+                .kvs_file_path::<PathResolver>(SnapshotId(KVS_MAX_SNAPSHOTS - 1)),
+        ) {
+            self.notify_discard(hooks, SnapshotId(KVS_MAX_SNAPSHOTS));
+        }
         for idx in (1..=KVS_MAX_SNAPSHOTS).rev() {
             let old_snapshot_id = SnapshotId(idx - 1);
             let new_snapshot_id = SnapshotId(idx);
 
-            let hash_path_old = PathResolver::hash_file_path(
-                &self.parameters.working_dir,
-                self.parameters.instance_id,
-                old_snapshot_id,
-            );
-            let hash_path_new = PathResolver::hash_file_path(
-                &self.parameters.working_dir,
-                self.parameters.instance_id,
-                new_snapshot_id,
-            );
+            let hash_path_old = self
+                .parameters
+                .hash_file_path::<PathResolver>(old_snapshot_id);
+            let hash_path_new = self
+                .parameters
+                .hash_file_path::<PathResolver>(new_snapshot_id);
             let snap_name_old =
                 PathResolver::kvs_file_name(self.parameters.instance_id, old_snapshot_id);
-            let snap_path_old = PathResolver::kvs_file_path(
-                &self.parameters.working_dir,
-                self.parameters.instance_id,
-                old_snapshot_id,
-            );
+            let snap_path_old = self
+                .parameters
+                .kvs_file_path::<PathResolver>(old_snapshot_id);
             let snap_name_new =
                 PathResolver::kvs_file_name(self.parameters.instance_id, new_snapshot_id);
-            let snap_path_new = PathResolver::kvs_file_path(
-                &self.parameters.working_dir,
-                self.parameters.instance_id,
-                new_snapshot_id,
-            );
+            let snap_path_new = self
+                .parameters
+                .kvs_file_path::<PathResolver>(new_snapshot_id);
 
-            println!("rotating: {snap_name_old} -> {snap_name_new}");
-
-            // Check snapshot and hash files exist.
-            let snap_old_exists = snap_path_old.exists();
-            let hash_old_exists = hash_path_old.exists();
+            kvs_debug!(
+                instance_id = self.parameters.instance_id,
+                "rotating: {snap_name_old} -> {snap_name_new}"
+            );
 
-            // If both exist - rename them.
-            if snap_old_exists && hash_old_exists {
-                fs::rename(hash_path_old, hash_path_new)?;
-                fs::rename(snap_path_old, snap_path_new)?;
-            }
-            // If neither exist - continue.
-            else if !snap_old_exists && !hash_old_exists {
-                continue;
-            }
-            // In other case - this is erroneous scenario.
-            // Either snapshot or hash file got removed.
-            else {
-                return Err(ErrorCode::IntegrityCorrupted);
-            }
+            Backend::move_kvs(
+                &snap_path_old,
+                &hash_path_old,
+                &snap_path_new,
+                &hash_path_new,
+            )?;
         }
 
-        Ok(())
+        self.apply_retention(hooks)
     }
 }
 
-impl<Backend: KvsBackend, PathResolver: KvsPathResolver> KvsApi
-    for GenericKvs<Backend, PathResolver>
+impl<Backend: KvsBackend, PathResolver: KvsPathResolver, Model: ValueModel> KvsApi
+    for GenericKvs<Backend, PathResolver, Model>
 {
+    type Value = Model;
+
     /// Resets a key-value-storage to its initial state
     ///
     /// # Return Values
     ///   * Ok: Reset of the KVS was successful
     ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
     fn reset(&self) -> Result<(), ErrorCode> {
-        let mut data = self.data.lock()?;
+        let mut data = self.lock()?;
         data.kvs_map = KvsMap::new();
-        Ok(())
+        data.lazy = None;
+        data.dirty = true;
+        self.journal_reset(&data)
     }
 
     /// Reset a key-value pair in the storage to its initial state
@@ -157,13 +1864,21 @@ impl<Backend: KvsBackend, PathResolver: KvsPathResolver> KvsApi
     ///    * `ErrorCode::MutexLockFailed`: Mutex locking failed
     ///    * `ErrorCode::KeyDefaultNotFound`: Key has no default value
     fn reset_key(&self, key: &str) -> Result<(), ErrorCode> {
-        let mut data = self.data.lock()?;
+        let mut data = self.lock_keys([key])?;
         if !data.defaults_map.contains_key(key) {
-            eprintln!("error: resetting key without a default value");
-            return Err(ErrorCode::KeyDefaultNotFound);
+            kvs_error!(
+                instance_id = self.parameters.instance_id,
+                key = key,
+                "resetting key without a default value"
+            );
+            return Err(ErrorCode::KeyDefaultNotFound.with_key(key));
         }
 
-        let _ = data.kvs_map.remove(key);
+        if data.kvs_map.remove(key).is_some() {
+            data.dirty = true;
+            self.record_access(&mut data, key, true);
+            self.journal_keys(&data, [key])?;
+        }
         Ok(())
     }
 
@@ -173,8 +1888,45 @@ impl<Backend: KvsBackend, PathResolver: KvsPathResolver> KvsApi
     ///   * Ok: List of all keys
     ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
     fn get_all_keys(&self) -> Result<Vec<String>, ErrorCode> {
-        let data = self.data.lock()?;
-        Ok(data.kvs_map.keys().map(|x| x.to_string()).collect())
+        let data = self.lock()?;
+        Ok(data.keys().map(|x| x.to_string()).collect())
+    }
+
+    /// Get list of keys starting with a prefix
+    ///
+    /// # Parameters
+    ///   * `prefix`: Key prefix, e.g. `module.submodule.`
+    ///
+    /// # Return Values
+    ///   * Ok: List of matching keys
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    fn get_keys_with_prefix(&self, prefix: &str) -> Result<Vec<String>, ErrorCode> {
+        let data = self.lock()?;
+        Ok(data
+            .keys()
+            .filter(|key| key.starts_with(prefix))
+            .cloned()
+            .collect())
+    }
+
+    /// Get list of keys matching a glob pattern
+    ///
+    /// `*` matches any sequence of characters, `?` matches a single character and `\` escapes
+    /// the next character.
+    ///
+    /// # Parameters
+    ///   * `pattern`: Glob pattern, e.g. `module.*.enabled`
+    ///
+    /// # Return Values
+    ///   * Ok: List of matching keys
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    fn get_keys_matching(&self, pattern: &str) -> Result<Vec<String>, ErrorCode> {
+        let data = self.lock()?;
+        Ok(data
+            .keys()
+            .filter(|key| glob_match(pattern, key))
+            .cloned()
+            .collect())
     }
 
     /// Check if a key exists
@@ -187,8 +1939,8 @@ impl<Backend: KvsBackend, PathResolver: KvsPathResolver> KvsApi
     ///   * Ok(`false`): Key doesn't exist
     ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
     fn key_exists(&self, key: &str) -> Result<bool, ErrorCode> {
-        let data = self.data.lock()?;
-        Ok(data.kvs_map.contains_key(key))
+        let data = self.lock()?;
+        Ok(data.contains_key(key))
     }
 
     /// Get the assigned value for a given key
@@ -204,15 +1956,69 @@ impl<Backend: KvsBackend, PathResolver: KvsPathResolver> KvsApi
     ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
     ///   * `ErrorCode::KeyNotFound`: Key wasn't found in KVS nor in defaults
     fn get_value(&self, key: &str) -> Result<KvsValue, ErrorCode> {
-        let data = self.data.lock()?;
-        if let Some(value) = data.kvs_map.get(key) {
-            Ok(value.clone())
+        let start = Instant::now();
+        let mut data = self.lock()?;
+        data.materialize_key(key)?;
+        let value = if let Some(value) = data.kvs_map.get(key) {
+            kvs_checked::verify(value)
+                .map_err(|e| e.with_key(key))?
+                .clone()
         } else if let Some(value) = data.defaults_map.get(key) {
-            Ok(value.clone())
+            value.clone()
         } else {
-            eprintln!("error: get_value could not find key: {key}");
-            Err(ErrorCode::KeyNotFound)
+            kvs_error!(
+                instance_id = self.parameters.instance_id,
+                key = key,
+                "get_value could not find key: {key}"
+            );
+            return Err(ErrorCode::KeyNotFound.with_key(key));
+        };
+        self.record_access(&mut data, key, false);
+        if let Some(metrics) = &self.metrics {
+            metrics.on_get(self.parameters.instance_id, key, start.elapsed());
+        }
+        Ok(value)
+    }
+
+    /// Get the assigned values for multiple keys
+    ///
+    /// The instance is locked once for all keys.
+    ///
+    /// # Features
+    ///   * `FEAT_REQ__KVS__default_values`
+    ///
+    /// # Parameters
+    ///   * `keys`: Keys to retrieve the values from
+    ///
+    /// # Return Values
+    ///   * Ok: Values in the order of `keys`
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    ///   * `ErrorCode::KeyNotFound`: A key wasn't found in KVS nor in defaults
+    fn get_values(&self, keys: &[&str]) -> Result<Vec<KvsValue>, ErrorCode> {
+        let mut data = self.lock()?;
+        let mut values = Vec::with_capacity(keys.len());
+        for key in keys {
+            data.materialize_key(key)?;
+            let value = if let Some(value) = data.kvs_map.get(*key) {
+                kvs_checked::verify(value)
+                    .map_err(|e| e.with_key(*key))?
+                    .clone()
+            } else if let Some(value) = data.defaults_map.get(*key) {
+                value.clone()
+            } else {
+                kvs_error!(
+                    instance_id = self.parameters.instance_id,
+                    key = key,
+                    "get_values could not find key: {key}"
+                );
+                return Err(ErrorCode::KeyNotFound.with_key(*key));
+            };
+            values.push(value);
+        }
+        for key in keys {
+            self.record_access(&mut data, key, false);
         }
+        Ok(values)
     }
 
     /// Get the assigned value for a given key
@@ -231,38 +2037,31 @@ impl<Backend: KvsBackend, PathResolver: KvsPathResolver> KvsApi
     ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
     ///   * `ErrorCode::ConversionFailed`: Type conversion failed
     ///   * `ErrorCode::KeyNotFound`: Key wasn't found in KVS nor in defaults
-    fn get_value_as<T>(&self, key: &str) -> Result<T, ErrorCode>
-    where
-        for<'a> T: TryFrom<&'a KvsValue> + std::clone::Clone,
-        for<'a> <T as TryFrom<&'a KvsValue>>::Error: std::fmt::Debug,
-    {
-        let data = self.data.lock()?;
-        if let Some(value) = data.kvs_map.get(key) {
-            match T::try_from(value) {
-                Ok(value) => Ok(value),
-                Err(err) => {
-                    eprintln!(
-                        "error: get_value could not convert KvsValue from KVS store: {err:#?}"
-                    );
-                    Err(ErrorCode::ConversionFailed)
-                }
-            }
+    fn get_value_as<T: KvsDeserialize>(&self, key: &str) -> Result<T, ErrorCode> {
+        let start = Instant::now();
+        let mut data = self.lock()?;
+        data.materialize_key(key)?;
+        let result = if let Some(value) = data.kvs_map.get(key) {
+            kvs_checked::verify(value).and_then(T::from_kvs_value)
         } else if let Some(value) = data.defaults_map.get(key) {
             // check if key has a default value
-            match T::try_from(value) {
-                Ok(value) => Ok(value),
-                Err(err) => {
-                    eprintln!(
-                        "error: get_value could not convert KvsValue from default store: {err:#?}"
-                    );
-                    Err(ErrorCode::ConversionFailed)
-                }
-            }
+            T::from_kvs_value(value)
         } else {
-            eprintln!("error: get_value could not find key: {key}");
+            kvs_error!(
+                instance_id = self.parameters.instance_id,
+                key = key,
+                "get_value could not find key: {key}"
+            );
 
-            Err(ErrorCode::KeyNotFound)
+            return Err(ErrorCode::KeyNotFound.with_key(key));
+        };
+        if result.is_ok() {
+            self.record_access(&mut data, key, false);
+            if let Some(metrics) = &self.metrics {
+                metrics.on_get(self.parameters.instance_id, key, start.elapsed());
+            }
         }
+        result.map_err(|e| e.with_key(key))
     }
 
     /// Get default value for a given key
@@ -278,11 +2077,11 @@ impl<Backend: KvsBackend, PathResolver: KvsPathResolver> KvsApi
     ///   * Ok: `KvsValue` for the key
     ///   * `ErrorCode::KeyNotFound`: Key not found in defaults
     fn get_default_value(&self, key: &str) -> Result<KvsValue, ErrorCode> {
-        let data = self.data.lock()?;
+        let data = self.lock()?;
         if let Some(value) = data.defaults_map.get(key) {
             Ok(value.clone())
         } else {
-            Err(ErrorCode::KeyNotFound)
+            Err(ErrorCode::KeyNotFound.with_key(key))
         }
     }
 
@@ -300,13 +2099,13 @@ impl<Backend: KvsBackend, PathResolver: KvsPathResolver> KvsApi
     ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
     ///   * `ErrorCode::KeyNotFound`: Key wasn't found
     fn is_value_default(&self, key: &str) -> Result<bool, ErrorCode> {
-        let data = self.data.lock()?;
-        if data.kvs_map.contains_key(key) {
+        let data = self.lock()?;
+        if data.contains_key(key) {
             Ok(false)
         } else if data.defaults_map.contains_key(key) {
             Ok(true)
         } else {
-            Err(ErrorCode::KeyNotFound)
+            Err(ErrorCode::KeyNotFound.with_key(key))
         }
     }
 
@@ -318,17 +2117,125 @@ impl<Backend: KvsBackend, PathResolver: KvsPathResolver> KvsApi
     ///
     /// # Return Values
     ///   * Ok: Value was assigned to key
+    ///   * `ErrorCode::InvalidKey`: Key violates the key policy
     ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
-    fn set_value<S: Into<String>, V: Into<KvsValue>>(
+    ///   * `ErrorCode::QuotaExceeded`: Value would exceed the maximum size, value size or key
+    ///     count, nothing was changed
+    ///   * `ErrorCode::TypeMismatch`: Strict typing is enabled and the key has a value of another
+    ///     type
+    ///   * `ErrorCode::ValidationFailed`: A validator rejected the value
+    fn set_value<S: Into<String>, V: KvsSerialize>(
         &self,
         key: S,
         value: V,
     ) -> Result<(), ErrorCode> {
-        let mut data = self.data.lock()?;
-        data.kvs_map.insert(key.into(), value.into());
+        let start = Instant::now();
+        let key = key.into();
+        self.parameters.key_policy.validate(&key)?;
+        let value = value.to_kvs_value();
+        let mut data = self.lock_keys([key.as_str()])?;
+        self.record_access(&mut data, &key, true);
+        let value = if data.kvs_map.get(&key).is_some_and(kvs_checked::is_checked) {
+            kvs_checked::checked(value)
+        } else {
+            value
+        };
+
+        // Storing an equal value doesn't require a flush.
+        if data.kvs_map.get(&key) == Some(&value) {
+            if let Some(metrics) = &self.metrics {
+                metrics.on_set(self.parameters.instance_id, &key, start.elapsed());
+            }
+            return Ok(());
+        }
+        self.check_type(
+            &key,
+            data.kvs_map
+                .get(&key)
+                .or_else(|| data.defaults_map.get(&key)),
+            &value,
+        )?;
+        self.check_value(&key, &value)?;
+        let previous_len = data.kvs_map.len();
+        let previous = data.kvs_map.insert(key.clone(), value);
+        if let Err(e) = self
+            .check_limits(&data.kvs_map, &[&key], previous_len)
+            .and_then(|()| self.check_size(&data.kvs_map))
+        {
+            match previous {
+                Some(previous) => data.kvs_map.insert(key, previous),
+                None => data.kvs_map.remove(&key),
+            };
+            return Err(e);
+        }
+        data.dirty = true;
+        self.journal_keys(&data, [key.as_str()])?;
+        if let Some(metrics) = &self.metrics {
+            metrics.on_set(self.parameters.instance_id, &key, start.elapsed());
+        }
         Ok(())
     }
 
+    /// Assign multiple values at once
+    ///
+    /// The instance is locked once for all values. Either all values are assigned or none.
+    ///
+    /// # Parameters
+    ///   * `values`: Key-value pairs to assign
+    ///
+    /// # Return Values
+    ///   * Ok: Values were assigned
+    ///   * `ErrorCode::InvalidKey`: A key violates the key policy, nothing was changed
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    ///   * `ErrorCode::QuotaExceeded`: Values would exceed the maximum size, value size or key
+    ///     count, nothing was changed
+    ///   * `ErrorCode::TypeMismatch`: Strict typing is enabled and a key has a value of another
+    ///     type, nothing was changed
+    ///   * `ErrorCode::ValidationFailed`: A validator rejected a value, nothing was changed
+    fn set_values<I: IntoIterator<Item = (String, KvsValue)>>(
+        &self,
+        values: I,
+    ) -> Result<(), ErrorCode> {
+        let values: Vec<(String, KvsValue)> = values.into_iter().collect();
+        for (key, _) in &values {
+            self.parameters.key_policy.validate(key)?;
+        }
+        let mut data = self.lock_keys(values.iter().map(|(key, _)| key.as_str()))?;
+        let mut changes = Vec::new();
+        for (key, value) in values {
+            self.record_access(&mut data, &key, true);
+            let value = if data.kvs_map.get(&key).is_some_and(kvs_checked::is_checked) {
+                kvs_checked::checked(value)
+            } else {
+                value
+            };
+            // Storing an equal value doesn't require a flush.
+            if data.kvs_map.get(&key) != Some(&value) {
+                changes.push((key, value));
+            }
+        }
+        for (key, value) in &changes {
+            let current = data.kvs_map.get(key).or_else(|| data.defaults_map.get(key));
+            self.check_type(key, current, value)?;
+            self.check_value(key, value)?;
+        }
+        if changes.is_empty() {
+            return Ok(());
+        }
+        if self.has_limits() {
+            let mut kvs_map = data.kvs_map.clone();
+            kvs_map.extend(changes.iter().cloned());
+            let keys: Vec<&str> = changes.iter().map(|(key, _)| key.as_str()).collect();
+            self.check_limits(&kvs_map, &keys, data.kvs_map.len())?;
+            self.check_size(&kvs_map)?;
+        }
+
+        let keys: Vec<String> = changes.iter().map(|(key, _)| key.clone()).collect();
+        data.kvs_map.extend(changes);
+        data.dirty = true;
+        self.journal_keys(&data, keys.iter().map(String::as_str))
+    }
+
     /// Remove a key
     ///
     /// # Parameters
@@ -339,49 +2246,152 @@ impl<Backend: KvsBackend, PathResolver: KvsPathResolver> KvsApi
     ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
     ///   * `ErrorCode::KeyNotFound`: Key not found
     fn remove_key(&self, key: &str) -> Result<(), ErrorCode> {
-        let mut data = self.data.lock()?;
+        let mut data = self.lock_keys([key])?;
         if data.kvs_map.remove(key).is_some() {
-            Ok(())
+            data.dirty = true;
+            self.record_access(&mut data, key, true);
+            self.journal_keys(&data, [key])
         } else {
-            Err(ErrorCode::KeyNotFound)
+            Err(ErrorCode::KeyNotFound.with_key(key))
+        }
+    }
+
+    /// Remove multiple keys at once
+    ///
+    /// The instance is locked once for all keys. Either all keys are removed or none.
+    ///
+    /// # Parameters
+    ///   * `keys`: Keys to remove
+    ///
+    /// # Return Values
+    ///   * Ok: Keys removed successfully
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    ///   * `ErrorCode::KeyNotFound`: A key wasn't found, nothing was removed
+    fn remove_keys(&self, keys: &[&str]) -> Result<(), ErrorCode> {
+        let mut data = self.lock_keys(keys.iter().copied())?;
+        if let Some(key) = keys.iter().find(|key| !data.kvs_map.contains_key(**key)) {
+            kvs_error!(
+                instance_id = self.parameters.instance_id,
+                key = key,
+                "remove_keys could not find key: {key}"
+            );
+            return Err(ErrorCode::KeyNotFound.with_key(*key));
+        }
+        for key in keys {
+            data.kvs_map.remove(*key);
+            self.record_access(&mut data, key, true);
+        }
+        if !keys.is_empty() {
+            data.dirty = true;
+        }
+        self.journal_keys(&data, keys.iter().copied())
+    }
+
+    /// Remove all keys starting with a prefix
+    ///
+    /// # Parameters
+    ///   * `prefix`: Key prefix, e.g. `module.submodule.`
+    ///
+    /// # Return Values
+    ///   * Ok: Number of removed keys
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    fn remove_keys_with_prefix(&self, prefix: &str) -> Result<usize, ErrorCode> {
+        let mut data = self.lock_data()?;
+        let keys: Vec<String> = data
+            .kvs_map
+            .keys()
+            .filter(|key| key.starts_with(prefix))
+            .cloned()
+            .collect();
+        for key in &keys {
+            data.kvs_map.remove(key);
+            self.record_access(&mut data, key, true);
+        }
+        if !keys.is_empty() {
+            data.dirty = true;
+        }
+        self.journal_keys(&data, keys.iter().map(String::as_str))?;
+        Ok(keys.len())
+    }
+
+    /// Run a read-modify-write transaction
+    ///
+    /// The closure gets a [`KvsTransaction`] to read and write keys. Writes are applied at once
+    /// if the closure returns `Ok` and discarded otherwise. The instance is locked while the
+    /// closure runs, other handles never observe a partially applied transaction.
+    ///
+    /// # Parameters
+    ///   * `f`: Closure performing the transaction
+    ///
+    /// # Return Values
+    ///   * Ok: Transaction committed, result of the closure
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    ///   * `ErrorCode::InvalidKey`: A written key violates the key policy, nothing was changed
+    ///   * `ErrorCode::QuotaExceeded`: Changes would exceed the maximum size, value size or key
+    ///     count, nothing was changed
+    ///   * `ErrorCode::TypeMismatch`: Strict typing is enabled and a written key has a value of
+    ///     another type, nothing was changed
+    ///   * `ErrorCode::ValidationFailed`: A validator rejected a written value, nothing was changed
+    ///   * Err: Error returned by the closure, nothing was changed
+    fn transaction<R, F>(&self, f: F) -> Result<R, ErrorCode>
+    where
+        F: FnOnce(&mut KvsTransaction) -> Result<R, ErrorCode>,
+    {
+        let mut data = self.lock_data()?;
+        let mut txn = KvsTransaction::new(&data.kvs_map, &data.defaults_map);
+        let result = f(&mut txn)?;
+        let (changes, reads) = txn.into_parts();
+        for (key, value) in changes
+            .iter()
+            .filter_map(|(key, change)| change.as_ref().map(|value| (key, value)))
+        {
+            self.parameters.key_policy.validate(key)?;
+            let current = data.kvs_map.get(key).or_else(|| data.defaults_map.get(key));
+            self.check_type(key, current, value)?;
+            self.check_value(key, value)?;
+        }
+        if !changes.is_empty() && self.has_limits() {
+            let mut kvs_map = data.kvs_map.clone();
+            kvs_transaction::apply_changes(&mut kvs_map, changes.clone());
+            let keys: Vec<&str> = changes.keys().map(String::as_str).collect();
+            self.check_limits(&kvs_map, &keys, data.kvs_map.len())?;
+            self.check_size(&kvs_map)?;
+        }
+
+        for key in &reads {
+            self.record_access(&mut data, key, false);
+        }
+        if !changes.is_empty() {
+            for key in changes.keys() {
+                self.record_access(&mut data, key, true);
+            }
+            let keys: Vec<String> = changes.keys().cloned().collect();
+            kvs_transaction::apply_changes(&mut data.kvs_map, changes);
+            data.dirty = true;
+            self.journal_keys(&data, keys.iter().map(String::as_str))?;
         }
+        Ok(result)
     }
 
     /// Flush the in-memory key-value-storage to the persistent storage
     ///
+    /// Nothing is written if the data is unchanged since it was loaded or last flushed and the
+    /// current snapshot exists, see [`GenericKvs::is_dirty`].
+    ///
     /// # Features
     ///   * `FEAT_REQ__KVS__snapshots`
     ///   * `FEAT_REQ__KVS__persistency`
     ///   * `FEAT_REQ__KVS__integrity_check`
     ///
     /// # Return Values
-    ///   * Ok: Flush successful
+    ///   * Ok: Flush successful or nothing to flush
     ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
     ///   * `ErrorCode::JsonGeneratorError`: Failed to serialize to JSON
     ///   * `ErrorCode::ConversionFailed`: JSON could not serialize into String
     ///   * `ErrorCode::UnmappedError`: Unmapped error
     fn flush(&self) -> Result<(), ErrorCode> {
-        let data = self.data.lock()?;
-        self.snapshot_rotate().map_err(|e| {
-            eprintln!("error: snapshot_rotate failed: {e:?}");
-            e
-        })?;
-        let snapshot_id = SnapshotId(0);
-        let kvs_path = PathResolver::kvs_file_path(
-            &self.parameters.working_dir,
-            self.parameters.instance_id,
-            snapshot_id,
-        );
-        let hash_path = PathResolver::hash_file_path(
-            &self.parameters.working_dir,
-            self.parameters.instance_id,
-            snapshot_id,
-        );
-        Backend::save_kvs(&data.kvs_map, &kvs_path, Some(&hash_path)).map_err(|e| {
-            eprintln!("error: save_kvs failed: {e:?}");
-            e
-        })?;
-        Ok(())
+        let mut data = self.lock()?;
+        self.flush_changed(&mut data)
     }
 
     /// Get the count of snapshots
@@ -393,12 +2403,8 @@ impl<Backend: KvsBackend, PathResolver: KvsPathResolver> KvsApi
 
         for idx in 0..KVS_MAX_SNAPSHOTS {
             let snapshot_id = SnapshotId(idx);
-            let snapshot_path = PathResolver::kvs_file_path(
-                &self.parameters.working_dir,
-                self.parameters.instance_id,
-                snapshot_id,
-            );
-            if !snapshot_path.exists() {
+            let snapshot_path = self.parameters.kvs_file_path::<PathResolver>(snapshot_id);
+            if !Backend::exists(&snapshot_path) {
                 break;
             }
 
@@ -435,29 +2441,15 @@ impl<Backend: KvsBackend, PathResolver: KvsPathResolver> KvsApi
     ///   * `ErrorCode::KvsHashFileReadError`: KVS hash file read error
     ///   * `ErrorCode::UnmappedError`: Generic error
     fn snapshot_restore(&self, snapshot_id: SnapshotId) -> Result<(), ErrorCode> {
-        let mut data = self.data.lock()?;
-        // fail if the snapshot ID is the current KVS
-        if snapshot_id == SnapshotId(0) {
-            eprintln!("error: tried to restore current KVS as snapshot");
-            return Err(ErrorCode::InvalidSnapshotId);
-        }
-
-        if self.snapshot_count() < snapshot_id.0 {
-            eprintln!("error: tried to restore a non-existing snapshot");
-            return Err(ErrorCode::InvalidSnapshotId);
-        }
-
-        let kvs_path = PathResolver::kvs_file_path(
-            &self.parameters.working_dir,
-            self.parameters.instance_id,
-            snapshot_id,
-        );
-        let hash_path = PathResolver::hash_file_path(
-            &self.parameters.working_dir,
-            self.parameters.instance_id,
+        let mut data = self.lock()?;
+        data.kvs_map = self.load_snapshot(snapshot_id)?;
+        data.lazy = None;
+        data.dirty = true;
+        self.journal_reset(&data)?;
+        kvs_event::emit(KvsEvent::SnapshotRestored {
+            instance_id: self.parameters.instance_id,
             snapshot_id,
-        );
-        data.kvs_map = Backend::load_kvs(&kvs_path, Some(&hash_path))?;
+        });
 
         Ok(())
     }
@@ -471,12 +2463,8 @@ impl<Backend: KvsBackend, PathResolver: KvsPathResolver> KvsApi
     ///   * `Ok`: Filename for ID
     ///   * `ErrorCode::FileNotFound`: KVS file for snapshot ID not found
     fn get_kvs_filename(&self, snapshot_id: SnapshotId) -> Result<PathBuf, ErrorCode> {
-        let path = PathResolver::kvs_file_path(
-            &self.parameters.working_dir,
-            self.parameters.instance_id,
-            snapshot_id,
-        );
-        if !path.exists() {
+        let path = self.parameters.kvs_file_path::<PathResolver>(snapshot_id);
+        if !Backend::exists(&path) {
             Err(ErrorCode::FileNotFound)
         } else {
             Ok(path)
@@ -492,30 +2480,78 @@ impl<Backend: KvsBackend, PathResolver: KvsPathResolver> KvsApi
     ///   * `Ok`: Hash filename for ID
     ///   * `ErrorCode::FileNotFound`: Hash file for snapshot ID not found
     fn get_hash_filename(&self, snapshot_id: SnapshotId) -> Result<PathBuf, ErrorCode> {
-        let path = PathResolver::hash_file_path(
-            &self.parameters.working_dir,
-            self.parameters.instance_id,
-            snapshot_id,
-        );
-        if !path.exists() {
+        let path = self.parameters.hash_file_path::<PathResolver>(snapshot_id);
+        if !Backend::exists(&path) {
             Err(ErrorCode::FileNotFound)
         } else {
             Ok(path)
         }
     }
+
+    /// Get whether changes are flushed when this handle is dropped
+    ///
+    /// # Return Values
+    ///   * FlushOnExit: Current setting of this handle
+    fn flush_on_exit(&self) -> FlushOnExit {
+        self.flush_on_exit
+    }
+
+    /// Control flushing of changes when this handle is dropped
+    ///
+    /// Handles opened with the builder flush on drop unless configured otherwise with
+    /// [`GenericKvsBuilder::flush_on_exit`](crate::kvs_builder::GenericKvsBuilder::flush_on_exit).
+    ///
+    /// # Parameters
+    ///   * `flush_on_exit`: Flush on drop
+    fn set_flush_on_exit(&mut self, flush_on_exit: FlushOnExit) {
+        self.flush_on_exit = flush_on_exit;
+    }
+}
+
+impl<Backend: KvsBackend, PathResolver: KvsPathResolver, Model: ValueModel> Drop
+    for GenericKvs<Backend, PathResolver, Model>
+{
+    fn drop(&mut self) {
+        if self.flush_on_exit == FlushOnExit::No || !self.is_dirty().unwrap_or(false) {
+            return;
+        }
+        if let Err(e) = self.flush() {
+            kvs_error!(
+                instance_id = self.parameters.instance_id,
+                "flushing instance {} on exit failed: {e}",
+                self.parameters.instance_id
+            );
+        }
+    }
 }
 
 #[cfg(test)]
 mod kvs_tests {
     use crate::error_code::ErrorCode;
+    #[cfg(feature = "json-backend")]
     use crate::json_backend::JsonBackend;
-    use crate::kvs::{GenericKvs, KvsParameters, KVS_MAX_SNAPSHOTS};
-    use crate::kvs_api::{InstanceId, KvsApi, KvsDefaults, KvsLoad, SnapshotId};
+    #[cfg(feature = "json-backend")]
+    use crate::kvs::KvsHealth;
+    use crate::kvs::{GenericKvs, KeyAccessStats, KvsParameters, KVS_MAX_SNAPSHOTS};
+    #[cfg(all(feature = "json-backend", feature = "snapshots"))]
+    use crate::kvs::{KvsRepairAction, KvsSnapshotDiff, KvsSnapshotStatus};
+    #[cfg(feature = "json-backend")]
+    use crate::kvs_api::{FlushOnExit, SyncPolicy};
+    use crate::kvs_api::{
+        InstanceId, KeyScope, KvsApi, KvsDefaults, KvsKeyPolicy, KvsLoad, SnapshotId,
+    };
     use crate::kvs_backend::{KvsBackend, KvsPathResolver};
     use crate::kvs_builder::KvsData;
+    #[cfg(feature = "json-backend")]
+    use crate::kvs_value::KvsValueKind;
     use crate::kvs_value::{KvsMap, KvsValue};
+    #[cfg(feature = "json-backend")]
+    use std::collections::HashMap;
     use std::path::PathBuf;
     use std::sync::{Arc, Mutex};
+    #[cfg(feature = "json-backend")]
+    use std::time::SystemTime;
+    #[cfg(feature = "json-backend")]
     use tempfile::tempdir;
 
     /// Most tests can be performed with mocked backend.
@@ -571,6 +2607,14 @@ mod kvs_tests {
         fn defaults_file_path(_working_dir: &std::path::Path, _instance_id: InstanceId) -> PathBuf {
             unimplemented!()
         }
+
+        fn global_defaults_file_name() -> String {
+            unimplemented!()
+        }
+
+        fn global_defaults_file_path(_working_dir: &std::path::Path) -> PathBuf {
+            unimplemented!()
+        }
     }
 
     fn get_kvs<B: KvsBackend + KvsPathResolver>(
@@ -579,15 +2623,10 @@ mod kvs_tests {
         defaults_map: KvsMap,
     ) -> GenericKvs<B> {
         let instance_id = InstanceId(1);
-        let data = Arc::new(Mutex::new(KvsData {
-            kvs_map,
-            defaults_map,
-        }));
+        let data = Arc::new(Mutex::new(KvsData::new(kvs_map, defaults_map)));
         let parameters = KvsParameters {
-            instance_id,
-            defaults: KvsDefaults::Optional,
-            kvs_load: KvsLoad::Optional,
             working_dir,
+            ..KvsParameters::new(instance_id)
         };
         GenericKvs::<B>::new(data, parameters)
     }
@@ -653,6 +2692,30 @@ mod kvs_tests {
             .is_err_and(|e| e == ErrorCode::KeyDefaultNotFound));
     }
 
+    #[test]
+    fn test_reset_key_or_remove() {
+        let kvs = get_kvs::<MockBackend>(
+            PathBuf::new(),
+            KvsMap::from([
+                ("example1".to_string(), KvsValue::from("explicit_value")),
+                ("example2".to_string(), KvsValue::from(true)),
+            ]),
+            KvsMap::from([("example1".to_string(), KvsValue::from("default_value"))]),
+        );
+
+        assert!(kvs.reset_key_or_remove("example1").unwrap());
+        assert_eq!(
+            kvs.get_value_as::<String>("example1").unwrap(),
+            "default_value"
+        );
+        assert!(!kvs.reset_key_or_remove("example2").unwrap());
+        assert!(kvs
+            .get_value("example2")
+            .is_err_and(|e| e == ErrorCode::KeyNotFound));
+        assert!(!kvs.reset_key_or_remove("missing").unwrap());
+        assert!(kvs.is_dirty().unwrap());
+    }
+
     #[test]
     fn test_get_all_keys_some() {
         let kvs = get_kvs::<MockBackend>(
@@ -664,17 +2727,101 @@ mod kvs_tests {
             KvsMap::new(),
         );
 
-        let mut keys = kvs.get_all_keys().unwrap();
+        let mut keys = kvs.get_all_keys().unwrap();
+        keys.sort();
+        assert_eq!(keys, vec!["example1", "example2"]);
+    }
+
+    #[test]
+    fn test_get_keys_scopes() {
+        let kvs = get_kvs::<MockBackend>(
+            PathBuf::new(),
+            KvsMap::from([
+                ("stored".to_string(), KvsValue::from(1.0)),
+                ("both".to_string(), KvsValue::from(2.0)),
+            ]),
+            KvsMap::from([
+                ("both".to_string(), KvsValue::from(0.0)),
+                ("default".to_string(), KvsValue::from(0.0)),
+            ]),
+        );
+
+        let sorted = |mut keys: Vec<String>| {
+            keys.sort();
+            keys
+        };
+        assert_eq!(
+            sorted(kvs.get_keys(KeyScope::Stored).unwrap()),
+            sorted(kvs.get_all_keys().unwrap())
+        );
+        assert_eq!(sorted(kvs.get_default_keys().unwrap()), ["both", "default"]);
+        assert_eq!(
+            sorted(kvs.get_all_keys_with_defaults().unwrap()),
+            ["both", "default", "stored"]
+        );
+    }
+
+    #[test]
+    fn test_get_all_keys_empty() {
+        let kvs = get_kvs::<MockBackend>(PathBuf::new(), KvsMap::new(), KvsMap::new());
+
+        let keys = kvs.get_all_keys().unwrap();
+        assert_eq!(keys.len(), 0);
+    }
+
+    #[test]
+    fn test_get_keys_with_prefix() {
+        let kvs = get_kvs::<MockBackend>(
+            PathBuf::new(),
+            KvsMap::from([
+                ("net.ip".to_string(), KvsValue::from("10.0.0.1")),
+                ("net.mask".to_string(), KvsValue::from(24.0)),
+                ("network".to_string(), KvsValue::from(true)),
+            ]),
+            KvsMap::new(),
+        );
+
+        let mut keys = kvs.get_keys_with_prefix("net.").unwrap();
+        keys.sort();
+        assert_eq!(keys, vec!["net.ip", "net.mask"]);
+        assert!(kvs.get_keys_with_prefix("disk.").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_get_keys_matching() {
+        let kvs = get_kvs::<MockBackend>(
+            PathBuf::new(),
+            KvsMap::from([
+                ("eth0.enabled".to_string(), KvsValue::from(true)),
+                ("eth1.enabled".to_string(), KvsValue::from(false)),
+                ("eth1.mtu".to_string(), KvsValue::from(1500.0)),
+            ]),
+            KvsMap::new(),
+        );
+
+        let mut keys = kvs.get_keys_matching("eth?.enabled").unwrap();
         keys.sort();
-        assert_eq!(keys, vec!["example1", "example2"]);
+        assert_eq!(keys, vec!["eth0.enabled", "eth1.enabled"]);
+        assert_eq!(kvs.get_keys_matching("*.mtu").unwrap(), vec!["eth1.mtu"]);
+        assert!(kvs.get_keys_matching("eth").unwrap().is_empty());
     }
 
     #[test]
-    fn test_get_all_keys_empty() {
-        let kvs = get_kvs::<MockBackend>(PathBuf::new(), KvsMap::new(), KvsMap::new());
+    fn test_remove_keys_with_prefix() {
+        let kvs = get_kvs::<MockBackend>(
+            PathBuf::new(),
+            KvsMap::from([
+                ("net.ip".to_string(), KvsValue::from("10.0.0.1")),
+                ("net.mask".to_string(), KvsValue::from(24.0)),
+                ("network".to_string(), KvsValue::from(true)),
+            ]),
+            KvsMap::new(),
+        );
 
-        let keys = kvs.get_all_keys().unwrap();
-        assert_eq!(keys.len(), 0);
+        assert_eq!(kvs.remove_keys_with_prefix("net.").unwrap(), 2);
+        assert_eq!(kvs.get_all_keys().unwrap(), vec!["network"]);
+        assert!(kvs.is_dirty().unwrap());
+        assert_eq!(kvs.remove_keys_with_prefix("net.").unwrap(), 0);
     }
 
     #[test]
@@ -763,6 +2910,34 @@ mod kvs_tests {
         assert_eq!(value, "value");
     }
 
+    #[test]
+    fn test_get_value_as_user_type() {
+        #[derive(Debug, PartialEq)]
+        struct Config {
+            name: String,
+            retries: u32,
+        }
+        crate::impl_kvs_object!(Config { name, retries });
+
+        let kvs = get_kvs::<MockBackend>(PathBuf::new(), KvsMap::new(), KvsMap::new());
+        let config = Config {
+            name: "cfg".to_string(),
+            retries: 3,
+        };
+        kvs.set_value("config", config).unwrap();
+
+        assert_eq!(
+            kvs.get_value_as::<Config>("config").unwrap(),
+            Config {
+                name: "cfg".to_string(),
+                retries: 3,
+            }
+        );
+        assert!(kvs
+            .get_value_as::<String>("config")
+            .is_err_and(|e| e == ErrorCode::ConversionFailed));
+    }
+
     #[test]
     fn test_get_value_as_available_default() {
         let kvs = get_kvs::<MockBackend>(
@@ -912,6 +3087,64 @@ mod kvs_tests {
         assert_eq!(kvs.get_value_as::<String>("key").unwrap(), "new_value");
     }
 
+    #[test]
+    fn test_access_stats_disabled() {
+        let kvs = get_kvs::<MockBackend>(
+            PathBuf::new(),
+            KvsMap::from([("example1".to_string(), KvsValue::from("value"))]),
+            KvsMap::new(),
+        );
+        kvs.get_value("example1").unwrap();
+        assert!(kvs.access_stats().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_access_stats_enabled() {
+        let mut kvs = get_kvs::<MockBackend>(
+            PathBuf::new(),
+            KvsMap::from([
+                ("example1".to_string(), KvsValue::from("value")),
+                ("unused".to_string(), KvsValue::from(1)),
+            ]),
+            KvsMap::from([("example2".to_string(), KvsValue::from(true))]),
+        );
+        kvs.parameters.access_stats = true;
+
+        kvs.get_value("example1").unwrap();
+        kvs.get_value_as::<String>("example1").unwrap();
+        kvs.get_value_as::<bool>("example2").unwrap();
+        kvs.set_value("example3", 3).unwrap();
+        kvs.remove_key("example3").unwrap();
+        // Failed accesses are not counted.
+        let _ = kvs.get_value("missing");
+        let _ = kvs.get_value_as::<f64>("example1");
+
+        let stats = kvs.access_stats().unwrap();
+        assert_eq!(stats.len(), 4);
+        assert_eq!(
+            stats["example1"],
+            KeyAccessStats {
+                reads: 2,
+                writes: 0
+            }
+        );
+        assert_eq!(
+            stats["example2"],
+            KeyAccessStats {
+                reads: 1,
+                writes: 0
+            }
+        );
+        assert_eq!(
+            stats["example3"],
+            KeyAccessStats {
+                reads: 0,
+                writes: 2
+            }
+        );
+        assert_eq!(stats["unused"], KeyAccessStats::default());
+    }
+
     #[test]
     fn test_remove_key_found() {
         let kvs = get_kvs::<MockBackend>(
@@ -939,27 +3172,530 @@ mod kvs_tests {
         );
 
         assert!(kvs
-            .remove_key("invalid_key")
-            .is_err_and(|e| e == ErrorCode::KeyNotFound));
+            .remove_key("invalid_key")
+            .is_err_and(|e| e == ErrorCode::KeyNotFound));
+    }
+
+    #[test]
+    fn test_transaction_commit() {
+        let mut kvs = get_kvs::<MockBackend>(
+            PathBuf::new(),
+            KvsMap::from([("counter".to_string(), KvsValue::from(1))]),
+            KvsMap::from([("limit".to_string(), KvsValue::from(10))]),
+        );
+        kvs.parameters.access_stats = true;
+
+        let counter = kvs
+            .transaction(|txn| {
+                let counter = txn.get_value_as::<i32>("counter")? + 1;
+                if counter > txn.get_value_as::<i32>("limit")? {
+                    return Err(ErrorCode::QuotaExceeded);
+                }
+                txn.set_value("counter", counter);
+                txn.set_value("previous", counter - 1);
+                Ok(counter)
+            })
+            .unwrap();
+
+        assert_eq!(counter, 2);
+        assert_eq!(kvs.get_value_as::<i32>("counter").unwrap(), 2);
+        assert_eq!(kvs.get_value_as::<i32>("previous").unwrap(), 1);
+        assert!(kvs.data.lock().unwrap().dirty);
+        assert_eq!(
+            kvs.access_stats().unwrap()["counter"],
+            KeyAccessStats {
+                reads: 2,
+                writes: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_transaction_discarded_on_error() {
+        let kvs = get_kvs::<MockBackend>(
+            PathBuf::new(),
+            KvsMap::from([("a".to_string(), KvsValue::from(1))]),
+            KvsMap::new(),
+        );
+
+        let result: Result<(), ErrorCode> = kvs.transaction(|txn| {
+            txn.set_value("b", 2);
+            txn.remove_key("a")?;
+            txn.remove_key("missing")
+        });
+
+        assert!(result.is_err_and(|e| e == ErrorCode::KeyNotFound));
+        assert!(kvs.key_exists("a").unwrap());
+        assert!(!kvs.key_exists("b").unwrap());
+        assert!(!kvs.data.lock().unwrap().dirty);
+    }
+
+    #[test]
+    fn test_transaction_isolated() {
+        let kvs = get_kvs::<MockBackend>(PathBuf::new(), KvsMap::new(), KvsMap::new());
+        let other = GenericKvs::<MockBackend>::new(kvs.data.clone(), kvs.parameters().clone());
+
+        let (started_tx, started_rx) = std::sync::mpsc::channel();
+        let (finish_tx, finish_rx) = std::sync::mpsc::channel::<()>();
+        let thread = std::thread::spawn(move || {
+            kvs.transaction(|txn| {
+                txn.set_value("a", 1);
+                started_tx.send(()).unwrap();
+                finish_rx.recv().unwrap();
+                txn.set_value("b", 2);
+                Ok(())
+            })
+            .unwrap();
+        });
+
+        started_rx.recv().unwrap();
+        let reader = std::thread::spawn(move || other.get_all_keys().unwrap().len());
+        finish_tx.send(()).unwrap();
+        // Reader is blocked until the transaction is applied.
+        assert_eq!(reader.join().unwrap(), 2);
+        thread.join().unwrap();
+    }
+
+    #[test]
+    fn test_increment_concurrent() {
+        let kvs = get_kvs::<MockBackend>(PathBuf::new(), KvsMap::new(), KvsMap::new());
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                let kvs =
+                    GenericKvs::<MockBackend>::new(kvs.data.clone(), kvs.parameters().clone());
+                scope.spawn(move || {
+                    for _ in 0..100 {
+                        kvs.increment("counter", 1).unwrap();
+                    }
+                });
+            }
+        });
+        assert_eq!(kvs.get_value("counter").unwrap(), KvsValue::I64(400));
+        assert!(kvs.is_dirty().unwrap());
+    }
+
+    #[cfg(feature = "json-backend")]
+    #[test]
+    fn test_flush() {
+        let dir = tempdir().unwrap();
+        let dir_path = dir.path().to_path_buf();
+        let kvs = get_kvs::<JsonBackend>(
+            dir_path,
+            KvsMap::from([("key".to_string(), KvsValue::from("value"))]),
+            KvsMap::new(),
+        );
+
+        kvs.flush().unwrap();
+        let snapshot_id = SnapshotId(0);
+        // Functions below check if file exist.
+        kvs.get_kvs_filename(snapshot_id).unwrap();
+        kvs.get_hash_filename(snapshot_id).unwrap();
+    }
+
+    #[cfg(feature = "json-backend")]
+    #[test]
+    fn test_with_model() {
+        let dir = tempdir().unwrap();
+        let mut kvs = get_kvs::<JsonBackend>(
+            dir.path().to_path_buf(),
+            KvsMap::from([("key".to_string(), KvsValue::from(1.5))]),
+            KvsMap::new(),
+        );
+        kvs.set_flush_on_exit(FlushOnExit::Yes);
+
+        let kvs = kvs.with_model::<f64>();
+        assert_eq!(kvs.flush_on_exit(), FlushOnExit::Yes);
+        assert_eq!(kvs.get_model_value("key").unwrap(), 1.5);
+        kvs.set_model_value("other", 2.5).unwrap();
+        assert_eq!(kvs.get_value("other").unwrap(), KvsValue::F64(2.5));
+    }
+
+    #[cfg(feature = "json-backend")]
+    #[test]
+    fn test_storage_usage() {
+        let dir = tempdir().unwrap();
+        let kvs = get_kvs::<JsonBackend>(
+            dir.path().to_path_buf(),
+            KvsMap::from([("key".to_string(), KvsValue::from(1.5))]),
+            KvsMap::from([("default".to_string(), KvsValue::from("value"))]),
+        );
+
+        let usage = kvs.storage_usage().unwrap();
+        kvs.flush().unwrap();
+        let kvs_path = kvs.get_kvs_filename(SnapshotId(0)).unwrap();
+        assert_eq!(usage as u64, std::fs::metadata(kvs_path).unwrap().len());
+    }
+
+    #[cfg(feature = "json-backend")]
+    #[test]
+    fn test_stats() {
+        let dir = tempdir().unwrap();
+        let kvs = get_kvs::<JsonBackend>(
+            dir.path().to_path_buf(),
+            KvsMap::from([
+                ("a".to_string(), KvsValue::I32(1)),
+                ("b".to_string(), KvsValue::I32(2)),
+                ("c".to_string(), KvsValue::from("value")),
+            ]),
+            KvsMap::from([
+                ("c".to_string(), KvsValue::from("default")),
+                ("d".to_string(), KvsValue::from(true)),
+            ]),
+        );
+
+        let stats = kvs.stats().unwrap();
+        assert_eq!(stats.key_count, 3);
+        // Stored map without the object type tag.
+        let map_hint = KvsValue::Object(kvs.data.lock().unwrap().kvs_map.clone())
+            .serialized_size_hint()
+            - r#"{"t":"obj","v":}"#.len();
+        assert_eq!(stats.size_hint, map_hint);
+        assert_eq!(
+            stats.kind_counts,
+            HashMap::from([(KvsValueKind::I32, 2), (KvsValueKind::String, 1)])
+        );
+        assert_eq!(stats.shadowed_defaults, 1);
+        assert_eq!(stats.last_flush, None);
+
+        let before = SystemTime::now();
+        kvs.flush().unwrap();
+        assert!(kvs.stats().unwrap().last_flush.unwrap() >= before);
+    }
+
+    #[cfg(feature = "json-backend")]
+    #[test]
+    fn test_set_value_max_size() {
+        let mut kvs = get_kvs::<JsonBackend>(
+            PathBuf::new(),
+            KvsMap::from([("key".to_string(), KvsValue::from("value"))]),
+            KvsMap::new(),
+        );
+        kvs.parameters.max_size = Some(kvs.storage_usage().unwrap());
+
+        // Same size fits, larger value is rejected and the previous value kept.
+        kvs.set_value("key", "other").unwrap();
+        assert!(kvs
+            .set_value("key", "longer value")
+            .is_err_and(|e| e == ErrorCode::QuotaExceeded));
+        assert!(kvs
+            .set_value("new_key", 1)
+            .is_err_and(|e| e == ErrorCode::QuotaExceeded));
+        assert_eq!(kvs.get_value_as::<String>("key").unwrap(), "other");
+        assert!(!kvs.key_exists("new_key").unwrap());
+    }
+
+    #[cfg(feature = "json-backend")]
+    #[test]
+    fn test_set_value_max_keys() {
+        let mut kvs = get_kvs::<JsonBackend>(
+            PathBuf::new(),
+            KvsMap::from([
+                ("key1".to_string(), KvsValue::from(1)),
+                ("key2".to_string(), KvsValue::from(2)),
+            ]),
+            KvsMap::new(),
+        );
+        kvs.parameters.max_keys = Some(1);
+
+        // Existing keys can be updated beyond the limit, new keys are rejected.
+        kvs.set_value("key1", 3).unwrap();
+        assert!(kvs
+            .set_value("key3", 3)
+            .is_err_and(|e| e == ErrorCode::QuotaExceeded));
+        assert!(kvs
+            .set_values([("key3".to_string(), KvsValue::from(3))])
+            .is_err_and(|e| e == ErrorCode::QuotaExceeded));
+        assert!(!kvs.key_exists("key3").unwrap());
+
+        kvs.remove_key("key2").unwrap();
+        assert!(kvs
+            .set_value("key3", 3)
+            .is_err_and(|e| e == ErrorCode::QuotaExceeded));
+        kvs.remove_key("key1").unwrap();
+        kvs.set_value("key3", 3).unwrap();
+    }
+
+    #[cfg(feature = "json-backend")]
+    #[test]
+    fn test_set_value_max_value_bytes() {
+        let mut kvs = get_kvs::<JsonBackend>(PathBuf::new(), KvsMap::new(), KvsMap::new());
+        let value = KvsValue::from("x".repeat(10));
+        kvs.parameters.max_value_bytes = Some(value.serialized_size_hint());
+
+        kvs.set_value("key", value.clone()).unwrap();
+        assert!(kvs
+            .set_value("key", "x".repeat(11))
+            .is_err_and(|e| e == ErrorCode::QuotaExceeded));
+        assert!(kvs
+            .transaction(|txn| {
+                txn.set_value("other", "x".repeat(11));
+                Ok(())
+            })
+            .is_err_and(|e| e == ErrorCode::QuotaExceeded));
+        assert_eq!(kvs.get_value("key").unwrap(), value);
+        assert!(!kvs.key_exists("other").unwrap());
+    }
+
+    #[cfg(feature = "json-backend")]
+    #[test]
+    fn test_set_value_strict_types() {
+        let mut kvs = get_kvs::<JsonBackend>(
+            PathBuf::new(),
+            KvsMap::from([("number".to_string(), KvsValue::I32(1))]),
+            KvsMap::from([("default".to_string(), KvsValue::Boolean(true))]),
+        );
+        kvs.parameters.strict_types = true;
+
+        kvs.set_value("number", 2).unwrap();
+        kvs.set_value("new", "text").unwrap();
+        assert!(kvs
+            .set_value("number", 2.0)
+            .is_err_and(|e| e == ErrorCode::TypeMismatch));
+        assert!(kvs
+            .set_value("default", KvsValue::Null)
+            .is_err_and(|e| e == ErrorCode::TypeMismatch));
+        assert!(kvs
+            .set_values([
+                ("new".to_string(), KvsValue::from("changed")),
+                ("number".to_string(), KvsValue::U32(3)),
+            ])
+            .is_err_and(|e| e == ErrorCode::TypeMismatch));
+        assert!(kvs
+            .transaction(|txn| {
+                txn.set_value("default", 1);
+                Ok(())
+            })
+            .is_err_and(|e| e == ErrorCode::TypeMismatch));
+        assert_eq!(kvs.get_value("number").unwrap(), KvsValue::I32(2));
+        assert_eq!(kvs.get_value("new").unwrap(), KvsValue::from("text"));
+
+        kvs.remove_key("new").unwrap();
+        kvs.set_value("new", 1).unwrap();
+    }
+
+    #[test]
+    fn test_set_values() {
+        let kvs = get_kvs::<MockBackend>(
+            PathBuf::new(),
+            KvsMap::from([("key1".to_string(), KvsValue::from(1.0))]),
+            KvsMap::new(),
+        );
+
+        kvs.set_values([
+            ("key1".to_string(), KvsValue::from(1.0)),
+            ("key2".to_string(), KvsValue::from("value")),
+        ])
+        .unwrap();
+        assert!(kvs.is_dirty().unwrap());
+        assert_eq!(kvs.get_value_as::<f64>("key1").unwrap(), 1.0);
+        assert_eq!(kvs.get_value_as::<String>("key2").unwrap(), "value");
+    }
+
+    #[test]
+    fn test_set_values_unchanged() {
+        let kvs = get_kvs::<MockBackend>(
+            PathBuf::new(),
+            KvsMap::from([("key".to_string(), KvsValue::from(1.0))]),
+            KvsMap::new(),
+        );
+
+        kvs.set_values([("key".to_string(), KvsValue::from(1.0))])
+            .unwrap();
+        assert!(!kvs.is_dirty().unwrap());
+    }
+
+    #[cfg(feature = "json-backend")]
+    #[test]
+    fn test_set_values_max_size() {
+        let mut kvs = get_kvs::<JsonBackend>(PathBuf::new(), KvsMap::new(), KvsMap::new());
+        kvs.parameters.max_size = Some(kvs.storage_usage().unwrap() + 24);
+
+        let result = kvs.set_values([
+            ("key1".to_string(), KvsValue::from("value")),
+            ("key2".to_string(), KvsValue::from("value")),
+        ]);
+        assert!(result.is_err_and(|e| e == ErrorCode::QuotaExceeded));
+        assert!(kvs.get_all_keys().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_set_value_key_policy() {
+        let mut kvs = get_kvs::<MockBackend>(PathBuf::new(), KvsMap::new(), KvsMap::new());
+        kvs.parameters.key_policy = KvsKeyPolicy {
+            reserved_prefixes: vec!["__".to_string()],
+            ..Default::default()
+        };
+
+        assert!(kvs
+            .set_value("__key", 1)
+            .is_err_and(|e| e == ErrorCode::InvalidKey));
+        assert!(kvs
+            .set_values([
+                ("key".to_string(), KvsValue::from(1)),
+                ("__key".to_string(), KvsValue::from(2)),
+            ])
+            .is_err_and(|e| e == ErrorCode::InvalidKey));
+        assert!(kvs
+            .transaction(|txn| {
+                txn.set_value("__key", 3);
+                Ok(())
+            })
+            .is_err_and(|e| e == ErrorCode::InvalidKey));
+        assert!(kvs.get_all_keys().unwrap().is_empty());
+        assert!(!kvs.is_dirty().unwrap());
+
+        kvs.set_value("key", 1).unwrap();
+        assert_eq!(kvs.get_value_as::<i32>("key").unwrap(), 1);
+    }
+
+    #[test]
+    fn test_get_values() {
+        let kvs = get_kvs::<MockBackend>(
+            PathBuf::new(),
+            KvsMap::from([("key".to_string(), KvsValue::from(1.0))]),
+            KvsMap::from([("default".to_string(), KvsValue::from(true))]),
+        );
+
+        assert_eq!(
+            kvs.get_values(&["default", "key"]).unwrap(),
+            vec![KvsValue::from(true), KvsValue::from(1.0)]
+        );
+        assert!(kvs
+            .get_values(&["key", "missing"])
+            .is_err_and(|e| e == ErrorCode::KeyNotFound));
+    }
+
+    #[test]
+    fn test_try_get_many() {
+        let kvs = get_kvs::<MockBackend>(
+            PathBuf::new(),
+            KvsMap::from([("key".to_string(), KvsValue::from(1.0))]),
+            KvsMap::from([("default".to_string(), KvsValue::from(true))]),
+        );
+
+        assert_eq!(
+            kvs.try_get_many(&["missing", "default", "key"]),
+            vec![
+                ("missing".to_string(), Err(ErrorCode::KeyNotFound)),
+                ("default".to_string(), Ok(KvsValue::from(true))),
+                ("key".to_string(), Ok(KvsValue::from(1.0))),
+            ]
+        );
+        assert!(kvs.try_get_many(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_remove_keys() {
+        let kvs = get_kvs::<MockBackend>(
+            PathBuf::new(),
+            KvsMap::from([
+                ("key1".to_string(), KvsValue::from(1.0)),
+                ("key2".to_string(), KvsValue::from(2.0)),
+                ("key3".to_string(), KvsValue::from(3.0)),
+            ]),
+            KvsMap::new(),
+        );
+
+        kvs.remove_keys(&["key1", "key2"]).unwrap();
+        assert_eq!(kvs.get_all_keys().unwrap(), vec!["key3"]);
+        assert!(kvs.is_dirty().unwrap());
+    }
+
+    #[test]
+    fn test_remove_keys_not_found() {
+        let kvs = get_kvs::<MockBackend>(
+            PathBuf::new(),
+            KvsMap::from([("key".to_string(), KvsValue::from(1.0))]),
+            KvsMap::new(),
+        );
+
+        assert!(kvs
+            .remove_keys(&["key", "missing"])
+            .is_err_and(|e| e == ErrorCode::KeyNotFound));
+        assert!(kvs.key_exists("key").unwrap());
+        assert!(!kvs.is_dirty().unwrap());
+    }
+
+    #[cfg(feature = "json-backend")]
+    #[test]
+    fn test_transaction_max_size() {
+        let mut kvs = get_kvs::<JsonBackend>(PathBuf::new(), KvsMap::new(), KvsMap::new());
+        kvs.parameters.max_size = Some(kvs.storage_usage().unwrap() + 4);
+
+        let result = kvs.transaction(|txn| {
+            txn.set_value("key", "value");
+            Ok(())
+        });
+        assert!(result.is_err_and(|e| e == ErrorCode::QuotaExceeded));
+        assert!(!kvs.key_exists("key").unwrap());
+        assert!(!kvs.data.lock().unwrap().dirty);
+    }
+
+    #[cfg(feature = "json-backend")]
+    #[test]
+    fn test_flush_max_size() {
+        let dir = tempdir().unwrap();
+        let mut kvs = get_kvs::<JsonBackend>(
+            dir.path().to_path_buf(),
+            KvsMap::from([("key".to_string(), KvsValue::from("value"))]),
+            KvsMap::new(),
+        );
+        kvs.parameters.max_size = Some(kvs.storage_usage().unwrap() - 1);
+
+        assert!(kvs.flush().is_err_and(|e| e == ErrorCode::QuotaExceeded));
+        assert!(kvs
+            .get_kvs_filename(SnapshotId(0))
+            .is_err_and(|e| e == ErrorCode::FileNotFound));
     }
 
+    #[cfg(feature = "json-backend")]
     #[test]
-    fn test_flush() {
+    fn test_flush_sync_policy() {
+        for sync_policy in [
+            SyncPolicy::None,
+            SyncPolicy::DataOnly,
+            SyncPolicy::DataAndDirectory,
+        ] {
+            let dir = tempdir().unwrap();
+            let mut kvs = get_kvs::<JsonBackend>(
+                dir.path().to_path_buf(),
+                KvsMap::from([("key".to_string(), KvsValue::from("value"))]),
+                KvsMap::new(),
+            );
+            kvs.parameters.sync_policy = sync_policy;
+
+            kvs.flush().unwrap();
+            kvs.get_kvs_filename(SnapshotId(0)).unwrap();
+            assert!(!kvs.is_dirty().unwrap());
+        }
+    }
+
+    #[cfg(all(feature = "json-backend", feature = "snapshots"))]
+    #[test]
+    fn test_flush_clean_skipped() {
         let dir = tempdir().unwrap();
-        let dir_path = dir.path().to_path_buf();
-        let kvs = get_kvs::<JsonBackend>(
-            dir_path,
-            KvsMap::from([("key".to_string(), KvsValue::from("value"))]),
-            KvsMap::new(),
-        );
+        let kvs = get_kvs::<JsonBackend>(dir.path().to_path_buf(), KvsMap::new(), KvsMap::new());
 
+        // Clean data is written if there is no current snapshot.
+        assert!(!kvs.is_dirty().unwrap());
         kvs.flush().unwrap();
-        let snapshot_id = SnapshotId(0);
-        // Functions below check if file exist.
-        kvs.get_kvs_filename(snapshot_id).unwrap();
-        kvs.get_hash_filename(snapshot_id).unwrap();
+        assert_eq!(kvs.snapshot_count(), 1);
+
+        kvs.flush().unwrap();
+        kvs.set_value("key", "value").unwrap();
+        assert!(kvs.is_dirty().unwrap());
+        kvs.flush().unwrap();
+        assert!(!kvs.is_dirty().unwrap());
+        assert_eq!(kvs.snapshot_count(), 2);
+
+        // Storing an equal value keeps data clean.
+        kvs.set_value("key", "value").unwrap();
+        assert!(!kvs.is_dirty().unwrap());
+        kvs.flush().unwrap();
+        assert_eq!(kvs.snapshot_count(), 2);
     }
 
+    #[cfg(feature = "json-backend")]
     #[test]
     fn test_snapshot_count_zero() {
         let dir = tempdir().unwrap();
@@ -968,6 +3704,7 @@ mod kvs_tests {
         assert_eq!(kvs.snapshot_count(), 0);
     }
 
+    #[cfg(feature = "json-backend")]
     #[test]
     fn test_snapshot_count_to_one() {
         let dir = tempdir().unwrap();
@@ -977,17 +3714,21 @@ mod kvs_tests {
         assert_eq!(kvs.snapshot_count(), 1);
     }
 
+    #[cfg(feature = "json-backend")]
     #[test]
     fn test_snapshot_count_to_max() {
         let dir = tempdir().unwrap();
         let dir_path = dir.path().to_path_buf();
         let kvs = get_kvs::<JsonBackend>(dir_path, KvsMap::new(), KvsMap::new());
         for i in 1..=KVS_MAX_SNAPSHOTS {
+            kvs.set_value("counter", KvsValue::I32(i as i32)).unwrap();
             kvs.flush().unwrap();
             assert_eq!(kvs.snapshot_count(), i);
         }
-        kvs.flush().unwrap();
-        kvs.flush().unwrap();
+        for i in 0..2 {
+            kvs.set_value("counter", KvsValue::I32(i)).unwrap();
+            kvs.flush().unwrap();
+        }
         assert_eq!(kvs.snapshot_count(), KVS_MAX_SNAPSHOTS);
     }
 
@@ -999,6 +3740,7 @@ mod kvs_tests {
         );
     }
 
+    #[cfg(all(feature = "json-backend", feature = "snapshots"))]
     #[test]
     fn test_snapshot_restore_ok() {
         let dir = tempdir().unwrap();
@@ -1013,6 +3755,7 @@ mod kvs_tests {
         assert_eq!(kvs.get_value_as::<i32>("counter").unwrap(), 2);
     }
 
+    #[cfg(feature = "json-backend")]
     #[test]
     fn test_snapshot_restore_invalid_id() {
         let dir = tempdir().unwrap();
@@ -1028,6 +3771,7 @@ mod kvs_tests {
             .is_err_and(|e| e == ErrorCode::InvalidSnapshotId));
     }
 
+    #[cfg(feature = "json-backend")]
     #[test]
     fn test_snapshot_restore_current_id() {
         let dir = tempdir().unwrap();
@@ -1043,6 +3787,7 @@ mod kvs_tests {
             .is_err_and(|e| e == ErrorCode::InvalidSnapshotId));
     }
 
+    #[cfg(feature = "json-backend")]
     #[test]
     fn test_snapshot_restore_not_available() {
         let dir = tempdir().unwrap();
@@ -1058,6 +3803,330 @@ mod kvs_tests {
             .is_err_and(|e| e == ErrorCode::InvalidSnapshotId));
     }
 
+    #[cfg(all(feature = "snapshots", feature = "json-backend"))]
+    #[test]
+    fn test_snapshot_preview() {
+        let dir = tempdir().unwrap();
+        let dir_path = dir.path().to_path_buf();
+        let kvs = get_kvs::<JsonBackend>(dir_path, KvsMap::new(), KvsMap::new());
+        for i in 1..=2 {
+            kvs.set_value("counter", KvsValue::I32(i)).unwrap();
+            kvs.flush().unwrap();
+        }
+
+        assert_eq!(
+            kvs.snapshot_preview(SnapshotId(1)).unwrap(),
+            KvsMap::from([("counter".to_string(), KvsValue::I32(1))])
+        );
+        assert_eq!(kvs.get_value_as::<i32>("counter").unwrap(), 2);
+        assert!(!kvs.is_dirty().unwrap());
+        assert!(kvs
+            .snapshot_preview(SnapshotId(0))
+            .is_err_and(|e| e == ErrorCode::InvalidSnapshotId));
+        assert!(kvs
+            .snapshot_preview(SnapshotId(3))
+            .is_err_and(|e| e == ErrorCode::InvalidSnapshotId));
+    }
+
+    #[cfg(all(feature = "snapshots", feature = "json-backend"))]
+    #[test]
+    fn test_value_history() {
+        let dir = tempdir().unwrap();
+        let dir_path = dir.path().to_path_buf();
+        let kvs = get_kvs::<JsonBackend>(dir_path, KvsMap::new(), KvsMap::new());
+        assert!(kvs.value_history("counter").unwrap().is_empty());
+        kvs.set_value("other", KvsValue::I32(0)).unwrap();
+        kvs.flush().unwrap();
+        for i in 1..=2 {
+            kvs.set_value("counter", KvsValue::I32(i)).unwrap();
+            kvs.flush().unwrap();
+        }
+
+        assert_eq!(
+            kvs.value_history("counter").unwrap(),
+            vec![
+                (SnapshotId(1), Some(KvsValue::I32(1))),
+                (SnapshotId(2), None),
+            ]
+        );
+        assert_eq!(kvs.get_value_as::<i32>("counter").unwrap(), 2);
+    }
+
+    #[cfg(all(feature = "snapshots", feature = "json-backend"))]
+    #[test]
+    fn test_value_history_oldest_snapshot() {
+        let dir = tempdir().unwrap();
+        let dir_path = dir.path().to_path_buf();
+        let kvs = get_kvs::<JsonBackend>(dir_path, KvsMap::new(), KvsMap::new());
+        for i in 0..=KVS_MAX_SNAPSHOTS {
+            kvs.set_value("counter", KvsValue::from(i as i32)).unwrap();
+            kvs.flush().unwrap();
+        }
+
+        let history = kvs.value_history("counter").unwrap();
+        assert_eq!(history.len(), KVS_MAX_SNAPSHOTS);
+        assert_eq!(
+            history.last().unwrap(),
+            &(SnapshotId(KVS_MAX_SNAPSHOTS), Some(KvsValue::from(0)))
+        );
+    }
+
+    #[cfg(all(feature = "snapshots", feature = "json-backend"))]
+    #[test]
+    fn test_snapshot_diff() {
+        let dir = tempdir().unwrap();
+        let dir_path = dir.path().to_path_buf();
+        let kvs = get_kvs::<JsonBackend>(dir_path, KvsMap::new(), KvsMap::new());
+        kvs.set_value("changed", KvsValue::I32(1)).unwrap();
+        kvs.set_value("same", KvsValue::I32(1)).unwrap();
+        kvs.set_value("removed_later", KvsValue::I32(1)).unwrap();
+        kvs.flush().unwrap();
+        kvs.snapshot_create().unwrap();
+
+        // Unflushed changes are compared.
+        kvs.set_value("changed", KvsValue::I32(2)).unwrap();
+        kvs.remove_key("removed_later").unwrap();
+        kvs.set_value("new", KvsValue::I32(1)).unwrap();
+        assert_eq!(
+            kvs.snapshot_diff(SnapshotId(1)).unwrap(),
+            KvsSnapshotDiff {
+                added: vec!["removed_later".to_string()],
+                removed: vec!["new".to_string()],
+                changed: vec!["changed".to_string()],
+            }
+        );
+    }
+
+    #[cfg(all(feature = "snapshots", feature = "json-backend"))]
+    #[test]
+    fn test_snapshot_create_unchanged() {
+        let dir = tempdir().unwrap();
+        let dir_path = dir.path().to_path_buf();
+        let kvs = get_kvs::<JsonBackend>(dir_path, KvsMap::new(), KvsMap::new());
+        kvs.set_value("counter", KvsValue::I32(1)).unwrap();
+        kvs.flush().unwrap();
+
+        kvs.snapshot_create().unwrap();
+        assert_eq!(kvs.snapshot_count(), 2);
+        kvs.snapshot_restore(SnapshotId(1)).unwrap();
+        assert_eq!(kvs.get_value_as::<i32>("counter").unwrap(), 1);
+    }
+
+    #[cfg(all(feature = "snapshots", feature = "json-backend"))]
+    #[test]
+    fn test_snapshot_prune() {
+        let dir = tempdir().unwrap();
+        let dir_path = dir.path().to_path_buf();
+        let kvs = get_kvs::<JsonBackend>(dir_path, KvsMap::new(), KvsMap::new());
+        for _ in 0..=KVS_MAX_SNAPSHOTS {
+            kvs.snapshot_create().unwrap();
+        }
+
+        assert_eq!(kvs.snapshot_prune(1).unwrap(), KVS_MAX_SNAPSHOTS - 1);
+        assert_eq!(kvs.snapshot_count(), 2);
+        assert!(kvs
+            .get_hash_filename(SnapshotId(2))
+            .is_err_and(|e| e == ErrorCode::FileNotFound));
+        assert_eq!(kvs.snapshot_prune(1).unwrap(), 0);
+
+        assert_eq!(kvs.snapshot_prune(0).unwrap(), 1);
+        assert_eq!(kvs.snapshot_count(), 1);
+    }
+
+    #[cfg(all(feature = "snapshots", feature = "json-backend"))]
+    #[test]
+    fn test_verify_integrity_repair() {
+        let dir = tempdir().unwrap();
+        let dir_path = dir.path().to_path_buf();
+        let kvs = get_kvs::<JsonBackend>(dir_path, KvsMap::new(), KvsMap::new());
+        for value in 0..=KVS_MAX_SNAPSHOTS as i32 {
+            kvs.set_value("key", value).unwrap();
+            kvs.flush().unwrap();
+        }
+        let all_valid: Vec<_> = (0..=KVS_MAX_SNAPSHOTS)
+            .map(|idx| (SnapshotId(idx), KvsSnapshotStatus::Valid))
+            .collect();
+        assert_eq!(kvs.verify_integrity().unwrap(), all_valid);
+
+        let kvs_path = kvs.get_kvs_filename(SnapshotId(1)).unwrap();
+        let mut content = std::fs::read_to_string(&kvs_path).unwrap();
+        content.push(' ');
+        std::fs::write(&kvs_path, content).unwrap();
+        std::fs::remove_file(kvs.get_hash_filename(SnapshotId(0)).unwrap()).unwrap();
+        let report = kvs.verify_integrity().unwrap();
+        assert!(matches!(
+            report[0],
+            (SnapshotId(0), KvsSnapshotStatus::Unreadable(_))
+        ));
+        assert_eq!(
+            report[1..],
+            [
+                (SnapshotId(1), KvsSnapshotStatus::HashMismatch),
+                (SnapshotId(2), KvsSnapshotStatus::Valid),
+                (SnapshotId(3), KvsSnapshotStatus::Valid),
+            ]
+        );
+
+        assert_eq!(
+            kvs.repair().unwrap(),
+            vec![
+                (SnapshotId(0), KvsRepairAction::Regenerated),
+                (SnapshotId(1), KvsRepairAction::Removed),
+            ]
+        );
+        assert_eq!(
+            kvs.verify_integrity().unwrap(),
+            all_valid[..KVS_MAX_SNAPSHOTS]
+        );
+        assert_eq!(
+            kvs.snapshot_preview(SnapshotId(1)).unwrap()["key"],
+            KvsValue::I32(1)
+        );
+        assert!(kvs.repair().unwrap().is_empty());
+    }
+
+    #[cfg(all(feature = "snapshots", feature = "json-backend"))]
+    #[test]
+    fn test_export_import_snapshot() {
+        let dir = tempdir().unwrap();
+        let dir_path = dir.path().to_path_buf();
+        let kvs = get_kvs::<JsonBackend>(dir_path.clone(), KvsMap::new(), KvsMap::new());
+        kvs.set_value("key", "first").unwrap();
+        kvs.flush().unwrap();
+        kvs.set_value("key", "second").unwrap();
+        kvs.flush().unwrap();
+
+        let export_path = dir_path.join("export.json");
+        kvs.export_snapshot(SnapshotId(1), &export_path).unwrap();
+        assert!(dir_path.join("export.hash").exists());
+        assert!(kvs
+            .export_snapshot(SnapshotId(2), &export_path)
+            .is_err_and(|e| e == ErrorCode::FileNotFound));
+
+        kvs.import_snapshot(&export_path).unwrap();
+        assert!(kvs.is_dirty().unwrap());
+        assert_eq!(kvs.get_value_as::<String>("key").unwrap(), "first");
+    }
+
+    #[cfg(all(feature = "snapshots", feature = "json-backend"))]
+    #[test]
+    fn test_flush_hooks() {
+        let dir = tempdir().unwrap();
+        let mut kvs =
+            get_kvs::<JsonBackend>(dir.path().to_path_buf(), KvsMap::new(), KvsMap::new());
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let before_calls = calls.clone();
+        kvs.on_before_flush(move |instance_id| {
+            before_calls
+                .lock()
+                .unwrap()
+                .push(format!("before {instance_id}"));
+        })
+        .unwrap();
+        let after_calls = calls.clone();
+        kvs.on_after_flush(move |instance_id, result| {
+            after_calls
+                .lock()
+                .unwrap()
+                .push(format!("after {instance_id} {result:?}"));
+        })
+        .unwrap();
+
+        kvs.set_value("key", "value").unwrap();
+        kvs.flush().unwrap();
+        kvs.flush().unwrap();
+        kvs.parameters.max_size = Some(1);
+        assert!(kvs.snapshot_create().is_err());
+        assert_eq!(
+            *calls.lock().unwrap(),
+            [
+                "before 1",
+                "after 1 Ok(())",
+                "before 1",
+                "after 1 Err(QuotaExceeded)"
+            ]
+        );
+    }
+
+    /// Panic in another thread while holding the data lock.
+    #[cfg(feature = "json-backend")]
+    fn poison<B: KvsBackend + KvsPathResolver>(kvs: &GenericKvs<B>) {
+        let data = kvs.data.clone();
+        let _ = std::thread::spawn(move || {
+            let _data = data.lock().unwrap();
+            panic!("poisoning instance lock");
+        })
+        .join();
+        assert!(kvs.data.is_poisoned());
+    }
+
+    #[cfg(feature = "json-backend")]
+    #[test]
+    fn test_poison_recovery() {
+        let dir = tempdir().unwrap();
+        let kvs = get_kvs::<JsonBackend>(dir.path().to_path_buf(), KvsMap::new(), KvsMap::new());
+        assert_eq!(kvs.health().unwrap(), KvsHealth::Healthy);
+        kvs.set_value("key", "flushed").unwrap();
+        kvs.flush().unwrap();
+        kvs.set_value("key", "unflushed").unwrap();
+
+        poison(&kvs);
+        assert_eq!(kvs.get_value_as::<String>("key").unwrap(), "flushed");
+        assert!(!kvs.data.is_poisoned());
+        assert!(!kvs.is_dirty().unwrap());
+        kvs.set_value("key", "changed").unwrap();
+
+        poison(&kvs);
+        assert_eq!(
+            kvs.health().unwrap(),
+            KvsHealth::Recovered { recoveries: 2 }
+        );
+        assert_eq!(kvs.get_value_as::<String>("key").unwrap(), "flushed");
+    }
+
+    #[cfg(feature = "json-backend")]
+    #[test]
+    fn test_poison_recovery_failed() {
+        let dir = tempdir().unwrap();
+        let mut kvs =
+            get_kvs::<JsonBackend>(dir.path().to_path_buf(), KvsMap::new(), KvsMap::new());
+        kvs.parameters.kvs_load = KvsLoad::Required;
+
+        poison(&kvs);
+        assert!(kvs
+            .get_value("key")
+            .is_err_and(|e| e == ErrorCode::FileNotFound));
+        assert_eq!(kvs.health().unwrap(), KvsHealth::Poisoned);
+
+        kvs.parameters.kvs_load = KvsLoad::Optional;
+        assert_eq!(
+            kvs.health().unwrap(),
+            KvsHealth::Recovered { recoveries: 1 }
+        );
+    }
+
+    #[cfg(all(feature = "snapshots", feature = "json-backend"))]
+    #[test]
+    fn test_import_snapshot_corrupted() {
+        let dir = tempdir().unwrap();
+        let dir_path = dir.path().to_path_buf();
+        let kvs = get_kvs::<JsonBackend>(dir_path.clone(), KvsMap::new(), KvsMap::new());
+        kvs.set_value("key", "value").unwrap();
+        kvs.flush().unwrap();
+
+        let export_path = dir_path.join("export.json");
+        kvs.export_snapshot(SnapshotId(0), &export_path).unwrap();
+        let content = std::fs::read_to_string(&export_path).unwrap();
+        std::fs::write(&export_path, content.replace("value", "other")).unwrap();
+
+        assert!(kvs
+            .import_snapshot(&export_path)
+            .is_err_and(|e| e == ErrorCode::ValidationFailed));
+        assert!(!kvs.is_dirty().unwrap());
+        assert_eq!(kvs.get_value_as::<String>("key").unwrap(), "value");
+    }
+
+    #[cfg(all(feature = "json-backend", feature = "snapshots"))]
     #[test]
     fn test_get_kvs_filename_found() {
         let dir = tempdir().unwrap();
@@ -1065,12 +4134,14 @@ mod kvs_tests {
         let kvs = get_kvs::<JsonBackend>(dir_path, KvsMap::new(), KvsMap::new());
 
         kvs.flush().unwrap();
+        kvs.set_value("key", "value").unwrap();
         kvs.flush().unwrap();
         let kvs_path = kvs.get_kvs_filename(SnapshotId(1)).unwrap();
         let kvs_name = kvs_path.file_name().unwrap().to_str().unwrap();
         assert_eq!(kvs_name, "kvs_1_1.json");
     }
 
+    #[cfg(feature = "json-backend")]
     #[test]
     fn test_get_kvs_filename_not_found() {
         let dir = tempdir().unwrap();
@@ -1082,6 +4153,7 @@ mod kvs_tests {
             .is_err_and(|e| e == ErrorCode::FileNotFound));
     }
 
+    #[cfg(all(feature = "json-backend", feature = "snapshots"))]
     #[test]
     fn test_get_hash_filename_found() {
         let dir = tempdir().unwrap();
@@ -1089,12 +4161,14 @@ mod kvs_tests {
         let kvs = get_kvs::<JsonBackend>(dir_path, KvsMap::new(), KvsMap::new());
 
         kvs.flush().unwrap();
+        kvs.set_value("key", "value").unwrap();
         kvs.flush().unwrap();
         let hash_path = kvs.get_hash_filename(SnapshotId(1)).unwrap();
         let hash_name = hash_path.file_name().unwrap().to_str().unwrap();
         assert_eq!(hash_name, "kvs_1_1.hash");
     }
 
+    #[cfg(feature = "json-backend")]
     #[test]
     fn test_get_hash_filename_not_found() {
         let dir = tempdir().unwrap();
@@ -1105,4 +4179,134 @@ mod kvs_tests {
             .get_hash_filename(SnapshotId(1))
             .is_err_and(|e| e == ErrorCode::FileNotFound));
     }
+
+    #[cfg(feature = "tooling")]
+    #[test]
+    fn test_export_dotenv() {
+        let kvs = get_kvs::<MockBackend>(
+            PathBuf::new(),
+            KvsMap::from([
+                ("example1".to_string(), KvsValue::from("value")),
+                ("example2".to_string(), KvsValue::from(true)),
+            ]),
+            KvsMap::from([("example3".to_string(), KvsValue::from(1.0))]),
+        );
+
+        assert_eq!(
+            kvs.export_dotenv().unwrap(),
+            "example1=\"value\"\nexample2=true\n"
+        );
+    }
+
+    #[cfg(feature = "tooling")]
+    #[test]
+    fn test_import_dotenv_ok() {
+        let kvs = get_kvs::<MockBackend>(
+            PathBuf::new(),
+            KvsMap::from([("example1".to_string(), KvsValue::from("old_value"))]),
+            KvsMap::new(),
+        );
+
+        kvs.import_dotenv("example1=\"new_value\"\nexample2=12\n")
+            .unwrap();
+        assert_eq!(kvs.get_value_as::<String>("example1").unwrap(), "new_value");
+        assert_eq!(kvs.get_value_as::<i32>("example2").unwrap(), 12);
+    }
+
+    #[cfg(feature = "tooling")]
+    #[test]
+    fn test_import_dotenv_max_keys() {
+        let mut kvs = get_kvs::<MockBackend>(
+            PathBuf::new(),
+            KvsMap::from([("example1".to_string(), KvsValue::from("old_value"))]),
+            KvsMap::new(),
+        );
+        kvs.parameters.max_keys = Some(1);
+
+        assert!(kvs
+            .import_dotenv("example1=\"new_value\"\nexample2=12\n")
+            .is_err_and(|e| e == ErrorCode::QuotaExceeded));
+        assert_eq!(kvs.get_value_as::<String>("example1").unwrap(), "old_value");
+        assert!(!kvs.key_exists("example2").unwrap());
+    }
+
+    #[cfg(feature = "tooling")]
+    #[test]
+    fn test_import_dotenv_malformed() {
+        let kvs = get_kvs::<MockBackend>(
+            PathBuf::new(),
+            KvsMap::from([("example1".to_string(), KvsValue::from("old_value"))]),
+            KvsMap::new(),
+        );
+
+        assert!(kvs
+            .import_dotenv("example1=\"new_value\"\nmalformed\n")
+            .is_err_and(|e| e == ErrorCode::ConversionFailed));
+        assert_eq!(kvs.get_value_as::<String>("example1").unwrap(), "old_value");
+    }
+
+    #[cfg(feature = "tooling")]
+    #[test]
+    fn test_export_import_protobuf() {
+        let kvs = get_kvs::<MockBackend>(
+            PathBuf::new(),
+            KvsMap::from([("example1".to_string(), KvsValue::from("value"))]),
+            KvsMap::from([
+                ("example1".to_string(), KvsValue::from("default")),
+                ("example2".to_string(), KvsValue::from(1.0)),
+            ]),
+        );
+
+        let schema = kvs.protobuf_schema("Example").unwrap();
+        let encoded = kvs.export_protobuf(&schema).unwrap();
+        kvs.reset().unwrap();
+        kvs.import_protobuf(&schema, &encoded).unwrap();
+
+        assert_eq!(kvs.get_value_as::<String>("example1").unwrap(), "value");
+        assert!(!kvs.is_value_default("example2").unwrap());
+        assert_eq!(kvs.get_value_as::<f64>("example2").unwrap(), 1.0);
+    }
+
+    #[cfg(feature = "tooling")]
+    #[test]
+    fn test_import_protobuf_max_keys() {
+        let mut kvs = get_kvs::<MockBackend>(
+            PathBuf::new(),
+            KvsMap::new(),
+            KvsMap::from([
+                ("example1".to_string(), KvsValue::from("default")),
+                ("example2".to_string(), KvsValue::from(1.0)),
+            ]),
+        );
+        let schema = kvs.protobuf_schema("Example").unwrap();
+        let encoded = schema
+            .encode(&KvsMap::from([
+                ("example1".to_string(), KvsValue::from("value")),
+                ("example2".to_string(), KvsValue::from(2.0)),
+            ]))
+            .unwrap();
+
+        kvs.parameters.max_keys = Some(1);
+        assert!(kvs
+            .import_protobuf(&schema, &encoded)
+            .is_err_and(|e| e == ErrorCode::QuotaExceeded));
+        assert!(kvs.is_value_default("example1").unwrap());
+        assert!(kvs.is_value_default("example2").unwrap());
+    }
+
+    #[cfg(feature = "tooling")]
+    #[test]
+    fn test_import_protobuf_malformed() {
+        let kvs = get_kvs::<MockBackend>(
+            PathBuf::new(),
+            KvsMap::new(),
+            KvsMap::from([("example1".to_string(), KvsValue::from("default"))]),
+        );
+
+        let schema = kvs.protobuf_schema("Example").unwrap();
+        assert!(kvs
+            .import_protobuf(&schema, &[0x0a, 0x05])
+            .is_err_and(|e| e == ErrorCode::SerializationFailed));
+        assert!(kvs.is_value_default("example1").unwrap());
+    }
 }
@@ -0,0 +1,325 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Administration of all instances stored in a working directory.
+//!
+//! [`KvsAdmin`] works on the files of a working directory without opening instances, e.g. for a
+//! factory reset:
+//!
+//! ```
+//! use rust_kvs::kvs_admin::KvsAdmin;
+//! use rust_kvs::prelude::*;
+//! use rust_kvs::JsonBackend;
+//!
+//! # let dir = tempfile::tempdir().unwrap();
+//! # let working_dir = dir.path();
+//! let admin = KvsAdmin::<JsonBackend>::new(working_dir);
+//! for report in admin.verify_all()? {
+//!     if !report.is_valid() {
+//!         eprintln!("instance {} is corrupted", report.info.instance_id);
+//!     }
+//! }
+//! admin.remove_all()?;
+//! # Ok::<(), ErrorCode>(())
+//! ```
+//!
+//! Removing an instance removes its snapshots, hash files and journal. Defaults files are
+//! provisioned with the software and kept. Instances open in this process can't be removed, their
+//! next flush would restore the files.
+
+use crate::error_code::ErrorCode;
+use crate::json_backend::JsonBackend;
+use crate::kvs::{KvsSnapshotStatus, KVS_MAX_SNAPSHOTS};
+use crate::kvs_api::{InstanceId, SnapshotId};
+use crate::kvs_backend::{KvsBackend, KvsPathResolver};
+use crate::kvs_builder;
+use crate::kvs_discovery::{self, InstanceInfo};
+use crate::kvs_journal;
+use crate::kvs_lock::KvsDirLock;
+use crate::kvs_log::{kvs_debug, kvs_error};
+use std::fs;
+use std::io::ErrorKind;
+use std::marker::PhantomData;
+use std::path::PathBuf;
+
+/// Instance with the integrity of its snapshots, see [`KvsAdmin::verify_all`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct KvsInstanceReport {
+    /// Files of the instance.
+    pub info: InstanceInfo,
+
+    /// Integrity of the stored snapshots, ordered by snapshot ID.
+    pub snapshots: Vec<(SnapshotId, KvsSnapshotStatus)>,
+}
+
+impl KvsInstanceReport {
+    /// Whether all snapshots of the instance are valid.
+    pub fn is_valid(&self) -> bool {
+        self.snapshots
+            .iter()
+            .all(|(_, status)| *status == KvsSnapshotStatus::Valid)
+    }
+}
+
+/// Administration of the instances stored in a working directory.
+pub struct KvsAdmin<Backend: KvsBackend = JsonBackend, PathResolver: KvsPathResolver = Backend> {
+    /// Working directory.
+    working_dir: PathBuf,
+
+    /// Marker for `Backend`.
+    _backend_marker: PhantomData<Backend>,
+
+    /// Marker for `PathResolver`.
+    _path_resolver_marker: PhantomData<PathResolver>,
+}
+
+impl<Backend: KvsBackend, PathResolver: KvsPathResolver> KvsAdmin<Backend, PathResolver> {
+    /// Create the administration of a working directory
+    ///
+    /// # Parameters
+    ///   * `working_dir`: Working directory, empty for the current directory
+    pub fn new(working_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            working_dir: working_dir.into(),
+            _backend_marker: PhantomData,
+            _path_resolver_marker: PhantomData,
+        }
+    }
+
+    /// List the instances stored in the working directory with file sizes and snapshot counts,
+    /// see [`GenericKvs::discover_instances`](crate::kvs::GenericKvs::discover_instances)
+    ///
+    /// # Return Values
+    ///   * Ok: Instances ordered by instance ID
+    ///   * `ErrorCode::FileNotFound`: Working directory not found
+    ///   * `ErrorCode::UnmappedError`: Directory couldn't be read
+    pub fn instances(&self) -> Result<Vec<InstanceInfo>, ErrorCode> {
+        kvs_discovery::discover_instances::<PathResolver>(&self.working_dir, KVS_MAX_SNAPSHOTS)
+    }
+
+    /// Verify the stored snapshots of an instance against their hashes
+    ///
+    /// # Parameters
+    ///   * `instance_id`: Instance ID
+    ///
+    /// # Return Values
+    ///   * Ok: Integrity of the stored snapshots, ordered by snapshot ID
+    ///   * `ErrorCode::FileNotFound`: Working directory not found
+    ///   * `ErrorCode::MutexLockFailed`: Lock registry poisoned
+    pub fn verify(
+        &self,
+        instance_id: InstanceId,
+    ) -> Result<Vec<(SnapshotId, KvsSnapshotStatus)>, ErrorCode> {
+        let _dir_lock = self.lock()?;
+        Ok(self.scan(instance_id))
+    }
+
+    /// Verify all instances stored in the working directory
+    ///
+    /// # Return Values
+    ///   * Ok: Instances with the integrity of their snapshots, ordered by instance ID
+    ///   * `ErrorCode::FileNotFound`: Working directory not found
+    ///   * `ErrorCode::MutexLockFailed`: Lock registry poisoned
+    ///   * `ErrorCode::UnmappedError`: Directory couldn't be read
+    pub fn verify_all(&self) -> Result<Vec<KvsInstanceReport>, ErrorCode> {
+        let _dir_lock = self.lock()?;
+        Ok(self
+            .instances()?
+            .into_iter()
+            .map(|info| KvsInstanceReport {
+                snapshots: self.scan(info.instance_id),
+                info,
+            })
+            .collect())
+    }
+
+    /// Remove the stored snapshots, hash files and journal of an instance
+    ///
+    /// # Parameters
+    ///   * `instance_id`: Instance ID
+    ///
+    /// # Return Values
+    ///   * Ok: Files removed or nothing to remove
+    ///   * `ErrorCode::ResourceBusy`: Instance is open in this process
+    ///   * `ErrorCode::FileNotFound`: Working directory not found
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    ///   * `ErrorCode::UnmappedError`: Removal failed
+    pub fn remove(&self, instance_id: InstanceId) -> Result<(), ErrorCode> {
+        let _dir_lock = self.lock()?;
+        self.remove_files(instance_id)
+    }
+
+    /// Remove all instances stored in the working directory, see [`KvsAdmin::remove`]
+    ///
+    /// Instances are removed one by one, removal stops at the first failure.
+    ///
+    /// # Return Values
+    ///   * Ok: IDs of the removed instances
+    ///   * `ErrorCode::ResourceBusy`: An instance is open in this process
+    ///   * `ErrorCode::FileNotFound`: Working directory not found
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    ///   * `ErrorCode::UnmappedError`: Directory couldn't be read or removal failed
+    pub fn remove_all(&self) -> Result<Vec<InstanceId>, ErrorCode> {
+        let _dir_lock = self.lock()?;
+        let mut removed = Vec::new();
+        for info in self.instances()? {
+            if info.snapshots.is_empty() {
+                continue;
+            }
+            self.remove_files(info.instance_id)?;
+            removed.push(info.instance_id);
+        }
+        Ok(removed)
+    }
+
+    /// Lock the working directory against flushes of other processes.
+    fn lock(&self) -> Result<Option<KvsDirLock>, ErrorCode> {
+        if Backend::locks_working_dir() {
+            KvsDirLock::acquire(&self.working_dir).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Check the stored snapshots of an instance.
+    fn scan(&self, instance_id: InstanceId) -> Vec<(SnapshotId, KvsSnapshotStatus)> {
+        (0..=KVS_MAX_SNAPSHOTS)
+            .map(SnapshotId)
+            .filter_map(|snapshot_id| {
+                let kvs_path =
+                    PathResolver::kvs_file_path(&self.working_dir, instance_id, snapshot_id);
+                let hash_path =
+                    PathResolver::hash_file_path(&self.working_dir, instance_id, snapshot_id);
+                if !Backend::exists(&kvs_path) && !Backend::exists(&hash_path) {
+                    return None;
+                }
+                let status = match Backend::load_kvs(&kvs_path, Some(&hash_path)) {
+                    Ok(_) => KvsSnapshotStatus::Valid,
+                    Err(e) if e.kind() == &ErrorCode::ValidationFailed => {
+                        KvsSnapshotStatus::HashMismatch
+                    }
+                    Err(e) => KvsSnapshotStatus::Unreadable(e),
+                };
+                Some((snapshot_id, status))
+            })
+            .collect()
+    }
+
+    /// Remove the files of an instance, the working directory must be locked.
+    fn remove_files(&self, instance_id: InstanceId) -> Result<(), ErrorCode> {
+        let open = kvs_builder::open_instances()?.into_iter().any(|kvs_inner| {
+            kvs_inner.parameters.instance_id == instance_id
+                && kvs_inner.parameters.working_dir == self.working_dir
+        });
+        if open {
+            kvs_error!(
+                instance_id = instance_id,
+                "tried to remove the files of an open instance"
+            );
+            return Err(ErrorCode::ResourceBusy);
+        }
+
+        for snapshot_id in (0..=KVS_MAX_SNAPSHOTS).map(SnapshotId) {
+            Backend::remove_kvs(
+                &PathResolver::kvs_file_path(&self.working_dir, instance_id, snapshot_id),
+                &PathResolver::hash_file_path(&self.working_dir, instance_id, snapshot_id),
+            )?;
+        }
+        let journal_path = self
+            .working_dir
+            .join(kvs_journal::journal_file_name(instance_id));
+        match fs::remove_file(&journal_path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => {
+                return Err(ErrorCode::from(e).with_path(&journal_path))
+            }
+            _ => {}
+        }
+        kvs_debug!(instance_id = instance_id, "instance files removed");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod kvs_admin_tests {
+    use crate::error_code::ErrorCode;
+    use crate::json_backend::JsonBackend;
+    #[cfg(feature = "snapshots")]
+    use crate::kvs::KvsSnapshotStatus;
+    use crate::kvs_admin::KvsAdmin;
+    #[cfg(feature = "snapshots")]
+    use crate::kvs_api::SnapshotId;
+    use crate::kvs_api::{InstanceId, KvsApi};
+    use crate::kvs_builder::kvs_builder_tests::lock_and_reset;
+    use crate::kvs_builder::GenericKvsBuilder;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[cfg(feature = "snapshots")]
+    #[test]
+    fn test_verify_all() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        for instance_id in [1, 2] {
+            let kvs = GenericKvsBuilder::<JsonBackend>::new(InstanceId(instance_id))
+                .dir(dir.path().to_string_lossy().to_string())
+                .build()
+                .unwrap();
+            kvs.set_value("key", 1).unwrap();
+            kvs.flush().unwrap();
+            kvs.set_value("key", 2).unwrap();
+            kvs.flush().unwrap();
+            kvs.close().unwrap();
+        }
+        fs::write(dir.path().join("kvs_2_1.json"), "{\"key\":3}").unwrap();
+
+        let admin = KvsAdmin::<JsonBackend>::new(dir.path());
+        let reports = admin.verify_all().unwrap();
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].info.instance_id, InstanceId(1));
+        assert_eq!(reports[0].info.snapshot_count(), 2);
+        assert!(reports[0].info.total_size() > 0);
+        assert!(reports[0].is_valid());
+        assert!(!reports[1].is_valid());
+        assert_eq!(
+            admin.verify(InstanceId(2)).unwrap(),
+            vec![
+                (SnapshotId(0), KvsSnapshotStatus::Valid),
+                (SnapshotId(1), KvsSnapshotStatus::HashMismatch),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_remove_all() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("kvs_3_default.json"), "{}").unwrap();
+        let kvs = GenericKvsBuilder::<JsonBackend>::new(InstanceId(1))
+            .dir(dir.path().to_string_lossy().to_string())
+            .build()
+            .unwrap();
+        kvs.set_value("key", 1).unwrap();
+        kvs.flush().unwrap();
+
+        let admin = KvsAdmin::<JsonBackend>::new(dir.path().to_string_lossy().to_string());
+        assert!(admin
+            .remove_all()
+            .is_err_and(|e| e == ErrorCode::ResourceBusy));
+        kvs.close().unwrap();
+        fs::write(dir.path().join("kvs_1.wal"), "").unwrap();
+
+        assert_eq!(admin.remove_all().unwrap(), vec![InstanceId(1)]);
+        let instances = admin.instances().unwrap();
+        assert_eq!(instances.len(), 1);
+        assert_eq!(instances[0].instance_id, InstanceId(3));
+        assert!(!dir.path().join("kvs_1.wal").exists());
+    }
+}
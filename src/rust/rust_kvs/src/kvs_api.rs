@@ -10,42 +10,13 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::error_code::ErrorCode;
-use crate::kvs_value::KvsValue;
-use core::fmt;
+use crate::kvs_log::kvs_error;
+pub use crate::kvs_storage::{InstanceId, SnapshotId};
+use crate::kvs_transaction::KvsTransaction;
+use crate::kvs_value::{KvsDeserialize, KvsMap, KvsSerialize, KvsValue, ValueModel};
+use std::ffi::OsString;
 use std::path::PathBuf;
 
-/// Instance ID
-#[derive(Clone, Copy, Debug, PartialEq)]
-pub struct InstanceId(pub usize);
-
-impl fmt::Display for InstanceId {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.0)
-    }
-}
-
-impl From<InstanceId> for usize {
-    fn from(value: InstanceId) -> Self {
-        value.0
-    }
-}
-
-/// Snapshot ID
-#[derive(Clone, Copy, Debug, PartialEq)]
-pub struct SnapshotId(pub usize);
-
-impl fmt::Display for SnapshotId {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.0)
-    }
-}
-
-impl From<SnapshotId> for usize {
-    fn from(value: SnapshotId) -> Self {
-        value.0
-    }
-}
-
 /// Defaults handling mode.
 #[derive(Clone, Debug, PartialEq)]
 pub enum KvsDefaults {
@@ -59,6 +30,33 @@ pub enum KvsDefaults {
     Required,
 }
 
+/// Default values compiled into the binary.
+///
+/// Used instead of the instance defaults file if it doesn't exist.
+#[derive(Clone, Debug, PartialEq)]
+pub enum KvsEmbeddedDefaults {
+    /// Content of a defaults file in the JSON format, e.g. `include_str!("defaults.json")`.
+    #[cfg(feature = "json-backend")]
+    Json(&'static str),
+
+    /// Default values.
+    Map(KvsMap),
+}
+
+/// Keys enumerated by [`GenericKvs::get_keys`](crate::kvs::GenericKvs::get_keys).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum KeyScope {
+    /// Keys with a stored value, like [`KvsApi::get_all_keys`].
+    #[default]
+    Stored,
+
+    /// Keys with a default value.
+    Defaults,
+
+    /// Keys with a stored or default value, the effective key space.
+    All,
+}
+
 /// KVS load mode.
 #[derive(Clone, Debug, PartialEq)]
 pub enum KvsLoad {
@@ -70,26 +68,244 @@ pub enum KvsLoad {
 
     /// KVS must be loaded.
     Required,
+
+    /// KVS is loaded if available, a corrupted KVS is replaced by the newest valid snapshot.
+    RecoverFromSnapshot,
+}
+
+/// Handling of duplicate keys within an object of a loaded file.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum DuplicateKeyPolicy {
+    /// Loading fails with a parser error.
+    Reject,
+
+    /// First occurrence is used.
+    FirstWins,
+
+    /// Last occurrence is used and a warning is printed.
+    #[default]
+    LastWinsWithWarning,
+}
+
+/// Rendering of `F64` values in stored files.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum FloatFormat {
+    /// Shortest round-trip value in plain decimal notation.
+    #[default]
+    Plain,
+
+    /// Shortest round-trip value, in scientific notation if that is shorter.
+    Shortest,
+
+    /// Rounded to the given number of decimal places, trailing zeros omitted.
+    MaxDecimals(u8),
+}
+
+/// Compression of stored KVS files.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum KvsCompression {
+    /// Files are stored uncompressed.
+    #[default]
+    None,
+
+    /// gzip, `.gz` is appended to the file name.
+    #[cfg(feature = "gzip")]
+    Gzip,
+
+    /// Zstandard, `.zst` is appended to the file name.
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+impl KvsCompression {
+    /// Extension appended to the file name of compressed files.
+    pub fn extension(&self) -> Option<&'static str> {
+        match self {
+            KvsCompression::None => None,
+            #[cfg(feature = "gzip")]
+            KvsCompression::Gzip => Some("gz"),
+            #[cfg(feature = "zstd")]
+            KvsCompression::Zstd => Some("zst"),
+        }
+    }
+}
+
+/// Syncing of stored files to the storage device on flush.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SyncPolicy {
+    /// Files are left to the operating system, a flush may be lost on power loss.
+    #[default]
+    None,
+
+    /// KVS and hash file are synced.
+    DataOnly,
+
+    /// KVS and hash file and the directory containing them are synced, so snapshot rotation and
+    /// newly created files survive a power loss as well. The directory is only synced on Unix.
+    DataAndDirectory,
+}
+
+/// Flushing of changes when a KVS handle is dropped.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FlushOnExit {
+    /// Changes are flushed.
+    Yes,
+
+    /// Changes are discarded unless flushed explicitly.
+    No,
+}
+
+/// Environment variable naming the working directory, see [`KvsDirStrategy::Environment`].
+pub const KVS_DATA_DIR_ENV: &str = "KVS_DATA_DIR";
+
+/// Working directory used if no directory is set explicitly.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum KvsDirStrategy {
+    /// Current working directory of the process.
+    #[default]
+    CurrentDir,
+
+    /// Directory named by [`KVS_DATA_DIR_ENV`], otherwise the platform data directory:
+    ///   * Windows: `%ProgramData%\kvs`
+    ///   * Others: `$XDG_DATA_HOME/kvs`, or `$HOME/.local/share/kvs` if not set
+    ///
+    /// Falls back to the current working directory if none of the variables is set.
+    Environment,
+}
+
+impl KvsDirStrategy {
+    /// Resolve the working directory from the process environment.
+    ///
+    /// # Return Values
+    ///   * Some: Resolved directory
+    ///   * None: Current working directory is used
+    pub fn resolve(&self) -> Option<PathBuf> {
+        self.resolve_with(|name| std::env::var_os(name))
+    }
+
+    /// Resolve the working directory, reading environment variables with `var`.
+    fn resolve_with(&self, var: impl Fn(&str) -> Option<OsString>) -> Option<PathBuf> {
+        // Empty and relative values are ignored as required by the XDG specification.
+        let dir = |name: &str| {
+            var(name)
+                .map(PathBuf::from)
+                .filter(|path| path.is_absolute())
+        };
+        match self {
+            KvsDirStrategy::CurrentDir => None,
+            KvsDirStrategy::Environment => {
+                if let Some(dir) = var(KVS_DATA_DIR_ENV).filter(|dir| !dir.is_empty()) {
+                    return Some(PathBuf::from(dir));
+                }
+                if cfg!(windows) {
+                    dir("ProgramData").map(|dir| dir.join("kvs"))
+                } else {
+                    dir("XDG_DATA_HOME")
+                        .or_else(|| dir("HOME").map(|home| home.join(".local").join("share")))
+                        .map(|dir| dir.join("kvs"))
+                }
+            }
+        }
+    }
+}
+
+/// Characters allowed in keys.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum KeyCharset {
+    /// Any character.
+    #[default]
+    Any,
+
+    /// ASCII letters and digits, and the given additional characters.
+    AsciiAlphanumeric(String),
+}
+
+/// Validation of keys written to an instance.
+///
+/// The default policy accepts all keys. Keys are validated when written, stored keys and defaults
+/// are not validated on load.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct KvsKeyPolicy {
+    /// Maximum key length in bytes, unlimited if `None`.
+    pub max_length: Option<usize>,
+
+    /// Characters allowed in keys.
+    pub charset: KeyCharset,
+
+    /// Prefixes keys must not start with, e.g. reserved for internal use.
+    pub reserved_prefixes: Vec<String>,
+}
+
+impl KvsKeyPolicy {
+    /// Check a key against the policy.
+    ///
+    /// # Parameters
+    ///   * `key`: Key to check
+    ///
+    /// # Return Values
+    ///   * Ok: Key is valid
+    ///   * `ErrorCode::InvalidKey`: Key is too long, contains a disallowed character or starts
+    ///     with a reserved prefix
+    pub fn validate(&self, key: &str) -> Result<(), ErrorCode> {
+        if let Some(max_length) = self.max_length.filter(|max| key.len() > *max) {
+            kvs_error!(key = key, "key {key:?} exceeds maximum length {max_length}");
+            return Err(ErrorCode::InvalidKey);
+        }
+        if let KeyCharset::AsciiAlphanumeric(additional) = &self.charset {
+            if let Some(c) = key
+                .chars()
+                .find(|c| !c.is_ascii_alphanumeric() && !additional.contains(*c))
+            {
+                kvs_error!(key = key, "key {key:?} contains disallowed character {c:?}");
+                return Err(ErrorCode::InvalidKey);
+            }
+        }
+        if let Some(prefix) = self
+            .reserved_prefixes
+            .iter()
+            .find(|prefix| key.starts_with(prefix.as_str()))
+        {
+            kvs_error!(
+                key = key,
+                "key {key:?} starts with reserved prefix {prefix:?}"
+            );
+            return Err(ErrorCode::InvalidKey);
+        }
+        Ok(())
+    }
 }
 
 pub trait KvsApi {
+    /// Value type of [`get_model_value`](Self::get_model_value) and
+    /// [`set_model_value`](Self::set_model_value).
+    type Value: ValueModel;
+
     fn reset(&self) -> Result<(), ErrorCode>;
     fn reset_key(&self, key: &str) -> Result<(), ErrorCode>;
     fn get_all_keys(&self) -> Result<Vec<String>, ErrorCode>;
+    fn get_keys_with_prefix(&self, prefix: &str) -> Result<Vec<String>, ErrorCode>;
+    fn get_keys_matching(&self, pattern: &str) -> Result<Vec<String>, ErrorCode>;
     fn key_exists(&self, key: &str) -> Result<bool, ErrorCode>;
     fn get_value(&self, key: &str) -> Result<KvsValue, ErrorCode>;
-    fn get_value_as<T>(&self, key: &str) -> Result<T, ErrorCode>
-    where
-        for<'a> T: TryFrom<&'a KvsValue> + Clone,
-        for<'a> <T as TryFrom<&'a KvsValue>>::Error: std::fmt::Debug;
+    fn get_values(&self, keys: &[&str]) -> Result<Vec<KvsValue>, ErrorCode>;
+    fn get_value_as<T: KvsDeserialize>(&self, key: &str) -> Result<T, ErrorCode>;
     fn get_default_value(&self, key: &str) -> Result<KvsValue, ErrorCode>;
     fn is_value_default(&self, key: &str) -> Result<bool, ErrorCode>;
-    fn set_value<S: Into<String>, J: Into<KvsValue>>(
+    fn set_value<S: Into<String>, J: KvsSerialize>(
         &self,
         key: S,
         value: J,
     ) -> Result<(), ErrorCode>;
+    fn set_values<I: IntoIterator<Item = (String, KvsValue)>>(
+        &self,
+        values: I,
+    ) -> Result<(), ErrorCode>;
     fn remove_key(&self, key: &str) -> Result<(), ErrorCode>;
+    fn remove_keys(&self, keys: &[&str]) -> Result<(), ErrorCode>;
+    fn remove_keys_with_prefix(&self, prefix: &str) -> Result<usize, ErrorCode>;
+    fn transaction<R, F>(&self, f: F) -> Result<R, ErrorCode>
+    where
+        F: FnOnce(&mut KvsTransaction) -> Result<R, ErrorCode>;
     fn flush(&self) -> Result<(), ErrorCode>;
     fn snapshot_count(&self) -> usize;
     fn snapshot_max_count() -> usize
@@ -98,11 +314,426 @@ pub trait KvsApi {
     fn snapshot_restore(&self, snapshot_id: SnapshotId) -> Result<(), ErrorCode>;
     fn get_kvs_filename(&self, snapshot_id: SnapshotId) -> Result<PathBuf, ErrorCode>;
     fn get_hash_filename(&self, snapshot_id: SnapshotId) -> Result<PathBuf, ErrorCode>;
+    fn flush_on_exit(&self) -> FlushOnExit;
+    fn set_flush_on_exit(&mut self, flush_on_exit: FlushOnExit);
+
+    /// Get the value for a given key, or a fallback if neither a value nor a default exists
+    ///
+    /// # Parameters
+    ///   * `key`: Key to retrieve the value from
+    ///   * `fallback`: Value returned if the key wasn't found
+    ///
+    /// # Return Values
+    ///   * Ok: Value, default value or fallback
+    ///   * `ErrorCode::ConversionFailed`: Value doesn't match the type of `fallback`
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    fn get_or<T: KvsDeserialize>(&self, key: &str, fallback: T) -> Result<T, ErrorCode> {
+        match self.get_value_as(key) {
+            Err(e) if e.kind() == &ErrorCode::KeyNotFound => Ok(fallback),
+            result => result,
+        }
+    }
+
+    /// Get the value for a given key converted into the value model of the instance
+    ///
+    /// # Parameters
+    ///   * `key`: Key to retrieve the value from
+    ///
+    /// # Return Values
+    ///   * Ok: Converted value or default value
+    ///   * `ErrorCode::ConversionFailed`: Value can't be represented by the value model
+    ///   * `ErrorCode::KeyNotFound`: Key wasn't found in KVS nor in defaults
+    fn get_model_value(&self, key: &str) -> Result<Self::Value, ErrorCode> {
+        self.get_value_as(key)
+    }
+
+    /// Assign a value of the value model of the instance to a given key
+    ///
+    /// # Parameters
+    ///   * `key`: Key to set value
+    ///   * `value`: Value to be set
+    ///
+    /// # Return Values
+    ///   * Ok: Value was assigned to key
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    fn set_model_value<S: Into<String>>(
+        &self,
+        key: S,
+        value: Self::Value,
+    ) -> Result<(), ErrorCode> {
+        self.set_value(key, value)
+    }
+
+    /// Get the `I32` value for a given key, see [`get_value_as`](Self::get_value_as)
+    fn get_i32(&self, key: &str) -> Result<i32, ErrorCode> {
+        self.get_value_as(key)
+    }
+
+    /// Get the `String` value for a given key, see [`get_value_as`](Self::get_value_as)
+    fn get_string(&self, key: &str) -> Result<String, ErrorCode> {
+        self.get_value_as(key)
+    }
+
+    /// Get the `Boolean` value for a given key, see [`get_value_as`](Self::get_value_as)
+    fn get_bool(&self, key: &str) -> Result<bool, ErrorCode> {
+        self.get_value_as(key)
+    }
+
+    /// Assign a value to a given key unless a value is already set
+    ///
+    /// Default values don't count as set. Check and write are performed in one transaction.
+    ///
+    /// # Parameters
+    ///   * `key`: Key to set value
+    ///   * `value`: Value to be set
+    ///
+    /// # Return Values
+    ///   * Ok(true): Value was assigned
+    ///   * Ok(false): Key already had a value, nothing was changed
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    ///   * `ErrorCode::QuotaExceeded`: Value would exceed the maximum size
+    fn set_if_absent<S: Into<String>, V: KvsSerialize>(
+        &self,
+        key: S,
+        value: V,
+    ) -> Result<bool, ErrorCode> {
+        let key = key.into();
+        self.transaction(|txn| {
+            if txn.key_exists(&key) {
+                return Ok(false);
+            }
+            txn.set_value(key, value);
+            Ok(true)
+        })
+    }
+
+    /// Replace the value of a key by a value computed from it
+    ///
+    /// Read and write are performed in one transaction, concurrent updates of the same key are
+    /// never lost. The closure runs while the instance is locked, see
+    /// [`transaction`](Self::transaction).
+    ///
+    /// # Parameters
+    ///   * `key`: Key to update
+    ///   * `f`: Closure computing the new value from the value or default value, `None` if
+    ///     neither exists
+    ///
+    /// # Return Values
+    ///   * Ok: New value was assigned
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    ///   * `ErrorCode::QuotaExceeded`: Value would exceed the maximum size
+    ///   * Err: Error returned by the closure, nothing was changed
+    fn update<F>(&self, key: &str, f: F) -> Result<KvsValue, ErrorCode>
+    where
+        F: FnOnce(Option<KvsValue>) -> Result<KvsValue, ErrorCode>,
+    {
+        self.transaction(|txn| {
+            let value = match txn.get_value(key) {
+                Ok(value) => Some(value),
+                Err(e) if e.kind() == &ErrorCode::KeyNotFound => None,
+                Err(e) => return Err(e),
+            };
+            let value = f(value)?;
+            txn.set_value(key, value.clone());
+            Ok(value)
+        })
+    }
+
+    /// Add to an integer counter, see [`update`](Self::update)
+    ///
+    /// The counter keeps its integer type, a missing counter is created as `I64` starting at 0.
+    ///
+    /// # Parameters
+    ///   * `key`: Key of the counter
+    ///   * `delta`: Value to add, negative to decrement
+    ///
+    /// # Return Values
+    ///   * Ok: New value of the counter
+    ///   * `ErrorCode::ConversionFailed`: Value isn't an integer or the result is out of range,
+    ///     nothing was changed
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    fn increment(&self, key: &str, delta: i64) -> Result<KvsValue, ErrorCode> {
+        self.update(key, |value| {
+            let value = value.unwrap_or(KvsValue::I64(0));
+            let sum = match value {
+                KvsValue::I32(n) => i128::from(n),
+                KvsValue::U32(n) => i128::from(n),
+                KvsValue::I64(n) => i128::from(n),
+                KvsValue::U64(n) => i128::from(n),
+                _ => {
+                    kvs_error!(key = key, "increment of non-integer key {key}");
+                    return Err(ErrorCode::ConversionFailed.with_key(key));
+                }
+            } + i128::from(delta);
+            let result = match value {
+                KvsValue::I32(_) => i32::try_from(sum).map(KvsValue::I32),
+                KvsValue::U32(_) => u32::try_from(sum).map(KvsValue::U32),
+                KvsValue::U64(_) => u64::try_from(sum).map(KvsValue::U64),
+                _ => i64::try_from(sum).map(KvsValue::I64),
+            };
+            result.map_err(|_| {
+                kvs_error!(key = key, "increment of key {key} out of range");
+                ErrorCode::ConversionFailed.with_key(key)
+            })
+        })
+    }
+}
+
+/// Match a key against a glob pattern.
+///
+/// `*` matches any sequence of characters, `?` matches a single character and `\` escapes the
+/// next character.
+pub(crate) fn glob_match(pattern: &str, key: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let key: Vec<char> = key.chars().collect();
+    let (mut p, mut k) = (0, 0);
+    // Position after the last `*` and the key position it currently covers up to.
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while k < key.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p + 1, k));
+                p += 1;
+                continue;
+            }
+            Some('?') => {
+                p += 1;
+                k += 1;
+                continue;
+            }
+            Some('\\') if pattern.get(p + 1) == Some(&key[k]) => {
+                p += 2;
+                k += 1;
+                continue;
+            }
+            Some(c) if *c != '\\' && *c == key[k] => {
+                p += 1;
+                k += 1;
+                continue;
+            }
+            _ => {}
+        }
+        // Mismatch, let the last `*` cover one more character.
+        match backtrack {
+            Some((star_p, star_k)) => {
+                backtrack = Some((star_p, star_k + 1));
+                p = star_p;
+                k = star_k + 1;
+            }
+            None => return false,
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
 }
 
 #[cfg(test)]
 mod kvs_api_tests {
-    use crate::kvs_api::{InstanceId, SnapshotId};
+    use crate::error_code::ErrorCode;
+    #[cfg(feature = "mock")]
+    use crate::kvs_api::KvsApi;
+    use crate::kvs_api::{
+        glob_match, InstanceId, KeyCharset, KvsDirStrategy, KvsKeyPolicy, SnapshotId,
+    };
+    #[cfg(feature = "mock")]
+    use crate::kvs_mock::MockKvs;
+    #[cfg(feature = "mock")]
+    use crate::kvs_value::{KvsDeserialize, KvsSerialize, KvsValue};
+    use std::ffi::OsString;
+    use std::path::PathBuf;
+
+    #[cfg(feature = "mock")]
+    #[test]
+    fn test_get_or() {
+        let kvs = MockKvs::default();
+        kvs.set_value("number", 5).unwrap();
+        kvs.set_value("string", "value").unwrap();
+
+        assert_eq!(kvs.get_or("number", 1i32).unwrap(), 5);
+        assert_eq!(kvs.get_or("missing", 1i32).unwrap(), 1);
+        assert_eq!(kvs.get_or("missing", "x".to_string()).unwrap(), "x");
+        assert!(kvs
+            .get_or("string", 1i32)
+            .is_err_and(|e| e == ErrorCode::ConversionFailed));
+    }
+
+    #[cfg(feature = "mock")]
+    #[derive(Debug, PartialEq)]
+    enum ScoreValue {
+        Int(i64),
+        Text(String),
+    }
+
+    #[cfg(feature = "mock")]
+    impl KvsSerialize for ScoreValue {
+        fn to_kvs_value(self) -> KvsValue {
+            match self {
+                ScoreValue::Int(value) => KvsValue::I64(value),
+                ScoreValue::Text(value) => KvsValue::String(value),
+            }
+        }
+    }
+
+    #[cfg(feature = "mock")]
+    impl KvsDeserialize for ScoreValue {
+        fn from_kvs_value(value: &KvsValue) -> Result<Self, ErrorCode> {
+            match value {
+                KvsValue::I64(value) => Ok(ScoreValue::Int(*value)),
+                KvsValue::String(value) => Ok(ScoreValue::Text(value.clone())),
+                _ => Err(ErrorCode::ConversionFailed),
+            }
+        }
+    }
+
+    #[cfg(feature = "mock")]
+    #[test]
+    fn test_model_value() {
+        let kvs = MockKvs::default().with_model::<ScoreValue>();
+        kvs.set_model_value("int", ScoreValue::Int(5)).unwrap();
+        kvs.set_model_value("text", ScoreValue::Text("a".to_string()))
+            .unwrap();
+        kvs.set_value("bool", true).unwrap();
+
+        assert_eq!(kvs.get_value("int").unwrap(), KvsValue::I64(5));
+        assert_eq!(kvs.get_model_value("int").unwrap(), ScoreValue::Int(5));
+        assert_eq!(
+            kvs.get_model_value("text").unwrap(),
+            ScoreValue::Text("a".to_string())
+        );
+        assert!(kvs
+            .get_model_value("bool")
+            .is_err_and(|e| e == ErrorCode::ConversionFailed));
+
+        // The default model is `KvsValue`.
+        let kvs = kvs.with_model::<KvsValue>();
+        assert_eq!(
+            kvs.get_model_value("bool").unwrap(),
+            KvsValue::Boolean(true)
+        );
+    }
+
+    #[cfg(feature = "mock")]
+    #[test]
+    fn test_typed_getters() {
+        let kvs = MockKvs::default();
+        kvs.set_value("i32", 5).unwrap();
+        kvs.set_value("string", "value").unwrap();
+        kvs.set_value("bool", true).unwrap();
+
+        assert_eq!(kvs.get_i32("i32").unwrap(), 5);
+        assert_eq!(kvs.get_string("string").unwrap(), "value");
+        assert!(kvs.get_bool("bool").unwrap());
+        assert!(kvs.get_bool("i32").is_err());
+        assert!(kvs
+            .get_i32("missing")
+            .is_err_and(|e| e == ErrorCode::KeyNotFound));
+    }
+
+    #[cfg(feature = "mock")]
+    #[test]
+    fn test_set_if_absent() {
+        let kvs = MockKvs::default();
+
+        assert!(kvs.set_if_absent("key", 1).unwrap());
+        assert!(!kvs.set_if_absent("key", 2).unwrap());
+        assert_eq!(kvs.get_value("key").unwrap(), KvsValue::I32(1));
+    }
+
+    #[cfg(feature = "mock")]
+    #[test]
+    fn test_update() {
+        let kvs = MockKvs::default();
+
+        assert_eq!(
+            kvs.update("key", |value| {
+                assert_eq!(value, None);
+                Ok(KvsValue::from("a"))
+            })
+            .unwrap(),
+            KvsValue::from("a")
+        );
+        kvs.update("key", |value| match value {
+            Some(KvsValue::String(s)) => Ok(KvsValue::String(s + "b")),
+            _ => Err(ErrorCode::TypeMismatch),
+        })
+        .unwrap();
+        assert_eq!(kvs.get_value("key").unwrap(), KvsValue::from("ab"));
+        assert!(kvs
+            .update("key", |_| Err(ErrorCode::ValidationFailed))
+            .is_err_and(|e| e == ErrorCode::ValidationFailed));
+        assert_eq!(kvs.get_value("key").unwrap(), KvsValue::from("ab"));
+    }
+
+    #[cfg(feature = "mock")]
+    #[test]
+    fn test_increment() {
+        let kvs = MockKvs::default();
+
+        assert_eq!(kvs.increment("new", 2).unwrap(), KvsValue::I64(2));
+        kvs.set_value("i32", i32::MAX - 1).unwrap();
+        assert_eq!(kvs.increment("i32", 1).unwrap(), KvsValue::I32(i32::MAX));
+        assert!(kvs
+            .increment("i32", 1)
+            .is_err_and(|e| e == ErrorCode::ConversionFailed));
+        kvs.set_value("u32", 1u32).unwrap();
+        assert_eq!(kvs.increment("u32", -1).unwrap(), KvsValue::U32(0));
+        assert!(kvs
+            .increment("u32", -1)
+            .is_err_and(|e| e == ErrorCode::ConversionFailed));
+        assert_eq!(kvs.get_value("u32").unwrap(), KvsValue::U32(0));
+        kvs.set_value("string", "1").unwrap();
+        assert!(kvs
+            .increment("string", 1)
+            .is_err_and(|e| e == ErrorCode::ConversionFailed));
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("module.*", "module.sub.key"));
+        assert!(glob_match("module.*.key", "module.sub.key"));
+        assert!(glob_match("*.key", "module.sub.key"));
+        assert!(glob_match("mod?le.*", "module.sub"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("a**b", "ab"));
+        assert!(glob_match("a\\*", "a*"));
+        assert!(!glob_match("a\\*", "ab"));
+        assert!(!glob_match("module.*.key", "module.sub.other"));
+        assert!(!glob_match("?", ""));
+        assert!(!glob_match("module", "module.sub"));
+    }
+
+    #[test]
+    fn test_key_policy_default() {
+        let policy = KvsKeyPolicy::default();
+        assert!(policy.validate("").is_ok());
+        assert!(policy.validate("any key/ä").is_ok());
+    }
+
+    #[test]
+    fn test_key_policy() {
+        let policy = KvsKeyPolicy {
+            max_length: Some(12),
+            charset: KeyCharset::AsciiAlphanumeric("._".to_string()),
+            reserved_prefixes: vec!["__".to_string(), "sys.".to_string()],
+        };
+        assert!(policy.validate("module.key_1").is_ok());
+        assert!(policy
+            .validate("module.key_12")
+            .is_err_and(|e| e == ErrorCode::InvalidKey));
+        assert!(policy
+            .validate("module/key")
+            .is_err_and(|e| e == ErrorCode::InvalidKey));
+        assert!(policy
+            .validate("schlüssel")
+            .is_err_and(|e| e == ErrorCode::InvalidKey));
+        assert!(policy
+            .validate("__internal")
+            .is_err_and(|e| e == ErrorCode::InvalidKey));
+        assert!(policy
+            .validate("sys.key")
+            .is_err_and(|e| e == ErrorCode::InvalidKey));
+        assert!(policy.validate("system").is_ok());
+    }
 
     #[test]
     fn test_instance_id_to_string() {
@@ -127,4 +758,41 @@ mod kvs_api_tests {
         let id = SnapshotId(0);
         assert_eq!(usize::from(id), 0);
     }
+
+    #[test]
+    fn test_dir_strategy_resolve() {
+        let resolve = |vars: &[(&str, &str)]| {
+            KvsDirStrategy::Environment.resolve_with(|name| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| OsString::from(value))
+            })
+        };
+
+        assert_eq!(
+            resolve(&[("KVS_DATA_DIR", "data"), ("HOME", "/home/user")]),
+            Some(PathBuf::from("data"))
+        );
+        assert_eq!(resolve(&[]), None);
+        assert_eq!(KvsDirStrategy::CurrentDir.resolve(), None);
+        if cfg!(windows) {
+            assert_eq!(
+                resolve(&[("ProgramData", "C:\\ProgramData")]),
+                Some(PathBuf::from("C:\\ProgramData\\kvs"))
+            );
+        } else {
+            assert_eq!(
+                resolve(&[("KVS_DATA_DIR", ""), ("HOME", "/home/user")]),
+                Some(PathBuf::from("/home/user/.local/share/kvs"))
+            );
+            assert_eq!(
+                resolve(&[("XDG_DATA_HOME", "/data"), ("HOME", "/home/user")]),
+                Some(PathBuf::from("/data/kvs"))
+            );
+            assert_eq!(
+                resolve(&[("XDG_DATA_HOME", "relative"), ("HOME", "/home/user")]),
+                Some(PathBuf::from("/home/user/.local/share/kvs"))
+            );
+        }
+    }
 }
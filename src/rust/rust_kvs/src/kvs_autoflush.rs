@@ -0,0 +1,195 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Periodic flushing of modified instances.
+//!
+//! [`GenericKvsBuilder::autoflush`](crate::kvs_builder::GenericKvsBuilder::autoflush) and
+//! [`GenericKvs::start_autoflush`] start a thread flushing the instance whenever the interval
+//! elapsed and the instance was modified. One flusher runs per instance and is shared by all
+//! handles, starting another one replaces it. The flusher runs until
+//! [`GenericKvs::cancel_autoflush`] is called or the instance is closed.
+//!
+//! Flushing on `SIGTERM`/`SIGINT` is provided by
+//! [`kvs_shutdown::install_signal_flush`](crate::kvs_shutdown) with the `signal-flush` feature.
+//! Callers having their own scheduler don't need a flusher, calling
+//! [`KvsApi::flush`] from the scheduler only writes modified instances.
+
+use crate::error_code::ErrorCode;
+use crate::kvs::{GenericKvs, KvsParameters};
+use crate::kvs_api::KvsApi;
+use crate::kvs_backend::{KvsBackend, KvsPathResolver};
+use crate::kvs_builder::KvsData;
+use crate::kvs_log::kvs_error;
+use crate::kvs_metrics::KvsMetrics;
+use crate::kvs_value::ValueModel;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Running flusher of an instance.
+pub(crate) struct AutoFlush {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl AutoFlush {
+    /// Start flushing an instance periodically.
+    ///
+    /// The instance data is referenced weakly, the flusher stops once the data is dropped.
+    fn start<Backend: KvsBackend + 'static, PathResolver: KvsPathResolver + 'static>(
+        data: Weak<Mutex<KvsData>>,
+        parameters: KvsParameters,
+        metrics: Option<Arc<dyn KvsMetrics>>,
+        interval: Duration,
+    ) -> Self {
+        let instance_id = parameters.instance_id;
+        let (stop, stop_rx) = mpsc::channel();
+
+        let thread = thread::spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = stop_rx.recv_timeout(interval) {
+                let Some(data) = data.upgrade() else {
+                    break;
+                };
+                let mut kvs = GenericKvs::<Backend, PathResolver>::new(data, parameters.clone());
+                kvs.set_metrics(metrics.clone());
+                if let Err(e) = kvs.flush() {
+                    kvs_error!(
+                        instance_id = instance_id,
+                        "autoflush of instance {instance_id} failed: {e}"
+                    );
+                }
+            }
+        });
+
+        Self {
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+
+    /// Stop flushing and wait for a running flush to finish.
+    fn stop(mut self) {
+        // Dropping the sender wakes up the flusher.
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Dropping only signals the flusher to stop, as the last data reference might be dropped by
+/// the flusher itself.
+impl Drop for AutoFlush {
+    fn drop(&mut self) {
+        self.stop.take();
+    }
+}
+
+impl<Backend: KvsBackend, PathResolver: KvsPathResolver, Model: ValueModel>
+    GenericKvs<Backend, PathResolver, Model>
+{
+    /// Flush the instance periodically
+    ///
+    /// A running flusher of the instance is replaced. Flushing errors are logged and retried
+    /// with the next interval.
+    ///
+    /// # Parameters
+    ///   * `interval`: Time between flushes
+    ///
+    /// # Return Values
+    ///   * Ok: Flusher started
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    pub fn start_autoflush(&self, interval: Duration) -> Result<(), ErrorCode>
+    where
+        Backend: 'static,
+        PathResolver: 'static,
+    {
+        let autoflush = AutoFlush::start::<Backend, PathResolver>(
+            Arc::downgrade(&self.data),
+            self.parameters().clone(),
+            self.metrics.clone(),
+            interval,
+        );
+        let previous = self.lock()?.autoflush.replace(autoflush);
+        if let Some(previous) = previous {
+            previous.stop();
+        }
+        Ok(())
+    }
+
+    /// Stop periodic flushing of the instance
+    ///
+    /// Waits for a running flush to finish. Changes not yet flushed are kept.
+    ///
+    /// # Return Values
+    ///   * Ok: Flusher stopped or none running
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    pub fn cancel_autoflush(&self) -> Result<(), ErrorCode> {
+        // Flusher is joined without holding the data lock it might wait for.
+        let autoflush = self.lock()?.autoflush.take();
+        if let Some(autoflush) = autoflush {
+            autoflush.stop();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "json-backend"))]
+mod kvs_autoflush_tests {
+    use crate::kvs_api::{InstanceId, KvsApi, SnapshotId};
+    use crate::kvs_builder::kvs_builder_tests::lock_and_reset;
+    use crate::KvsBuilder;
+    use std::thread;
+    use std::time::{Duration, Instant};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_autoflush() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+
+        let kvs = KvsBuilder::new(InstanceId(0))
+            .dir(dir_string)
+            .autoflush(Duration::from_millis(10))
+            .build()
+            .unwrap();
+        kvs.set_value("key", "value").unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while kvs.is_dirty().unwrap() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(!kvs.is_dirty().unwrap());
+        assert!(kvs.get_kvs_filename(SnapshotId(0)).is_ok());
+        kvs.cancel_autoflush().unwrap();
+    }
+
+    #[test]
+    fn test_cancel_autoflush() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+
+        let kvs = KvsBuilder::new(InstanceId(0))
+            .dir(dir_string)
+            .autoflush(Duration::from_millis(10))
+            .build()
+            .unwrap();
+        kvs.cancel_autoflush().unwrap();
+        kvs.set_value("key", "value").unwrap();
+
+        thread::sleep(Duration::from_millis(50));
+        assert!(kvs.is_dirty().unwrap());
+        assert!(kvs.cancel_autoflush().is_ok());
+    }
+}
@@ -10,21 +10,177 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::error_code::ErrorCode;
-use crate::kvs_api::{InstanceId, SnapshotId};
-use crate::kvs_value::KvsMap;
+use crate::kvs_api::{
+    DuplicateKeyPolicy, FloatFormat, InstanceId, KvsCompression, SnapshotId, SyncPolicy,
+};
+use crate::kvs_fs::{KvsFs, StdFs};
+use crate::kvs_lazy::LazyKvsMap;
+use crate::kvs_storage::kvs_hash;
+use crate::kvs_value::{KvsMap, KvsValue};
+use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tinyjson::JsonValue;
 
 /// KVS backend interface.
 pub trait KvsBackend {
     /// Load KvsMap from given file.
     fn load_kvs(kvs_path: &Path, hash_path: Option<&PathBuf>) -> Result<KvsMap, ErrorCode>;
 
+    /// Load KvsMap from given file, handling duplicate keys as requested.
+    ///
+    /// Default implementation ignores `duplicate_keys`.
+    fn load_kvs_with_policy(
+        kvs_path: &Path,
+        hash_path: Option<&PathBuf>,
+        _duplicate_keys: DuplicateKeyPolicy,
+    ) -> Result<KvsMap, ErrorCode> {
+        Self::load_kvs(kvs_path, hash_path)
+    }
+
+    /// Load KvsMap from given file with values parsed on first access.
+    ///
+    /// Default implementation returns `None`, the file is loaded with `load_kvs_with_policy`
+    /// instead.
+    ///
+    /// # Return Values
+    ///   * Ok: Indexed values, `None` if the file must be loaded eagerly
+    ///   * Errors of `load_kvs_with_policy`
+    fn load_kvs_lazy(
+        _kvs_path: &Path,
+        _hash_path: Option<&PathBuf>,
+        _duplicate_keys: DuplicateKeyPolicy,
+    ) -> Result<Option<LazyKvsMap>, ErrorCode> {
+        Ok(None)
+    }
+
+    /// Load KvsMap from the content of a stored file, without file access or hash check.
+    ///
+    /// Exercises the parser of the backend on bytes in memory, e.g. in fuzz targets, see
+    /// [`kvs_fuzz`](crate::kvs_fuzz). Default implementation returns
+    /// `ErrorCode::UnmappedError`, for backends not parsing stored files.
+    fn load_kvs_from_bytes(_bytes: &[u8]) -> Result<KvsMap, ErrorCode> {
+        Err(ErrorCode::UnmappedError)
+    }
+
     /// Store KvsMap at given file path.
     fn save_kvs(
         kvs_map: &KvsMap,
         kvs_path: &Path,
         hash_path: Option<&PathBuf>,
     ) -> Result<(), ErrorCode>;
+
+    /// Store KvsMap at given file path, rendering `F64` values as requested.
+    ///
+    /// Default implementation ignores `float_format`.
+    fn save_kvs_formatted(
+        kvs_map: &KvsMap,
+        kvs_path: &Path,
+        hash_path: Option<&PathBuf>,
+        _float_format: &FloatFormat,
+    ) -> Result<(), ErrorCode> {
+        Self::save_kvs(kvs_map, kvs_path, hash_path)
+    }
+
+    /// Store KvsMap at given file path, rendering `F64` values and compressing as requested.
+    ///
+    /// Default implementation ignores `compression`.
+    fn save_kvs_compressed(
+        kvs_map: &KvsMap,
+        kvs_path: &Path,
+        hash_path: Option<&PathBuf>,
+        float_format: &FloatFormat,
+        _compression: KvsCompression,
+    ) -> Result<(), ErrorCode> {
+        Self::save_kvs_formatted(kvs_map, kvs_path, hash_path, float_format)
+    }
+
+    /// Size in bytes of KvsMap as stored by `save_kvs_formatted`.
+    ///
+    /// Default implementation returns the size of the JSON representation.
+    fn serialized_size(kvs_map: &KvsMap, _float_format: &FloatFormat) -> Result<usize, ErrorCode> {
+        let json_value = JsonValue::from(KvsValue::Object(kvs_map.clone()));
+        Ok(json_value.stringify()?.len())
+    }
+
+    /// Check whether a stored file exists at given path.
+    ///
+    /// Default implementation checks the filesystem.
+    fn exists(path: &Path) -> bool {
+        path.exists()
+    }
+
+    /// Get the last modification time of a stored file, used by time-based snapshot retention.
+    ///
+    /// Default implementation reads the modification time from the filesystem.
+    fn modified(path: &Path) -> Option<SystemTime> {
+        StdFs::modified(path).ok()
+    }
+
+    /// Whether the working directory is locked while flushing.
+    ///
+    /// Default implementation returns `true`, backends not storing files in the working directory
+    /// return `false`.
+    fn locks_working_dir() -> bool {
+        true
+    }
+
+    /// Sync stored KvsMap and its hash to the storage device, called after saving on flush.
+    ///
+    /// Default implementation syncs the existing files, see [`sync_files`].
+    ///
+    /// # Return Values
+    ///   * Ok: Synced or nothing to sync
+    ///   * Err: Syncing failed
+    fn sync_kvs(
+        kvs_path: &Path,
+        hash_path: &Path,
+        sync_policy: SyncPolicy,
+    ) -> Result<(), ErrorCode> {
+        sync_files::<StdFs>(&[kvs_path, hash_path], sync_policy)
+    }
+
+    /// Move stored KvsMap and its hash to another location, used for snapshot rotation.
+    ///
+    /// Default implementation renames the files. Nothing is done if neither file exists.
+    ///
+    /// # Return Values
+    ///   * Ok: Moved or nothing to move
+    ///   * `ErrorCode::IntegrityCorrupted`: Only one of KVS and hash file exists
+    fn move_kvs(
+        old_kvs_path: &Path,
+        old_hash_path: &Path,
+        new_kvs_path: &Path,
+        new_hash_path: &Path,
+    ) -> Result<(), ErrorCode> {
+        match (old_kvs_path.exists(), old_hash_path.exists()) {
+            (true, true) => {
+                fs::rename(old_hash_path, new_hash_path)?;
+                fs::rename(old_kvs_path, new_kvs_path)?;
+                Ok(())
+            }
+            (false, false) => Ok(()),
+            // Either snapshot or hash file got removed.
+            _ => Err(ErrorCode::IntegrityCorrupted),
+        }
+    }
+
+    /// Remove stored KvsMap and its hash, used for snapshot pruning.
+    ///
+    /// Default implementation removes the files. Missing files are ignored.
+    ///
+    /// # Return Values
+    ///   * Ok: Removed or nothing to remove
+    ///   * `ErrorCode::UnmappedError`: Removal failed
+    fn remove_kvs(kvs_path: &Path, hash_path: &Path) -> Result<(), ErrorCode> {
+        for path in [kvs_path, hash_path] {
+            match fs::remove_file(path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
 }
 
 /// KVS path resolver interface.
@@ -54,4 +210,95 @@ pub trait KvsPathResolver {
 
     /// Get defaults file path in working directory.
     fn defaults_file_path(working_dir: &Path, instance_id: InstanceId) -> PathBuf;
+
+    /// Get global defaults file name, shared by all instances in a working directory.
+    fn global_defaults_file_name() -> String;
+
+    /// Get global defaults file path in working directory.
+    fn global_defaults_file_path(working_dir: &Path) -> PathBuf;
+}
+
+/// Sync existing files and, if requested, the directory of the first file.
+///
+/// Missing files are skipped, the directory is only synced if a file exists.
+///
+/// # Return Values
+///   * Ok: Synced or nothing to sync
+///   * Err: Opening or syncing a file or the directory failed
+pub(crate) fn sync_files<Fs: KvsFs>(
+    paths: &[&Path],
+    sync_policy: SyncPolicy,
+) -> Result<(), ErrorCode> {
+    if sync_policy == SyncPolicy::None {
+        return Ok(());
+    }
+    let mut synced = false;
+    for path in paths.iter().filter(|path| Fs::exists(path)) {
+        Fs::sync(path)?;
+        synced = true;
+    }
+    // Directories can't be opened for syncing on other platforms.
+    #[cfg(unix)]
+    if synced && sync_policy == SyncPolicy::DataAndDirectory {
+        if let Some(dir) = paths.first().and_then(|path| path.parent()) {
+            let dir = if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir
+            };
+            Fs::sync(dir)?;
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = synced;
+    Ok(())
+}
+
+/// Maximum nesting depth of arrays and objects accepted when decoding binary formats.
+///
+/// Decoders recurse per nesting level, deeper data is rejected instead of overflowing the stack.
+pub(crate) const MAX_NESTING_DEPTH: usize = 128;
+
+/// Check path have correct extension.
+pub(crate) fn check_extension(path: &Path, extension: &str) -> bool {
+    let ext = path.extension();
+    ext.is_some_and(|ep| ep.to_str().is_some_and(|es| es == extension))
+}
+
+/// Compare hash of provided data with hash stored in hash file.
+///
+/// # Return Values
+///   * Ok: Hash matches
+///   * `ErrorCode::ValidationFailed`: Hash mismatch or malformed hash file
+///   * `ErrorCode::KvsHashFileReadError`: Hash file could not be read
+pub(crate) fn validate_hash<Fs: KvsFs>(data: &[u8], hash_path: &Path) -> Result<(), ErrorCode> {
+    let hash_bytes = Fs::read(hash_path).map_err(|e| {
+        ErrorCode::KvsHashFileReadError
+            .with_path(hash_path)
+            .with_source(e)
+    })?;
+    let hash_kvs = kvs_hash(data);
+    if hash_bytes.len() == 4 {
+        let file_hash =
+            u32::from_be_bytes([hash_bytes[0], hash_bytes[1], hash_bytes[2], hash_bytes[3]]);
+        if hash_kvs != file_hash {
+            return Err(ErrorCode::ValidationFailed.with_path(hash_path));
+        }
+    } else {
+        return Err(ErrorCode::ValidationFailed.with_path(hash_path));
+    }
+
+    Ok(())
+}
+
+/// Generate hash of provided data and store it in hash file.
+pub(crate) fn write_hash<Fs: KvsFs>(data: &[u8], hash_path: &Path) -> Result<(), ErrorCode> {
+    write_hash_value::<Fs>(kvs_hash(data), hash_path)
+}
+
+/// Store a hash computed while writing in hash file.
+pub(crate) fn write_hash_value<Fs: KvsFs>(hash: u32, hash_path: &Path) -> Result<(), ErrorCode> {
+    Fs::write(hash_path, &hash.to_be_bytes())
+        .map_err(|e| ErrorCode::from(e).with_path(hash_path))?;
+    Ok(())
 }
@@ -10,15 +10,43 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::error_code::ErrorCode;
-use crate::kvs::{GenericKvs, KvsParameters};
-use crate::kvs_api::{InstanceId, KvsDefaults, KvsLoad, SnapshotId};
+#[cfg(feature = "json-backend")]
+use crate::json_backend::JsonBackend;
+use crate::kvs::{
+    FlushHooks, GenericKvs, KeyAccessStats, KvsParameters, KvsRecoveryInfo, KvsSnapshotStatus,
+    KVS_MAX_SNAPSHOTS,
+};
+use crate::kvs_api::{
+    DuplicateKeyPolicy, FloatFormat, FlushOnExit, InstanceId, KvsApi, KvsCompression, KvsDefaults,
+    KvsDirStrategy, KvsEmbeddedDefaults, KvsKeyPolicy, KvsLoad, SnapshotId, SyncPolicy,
+};
+use crate::kvs_autoflush::AutoFlush;
 use crate::kvs_backend::{KvsBackend, KvsPathResolver};
-use crate::kvs_value::KvsMap;
+use crate::kvs_clock::{KvsClock, KvsSharedClock};
+use crate::kvs_event::{self, KvsEvent};
+use crate::kvs_instance_name;
+use crate::kvs_journal;
+use crate::kvs_lazy::LazyKvsMap;
+use crate::kvs_log::{kvs_debug, kvs_error, kvs_warn};
+use crate::kvs_merge::KvsMergePolicy;
+use crate::kvs_metrics::KvsMetrics;
+use crate::kvs_migration;
+use crate::kvs_resolver::{KvsFile, KvsPathOverride, KvsRuntimePathResolver};
+#[cfg(feature = "snapshots")]
+use crate::kvs_retention::{KvsRetention, KvsRetentionPolicy};
+use crate::kvs_validator::KvsValidator;
+use crate::kvs_value::{KvsMap, KvsValue};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
 use std::marker::PhantomData;
-use std::path::PathBuf;
-use std::sync::{Arc, LazyLock, Mutex, MutexGuard, PoisonError};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, SystemTime};
 
-/// Maximum number of instances.
+/// Default maximum number of instances, see [`GenericKvsBuilder::set_max_instances`].
 const KVS_MAX_INSTANCES: usize = 10;
 
 /// KVS instance data.
@@ -29,6 +57,97 @@ pub(crate) struct KvsData {
 
     /// Optional default values.
     pub(crate) defaults_map: KvsMap,
+
+    /// Storage data was modified since last load or flush.
+    pub(crate) dirty: bool,
+
+    /// Per-key access statistics, if enabled.
+    pub(crate) access_stats: HashMap<String, KeyAccessStats>,
+
+    /// Recovery performed when loading, if any.
+    pub(crate) recovery: Option<KvsRecoveryInfo>,
+
+    /// Snapshot verification performed when opening, if enabled.
+    pub(crate) verification: Option<Vec<(SnapshotId, KvsSnapshotStatus)>>,
+
+    /// Running periodic flusher, if any.
+    pub(crate) autoflush: Option<AutoFlush>,
+
+    /// Data as loaded or last flushed, kept if changes are merged on flush.
+    pub(crate) merge_base: Option<KvsMap>,
+
+    /// Loaded values not parsed yet, not contained in `kvs_map`.
+    pub(crate) lazy: Option<LazyKvsMap>,
+
+    /// Number of reloads after threads panicked while holding the lock.
+    pub(crate) poison_recoveries: usize,
+
+    /// Functions called before and after writing the KVS.
+    pub(crate) flush_hooks: FlushHooks,
+
+    /// Time of the last successful flush by this process.
+    pub(crate) last_flush: Option<SystemTime>,
+
+    /// Defaults changed at runtime and not saved yet, `None` for removed defaults.
+    #[cfg(feature = "defaults")]
+    pub(crate) default_changes: HashMap<String, Option<KvsValue>>,
+}
+
+impl KvsData {
+    /// Parse all values not parsed yet.
+    ///
+    /// # Return Values
+    ///   * Ok: All values are contained in `kvs_map`
+    ///   * `ErrorCode::JsonParserError`: A value couldn't be parsed
+    pub(crate) fn materialize(&mut self) -> Result<(), ErrorCode> {
+        if let Some(lazy) = &mut self.lazy {
+            let kvs_map = lazy.take_all()?;
+            self.kvs_map.extend(kvs_map);
+            self.lazy = None;
+        }
+        Ok(())
+    }
+
+    /// Parse the value of a key if not parsed yet.
+    ///
+    /// # Return Values
+    ///   * Ok: Value of the key is contained in `kvs_map` if it exists
+    ///   * `ErrorCode::JsonParserError`: Value couldn't be parsed
+    pub(crate) fn materialize_key(&mut self, key: &str) -> Result<(), ErrorCode> {
+        if let Some(lazy) = &mut self.lazy {
+            if let Some(value) = lazy.take(key)? {
+                self.kvs_map.insert(key.to_string(), value);
+            }
+            if lazy.is_empty() {
+                self.lazy = None;
+            }
+        }
+        Ok(())
+    }
+
+    /// Replace the defaults, keeping defaults changed at runtime.
+    #[cfg(feature = "defaults")]
+    pub(crate) fn set_defaults(&mut self, mut defaults_map: KvsMap) {
+        for (key, value) in &self.default_changes {
+            match value {
+                Some(value) => defaults_map.insert(key.clone(), value.clone()),
+                None => defaults_map.remove(key),
+            };
+        }
+        self.defaults_map = defaults_map;
+    }
+
+    /// Whether a key exists, without parsing its value.
+    pub(crate) fn contains_key(&self, key: &str) -> bool {
+        self.kvs_map.contains_key(key) || self.lazy.as_ref().is_some_and(|l| l.contains_key(key))
+    }
+
+    /// All keys, without parsing their values.
+    pub(crate) fn keys(&self) -> impl Iterator<Item = &String> {
+        self.kvs_map
+            .keys()
+            .chain(self.lazy.iter().flat_map(|lazy| lazy.keys()))
+    }
 }
 
 impl From<PoisonError<MutexGuard<'_, KvsData>>> for ErrorCode {
@@ -37,91 +156,702 @@ impl From<PoisonError<MutexGuard<'_, KvsData>>> for ErrorCode {
     }
 }
 
+/// Flush of a pooled instance, see [`KvsInner::flush`].
+type KvsFlushFn = fn(&KvsInner, Option<&mut KvsData>) -> Result<(), ErrorCode>;
+
 /// KVS instance inner representation.
+#[derive(Clone)]
 pub(crate) struct KvsInner {
     /// KVS instance parameters.
     pub(crate) parameters: KvsParameters,
 
     /// KVS instance data.
     pub(crate) data: Arc<Mutex<KvsData>>,
+
+    /// Flush with the backend and path resolver the instance was opened with.
+    flush_fn: KvsFlushFn,
+}
+
+impl KvsInner {
+    /// Flush the instance if it changed or wasn't stored yet, see [`KvsApi::flush`].
+    ///
+    /// The pool is untyped, the instance is flushed with the backend and path resolver it was
+    /// opened with.
+    ///
+    /// # Parameters
+    ///   * `data`: Already locked data of the instance, `None` to lock it
+    pub(crate) fn flush(&self, data: Option<&mut KvsData>) -> Result<(), ErrorCode> {
+        (self.flush_fn)(self, data)
+    }
+}
+
+/// Flush a pooled instance with the given backend and path resolver, see [`KvsInner::flush`].
+fn flush_inner<Backend: KvsBackend, PathResolver: KvsPathResolver>(
+    kvs_inner: &KvsInner,
+    data: Option<&mut KvsData>,
+) -> Result<(), ErrorCode> {
+    let kvs = GenericKvs::<Backend, PathResolver>::new(
+        kvs_inner.data.clone(),
+        kvs_inner.parameters.clone(),
+    );
+    match data {
+        Some(data) => kvs.flush_changed(data),
+        None => kvs.flush(),
+    }
+}
+
+/// Open instances by instance ID, numeric and named IDs have separate slots.
+///
+/// Slots are grown when an instance with a higher ID is opened.
+pub(crate) struct KvsPool {
+    /// Instances with numeric IDs, by ID
+    pub(crate) numeric: Vec<Option<KvsInner>>,
+
+    /// Instances with named IDs, by registration order of the name
+    pub(crate) named: Vec<Option<KvsInner>>,
+}
+
+impl KvsPool {
+    /// Create an empty instance pool.
+    pub(crate) const fn new() -> Self {
+        Self {
+            numeric: Vec::new(),
+            named: Vec::new(),
+        }
+    }
+
+    /// Get the slots of the namespace of an instance ID.
+    fn slots(&mut self, instance_id: InstanceId) -> &mut Vec<Option<KvsInner>> {
+        if kvs_instance_name::is_named(instance_id) {
+            &mut self.named
+        } else {
+            &mut self.numeric
+        }
+    }
+
+    /// Get the slot of an instance, grown if needed.
+    fn entry(&mut self, instance_id: InstanceId) -> &mut Option<KvsInner> {
+        let index = kvs_instance_name::pool_index(instance_id);
+        let slots = self.slots(instance_id);
+        if slots.len() <= index {
+            slots.resize(index + 1, None);
+        }
+        &mut slots[index]
+    }
+
+    /// Get an open instance.
+    fn get(&self, instance_id: InstanceId) -> Option<&KvsInner> {
+        let slots = if kvs_instance_name::is_named(instance_id) {
+            &self.named
+        } else {
+            &self.numeric
+        };
+        slots
+            .get(kvs_instance_name::pool_index(instance_id))
+            .and_then(Option::as_ref)
+    }
+
+    /// Iterate all open instances, numeric IDs first.
+    fn iter(&self) -> impl Iterator<Item = &KvsInner> {
+        self.numeric.iter().chain(self.named.iter()).flatten()
+    }
 }
 
-static KVS_POOL: LazyLock<Mutex<[Option<KvsInner>; KVS_MAX_INSTANCES]>> =
-    LazyLock::new(|| Mutex::new([const { None }; KVS_MAX_INSTANCES]));
+static KVS_POOL: Mutex<KvsPool> = Mutex::new(KvsPool::new());
 
-impl From<PoisonError<MutexGuard<'_, [Option<KvsInner>; KVS_MAX_INSTANCES]>>> for ErrorCode {
-    fn from(_cause: PoisonError<MutexGuard<'_, [Option<KvsInner>; KVS_MAX_INSTANCES]>>) -> Self {
+/// Maximum number of instances, only changed while holding the `KVS_POOL` lock.
+static KVS_POOL_MAX_INSTANCES: AtomicUsize = AtomicUsize::new(KVS_MAX_INSTANCES);
+
+impl From<PoisonError<MutexGuard<'_, KvsPool>>> for ErrorCode {
+    fn from(_cause: PoisonError<MutexGuard<'_, KvsPool>>) -> Self {
         ErrorCode::MutexLockFailed
     }
-}
-
-/// Key-value-storage builder.
-pub struct GenericKvsBuilder<Backend: KvsBackend, PathResolver: KvsPathResolver = Backend> {
-    /// KVS instance parameters.
-    parameters: KvsParameters,
+}
+
+/// Get parameters and data of all open instances, ordered by instance ID.
+pub(crate) fn open_instances() -> Result<Vec<KvsInner>, ErrorCode> {
+    let kvs_pool = KVS_POOL.lock()?;
+    Ok(kvs_pool.iter().cloned().collect())
+}
+
+/// Get the maximum number of instances.
+pub(crate) fn pool_max_instances() -> usize {
+    KVS_POOL_MAX_INSTANCES.load(Ordering::Relaxed)
+}
+
+/// Get all open instances without blocking, `None` if the instance pool is locked or poisoned.
+pub(crate) fn try_open_instances() -> Option<Vec<KvsInner>> {
+    let kvs_pool = KVS_POOL.try_lock().ok()?;
+    Some(kvs_pool.iter().cloned().collect())
+}
+
+/// Remove an instance from the instance pool, unless it was already replaced by a reopen.
+///
+/// # Parameters
+///   * `instance_id`: Instance ID
+///   * `data`: Data of the instance to remove
+///
+/// # Return Values
+///   * Ok: Instance is not in the pool anymore
+///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+pub(crate) fn remove_instance(
+    instance_id: InstanceId,
+    data: &Arc<Mutex<KvsData>>,
+) -> Result<(), ErrorCode> {
+    let mut kvs_pool = KVS_POOL.lock()?;
+    let index = kvs_instance_name::pool_index(instance_id);
+    if let Some(kvs_pool_entry) = kvs_pool.slots(instance_id).get_mut(index) {
+        if kvs_pool_entry
+            .as_ref()
+            .is_some_and(|kvs_inner| Arc::ptr_eq(&kvs_inner.data, data))
+        {
+            *kvs_pool_entry = None;
+        }
+    }
+    Ok(())
+}
+
+/// Function starting periodic flushing, set where backend types are known to be `'static`.
+type StartAutoFlush<Backend, PathResolver> =
+    fn(&GenericKvs<Backend, PathResolver>, Duration) -> Result<(), ErrorCode>;
+
+/// Key-value-storage builder.
+pub struct GenericKvsBuilder<Backend: KvsBackend, PathResolver: KvsPathResolver = Backend> {
+    /// KVS instance parameters.
+    parameters: KvsParameters,
+
+    /// Close an already open instance instead of returning it.
+    force_reopen: bool,
+
+    /// Verify all stored snapshots when opening a new instance.
+    verify_snapshots: bool,
+
+    /// Flush changes when the returned handle is dropped.
+    flush_on_exit: FlushOnExit,
+
+    /// Receiver of measurements of the returned handle.
+    metrics: Option<Arc<dyn KvsMetrics>>,
+
+    /// Interval and start function of periodic flushing.
+    autoflush: Option<(Duration, StartAutoFlush<Backend, PathResolver>)>,
+
+    /// Working directory used if none is set.
+    dir_strategy: KvsDirStrategy,
+
+    /// Marker for `Backend`.
+    _backend_marker: PhantomData<Backend>,
+
+    /// Marker for `PathResolver`.
+    _path_resolver_marker: PhantomData<PathResolver>,
+}
+
+impl<Backend: KvsBackend, PathResolver: KvsPathResolver> GenericKvsBuilder<Backend, PathResolver> {
+    /// Create a builder to open the key-value-storage
+    ///
+    /// Only the instance ID must be set. All other settings are using default values until changed
+    /// via the builder API.
+    ///
+    /// # Parameters
+    ///   * `instance_id`: Instance ID
+    ///
+    /// # Return Values
+    ///   * KvsBuilder instance
+    pub fn new(instance_id: InstanceId) -> Self {
+        let parameters = KvsParameters::new(instance_id);
+
+        Self {
+            parameters,
+            force_reopen: false,
+            verify_snapshots: false,
+            flush_on_exit: FlushOnExit::Yes,
+            metrics: None,
+            autoflush: None,
+            dir_strategy: KvsDirStrategy::default(),
+            _backend_marker: PhantomData,
+            _path_resolver_marker: PhantomData,
+        }
+    }
+
+    /// Return maximum number of allowed KVS instances.
+    ///
+    /// # Return Values
+    ///   * Max number of KVS instances
+    pub fn max_instances() -> usize {
+        KVS_POOL_MAX_INSTANCES.load(Ordering::Relaxed)
+    }
+
+    /// Set maximum number of allowed KVS instances.
+    ///
+    /// Instance IDs from 0 up to `max_instances - 1` can be opened. The limit applies to all
+    /// backends and is 10 by default.
+    ///
+    /// # Parameters
+    ///   * `max_instances`: Max number of KVS instances
+    ///
+    /// # Return Values
+    ///   * Ok: Limit was set
+    ///   * `ErrorCode::InvalidInstanceId`: An instance with an ID beyond the limit is open
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    pub fn set_max_instances(max_instances: usize) -> Result<(), ErrorCode> {
+        let kvs_pool = KVS_POOL.lock()?;
+        if let Some(instance_id) = kvs_pool
+            .iter()
+            .map(|kvs_inner| kvs_inner.parameters.instance_id)
+            .find(|instance_id| kvs_instance_name::pool_index(*instance_id) >= max_instances)
+        {
+            kvs_error!(
+                instance_id = instance_id,
+                "can't limit instances to {max_instances}, instance {instance_id} is open"
+            );
+            return Err(ErrorCode::InvalidInstanceId);
+        }
+        KVS_POOL_MAX_INSTANCES.store(max_instances, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Configure defaults handling mode.
+    ///
+    /// # Parameters
+    ///   * `mode`: defaults handling mode (default: [`KvsDefaults::Optional`](KvsDefaults::Optional))
+    ///
+    /// # Return Values
+    ///   * KvsBuilder instance
+    pub fn defaults(mut self, mode: KvsDefaults) -> Self {
+        self.parameters.defaults = mode;
+        self
+    }
+
+    /// Embed default values in JSON format.
+    ///
+    /// The defaults are used instead of the instance defaults file if it doesn't exist, also
+    /// with [`KvsDefaults::Required`](KvsDefaults::Required). The content has the format of a
+    /// defaults file of the JSON backend and is parsed when opening the instance.
+    ///
+    /// ```
+    /// use rust_kvs::prelude::*;
+    ///
+    /// let builder = KvsBuilder::new(InstanceId(0))
+    ///     .defaults_from_str(r#"{"t":"obj","v":{"timeout":{"t":"i32","v":30}}}"#);
+    /// ```
+    ///
+    /// # Parameters
+    ///   * `json`: Content of a defaults file, e.g. `include_str!("defaults.json")`
+    ///
+    /// # Return Values
+    ///   * KvsBuilder instance
+    #[cfg(feature = "json-backend")]
+    pub fn defaults_from_str(mut self, json: &'static str) -> Self {
+        self.parameters.embedded_defaults = Some(KvsEmbeddedDefaults::Json(json));
+        self
+    }
+
+    /// Embed default values.
+    ///
+    /// The defaults are used instead of the instance defaults file if it doesn't exist, see
+    /// [`GenericKvsBuilder::defaults_from_str`].
+    ///
+    /// # Parameters
+    ///   * `defaults_map`: Default values
+    ///
+    /// # Return Values
+    ///   * KvsBuilder instance
+    pub fn defaults_map(mut self, defaults_map: KvsMap) -> Self {
+        self.parameters.embedded_defaults = Some(KvsEmbeddedDefaults::Map(defaults_map));
+        self
+    }
+
+    /// Configure KVS load mode.
+    ///
+    /// # Parameters
+    ///   * `mode`: KVS load mode (default: [`KvsLoad::Optional`](KvsLoad::Optional))
+    ///
+    /// # Return Values
+    ///   * KvsBuilder instance
+    pub fn kvs_load(mut self, mode: KvsLoad) -> Self {
+        self.parameters.kvs_load = mode;
+        self
+    }
+
+    /// Configure per-key access statistics.
+    ///
+    /// # Parameters
+    ///   * `enabled`: Track read and write counts per key (default: `false`)
+    ///
+    /// # Return Values
+    ///   * KvsBuilder instance
+    pub fn access_stats(mut self, enabled: bool) -> Self {
+        self.parameters.access_stats = enabled;
+        self
+    }
+
+    /// Configure rendering of `F64` values in stored files.
+    ///
+    /// Only supported by the JSON backend, other backends ignore this setting.
+    ///
+    /// # Parameters
+    ///   * `format`: Float format (default: [`FloatFormat::Plain`](FloatFormat::Plain))
+    ///
+    /// # Return Values
+    ///   * KvsBuilder instance
+    pub fn float_format(mut self, format: FloatFormat) -> Self {
+        self.parameters.float_format = format;
+        self
+    }
+
+    /// Configure handling of duplicate keys in loaded defaults and KVS files.
+    ///
+    /// Only supported by the JSON backend, other backends ignore this setting.
+    ///
+    /// # Parameters
+    ///   * `policy`: Duplicate key policy (default: [`DuplicateKeyPolicy::LastWinsWithWarning`](DuplicateKeyPolicy::LastWinsWithWarning))
+    ///
+    /// # Return Values
+    ///   * KvsBuilder instance
+    pub fn duplicate_keys(mut self, policy: DuplicateKeyPolicy) -> Self {
+        self.parameters.duplicate_keys = policy;
+        self
+    }
+
+    /// Configure compression of stored KVS files.
+    ///
+    /// Only supported by the JSON backend, other backends ignore this setting. Compressed files
+    /// are loaded transparently regardless of this setting, the size limit of
+    /// [`max_size`](Self::max_size) applies to the uncompressed size.
+    ///
+    /// # Parameters
+    ///   * `compression`: Compression (default: [`KvsCompression::None`](KvsCompression::None))
+    ///
+    /// # Return Values
+    ///   * KvsBuilder instance
+    pub fn compression(mut self, compression: KvsCompression) -> Self {
+        self.parameters.compression = compression;
+        self
+    }
+
+    /// Configure syncing of stored files to the storage device on flush.
+    ///
+    /// Without syncing a flush that returned successfully may be lost on power loss.
+    ///
+    /// # Parameters
+    ///   * `sync_policy`: Sync policy (default: [`SyncPolicy::None`](SyncPolicy::None))
+    ///
+    /// # Return Values
+    ///   * KvsBuilder instance
+    pub fn sync_policy(mut self, sync_policy: SyncPolicy) -> Self {
+        self.parameters.sync_policy = sync_policy;
+        self
+    }
+
+    /// Configure journaling of changes between flushes.
+    ///
+    /// Each change is appended to a journal file, which is compacted into the current snapshot on
+    /// flush and when the instance is opened, see [`kvs_journal`](crate::kvs_journal).
+    ///
+    /// # Parameters
+    ///   * `journal`: Journal is written (default: `false`)
+    ///
+    /// # Return Values
+    ///   * KvsBuilder instance
+    pub fn journal(mut self, journal: bool) -> Self {
+        self.parameters.journal = journal;
+        self
+    }
+
+    /// Configure validation of written keys.
+    ///
+    /// Writes of keys violating the policy fail with `ErrorCode::InvalidKey`.
+    ///
+    /// # Parameters
+    ///   * `policy`: Key policy (default: all keys accepted)
+    ///
+    /// # Return Values
+    ///   * KvsBuilder instance
+    pub fn key_policy(mut self, policy: KvsKeyPolicy) -> Self {
+        self.parameters.key_policy = policy;
+        self
+    }
+
+    /// Limit the serialized size of the instance.
+    ///
+    /// Writes and flushes making the stored instance exceed the limit fail with
+    /// `ErrorCode::QuotaExceeded`. The size is computed as stored by the backend.
+    ///
+    /// # Features
+    ///   * `FEAT_REQ__KVS__maximum_size`
+    ///
+    /// # Parameters
+    ///   * `bytes`: Maximum size in bytes (default: unlimited)
+    ///
+    /// # Return Values
+    ///   * KvsBuilder instance
+    pub fn max_size(mut self, bytes: usize) -> Self {
+        self.parameters.max_size = Some(bytes);
+        self
+    }
+
+    /// Limit the count of keys of the instance.
+    ///
+    /// Writes adding keys beyond the limit fail with `ErrorCode::QuotaExceeded`. Instances loaded
+    /// with more keys can still be updated.
+    ///
+    /// # Parameters
+    ///   * `count`: Maximum count of keys (default: unlimited)
+    ///
+    /// # Return Values
+    ///   * KvsBuilder instance
+    pub fn max_keys(mut self, count: usize) -> Self {
+        self.parameters.max_keys = Some(count);
+        self
+    }
+
+    /// Limit the size of a single value.
+    ///
+    /// Writes of larger values fail with `ErrorCode::QuotaExceeded`. The size is estimated by
+    /// [`KvsValue::serialized_size_hint`](crate::kvs_value::KvsValue::serialized_size_hint).
+    ///
+    /// # Parameters
+    ///   * `bytes`: Maximum value size in bytes (default: unlimited)
+    ///
+    /// # Return Values
+    ///   * KvsBuilder instance
+    pub fn max_value_bytes(mut self, bytes: usize) -> Self {
+        self.parameters.max_value_bytes = Some(bytes);
+        self
+    }
+
+    /// Reject writes changing the value type of a key.
+    ///
+    /// With strict typing, writing a key that is stored or has a default value fails with
+    /// `ErrorCode::TypeMismatch` if the written value has another
+    /// [`KvsValueKind`](crate::kvs_value::KvsValueKind), e.g. an `I32` key can't be overwritten
+    /// with `I64` or `Null`. Removing and re-adding a key without default value changes its type.
+    ///
+    /// # Parameters
+    ///   * `strict_types`: Reject type changes (default: `false`)
+    ///
+    /// # Return Values
+    ///   * KvsBuilder instance
+    pub fn strict_types(mut self, strict_types: bool) -> Self {
+        self.parameters.strict_types = strict_types;
+        self
+    }
+
+    /// Reopen the instance if it is already open.
+    ///
+    /// An open instance is closed with [`GenericKvs::close`] first, so the instance is opened
+    /// with the configured parameters even if they differ from the open instance. Without this
+    /// setting an open instance is returned if the parameters match and opening fails with
+    /// `ErrorCode::InstanceParametersMismatch` otherwise.
+    ///
+    /// # Return Values
+    ///   * KvsBuilder instance
+    pub fn force_reopen(mut self) -> Self {
+        self.force_reopen = true;
+        self
+    }
+
+    /// Verify all stored snapshots when opening the instance
+    ///
+    /// Opening only validates the loaded KVS. With this setting all stored snapshots are
+    /// validated against their hash files concurrently after loading, so a corrupted snapshot is
+    /// detected before it's needed for a restore. Broken snapshots are logged and reported by
+    /// [`GenericKvs::verification_info`], opening doesn't fail because of them. An instance which
+    /// is already open isn't verified again.
+    ///
+    /// # Parameters
+    ///   * `verify_snapshots`: Verify all snapshots (default: `false`)
+    ///
+    /// # Return Values
+    ///   * KvsBuilder instance
+    pub fn verify_snapshots(mut self, verify_snapshots: bool) -> Self {
+        self.verify_snapshots = verify_snapshots;
+        self
+    }
+
+    /// Configure flushing of changes when the returned handle is dropped.
+    ///
+    /// Can be changed later with [`KvsApi::set_flush_on_exit`](crate::kvs_api::KvsApi::set_flush_on_exit).
+    ///
+    /// # Parameters
+    ///   * `flush_on_exit`: Flush on drop (default: [`FlushOnExit::Yes`](FlushOnExit::Yes))
+    ///
+    /// # Return Values
+    ///   * KvsBuilder instance
+    pub fn flush_on_exit(mut self, flush_on_exit: FlushOnExit) -> Self {
+        self.flush_on_exit = flush_on_exit;
+        self
+    }
+
+    /// Set defaults files layered over the instance defaults
+    ///
+    /// Defaults are merged in order: global defaults, instance defaults, then `files`, later files
+    /// overriding earlier ones. Unless defaults are ignored, all files must exist. Reloading
+    /// defaults reloads all files.
+    ///
+    /// # Parameters
+    ///   * `files`: Defaults file paths, e.g. base calibration followed by variant overrides
+    ///     (default: none)
+    ///
+    /// # Return Values
+    ///   * KvsBuilder instance
+    pub fn defaults_files<I, P>(mut self, files: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        self.parameters.defaults_files = files.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Merge changes flushed by other processes on flush
+    ///
+    /// The stored KVS is reloaded and merged per key before writing, see
+    /// [`kvs_merge`](crate::kvs_merge).
+    ///
+    /// # Parameters
+    ///   * `policy`: Merge policy (default: [`KvsMergePolicy::Overwrite`](KvsMergePolicy::Overwrite))
+    ///
+    /// # Return Values
+    ///   * KvsBuilder instance
+    pub fn merge_on_flush(mut self, policy: KvsMergePolicy) -> Self {
+        self.parameters.merge_policy = policy;
+        self
+    }
+
+    /// Parse values on first access
+    ///
+    /// Values of the KVS file are parsed when their key is read first instead of when opening
+    /// the instance, see [`kvs_lazy`](crate::kvs_lazy). Not used if changes are merged on flush.
+    ///
+    /// # Parameters
+    ///   * `enabled`: Lazy loading is enabled (default: `false`)
+    ///
+    /// # Return Values
+    ///   * KvsBuilder instance
+    pub fn lazy_load(mut self, enabled: bool) -> Self {
+        self.parameters.lazy_load = enabled;
+        self
+    }
 
-    /// Marker for `Backend`.
-    _backend_marker: PhantomData<Backend>,
+    /// Resolve file paths at runtime
+    ///
+    /// The resolver receives each path of the `KvsPathResolver` type parameter and returns the
+    /// path to use, see [`kvs_resolver`](crate::kvs_resolver).
+    ///
+    /// # Parameters
+    ///   * `resolver`: Resolver object or function (default: paths of the type parameter)
+    ///
+    /// # Return Values
+    ///   * KvsBuilder instance
+    pub fn path_resolver<R: KvsRuntimePathResolver + 'static>(mut self, resolver: R) -> Self {
+        self.parameters.path_override = Some(KvsPathOverride(Arc::new(resolver)));
+        self
+    }
 
-    /// Marker for `PathResolver`.
-    _path_resolver_marker: PhantomData<PathResolver>,
-}
+    /// Set the retention policy of snapshots
+    ///
+    /// The policy is consulted after each snapshot rotation and selects snapshots to discard, see
+    /// [`kvs_retention`](crate::kvs_retention).
+    ///
+    /// # Parameters
+    ///   * `policy`: Retention policy, e.g. [`KeepLastN`](crate::kvs_retention::KeepLastN) (default:
+    ///     all snapshot slots are kept)
+    ///
+    /// # Return Values
+    ///   * KvsBuilder instance
+    #[cfg(feature = "snapshots")]
+    pub fn retention<P: KvsRetentionPolicy + 'static>(mut self, policy: P) -> Self {
+        self.parameters.retention = Some(KvsRetention(Arc::new(policy)));
+        self
+    }
 
-impl<Backend: KvsBackend, PathResolver: KvsPathResolver> GenericKvsBuilder<Backend, PathResolver> {
-    /// Create a builder to open the key-value-storage
+    /// Set keys holding sensitive values
     ///
-    /// Only the instance ID must be set. All other settings are using default values until changed
-    /// via the builder API.
+    /// Values of these keys are printed as `<redacted>` by [`GenericKvs::redacted`], see
+    /// [`kvs_redact`](crate::kvs_redact).
     ///
     /// # Parameters
-    ///   * `instance_id`: Instance ID
+    ///   * `keys`: Sensitive keys, e.g. credentials (default: none)
     ///
     /// # Return Values
     ///   * KvsBuilder instance
-    pub fn new(instance_id: InstanceId) -> Self {
-        let parameters = KvsParameters {
-            instance_id,
-            defaults: KvsDefaults::Optional,
-            kvs_load: KvsLoad::Optional,
-            working_dir: PathBuf::new(),
-        };
+    pub fn sensitive_keys<I, S>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.parameters.sensitive_keys = keys.into_iter().map(Into::into).collect();
+        self
+    }
 
-        Self {
-            parameters,
-            _backend_marker: PhantomData,
-            _path_resolver_marker: PhantomData,
-        }
+    /// Set the clock of the instance
+    ///
+    /// Timestamps, e.g. of the last flush, are taken from the clock, see
+    /// [`kvs_clock`](crate::kvs_clock).
+    ///
+    /// # Parameters
+    ///   * `clock`: Clock, e.g. a shared [`FakeClock`](crate::kvs_clock::FakeClock) (default:
+    ///     system time)
+    ///
+    /// # Return Values
+    ///   * KvsBuilder instance
+    pub fn clock(mut self, clock: Arc<dyn KvsClock>) -> Self {
+        self.parameters.clock = Some(KvsSharedClock(clock));
+        self
     }
 
-    /// Return maximum number of allowed KVS instances.
+    /// Add a validator of written values
+    ///
+    /// Writes of keys matching the pattern are rejected with `ErrorCode::ValidationFailed` if the
+    /// function returns an error, see [`kvs_validator`](crate::kvs_validator). Multiple validators
+    /// can match a key, all of them must accept the value.
+    ///
+    /// # Parameters
+    ///   * `key_pattern`: Glob pattern of validated keys, `*` and `?` as wildcards
+    ///   * `validate`: Function returning the reason if a value is rejected
     ///
     /// # Return Values
-    ///   * Max number of KVS instances
-    pub fn max_instances() -> usize {
-        KVS_MAX_INSTANCES
+    ///   * KvsBuilder instance
+    pub fn validator<S, F>(mut self, key_pattern: S, validate: F) -> Self
+    where
+        S: Into<String>,
+        F: Fn(&KvsValue) -> Result<(), String> + Send + Sync + 'static,
+    {
+        self.parameters.validators.push(KvsValidator {
+            pattern: key_pattern.into(),
+            validate: Arc::new(validate),
+        });
+        self
     }
 
-    /// Configure defaults handling mode.
+    /// Publish a read-only view of the instance for other processes
+    ///
+    /// The effective values are written to the view file when the instance is opened and on
+    /// every flush, see [`kvs_shared_view`](crate::kvs_shared_view).
     ///
     /// # Parameters
-    ///   * `mode`: defaults handling mode (default: [`KvsDefaults::Optional`](KvsDefaults::Optional))
+    ///   * `enabled`: View is published (default: `false`)
     ///
     /// # Return Values
     ///   * KvsBuilder instance
-    pub fn defaults(mut self, mode: KvsDefaults) -> Self {
-        self.parameters.defaults = mode;
+    #[cfg(feature = "shm-cache")]
+    pub fn shared_view(mut self, enabled: bool) -> Self {
+        self.parameters.shared_view = enabled;
         self
     }
 
-    /// Configure KVS load mode.
+    /// Set receiver of measurements
+    ///
+    /// Measurements of operations on the returned handle and hash validation failures while
+    /// opening are reported, see [`KvsMetrics`].
     ///
     /// # Parameters
-    ///   * `mode`: KVS load mode (default: [`KvsLoad::Optional`](KvsLoad::Optional))
+    ///   * `metrics`: Receiver of measurements (default: none)
     ///
     /// # Return Values
     ///   * KvsBuilder instance
-    pub fn kvs_load(mut self, mode: KvsLoad) -> Self {
-        self.parameters.kvs_load = mode;
+    pub fn metrics(mut self, metrics: Arc<dyn KvsMetrics>) -> Self {
+        self.metrics = Some(metrics);
         self
     }
 
@@ -136,10 +866,44 @@ impl<Backend: KvsBackend, PathResolver: KvsPathResolver> GenericKvsBuilder<Backe
         self
     }
 
+    /// Configure renamed keys
+    ///
+    /// Values stored under a legacy key are moved to the new key when loading, so reads of the
+    /// new key return them. The data is written under the new key on next flush. Values of legacy
+    /// keys are dropped if the new key is stored as well.
+    ///
+    /// # Parameters
+    ///   * `aliases`: New key by legacy key (default: none)
+    ///
+    /// # Return Values
+    ///   * KvsBuilder instance
+    pub fn key_aliases(mut self, aliases: HashMap<String, String>) -> Self {
+        self.parameters.key_aliases = aliases;
+        self
+    }
+
+    /// Configure the working directory used if none is set with [`dir`](Self::dir)
+    ///
+    /// The directory is resolved when building and created if it doesn't exist. The resolved
+    /// directory is part of the instance parameters.
+    ///
+    /// # Parameters
+    ///   * `strategy`: Directory strategy (default: [`KvsDirStrategy::CurrentDir`](KvsDirStrategy::CurrentDir))
+    ///
+    /// # Return Values
+    ///   * KvsBuilder instance
+    pub fn dir_strategy(mut self, strategy: KvsDirStrategy) -> Self {
+        self.dir_strategy = strategy;
+        self
+    }
+
     /// Finalize the builder and open the key-value-storage
     ///
     /// Calls `Kvs::open` with the configured settings.
     ///
+    /// Unless defaults are ignored, an existing global defaults file in the working directory is
+    /// loaded first. Per-instance defaults take precedence over global defaults.
+    ///
     /// # Features
     ///   * `FEAT_REQ__KVS__default_values`
     ///   * `FEAT_REQ__KVS__multiple_kvs`
@@ -153,104 +917,649 @@ impl<Backend: KvsBackend, PathResolver: KvsPathResolver> GenericKvsBuilder<Backe
     ///   * `ErrorCode::KvsHashFileReadError`: KVS hash file read error
     ///   * `ErrorCode::UnmappedError`: Generic error
     pub fn build(self) -> Result<GenericKvs<Backend, PathResolver>, ErrorCode> {
+        self.build_steps(&mut Vec::new())
+    }
+
+    /// Finalize the builder and open the key-value-storage, reporting each step on failure
+    ///
+    /// Same as [`build`](Self::build), but the error contains the outcome of every resolution
+    /// step performed, including the paths involved.
+    ///
+    /// # Return Values
+    ///   * Ok: KVS instance
+    ///   * Err: Diagnostic report
+    pub fn try_build(self) -> Result<GenericKvs<Backend, PathResolver>, KvsBuildReport> {
+        let instance_id = self.parameters.instance_id;
+        let mut steps = Vec::new();
+        self.build_steps(&mut steps)
+            .map_err(|error| KvsBuildReport {
+                instance_id,
+                steps,
+                error,
+            })
+    }
+
+    /// Open the key-value-storage, recording performed steps.
+    fn build_steps(
+        mut self,
+        steps: &mut Vec<KvsBuildStep>,
+    ) -> Result<GenericKvs<Backend, PathResolver>, ErrorCode> {
         let instance_id = self.parameters.clone().instance_id;
-        let instance_id_index: usize = instance_id.into();
-        let working_dir = self.parameters.clone().working_dir;
+        let instance_id_index = kvs_instance_name::pool_index(instance_id);
+
+        // Resolve working directory before parameters are compared with an open instance.
+        if self.parameters.working_dir.as_os_str().is_empty() {
+            if let Some(working_dir) = self.dir_strategy.resolve() {
+                record(
+                    steps,
+                    KvsBuildStepKind::WorkingDir,
+                    Some(&working_dir),
+                    fs::create_dir_all(&working_dir).map_err(ErrorCode::from),
+                )?;
+                self.parameters.working_dir = working_dir;
+            }
+        }
 
         // Check if instance already exists.
-        {
-            let kvs_pool = KVS_POOL.lock()?;
-            let kvs_inner_option = match kvs_pool.get(instance_id_index) {
-                Some(kvs_pool_entry) => match kvs_pool_entry {
-                    // If instance exists then parameters must match.
+        let kvs_inner_option = KVS_POOL
+            .lock()
+            .map_err(ErrorCode::from)
+            .and_then(|kvs_pool| {
+                // Instance ID out of range.
+                if instance_id_index >= Self::max_instances() {
+                    return Err(ErrorCode::InvalidInstanceId);
+                }
+                kvs_instance_name::check(instance_id)?;
+                match kvs_pool.get(instance_id) {
+                    // If instance exists then parameters must match unless it's reopened.
                     Some(kvs_inner) => {
-                        if kvs_inner.parameters == self.parameters {
-                            Ok(Some(kvs_inner))
+                        if self.force_reopen || kvs_inner.parameters == self.parameters {
+                            Ok(Some(kvs_inner.clone()))
                         } else {
                             Err(ErrorCode::InstanceParametersMismatch)
                         }
                     }
                     // Instance not found - not an error, will initialize later.
                     None => Ok(None),
-                },
-                // Instance ID out of range.
-                None => Err(ErrorCode::InvalidInstanceId),
-            }?;
-
-            // Return existing instance if initialized.
-            if let Some(kvs_inner) = kvs_inner_option {
-                return Ok(GenericKvs::<Backend, PathResolver>::new(
-                    kvs_inner.data.clone(),
-                    kvs_inner.parameters.clone(),
-                ));
+                }
+            });
+        let kvs_inner_option = record(
+            steps,
+            KvsBuildStepKind::InstancePool,
+            None,
+            kvs_inner_option,
+        )?;
+
+        // Return existing instance if initialized, or close it on forced reopen.
+        if let Some(kvs_inner) = kvs_inner_option {
+            let mut kvs =
+                GenericKvs::<Backend, PathResolver>::new(kvs_inner.data, kvs_inner.parameters);
+            if !self.force_reopen {
+                kvs.set_metrics(self.metrics);
+                if let Some((interval, start_autoflush)) = self.autoflush {
+                    start_autoflush(&kvs, interval)?;
+                }
+                return Ok(kvs);
             }
+            record(steps, KvsBuildStepKind::InstancePool, None, kvs.close())?;
         }
 
         // Initialize KVS instance with provided parameters.
-        // Load file containing defaults.
-        let defaults_path = PathResolver::defaults_file_path(&working_dir, instance_id);
-        let defaults_map = match self.parameters.defaults {
-            KvsDefaults::Ignored => KvsMap::new(),
+        // Load file containing defaults, embedded defaults replace a missing file.
+        let defaults_path = self.parameters.file_path::<PathResolver>(KvsFile::Defaults);
+        let embedded_defaults = |steps: &mut Vec<KvsBuildStep>| {
+            let embedded = self.parameters.embedded_defaults.as_ref()?;
+            skip(
+                steps,
+                KvsBuildStepKind::Defaults,
+                &defaults_path,
+                "file not found, embedded defaults used",
+            );
+            Some(match embedded {
+                #[cfg(feature = "json-backend")]
+                KvsEmbeddedDefaults::Json(json) => record(
+                    steps,
+                    KvsBuildStepKind::EmbeddedDefaults,
+                    None,
+                    JsonBackend::kvs_map_from_str(json, self.parameters.duplicate_keys),
+                ),
+                KvsEmbeddedDefaults::Map(defaults_map) => Ok::<_, ErrorCode>(defaults_map.clone()),
+            })
+        };
+        let instance_defaults_map = match self.parameters.defaults {
+            KvsDefaults::Ignored => {
+                skip(
+                    steps,
+                    KvsBuildStepKind::Defaults,
+                    &defaults_path,
+                    "defaults ignored",
+                );
+                KvsMap::new()
+            }
             KvsDefaults::Optional => {
-                if defaults_path.exists() {
-                    Backend::load_kvs(&defaults_path, None)?
+                if Backend::exists(&defaults_path) {
+                    record(
+                        steps,
+                        KvsBuildStepKind::Defaults,
+                        Some(&defaults_path),
+                        Backend::load_kvs_with_policy(
+                            &defaults_path,
+                            None,
+                            self.parameters.duplicate_keys,
+                        ),
+                    )?
+                } else if let Some(defaults_map) = embedded_defaults(steps) {
+                    defaults_map?
+                } else {
+                    skip(
+                        steps,
+                        KvsBuildStepKind::Defaults,
+                        &defaults_path,
+                        "file not found",
+                    );
+                    KvsMap::new()
+                }
+            }
+            KvsDefaults::Required if !Backend::exists(&defaults_path) => {
+                match embedded_defaults(steps) {
+                    Some(defaults_map) => defaults_map?,
+                    None => record(
+                        steps,
+                        KvsBuildStepKind::Defaults,
+                        Some(&defaults_path),
+                        Backend::load_kvs_with_policy(
+                            &defaults_path,
+                            None,
+                            self.parameters.duplicate_keys,
+                        ),
+                    )?,
+                }
+            }
+            KvsDefaults::Required => record(
+                steps,
+                KvsBuildStepKind::Defaults,
+                Some(&defaults_path),
+                Backend::load_kvs_with_policy(&defaults_path, None, self.parameters.duplicate_keys),
+            )?,
+        };
+
+        // Global defaults are optional and overridden by instance defaults.
+        let global_defaults_path = self
+            .parameters
+            .file_path::<PathResolver>(KvsFile::GlobalDefaults);
+        let mut defaults_map = match self.parameters.defaults {
+            KvsDefaults::Ignored => {
+                skip(
+                    steps,
+                    KvsBuildStepKind::GlobalDefaults,
+                    &global_defaults_path,
+                    "defaults ignored",
+                );
+                KvsMap::new()
+            }
+            KvsDefaults::Optional | KvsDefaults::Required => {
+                if Backend::exists(&global_defaults_path) {
+                    record(
+                        steps,
+                        KvsBuildStepKind::GlobalDefaults,
+                        Some(&global_defaults_path),
+                        Backend::load_kvs_with_policy(
+                            &global_defaults_path,
+                            None,
+                            self.parameters.duplicate_keys,
+                        ),
+                    )?
                 } else {
+                    skip(
+                        steps,
+                        KvsBuildStepKind::GlobalDefaults,
+                        &global_defaults_path,
+                        "file not found",
+                    );
                     KvsMap::new()
                 }
             }
-            KvsDefaults::Required => Backend::load_kvs(&defaults_path, None)?,
         };
+        defaults_map.extend(instance_defaults_map);
+
+        // Layered defaults files override instance defaults in order.
+        for layer_path in &self.parameters.defaults_files {
+            if self.parameters.defaults == KvsDefaults::Ignored {
+                skip(
+                    steps,
+                    KvsBuildStepKind::LayeredDefaults,
+                    layer_path,
+                    "defaults ignored",
+                );
+                continue;
+            }
+            defaults_map.extend(record(
+                steps,
+                KvsBuildStepKind::LayeredDefaults,
+                Some(layer_path),
+                Backend::load_kvs_with_policy(layer_path, None, self.parameters.duplicate_keys),
+            )?);
+        }
 
         // Load KVS and hash files.
-        let snapshot_id = SnapshotId(0);
-        let kvs_path = PathResolver::kvs_file_path(&working_dir, instance_id, snapshot_id);
-        let hash_path = PathResolver::hash_file_path(&working_dir, instance_id, snapshot_id);
+        let kvs_path = self.parameters.kvs_file_path::<PathResolver>(SnapshotId(0));
+        let hash_path = self
+            .parameters
+            .hash_file_path::<PathResolver>(SnapshotId(0));
+        // Lazily loaded values are kept aside, the loaded map stays empty.
+        let lazy_load =
+            self.parameters.lazy_load && self.parameters.merge_policy == KvsMergePolicy::Overwrite;
+        let lazy = Cell::new(None);
+        let load_kvs = |steps: &mut Vec<KvsBuildStep>, snapshot_id: SnapshotId| {
+            let kvs_path = self.parameters.kvs_file_path::<PathResolver>(snapshot_id);
+            let hash_path = self.parameters.hash_file_path::<PathResolver>(snapshot_id);
+            let loaded = if lazy_load {
+                Backend::load_kvs_lazy(&kvs_path, Some(&hash_path), self.parameters.duplicate_keys)
+                    .transpose()
+            } else {
+                None
+            };
+            let loaded = match loaded {
+                Some(Ok(lazy_map)) => {
+                    lazy.set(Some(lazy_map));
+                    Ok(KvsMap::new())
+                }
+                Some(Err(e)) => Err(e),
+                None => Backend::load_kvs_with_policy(
+                    &kvs_path,
+                    Some(&hash_path),
+                    self.parameters.duplicate_keys,
+                ),
+            };
+            let result = kvs_event::check_integrity(
+                loaded,
+                instance_id,
+                snapshot_id,
+                self.metrics.as_deref(),
+            );
+            // Hash related errors are attributed to the hash file.
+            let kind = match result.as_ref().map_err(ErrorCode::kind) {
+                Err(
+                    ErrorCode::KvsHashFileReadError
+                    | ErrorCode::ValidationFailed
+                    | ErrorCode::IntegrityCorrupted,
+                ) => KvsBuildStepKind::HashFile,
+                _ => KvsBuildStepKind::KvsFile,
+            };
+            record(steps, kind, Some(&kvs_path), result)
+        };
+        let mut recovery = None;
         let kvs_map = match self.parameters.kvs_load {
-            KvsLoad::Ignored => KvsMap::new(),
+            KvsLoad::Ignored => {
+                skip(
+                    steps,
+                    KvsBuildStepKind::KvsFile,
+                    &kvs_path,
+                    "KVS load ignored",
+                );
+                KvsMap::new()
+            }
             KvsLoad::Optional => {
-                if kvs_path.exists() && hash_path.exists() {
-                    Backend::load_kvs(&kvs_path, Some(&hash_path))?
+                if Backend::exists(&kvs_path) && Backend::exists(&hash_path) {
+                    load_kvs(steps, SnapshotId(0))?
                 } else {
+                    let reason = if Backend::exists(&kvs_path) {
+                        "hash file not found"
+                    } else {
+                        "file not found"
+                    };
+                    skip(steps, KvsBuildStepKind::KvsFile, &kvs_path, reason);
+                    KvsMap::new()
+                }
+            }
+            KvsLoad::Required => load_kvs(steps, SnapshotId(0))?,
+            KvsLoad::RecoverFromSnapshot => {
+                if !Backend::exists(&kvs_path) && !Backend::exists(&hash_path) {
+                    skip(
+                        steps,
+                        KvsBuildStepKind::KvsFile,
+                        &kvs_path,
+                        "file not found",
+                    );
                     KvsMap::new()
+                } else {
+                    match load_kvs(steps, SnapshotId(0)) {
+                        Ok(kvs_map) => kvs_map,
+                        Err(error) => {
+                            // Newest valid snapshot is used, original error if none is valid.
+                            let (snapshot_id, kvs_map) = (1..=KVS_MAX_SNAPSHOTS)
+                                .map(SnapshotId)
+                                .filter(|snapshot_id| {
+                                    Backend::exists(
+                                        &self
+                                            .parameters
+                                            .kvs_file_path::<PathResolver>(*snapshot_id),
+                                    )
+                                })
+                                .find_map(|snapshot_id| {
+                                    load_kvs(steps, snapshot_id)
+                                        .ok()
+                                        .map(|kvs_map| (snapshot_id, kvs_map))
+                                })
+                                .ok_or(error.clone())?;
+                            kvs_warn!(
+                                instance_id = instance_id,
+                                "KVS {instance_id} recovered from snapshot {snapshot_id} after {error}"
+                            );
+                            kvs_event::emit(KvsEvent::RecoveredFromSnapshot {
+                                instance_id,
+                                snapshot_id,
+                            });
+                            recovery = Some(KvsRecoveryInfo { snapshot_id, error });
+                            kvs_map
+                        }
+                    }
                 }
             }
-            KvsLoad::Required => Backend::load_kvs(&kvs_path, Some(&hash_path))?,
+        };
+
+        let merge_base =
+            (self.parameters.merge_policy != KvsMergePolicy::Overwrite).then(|| kvs_map.clone());
+
+        // Merge base keeps legacy keys, so they are removed from storage on flush.
+        let mut kvs_map = kvs_map;
+        let mut lazy = lazy.take();
+        let renamed =
+            kvs_migration::rename_keys(&mut kvs_map, lazy.as_mut(), &self.parameters.key_aliases)?;
+
+        // Changes journaled since the last flush are applied to the loaded data.
+        let journal_path = self.parameters.journal_file_path::<PathResolver>();
+        let journaled = if self.parameters.kvs_load != KvsLoad::Ignored && journal_path.exists() {
+            if let Some(mut pending) = lazy.take() {
+                kvs_map.extend(pending.take_all()?);
+            }
+            let replay = kvs_journal::replay(&journal_path, &mut kvs_map);
+            record(
+                steps,
+                KvsBuildStepKind::Journal,
+                Some(&journal_path),
+                replay,
+            )? > 0
+        } else {
+            false
         };
 
         // Shared object containing data.
         let data = Arc::new(Mutex::new(KvsData {
             kvs_map,
             defaults_map,
+            access_stats: HashMap::new(),
+            // Recovered, renamed and journaled data is written back as current KVS on next flush.
+            dirty: recovery.is_some() || renamed || journaled,
+            recovery,
+            verification: None,
+            autoflush: None,
+            merge_base,
+            lazy,
+            poison_recoveries: 0,
+            flush_hooks: FlushHooks::default(),
+            last_flush: None,
+            #[cfg(feature = "defaults")]
+            default_changes: HashMap::new(),
         }));
 
         // Initialize entry in pool and return new KVS instance.
-        {
-            let mut kvs_pool = KVS_POOL.lock()?;
-            let kvs_pool_entry = match kvs_pool.get_mut(instance_id_index) {
-                Some(entry) => entry,
-                None => return Err(ErrorCode::InvalidInstanceId),
-            };
-
-            let _ = kvs_pool_entry.insert(KvsInner {
-                parameters: self.parameters.clone(),
-                data: data.clone(),
+        let register = KVS_POOL
+            .lock()
+            .map_err(ErrorCode::from)
+            .and_then(|mut kvs_pool| {
+                if instance_id_index >= Self::max_instances() {
+                    return Err(ErrorCode::InvalidInstanceId);
+                }
+                kvs_instance_name::check(instance_id)?;
+                let _ = kvs_pool.entry(instance_id).insert(KvsInner {
+                    parameters: self.parameters.clone(),
+                    data: data.clone(),
+                    flush_fn: flush_inner::<Backend, PathResolver>,
+                });
+                Ok(())
             });
+        record(steps, KvsBuildStepKind::Register, None, register)?;
+
+        let mut kvs = GenericKvs::new(data, self.parameters);
+        kvs.set_flush_on_exit(self.flush_on_exit);
+        kvs.set_metrics(self.metrics);
+        // Journal is compacted right away, it's kept until the next flush if this fails.
+        if journaled {
+            if let Err(e) = kvs.flush() {
+                kvs_warn!(
+                    instance_id = instance_id,
+                    "compacting journal {} failed: {e}",
+                    journal_path.display()
+                );
+            }
+        }
+        #[cfg(feature = "shm-cache")]
+        kvs.publish_shared_view(&*kvs.lock()?);
+        if self.verify_snapshots {
+            let verification = kvs.verify_integrity()?;
+            let broken = verification
+                .iter()
+                .filter(|(snapshot_id, status)| {
+                    if *status == KvsSnapshotStatus::Valid {
+                        return false;
+                    }
+                    kvs_warn!(
+                        instance_id = instance_id,
+                        "snapshot {snapshot_id} of KVS {instance_id} is broken: {status:?}"
+                    );
+                    true
+                })
+                .count();
+            kvs_debug!(
+                instance_id = instance_id,
+                "verified {} snapshots, {broken} broken",
+                verification.len()
+            );
+            kvs.lock()?.verification = Some(verification);
+        }
+        if let Some((interval, start_autoflush)) = self.autoflush {
+            start_autoflush(&kvs, interval)?;
+        }
+        Ok(kvs)
+    }
+}
+
+impl<Backend: KvsBackend + 'static, PathResolver: KvsPathResolver + 'static>
+    GenericKvsBuilder<Backend, PathResolver>
+{
+    /// Flush the instance periodically
+    ///
+    /// The instance is flushed whenever `interval` elapsed and it was modified, until
+    /// [`GenericKvs::cancel_autoflush`] is called or the instance is closed, see
+    /// [`kvs_autoflush`](crate::kvs_autoflush). If the instance is already open, its flusher is
+    /// replaced.
+    ///
+    /// # Parameters
+    ///   * `interval`: Time between flushes (default: no periodic flushing)
+    ///
+    /// # Return Values
+    ///   * KvsBuilder instance
+    pub fn autoflush(mut self, interval: Duration) -> Self {
+        self.autoflush = Some((interval, GenericKvs::start_autoflush));
+        self
+    }
+}
+
+/// Step performed while opening a KVS instance.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KvsBuildStepKind {
+    /// Creation of the working directory resolved from the directory strategy.
+    WorkingDir,
+
+    /// Lookup of the instance in the instance pool.
+    InstancePool,
+
+    /// Loading of the instance defaults file.
+    Defaults,
+
+    /// Loading of the global defaults file.
+    GlobalDefaults,
+
+    /// Loading of a layered defaults file.
+    LayeredDefaults,
+
+    /// Parsing of embedded defaults.
+    EmbeddedDefaults,
+
+    /// Loading of the KVS file.
+    KvsFile,
+
+    /// Validation of the KVS file against the hash file.
+    HashFile,
+
+    /// Replay of the journal, see [`kvs_journal`](crate::kvs_journal).
+    Journal,
+
+    /// Registration of the new instance in the instance pool.
+    Register,
+}
+
+impl fmt::Display for KvsBuildStepKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            KvsBuildStepKind::WorkingDir => "working directory",
+            KvsBuildStepKind::InstancePool => "instance pool",
+            KvsBuildStepKind::Defaults => "defaults file",
+            KvsBuildStepKind::GlobalDefaults => "global defaults file",
+            KvsBuildStepKind::LayeredDefaults => "layered defaults file",
+            KvsBuildStepKind::EmbeddedDefaults => "embedded defaults",
+            KvsBuildStepKind::KvsFile => "KVS file",
+            KvsBuildStepKind::HashFile => "hash file",
+            KvsBuildStepKind::Journal => "journal",
+            KvsBuildStepKind::Register => "instance registration",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Outcome of a build step.
+#[derive(Clone, Debug, PartialEq)]
+pub enum KvsBuildOutcome {
+    /// Step performed successfully.
+    Done,
+
+    /// Step not performed.
+    Skipped(&'static str),
+
+    /// Step failed.
+    Failed(ErrorCode),
+}
+
+/// Build step with outcome.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KvsBuildStep {
+    /// Performed step.
+    pub kind: KvsBuildStepKind,
+
+    /// File involved in the step.
+    pub path: Option<PathBuf>,
+
+    /// Step outcome.
+    pub outcome: KvsBuildOutcome,
+}
+
+impl fmt::Display for KvsBuildStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if let Some(path) = &self.path {
+            write!(f, " {}", path.display())?;
+        }
+        match &self.outcome {
+            KvsBuildOutcome::Done => write!(f, ": ok"),
+            KvsBuildOutcome::Skipped(reason) => write!(f, ": skipped ({reason})"),
+            KvsBuildOutcome::Failed(error) => write!(f, ": failed ({error})"),
+        }
+    }
+}
+
+/// Diagnostic report of a failed [`GenericKvsBuilder::try_build`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct KvsBuildReport {
+    /// Instance ID.
+    pub instance_id: InstanceId,
+
+    /// Steps performed, in order, the last one failed.
+    pub steps: Vec<KvsBuildStep>,
+
+    /// Error returned by the failed step.
+    pub error: ErrorCode,
+}
+
+impl fmt::Display for KvsBuildReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "opening KVS instance {} failed: {}",
+            self.instance_id, self.error
+        )?;
+        for step in &self.steps {
+            write!(f, "\n  {step}")?;
         }
+        Ok(())
+    }
+}
 
-        Ok(GenericKvs::new(data, self.parameters))
+impl From<KvsBuildReport> for ErrorCode {
+    fn from(report: KvsBuildReport) -> Self {
+        report.error
     }
 }
 
-#[cfg(test)]
-mod kvs_builder_tests {
+/// Record outcome of a step, result is passed through.
+fn record<T>(
+    steps: &mut Vec<KvsBuildStep>,
+    kind: KvsBuildStepKind,
+    path: Option<&Path>,
+    result: Result<T, ErrorCode>,
+) -> Result<T, ErrorCode> {
+    let outcome = match &result {
+        Ok(_) => KvsBuildOutcome::Done,
+        Err(error) => KvsBuildOutcome::Failed(error.clone()),
+    };
+    steps.push(KvsBuildStep {
+        kind,
+        path: path.map(Path::to_path_buf),
+        outcome,
+    });
+    result
+}
+
+/// Record skipped step.
+fn skip(steps: &mut Vec<KvsBuildStep>, kind: KvsBuildStepKind, path: &Path, reason: &'static str) {
+    steps.push(KvsBuildStep {
+        kind,
+        path: Some(path.to_path_buf()),
+        outcome: KvsBuildOutcome::Skipped(reason),
+    });
+}
+
+#[cfg(all(test, feature = "json-backend"))]
+pub(crate) mod kvs_builder_tests {
     use crate::error_code::ErrorCode;
     use crate::json_backend::JsonBackend;
-    use crate::kvs_api::{InstanceId, KvsDefaults, KvsLoad, SnapshotId};
+    #[cfg(feature = "snapshots")]
+    use crate::kvs::{KvsRecoveryInfo, KvsSnapshotStatus};
+    use crate::kvs_api::{
+        DuplicateKeyPolicy, FloatFormat, FlushOnExit, InstanceId, KvsApi, KvsDefaults, KvsLoad,
+        SnapshotId,
+    };
     use crate::kvs_backend::{KvsBackend, KvsPathResolver};
-    use crate::kvs_builder::{GenericKvsBuilder, KVS_MAX_INSTANCES, KVS_POOL};
+    use crate::kvs_builder::{
+        GenericKvsBuilder, KvsBuildOutcome, KvsBuildStep, KvsBuildStepKind, KvsPool,
+        KVS_MAX_INSTANCES, KVS_POOL, KVS_POOL_MAX_INSTANCES,
+    };
+    use crate::kvs_instance_name;
     use crate::kvs_value::{KvsMap, KvsValue};
+    use std::collections::HashMap;
     use std::ops::DerefMut;
     use std::path::{Path, PathBuf};
+    use std::sync::atomic::Ordering;
     use std::sync::{LazyLock, Mutex, MutexGuard};
     use tempfile::tempdir;
 
@@ -258,14 +1567,16 @@ mod kvs_builder_tests {
     static SERIAL_TEST: LazyLock<Mutex<()>> = LazyLock::new(|| Mutex::new(()));
 
     /// Execute test serially with KVS pool uninitialized.
-    fn lock_and_reset<'a>() -> MutexGuard<'a, ()> {
+    pub(crate) fn lock_and_reset<'a>() -> MutexGuard<'a, ()> {
         // Tests in this group must be executed serially.
         let serial_lock: MutexGuard<'a, ()> = SERIAL_TEST.lock().unwrap();
 
         // Reset `KVS_POOL` state to uninitialized.
         // This is to mitigate `InstanceParametersMismatch` errors between tests.
         let mut pool = KVS_POOL.lock().unwrap();
-        *pool.deref_mut() = [const { None }; KVS_MAX_INSTANCES];
+        *pool.deref_mut() = KvsPool::new();
+        KVS_POOL_MAX_INSTANCES.store(KVS_MAX_INSTANCES, Ordering::Relaxed);
+        kvs_instance_name::clear();
 
         serial_lock
     }
@@ -426,6 +1737,150 @@ mod kvs_builder_tests {
         assert!(result.is_err_and(|e| e == ErrorCode::InstanceParametersMismatch));
     }
 
+    #[test]
+    fn test_build_instance_exists_force_reopen() {
+        let _lock = lock_and_reset();
+
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+
+        let instance_id = InstanceId(1);
+        let kvs1 = TestKvsBuilder::new(instance_id)
+            .dir(dir_string.clone())
+            .build()
+            .unwrap();
+        kvs1.set_value("key", "value").unwrap();
+
+        // Pending changes of the open instance are flushed and loaded again.
+        let kvs2 = TestKvsBuilder::new(instance_id)
+            .access_stats(true)
+            .dir(dir_string)
+            .force_reopen()
+            .build()
+            .unwrap();
+        assert!(kvs2.parameters().access_stats);
+        assert_eq!(kvs2.get_value_as::<String>("key").unwrap(), "value");
+
+        // Closing a replaced handle keeps the reopened instance.
+        kvs1.close().unwrap();
+        let kvs_pool = KVS_POOL.lock().unwrap();
+        assert!(kvs_pool.numeric[1]
+            .as_ref()
+            .is_some_and(|kvs_inner| kvs_inner.parameters.access_stats));
+    }
+
+    #[test]
+    fn test_close() {
+        let _lock = lock_and_reset();
+
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+
+        let instance_id = InstanceId(1);
+        let kvs = TestKvsBuilder::new(instance_id)
+            .dir(dir_string.clone())
+            .build()
+            .unwrap();
+        kvs.set_value("key", "value").unwrap();
+        kvs.close().unwrap();
+        assert!(KVS_POOL.lock().unwrap().numeric[1].is_none());
+
+        // Reopen with different parameters.
+        let kvs = TestKvsBuilder::new(instance_id)
+            .defaults(KvsDefaults::Ignored)
+            .dir(dir_string)
+            .build()
+            .unwrap();
+        assert_eq!(kvs.get_value_as::<String>("key").unwrap(), "value");
+    }
+
+    #[test]
+    fn test_close_flush_failure() {
+        let _lock = lock_and_reset();
+
+        let dir = tempdir().unwrap();
+        let missing_dir = dir.path().join("missing").to_string_lossy().to_string();
+
+        let instance_id = InstanceId(1);
+        let kvs = TestKvsBuilder::new(instance_id)
+            .dir(missing_dir)
+            .build()
+            .unwrap();
+        kvs.set_value("key", "value").unwrap();
+        assert!(kvs.close().is_err());
+        assert!(KVS_POOL.lock().unwrap().numeric[1].is_none());
+
+        // Reopen with different parameters.
+        let kvs = TestKvsBuilder::new(instance_id)
+            .dir(dir.path().to_string_lossy().to_string())
+            .build()
+            .unwrap();
+        assert!(kvs.get_value("key").is_err());
+    }
+
+    #[test]
+    fn test_close_unchanged_not_flushed() {
+        let _lock = lock_and_reset();
+
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+
+        let instance_id = InstanceId(1);
+        let kvs = TestKvsBuilder::new(instance_id)
+            .dir(dir_string)
+            .build()
+            .unwrap();
+        kvs.close().unwrap();
+        assert!(!TestBackend::kvs_file_path(dir.path(), instance_id, SnapshotId(0)).exists());
+    }
+
+    #[test]
+    fn test_flush_on_exit() {
+        let _lock = lock_and_reset();
+
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+
+        let instance_id = InstanceId(1);
+        let kvs = TestKvsBuilder::new(instance_id)
+            .dir(dir_string.clone())
+            .build()
+            .unwrap();
+        assert_eq!(kvs.flush_on_exit(), FlushOnExit::Yes);
+        kvs.set_value("key", "value").unwrap();
+        drop(kvs);
+        assert!(TestBackend::kvs_file_path(dir.path(), instance_id, SnapshotId(0)).exists());
+    }
+
+    #[test]
+    fn test_flush_on_exit_no() {
+        let _lock = lock_and_reset();
+
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+
+        let instance_id = InstanceId(1);
+        let kvs = TestKvsBuilder::new(instance_id)
+            .dir(dir_string.clone())
+            .flush_on_exit(FlushOnExit::No)
+            .build()
+            .unwrap();
+        assert_eq!(kvs.flush_on_exit(), FlushOnExit::No);
+        kvs.set_value("key", "value").unwrap();
+        drop(kvs);
+        assert!(!TestBackend::kvs_file_path(dir.path(), instance_id, SnapshotId(0)).exists());
+
+        // Disabled on the handle.
+        let mut kvs = TestKvsBuilder::new(instance_id)
+            .dir(dir_string)
+            .build()
+            .unwrap();
+        kvs.set_flush_on_exit(FlushOnExit::No);
+        kvs.set_value("key", "value").unwrap();
+        drop(kvs);
+        assert!(!TestBackend::kvs_file_path(dir.path(), instance_id, SnapshotId(0)).exists());
+    }
+
     #[test]
     fn test_build_instance_id_out_of_range() {
         let _lock = lock_and_reset();
@@ -435,6 +1890,30 @@ mod kvs_builder_tests {
         assert!(result.is_err_and(|e| e == ErrorCode::InvalidInstanceId));
     }
 
+    #[test]
+    fn test_set_max_instances() {
+        let _lock = lock_and_reset();
+
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+        TestKvsBuilder::set_max_instances(200).unwrap();
+        assert_eq!(TestKvsBuilder::max_instances(), 200);
+        let kvs = TestKvsBuilder::new(InstanceId(123))
+            .dir(dir_string.clone())
+            .build()
+            .unwrap();
+        kvs.set_value("key", "value").unwrap();
+        assert_eq!(KVS_POOL.lock().unwrap().numeric.len(), 124);
+
+        // Open instances must stay within the limit.
+        assert!(TestKvsBuilder::set_max_instances(100)
+            .is_err_and(|e| e == ErrorCode::InvalidInstanceId));
+        kvs.close().unwrap();
+        TestKvsBuilder::set_max_instances(100).unwrap();
+        let result = TestKvsBuilder::new(InstanceId(123)).dir(dir_string).build();
+        assert!(result.is_err_and(|e| e == ErrorCode::InvalidInstanceId));
+    }
+
     /// Generate and store file containing example default values.
     fn create_defaults_file(
         working_dir: &Path,
@@ -485,7 +1964,7 @@ mod kvs_builder_tests {
 
         assert_eq!(kvs.parameters().defaults, KvsDefaults::Ignored);
         let kvs_pool = KVS_POOL.lock().unwrap();
-        let kvs_pool_entry = kvs_pool.get(2).unwrap();
+        let kvs_pool_entry = kvs_pool.numeric.get(2).unwrap();
         let kvs_data = kvs_pool_entry.as_ref().unwrap();
         assert_eq!(kvs_data.data.lock().unwrap().defaults_map, KvsMap::new());
     }
@@ -505,7 +1984,7 @@ mod kvs_builder_tests {
 
         assert_eq!(kvs.parameters().defaults, KvsDefaults::Optional);
         let kvs_pool = KVS_POOL.lock().unwrap();
-        let kvs_pool_entry = kvs_pool.get(2).unwrap();
+        let kvs_pool_entry = kvs_pool.numeric.get(2).unwrap();
         let kvs_data = kvs_pool_entry.as_ref().unwrap();
         assert_eq!(kvs_data.data.lock().unwrap().defaults_map, KvsMap::new());
     }
@@ -526,7 +2005,7 @@ mod kvs_builder_tests {
 
         assert_eq!(kvs.parameters().defaults, KvsDefaults::Optional);
         let kvs_pool = KVS_POOL.lock().unwrap();
-        let kvs_pool_entry = kvs_pool.get(2).unwrap();
+        let kvs_pool_entry = kvs_pool.numeric.get(2).unwrap();
         let kvs_data = kvs_pool_entry.as_ref().unwrap();
         assert_eq!(kvs_data.data.lock().unwrap().defaults_map.len(), 3);
     }
@@ -563,11 +2042,293 @@ mod kvs_builder_tests {
 
         assert_eq!(kvs.parameters().defaults, KvsDefaults::Required);
         let kvs_pool = KVS_POOL.lock().unwrap();
-        let kvs_pool_entry = kvs_pool.get(2).unwrap();
+        let kvs_pool_entry = kvs_pool.numeric.get(2).unwrap();
         let kvs_data = kvs_pool_entry.as_ref().unwrap();
         assert_eq!(kvs_data.data.lock().unwrap().defaults_map.len(), 3);
     }
 
+    #[test]
+    fn test_build_embedded_defaults() {
+        let _lock = lock_and_reset();
+
+        let dir = tempdir().unwrap();
+        let kvs = TestKvsBuilder::new(InstanceId(2))
+            .defaults(KvsDefaults::Required)
+            .defaults_from_str(r#"{"t":"obj","v":{"timeout":{"t":"i32","v":30}}}"#)
+            .dir(dir.path().to_string_lossy())
+            .build()
+            .unwrap();
+        assert_eq!(kvs.get_default_value("timeout").unwrap(), KvsValue::I32(30));
+
+        let defaults_map = KvsMap::from([("timeout".to_string(), KvsValue::I32(60))]);
+        let kvs = TestKvsBuilder::new(InstanceId(3))
+            .defaults_map(defaults_map)
+            .dir(dir.path().to_string_lossy())
+            .build()
+            .unwrap();
+        assert_eq!(kvs.get_default_value("timeout").unwrap(), KvsValue::I32(60));
+    }
+
+    #[test]
+    fn test_build_embedded_defaults_file_provided() {
+        let _lock = lock_and_reset();
+
+        let dir = tempdir().unwrap();
+        let instance_id = InstanceId(2);
+        create_defaults_file(dir.path(), instance_id).unwrap();
+        let kvs = TestKvsBuilder::new(instance_id)
+            .defaults_map(KvsMap::from([(
+                "embedded".to_string(),
+                KvsValue::Boolean(true),
+            )]))
+            .dir(dir.path().to_string_lossy())
+            .build()
+            .unwrap();
+        assert!(kvs
+            .get_default_value("embedded")
+            .is_err_and(|e| e == ErrorCode::KeyNotFound));
+        assert_eq!(kvs.data.lock().unwrap().defaults_map.len(), 3);
+    }
+
+    #[test]
+    fn test_build_embedded_defaults_invalid() {
+        let _lock = lock_and_reset();
+
+        let dir = tempdir().unwrap();
+        let report = TestKvsBuilder::new(InstanceId(2))
+            .defaults_from_str("{")
+            .dir(dir.path().to_string_lossy())
+            .try_build()
+            .err()
+            .unwrap();
+        assert_eq!(report.error, ErrorCode::JsonParserError);
+        assert_eq!(
+            report.steps.last().unwrap().kind,
+            KvsBuildStepKind::EmbeddedDefaults
+        );
+    }
+
+    #[test]
+    fn test_build_key_aliases() {
+        let _lock = lock_and_reset();
+
+        let dir = tempdir().unwrap();
+        let instance_id = InstanceId(2);
+        let (kvs_path, hash_path) =
+            create_kvs_files(dir.path(), instance_id, SnapshotId(0)).unwrap();
+        let kvs = TestKvsBuilder::new(instance_id)
+            .dir(dir.path().to_string_lossy())
+            .key_aliases(HashMap::from([
+                ("number1".to_string(), "timeout".to_string()),
+                ("bool1".to_string(), "string1".to_string()),
+                ("missing".to_string(), "other".to_string()),
+            ]))
+            .build()
+            .unwrap();
+        assert_eq!(kvs.get_value("timeout").unwrap(), KvsValue::F64(321.0));
+        assert_eq!(kvs.get_value("string1").unwrap(), KvsValue::from("Hi"));
+        assert!(!kvs.key_exists("number1").unwrap());
+        assert!(!kvs.key_exists("bool1").unwrap());
+        assert!(!kvs.key_exists("other").unwrap());
+        assert!(kvs.is_dirty().unwrap());
+
+        kvs.flush().unwrap();
+        let stored = TestBackend::load_kvs(&kvs_path, Some(&hash_path)).unwrap();
+        assert_eq!(
+            stored,
+            KvsMap::from([
+                ("timeout".to_string(), KvsValue::F64(321.0)),
+                ("string1".to_string(), KvsValue::from("Hi")),
+            ])
+        );
+    }
+
+    /// Generate and store global defaults file.
+    fn create_global_defaults_file(working_dir: &Path) -> Result<PathBuf, ErrorCode> {
+        let global_defaults_file_path = TestBackend::global_defaults_file_path(working_dir);
+        let kvs_map = KvsMap::from([
+            ("number1".to_string(), KvsValue::F64(999.0)),
+            (
+                "global1".to_string(),
+                KvsValue::String("Global".to_string()),
+            ),
+        ]);
+        TestBackend::save_kvs(&kvs_map, &global_defaults_file_path, None)?;
+
+        Ok(global_defaults_file_path)
+    }
+
+    #[test]
+    fn test_build_global_defaults_only() {
+        let _lock = lock_and_reset();
+
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+
+        let instance_id = InstanceId(2);
+        create_global_defaults_file(dir.path()).unwrap();
+        let builder = TestKvsBuilder::new(instance_id)
+            .defaults(KvsDefaults::Optional)
+            .dir(dir_string);
+        builder.build().unwrap();
+
+        let kvs_pool = KVS_POOL.lock().unwrap();
+        let kvs_pool_entry = kvs_pool.numeric.get(2).unwrap();
+        let kvs_data = kvs_pool_entry.as_ref().unwrap();
+        let defaults_map = &kvs_data.data.lock().unwrap().defaults_map;
+        assert_eq!(defaults_map.len(), 2);
+        assert_eq!(defaults_map["number1"], KvsValue::F64(999.0));
+    }
+
+    #[test]
+    fn test_build_global_defaults_instance_precedence() {
+        let _lock = lock_and_reset();
+
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+
+        let instance_id = InstanceId(2);
+        create_global_defaults_file(dir.path()).unwrap();
+        create_defaults_file(dir.path(), instance_id).unwrap();
+        let builder = TestKvsBuilder::new(instance_id)
+            .defaults(KvsDefaults::Required)
+            .dir(dir_string);
+        builder.build().unwrap();
+
+        let kvs_pool = KVS_POOL.lock().unwrap();
+        let kvs_pool_entry = kvs_pool.numeric.get(2).unwrap();
+        let kvs_data = kvs_pool_entry.as_ref().unwrap();
+        let defaults_map = &kvs_data.data.lock().unwrap().defaults_map;
+        assert_eq!(defaults_map.len(), 4);
+        assert_eq!(defaults_map["number1"], KvsValue::F64(123.0));
+        assert_eq!(
+            defaults_map["global1"],
+            KvsValue::String("Global".to_string())
+        );
+    }
+
+    /// Store defaults layer file with the given values.
+    fn create_layer_file(path: &Path, values: &[(&str, i32)]) {
+        let kvs_map = values
+            .iter()
+            .map(|(key, value)| (key.to_string(), KvsValue::I32(*value)))
+            .collect();
+        TestBackend::save_kvs(&kvs_map, path, None).unwrap();
+    }
+
+    #[cfg(feature = "defaults")]
+    #[test]
+    fn test_build_defaults_files() {
+        let _lock = lock_and_reset();
+
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+
+        let instance_id = InstanceId(2);
+        create_defaults_file(dir.path(), instance_id).unwrap();
+        let system_path = dir.path().join("system.json");
+        let variant_path = dir.path().join("variant.json");
+        create_layer_file(&system_path, &[("number1", 1), ("layer", 1), ("system", 1)]);
+        create_layer_file(&variant_path, &[("layer", 2)]);
+        let builder = TestKvsBuilder::new(instance_id)
+            .defaults_files([&system_path, &variant_path])
+            .dir(dir_string);
+        let kvs = builder.build().unwrap();
+
+        assert_eq!(
+            kvs.parameters().defaults_files,
+            vec![system_path.clone(), variant_path.clone()]
+        );
+        assert_eq!(kvs.get_value("number1").unwrap(), KvsValue::I32(1));
+        assert_eq!(kvs.get_value("layer").unwrap(), KvsValue::I32(2));
+        assert_eq!(kvs.get_value("system").unwrap(), KvsValue::I32(1));
+        assert_eq!(kvs.get_value("bool1").unwrap(), KvsValue::Boolean(true));
+
+        create_layer_file(&variant_path, &[("layer", 3)]);
+        kvs.reload_defaults().unwrap();
+        assert_eq!(kvs.get_value("layer").unwrap(), KvsValue::I32(3));
+    }
+
+    #[test]
+    fn test_build_defaults_files_not_provided() {
+        let _lock = lock_and_reset();
+
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+
+        let layer_path = dir.path().join("variant.json");
+        let builder = TestKvsBuilder::new(InstanceId(2))
+            .defaults_files([&layer_path])
+            .dir(dir_string);
+        let report = builder.try_build().err().unwrap();
+
+        assert_eq!(
+            report.steps.last().unwrap().kind,
+            KvsBuildStepKind::LayeredDefaults
+        );
+        assert_eq!(
+            report.steps.last().unwrap().path.as_ref(),
+            Some(&layer_path)
+        );
+    }
+
+    #[test]
+    fn test_build_defaults_files_ignored() {
+        let _lock = lock_and_reset();
+
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+
+        let layer_path = dir.path().join("variant.json");
+        create_layer_file(&layer_path, &[("layer", 1)]);
+        let builder = TestKvsBuilder::new(InstanceId(2))
+            .defaults(KvsDefaults::Ignored)
+            .defaults_files([&layer_path])
+            .dir(dir_string);
+        let kvs = builder.build().unwrap();
+
+        assert!(kvs
+            .get_value("layer")
+            .is_err_and(|e| e == ErrorCode::KeyNotFound));
+    }
+
+    #[test]
+    fn test_build_global_defaults_ignored() {
+        let _lock = lock_and_reset();
+
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+
+        let instance_id = InstanceId(2);
+        create_global_defaults_file(dir.path()).unwrap();
+        let builder = TestKvsBuilder::new(instance_id)
+            .defaults(KvsDefaults::Ignored)
+            .dir(dir_string);
+        builder.build().unwrap();
+
+        let kvs_pool = KVS_POOL.lock().unwrap();
+        let kvs_pool_entry = kvs_pool.numeric.get(2).unwrap();
+        let kvs_data = kvs_pool_entry.as_ref().unwrap();
+        assert_eq!(kvs_data.data.lock().unwrap().defaults_map, KvsMap::new());
+    }
+
+    #[test]
+    fn test_build_global_defaults_required_instance_missing() {
+        let _lock = lock_and_reset();
+
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+
+        let instance_id = InstanceId(2);
+        create_global_defaults_file(dir.path()).unwrap();
+        let builder = TestKvsBuilder::new(instance_id)
+            .defaults(KvsDefaults::Required)
+            .dir(dir_string);
+        let result = builder.build();
+
+        assert!(result.is_err_and(|e| e == ErrorCode::FileNotFound));
+    }
+
     #[test]
     fn test_build_kvs_load_ignored() {
         let _lock = lock_and_reset();
@@ -584,7 +2345,7 @@ mod kvs_builder_tests {
 
         assert_eq!(kvs.parameters().kvs_load, KvsLoad::Ignored);
         let kvs_pool = KVS_POOL.lock().unwrap();
-        let kvs_pool_entry = kvs_pool.get(2).unwrap();
+        let kvs_pool_entry = kvs_pool.numeric.get(2).unwrap();
         let kvs_data = kvs_pool_entry.as_ref().unwrap();
         assert_eq!(kvs_data.data.lock().unwrap().kvs_map, KvsMap::new());
     }
@@ -604,7 +2365,7 @@ mod kvs_builder_tests {
 
         assert_eq!(kvs.parameters().kvs_load, KvsLoad::Optional);
         let kvs_pool = KVS_POOL.lock().unwrap();
-        let kvs_pool_entry = kvs_pool.get(2).unwrap();
+        let kvs_pool_entry = kvs_pool.numeric.get(2).unwrap();
         let kvs_data = kvs_pool_entry.as_ref().unwrap();
         assert_eq!(kvs_data.data.lock().unwrap().kvs_map, KvsMap::new());
     }
@@ -673,7 +2434,7 @@ mod kvs_builder_tests {
 
         assert_eq!(kvs.parameters().kvs_load, KvsLoad::Optional);
         let kvs_pool = KVS_POOL.lock().unwrap();
-        let kvs_pool_entry = kvs_pool.get(2).unwrap();
+        let kvs_pool_entry = kvs_pool.numeric.get(2).unwrap();
         let kvs_data = kvs_pool_entry.as_ref().unwrap();
         assert_eq!(kvs_data.data.lock().unwrap().kvs_map.len(), 3);
     }
@@ -758,8 +2519,296 @@ mod kvs_builder_tests {
 
         assert_eq!(kvs.parameters().kvs_load, KvsLoad::Required);
         let kvs_pool = KVS_POOL.lock().unwrap();
-        let kvs_pool_entry = kvs_pool.get(2).unwrap();
+        let kvs_pool_entry = kvs_pool.numeric.get(2).unwrap();
         let kvs_data = kvs_pool_entry.as_ref().unwrap();
         assert_eq!(kvs_data.data.lock().unwrap().kvs_map.len(), 3);
     }
+
+    #[test]
+    fn test_build_kvs_load_recover_not_provided() {
+        let _lock = lock_and_reset();
+
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+
+        let instance_id = InstanceId(2);
+        let builder = TestKvsBuilder::new(instance_id)
+            .kvs_load(KvsLoad::RecoverFromSnapshot)
+            .dir(dir_string);
+        let kvs = builder.build().unwrap();
+
+        assert_eq!(kvs.parameters().kvs_load, KvsLoad::RecoverFromSnapshot);
+        assert_eq!(kvs.recovery_info().unwrap(), None);
+        assert!(kvs.get_all_keys().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_build_kvs_load_recover_valid() {
+        let _lock = lock_and_reset();
+
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+
+        let instance_id = InstanceId(2);
+        create_kvs_files(dir.path(), instance_id, SnapshotId(0)).unwrap();
+        let builder = TestKvsBuilder::new(instance_id)
+            .kvs_load(KvsLoad::RecoverFromSnapshot)
+            .dir(dir_string);
+        let kvs = builder.build().unwrap();
+
+        assert_eq!(kvs.recovery_info().unwrap(), None);
+        assert_eq!(kvs.get_all_keys().unwrap().len(), 3);
+        assert!(!kvs.is_dirty().unwrap());
+    }
+
+    #[cfg(feature = "snapshots")]
+    #[test]
+    fn test_build_kvs_load_recover_from_snapshot() {
+        let _lock = lock_and_reset();
+
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+
+        let instance_id = InstanceId(2);
+        let (_, hash_path) = create_kvs_files(dir.path(), instance_id, SnapshotId(0)).unwrap();
+        std::fs::write(hash_path, [0u8; 4]).unwrap();
+        let (_, hash_path) = create_kvs_files(dir.path(), instance_id, SnapshotId(1)).unwrap();
+        std::fs::write(hash_path, [0u8; 4]).unwrap();
+        create_kvs_files(dir.path(), instance_id, SnapshotId(2)).unwrap();
+        let builder = TestKvsBuilder::new(instance_id)
+            .kvs_load(KvsLoad::RecoverFromSnapshot)
+            .dir(dir_string);
+        let kvs = builder.build().unwrap();
+
+        assert_eq!(
+            kvs.recovery_info().unwrap(),
+            Some(KvsRecoveryInfo {
+                snapshot_id: SnapshotId(2),
+                error: ErrorCode::ValidationFailed,
+            })
+        );
+        assert_eq!(kvs.get_all_keys().unwrap().len(), 3);
+        assert!(kvs.is_dirty().unwrap());
+    }
+
+    #[cfg(feature = "snapshots")]
+    #[test]
+    fn test_build_verify_snapshots() {
+        let _lock = lock_and_reset();
+
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+
+        let instance_id = InstanceId(2);
+        create_kvs_files(dir.path(), instance_id, SnapshotId(0)).unwrap();
+        create_kvs_files(dir.path(), instance_id, SnapshotId(1)).unwrap();
+        let (_, hash_path) = create_kvs_files(dir.path(), instance_id, SnapshotId(2)).unwrap();
+        std::fs::write(hash_path, [0u8; 4]).unwrap();
+        let kvs = TestKvsBuilder::new(instance_id)
+            .kvs_load(KvsLoad::Required)
+            .verify_snapshots(true)
+            .dir(dir_string.clone())
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            kvs.verification_info().unwrap(),
+            Some(vec![
+                (SnapshotId(0), KvsSnapshotStatus::Valid),
+                (SnapshotId(1), KvsSnapshotStatus::Valid),
+                (SnapshotId(2), KvsSnapshotStatus::HashMismatch),
+            ])
+        );
+        assert_eq!(kvs.get_all_keys().unwrap().len(), 3);
+
+        let kvs = TestKvsBuilder::new(instance_id)
+            .kvs_load(KvsLoad::Required)
+            .dir(dir_string)
+            .force_reopen()
+            .build()
+            .unwrap();
+        assert_eq!(kvs.verification_info().unwrap(), None);
+    }
+
+    #[test]
+    fn test_build_kvs_load_recover_no_valid_snapshot() {
+        let _lock = lock_and_reset();
+
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+
+        let instance_id = InstanceId(2);
+        let (_, hash_path) = create_kvs_files(dir.path(), instance_id, SnapshotId(0)).unwrap();
+        std::fs::write(hash_path, [0u8; 4]).unwrap();
+        let (kvs_path, _) = create_kvs_files(dir.path(), instance_id, SnapshotId(1)).unwrap();
+        std::fs::write(kvs_path, "invalid").unwrap();
+        let builder = TestKvsBuilder::new(instance_id)
+            .kvs_load(KvsLoad::RecoverFromSnapshot)
+            .dir(dir_string);
+        let result = builder.build();
+
+        assert!(result.is_err_and(|e| e == ErrorCode::ValidationFailed));
+    }
+
+    #[test]
+    fn test_parameters_access_stats() {
+        let _lock = lock_and_reset();
+
+        let kvs = TestKvsBuilder::new(InstanceId(1))
+            .access_stats(true)
+            .build()
+            .unwrap();
+        assert!(kvs.parameters().access_stats);
+
+        // Instance is already open without access statistics.
+        let result = TestKvsBuilder::new(InstanceId(1)).build();
+        assert!(result.is_err_and(|e| e == ErrorCode::InstanceParametersMismatch));
+    }
+
+    #[test]
+    fn test_parameters_float_format() {
+        let _lock = lock_and_reset();
+
+        let kvs = TestKvsBuilder::new(InstanceId(1))
+            .float_format(FloatFormat::MaxDecimals(2))
+            .build()
+            .unwrap();
+        assert_eq!(kvs.parameters().float_format, FloatFormat::MaxDecimals(2));
+    }
+
+    #[test]
+    fn test_parameters_max_size() {
+        let _lock = lock_and_reset();
+
+        let kvs = TestKvsBuilder::new(InstanceId(1))
+            .max_size(1024)
+            .build()
+            .unwrap();
+        assert_eq!(kvs.parameters().max_size, Some(1024));
+    }
+
+    #[test]
+    fn test_build_duplicate_keys_reject() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+        let defaults_path = TestBackend::defaults_file_path(dir.path(), InstanceId(1));
+        std::fs::write(
+            defaults_path,
+            r#"{"t":"obj","v":{"k":{"t":"i32","v":1},"k":{"t":"i32","v":2}}}"#,
+        )
+        .unwrap();
+
+        let result = TestKvsBuilder::new(InstanceId(1))
+            .dir(dir_string.clone())
+            .duplicate_keys(DuplicateKeyPolicy::Reject)
+            .build();
+        assert!(result.is_err_and(|e| e == ErrorCode::JsonParserError));
+
+        let kvs = TestKvsBuilder::new(InstanceId(1))
+            .dir(dir_string)
+            .duplicate_keys(DuplicateKeyPolicy::FirstWins)
+            .build()
+            .unwrap();
+        assert_eq!(kvs.data.lock().unwrap().defaults_map["k"], KvsValue::I32(1));
+    }
+
+    #[test]
+    fn test_try_build_ok() {
+        let _lock = lock_and_reset();
+
+        let dir = tempdir().unwrap();
+        let builder = TestKvsBuilder::new(InstanceId(2)).dir(dir.path().to_string_lossy());
+        assert!(builder.try_build().is_ok());
+    }
+
+    #[test]
+    fn test_try_build_report_hash_invalid() {
+        let _lock = lock_and_reset();
+
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+
+        let instance_id = InstanceId(2);
+        let defaults_path = create_defaults_file(dir.path(), instance_id).unwrap();
+        let (kvs_path, hash_path) =
+            create_kvs_files(dir.path(), instance_id, SnapshotId(0)).unwrap();
+        std::fs::write(hash_path, [0u8; 4]).unwrap();
+        let builder = TestKvsBuilder::new(instance_id).dir(dir_string);
+        let report = builder.try_build().err().unwrap();
+
+        assert_eq!(report.instance_id, instance_id);
+        assert_eq!(report.error, ErrorCode::ValidationFailed);
+        assert_eq!(
+            report.steps,
+            vec![
+                KvsBuildStep {
+                    kind: KvsBuildStepKind::InstancePool,
+                    path: None,
+                    outcome: KvsBuildOutcome::Done,
+                },
+                KvsBuildStep {
+                    kind: KvsBuildStepKind::Defaults,
+                    path: Some(defaults_path),
+                    outcome: KvsBuildOutcome::Done,
+                },
+                KvsBuildStep {
+                    kind: KvsBuildStepKind::GlobalDefaults,
+                    path: Some(TestBackend::global_defaults_file_path(dir.path())),
+                    outcome: KvsBuildOutcome::Skipped("file not found"),
+                },
+                KvsBuildStep {
+                    kind: KvsBuildStepKind::HashFile,
+                    path: Some(kvs_path.clone()),
+                    outcome: KvsBuildOutcome::Failed(ErrorCode::ValidationFailed),
+                },
+            ]
+        );
+        let text = report.to_string();
+        assert!(text.starts_with("opening KVS instance 2 failed: ValidationFailed"));
+        assert!(text.contains(&format!("hash file {}: failed", kvs_path.display())));
+    }
+
+    #[test]
+    fn test_try_build_report_defaults_required_not_provided() {
+        let _lock = lock_and_reset();
+
+        let dir = tempdir().unwrap();
+        let builder = TestKvsBuilder::new(InstanceId(2))
+            .defaults(KvsDefaults::Required)
+            .dir(dir.path().to_string_lossy());
+        let report = builder.try_build().err().unwrap();
+
+        assert_eq!(report.error, ErrorCode::FileNotFound);
+        let last = report.steps.last().unwrap();
+        assert_eq!(last.kind, KvsBuildStepKind::Defaults);
+        assert_eq!(
+            last.path,
+            Some(TestBackend::defaults_file_path(dir.path(), InstanceId(2)))
+        );
+        assert_eq!(ErrorCode::from(report), ErrorCode::FileNotFound);
+    }
+
+    #[test]
+    fn test_try_build_report_parameters_mismatch() {
+        let _lock = lock_and_reset();
+
+        let instance_id = InstanceId(1);
+        TestKvsBuilder::new(instance_id).build().unwrap();
+        let report = TestKvsBuilder::new(instance_id)
+            .kvs_load(KvsLoad::Ignored)
+            .try_build()
+            .err()
+            .unwrap();
+
+        assert_eq!(report.error, ErrorCode::InstanceParametersMismatch);
+        assert_eq!(
+            report.steps,
+            vec![KvsBuildStep {
+                kind: KvsBuildStepKind::InstancePool,
+                path: None,
+                outcome: KvsBuildOutcome::Failed(ErrorCode::InstanceParametersMismatch),
+            }]
+        );
+    }
 }
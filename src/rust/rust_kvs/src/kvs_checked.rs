@@ -0,0 +1,194 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checksums of integrity-critical values.
+//!
+//! The hash file only protects the file as written, a value corrupted in memory is written with a
+//! valid hash by the next flush. Values written with [`GenericKvs::set_value_checked`] are stored
+//! together with a checksum, which is verified whenever the value is read:
+//! ```text
+//! "key": {"t": "obj", "v": {"__kvs_checked": <value>, "__kvs_checksum": {"t": "u32", "v": ...}}}
+//! ```
+//! Reading returns the plain value, or `ErrorCode::ValidationFailed` if it doesn't match its
+//! checksum. Writing a checked key with `set_value` keeps it checked, removing or resetting the key
+//! drops the checksum.
+
+use crate::error_code::ErrorCode;
+use crate::kvs::GenericKvs;
+use crate::kvs_api::KvsApi;
+use crate::kvs_backend::{KvsBackend, KvsPathResolver};
+use crate::kvs_log::kvs_error;
+use crate::kvs_storage::kvs_hash;
+use crate::kvs_value::{KvsMap, KvsSerialize, KvsValue, ValueModel};
+
+/// Field of a checked value containing the value.
+pub const CHECKED_VALUE_FIELD: &str = "__kvs_checked";
+
+/// Field of a checked value containing the checksum.
+pub const CHECKSUM_FIELD: &str = "__kvs_checksum";
+
+/// Checksum of a value, covering its type and content.
+pub fn value_checksum(value: &KvsValue) -> u32 {
+    kvs_hash(format!("{}:{value}", value.kind()).as_bytes())
+}
+
+/// Wrap a value with its checksum, checked values are kept as they are.
+pub(crate) fn checked(value: KvsValue) -> KvsValue {
+    if is_checked(&value) {
+        return value;
+    }
+    let checksum = value_checksum(&value);
+    KvsValue::Object(KvsMap::from([
+        (CHECKED_VALUE_FIELD.to_string(), value),
+        (CHECKSUM_FIELD.to_string(), KvsValue::U32(checksum)),
+    ]))
+}
+
+/// Whether a stored value is a checked value.
+pub(crate) fn is_checked(value: &KvsValue) -> bool {
+    matches!(value, KvsValue::Object(map)
+        if map.len() == 2
+            && map.contains_key(CHECKED_VALUE_FIELD)
+            && map.contains_key(CHECKSUM_FIELD))
+}
+
+/// Get the plain value of a stored value without verifying it.
+pub(crate) fn plain(value: &KvsValue) -> &KvsValue {
+    match value {
+        KvsValue::Object(map) if is_checked(value) => &map[CHECKED_VALUE_FIELD],
+        value => value,
+    }
+}
+
+/// Verify a stored value against its checksum
+///
+/// # Return Values
+///   * Ok: Plain value, unchecked values are returned as they are
+///   * `ErrorCode::ValidationFailed`: Value doesn't match its checksum
+pub(crate) fn verify(value: &KvsValue) -> Result<&KvsValue, ErrorCode> {
+    let KvsValue::Object(map) = value else {
+        return Ok(value);
+    };
+    if !is_checked(value) {
+        return Ok(value);
+    }
+    let plain = &map[CHECKED_VALUE_FIELD];
+    match &map[CHECKSUM_FIELD] {
+        KvsValue::U32(checksum) if *checksum == value_checksum(plain) => Ok(plain),
+        _ => {
+            kvs_error!("value doesn't match its checksum");
+            Err(ErrorCode::ValidationFailed)
+        }
+    }
+}
+
+impl<Backend: KvsBackend, PathResolver: KvsPathResolver, Model: ValueModel>
+    GenericKvs<Backend, PathResolver, Model>
+{
+    /// Assign a value to a key and mark the key as integrity-critical
+    ///
+    /// The value is stored with a checksum verified on every read, see
+    /// [`kvs_checked`](crate::kvs_checked).
+    ///
+    /// # Parameters
+    ///   * `key`: Key to set value
+    ///   * `value`: Value to be set
+    ///
+    /// # Return Values
+    ///   * Ok: Value was assigned to key
+    ///   * Errors of [`KvsApi::set_value`]
+    pub fn set_value_checked<S: Into<String>, V: KvsSerialize>(
+        &self,
+        key: S,
+        value: V,
+    ) -> Result<(), ErrorCode> {
+        self.set_value(key, checked(value.to_kvs_value()))
+    }
+
+    /// Check if a key is integrity-critical
+    ///
+    /// # Parameters
+    ///   * `key`: Key to check
+    ///
+    /// # Return Values
+    ///   * Ok: `true` if the stored value has a checksum
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    pub fn is_value_checked(&self, key: &str) -> Result<bool, ErrorCode> {
+        let mut data = self.lock()?;
+        data.materialize_key(key)?;
+        Ok(data.kvs_map.get(key).is_some_and(is_checked))
+    }
+}
+
+#[cfg(all(test, feature = "json-backend"))]
+mod kvs_checked_tests {
+    use crate::error_code::ErrorCode;
+    use crate::json_backend::JsonBackend;
+    use crate::kvs_api::{InstanceId, KvsApi, KvsLoad};
+    use crate::kvs_builder::kvs_builder_tests::lock_and_reset;
+    use crate::kvs_builder::GenericKvsBuilder;
+    use crate::kvs_checked::CHECKED_VALUE_FIELD;
+    use crate::kvs_value::KvsValue;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_set_value_checked() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+        let kvs = GenericKvsBuilder::<JsonBackend>::new(InstanceId(1))
+            .dir(dir_string.clone())
+            .strict_types(true)
+            .build()
+            .unwrap();
+        kvs.set_value_checked("critical", 42u32).unwrap();
+        kvs.set_value("plain", 1).unwrap();
+        assert!(kvs.is_value_checked("critical").unwrap());
+        assert!(!kvs.is_value_checked("plain").unwrap());
+        assert_eq!(kvs.get_value("critical").unwrap(), KvsValue::U32(42));
+        assert_eq!(kvs.get_value_as::<u32>("critical").unwrap(), 42);
+
+        // Key stays checked and keeps its type.
+        kvs.set_values([("critical".to_string(), KvsValue::U32(44))])
+            .unwrap();
+        assert!(kvs.is_value_checked("critical").unwrap());
+        kvs.set_value("critical", 43u32).unwrap();
+        assert!(kvs.is_value_checked("critical").unwrap());
+        assert!(kvs
+            .set_value("critical", "x")
+            .is_err_and(|e| e == ErrorCode::TypeMismatch));
+        kvs.flush().unwrap();
+        drop(kvs);
+
+        let kvs = GenericKvsBuilder::<JsonBackend>::new(InstanceId(1))
+            .dir(dir_string)
+            .strict_types(true)
+            .kvs_load(KvsLoad::Required)
+            .force_reopen()
+            .build()
+            .unwrap();
+        assert_eq!(kvs.get_values(&["critical"]).unwrap(), [KvsValue::U32(43)]);
+
+        // Simulate a bit flip in memory.
+        if let Some(KvsValue::Object(map)) = kvs.lock().unwrap().kvs_map.get_mut("critical") {
+            map.insert(CHECKED_VALUE_FIELD.to_string(), KvsValue::U32(47));
+        }
+        assert!(kvs
+            .get_value("critical")
+            .is_err_and(|e| e == ErrorCode::ValidationFailed));
+        assert!(kvs
+            .get_value_as::<u32>("critical")
+            .is_err_and(|e| e == ErrorCode::ValidationFailed));
+        assert!(kvs
+            .get_values(&["plain", "critical"])
+            .is_err_and(|e| e == ErrorCode::ValidationFailed));
+    }
+}
@@ -0,0 +1,142 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Clock abstraction.
+//!
+//! Instances take timestamps, e.g. the time of the last flush, from the clock set with
+//! [`GenericKvsBuilder::clock`](crate::kvs_builder::GenericKvsBuilder::clock), [`SystemClock`] by
+//! default. [`FakeClock`] is set and advanced explicitly, so time-dependent behavior can be tested
+//! deterministically.
+
+use crate::kvs::KvsParameters;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+/// Source of the current time.
+pub trait KvsClock: Send + Sync {
+    /// Get the current time.
+    fn now(&self) -> SystemTime;
+}
+
+/// Clock of the system.
+pub struct SystemClock;
+
+impl KvsClock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// Clock only changed explicitly.
+pub struct FakeClock(Mutex<SystemTime>);
+
+impl FakeClock {
+    /// Create a clock
+    ///
+    /// # Parameters
+    ///   * `now`: Initial time
+    pub fn new(now: SystemTime) -> Self {
+        Self(Mutex::new(now))
+    }
+
+    /// Set the current time
+    ///
+    /// # Parameters
+    ///   * `now`: New time
+    pub fn set(&self, now: SystemTime) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = now;
+    }
+
+    /// Advance the current time
+    ///
+    /// # Parameters
+    ///   * `duration`: Time to add
+    pub fn advance(&self, duration: Duration) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) += duration;
+    }
+}
+
+impl KvsClock for FakeClock {
+    fn now(&self) -> SystemTime {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Clock set for an instance.
+///
+/// Clocks are equal if they share the same object.
+#[derive(Clone)]
+pub struct KvsSharedClock(pub Arc<dyn KvsClock>);
+
+impl PartialEq for KvsSharedClock {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl fmt::Debug for KvsSharedClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("KvsSharedClock")
+    }
+}
+
+impl KvsParameters {
+    /// Current time of the clock set for the instance, the system time if none is set.
+    pub fn now(&self) -> SystemTime {
+        self.clock
+            .as_ref()
+            .map_or_else(SystemTime::now, |clock| clock.0.now())
+    }
+}
+
+#[cfg(all(test, feature = "json-backend"))]
+mod kvs_clock_tests {
+    use crate::json_backend::GenericJsonBackend;
+    use crate::kvs_api::{InstanceId, KvsApi};
+    use crate::kvs_builder::kvs_builder_tests::lock_and_reset;
+    use crate::kvs_builder::GenericKvsBuilder;
+    use crate::kvs_clock::FakeClock;
+    use crate::kvs_fs::MemoryFs;
+    use std::path::Path;
+    use std::sync::Arc;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn test_fake_clock() {
+        let _lock = lock_and_reset();
+        let dir = "kvs_clock_tests/test_fake_clock";
+        let clock = Arc::new(FakeClock::new(UNIX_EPOCH));
+        let kvs = GenericKvsBuilder::<GenericJsonBackend<MemoryFs>>::new(InstanceId(1))
+            .dir(dir)
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        assert_eq!(kvs.parameters().now(), UNIX_EPOCH);
+
+        clock.advance(Duration::from_secs(60));
+        kvs.set_value("key", 1).unwrap();
+        kvs.flush().unwrap();
+        assert_eq!(
+            kvs.stats().unwrap().last_flush,
+            Some(UNIX_EPOCH + Duration::from_secs(60))
+        );
+
+        clock.set(UNIX_EPOCH + Duration::from_secs(3_600));
+        kvs.set_value("key", 2).unwrap();
+        kvs.flush().unwrap();
+        assert_eq!(
+            kvs.stats().unwrap().last_flush,
+            Some(UNIX_EPOCH + Duration::from_secs(3_600))
+        );
+        MemoryFs::clear_dir(Path::new(dir)).unwrap();
+    }
+}
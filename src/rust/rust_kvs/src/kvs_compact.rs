@@ -0,0 +1,207 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Compaction of stored values.
+//!
+//! Arrays used as slot lists are typically cleared by setting elements to `Null`, so they keep
+//! growing in memory and on disk. [`GenericKvs::compact`] drops `Null` elements from all arrays,
+//! also nested ones, and releases unused capacity of the stored data. Values stored with
+//! [`set_value_checked`](GenericKvs::set_value_checked) are left unchanged, as are defaults.
+
+use crate::error_code::ErrorCode;
+use crate::kvs::GenericKvs;
+use crate::kvs_backend::{KvsBackend, KvsPathResolver};
+use crate::kvs_checked;
+use crate::kvs_lock::KvsDirLock;
+use crate::kvs_log::{kvs_debug, kvs_error};
+use crate::kvs_storage::SnapshotId;
+use crate::kvs_value::{KvsValue, ValueModel};
+
+/// Drop `Null` elements of arrays and release unused capacity
+///
+/// # Return Values
+///   * Number of dropped elements
+fn compact_value(value: &mut KvsValue) -> usize {
+    match value {
+        KvsValue::Array(arr) => {
+            let len = arr.len();
+            arr.retain(|element| *element != KvsValue::Null);
+            let nested: usize = arr.iter_mut().map(compact_value).sum();
+            arr.shrink_to_fit();
+            len - arr.len() + nested
+        }
+        KvsValue::Object(map) => {
+            let nested = map.values_mut().map(compact_value).sum();
+            map.shrink_to_fit();
+            nested
+        }
+        KvsValue::String(s) => {
+            s.shrink_to_fit();
+            0
+        }
+        _ => 0,
+    }
+}
+
+impl<Backend: KvsBackend, PathResolver: KvsPathResolver, Model: ValueModel>
+    GenericKvs<Backend, PathResolver, Model>
+{
+    /// Compact the stored values
+    ///
+    /// Drops `Null` elements from arrays and releases unused capacity, see
+    /// [`kvs_compact`](crate::kvs_compact). The instance is marked dirty if elements were dropped.
+    ///
+    /// With `rewrite` the current KVS file (snapshot 0) is rewritten in place afterwards, without
+    /// rotating snapshots. Unflushed changes are written as well, the stored KVS isn't merged.
+    ///
+    /// # Parameters
+    ///   * `rewrite`: Rewrite the current KVS file
+    ///
+    /// # Return Values
+    ///   * Ok: Number of dropped array elements
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    ///   * `ErrorCode::QuotaExceeded`: Rewritten data exceeds the maximum size
+    ///   * Err: Writing the KVS file failed
+    pub fn compact(&self, rewrite: bool) -> Result<usize, ErrorCode> {
+        let mut data = self.lock_data()?;
+        let instance_id = self.parameters().instance_id;
+        let dropped: usize = data
+            .kvs_map
+            .values_mut()
+            .filter(|value| !kvs_checked::is_checked(value))
+            .map(compact_value)
+            .sum();
+        data.kvs_map.shrink_to_fit();
+        if dropped > 0 {
+            data.dirty = true;
+        }
+        kvs_debug!(
+            instance_id = instance_id,
+            "compaction dropped {dropped} array elements"
+        );
+        if !rewrite {
+            return Ok(dropped);
+        }
+
+        let _dir_lock = if Backend::locks_working_dir() {
+            Some(KvsDirLock::acquire(&self.parameters().working_dir)?)
+        } else {
+            None
+        };
+        self.check_size(&data.kvs_map)?;
+        let kvs_path = self
+            .parameters()
+            .kvs_file_path::<PathResolver>(SnapshotId(0));
+        let hash_path = self
+            .parameters()
+            .hash_file_path::<PathResolver>(SnapshotId(0));
+        Backend::save_kvs_compressed(
+            &data.kvs_map,
+            &kvs_path,
+            Some(&hash_path),
+            &self.parameters().float_format,
+            self.parameters().compression,
+        )
+        .and_then(|()| Backend::sync_kvs(&kvs_path, &hash_path, self.parameters().sync_policy))
+        .and_then(|()| self.remove_journal())
+        .inspect_err(|e| {
+            kvs_error!(
+                instance_id = instance_id,
+                "rewriting compacted KVS failed: {e}"
+            )
+        })?;
+        if data.merge_base.is_some() {
+            data.merge_base = Some(data.kvs_map.clone());
+        }
+        data.dirty = false;
+        data.last_flush = Some(self.parameters().now());
+        #[cfg(feature = "shm-cache")]
+        self.publish_shared_view(&data);
+        Ok(dropped)
+    }
+}
+
+#[cfg(all(test, feature = "json-backend"))]
+mod kvs_compact_tests {
+    use crate::json_backend::JsonBackend;
+    use crate::kvs_api::{InstanceId, KvsApi, KvsLoad};
+    use crate::kvs_builder::kvs_builder_tests::lock_and_reset;
+    use crate::kvs_builder::GenericKvsBuilder;
+    use crate::kvs_value::{KvsMap, KvsValue};
+    use tempfile::tempdir;
+
+    fn slots() -> KvsValue {
+        KvsValue::Array(vec![
+            KvsValue::Null,
+            KvsValue::I32(1),
+            KvsValue::Object(KvsMap::from([(
+                "nested".to_string(),
+                KvsValue::Array(vec![KvsValue::Null, KvsValue::Null]),
+            )])),
+            KvsValue::Null,
+        ])
+    }
+
+    #[test]
+    fn test_compact() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let kvs = GenericKvsBuilder::<JsonBackend>::new(InstanceId(1))
+            .dir(dir.path().to_string_lossy().to_string())
+            .build()
+            .unwrap();
+        kvs.set_value("slots", slots()).unwrap();
+        kvs.set_value_checked("checked", slots()).unwrap();
+        kvs.flush().unwrap();
+
+        assert_eq!(kvs.compact(false).unwrap(), 4);
+        assert!(kvs.is_dirty().unwrap());
+        assert_eq!(
+            kvs.get_value("slots").unwrap(),
+            KvsValue::Array(vec![
+                KvsValue::I32(1),
+                KvsValue::Object(KvsMap::from([(
+                    "nested".to_string(),
+                    KvsValue::Array(Vec::new()),
+                )])),
+            ])
+        );
+        assert_eq!(kvs.get_value("checked").unwrap(), slots());
+        assert_eq!(kvs.compact(false).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_compact_rewrite() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+        let kvs = GenericKvsBuilder::<JsonBackend>::new(InstanceId(1))
+            .dir(dir_string.clone())
+            .build()
+            .unwrap();
+        kvs.set_value("slots", slots()).unwrap();
+        kvs.flush().unwrap();
+        let snapshot_count = kvs.snapshot_count();
+
+        assert_eq!(kvs.compact(true).unwrap(), 4);
+        assert!(!kvs.is_dirty().unwrap());
+        assert_eq!(kvs.snapshot_count(), snapshot_count);
+        drop(kvs);
+
+        let kvs = GenericKvsBuilder::<JsonBackend>::new(InstanceId(1))
+            .dir(dir_string)
+            .kvs_load(KvsLoad::Required)
+            .force_reopen()
+            .build()
+            .unwrap();
+        assert_eq!(kvs.compact(false).unwrap(), 0);
+    }
+}
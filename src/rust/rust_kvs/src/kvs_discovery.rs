@@ -0,0 +1,215 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Discovery of KVS instances stored in a working directory.
+//!
+//! Use [`GenericKvs::discover_instances`](crate::kvs::GenericKvs::discover_instances) to list the
+//! instances stored with a given backend. Only numeric instance IDs are discovered, files of
+//! [named instances](crate::kvs_instance_name) are skipped.
+
+use crate::error_code::ErrorCode;
+use crate::kvs_api::{InstanceId, SnapshotId};
+use crate::kvs_backend::KvsPathResolver;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File belonging to a KVS instance.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KvsFileInfo {
+    /// File path.
+    pub path: PathBuf,
+
+    /// File size in bytes.
+    pub size: u64,
+}
+
+impl KvsFileInfo {
+    /// Get file info, `None` if the file doesn't exist.
+    fn from_path(path: PathBuf) -> Result<Option<Self>, ErrorCode> {
+        match fs::metadata(&path) {
+            Ok(metadata) if metadata.is_file() => Ok(Some(Self {
+                path,
+                size: metadata.len(),
+            })),
+            Ok(_) => Ok(None),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Snapshot of a discovered instance.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SnapshotInfo {
+    /// Snapshot ID.
+    pub snapshot_id: SnapshotId,
+
+    /// KVS file of the snapshot.
+    pub kvs_file: KvsFileInfo,
+
+    /// Hash file of the snapshot, `None` if missing.
+    pub hash_file: Option<KvsFileInfo>,
+}
+
+/// Instance found in a working directory.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InstanceInfo {
+    /// Instance ID.
+    pub instance_id: InstanceId,
+
+    /// Existing snapshots, ordered by snapshot ID.
+    pub snapshots: Vec<SnapshotInfo>,
+
+    /// Defaults file, `None` if missing.
+    pub defaults_file: Option<KvsFileInfo>,
+}
+
+impl InstanceInfo {
+    /// Number of existing snapshots.
+    pub fn snapshot_count(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    /// Total size of all files of the instance in bytes.
+    pub fn total_size(&self) -> u64 {
+        let snapshots_size: u64 = self
+            .snapshots
+            .iter()
+            .map(|s| s.kvs_file.size + s.hash_file.as_ref().map_or(0, |h| h.size))
+            .sum();
+        snapshots_size + self.defaults_file.as_ref().map_or(0, |d| d.size)
+    }
+}
+
+/// Discover instances in a working directory.
+///
+/// Every number contained in a file name is a candidate instance ID. A candidate is reported if
+/// the path resolver maps it to an existing snapshot or defaults file.
+///
+/// # Parameters
+///   * `working_dir`: Directory to scan
+///   * `max_snapshot_id`: Highest snapshot ID to check
+///
+/// # Return Values
+///   * Ok: Discovered instances ordered by instance ID
+///   * `ErrorCode::FileNotFound`: Working directory not found
+///   * `ErrorCode::UnmappedError`: Directory couldn't be read
+pub(crate) fn discover_instances<PathResolver: KvsPathResolver>(
+    working_dir: &Path,
+    max_snapshot_id: usize,
+) -> Result<Vec<InstanceInfo>, ErrorCode> {
+    // Empty working directory refers to the current directory.
+    let scan_dir = if working_dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        working_dir
+    };
+
+    let mut candidates = BTreeSet::new();
+    for entry in fs::read_dir(scan_dir)? {
+        let file_name = entry?.file_name();
+        let file_name = file_name.to_string_lossy();
+        candidates.extend(
+            file_name
+                .split(|c: char| !c.is_ascii_digit())
+                .filter_map(|part| part.parse::<usize>().ok()),
+        );
+    }
+
+    let mut instances = Vec::new();
+    for candidate in candidates {
+        let instance_id = InstanceId(candidate);
+
+        let mut snapshots = Vec::new();
+        for idx in 0..=max_snapshot_id {
+            let snapshot_id = SnapshotId(idx);
+            let kvs_path = PathResolver::kvs_file_path(working_dir, instance_id, snapshot_id);
+            let Some(kvs_file) = KvsFileInfo::from_path(kvs_path)? else {
+                continue;
+            };
+            let hash_path = PathResolver::hash_file_path(working_dir, instance_id, snapshot_id);
+            snapshots.push(SnapshotInfo {
+                snapshot_id,
+                kvs_file,
+                hash_file: KvsFileInfo::from_path(hash_path)?,
+            });
+        }
+
+        let defaults_path = PathResolver::defaults_file_path(working_dir, instance_id);
+        let defaults_file = KvsFileInfo::from_path(defaults_path)?;
+
+        if !snapshots.is_empty() || defaults_file.is_some() {
+            instances.push(InstanceInfo {
+                instance_id,
+                snapshots,
+                defaults_file,
+            });
+        }
+    }
+
+    Ok(instances)
+}
+
+#[cfg(test)]
+mod kvs_discovery_tests {
+    use crate::error_code::ErrorCode;
+    use crate::json_backend::JsonBackend;
+    use crate::kvs_api::{InstanceId, SnapshotId};
+    use crate::kvs_discovery::discover_instances;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_discover_instances() {
+        let dir = tempdir().unwrap();
+        let dir_path = dir.path();
+        fs::write(dir_path.join("kvs_3_0.json"), "{}").unwrap();
+        fs::write(dir_path.join("kvs_3_0.hash"), [0u8; 4]).unwrap();
+        fs::write(dir_path.join("kvs_3_2.json"), "{}").unwrap();
+        fs::write(dir_path.join("kvs_12_default.json"), "{\"a\":1}").unwrap();
+        fs::write(dir_path.join("unrelated_7.txt"), "").unwrap();
+
+        let instances = discover_instances::<JsonBackend>(dir_path, 3).unwrap();
+        assert_eq!(instances.len(), 2);
+
+        let first = &instances[0];
+        assert_eq!(first.instance_id, InstanceId(3));
+        assert_eq!(first.snapshot_count(), 2);
+        assert_eq!(first.snapshots[0].snapshot_id, SnapshotId(0));
+        assert_eq!(first.snapshots[0].hash_file.as_ref().unwrap().size, 4);
+        assert_eq!(first.snapshots[1].snapshot_id, SnapshotId(2));
+        assert!(first.snapshots[1].hash_file.is_none());
+        assert!(first.defaults_file.is_none());
+        assert_eq!(first.total_size(), 8);
+
+        let second = &instances[1];
+        assert_eq!(second.instance_id, InstanceId(12));
+        assert_eq!(second.snapshot_count(), 0);
+        assert_eq!(second.defaults_file.as_ref().unwrap().size, 7);
+    }
+
+    #[test]
+    fn test_discover_instances_empty() {
+        let dir = tempdir().unwrap();
+        assert!(discover_instances::<JsonBackend>(dir.path(), 3)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_discover_instances_dir_not_found() {
+        let dir = tempdir().unwrap();
+        let missing = dir.path().join("missing");
+        assert!(discover_instances::<JsonBackend>(&missing, 3)
+            .is_err_and(|e| e == ErrorCode::FileNotFound));
+    }
+}
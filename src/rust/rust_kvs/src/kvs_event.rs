@@ -0,0 +1,229 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Notification of significant KVS events.
+//!
+//! A single process-wide sink can be registered with [`set_event_sink`]. Events are emitted
+//! synchronously from the thread performing the operation.
+
+use crate::error_code::ErrorCode;
+use crate::kvs_api::{InstanceId, SnapshotId};
+use crate::kvs_metrics::KvsMetrics;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// Significant KVS event.
+#[derive(Clone, Debug, PartialEq)]
+pub enum KvsEvent {
+    /// Flush finished successfully.
+    FlushSucceeded { instance_id: InstanceId },
+
+    /// Flush failed.
+    FlushFailed {
+        instance_id: InstanceId,
+        error: ErrorCode,
+    },
+
+    /// Stored data failed integrity check.
+    IntegrityFailure {
+        instance_id: InstanceId,
+        snapshot_id: SnapshotId,
+        error: ErrorCode,
+    },
+
+    /// Snapshot was restored.
+    SnapshotRestored {
+        instance_id: InstanceId,
+        snapshot_id: SnapshotId,
+    },
+
+    /// Defaults were reloaded from changed defaults files.
+    DefaultsReloaded { instance_id: InstanceId },
+
+    /// Corrupted KVS was replaced by a snapshot when loading.
+    RecoveredFromSnapshot {
+        instance_id: InstanceId,
+        snapshot_id: SnapshotId,
+    },
+}
+
+impl fmt::Display for KvsEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KvsEvent::FlushSucceeded { instance_id } => {
+                write!(f, "KVS {instance_id}: flush succeeded")
+            }
+            KvsEvent::FlushFailed { instance_id, error } => {
+                write!(f, "KVS {instance_id}: flush failed: {:?}", error.kind())
+            }
+            KvsEvent::IntegrityFailure {
+                instance_id,
+                snapshot_id,
+                error,
+            } => write!(
+                f,
+                "KVS {instance_id}: integrity check of snapshot {snapshot_id} failed: {:?}",
+                error.kind()
+            ),
+            KvsEvent::SnapshotRestored {
+                instance_id,
+                snapshot_id,
+            } => write!(f, "KVS {instance_id}: snapshot {snapshot_id} restored"),
+            KvsEvent::DefaultsReloaded { instance_id } => {
+                write!(f, "KVS {instance_id}: defaults reloaded")
+            }
+            KvsEvent::RecoveredFromSnapshot {
+                instance_id,
+                snapshot_id,
+            } => write!(
+                f,
+                "KVS {instance_id}: recovered from snapshot {snapshot_id}"
+            ),
+        }
+    }
+}
+
+/// Receiver of KVS events.
+pub trait KvsEventSink: Send + Sync {
+    /// Handle event.
+    fn on_event(&self, event: &KvsEvent);
+}
+
+/// Registered event sink.
+static EVENT_SINK: Mutex<Option<Arc<dyn KvsEventSink>>> = Mutex::new(None);
+
+/// Register process-wide event sink, replacing previous one.
+pub fn set_event_sink(sink: Arc<dyn KvsEventSink>) {
+    if let Ok(mut event_sink) = EVENT_SINK.lock() {
+        *event_sink = Some(sink);
+    }
+}
+
+/// Remove registered event sink.
+pub fn clear_event_sink() {
+    if let Ok(mut event_sink) = EVENT_SINK.lock() {
+        *event_sink = None;
+    }
+}
+
+/// Pass event to registered sink, if any.
+pub(crate) fn emit(event: KvsEvent) {
+    // Sink is called without holding the lock.
+    let sink = match EVENT_SINK.lock() {
+        Ok(event_sink) => event_sink.clone(),
+        Err(_) => None,
+    };
+    if let Some(sink) = sink {
+        sink.on_event(&event);
+    }
+}
+
+/// Emit `KvsEvent::IntegrityFailure` if result is an integrity error, result is passed through.
+///
+/// The failure is also reported to `metrics`, if set.
+pub(crate) fn check_integrity<T>(
+    result: Result<T, ErrorCode>,
+    instance_id: InstanceId,
+    snapshot_id: SnapshotId,
+    metrics: Option<&dyn KvsMetrics>,
+) -> Result<T, ErrorCode> {
+    if let Err(error) = &result {
+        if !matches!(
+            error.kind(),
+            ErrorCode::ValidationFailed | ErrorCode::IntegrityCorrupted
+        ) {
+            return result;
+        }
+        if let Some(metrics) = metrics {
+            metrics.on_validation_failure(instance_id, snapshot_id, error);
+        }
+        emit(KvsEvent::IntegrityFailure {
+            instance_id,
+            snapshot_id,
+            error: error.clone(),
+        });
+    }
+    result
+}
+
+#[cfg(all(test, feature = "json-backend", feature = "snapshots"))]
+mod kvs_event_tests {
+    use crate::error_code::ErrorCode;
+    use crate::json_backend::JsonBackend;
+    use crate::kvs::{GenericKvs, KvsParameters};
+    use crate::kvs_api::{InstanceId, KvsApi, SnapshotId};
+    use crate::kvs_builder::KvsData;
+    use crate::kvs_event::{clear_event_sink, set_event_sink, KvsEvent, KvsEventSink};
+    use crate::kvs_value::KvsMap;
+    use std::fs;
+    use std::sync::{Arc, Mutex};
+    use tempfile::tempdir;
+
+    #[derive(Default)]
+    struct RecordingSink {
+        events: Mutex<Vec<KvsEvent>>,
+    }
+
+    impl KvsEventSink for RecordingSink {
+        fn on_event(&self, event: &KvsEvent) {
+            self.events.lock().unwrap().push(event.clone());
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        let event = KvsEvent::IntegrityFailure {
+            instance_id: InstanceId(1),
+            snapshot_id: SnapshotId(2),
+            error: ErrorCode::ValidationFailed,
+        };
+        assert_eq!(
+            event.to_string(),
+            "KVS 1: integrity check of snapshot 2 failed: ValidationFailed"
+        );
+    }
+
+    #[test]
+    fn test_events_emitted() {
+        let dir = tempdir().unwrap();
+        let data = Arc::new(Mutex::new(KvsData::new(KvsMap::new(), KvsMap::new())));
+        let parameters = KvsParameters {
+            working_dir: dir.path().to_path_buf(),
+            ..KvsParameters::new(InstanceId(9))
+        };
+        let kvs = GenericKvs::<JsonBackend>::new(data, parameters);
+
+        let sink = Arc::new(RecordingSink::default());
+        set_event_sink(sink.clone());
+        kvs.flush().unwrap();
+        kvs.set_value("key", "value").unwrap();
+        kvs.flush().unwrap();
+        kvs.snapshot_restore(SnapshotId(1)).unwrap();
+        fs::write(kvs.get_hash_filename(SnapshotId(1)).unwrap(), [0u8; 4]).unwrap();
+        let _ = kvs.snapshot_restore(SnapshotId(1));
+        clear_event_sink();
+
+        // Other tests might emit events concurrently.
+        let events = sink.events.lock().unwrap();
+        assert!(events.contains(&KvsEvent::FlushSucceeded {
+            instance_id: InstanceId(9)
+        }));
+        assert!(events.contains(&KvsEvent::SnapshotRestored {
+            instance_id: InstanceId(9),
+            snapshot_id: SnapshotId(1)
+        }));
+        assert!(events.contains(&KvsEvent::IntegrityFailure {
+            instance_id: InstanceId(9),
+            snapshot_id: SnapshotId(1),
+            error: ErrorCode::ValidationFailed
+        }));
+    }
+}
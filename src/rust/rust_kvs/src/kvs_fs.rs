@@ -0,0 +1,358 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Filesystem abstraction.
+//!
+//! File-based backends access files through [`KvsFs`]. [`StdFs`] uses the filesystem of the
+//! platform, [`MemoryFs`] keeps files in a process-global map, so tests and SIL environments can
+//! run the complete file handling, including hashes, compression and snapshot rotation, without a
+//! working directory on disk.
+//!
+//! The JSON backend is generic over the filesystem, [`JsonBackend`](crate::JsonBackend) uses
+//! [`StdFs`]:
+//!
+//! ```
+//! use rust_kvs::GenericJsonBackend;
+//! use rust_kvs::kvs_fs::MemoryFs;
+//! use rust_kvs::prelude::*;
+//!
+//! let kvs = GenericKvsBuilder::<GenericJsonBackend<MemoryFs>>::new(InstanceId(0))
+//!     .dir("sil")
+//!     .build()?;
+//! kvs.set_value("counter", 1)?;
+//! kvs.flush()?;
+//! # Ok::<(), ErrorCode>(())
+//! ```
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex, MutexGuard};
+use std::time::SystemTime;
+
+/// Filesystem accessed by file-based backends.
+pub trait KvsFs {
+    /// Read the content of a file.
+    fn read(path: &Path) -> io::Result<Vec<u8>>;
+
+    /// Create or replace a file.
+    fn write(path: &Path, data: &[u8]) -> io::Result<()>;
+
+    /// Create or replace a file with the data written by `f`.
+    ///
+    /// The file is only replaced if `f` succeeds. Default implementation collects the data and
+    /// calls [`KvsFs::write`], filesystems on disk stream it to a temporary file which replaces the
+    /// file afterwards.
+    fn write_with<E: From<io::Error>>(
+        path: &Path,
+        f: impl FnOnce(&mut dyn Write) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let mut data = Vec::new();
+        f(&mut data)?;
+        Self::write(path, &data)?;
+        Ok(())
+    }
+
+    /// Check whether a file exists.
+    fn exists(path: &Path) -> bool;
+
+    /// Rename a file, replacing an existing file at the destination.
+    fn rename(from: &Path, to: &Path) -> io::Result<()>;
+
+    /// Remove a file.
+    fn remove_file(path: &Path) -> io::Result<()>;
+
+    /// Get the last modification time of a file.
+    fn modified(path: &Path) -> io::Result<SystemTime>;
+
+    /// Sync a file or directory to the storage device.
+    fn sync(path: &Path) -> io::Result<()>;
+
+    /// Whether the working directory can be locked while flushing.
+    ///
+    /// Default implementation returns `true`, filesystems without directories on disk return
+    /// `false`.
+    fn locks_working_dir() -> bool {
+        true
+    }
+}
+
+/// Filesystem of the platform.
+pub struct StdFs;
+
+impl KvsFs for StdFs {
+    fn read(path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+
+    fn write(path: &Path, data: &[u8]) -> io::Result<()> {
+        fs::write(path, data)
+    }
+
+    fn write_with<E: From<io::Error>>(
+        path: &Path,
+        f: impl FnOnce(&mut dyn Write) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_path);
+
+        let result = (|| {
+            let mut out = BufWriter::new(fs::File::create(&tmp_path)?);
+            f(&mut out)?;
+            let file = out.into_inner().map_err(|e| e.into_error())?;
+            file.sync_all()?;
+            fs::rename(&tmp_path, path)?;
+            Ok(())
+        })();
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp_path);
+        }
+        result
+    }
+
+    fn exists(path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn rename(from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    fn remove_file(path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    fn modified(path: &Path) -> io::Result<SystemTime> {
+        fs::metadata(path)?.modified()
+    }
+
+    fn sync(path: &Path) -> io::Result<()> {
+        fs::File::open(path)?.sync_all()
+    }
+}
+
+/// File stored by [`MemoryFs`].
+struct MemoryFsFile {
+    data: Vec<u8>,
+    modified: SystemTime,
+}
+
+/// Files of all [`MemoryFs`] users.
+static MEMORY_FS_FILES: LazyLock<Mutex<HashMap<PathBuf, MemoryFsFile>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Lock stored files.
+fn files<'a>() -> io::Result<MutexGuard<'a, HashMap<PathBuf, MemoryFsFile>>> {
+    MEMORY_FS_FILES
+        .lock()
+        .map_err(|_| io::Error::other("memory filesystem lock poisoned"))
+}
+
+/// Error of a missing file.
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{} not found", path.display()),
+    )
+}
+
+/// Filesystem in process memory.
+///
+/// Paths are only used as keys, directories don't need to exist. Files survive closing and
+/// reopening an instance within the same process and are lost when the process exits. Use a
+/// distinct directory per test to keep tests independent, and [`MemoryFs::clear_dir`] to remove
+/// stored files.
+pub struct MemoryFs;
+
+impl MemoryFs {
+    /// Set the modification time of a file, e.g. to test time-based retention
+    ///
+    /// # Parameters
+    ///   * `path`: Path of the file
+    ///   * `modified`: New modification time
+    ///
+    /// # Return Values
+    ///   * Ok: Modification time set
+    ///   * Err: File doesn't exist
+    pub fn set_modified(path: &Path, modified: SystemTime) -> io::Result<()> {
+        let mut files = files()?;
+        let file = files.get_mut(path).ok_or_else(|| not_found(path))?;
+        file.modified = modified;
+        Ok(())
+    }
+
+    /// Remove all files stored in a directory, including subdirectories
+    ///
+    /// # Parameters
+    ///   * `dir`: Directory to clear
+    ///
+    /// # Return Values
+    ///   * Ok: Files removed
+    ///   * Err: Lock poisoned
+    pub fn clear_dir(dir: &Path) -> io::Result<()> {
+        files()?.retain(|path, _| !path.starts_with(dir));
+        Ok(())
+    }
+}
+
+impl KvsFs for MemoryFs {
+    fn read(path: &Path) -> io::Result<Vec<u8>> {
+        files()?
+            .get(path)
+            .map(|file| file.data.clone())
+            .ok_or_else(|| not_found(path))
+    }
+
+    fn write(path: &Path, data: &[u8]) -> io::Result<()> {
+        let file = MemoryFsFile {
+            data: data.to_vec(),
+            modified: SystemTime::now(),
+        };
+        files()?.insert(path.to_path_buf(), file);
+        Ok(())
+    }
+
+    fn exists(path: &Path) -> bool {
+        files().is_ok_and(|files| files.contains_key(path))
+    }
+
+    fn rename(from: &Path, to: &Path) -> io::Result<()> {
+        let mut files = files()?;
+        let file = files.remove(from).ok_or_else(|| not_found(from))?;
+        files.insert(to.to_path_buf(), file);
+        Ok(())
+    }
+
+    fn remove_file(path: &Path) -> io::Result<()> {
+        files()?
+            .remove(path)
+            .map(|_| ())
+            .ok_or_else(|| not_found(path))
+    }
+
+    fn modified(path: &Path) -> io::Result<SystemTime> {
+        files()?
+            .get(path)
+            .map(|file| file.modified)
+            .ok_or_else(|| not_found(path))
+    }
+
+    fn sync(_path: &Path) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn locks_working_dir() -> bool {
+        false
+    }
+}
+
+#[cfg(all(test, feature = "json-backend"))]
+mod kvs_fs_tests {
+    #[cfg(feature = "snapshots")]
+    use crate::json_backend::GenericJsonBackend;
+    #[cfg(feature = "snapshots")]
+    use crate::kvs_api::{InstanceId, KvsApi, KvsLoad, SnapshotId};
+    #[cfg(feature = "snapshots")]
+    use crate::kvs_builder::kvs_builder_tests::lock_and_reset;
+    #[cfg(feature = "snapshots")]
+    use crate::kvs_builder::GenericKvsBuilder;
+    use crate::kvs_fs::{KvsFs, MemoryFs, StdFs};
+    #[cfg(feature = "snapshots")]
+    use crate::kvs_value::KvsValue;
+    use std::fs;
+    use std::io;
+    use std::path::Path;
+    use std::time::UNIX_EPOCH;
+    use tempfile::tempdir;
+
+    #[cfg(feature = "snapshots")]
+    type MemoryJsonBackend = GenericJsonBackend<MemoryFs>;
+
+    #[test]
+    fn test_memory_fs() {
+        let dir = Path::new("kvs_fs_tests/test_memory_fs");
+        let path = dir.join("file");
+        let renamed = dir.join("renamed");
+        MemoryFs::write(&path, b"data").unwrap();
+        assert!(MemoryFs::exists(&path));
+        assert_eq!(MemoryFs::read(&path).unwrap(), b"data");
+
+        MemoryFs::set_modified(&path, UNIX_EPOCH).unwrap();
+        MemoryFs::rename(&path, &renamed).unwrap();
+        assert!(!MemoryFs::exists(&path));
+        assert_eq!(MemoryFs::modified(&renamed).unwrap(), UNIX_EPOCH);
+        assert_eq!(
+            MemoryFs::read(&path).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+
+        MemoryFs::clear_dir(dir).unwrap();
+        assert!(!MemoryFs::exists(&renamed));
+        assert_eq!(
+            MemoryFs::remove_file(&renamed).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+    }
+
+    #[test]
+    fn test_std_fs_write_with_failure() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("file");
+        StdFs::write(&path, b"data").unwrap();
+
+        let result = StdFs::write_with(&path, |out| {
+            out.write_all(b"partial")?;
+            Err(io::Error::other("serialization failed"))
+        });
+        assert!(result.is_err());
+        assert_eq!(StdFs::read(&path).unwrap(), b"data");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        StdFs::write_with(&path, |out| out.write_all(b"replaced")).unwrap();
+        assert_eq!(StdFs::read(&path).unwrap(), b"replaced");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[cfg(feature = "snapshots")]
+    #[test]
+    fn test_json_backend_memory_fs() {
+        let _lock = lock_and_reset();
+        let dir = "kvs_fs_tests/test_json_backend_memory_fs";
+        let kvs = GenericKvsBuilder::<MemoryJsonBackend>::new(InstanceId(1))
+            .dir(dir)
+            .build()
+            .unwrap();
+        for counter in 0..3 {
+            kvs.set_value("counter", counter).unwrap();
+            kvs.flush().unwrap();
+        }
+        assert_eq!(kvs.snapshot_count(), 3);
+        let kvs_path = kvs.get_kvs_filename(SnapshotId(0)).unwrap();
+        assert!(MemoryFs::exists(&kvs_path));
+        assert!(!kvs_path.exists());
+
+        kvs.snapshot_restore(SnapshotId(2)).unwrap();
+        assert_eq!(kvs.get_value("counter").unwrap(), KvsValue::I32(0));
+        drop(kvs);
+
+        let kvs = GenericKvsBuilder::<MemoryJsonBackend>::new(InstanceId(1))
+            .dir(dir)
+            .kvs_load(KvsLoad::Required)
+            .force_reopen()
+            .build()
+            .unwrap();
+        assert_eq!(kvs.get_value("counter").unwrap(), KvsValue::I32(0));
+
+        MemoryFs::clear_dir(Path::new(dir)).unwrap();
+    }
+}
@@ -0,0 +1,216 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Fuzzing entry points and value generators.
+//!
+//! Feature `fuzzing` provides [`fuzz_load`], parsing arbitrary bytes with the parser of a backend
+//! in memory. It's meant to be called from fuzz targets, e.g. of `cargo fuzz`:
+//!
+//! ```ignore
+//! fuzz_target!(|data: &[u8]| {
+//!     let _ = rust_kvs::kvs_fuzz::fuzz_load::<rust_kvs::JsonBackend>(data);
+//! });
+//! ```
+//!
+//! Generators of [`KvsValue`] and [`KvsMap`] for property-based tests:
+//!   * Feature `arbitrary`: `KvsValue` implements `arbitrary::Arbitrary`, so does `KvsMap`.
+//!   * Feature `proptest`: Strategies in [`strategy`](crate::kvs_fuzz::strategy).
+//!
+//! Generated values are nested up to [`MAX_DEPTH`] levels. `F64` values generated by the
+//! strategies are finite, JSON can't represent other values.
+
+use crate::error_code::ErrorCode;
+use crate::kvs_backend::KvsBackend;
+use crate::kvs_value::KvsMap;
+#[cfg(feature = "arbitrary")]
+use crate::kvs_value::KvsValue;
+
+/// Maximum nesting of generated arrays and objects.
+pub const MAX_DEPTH: u32 = 4;
+
+/// Maximum number of elements of a generated array or object.
+pub const MAX_LEN: usize = 8;
+
+/// Load arbitrary bytes as KVS file content
+///
+/// The bytes are parsed by [`KvsBackend::load_kvs_from_bytes`] without file access or hash
+/// validation, so the parser of the backend sees the bytes unchanged. Loading must fail with an
+/// error for malformed input, a panic or a stack overflow is a finding.
+///
+/// # Parameters
+///   * `data`: KVS file content
+///
+/// # Return Values
+///   * Ok: Loaded data
+///   * Err: Content was rejected
+pub fn fuzz_load<Backend: KvsBackend>(data: &[u8]) -> Result<KvsMap, ErrorCode> {
+    Backend::load_kvs_from_bytes(data)
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for KvsValue {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        arbitrary_value(u, MAX_DEPTH)
+    }
+}
+
+/// Generate a value nested up to `depth` levels.
+#[cfg(feature = "arbitrary")]
+fn arbitrary_value(u: &mut arbitrary::Unstructured<'_>, depth: u32) -> arbitrary::Result<KvsValue> {
+    // Arrays and objects are the last two variants.
+    let variants = if depth == 0 { 8 } else { 10 };
+    Ok(match u.choose_index(variants)? {
+        0 => KvsValue::I32(u.arbitrary()?),
+        1 => KvsValue::U32(u.arbitrary()?),
+        2 => KvsValue::I64(u.arbitrary()?),
+        3 => KvsValue::U64(u.arbitrary()?),
+        4 => KvsValue::F64(u.arbitrary()?),
+        5 => KvsValue::Boolean(u.arbitrary()?),
+        6 => KvsValue::String(u.arbitrary()?),
+        7 => KvsValue::Null,
+        8 => {
+            let len = u.int_in_range(0..=MAX_LEN)?;
+            KvsValue::Array(
+                (0..len)
+                    .map(|_| arbitrary_value(u, depth - 1))
+                    .collect::<arbitrary::Result<_>>()?,
+            )
+        }
+        _ => {
+            let len = u.int_in_range(0..=MAX_LEN)?;
+            KvsValue::Object(
+                (0..len)
+                    .map(|_| Ok((u.arbitrary()?, arbitrary_value(u, depth - 1)?)))
+                    .collect::<arbitrary::Result<_>>()?,
+            )
+        }
+    })
+}
+
+/// Strategies generating values for `proptest`.
+#[cfg(feature = "proptest")]
+pub mod strategy {
+    use crate::kvs_fuzz::{MAX_DEPTH, MAX_LEN};
+    use crate::kvs_value::{KvsMap, KvsValue};
+    use proptest::collection::{hash_map, vec};
+    use proptest::prelude::*;
+
+    /// Strategy generating values of all types.
+    pub fn kvs_value() -> impl Strategy<Value = KvsValue> {
+        let leaf = prop_oneof![
+            any::<i32>().prop_map(KvsValue::I32),
+            any::<u32>().prop_map(KvsValue::U32),
+            any::<i64>().prop_map(KvsValue::I64),
+            any::<u64>().prop_map(KvsValue::U64),
+            any::<f64>()
+                .prop_filter("non-finite", |n| n.is_finite())
+                .prop_map(KvsValue::F64),
+            any::<bool>().prop_map(KvsValue::Boolean),
+            any::<String>().prop_map(KvsValue::String),
+            Just(KvsValue::Null),
+        ];
+        leaf.prop_recursive(MAX_DEPTH, 64, MAX_LEN as u32, |inner| {
+            prop_oneof![
+                vec(inner.clone(), 0..=MAX_LEN).prop_map(KvsValue::Array),
+                hash_map(any::<String>(), inner, 0..=MAX_LEN).prop_map(KvsValue::Object),
+            ]
+        })
+    }
+
+    /// Strategy generating the data of an instance.
+    pub fn kvs_map() -> impl Strategy<Value = KvsMap> {
+        hash_map(any::<String>(), kvs_value(), 0..=MAX_LEN)
+    }
+}
+
+#[cfg(all(test, feature = "json-backend"))]
+mod kvs_fuzz_tests {
+    use crate::bin_backend::BinBackend;
+    use crate::error_code::ErrorCode;
+    use crate::json_backend::JsonBackend;
+    use crate::kvs_fuzz::fuzz_load;
+    use crate::kvs_value::{KvsMap, KvsValue};
+    use crate::memory_backend::MemoryBackend;
+
+    #[test]
+    fn test_fuzz_load() {
+        assert_eq!(
+            fuzz_load::<JsonBackend>(br#"{"a":{"t":"i32","v":1}}"#).unwrap(),
+            KvsMap::from([("a".to_string(), KvsValue::I32(1))])
+        );
+        assert!(
+            fuzz_load::<JsonBackend>(b"{\"a\":").is_err_and(|e| e == ErrorCode::JsonParserError)
+        );
+        assert!(fuzz_load::<JsonBackend>(&[0xff, 0xfe]).is_err());
+        assert!(fuzz_load::<BinBackend>(&[0xff; 16]).is_err());
+    }
+
+    #[test]
+    fn test_fuzz_load_nesting() {
+        // Header and root map with one entry, value nested in single element arrays.
+        let mut data = b"KVSB\x01\x01\0\0\0\x01\0\0\0a".to_vec();
+        for _ in 0..1 << 18 {
+            data.extend_from_slice(&[8, 1, 0, 0, 0]);
+        }
+        data.push(7);
+        assert!(fuzz_load::<BinBackend>(&data).is_err_and(|e| e == ErrorCode::SerializationFailed));
+        assert!(fuzz_load::<MemoryBackend>(b"{}").is_err());
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn test_arbitrary() {
+        use crate::kvs_api::FloatFormat;
+        use arbitrary::{Arbitrary, Unstructured};
+
+        let data: Vec<u8> = (0..=255).cycle().take(4096).collect();
+        let mut u = Unstructured::new(&data);
+        let mut nested = false;
+        while let Ok(kvs_map) = KvsMap::arbitrary(&mut u) {
+            if u.is_empty() {
+                break;
+            }
+            nested |= kvs_map
+                .values()
+                .any(|value| matches!(value, KvsValue::Array(_) | KvsValue::Object(_)));
+            // Non-finite floats can't be rendered.
+            if let Ok(json) = JsonBackend::kvs_map_to_string(&kvs_map, &FloatFormat::default()) {
+                fuzz_load::<JsonBackend>(json.as_bytes()).unwrap();
+            }
+        }
+        assert!(nested);
+    }
+
+    #[cfg(feature = "proptest")]
+    mod properties {
+        use crate::json_backend::JsonBackend;
+        use crate::kvs_backend::KvsBackend;
+        use crate::kvs_fuzz::{fuzz_load, strategy};
+        use proptest::prelude::*;
+        use tempfile::tempdir;
+
+        proptest! {
+            #[test]
+            fn test_json_round_trip(kvs_map in strategy::kvs_map()) {
+                let dir = tempdir().unwrap();
+                let kvs_path = dir.path().join("kvs_0_0.json");
+                let hash_path = dir.path().join("kvs_0_0.hash");
+                JsonBackend::save_kvs(&kvs_map, &kvs_path, Some(&hash_path)).unwrap();
+                prop_assert_eq!(JsonBackend::load_kvs(&kvs_path, Some(&hash_path)).unwrap(), kvs_map);
+            }
+
+            #[test]
+            fn test_json_load_no_panic(data in proptest::collection::vec(any::<u8>(), 0..256)) {
+                let _ = fuzz_load::<JsonBackend>(&data);
+            }
+        }
+    }
+}
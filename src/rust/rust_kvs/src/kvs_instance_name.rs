@@ -0,0 +1,221 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Instances identified by name.
+//!
+//! [`InstanceId::from_name`] registers a name process-wide, registering the same name again returns
+//! the same ID. Named instances have their own instance pool slots, numbered in registration order
+//! and limited by [`max_instances`](crate::kvs_builder::GenericKvsBuilder::max_instances) like
+//! numeric IDs. Numeric and named instances never share slots, data or files, registering a name
+//! doesn't affect which numeric IDs can be opened.
+//!
+//! Named IDs are displayed as the name, so instances opened with a named ID store their files under
+//! the name instead of the ID, e.g. `kvs_telemetry_0.json`, `kvs_telemetry_0.hash` and
+//! `kvs_telemetry_default.json`, and logs and errors refer to the name. The registration index is
+//! only used within the process, so instances of different components can't share files by
+//! accident: the same name is the same instance, different names use different files.
+//!
+//! Names consist of ASCII letters, digits, `-` and `_` and must not be numeric, to be
+//! distinguishable from numeric IDs in file names.
+
+use crate::error_code::ErrorCode;
+use crate::kvs_builder;
+use crate::kvs_log::{kvs_debug, kvs_error};
+use crate::kvs_storage::InstanceId;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Flag set in named instance IDs, the remaining bits are the registration index of the name.
+const NAMED_ID_FLAG: usize = 1 << (usize::BITS - 1);
+
+/// Registered names by registration index.
+///
+/// Never locked while an instance ID is formatted, displaying a named ID locks it.
+static NAMES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+impl From<PoisonError<MutexGuard<'_, Vec<String>>>> for ErrorCode {
+    fn from(_cause: PoisonError<MutexGuard<'_, Vec<String>>>) -> Self {
+        ErrorCode::MutexLockFailed
+    }
+}
+
+/// Check an instance name
+///
+/// # Return Values
+///   * Ok: Name is valid
+///   * `ErrorCode::InvalidInstanceId`: Name is empty, numeric or contains other characters
+fn validate(name: &str) -> Result<(), ErrorCode> {
+    let valid_chars = name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if name.is_empty() || !valid_chars || name.chars().all(|c| c.is_ascii_digit()) {
+        kvs_error!("invalid instance name {name:?}");
+        return Err(ErrorCode::InvalidInstanceId);
+    }
+    Ok(())
+}
+
+/// Register an instance name, see [`InstanceId::from_name`].
+pub(crate) fn register(name: &str) -> Result<InstanceId, ErrorCode> {
+    validate(name)?;
+    let mut names = NAMES.lock()?;
+    if let Some(idx) = names.iter().position(|n| n == name) {
+        return Ok(InstanceId(NAMED_ID_FLAG | idx));
+    }
+    let idx = names.len();
+    if idx >= kvs_builder::pool_max_instances() {
+        drop(names);
+        kvs_error!("no free instance ID for name {name:?}");
+        return Err(ErrorCode::InvalidInstanceId);
+    }
+    names.push(name.to_string());
+    drop(names);
+    let instance_id = InstanceId(NAMED_ID_FLAG | idx);
+    kvs_debug!(instance_id = instance_id, "instance name registered");
+    Ok(instance_id)
+}
+
+/// Check if an instance ID is named.
+pub(crate) fn is_named(instance_id: InstanceId) -> bool {
+    instance_id.0 & NAMED_ID_FLAG != 0
+}
+
+/// Get the registered name of an instance ID, `None` for numeric IDs.
+pub(crate) fn lookup(instance_id: InstanceId) -> Option<String> {
+    if !is_named(instance_id) {
+        return None;
+    }
+    NAMES.lock().ok()?.get(pool_index(instance_id)).cloned()
+}
+
+/// Get the instance pool slot of an instance ID within its namespace.
+pub(crate) fn pool_index(instance_id: InstanceId) -> usize {
+    instance_id.0 & !NAMED_ID_FLAG
+}
+
+/// Check that an instance ID can be opened
+///
+/// # Return Values
+///   * Ok: Numeric ID or registered named ID
+///   * `ErrorCode::InvalidInstanceId`: Unregistered named ID
+///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+pub(crate) fn check(instance_id: InstanceId) -> Result<(), ErrorCode> {
+    if !is_named(instance_id) || pool_index(instance_id) < NAMES.lock()?.len() {
+        return Ok(());
+    }
+    kvs_error!(
+        instance_id = instance_id,
+        "instance ID {instance_id} has no registered name"
+    );
+    Err(ErrorCode::InvalidInstanceId)
+}
+
+/// Remove all registered names.
+#[cfg(all(test, feature = "json-backend"))]
+pub(crate) fn clear() {
+    if let Ok(mut names) = NAMES.lock() {
+        names.clear();
+    }
+}
+
+#[cfg(all(test, feature = "json-backend"))]
+mod kvs_instance_name_tests {
+    use crate::error_code::ErrorCode;
+    use crate::json_backend::JsonBackend;
+    use crate::kvs_api::{InstanceId, KvsApi, SnapshotId};
+    use crate::kvs_builder::kvs_builder_tests::lock_and_reset;
+    use crate::kvs_builder::GenericKvsBuilder;
+    use crate::kvs_instance_name::{clear, pool_index};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_from_name() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+        let open = GenericKvsBuilder::<JsonBackend>::new(InstanceId(9))
+            .dir(dir_string.clone())
+            .build()
+            .unwrap();
+
+        let telemetry = InstanceId::from_name("telemetry").unwrap();
+        assert_eq!(pool_index(telemetry), 0);
+        assert_eq!(InstanceId::from_name("telemetry").unwrap(), telemetry);
+        assert_eq!(telemetry.name().as_deref(), Some("telemetry"));
+        assert_eq!(telemetry.to_string(), "telemetry");
+        assert_eq!(InstanceId(0).name(), None);
+        assert_eq!(InstanceId(0).to_string(), "0");
+        assert_eq!(pool_index(InstanceId::from_name("network").unwrap()), 1);
+        assert_eq!(open.parameters().instance_id.name(), None);
+        for name in ["", "42", "a b", "a/b"] {
+            assert!(InstanceId::from_name(name).is_err_and(|e| e == ErrorCode::InvalidInstanceId));
+        }
+
+        let kvs = GenericKvsBuilder::<JsonBackend>::new(telemetry)
+            .dir(dir_string)
+            .build()
+            .unwrap();
+        kvs.set_value("key", 1).unwrap();
+        kvs.flush().unwrap();
+        assert_eq!(
+            kvs.get_kvs_filename(SnapshotId(0)).unwrap(),
+            dir.path().join("kvs_telemetry_0.json")
+        );
+        assert!(dir.path().join("kvs_telemetry_0.hash").exists());
+    }
+
+    #[test]
+    fn test_from_name_namespace() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+        let numeric = GenericKvsBuilder::<JsonBackend>::new(InstanceId(0))
+            .dir(dir_string.clone())
+            .build()
+            .unwrap();
+        numeric.set_value("key", "numeric").unwrap();
+        numeric.flush().unwrap();
+
+        // Named and numeric instances with the same pool index are distinct.
+        let telemetry = InstanceId::from_name("telemetry").unwrap();
+        assert_eq!(pool_index(telemetry), 0);
+        let named = GenericKvsBuilder::<JsonBackend>::new(telemetry)
+            .dir(dir_string.clone())
+            .build()
+            .unwrap();
+        named.set_value("key", "named").unwrap();
+        named.flush().unwrap();
+        assert_eq!(numeric.get_value_as::<String>("key").unwrap(), "numeric");
+        assert!(dir.path().join("kvs_0_0.json").exists());
+        assert!(dir.path().join("kvs_telemetry_0.json").exists());
+
+        // Numeric IDs open independently of registered names.
+        for id in 0..10 {
+            GenericKvsBuilder::<JsonBackend>::new(InstanceId(id))
+                .dir(dir_string.clone())
+                .build()
+                .unwrap();
+        }
+
+        // Names are limited by max instances like numeric IDs.
+        for idx in 1..10 {
+            InstanceId::from_name(&format!("name{idx}")).unwrap();
+        }
+        assert!(InstanceId::from_name("name10").is_err_and(|e| e == ErrorCode::InvalidInstanceId));
+
+        // Named IDs must be registered.
+        let name9 = InstanceId::from_name("name9").unwrap();
+        clear();
+        assert!(GenericKvsBuilder::<JsonBackend>::new(name9)
+            .dir(dir_string)
+            .build()
+            .is_err_and(|e| e == ErrorCode::InvalidInstanceId));
+    }
+}
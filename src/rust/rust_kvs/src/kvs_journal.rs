@@ -0,0 +1,345 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Write-ahead journal of changes between flushes.
+//!
+//! With [`GenericKvsBuilder::journal`](crate::kvs_builder::GenericKvsBuilder::journal) enabled,
+//! each change is appended as one record to the journal file `kvs_<instance_id>.wal` before the
+//! call returns. A record only contains the changed keys, so frequently updated values, e.g. event
+//! counters, are durable per write without serializing the whole instance. Records are synced to
+//! the storage device unless the [`SyncPolicy`] is `None`. If appending fails, the change is kept in
+//! memory and the error is returned.
+//!
+//! A flush writes the current snapshot and removes the journal. When an instance is opened, an
+//! existing journal is replayed onto the loaded data and compacted by a flush, regardless of the
+//! journal setting, unless loading is [`KvsLoad::Ignored`](crate::kvs_api::KvsLoad::Ignored). A torn last record, e.g. after power loss, is ignored.
+//!
+//! Records are type-tagged JSON objects, one per line:
+//! ```text
+//! {"op":"set","k":"counter","v":{"t":"u32","v":7}}
+//! {"op":"remove","k":"counter"}
+//! {"op":"clear"}
+//! ```
+
+use crate::error_code::ErrorCode;
+use crate::kvs::GenericKvs;
+use crate::kvs_api::{InstanceId, SyncPolicy};
+use crate::kvs_backend::{KvsBackend, KvsPathResolver};
+use crate::kvs_builder::KvsData;
+use crate::kvs_log::{kvs_error, kvs_warn};
+use crate::kvs_value::{KvsMap, KvsValue, ValueModel};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::Path;
+use tinyjson::JsonValue;
+
+/// Name of the journal file of an instance.
+pub(crate) fn journal_file_name(instance_id: InstanceId) -> String {
+    format!("kvs_{instance_id}.wal")
+}
+
+/// Change recorded in the journal.
+#[derive(Clone, Debug, PartialEq)]
+enum JournalRecord {
+    /// Key was assigned a value.
+    Set(String, KvsValue),
+
+    /// Key was removed.
+    Remove(String),
+
+    /// All keys were removed.
+    Clear,
+}
+
+impl JournalRecord {
+    /// Serialize the record to one line of JSON.
+    fn to_line(&self) -> Result<String, ErrorCode> {
+        let mut obj = HashMap::new();
+        let op = match self {
+            JournalRecord::Set(key, value) => {
+                obj.insert("k".to_string(), JsonValue::String(key.clone()));
+                obj.insert("v".to_string(), JsonValue::from(value.clone()));
+                "set"
+            }
+            JournalRecord::Remove(key) => {
+                obj.insert("k".to_string(), JsonValue::String(key.clone()));
+                "remove"
+            }
+            JournalRecord::Clear => "clear",
+        };
+        obj.insert("op".to_string(), JsonValue::String(op.to_string()));
+        Ok(JsonValue::Object(obj).stringify()?)
+    }
+
+    /// Parse a record from one line of JSON, `None` if the line isn't a valid record.
+    fn from_line(line: &str) -> Option<JournalRecord> {
+        let JsonValue::Object(mut obj) = line.parse::<JsonValue>().ok()? else {
+            return None;
+        };
+        let key = match obj.remove("k") {
+            Some(JsonValue::String(key)) => Some(key),
+            _ => None,
+        };
+        match (obj.remove("op")?, key) {
+            (JsonValue::String(op), Some(key)) if op == "set" => {
+                Some(JournalRecord::Set(key, KvsValue::from(obj.remove("v")?)))
+            }
+            (JsonValue::String(op), Some(key)) if op == "remove" => {
+                Some(JournalRecord::Remove(key))
+            }
+            (JsonValue::String(op), None) if op == "clear" => Some(JournalRecord::Clear),
+            _ => None,
+        }
+    }
+
+    /// Apply the record to a map.
+    fn apply(self, kvs_map: &mut KvsMap) {
+        match self {
+            JournalRecord::Set(key, value) => {
+                kvs_map.insert(key, value);
+            }
+            JournalRecord::Remove(key) => {
+                kvs_map.remove(&key);
+            }
+            JournalRecord::Clear => kvs_map.clear(),
+        }
+    }
+}
+
+/// Append records to a journal file
+///
+/// # Parameters
+///   * `path`: Journal file
+///   * `records`: Records to append
+///   * `sync`: Journal file is synced to the storage device
+///
+/// # Return Values
+///   * Ok: Records appended
+///   * `ErrorCode::JsonGeneratorError`: Record could not be serialized
+///   * `ErrorCode::UnmappedError`: Journal file could not be written
+fn append(path: &Path, records: &[JournalRecord], sync: bool) -> Result<(), ErrorCode> {
+    let mut buf = String::new();
+    for record in records {
+        buf.push_str(&record.to_line()?);
+        buf.push('\n');
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| ErrorCode::from(e).with_path(path))?;
+    file.write_all(buf.as_bytes())
+        .and_then(|()| if sync { file.sync_data() } else { Ok(()) })
+        .map_err(|e| ErrorCode::from(e).with_path(path))
+}
+
+/// Replay a journal file onto a map
+///
+/// Replay stops at the first invalid record.
+///
+/// # Parameters
+///   * `path`: Journal file
+///   * `kvs_map`: Map the records are applied to
+///
+/// # Return Values
+///   * Ok: Count of applied records, 0 if the journal doesn't exist
+///   * `ErrorCode::UnmappedError`: Journal file could not be read
+pub(crate) fn replay(path: &Path, kvs_map: &mut KvsMap) -> Result<usize, ErrorCode> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(ErrorCode::from(e).with_path(path)),
+    };
+    let mut count = 0;
+    for line in content.lines() {
+        match JournalRecord::from_line(line) {
+            Some(record) => {
+                record.apply(kvs_map);
+                count += 1;
+            }
+            None => {
+                kvs_warn!(
+                    "ignoring invalid journal record {} of {}",
+                    count + 1,
+                    path.display()
+                );
+                break;
+            }
+        }
+    }
+    Ok(count)
+}
+
+impl<Backend: KvsBackend, PathResolver: KvsPathResolver, Model: ValueModel>
+    GenericKvs<Backend, PathResolver, Model>
+{
+    /// Record the current values of changed keys, keys without a value are recorded as removed.
+    ///
+    /// Nothing is written if journaling is disabled.
+    pub(crate) fn journal_keys<'a>(
+        &self,
+        data: &KvsData,
+        keys: impl IntoIterator<Item = &'a str>,
+    ) -> Result<(), ErrorCode> {
+        if !self.parameters().journal {
+            return Ok(());
+        }
+        let records: Vec<JournalRecord> = keys
+            .into_iter()
+            .map(|key| match data.kvs_map.get(key) {
+                Some(value) => JournalRecord::Set(key.to_string(), value.clone()),
+                None => JournalRecord::Remove(key.to_string()),
+            })
+            .collect();
+        self.journal_append(&records)
+    }
+
+    /// Record replacement of all values by the current values.
+    ///
+    /// Nothing is written if journaling is disabled.
+    pub(crate) fn journal_reset(&self, data: &KvsData) -> Result<(), ErrorCode> {
+        if !self.parameters().journal {
+            return Ok(());
+        }
+        let records: Vec<JournalRecord> = std::iter::once(JournalRecord::Clear)
+            .chain(
+                data.kvs_map
+                    .iter()
+                    .map(|(key, value)| JournalRecord::Set(key.clone(), value.clone())),
+            )
+            .collect();
+        self.journal_append(&records)
+    }
+
+    /// Append records to the journal of the instance.
+    fn journal_append(&self, records: &[JournalRecord]) -> Result<(), ErrorCode> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        let parameters = self.parameters();
+        let path = parameters.journal_file_path::<PathResolver>();
+        append(&path, records, parameters.sync_policy != SyncPolicy::None).inspect_err(|e| {
+            kvs_error!(
+                instance_id = parameters.instance_id,
+                "writing journal failed: {e}"
+            );
+        })
+    }
+
+    /// Remove the journal after its records were flushed.
+    pub(crate) fn remove_journal(&self) -> Result<(), ErrorCode> {
+        let path = self.parameters().journal_file_path::<PathResolver>();
+        match fs::remove_file(&path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(ErrorCode::from(e).with_path(&path)),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "json-backend"))]
+mod kvs_journal_tests {
+    use crate::json_backend::JsonBackend;
+    use crate::kvs_api::{InstanceId, KvsApi, KvsLoad};
+    use crate::kvs_builder::kvs_builder_tests::lock_and_reset;
+    use crate::kvs_builder::GenericKvsBuilder;
+    use crate::kvs_journal::{replay, JournalRecord};
+    use crate::kvs_value::{KvsMap, KvsValue};
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_record_roundtrip() {
+        let records = [
+            JournalRecord::Set("a\nb".to_string(), KvsValue::U64(u64::MAX)),
+            JournalRecord::Remove("key".to_string()),
+            JournalRecord::Clear,
+        ];
+        for record in records {
+            let line = record.to_line().unwrap();
+            assert!(!line.contains('\n'));
+            assert_eq!(JournalRecord::from_line(&line), Some(record));
+        }
+        assert_eq!(JournalRecord::from_line(r#"{"op":"set","k":"#), None);
+        assert_eq!(JournalRecord::from_line(r#"{"op":"clear","k":"x"}"#), None);
+    }
+
+    #[test]
+    fn test_journal_replayed_on_open() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+        let journal_path = dir.path().join("kvs_1.wal");
+        {
+            let kvs = GenericKvsBuilder::<JsonBackend>::new(InstanceId(1))
+                .dir(dir_string.clone())
+                .journal(true)
+                .build()
+                .unwrap();
+            kvs.set_value("kept", 1).unwrap();
+            kvs.set_value("removed", true).unwrap();
+            kvs.flush().unwrap();
+            assert!(!journal_path.exists());
+
+            for counter in 0..5 {
+                kvs.set_value("counter", counter).unwrap();
+            }
+            kvs.remove_key("removed").unwrap();
+            assert_eq!(
+                fs::read_to_string(&journal_path).unwrap().lines().count(),
+                6
+            );
+            // Simulate a crash, nothing is flushed.
+            std::mem::forget(kvs);
+        }
+        drop(_lock);
+        // Torn record of the crash.
+        let mut content = fs::read_to_string(&journal_path).unwrap();
+        content.push_str(r#"{"op":"set","k":"counter","v":{"t":"#);
+        fs::write(&journal_path, content).unwrap();
+
+        let _lock = lock_and_reset();
+        let kvs = GenericKvsBuilder::<JsonBackend>::new(InstanceId(1))
+            .dir(dir_string)
+            .kvs_load(KvsLoad::Required)
+            .build()
+            .unwrap();
+        assert_eq!(kvs.get_value_as::<i32>("counter").unwrap(), 4);
+        assert_eq!(kvs.get_value_as::<i32>("kept").unwrap(), 1);
+        assert!(!kvs.key_exists("removed").unwrap());
+        assert!(!journal_path.exists());
+        assert!(!kvs.is_dirty().unwrap());
+    }
+
+    #[test]
+    fn test_journal_reset() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let kvs = GenericKvsBuilder::<JsonBackend>::new(InstanceId(1))
+            .dir(dir.path().to_string_lossy().to_string())
+            .journal(true)
+            .build()
+            .unwrap();
+        kvs.set_value("a", 1).unwrap();
+        kvs.reset().unwrap();
+        kvs.set_value("b", 2).unwrap();
+
+        let mut kvs_map = KvsMap::new();
+        kvs_map.insert("old".to_string(), KvsValue::Null);
+        assert_eq!(
+            replay(&dir.path().join("kvs_1.wal"), &mut kvs_map).unwrap(),
+            3
+        );
+        assert_eq!(kvs_map.len(), 1);
+        assert_eq!(kvs_map.get("b"), Some(&KvsValue::I32(2)));
+    }
+}
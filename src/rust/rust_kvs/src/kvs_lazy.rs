@@ -0,0 +1,285 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Values parsed on first access.
+//!
+//! With [`GenericKvsBuilder::lazy_load`](crate::kvs_builder::GenericKvsBuilder::lazy_load) the
+//! KVS file is read, validated and indexed when opening the instance, but values are only parsed
+//! when their key is read first. Writes of single keys, e.g. setting or removing, only parse the
+//! written keys. Operations working on all values, e.g. flushing or writes checked against size or
+//! count limits, parse the remaining values first.
+//!
+//! Backends support lazy loading by implementing `KvsBackend::load_kvs_lazy`, other backends load
+//! eagerly. Lazy loading isn't used if changes are merged on flush.
+
+use crate::error_code::ErrorCode;
+use crate::kvs_log::kvs_error;
+use crate::kvs_value::{KvsMap, KvsValue};
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Function parsing the text of a single value.
+pub type LazyParseFn = fn(&str) -> Result<KvsValue, ErrorCode>;
+
+/// Values of a loaded KVS not parsed yet.
+pub struct LazyKvsMap {
+    source: String,
+    spans: HashMap<String, Range<usize>>,
+    parse: LazyParseFn,
+}
+
+impl LazyKvsMap {
+    /// Create from the loaded text and the location of each value.
+    ///
+    /// # Parameters
+    ///   * `source`: Loaded text
+    ///   * `spans`: Byte range of the value text per key
+    ///   * `parse`: Function parsing a value text
+    pub fn new(source: String, spans: HashMap<String, Range<usize>>, parse: LazyParseFn) -> Self {
+        Self {
+            source,
+            spans,
+            parse,
+        }
+    }
+
+    /// Count of values not parsed yet.
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    /// Whether all values were parsed.
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// Whether the value of a key wasn't parsed yet.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.spans.contains_key(key)
+    }
+
+    /// Keys of values not parsed yet.
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.spans.keys()
+    }
+
+    /// Parse the value of a key and remove it.
+    ///
+    /// The value is kept if parsing fails.
+    ///
+    /// # Return Values
+    ///   * Ok: Parsed value, `None` if the key isn't pending
+    ///   * `ErrorCode::JsonParserError`: Value text couldn't be parsed
+    pub fn take(&mut self, key: &str) -> Result<Option<KvsValue>, ErrorCode> {
+        let Some(span) = self.spans.get(key) else {
+            return Ok(None);
+        };
+        let value = (self.parse)(&self.source[span.clone()]).inspect_err(|e| {
+            kvs_error!(
+                key = key,
+                "lazy value of key {key} could not be parsed: {e}"
+            )
+        })?;
+        self.spans.remove(key);
+        Ok(Some(value))
+    }
+
+    /// Parse and remove all values.
+    ///
+    /// Values are kept if parsing any of them fails.
+    ///
+    /// # Return Values
+    ///   * Ok: Parsed values
+    ///   * `ErrorCode::JsonParserError`: A value text couldn't be parsed
+    pub fn take_all(&mut self) -> Result<KvsMap, ErrorCode> {
+        let kvs_map = self
+            .spans
+            .iter()
+            .map(|(key, span)| {
+                (self.parse)(&self.source[span.clone()])
+                    .map(|value| (key.clone(), value))
+                    .inspect_err(|e| {
+                        kvs_error!(
+                            key = key,
+                            "lazy value of key {key} could not be parsed: {e}"
+                        )
+                    })
+            })
+            .collect::<Result<KvsMap, ErrorCode>>()?;
+        self.spans.clear();
+        self.source = String::new();
+        Ok(kvs_map)
+    }
+}
+
+#[cfg(all(test, feature = "json-backend"))]
+mod kvs_lazy_tests {
+    use crate::error_code::ErrorCode;
+    use crate::kvs_api::{InstanceId, KvsApi};
+    use crate::kvs_builder::kvs_builder_tests::lock_and_reset;
+    use crate::kvs_lazy::LazyKvsMap;
+    use crate::kvs_merge::KvsMergePolicy;
+    use crate::kvs_value::{KvsMap, KvsValue};
+    use crate::{Kvs, KvsBuilder};
+    use std::collections::HashMap;
+    use tempfile::{tempdir, TempDir};
+
+    fn parse(s: &str) -> Result<KvsValue, ErrorCode> {
+        s.parse::<i32>()
+            .map(KvsValue::I32)
+            .map_err(|_| ErrorCode::JsonParserError)
+    }
+
+    fn lazy_map() -> LazyKvsMap {
+        LazyKvsMap::new(
+            "1,22,x".to_string(),
+            HashMap::from([
+                ("a".to_string(), 0..1),
+                ("b".to_string(), 2..4),
+                ("bad".to_string(), 5..6),
+            ]),
+            parse,
+        )
+    }
+
+    #[test]
+    fn test_take() {
+        let mut lazy = lazy_map();
+        assert_eq!(lazy.take("b").unwrap(), Some(KvsValue::I32(22)));
+        assert_eq!(lazy.take("b").unwrap(), None);
+        assert!(lazy
+            .take("bad")
+            .is_err_and(|e| e == ErrorCode::JsonParserError));
+        assert!(lazy.contains_key("bad"));
+        assert_eq!(lazy.len(), 2);
+    }
+
+    #[test]
+    fn test_take_all() {
+        let mut lazy = lazy_map();
+        assert!(lazy
+            .take_all()
+            .is_err_and(|e| e == ErrorCode::JsonParserError));
+        assert_eq!(lazy.len(), 3);
+
+        lazy.take("a").unwrap();
+        lazy.spans.remove("bad");
+        assert_eq!(
+            lazy.take_all().unwrap(),
+            KvsMap::from([("b".to_string(), KvsValue::I32(22))])
+        );
+        assert!(lazy.is_empty());
+    }
+
+    fn stored_kvs() -> TempDir {
+        let dir = tempdir().unwrap();
+        let kvs = KvsBuilder::new(InstanceId(0))
+            .dir(dir.path().to_string_lossy().to_string())
+            .build()
+            .unwrap();
+        kvs.set_value("number", 1.5).unwrap();
+        kvs.set_value("string", "text").unwrap();
+        kvs.set_value(
+            "object",
+            KvsValue::Object(KvsMap::from([("k".to_string(), KvsValue::I32(1))])),
+        )
+        .unwrap();
+        kvs.flush().unwrap();
+        kvs.close().unwrap();
+        dir
+    }
+
+    fn open_lazy(dir: &TempDir) -> Kvs {
+        KvsBuilder::new(InstanceId(0))
+            .dir(dir.path().to_string_lossy().to_string())
+            .lazy_load(true)
+            .force_reopen()
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_lazy_load() {
+        let _lock = lock_and_reset();
+        let dir = stored_kvs();
+        let kvs = open_lazy(&dir);
+        assert!(kvs.data.lock().unwrap().kvs_map.is_empty());
+
+        assert!(kvs.key_exists("object").unwrap());
+        assert!(!kvs.is_value_default("string").unwrap());
+        let mut keys = kvs.get_all_keys().unwrap();
+        keys.sort();
+        assert_eq!(keys, ["number", "object", "string"]);
+        assert_eq!(kvs.get_value_as::<String>("string").unwrap(), "text");
+        {
+            let data = kvs.data.lock().unwrap();
+            assert_eq!(data.kvs_map.len(), 1);
+            assert_eq!(data.lazy.as_ref().unwrap().len(), 2);
+        }
+
+        // Writes only parse the written keys.
+        kvs.set_value("added", true).unwrap();
+        kvs.set_value("number", 2.5).unwrap();
+        assert_eq!(kvs.data.lock().unwrap().lazy.as_ref().unwrap().len(), 1);
+        kvs.remove_key("object").unwrap();
+        assert!(kvs.data.lock().unwrap().lazy.is_none());
+        assert_eq!(kvs.get_value("number").unwrap(), KvsValue::F64(2.5));
+    }
+
+    #[test]
+    fn test_lazy_load_limits() {
+        let _lock = lock_and_reset();
+        let dir = stored_kvs();
+        let kvs = KvsBuilder::new(InstanceId(0))
+            .dir(dir.path().to_string_lossy().to_string())
+            .lazy_load(true)
+            .max_keys(3)
+            .force_reopen()
+            .build()
+            .unwrap();
+        assert!(kvs
+            .set_value("added", true)
+            .is_err_and(|e| e == ErrorCode::QuotaExceeded));
+        assert!(kvs.data.lock().unwrap().lazy.is_none());
+    }
+
+    #[cfg(feature = "snapshots")]
+    #[test]
+    fn test_lazy_load_flush() {
+        let _lock = lock_and_reset();
+        let dir = stored_kvs();
+        let kvs = open_lazy(&dir);
+        kvs.snapshot_create().unwrap();
+
+        let reopened = open_lazy(&dir);
+        assert_eq!(
+            reopened.get_value("object").unwrap(),
+            KvsValue::Object(KvsMap::from([("k".to_string(), KvsValue::I32(1))]))
+        );
+        assert_eq!(reopened.get_value("number").unwrap(), KvsValue::F64(1.5));
+    }
+
+    #[test]
+    fn test_lazy_load_merge_policy() {
+        let _lock = lock_and_reset();
+        let dir = stored_kvs();
+        let kvs = KvsBuilder::new(InstanceId(0))
+            .dir(dir.path().to_string_lossy().to_string())
+            .lazy_load(true)
+            .merge_on_flush(KvsMergePolicy::LastWriterWins)
+            .force_reopen()
+            .build()
+            .unwrap();
+        let data = kvs.data.lock().unwrap();
+        assert!(data.lazy.is_none());
+        assert_eq!(data.kvs_map.len(), 3);
+    }
+}
@@ -0,0 +1,269 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Inter-process advisory lock of a KVS working directory.
+//!
+//! Flushing an instance holds the exclusive lock of its working directory while snapshots are
+//! rotated and the new files are written. Other processes accessing the directory, e.g.
+//! `kvs_tool`, take the same lock to never observe or interleave with an in-progress flush.
+//!
+//! The lock is reentrant within a process: nested acquisitions of the same directory share one
+//! lock, which is released when the last [`KvsDirLock`] is dropped. Locking is advisory, processes
+//! not taking the lock aren't prevented from accessing the files.
+
+use crate::error_code::ErrorCode;
+use crate::kvs_log::kvs_error;
+use fs4::fs_std::FileExt;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex, MutexGuard, PoisonError};
+
+/// Name of the lock file inside the working directory.
+pub const LOCK_FILE_NAME: &str = "kvs.lock";
+
+/// Lock file held by this process.
+struct HeldLock {
+    /// Locked file, unlocked when closed.
+    _file: File,
+
+    /// Number of `KvsDirLock` instances sharing the lock.
+    count: usize,
+}
+
+/// Locks held by this process, by lock file path.
+static HELD_LOCKS: LazyLock<Mutex<HashMap<PathBuf, HeldLock>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+impl From<PoisonError<MutexGuard<'_, HashMap<PathBuf, HeldLock>>>> for ErrorCode {
+    fn from(_cause: PoisonError<MutexGuard<'_, HashMap<PathBuf, HeldLock>>>) -> Self {
+        ErrorCode::MutexLockFailed
+    }
+}
+
+/// Held lock of a working directory, released on drop.
+pub struct KvsDirLock {
+    path: PathBuf,
+}
+
+impl KvsDirLock {
+    /// Get lock file path of a working directory.
+    ///
+    /// # Parameters
+    ///   * `working_dir`: Working directory, empty for the current directory
+    ///
+    /// # Return Values
+    ///   * Lock file path
+    pub fn lock_file_path(working_dir: &Path) -> PathBuf {
+        let working_dir = if working_dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            working_dir
+        };
+        // Same directory must map to the same path for reentrant locking.
+        working_dir
+            .canonicalize()
+            .unwrap_or_else(|_| working_dir.to_path_buf())
+            .join(LOCK_FILE_NAME)
+    }
+
+    /// Acquire lock of a working directory, waiting until it's available.
+    ///
+    /// # Parameters
+    ///   * `working_dir`: Working directory, empty for the current directory
+    ///
+    /// # Return Values
+    ///   * Ok: Lock acquired
+    ///   * `ErrorCode::MutexLockFailed`: Lock registry poisoned
+    ///   * `ErrorCode::FileNotFound`: Working directory doesn't exist
+    ///   * `ErrorCode::UnmappedError`: Unmapped error
+    pub fn acquire(working_dir: &Path) -> Result<Self, ErrorCode> {
+        let path = Self::lock_file_path(working_dir);
+        if let Some(lock) = Self::reenter(&path)? {
+            return Ok(lock);
+        }
+
+        let file = Self::open(&path)?;
+        file.lock_exclusive()?;
+        Self::register(path, file)
+    }
+
+    /// Acquire lock of a working directory without waiting.
+    ///
+    /// # Parameters
+    ///   * `working_dir`: Working directory, empty for the current directory
+    ///
+    /// # Return Values
+    ///   * Ok(Some): Lock acquired
+    ///   * Ok(None): Lock is held by another process
+    ///   * `ErrorCode::MutexLockFailed`: Lock registry poisoned
+    ///   * `ErrorCode::FileNotFound`: Working directory doesn't exist
+    ///   * `ErrorCode::UnmappedError`: Unmapped error
+    pub fn try_acquire(working_dir: &Path) -> Result<Option<Self>, ErrorCode> {
+        let path = Self::lock_file_path(working_dir);
+        if let Some(lock) = Self::reenter(&path)? {
+            return Ok(Some(lock));
+        }
+
+        let file = Self::open(&path)?;
+        if file.try_lock_exclusive()? {
+            Self::register(path, file).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Lock file path.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Share lock already held by this process.
+    fn reenter(path: &Path) -> Result<Option<Self>, ErrorCode> {
+        let mut held_locks = HELD_LOCKS.lock()?;
+        Ok(held_locks.get_mut(path).map(|held_lock| {
+            held_lock.count += 1;
+            Self {
+                path: path.to_path_buf(),
+            }
+        }))
+    }
+
+    fn open(path: &Path) -> Result<File, ErrorCode> {
+        Ok(OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?)
+    }
+
+    /// Register acquired lock.
+    fn register(path: PathBuf, file: File) -> Result<Self, ErrorCode> {
+        let mut held_locks = HELD_LOCKS.lock()?;
+        held_locks.insert(
+            path.clone(),
+            HeldLock {
+                _file: file,
+                count: 1,
+            },
+        );
+        Ok(Self { path })
+    }
+}
+
+impl Drop for KvsDirLock {
+    fn drop(&mut self) {
+        let Ok(mut held_locks) = HELD_LOCKS.lock() else {
+            kvs_error!(
+                "lock registry poisoned, {} not released",
+                self.path.display()
+            );
+            return;
+        };
+        if let Some(held_lock) = held_locks.get_mut(&self.path) {
+            held_lock.count -= 1;
+            if held_lock.count == 0 {
+                held_locks.remove(&self.path);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "json-backend"))]
+mod kvs_lock_tests {
+    use crate::json_backend::JsonBackend;
+    use crate::kvs_api::{InstanceId, KvsApi};
+    use crate::kvs_builder::kvs_builder_tests::lock_and_reset;
+    use crate::kvs_builder::GenericKvsBuilder;
+    use crate::kvs_lock::{KvsDirLock, LOCK_FILE_NAME};
+    use fs4::fs_std::FileExt;
+    use std::fs::File;
+    use std::path::Path;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    /// Open lock file separately, behaving like another process.
+    fn other_process_file(working_dir: &Path) -> File {
+        File::open(working_dir.join(LOCK_FILE_NAME)).unwrap()
+    }
+
+    #[test]
+    fn test_acquire_exclusive() {
+        let dir = tempdir().unwrap();
+        let lock = KvsDirLock::acquire(dir.path()).unwrap();
+        assert_eq!(
+            lock.path(),
+            dir.path().canonicalize().unwrap().join(LOCK_FILE_NAME)
+        );
+        assert!(!other_process_file(dir.path()).try_lock_exclusive().unwrap());
+
+        drop(lock);
+        assert!(other_process_file(dir.path()).try_lock_exclusive().unwrap());
+    }
+
+    #[test]
+    fn test_acquire_reentrant() {
+        let dir = tempdir().unwrap();
+        let outer = KvsDirLock::acquire(dir.path()).unwrap();
+        let inner = KvsDirLock::acquire(dir.path()).unwrap();
+        let nested = KvsDirLock::try_acquire(dir.path()).unwrap();
+        assert!(nested.is_some());
+
+        drop(nested);
+        drop(inner);
+        assert!(!other_process_file(dir.path()).try_lock_exclusive().unwrap());
+        drop(outer);
+        assert!(other_process_file(dir.path()).try_lock_exclusive().unwrap());
+    }
+
+    #[test]
+    fn test_try_acquire_held_by_other_process() {
+        let dir = tempdir().unwrap();
+        drop(KvsDirLock::acquire(dir.path()).unwrap());
+
+        let file = other_process_file(dir.path());
+        file.lock_exclusive().unwrap();
+        assert!(KvsDirLock::try_acquire(dir.path()).unwrap().is_none());
+        FileExt::unlock(&file).unwrap();
+        assert!(KvsDirLock::try_acquire(dir.path()).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_acquire_missing_dir() {
+        let dir = tempdir().unwrap();
+        assert!(KvsDirLock::acquire(&dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn test_flush_waits_for_lock() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let kvs = GenericKvsBuilder::<JsonBackend>::new(InstanceId(1))
+            .dir(dir.path().to_string_lossy().to_string())
+            .build()
+            .unwrap();
+        kvs.set_value("key", 1).unwrap();
+        drop(KvsDirLock::acquire(dir.path()).unwrap());
+
+        let file = other_process_file(dir.path());
+        file.lock_exclusive().unwrap();
+        let (tx, rx) = mpsc::channel();
+        let thread = thread::spawn(move || tx.send(kvs.flush()).unwrap());
+        assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+
+        FileExt::unlock(&file).unwrap();
+        assert!(rx.recv_timeout(Duration::from_secs(10)).unwrap().is_ok());
+        thread.join().unwrap();
+    }
+}
@@ -0,0 +1,285 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Diagnostic output.
+//!
+//! Diagnostics are passed to a single process-wide sink registered with [`set_log_sink`], e.g.
+//! [`StderrLogSink`]. Additionally they are routed to the facades enabled by Cargo features, with
+//! target `rust_kvs`:
+//!   * `log`: [`log`](https://crates.io/crates/log) records, instance ID and key are attached as
+//!     key-values.
+//!   * `tracing`: [`tracing`](https://crates.io/crates/tracing) events, instance ID and key are
+//!     attached as fields.
+//!
+//! Without a registered sink and these features diagnostics are discarded. Sinks require the `std`
+//! feature.
+
+use crate::kvs_storage::InstanceId;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::sync::{Arc, Mutex};
+
+/// Target of emitted diagnostics.
+pub const LOG_TARGET: &str = "rust_kvs";
+
+/// Severity of a diagnostic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum KvsLogLevel {
+    /// Operation failed.
+    Error,
+
+    /// Operation succeeded with unexpected input or state.
+    Warn,
+
+    /// Significant state change.
+    Info,
+
+    /// Details of an operation.
+    Debug,
+}
+
+impl fmt::Display for KvsLogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            KvsLogLevel::Error => "error",
+            KvsLogLevel::Warn => "warning",
+            KvsLogLevel::Info => "info",
+            KvsLogLevel::Debug => "debug",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Receiver of diagnostics.
+pub trait KvsLogSink: Send + Sync {
+    /// Handle diagnostic.
+    ///
+    /// # Parameters
+    ///   * `level`: Severity
+    ///   * `instance_id`: Instance concerned, if any
+    ///   * `key`: Key concerned, if any
+    ///   * `args`: Message
+    fn log(
+        &self,
+        level: KvsLogLevel,
+        instance_id: Option<InstanceId>,
+        key: Option<&str>,
+        args: fmt::Arguments,
+    );
+}
+
+/// Sink printing diagnostics to stderr as `<level>: <message>`.
+#[cfg(feature = "std")]
+pub struct StderrLogSink;
+
+#[cfg(feature = "std")]
+impl KvsLogSink for StderrLogSink {
+    fn log(
+        &self,
+        level: KvsLogLevel,
+        _instance_id: Option<InstanceId>,
+        _key: Option<&str>,
+        args: fmt::Arguments,
+    ) {
+        eprintln!("{level}: {args}");
+    }
+}
+
+/// Registered log sink.
+#[cfg(feature = "std")]
+static LOG_SINK: Mutex<Option<Arc<dyn KvsLogSink>>> = Mutex::new(None);
+
+/// Register process-wide log sink, replacing previous one.
+#[cfg(feature = "std")]
+pub fn set_log_sink(sink: Arc<dyn KvsLogSink>) {
+    if let Ok(mut log_sink) = LOG_SINK.lock() {
+        *log_sink = Some(sink);
+    }
+}
+
+/// Remove registered log sink.
+#[cfg(feature = "std")]
+pub fn clear_log_sink() {
+    if let Ok(mut log_sink) = LOG_SINK.lock() {
+        *log_sink = None;
+    }
+}
+
+/// Emit a diagnostic.
+///
+/// Called by the logging macros of this crate, also from macros expanded in other crates.
+///
+/// # Parameters
+///   * `level`: Severity
+///   * `instance_id`: Instance concerned, if any
+///   * `key`: Key concerned, if any
+///   * `args`: Message
+#[doc(hidden)]
+#[cfg_attr(not(feature = "std"), allow(unused_variables))]
+pub fn log(
+    level: KvsLogLevel,
+    instance_id: Option<InstanceId>,
+    key: Option<&str>,
+    args: fmt::Arguments,
+) {
+    #[cfg(feature = "std")]
+    {
+        // Sink is called without holding the lock.
+        let sink = match LOG_SINK.lock() {
+            Ok(log_sink) => log_sink.clone(),
+            Err(_) => None,
+        };
+        if let Some(sink) = sink {
+            sink.log(level, instance_id, key, args);
+        }
+    }
+
+    #[cfg(feature = "log")]
+    {
+        let log_level = match level {
+            KvsLogLevel::Error => log::Level::Error,
+            KvsLogLevel::Warn => log::Level::Warn,
+            KvsLogLevel::Info => log::Level::Info,
+            KvsLogLevel::Debug => log::Level::Debug,
+        };
+        let instance_id = instance_id.map(|id| id.0);
+        log::log!(target: LOG_TARGET, log_level, instance_id, key; "{args}");
+    }
+
+    #[cfg(feature = "tracing")]
+    {
+        let instance_id = instance_id.map(|id| id.0);
+        match level {
+            KvsLogLevel::Error => {
+                tracing::error!(target: LOG_TARGET, instance_id, key, "{args}")
+            }
+            KvsLogLevel::Warn => tracing::warn!(target: LOG_TARGET, instance_id, key, "{args}"),
+            KvsLogLevel::Info => tracing::info!(target: LOG_TARGET, instance_id, key, "{args}"),
+            KvsLogLevel::Debug => {
+                tracing::debug!(target: LOG_TARGET, instance_id, key, "{args}")
+            }
+        }
+    }
+}
+
+/// Emit a diagnostic with optional `instance_id = ...` and `key = ...` fields before the message.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! kvs_log {
+    ($level:expr, instance_id = $instance_id:expr, key = $key:expr, $($arg:tt)+) => {
+        $crate::kvs_log::log(
+            $level,
+            Some($instance_id),
+            Some(::core::convert::AsRef::<str>::as_ref(&$key)),
+            format_args!($($arg)+),
+        )
+    };
+    ($level:expr, instance_id = $instance_id:expr, $($arg:tt)+) => {
+        $crate::kvs_log::log($level, Some($instance_id), None, format_args!($($arg)+))
+    };
+    ($level:expr, key = $key:expr, $($arg:tt)+) => {
+        $crate::kvs_log::log(
+            $level,
+            None,
+            Some(::core::convert::AsRef::<str>::as_ref(&$key)),
+            format_args!($($arg)+),
+        )
+    };
+    ($level:expr, $($arg:tt)+) => {
+        $crate::kvs_log::log($level, None, None, format_args!($($arg)+))
+    };
+}
+
+/// Emit an error diagnostic.
+macro_rules! kvs_error {
+    ($($arg:tt)+) => {
+        $crate::kvs_log!($crate::kvs_log::KvsLogLevel::Error, $($arg)+)
+    };
+}
+
+/// Emit a warning diagnostic.
+#[cfg(feature = "std")]
+macro_rules! kvs_warn {
+    ($($arg:tt)+) => {
+        $crate::kvs_log!($crate::kvs_log::KvsLogLevel::Warn, $($arg)+)
+    };
+}
+
+/// Emit a debug diagnostic.
+#[cfg(feature = "std")]
+macro_rules! kvs_debug {
+    ($($arg:tt)+) => {
+        $crate::kvs_log!($crate::kvs_log::KvsLogLevel::Debug, $($arg)+)
+    };
+}
+
+pub(crate) use kvs_error;
+#[cfg(feature = "std")]
+pub(crate) use {kvs_debug, kvs_warn};
+
+#[cfg(all(test, feature = "std"))]
+mod kvs_log_tests {
+    use crate::kvs_api::InstanceId;
+    use crate::kvs_log::{clear_log_sink, set_log_sink, KvsLogLevel, KvsLogSink};
+    use std::fmt;
+    use std::sync::{Arc, Mutex};
+
+    /// Level, instance ID, key and message of a diagnostic.
+    type Record = (KvsLogLevel, Option<InstanceId>, Option<String>, String);
+
+    #[derive(Default)]
+    struct RecordingSink {
+        records: Mutex<Vec<Record>>,
+    }
+
+    impl KvsLogSink for RecordingSink {
+        fn log(
+            &self,
+            level: KvsLogLevel,
+            instance_id: Option<InstanceId>,
+            key: Option<&str>,
+            args: fmt::Arguments,
+        ) {
+            self.records.lock().unwrap().push((
+                level,
+                instance_id,
+                key.map(str::to_string),
+                args.to_string(),
+            ));
+        }
+    }
+
+    #[test]
+    fn test_level_display() {
+        assert_eq!(KvsLogLevel::Error.to_string(), "error");
+        assert_eq!(KvsLogLevel::Warn.to_string(), "warning");
+    }
+
+    #[test]
+    fn test_log_sink() {
+        let sink = Arc::new(RecordingSink::default());
+        set_log_sink(sink.clone());
+        let key = "unique_log_sink_key".to_string();
+        kvs_error!(instance_id = InstanceId(3), key = key, "message {}", 1);
+        kvs_error!(key = "unique_log_sink_key", "plain");
+        clear_log_sink();
+
+        // Other tests might log concurrently.
+        let records = sink.records.lock().unwrap();
+        assert!(records.contains(&(
+            KvsLogLevel::Error,
+            Some(InstanceId(3)),
+            Some(key.clone()),
+            "message 1".to_string()
+        )));
+        assert!(records.contains(&(KvsLogLevel::Error, None, Some(key), "plain".to_string())));
+    }
+}
@@ -0,0 +1,228 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Per-key merging of concurrently flushed instances.
+//!
+//! Handles of the same instance within a process share their data, but processes opening the
+//! same instance don't. By default a flush replaces the stored KVS, dropping changes flushed by
+//! other processes in the meantime. With a merge policy set via
+//! [`GenericKvsBuilder::merge_on_flush`](crate::kvs_builder::GenericKvsBuilder::merge_on_flush),
+//! the stored KVS is reloaded while the working directory is locked and merged per key:
+//!   * Keys not changed by this instance since it was loaded or last flushed take the stored
+//!     value, including removals.
+//!   * Keys changed by this instance take the local value if the stored value is unchanged or
+//!     equal.
+//!   * Otherwise the key is in conflict, which is resolved by the policy.
+//!
+//! The merged data becomes the data of the instance.
+
+use crate::kvs_value::{KvsMap, KvsValue};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::Arc;
+
+/// Conflict resolution function.
+///
+/// Called with key, stored value and local value, `None` if the key was removed. Returns the
+/// merged value, `None` to remove the key.
+pub type KvsConflictFn =
+    dyn Fn(&str, Option<&KvsValue>, Option<&KvsValue>) -> Option<KvsValue> + Send + Sync;
+
+/// Conflict resolver used by [`KvsMergePolicy::Resolve`].
+///
+/// Resolvers are equal if they share the same function.
+#[derive(Clone)]
+pub struct KvsConflictResolver(pub Arc<KvsConflictFn>);
+
+impl KvsConflictResolver {
+    /// Create a resolver from a function.
+    ///
+    /// # Parameters
+    ///   * `resolve`: Resolution function, see [`KvsConflictFn`]
+    pub fn new<F>(resolve: F) -> Self
+    where
+        F: Fn(&str, Option<&KvsValue>, Option<&KvsValue>) -> Option<KvsValue>
+            + Send
+            + Sync
+            + 'static,
+    {
+        Self(Arc::new(resolve))
+    }
+}
+
+impl PartialEq for KvsConflictResolver {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl fmt::Debug for KvsConflictResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("KvsConflictResolver")
+    }
+}
+
+/// Handling of changes flushed by other processes.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum KvsMergePolicy {
+    /// Stored KVS is replaced.
+    #[default]
+    Overwrite,
+
+    /// Stored KVS is merged, conflicts are resolved with the local value.
+    LastWriterWins,
+
+    /// Stored KVS is merged, conflicts are resolved by the resolver.
+    Resolve(KvsConflictResolver),
+}
+
+/// Merge local changes into the stored KVS.
+///
+/// # Parameters
+///   * `base`: Data as loaded or last flushed by this instance
+///   * `local`: Current data of this instance
+///   * `stored`: Currently stored data
+///   * `policy`: Merge policy, `KvsMergePolicy::Overwrite` returns `local`
+///
+/// # Return Values
+///   * Merged data
+pub(crate) fn merge(
+    base: &KvsMap,
+    local: &KvsMap,
+    stored: &KvsMap,
+    policy: &KvsMergePolicy,
+) -> KvsMap {
+    if *policy == KvsMergePolicy::Overwrite {
+        return local.clone();
+    }
+
+    let keys: BTreeSet<&String> = base
+        .keys()
+        .chain(local.keys())
+        .chain(stored.keys())
+        .collect();
+    let mut merged = KvsMap::new();
+    for key in keys {
+        let base_value = base.get(key);
+        let local_value = local.get(key);
+        let stored_value = stored.get(key);
+
+        let value = if local_value == base_value {
+            stored_value.cloned()
+        } else if stored_value == base_value || stored_value == local_value {
+            local_value.cloned()
+        } else {
+            match policy {
+                KvsMergePolicy::Resolve(resolver) => (resolver.0)(key, stored_value, local_value),
+                _ => local_value.cloned(),
+            }
+        };
+        if let Some(value) = value {
+            merged.insert(key.clone(), value);
+        }
+    }
+    merged
+}
+
+#[cfg(all(test, feature = "json-backend"))]
+mod kvs_merge_tests {
+    use crate::kvs_api::{InstanceId, KvsApi};
+    use crate::kvs_builder::kvs_builder_tests::lock_and_reset;
+    use crate::kvs_merge::{merge, KvsConflictResolver, KvsMergePolicy};
+    use crate::kvs_value::{KvsMap, KvsValue};
+    use crate::KvsBuilder;
+    use tempfile::tempdir;
+
+    fn map(entries: &[(&str, i32)]) -> KvsMap {
+        entries
+            .iter()
+            .map(|(key, value)| (key.to_string(), KvsValue::I32(*value)))
+            .collect()
+    }
+
+    #[test]
+    fn test_merge_overwrite() {
+        let base = map(&[("a", 1)]);
+        let local = map(&[("a", 2)]);
+        let stored = map(&[("a", 1), ("b", 1)]);
+        assert_eq!(
+            merge(&base, &local, &stored, &KvsMergePolicy::Overwrite),
+            local
+        );
+    }
+
+    #[test]
+    fn test_merge_last_writer_wins() {
+        let base = map(&[("unchanged", 1), ("local", 1), ("stored", 1), ("both", 1)]);
+        let local = map(&[("unchanged", 1), ("local", 2), ("stored", 1), ("both", 2)]);
+        let stored = map(&[("local", 1), ("stored", 3), ("both", 3), ("new", 3)]);
+
+        assert_eq!(
+            merge(&base, &local, &stored, &KvsMergePolicy::LastWriterWins),
+            map(&[("local", 2), ("stored", 3), ("both", 2), ("new", 3)])
+        );
+    }
+
+    #[test]
+    fn test_merge_resolve() {
+        let base = map(&[("both", 1), ("removed", 1)]);
+        let local = map(&[("both", 2)]);
+        let stored = map(&[("both", 3), ("removed", 3)]);
+        let resolver = KvsConflictResolver::new(|_key, stored, local| match (stored, local) {
+            (Some(KvsValue::I32(s)), Some(KvsValue::I32(l))) => Some(KvsValue::I32(s + l)),
+            (stored, _) => stored.cloned(),
+        });
+
+        assert_eq!(
+            merge(&base, &local, &stored, &KvsMergePolicy::Resolve(resolver)),
+            map(&[("both", 5), ("removed", 3)])
+        );
+    }
+
+    #[test]
+    fn test_merge_on_flush() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+
+        let kvs = KvsBuilder::new(InstanceId(0))
+            .dir(dir_string.clone())
+            .merge_on_flush(KvsMergePolicy::LastWriterWins)
+            .build()
+            .unwrap();
+        kvs.set_value("first", 1).unwrap();
+        kvs.flush().unwrap();
+
+        // Another process flushes the instance in the meantime.
+        let other = KvsBuilder::new(InstanceId(0))
+            .dir(dir_string)
+            .force_reopen()
+            .build()
+            .unwrap();
+        other.set_value("second", 2).unwrap();
+        other.flush().unwrap();
+
+        kvs.set_value("third", 3).unwrap();
+        kvs.flush().unwrap();
+        assert_eq!(kvs.get_value_as::<i32>("second").unwrap(), 2);
+        assert_eq!(kvs.get_value_as::<i32>("third").unwrap(), 3);
+        assert!(!kvs.is_dirty().unwrap());
+
+        let stored = KvsBuilder::new(InstanceId(0))
+            .dir(dir.path().to_string_lossy().to_string())
+            .force_reopen()
+            .build()
+            .unwrap();
+        let mut keys = stored.get_all_keys().unwrap();
+        keys.sort();
+        assert_eq!(keys, ["first", "second", "third"]);
+    }
+}
@@ -0,0 +1,194 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Metrics of KVS operations.
+//!
+//! A receiver is set per handle with
+//! [`GenericKvsBuilder::metrics`](crate::kvs_builder::GenericKvsBuilder::metrics). Unlike
+//! [`kvs_event`](crate::kvs_event), measurements are reported for every operation and are meant
+//! to be aggregated by the receiver, e.g. for export to health monitoring.
+//!
+//! All methods have empty default implementations, a receiver implements the ones it needs.
+//! Methods are called synchronously from the thread performing the operation, partly while the
+//! instance is locked, and must not call back into the instance.
+
+use crate::error_code::ErrorCode;
+use crate::kvs_api::{InstanceId, SnapshotId};
+use std::time::Duration;
+
+/// Receiver of KVS measurements.
+pub trait KvsMetrics: Send + Sync {
+    /// Value was read successfully, including reads returning the default value.
+    ///
+    /// # Parameters
+    ///   * `instance_id`: Instance ID
+    ///   * `key`: Key read
+    ///   * `latency`: Duration of the read, including waiting for the instance lock
+    fn on_get(&self, _instance_id: InstanceId, _key: &str, _latency: Duration) {}
+
+    /// Value was written successfully.
+    ///
+    /// # Parameters
+    ///   * `instance_id`: Instance ID
+    ///   * `key`: Key written
+    ///   * `latency`: Duration of the write, including waiting for the instance lock
+    fn on_set(&self, _instance_id: InstanceId, _key: &str, _latency: Duration) {}
+
+    /// Data was flushed successfully.
+    ///
+    /// # Parameters
+    ///   * `instance_id`: Instance ID
+    ///   * `duration`: Duration of the flush, including snapshot rotation
+    ///   * `bytes_written`: Serialized size of the written data, before compression
+    fn on_flush(&self, _instance_id: InstanceId, _duration: Duration, _bytes_written: usize) {}
+
+    /// Snapshots were rotated before a flush.
+    ///
+    /// # Parameters
+    ///   * `instance_id`: Instance ID
+    fn on_snapshot_rotation(&self, _instance_id: InstanceId) {}
+
+    /// Stored data failed hash validation or integrity check.
+    ///
+    /// # Parameters
+    ///   * `instance_id`: Instance ID
+    ///   * `snapshot_id`: Snapshot checked
+    ///   * `error`: `ErrorCode::ValidationFailed` or `ErrorCode::IntegrityCorrupted`
+    fn on_validation_failure(
+        &self,
+        _instance_id: InstanceId,
+        _snapshot_id: SnapshotId,
+        _error: &ErrorCode,
+    ) {
+    }
+}
+
+#[cfg(all(test, feature = "json-backend"))]
+pub(crate) mod kvs_metrics_tests {
+    use crate::error_code::ErrorCode;
+    use crate::kvs_api::{InstanceId, KvsApi, KvsLoad, SnapshotId};
+    use crate::kvs_builder::kvs_builder_tests::lock_and_reset;
+    use crate::kvs_metrics::KvsMetrics;
+    use crate::KvsBuilder;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    /// Recorded measurement, durations are omitted.
+    #[derive(Debug, PartialEq)]
+    enum Measurement {
+        Get(String),
+        Set(String),
+        Flush(usize),
+        SnapshotRotation,
+        ValidationFailure(SnapshotId, ErrorCode),
+    }
+
+    #[derive(Default)]
+    struct RecordingMetrics {
+        measurements: Mutex<Vec<Measurement>>,
+    }
+
+    impl RecordingMetrics {
+        fn record(&self, measurement: Measurement) {
+            self.measurements.lock().unwrap().push(measurement);
+        }
+    }
+
+    impl KvsMetrics for RecordingMetrics {
+        fn on_get(&self, _instance_id: InstanceId, key: &str, _latency: Duration) {
+            self.record(Measurement::Get(key.to_string()));
+        }
+
+        fn on_set(&self, _instance_id: InstanceId, key: &str, _latency: Duration) {
+            self.record(Measurement::Set(key.to_string()));
+        }
+
+        fn on_flush(&self, _instance_id: InstanceId, _duration: Duration, bytes_written: usize) {
+            self.record(Measurement::Flush(bytes_written));
+        }
+
+        fn on_snapshot_rotation(&self, _instance_id: InstanceId) {
+            self.record(Measurement::SnapshotRotation);
+        }
+
+        fn on_validation_failure(
+            &self,
+            _instance_id: InstanceId,
+            snapshot_id: SnapshotId,
+            error: &ErrorCode,
+        ) {
+            self.record(Measurement::ValidationFailure(snapshot_id, error.clone()));
+        }
+    }
+
+    #[cfg(feature = "snapshots")]
+    #[test]
+    fn test_metrics_operations() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+        let metrics = Arc::new(RecordingMetrics::default());
+
+        let kvs = KvsBuilder::new(InstanceId(0))
+            .dir(dir_string)
+            .metrics(metrics.clone())
+            .build()
+            .unwrap();
+        kvs.set_value("key", "value").unwrap();
+        kvs.get_value("key").unwrap();
+        let _ = kvs.get_value("missing");
+        kvs.flush().unwrap();
+
+        assert_eq!(
+            *metrics.measurements.lock().unwrap(),
+            vec![
+                Measurement::Set("key".to_string()),
+                Measurement::Get("key".to_string()),
+                Measurement::SnapshotRotation,
+                Measurement::Flush(kvs.storage_usage().unwrap()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_metrics_validation_failure() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+
+        let kvs = KvsBuilder::new(InstanceId(0))
+            .dir(dir_string.clone())
+            .build()
+            .unwrap();
+        kvs.set_value("key", "value").unwrap();
+        kvs.flush().unwrap();
+        let hash_path = kvs.get_hash_filename(SnapshotId(0)).unwrap();
+        kvs.close().unwrap();
+        std::fs::write(hash_path, [0u8; 4]).unwrap();
+
+        let metrics = Arc::new(RecordingMetrics::default());
+        let result = KvsBuilder::new(InstanceId(0))
+            .dir(dir_string)
+            .kvs_load(KvsLoad::Required)
+            .metrics(metrics.clone())
+            .build();
+
+        assert!(result.is_err_and(|e| e == ErrorCode::ValidationFailed));
+        assert_eq!(
+            *metrics.measurements.lock().unwrap(),
+            vec![Measurement::ValidationFailure(
+                SnapshotId(0),
+                ErrorCode::ValidationFailed
+            )]
+        );
+    }
+}
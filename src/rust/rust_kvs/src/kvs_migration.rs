@@ -0,0 +1,282 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Format version of stored JSON files and migration of older files.
+//!
+//! The JSON backend writes [`KVS_FORMAT_VERSION`] into the root object of every file, next to the
+//! type tag: `{"__kvs_version":1,"t":"obj","v":{...}}`. Files without the field were written
+//! before versioning was introduced and use format version 1.
+//!
+//! Loading a file with an older version runs the registered [`KvsMigration`]s, each upgrading the
+//! file content by one version, until the current version is reached. Loading fails with
+//! `ErrorCode::UnsupportedVersion` if a migration is missing or the file is newer than supported.
+//!
+//! Format history:
+//!   * 1: Initial format.
+//!   * 2: `i64` and `u64` values are stored as decimal strings, e.g. `{"t":"u64","v":"42"}`, to
+//!     preserve values above 2^53. Version 1 files are read without migration, as numbers are
+//!     still accepted for these types.
+//!
+//! The C++ implementation skips the field and rejects unsupported versions without migrating,
+//! its supported version range in `src/cpp/src/kvs.cpp` is updated with [`KVS_FORMAT_VERSION`].
+//!
+//! Migrations are registered process-wide with [`register_migration`].
+//!
+//! Renamed keys are migrated per instance, see
+//! [`GenericKvsBuilder::key_aliases`](crate::kvs_builder::GenericKvsBuilder::key_aliases).
+
+use crate::error_code::ErrorCode;
+use crate::kvs_lazy::LazyKvsMap;
+use crate::kvs_log::kvs_debug;
+#[cfg(feature = "json-backend")]
+use crate::kvs_log::kvs_error;
+use crate::kvs_value::KvsMap;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Current format version of stored JSON files.
+///
+/// Feature: `FEAT_REQ__KVS__versioning`
+pub const KVS_FORMAT_VERSION: u32 = 2;
+
+/// Oldest format version that is read without registered migrations.
+#[cfg(feature = "json-backend")]
+const DIRECT_READ_VERSION: u32 = 1;
+
+/// Name of the version field in the root object of stored JSON files.
+pub const VERSION_FIELD: &str = "__kvs_version";
+
+/// Upgrade of stored JSON files from an older format version.
+pub trait KvsMigration: Send + Sync {
+    /// Format version of the files upgraded by this migration.
+    fn source_version(&self) -> u32;
+
+    /// Upgrade file content from `source_version()` to `source_version() + 1`.
+    ///
+    /// # Parameters
+    ///   * `json`: Content of the file in format `source_version()`
+    ///
+    /// # Return Values
+    ///   * Ok: Content in format `source_version() + 1`
+    ///   * Err: Migration failed, loading the file fails with the returned error
+    fn migrate(&self, json: &str) -> Result<String, ErrorCode>;
+}
+
+/// Registered migrations.
+static MIGRATIONS: Mutex<Vec<Arc<dyn KvsMigration>>> = Mutex::new(Vec::new());
+
+/// Register process-wide migration, replacing a previous one for the same version.
+pub fn register_migration(migration: Arc<dyn KvsMigration>) {
+    if let Ok(mut migrations) = MIGRATIONS.lock() {
+        migrations.retain(|m| m.source_version() != migration.source_version());
+        migrations.push(migration);
+    }
+}
+
+/// Remove all registered migrations.
+pub fn clear_migrations() {
+    if let Ok(mut migrations) = MIGRATIONS.lock() {
+        migrations.clear();
+    }
+}
+
+/// Upgrade file content to the current format version.
+///
+/// # Parameters
+///   * `json`: File content
+///   * `version`: Format version of the file
+///
+/// # Return Values
+///   * Ok: Content readable as current format version, unchanged if no migration was needed
+///   * `ErrorCode::UnsupportedVersion`: Newer version or no migration registered
+///   * Err: Error returned by a migration
+#[cfg(feature = "json-backend")]
+pub(crate) fn upgrade(json: String, version: u32) -> Result<String, ErrorCode> {
+    if version > KVS_FORMAT_VERSION {
+        kvs_error!("format version {version} is newer than supported {KVS_FORMAT_VERSION}");
+        return Err(ErrorCode::UnsupportedVersion);
+    }
+
+    // Migrations are called without holding the lock.
+    let migrations = match MIGRATIONS.lock() {
+        Ok(migrations) => migrations.clone(),
+        Err(_) => return Err(ErrorCode::MutexLockFailed),
+    };
+    let mut json = json;
+    for version in version..KVS_FORMAT_VERSION {
+        match migrations.iter().find(|m| m.source_version() == version) {
+            Some(migration) => json = migration.migrate(&json)?,
+            // Content is read as is.
+            None if version >= DIRECT_READ_VERSION => {}
+            None => {
+                kvs_error!("no migration from format version {version} registered");
+                return Err(ErrorCode::UnsupportedVersion);
+            }
+        }
+    }
+    Ok(json)
+}
+
+/// Move values stored under legacy keys to their new keys.
+///
+/// Legacy values are dropped if the new key is stored as well.
+///
+/// # Parameters
+///   * `kvs_map`: Loaded values
+///   * `lazy`: Loaded values not parsed yet
+///   * `aliases`: New key by legacy key
+///
+/// # Return Values
+///   * Ok: `true` if any legacy key was stored
+///   * `ErrorCode::JsonParserError`: A lazily loaded legacy value couldn't be parsed
+pub(crate) fn rename_keys(
+    kvs_map: &mut KvsMap,
+    mut lazy: Option<&mut LazyKvsMap>,
+    aliases: &HashMap<String, String>,
+) -> Result<bool, ErrorCode> {
+    let mut renamed = false;
+    for (legacy_key, key) in aliases {
+        let value = match kvs_map.remove(legacy_key) {
+            Some(value) => Some(value),
+            None => match lazy.as_deref_mut() {
+                Some(lazy) => lazy.take(legacy_key)?,
+                None => None,
+            },
+        };
+        let Some(value) = value else {
+            continue;
+        };
+        renamed = true;
+        if kvs_map.contains_key(key) || lazy.as_deref().is_some_and(|lazy| lazy.contains_key(key)) {
+            kvs_debug!(
+                key = key,
+                "legacy key {legacy_key} dropped, {key} is stored"
+            );
+        } else {
+            kvs_debug!(key = key, "legacy key {legacy_key} renamed to {key}");
+            kvs_map.insert(key.clone(), value);
+        }
+    }
+    Ok(renamed)
+}
+
+#[cfg(test)]
+pub(crate) mod kvs_migration_tests {
+    #[cfg(feature = "json-backend")]
+    use crate::error_code::ErrorCode;
+    use crate::kvs_migration::rename_keys;
+    #[cfg(feature = "json-backend")]
+    use crate::kvs_migration::{
+        clear_migrations, register_migration, upgrade, KvsMigration, KVS_FORMAT_VERSION,
+    };
+    use crate::kvs_value::{KvsMap, KvsValue};
+    use std::collections::HashMap;
+    #[cfg(feature = "json-backend")]
+    use std::sync::{Arc, LazyLock, Mutex, MutexGuard};
+
+    /// Migrations are process-wide, tests registering them are executed serially.
+    #[cfg(feature = "json-backend")]
+    static SERIAL_TEST: LazyLock<Mutex<()>> = LazyLock::new(|| Mutex::new(()));
+
+    #[cfg(feature = "json-backend")]
+    pub(crate) fn lock_and_clear<'a>() -> MutexGuard<'a, ()> {
+        let serial_lock = SERIAL_TEST.lock().unwrap_or_else(|e| e.into_inner());
+        clear_migrations();
+        serial_lock
+    }
+
+    /// Migration appending its version to the content.
+    #[cfg(feature = "json-backend")]
+    struct AppendMigration(u32);
+
+    #[cfg(feature = "json-backend")]
+    impl KvsMigration for AppendMigration {
+        fn source_version(&self) -> u32 {
+            self.0
+        }
+
+        fn migrate(&self, json: &str) -> Result<String, ErrorCode> {
+            Ok(format!("{json}{}", self.0))
+        }
+    }
+
+    #[cfg(feature = "json-backend")]
+    #[test]
+    fn test_upgrade_current() {
+        let _lock = lock_and_clear();
+        assert_eq!(
+            upgrade("content".to_string(), KVS_FORMAT_VERSION).unwrap(),
+            "content"
+        );
+    }
+
+    #[cfg(feature = "json-backend")]
+    #[test]
+    fn test_upgrade_migration() {
+        let _lock = lock_and_clear();
+        register_migration(Arc::new(AppendMigration(0)));
+        assert_eq!(upgrade("content".to_string(), 0).unwrap(), "content0");
+        clear_migrations();
+    }
+
+    #[cfg(feature = "json-backend")]
+    #[test]
+    fn test_upgrade_direct_read() {
+        let _lock = lock_and_clear();
+        assert_eq!(upgrade("content".to_string(), 1).unwrap(), "content");
+
+        // Registered migration takes precedence.
+        register_migration(Arc::new(AppendMigration(1)));
+        assert_eq!(upgrade("content".to_string(), 1).unwrap(), "content1");
+        clear_migrations();
+    }
+
+    #[cfg(feature = "json-backend")]
+    #[test]
+    fn test_upgrade_missing_migration() {
+        let _lock = lock_and_clear();
+        assert!(
+            upgrade("content".to_string(), 0).is_err_and(|e| e == ErrorCode::UnsupportedVersion)
+        );
+    }
+
+    #[cfg(feature = "json-backend")]
+    #[test]
+    fn test_upgrade_newer_version() {
+        let _lock = lock_and_clear();
+        assert!(upgrade("content".to_string(), KVS_FORMAT_VERSION + 1)
+            .is_err_and(|e| e == ErrorCode::UnsupportedVersion));
+    }
+
+    #[test]
+    fn test_rename_keys() {
+        let aliases = HashMap::from([
+            ("old".to_string(), "new".to_string()),
+            ("legacy".to_string(), "kept".to_string()),
+        ]);
+        let mut kvs_map = KvsMap::from([
+            ("old".to_string(), KvsValue::I32(1)),
+            ("legacy".to_string(), KvsValue::I32(2)),
+            ("kept".to_string(), KvsValue::I32(3)),
+        ]);
+        assert!(rename_keys(&mut kvs_map, None, &aliases).unwrap());
+        assert_eq!(
+            kvs_map,
+            KvsMap::from([
+                ("new".to_string(), KvsValue::I32(1)),
+                ("kept".to_string(), KvsValue::I32(3)),
+            ])
+        );
+
+        // Nothing to rename.
+        assert!(!rename_keys(&mut kvs_map, None, &aliases).unwrap());
+    }
+}
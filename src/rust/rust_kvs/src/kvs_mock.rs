@@ -10,31 +10,59 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::error_code::ErrorCode;
-use crate::kvs_api::{KvsApi, SnapshotId};
-use crate::kvs_value::{KvsMap, KvsValue};
+use crate::kvs_api::{glob_match, FlushOnExit, KvsApi, SnapshotId};
+use crate::kvs_transaction::{self, KvsTransaction};
+use crate::kvs_value::{KvsDeserialize, KvsMap, KvsSerialize, KvsValue, ValueModel};
+use std::marker::PhantomData;
 use std::sync::{Arc, Mutex};
 
 #[derive(Clone)]
-pub struct MockKvs {
+pub struct MockKvs<Model: ValueModel = KvsValue> {
     pub map: Arc<Mutex<KvsMap>>,
     pub fail: bool,
+    pub flush_on_exit: FlushOnExit,
+    _model_marker: PhantomData<Model>,
 }
 
 impl Default for MockKvs {
     fn default() -> Self {
         let map = Arc::new(Mutex::new(KvsMap::new()));
-        Self { map, fail: false }
+        Self {
+            map,
+            fail: false,
+            flush_on_exit: FlushOnExit::No,
+            _model_marker: PhantomData,
+        }
     }
 }
 
 impl MockKvs {
     pub fn new(kvs_map: KvsMap, fail: bool) -> Result<Self, ErrorCode> {
         let map = Arc::new(Mutex::new(kvs_map));
-        Ok(MockKvs { map, fail })
+        Ok(MockKvs {
+            map,
+            fail,
+            flush_on_exit: FlushOnExit::No,
+            _model_marker: PhantomData,
+        })
+    }
+}
+
+impl<Model: ValueModel> MockKvs<Model> {
+    /// Convert into a mock exposing another value model, sharing the same map.
+    pub fn with_model<Other: ValueModel>(self) -> MockKvs<Other> {
+        MockKvs {
+            map: self.map,
+            fail: self.fail,
+            flush_on_exit: self.flush_on_exit,
+            _model_marker: PhantomData,
+        }
     }
 }
 
-impl KvsApi for MockKvs {
+impl<Model: ValueModel> KvsApi for MockKvs<Model> {
+    type Value = Model;
+
     fn reset(&self) -> Result<(), ErrorCode> {
         if self.fail {
             return Err(ErrorCode::UnmappedError);
@@ -60,6 +88,32 @@ impl KvsApi for MockKvs {
         }
         Ok(self.map.lock().unwrap().keys().cloned().collect())
     }
+    fn get_keys_with_prefix(&self, prefix: &str) -> Result<Vec<String>, ErrorCode> {
+        if self.fail {
+            return Err(ErrorCode::UnmappedError);
+        }
+        Ok(self
+            .map
+            .lock()
+            .unwrap()
+            .keys()
+            .filter(|key| key.starts_with(prefix))
+            .cloned()
+            .collect())
+    }
+    fn get_keys_matching(&self, pattern: &str) -> Result<Vec<String>, ErrorCode> {
+        if self.fail {
+            return Err(ErrorCode::UnmappedError);
+        }
+        Ok(self
+            .map
+            .lock()
+            .unwrap()
+            .keys()
+            .filter(|key| glob_match(pattern, key))
+            .cloned()
+            .collect())
+    }
     fn key_exists(&self, key: &str) -> Result<bool, ErrorCode> {
         if self.fail {
             return Err(ErrorCode::UnmappedError);
@@ -77,16 +131,21 @@ impl KvsApi for MockKvs {
             .cloned()
             .ok_or(ErrorCode::KeyNotFound)
     }
-    fn get_value_as<T>(&self, key: &str) -> Result<T, ErrorCode>
-    where
-        for<'a> T: TryFrom<&'a KvsValue> + Clone,
-        for<'a> <T as TryFrom<&'a KvsValue>>::Error: std::fmt::Debug,
-    {
+    fn get_values(&self, keys: &[&str]) -> Result<Vec<KvsValue>, ErrorCode> {
+        if self.fail {
+            return Err(ErrorCode::UnmappedError);
+        }
+        let map = self.map.lock().unwrap();
+        keys.iter()
+            .map(|key| map.get(*key).cloned().ok_or(ErrorCode::KeyNotFound))
+            .collect()
+    }
+    fn get_value_as<T: KvsDeserialize>(&self, key: &str) -> Result<T, ErrorCode> {
         if self.fail {
             return Err(ErrorCode::UnmappedError);
         }
         let v = self.get_value(key)?;
-        T::try_from(&v).map_err(|_| ErrorCode::ConversionFailed)
+        T::from_kvs_value(&v)
     }
     fn get_default_value(&self, _key: &str) -> Result<KvsValue, ErrorCode> {
         if self.fail {
@@ -100,7 +159,7 @@ impl KvsApi for MockKvs {
         }
         Ok(false)
     }
-    fn set_value<S: Into<String>, V: Into<KvsValue>>(
+    fn set_value<S: Into<String>, V: KvsSerialize>(
         &self,
         key: S,
         value: V,
@@ -108,7 +167,20 @@ impl KvsApi for MockKvs {
         if self.fail {
             return Err(ErrorCode::UnmappedError);
         }
-        self.map.lock().unwrap().insert(key.into(), value.into());
+        self.map
+            .lock()
+            .unwrap()
+            .insert(key.into(), value.to_kvs_value());
+        Ok(())
+    }
+    fn set_values<I: IntoIterator<Item = (String, KvsValue)>>(
+        &self,
+        values: I,
+    ) -> Result<(), ErrorCode> {
+        if self.fail {
+            return Err(ErrorCode::UnmappedError);
+        }
+        self.map.lock().unwrap().extend(values);
         Ok(())
     }
     fn remove_key(&self, key: &str) -> Result<(), ErrorCode> {
@@ -118,6 +190,40 @@ impl KvsApi for MockKvs {
         self.map.lock().unwrap().remove(key);
         Ok(())
     }
+    fn remove_keys(&self, keys: &[&str]) -> Result<(), ErrorCode> {
+        if self.fail {
+            return Err(ErrorCode::UnmappedError);
+        }
+        let mut map = self.map.lock().unwrap();
+        for key in keys {
+            map.remove(*key);
+        }
+        Ok(())
+    }
+    fn remove_keys_with_prefix(&self, prefix: &str) -> Result<usize, ErrorCode> {
+        if self.fail {
+            return Err(ErrorCode::UnmappedError);
+        }
+        let mut map = self.map.lock().unwrap();
+        let len = map.len();
+        map.retain(|key, _| !key.starts_with(prefix));
+        Ok(len - map.len())
+    }
+    fn transaction<R, F>(&self, f: F) -> Result<R, ErrorCode>
+    where
+        F: FnOnce(&mut KvsTransaction) -> Result<R, ErrorCode>,
+    {
+        if self.fail {
+            return Err(ErrorCode::UnmappedError);
+        }
+        let mut map = self.map.lock().unwrap();
+        let defaults_map = KvsMap::new();
+        let mut txn = KvsTransaction::new(&map, &defaults_map);
+        let result = f(&mut txn)?;
+        let (changes, _reads) = txn.into_parts();
+        kvs_transaction::apply_changes(&mut map, changes);
+        Ok(result)
+    }
     fn flush(&self) -> Result<(), ErrorCode> {
         if self.fail {
             return Err(ErrorCode::UnmappedError);
@@ -151,6 +257,12 @@ impl KvsApi for MockKvs {
         }
         Err(ErrorCode::FileNotFound)
     }
+    fn flush_on_exit(&self) -> FlushOnExit {
+        self.flush_on_exit
+    }
+    fn set_flush_on_exit(&mut self, flush_on_exit: FlushOnExit) {
+        self.flush_on_exit = flush_on_exit;
+    }
 }
 
 #[cfg(test)]
@@ -170,9 +282,28 @@ mod tests {
         assert!(kvs.key_exists("a").unwrap());
         assert!(kvs.remove_key("a").is_ok());
         assert!(!kvs.key_exists("a").unwrap());
+        kvs.transaction(|txn| {
+            txn.set_value("b", 2.0);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(kvs.get_value("b").unwrap(), KvsValue::from(2.0));
+        assert_eq!(
+            kvs.get_keys_with_prefix("b").unwrap(),
+            vec!["b".to_string()]
+        );
+        assert_eq!(kvs.get_keys_matching("?").unwrap(), vec!["b".to_string()]);
+        assert_eq!(kvs.remove_keys_with_prefix("b").unwrap(), 1);
+        kvs.set_values([("c".to_string(), KvsValue::from(3.0))])
+            .unwrap();
+        assert_eq!(kvs.get_values(&["c"]).unwrap(), vec![KvsValue::from(3.0)]);
+        assert!(kvs.remove_keys(&["c"]).is_ok());
         assert_eq!(kvs.snapshot_count(), 0);
         assert!(kvs.flush().is_ok());
         assert!(kvs.reset().is_ok());
+        let mut kvs = kvs;
+        kvs.set_flush_on_exit(FlushOnExit::Yes);
+        assert_eq!(kvs.flush_on_exit(), FlushOnExit::Yes);
 
         // Failure case
         let kvs_fail = MockKvs {
@@ -183,7 +314,14 @@ mod tests {
         assert!(kvs_fail.get_value("a").is_err());
         assert!(kvs_fail.get_all_keys().is_err());
         assert!(kvs_fail.key_exists("a").is_err());
+        assert!(kvs_fail.transaction(|_| Ok(())).is_err());
         assert!(kvs_fail.remove_key("a").is_err());
+        assert!(kvs_fail.get_keys_with_prefix("a").is_err());
+        assert!(kvs_fail.get_keys_matching("a*").is_err());
+        assert!(kvs_fail.remove_keys_with_prefix("a").is_err());
+        assert!(kvs_fail.set_values([]).is_err());
+        assert!(kvs_fail.get_values(&["a"]).is_err());
+        assert!(kvs_fail.remove_keys(&["a"]).is_err());
         assert_eq!(kvs_fail.snapshot_count(), 9999);
         assert!(kvs_fail.flush().is_err());
         assert!(kvs_fail.reset().is_err());
@@ -0,0 +1,589 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Writes spanning multiple KVS instances with an all-or-nothing flush.
+//!
+//! Writes are collected in a [`MultiKvsWrite`] and applied by [`MultiKvsWrite::commit`] in two
+//! phases:
+//!   1. Stage: the resulting map of every instance is saved into temporary files next to the
+//!      snapshot files, with the compression of the instance. Any failure removes all staged
+//!      files, no instance is changed.
+//!   2. Switch: snapshots of every instance are rotated and the staged files are moved into
+//!      place, then synced according to the sync policy of the instance. Only moves and syncs are
+//!      performed in this phase.
+//!
+//! Flush hooks of every instance are called before staging and after the switch, like for a flush
+//! of the instance.
+//!
+//! The in-memory data of all instances is updated after the switch and their journals are removed,
+//! the written snapshots contain the journaled changes. If switching fails, instances switched
+//! before keep the written data and their in-memory data is updated as well, so memory and storage
+//! never disagree. All involved instances are locked for the whole commit, other handles never
+//! observe a partially applied write set. The working directories are locked with [`KvsDirLock`]
+//! from staging until the switch is complete.
+
+use crate::error_code::ErrorCode;
+use crate::kvs::{FlushHooks, GenericKvs};
+use crate::kvs_api::SnapshotId;
+use crate::kvs_backend::{KvsBackend, KvsPathResolver};
+use crate::kvs_lock::KvsDirLock;
+use crate::kvs_log::kvs_error;
+use crate::kvs_value::{KvsMap, KvsSerialize, KvsValue};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Single write operation.
+enum WriteOp {
+    Set(String, KvsValue),
+    Remove(String),
+}
+
+/// Write operations of one instance.
+struct InstanceWrites<'a, Backend: KvsBackend, PathResolver: KvsPathResolver> {
+    kvs: &'a GenericKvs<Backend, PathResolver>,
+    ops: Vec<WriteOp>,
+}
+
+/// Staged files of one instance.
+struct StagedFiles {
+    kvs_path: PathBuf,
+    hash_path: PathBuf,
+    staged_kvs_path: PathBuf,
+    staged_hash_path: PathBuf,
+}
+
+impl StagedFiles {
+    /// Remove staged files, errors are ignored as the files might not exist.
+    fn remove<Backend: KvsBackend>(&self) {
+        let _ = Backend::remove_kvs(&self.staged_kvs_path, &self.staged_hash_path);
+    }
+}
+
+/// Staged file path, the extension is kept to satisfy backend checks.
+fn staged_path(path: &Path) -> PathBuf {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(".staged.{file_name}"))
+}
+
+/// Set of writes spanning multiple KVS instances.
+pub struct MultiKvsWrite<'a, Backend: KvsBackend, PathResolver: KvsPathResolver = Backend> {
+    writes: Vec<InstanceWrites<'a, Backend, PathResolver>>,
+}
+
+impl<Backend: KvsBackend, PathResolver: KvsPathResolver> Default
+    for MultiKvsWrite<'_, Backend, PathResolver>
+{
+    fn default() -> Self {
+        Self { writes: Vec::new() }
+    }
+}
+
+impl<'a, Backend: KvsBackend, PathResolver: KvsPathResolver>
+    MultiKvsWrite<'a, Backend, PathResolver>
+{
+    /// Create an empty write set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get operations of an instance, handles sharing the same instance data are grouped.
+    fn instance_ops(&mut self, kvs: &'a GenericKvs<Backend, PathResolver>) -> &mut Vec<WriteOp> {
+        let idx = match self
+            .writes
+            .iter()
+            .position(|w| Arc::ptr_eq(&w.kvs.data, &kvs.data))
+        {
+            Some(idx) => idx,
+            None => {
+                self.writes.push(InstanceWrites {
+                    kvs,
+                    ops: Vec::new(),
+                });
+                self.writes.len() - 1
+            }
+        };
+        &mut self.writes[idx].ops
+    }
+
+    /// Add a value assignment to the write set.
+    ///
+    /// # Parameters
+    ///   * `kvs`: Instance to write to
+    ///   * `key`: Key to set value
+    ///   * `value`: Value to be set
+    pub fn set_value<S: Into<String>, V: KvsSerialize>(
+        &mut self,
+        kvs: &'a GenericKvs<Backend, PathResolver>,
+        key: S,
+        value: V,
+    ) -> &mut Self {
+        self.instance_ops(kvs)
+            .push(WriteOp::Set(key.into(), value.to_kvs_value()));
+        self
+    }
+
+    /// Add a key removal to the write set.
+    ///
+    /// # Parameters
+    ///   * `kvs`: Instance to remove the key from
+    ///   * `key`: Key to remove
+    pub fn remove_key(
+        &mut self,
+        kvs: &'a GenericKvs<Backend, PathResolver>,
+        key: &str,
+    ) -> &mut Self {
+        self.instance_ops(kvs)
+            .push(WriteOp::Remove(key.to_string()));
+        self
+    }
+
+    /// Apply all writes and flush the involved instances.
+    ///
+    /// # Return Values
+    ///   * Ok: All writes applied and flushed
+    ///   * `ErrorCode::KeyNotFound`: Key to remove not found, nothing was changed
+    ///   * `ErrorCode::InvalidKey`: Key violates the key policy, nothing was changed
+    ///   * `ErrorCode::QuotaExceeded`: Instance would exceed its maximum size, nothing was changed
+    ///   * `ErrorCode::TypeMismatch`: Value type of a key would change with strict typing, nothing
+    ///     was changed
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    ///   * `ErrorCode::IntegrityCorrupted`: Snapshot rotation failed on missing files, instances
+    ///     switched before keep the written data
+    ///   * `ErrorCode::UnmappedError`: Unmapped error
+    pub fn commit(mut self) -> Result<(), ErrorCode> {
+        // Lock in instance ID order to prevent deadlocks with concurrent commits.
+        self.writes
+            .sort_by_key(|w| w.kvs.parameters().instance_id.0);
+        let mut guards = Vec::with_capacity(self.writes.len());
+        for write in &self.writes {
+            guards.push(write.kvs.lock_data()?);
+        }
+
+        // Apply operations on copies of the current maps.
+        let mut new_maps: Vec<KvsMap> = Vec::with_capacity(self.writes.len());
+        for (write, guard) in self.writes.iter().zip(&guards) {
+            let mut kvs_map = guard.kvs_map.clone();
+            for op in &write.ops {
+                match op {
+                    WriteOp::Set(key, value) => {
+                        write.kvs.parameters().key_policy.validate(key)?;
+                        let current = kvs_map.get(key).or_else(|| guard.defaults_map.get(key));
+                        write.kvs.check_type(key, current, value)?;
+                        write.kvs.check_value(key, value)?;
+                        kvs_map.insert(key.clone(), value.clone());
+                    }
+                    WriteOp::Remove(key) => {
+                        if kvs_map.remove(key).is_none() {
+                            return Err(ErrorCode::KeyNotFound);
+                        }
+                    }
+                }
+            }
+            let keys: Vec<&str> = write
+                .ops
+                .iter()
+                .filter_map(|op| match op {
+                    WriteOp::Set(key, _) => Some(key.as_str()),
+                    WriteOp::Remove(_) => None,
+                })
+                .collect();
+            write
+                .kvs
+                .check_limits(&kvs_map, &keys, guard.kvs_map.len())?;
+            write.kvs.check_size(&kvs_map)?;
+            new_maps.push(kvs_map);
+        }
+
+        // Flush hooks are called as for flushes of the single instances.
+        let hooks: Vec<FlushHooks> = guards.iter().map(|g| g.flush_hooks.clone()).collect();
+        for (write, hooks) in self.writes.iter().zip(&hooks) {
+            for hook in &hooks.before {
+                hook(write.kvs.parameters().instance_id);
+            }
+        }
+
+        #[cfg(feature = "snapshots")]
+        let (switched, result) = self.write_staged(&hooks, &new_maps);
+        #[cfg(not(feature = "snapshots"))]
+        let (switched, result) = self.write_staged(&new_maps);
+        let mut results: Vec<Result<(), ErrorCode>> = (0..self.writes.len())
+            .map(|idx| match &result {
+                Err(e) if idx >= switched => Err(e.clone()),
+                _ => Ok(()),
+            })
+            .collect();
+
+        // Update in-memory data of the switched instances, journaled changes are contained in the
+        // written snapshots.
+        for (((write, guard), kvs_map), result) in self
+            .writes
+            .iter()
+            .zip(guards.iter_mut())
+            .zip(new_maps)
+            .zip(&mut results)
+            .take(switched)
+        {
+            guard.kvs_map = kvs_map;
+            guard.dirty = false;
+            if guard.merge_base.is_some() {
+                guard.merge_base = Some(guard.kvs_map.clone());
+            }
+            guard.last_flush = Some(write.kvs.parameters().now());
+            *result = write.kvs.remove_journal();
+        }
+
+        for ((write, hooks), result) in self.writes.iter().zip(&hooks).zip(&results) {
+            for hook in &hooks.after {
+                hook(write.kvs.parameters().instance_id, result);
+            }
+        }
+        results.into_iter().collect()
+    }
+
+    /// Stage and switch the files of all instances, see [`kvs_multi_write`](crate::kvs_multi_write).
+    ///
+    /// Files are written like on flush, with the compression and sync policy of each instance.
+    ///
+    /// # Parameters
+    ///   * `hooks`: Flush hooks of each instance, called on snapshot rotation
+    ///   * `new_maps`: Data to write for each instance
+    ///
+    /// # Return Values
+    ///   * Count of switched instances
+    ///   * Result of the write, instances not switched are unchanged on failure
+    fn write_staged(
+        &self,
+        #[cfg(feature = "snapshots")] hooks: &[FlushHooks],
+        new_maps: &[KvsMap],
+    ) -> (usize, Result<(), ErrorCode>) {
+        // Lock working directories in path order, also to prevent deadlocks.
+        let working_dirs: BTreeSet<&Path> = self
+            .writes
+            .iter()
+            .map(|w| w.kvs.parameters().working_dir.as_path())
+            .collect();
+        let mut _dir_locks = Vec::with_capacity(working_dirs.len());
+        for working_dir in working_dirs {
+            match KvsDirLock::acquire(working_dir) {
+                Ok(dir_lock) => _dir_locks.push(dir_lock),
+                Err(e) => return (0, Err(e)),
+            }
+        }
+
+        // Phase 1: stage files of all instances.
+        let mut staged = Vec::with_capacity(self.writes.len());
+        for (write, kvs_map) in self.writes.iter().zip(new_maps) {
+            let parameters = write.kvs.parameters();
+            let snapshot_id = SnapshotId(0);
+            let kvs_path = parameters.kvs_file_path::<PathResolver>(snapshot_id);
+            let hash_path = parameters.hash_file_path::<PathResolver>(snapshot_id);
+            let files = StagedFiles {
+                staged_kvs_path: staged_path(&kvs_path),
+                staged_hash_path: staged_path(&hash_path),
+                kvs_path,
+                hash_path,
+            };
+
+            let result = Backend::save_kvs_compressed(
+                kvs_map,
+                &files.staged_kvs_path,
+                Some(&files.staged_hash_path),
+                &parameters.float_format,
+                parameters.compression,
+            );
+            staged.push(files);
+            if let Err(e) = result {
+                kvs_error!("staging multi-instance write failed: {e}");
+                staged.iter().for_each(StagedFiles::remove::<Backend>);
+                return (0, Err(e));
+            }
+        }
+
+        // Phase 2: rotate snapshots and move staged files into place.
+        for (idx, files) in staged.iter().enumerate() {
+            let parameters = self.writes[idx].kvs.parameters();
+            #[cfg(feature = "snapshots")]
+            let rotated = self.writes[idx].kvs.snapshot_rotate(&hooks[idx]);
+            #[cfg(not(feature = "snapshots"))]
+            let rotated: Result<(), ErrorCode> = Ok(());
+            let switch = rotated
+                .and_then(|()| {
+                    Backend::move_kvs(
+                        &files.staged_kvs_path,
+                        &files.staged_hash_path,
+                        &files.kvs_path,
+                        &files.hash_path,
+                    )
+                })
+                .and_then(|()| {
+                    Backend::sync_kvs(&files.kvs_path, &files.hash_path, parameters.sync_policy)
+                });
+            if let Err(e) = switch {
+                kvs_error!("switching multi-instance write failed: {e}");
+                staged[idx..]
+                    .iter()
+                    .for_each(StagedFiles::remove::<Backend>);
+                return (idx, Err(e));
+            }
+        }
+        (staged.len(), Ok(()))
+    }
+}
+
+#[cfg(all(test, feature = "json-backend"))]
+mod kvs_multi_write_tests {
+    use crate::error_code::ErrorCode;
+    use crate::json_backend::JsonBackend;
+    use crate::kvs::{GenericKvs, KvsParameters};
+    #[cfg(all(feature = "gzip", feature = "snapshots"))]
+    use crate::kvs_api::KvsCompression;
+    use crate::kvs_api::{InstanceId, KvsApi, SnapshotId};
+    use crate::kvs_backend::{KvsBackend, KvsPathResolver};
+    use crate::kvs_builder::kvs_builder_tests::lock_and_reset;
+    use crate::kvs_builder::{GenericKvsBuilder, KvsData};
+    use crate::kvs_lock::LOCK_FILE_NAME;
+    use crate::kvs_multi_write::MultiKvsWrite;
+    use crate::kvs_value::{KvsMap, KvsValue};
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex};
+    use tempfile::tempdir;
+
+    fn get_kvs(
+        working_dir: PathBuf,
+        instance_id: usize,
+        kvs_map: KvsMap,
+    ) -> GenericKvs<JsonBackend> {
+        let data = Arc::new(Mutex::new(KvsData::new(kvs_map, KvsMap::new())));
+        let parameters = KvsParameters {
+            working_dir,
+            ..KvsParameters::new(InstanceId(instance_id))
+        };
+        GenericKvs::new(data, parameters)
+    }
+
+    fn load(working_dir: &Path, instance_id: usize) -> KvsMap {
+        let kvs_path =
+            JsonBackend::kvs_file_path(working_dir, InstanceId(instance_id), SnapshotId(0));
+        let hash_path =
+            JsonBackend::hash_file_path(working_dir, InstanceId(instance_id), SnapshotId(0));
+        JsonBackend::load_kvs(&kvs_path, Some(&hash_path)).unwrap()
+    }
+
+    /// Count directory entries, the lock file is ignored.
+    fn dir_entries(working_dir: &Path) -> usize {
+        std::fs::read_dir(working_dir)
+            .unwrap()
+            .filter(|e| e.as_ref().unwrap().file_name() != LOCK_FILE_NAME)
+            .count()
+    }
+
+    #[test]
+    fn test_commit_ok() {
+        let dir = tempdir().unwrap();
+        let dir_path = dir.path().to_path_buf();
+        let kvs1 = get_kvs(
+            dir_path.clone(),
+            1,
+            KvsMap::from([("old".to_string(), KvsValue::from(true))]),
+        );
+        let kvs2 = get_kvs(dir_path.clone(), 2, KvsMap::new());
+
+        let mut write = MultiKvsWrite::new();
+        write
+            .set_value(&kvs1, "a", 1.0)
+            .remove_key(&kvs1, "old")
+            .set_value(&kvs2, "b", "text");
+        write.commit().unwrap();
+
+        assert_eq!(
+            load(&dir_path, 1),
+            KvsMap::from([("a".to_string(), KvsValue::from(1.0))])
+        );
+        assert_eq!(
+            load(&dir_path, 2),
+            KvsMap::from([("b".to_string(), KvsValue::from("text"))])
+        );
+        assert!(!kvs1.key_exists("old").unwrap());
+        assert_eq!(kvs2.get_value_as::<String>("b").unwrap(), "text");
+        // Only snapshot and hash files, no staged files left.
+        assert_eq!(dir_entries(&dir_path), 4);
+    }
+
+    #[cfg(feature = "snapshots")]
+    #[test]
+    fn test_commit_rotates_snapshots() {
+        let dir = tempdir().unwrap();
+        let dir_path = dir.path().to_path_buf();
+        let kvs = get_kvs(dir_path.clone(), 1, KvsMap::new());
+        kvs.set_value("a", 1.0).unwrap();
+        kvs.flush().unwrap();
+
+        let mut write = MultiKvsWrite::new();
+        write.set_value(&kvs, "a", 2.0);
+        write.commit().unwrap();
+
+        assert_eq!(kvs.snapshot_count(), 2);
+        assert_eq!(load(&dir_path, 1)["a"], KvsValue::from(2.0));
+    }
+
+    #[test]
+    fn test_commit_shared_instance_data() {
+        let dir = tempdir().unwrap();
+        let kvs1 = get_kvs(dir.path().to_path_buf(), 1, KvsMap::new());
+        let kvs2 = GenericKvs::<JsonBackend>::new(kvs1.data.clone(), kvs1.parameters().clone());
+
+        let mut write = MultiKvsWrite::new();
+        write.set_value(&kvs1, "a", 1.0).set_value(&kvs2, "b", 2.0);
+        write.commit().unwrap();
+
+        assert_eq!(kvs1.get_all_keys().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_commit_remove_not_found() {
+        let dir = tempdir().unwrap();
+        let dir_path = dir.path().to_path_buf();
+        let kvs1 = get_kvs(dir_path.clone(), 1, KvsMap::new());
+        let kvs2 = get_kvs(dir_path.clone(), 2, KvsMap::new());
+
+        let mut write = MultiKvsWrite::new();
+        write
+            .set_value(&kvs1, "a", 1.0)
+            .remove_key(&kvs2, "missing");
+        assert!(write.commit().is_err_and(|e| e == ErrorCode::KeyNotFound));
+
+        assert!(!kvs1.key_exists("a").unwrap());
+        assert_eq!(dir_entries(&dir_path), 0);
+    }
+
+    #[test]
+    fn test_commit_stage_failure() {
+        let dir = tempdir().unwrap();
+        let dir_path = dir.path().to_path_buf();
+        let kvs1 = get_kvs(dir_path.clone(), 1, KvsMap::new());
+        let kvs2 = get_kvs(dir_path.join("missing"), 2, KvsMap::new());
+
+        let mut write = MultiKvsWrite::new();
+        write.set_value(&kvs1, "a", 1.0).set_value(&kvs2, "b", 2.0);
+        assert!(write.commit().is_err_and(|e| e == ErrorCode::FileNotFound));
+
+        assert!(!kvs1.key_exists("a").unwrap());
+        assert!(!kvs2.key_exists("b").unwrap());
+        assert_eq!(dir_entries(&dir_path), 0);
+    }
+
+    #[test]
+    fn test_commit_removes_journal() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let open = || {
+            GenericKvsBuilder::<JsonBackend>::new(InstanceId(1))
+                .dir(dir.path().to_string_lossy().to_string())
+                .journal(true)
+                .build()
+                .unwrap()
+        };
+        let kvs = open();
+        kvs.set_value("counter", 1).unwrap();
+        assert!(dir.path().join("kvs_1.wal").exists());
+
+        let mut write = MultiKvsWrite::new();
+        write.set_value(&kvs, "counter", 2);
+        write.commit().unwrap();
+        assert!(!dir.path().join("kvs_1.wal").exists());
+        assert!(!kvs.is_dirty().unwrap());
+        kvs.close().unwrap();
+
+        let kvs = open();
+        assert_eq!(kvs.get_value("counter").unwrap(), KvsValue::I32(2));
+        kvs.close().unwrap();
+    }
+
+    #[cfg(feature = "snapshots")]
+    #[test]
+    fn test_commit_switch_failure() {
+        let dir = tempdir().unwrap();
+        let dir_path = dir.path().to_path_buf();
+        let kvs1 = get_kvs(dir_path.clone(), 1, KvsMap::new());
+        let kvs2 = get_kvs(dir_path.clone(), 2, KvsMap::new());
+        // Current KVS of instance 2 without hash file fails its rotation.
+        std::fs::write(dir_path.join("kvs_2_0.json"), "{}").unwrap();
+
+        let mut write = MultiKvsWrite::new();
+        write.set_value(&kvs1, "a", 1.0).set_value(&kvs2, "b", 2.0);
+        assert!(write
+            .commit()
+            .is_err_and(|e| e == ErrorCode::IntegrityCorrupted));
+
+        // Instance 1 was switched, memory matches the stored data.
+        assert_eq!(load(&dir_path, 1)["a"], KvsValue::from(1.0));
+        assert_eq!(kvs1.get_value("a").unwrap(), KvsValue::from(1.0));
+        assert!(!kvs1.is_dirty().unwrap());
+        assert!(!kvs2.key_exists("b").unwrap());
+        assert!(!dir_path.join(".staged.kvs_2_0.json").exists());
+    }
+
+    #[test]
+    fn test_commit_flush_hooks() {
+        let dir = tempdir().unwrap();
+        let kvs = get_kvs(dir.path().to_path_buf(), 1, KvsMap::new());
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let before = calls.clone();
+        kvs.on_before_flush(move |instance_id| {
+            before.lock().unwrap().push(format!("before {instance_id}"))
+        })
+        .unwrap();
+        let after = calls.clone();
+        kvs.on_after_flush(move |instance_id, result| {
+            after
+                .lock()
+                .unwrap()
+                .push(format!("after {instance_id} {}", result.is_ok()))
+        })
+        .unwrap();
+
+        let mut write = MultiKvsWrite::new();
+        write.set_value(&kvs, "a", 1.0);
+        write.commit().unwrap();
+
+        assert_eq!(*calls.lock().unwrap(), ["before 1", "after 1 true"]);
+    }
+
+    #[cfg(all(feature = "gzip", feature = "snapshots"))]
+    #[test]
+    fn test_commit_compressed() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let open = || {
+            GenericKvsBuilder::<JsonBackend>::new(InstanceId(1))
+                .dir(dir.path().to_string_lossy().to_string())
+                .compression(KvsCompression::Gzip)
+                .build()
+                .unwrap()
+        };
+        let kvs = open();
+        kvs.set_value("a", 1).unwrap();
+        kvs.flush().unwrap();
+
+        let mut write = MultiKvsWrite::new();
+        write.set_value(&kvs, "a", 2);
+        write.commit().unwrap();
+        assert!(dir.path().join("kvs_1_0.json.gz").exists());
+        assert!(!dir.path().join("kvs_1_0.json").exists());
+        assert!(dir.path().join("kvs_1_1.json.gz").exists());
+        kvs.close().unwrap();
+
+        let kvs = open();
+        assert_eq!(kvs.get_value("a").unwrap(), KvsValue::I32(2));
+        kvs.close().unwrap();
+    }
+}
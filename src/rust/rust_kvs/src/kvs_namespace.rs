@@ -0,0 +1,196 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Logical namespaces within one instance.
+//!
+//! [`GenericKvs::namespace`] returns a [`KvsNamespace`] view, keys accessed through the view are
+//! stored as `<namespace>/<key>`. Components sharing an instance are isolated from each other
+//! without using an instance ID per component. Flushing, snapshots and defaults are shared by all
+//! namespaces of the instance, default values are looked up with the prefixed key.
+
+use crate::error_code::ErrorCode;
+use crate::kvs::GenericKvs;
+use crate::kvs_api::KvsApi;
+use crate::kvs_backend::{KvsBackend, KvsPathResolver};
+use crate::kvs_log::kvs_error;
+use crate::kvs_value::{KvsDeserialize, KvsSerialize, KvsValue, ValueModel};
+
+/// Separator between namespace and key.
+pub const KVS_NAMESPACE_SEPARATOR: char = '/';
+
+/// View of an instance restricted to the keys of one namespace.
+pub struct KvsNamespace<
+    'a,
+    Backend: KvsBackend,
+    PathResolver: KvsPathResolver = Backend,
+    Model: ValueModel = KvsValue,
+> {
+    kvs: &'a GenericKvs<Backend, PathResolver, Model>,
+    name: String,
+    prefix: String,
+}
+
+impl<Backend: KvsBackend, PathResolver: KvsPathResolver, Model: ValueModel>
+    GenericKvs<Backend, PathResolver, Model>
+{
+    /// Get a view of a namespace
+    ///
+    /// # Parameters
+    ///   * `name`: Name of the namespace, must not be empty or contain
+    ///     [`KVS_NAMESPACE_SEPARATOR`]
+    ///
+    /// # Return Values
+    ///   * Ok: Namespace view
+    ///   * `ErrorCode::InvalidKey`: Invalid namespace name
+    pub fn namespace(
+        &self,
+        name: &str,
+    ) -> Result<KvsNamespace<'_, Backend, PathResolver, Model>, ErrorCode> {
+        if name.is_empty() || name.contains(KVS_NAMESPACE_SEPARATOR) {
+            kvs_error!(
+                instance_id = self.parameters().instance_id,
+                "invalid namespace name: {name:?}"
+            );
+            return Err(ErrorCode::InvalidKey);
+        }
+        Ok(KvsNamespace {
+            kvs: self,
+            name: name.to_string(),
+            prefix: format!("{name}{KVS_NAMESPACE_SEPARATOR}"),
+        })
+    }
+}
+
+impl<Backend: KvsBackend, PathResolver: KvsPathResolver, Model: ValueModel>
+    KvsNamespace<'_, Backend, PathResolver, Model>
+{
+    /// Get the name of the namespace.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the key stored in the instance for a key of the namespace.
+    pub fn full_key(&self, key: &str) -> String {
+        format!("{}{key}", self.prefix)
+    }
+
+    /// Get all keys of the namespace, without namespace prefix
+    ///
+    /// # Return Values
+    ///   * Ok: List of keys
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    pub fn get_all_keys(&self) -> Result<Vec<String>, ErrorCode> {
+        Ok(self
+            .kvs
+            .get_keys_with_prefix(&self.prefix)?
+            .into_iter()
+            .map(|key| key[self.prefix.len()..].to_string())
+            .collect())
+    }
+
+    /// Check if a key exists in the namespace, see [`KvsApi::key_exists`].
+    pub fn key_exists(&self, key: &str) -> Result<bool, ErrorCode> {
+        self.kvs.key_exists(&self.full_key(key))
+    }
+
+    /// Get the value of a key in the namespace, see [`KvsApi::get_value`].
+    pub fn get_value(&self, key: &str) -> Result<KvsValue, ErrorCode> {
+        self.kvs.get_value(&self.full_key(key))
+    }
+
+    /// Get the converted value of a key in the namespace, see [`KvsApi::get_value_as`].
+    pub fn get_value_as<T: KvsDeserialize>(&self, key: &str) -> Result<T, ErrorCode> {
+        self.kvs.get_value_as(&self.full_key(key))
+    }
+
+    /// Assign a value to a key in the namespace, see [`KvsApi::set_value`].
+    pub fn set_value<J: KvsSerialize>(&self, key: &str, value: J) -> Result<(), ErrorCode> {
+        self.kvs.set_value(self.full_key(key), value)
+    }
+
+    /// Remove a key from the namespace, see [`KvsApi::remove_key`].
+    pub fn remove_key(&self, key: &str) -> Result<(), ErrorCode> {
+        self.kvs.remove_key(&self.full_key(key))
+    }
+
+    /// Reset a key in the namespace to its default value, see [`KvsApi::reset_key`].
+    pub fn reset_key(&self, key: &str) -> Result<(), ErrorCode> {
+        self.kvs.reset_key(&self.full_key(key))
+    }
+
+    /// Remove all keys of the namespace
+    ///
+    /// # Return Values
+    ///   * Ok: Count of removed keys
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    pub fn clear(&self) -> Result<usize, ErrorCode> {
+        self.kvs.remove_keys_with_prefix(&self.prefix)
+    }
+}
+
+#[cfg(all(test, feature = "json-backend"))]
+mod kvs_namespace_tests {
+    use crate::error_code::ErrorCode;
+    use crate::json_backend::JsonBackend;
+    use crate::kvs_api::{InstanceId, KvsApi};
+    use crate::kvs_builder::kvs_builder_tests::lock_and_reset;
+    use crate::kvs_builder::GenericKvsBuilder;
+    use crate::kvs_value::KvsValue;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_namespace() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let kvs = GenericKvsBuilder::<JsonBackend>::new(InstanceId(1))
+            .dir(dir.path().to_string_lossy().to_string())
+            .build()
+            .unwrap();
+        kvs.set_value("key", 0).unwrap();
+        let netcfg = kvs.namespace("netcfg").unwrap();
+        let diag = kvs.namespace("diag").unwrap();
+
+        netcfg.set_value("key", 1).unwrap();
+        diag.set_value("key", 2).unwrap();
+        diag.set_value("other", true).unwrap();
+
+        assert_eq!(netcfg.name(), "netcfg");
+        assert_eq!(netcfg.get_value_as::<i32>("key").unwrap(), 1);
+        assert_eq!(diag.get_value("key").unwrap(), KvsValue::I32(2));
+        assert_eq!(kvs.get_value_as::<i32>("key").unwrap(), 0);
+        assert_eq!(kvs.get_value_as::<i32>("netcfg/key").unwrap(), 1);
+        assert!(!netcfg.key_exists("other").unwrap());
+
+        let mut keys = diag.get_all_keys().unwrap();
+        keys.sort();
+        assert_eq!(keys, vec!["key".to_string(), "other".to_string()]);
+
+        netcfg.remove_key("key").unwrap();
+        assert!(netcfg.get_all_keys().unwrap().is_empty());
+        assert_eq!(diag.clear().unwrap(), 2);
+        assert_eq!(kvs.get_all_keys().unwrap(), vec!["key".to_string()]);
+    }
+
+    #[test]
+    fn test_namespace_invalid_name() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let kvs = GenericKvsBuilder::<JsonBackend>::new(InstanceId(1))
+            .dir(dir.path().to_string_lossy().to_string())
+            .build()
+            .unwrap();
+
+        assert!(kvs.namespace("").is_err_and(|e| e == ErrorCode::InvalidKey));
+        assert!(kvs
+            .namespace("a/b")
+            .is_err_and(|e| e == ErrorCode::InvalidKey));
+    }
+}
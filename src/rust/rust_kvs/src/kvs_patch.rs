@@ -0,0 +1,368 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Store-wide JSON patches.
+//!
+//! [`GenericKvs::apply_patch`] applies a JSON Patch in the style of RFC 6902, e.g. shipped with
+//! OTA delta updates:
+//!
+//! ```json
+//! [
+//!   { "op": "add", "path": "/network/retries", "value": 3 },
+//!   { "op": "replace", "path": "/log_level", "value": { "t": "str", "v": "info" } },
+//!   { "op": "remove", "path": "/legacy_flag" }
+//! ]
+//! ```
+//!
+//! The first segment of a path is the key, further segments address object members and array
+//! elements of its value, `-` appends to an array. Segments are escaped like JSON Pointers, `~1`
+//! for `/` and `~0` for `~`. Only `add`, `remove` and `replace` are supported.
+//!
+//! Values are type-tagged like in KVS files or plain JSON, types of plain values are inferred
+//! like for legacy files, numbers become `F64`. Nested changes start from the stored or default
+//! value of the key, `remove` of a whole key only removes stored values.
+//!
+//! The patch is applied atomically as a [`transaction`](crate::kvs_api::KvsApi::transaction),
+//! either all operations are applied or none.
+
+use crate::error_code::ErrorCode;
+use crate::json_backend::JsonBackend;
+use crate::kvs::GenericKvs;
+use crate::kvs_api::KvsApi;
+use crate::kvs_backend::{KvsBackend, KvsPathResolver};
+use crate::kvs_log::{kvs_debug, kvs_error};
+use crate::kvs_transaction::KvsTransaction;
+use crate::kvs_value::{KvsValue, ValueModel};
+use tinyjson::JsonValue;
+
+/// Supported patch operations.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PatchOp {
+    Add,
+    Remove,
+    Replace,
+}
+
+/// Parsed patch operation.
+struct PatchOperation {
+    op: PatchOp,
+    key: String,
+    segments: Vec<String>,
+    value: Option<KvsValue>,
+}
+
+/// Split a path into unescaped segments
+///
+/// # Return Values
+///   * Ok: Key and further segments
+///   * `ErrorCode::InvalidKey`: Path doesn't start with `/`
+fn parse_path(path: &str) -> Result<(String, Vec<String>), ErrorCode> {
+    let Some(path) = path.strip_prefix('/') else {
+        kvs_error!("patch path {path:?} doesn't start with '/'");
+        return Err(ErrorCode::InvalidKey);
+    };
+    let mut segments = path
+        .split('/')
+        .map(|segment| segment.replace("~1", "/").replace("~0", "~"));
+    let key = segments.next().unwrap_or_default();
+    Ok((key, segments.collect()))
+}
+
+/// Parse a JSON patch
+///
+/// # Return Values
+///   * Ok: Operations in order
+///   * `ErrorCode::JsonParserError`: Patch isn't an array of supported operations
+///   * `ErrorCode::InvalidKey`: A path is malformed
+fn parse_patch(patch: &str) -> Result<Vec<PatchOperation>, ErrorCode> {
+    let JsonValue::Array(operations) = patch.parse::<JsonValue>()? else {
+        kvs_error!("patch isn't a JSON array");
+        return Err(ErrorCode::JsonParserError);
+    };
+    operations
+        .into_iter()
+        .map(|operation| {
+            let JsonValue::Object(mut operation) = operation else {
+                kvs_error!("patch operation isn't a JSON object");
+                return Err(ErrorCode::JsonParserError);
+            };
+            let op = match operation.get("op") {
+                Some(JsonValue::String(op)) if op == "add" => PatchOp::Add,
+                Some(JsonValue::String(op)) if op == "remove" => PatchOp::Remove,
+                Some(JsonValue::String(op)) if op == "replace" => PatchOp::Replace,
+                op => {
+                    kvs_error!("unsupported patch operation {op:?}");
+                    return Err(ErrorCode::JsonParserError);
+                }
+            };
+            let Some(JsonValue::String(path)) = operation.get("path") else {
+                kvs_error!("patch operation without path");
+                return Err(ErrorCode::JsonParserError);
+            };
+            let (key, segments) = parse_path(path)?;
+            let value = operation.remove("value").map(JsonBackend::value_from_json);
+            if op != PatchOp::Remove && value.is_none() {
+                kvs_error!(key = key, "patch operation {op:?} without value");
+                return Err(ErrorCode::JsonParserError);
+            }
+            Ok(PatchOperation {
+                op,
+                key,
+                segments,
+                value,
+            })
+        })
+        .collect()
+}
+
+/// Parse an array index, `None` for `-`
+///
+/// # Return Values
+///   * Ok: Index within `0..=len`, `None` for the end of the array
+///   * `ErrorCode::InvalidKey`: Segment isn't an index
+///   * `ErrorCode::KeyNotFound`: Index is out of bounds
+fn parse_index(segment: &str, len: usize) -> Result<Option<usize>, ErrorCode> {
+    if segment == "-" {
+        return Ok(None);
+    }
+    let index: usize = segment.parse().map_err(|_| {
+        kvs_error!("invalid array index {segment:?} in patch path");
+        ErrorCode::InvalidKey
+    })?;
+    if index > len {
+        return Err(ErrorCode::KeyNotFound);
+    }
+    Ok(Some(index))
+}
+
+/// Apply an operation to a nested element of a value
+///
+/// # Parameters
+///   * `target`: Value containing the element
+///   * `segments`: Path of the element within `target`, not empty
+///   * `op`: Operation
+///   * `value`: New value for `add` and `replace`
+fn apply_nested(
+    target: &mut KvsValue,
+    segments: &[String],
+    op: PatchOp,
+    value: Option<KvsValue>,
+) -> Result<(), ErrorCode> {
+    let (segment, rest) = segments.split_first().ok_or(ErrorCode::InvalidKey)?;
+    if !rest.is_empty() {
+        let child = match target {
+            KvsValue::Object(map) => map.get_mut(segment),
+            KvsValue::Array(arr) => match parse_index(segment, arr.len())? {
+                Some(index) => arr.get_mut(index),
+                None => None,
+            },
+            _ => return Err(ErrorCode::InvalidKey),
+        };
+        return apply_nested(child.ok_or(ErrorCode::KeyNotFound)?, rest, op, value);
+    }
+
+    match (target, op) {
+        (KvsValue::Object(map), PatchOp::Add) => {
+            map.insert(segment.clone(), value.unwrap_or(KvsValue::Null));
+        }
+        (KvsValue::Object(map), PatchOp::Replace) => {
+            let element = map.get_mut(segment).ok_or(ErrorCode::KeyNotFound)?;
+            *element = value.unwrap_or(KvsValue::Null);
+        }
+        (KvsValue::Object(map), PatchOp::Remove) => {
+            map.remove(segment).ok_or(ErrorCode::KeyNotFound)?;
+        }
+        (KvsValue::Array(arr), PatchOp::Add) => {
+            let value = value.unwrap_or(KvsValue::Null);
+            match parse_index(segment, arr.len())? {
+                Some(index) => arr.insert(index, value),
+                None => arr.push(value),
+            }
+        }
+        (KvsValue::Array(arr), op) => {
+            let index = parse_index(segment, arr.len())?
+                .filter(|index| *index < arr.len())
+                .ok_or(ErrorCode::KeyNotFound)?;
+            if op == PatchOp::Remove {
+                arr.remove(index);
+            } else {
+                arr[index] = value.unwrap_or(KvsValue::Null);
+            }
+        }
+        _ => return Err(ErrorCode::InvalidKey),
+    }
+    Ok(())
+}
+
+/// Apply an operation within a transaction.
+fn apply_operation(txn: &mut KvsTransaction, operation: PatchOperation) -> Result<(), ErrorCode> {
+    let PatchOperation {
+        op,
+        key,
+        segments,
+        value,
+    } = operation;
+    if segments.is_empty() {
+        return match (op, value) {
+            (PatchOp::Remove, _) => txn.remove_key(&key),
+            (PatchOp::Replace, Some(value)) => {
+                txn.get_value(&key)?;
+                txn.set_value(key, value);
+                Ok(())
+            }
+            (_, value) => {
+                txn.set_value(key, value.unwrap_or(KvsValue::Null));
+                Ok(())
+            }
+        };
+    }
+    let mut current = txn.get_value(&key)?;
+    apply_nested(&mut current, &segments, op, value)?;
+    txn.set_value(key, current);
+    Ok(())
+}
+
+impl<Backend: KvsBackend, PathResolver: KvsPathResolver, Model: ValueModel>
+    GenericKvs<Backend, PathResolver, Model>
+{
+    /// Apply a JSON patch atomically
+    ///
+    /// Supports `add`, `remove` and `replace` operations on key paths, see
+    /// [`kvs_patch`](crate::kvs_patch). Either all operations are applied or none.
+    ///
+    /// # Parameters
+    ///   * `patch`: JSON Patch document
+    ///
+    /// # Return Values
+    ///   * Ok: Number of applied operations
+    ///   * `ErrorCode::JsonParserError`: Patch isn't an array of supported operations
+    ///   * `ErrorCode::InvalidKey`: A path is malformed or addresses into a non-container value
+    ///   * `ErrorCode::KeyNotFound`: A removed or replaced key or element doesn't exist
+    ///   * Err: Transaction failed, see [`KvsApi::transaction`]
+    pub fn apply_patch(&self, patch: &str) -> Result<usize, ErrorCode> {
+        let instance_id = self.parameters().instance_id;
+        let operations = parse_patch(patch)?;
+        let count = operations.len();
+        self.transaction(|txn| {
+            for operation in operations {
+                let key = operation.key.clone();
+                apply_operation(txn, operation).inspect_err(|e| {
+                    kvs_error!(
+                        instance_id = instance_id,
+                        key = key,
+                        "applying patch failed: {e}"
+                    )
+                })?;
+            }
+            Ok(())
+        })?;
+        kvs_debug!(
+            instance_id = instance_id,
+            "applied patch with {count} operations"
+        );
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod kvs_patch_tests {
+    use crate::error_code::ErrorCode;
+    use crate::json_backend::JsonBackend;
+    use crate::kvs_api::{InstanceId, KvsApi};
+    use crate::kvs_builder::kvs_builder_tests::lock_and_reset;
+    use crate::kvs_builder::GenericKvsBuilder;
+    use crate::kvs_value::{KvsMap, KvsValue};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_apply_patch() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let kvs = GenericKvsBuilder::<JsonBackend>::new(InstanceId(1))
+            .dir(dir.path().to_string_lossy().to_string())
+            .build()
+            .unwrap();
+        kvs.set_value("log_level", "debug").unwrap();
+        kvs.set_value("legacy_flag", true).unwrap();
+        kvs.set_value(
+            "network",
+            KvsValue::Object(KvsMap::from([(
+                "hosts".to_string(),
+                KvsValue::Array(vec![KvsValue::from("a")]),
+            )])),
+        )
+        .unwrap();
+
+        let patch = r#"[
+            { "op": "replace", "path": "/log_level", "value": { "t": "str", "v": "info" } },
+            { "op": "remove", "path": "/legacy_flag" },
+            { "op": "add", "path": "/network/retries", "value": { "t": "i32", "v": 3 } },
+            { "op": "add", "path": "/network/hosts/-", "value": "b" },
+            { "op": "add", "path": "/a~1b", "value": 1.5 }
+        ]"#;
+        assert_eq!(kvs.apply_patch(patch).unwrap(), 5);
+        assert_eq!(kvs.get_value("log_level").unwrap(), KvsValue::from("info"));
+        assert!(!kvs.key_exists("legacy_flag").unwrap());
+        assert_eq!(
+            kvs.get_value("network").unwrap(),
+            KvsValue::Object(KvsMap::from([
+                (
+                    "hosts".to_string(),
+                    KvsValue::Array(vec![KvsValue::from("a"), KvsValue::from("b")]),
+                ),
+                ("retries".to_string(), KvsValue::I32(3)),
+            ]))
+        );
+        assert_eq!(kvs.get_value("a/b").unwrap(), KvsValue::F64(1.5));
+    }
+
+    #[test]
+    fn test_apply_patch_atomic() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let kvs = GenericKvsBuilder::<JsonBackend>::new(InstanceId(1))
+            .dir(dir.path().to_string_lossy().to_string())
+            .build()
+            .unwrap();
+        kvs.set_value("key", 1).unwrap();
+
+        let patch = r#"[
+            { "op": "replace", "path": "/key", "value": 2 },
+            { "op": "remove", "path": "/missing" }
+        ]"#;
+        assert!(kvs
+            .apply_patch(patch)
+            .is_err_and(|e| e == ErrorCode::KeyNotFound));
+        assert_eq!(kvs.get_value("key").unwrap(), KvsValue::I32(1));
+
+        for (patch, error) in [
+            (r#"{"op": "add"}"#, ErrorCode::JsonParserError),
+            (
+                r#"[{"op": "move", "path": "/key"}]"#,
+                ErrorCode::JsonParserError,
+            ),
+            (
+                r#"[{"op": "add", "path": "/key"}]"#,
+                ErrorCode::JsonParserError,
+            ),
+            (
+                r#"[{"op": "add", "path": "key", "value": 1}]"#,
+                ErrorCode::InvalidKey,
+            ),
+            (
+                r#"[{"op": "add", "path": "/key/x", "value": 1}]"#,
+                ErrorCode::InvalidKey,
+            ),
+        ] {
+            assert!(kvs.apply_patch(patch).is_err_and(|e| e == error));
+        }
+    }
+}
@@ -0,0 +1,441 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Access of nested values by path.
+//!
+//! A path starts with a key of the KVS, followed by object fields separated by `.` and array
+//! indices in brackets, e.g. `network.interfaces[2].mtu`. Keys and fields containing `.`, `[` or
+//! `]` can't be addressed by path.
+
+use crate::error_code::ErrorCode;
+use crate::kvs::GenericKvs;
+use crate::kvs_api::KvsApi;
+use crate::kvs_backend::{KvsBackend, KvsPathResolver};
+use crate::kvs_log::kvs_error;
+use crate::kvs_value::{KvsSerialize, KvsValue, ValueModel};
+
+/// Segment of a path following the key.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PathSegment {
+    /// Field of an object.
+    Field(String),
+
+    /// Index of an array.
+    Index(usize),
+}
+
+/// Split path into key and segments.
+///
+/// # Parameters
+///   * `path`: Path to split
+///
+/// # Return Values
+///   * Ok: Key and segments following it
+///   * `ErrorCode::InvalidKey`: Malformed path
+pub fn parse_path(path: &str) -> Result<(String, Vec<PathSegment>), ErrorCode> {
+    let invalid = || {
+        kvs_error!("invalid value path: {path}");
+        ErrorCode::InvalidKey
+    };
+
+    let mut key = None;
+    let mut segments = Vec::new();
+    for part in path.split('.') {
+        let (name, mut indices) = part.split_at(part.find('[').unwrap_or(part.len()));
+        if name.is_empty() || name.contains(']') {
+            return Err(invalid());
+        }
+        match key {
+            None => key = Some(name.to_string()),
+            Some(_) => segments.push(PathSegment::Field(name.to_string())),
+        }
+        while let Some(rest) = indices.strip_prefix('[') {
+            let (index, rest) = rest.split_once(']').ok_or_else(invalid)?;
+            let index = index.parse().map_err(|_| invalid())?;
+            segments.push(PathSegment::Index(index));
+            indices = rest;
+        }
+        if !indices.is_empty() {
+            return Err(invalid());
+        }
+    }
+    Ok((key.ok_or_else(invalid)?, segments))
+}
+
+/// Resolve segments starting at a value.
+fn value_at<'a>(value: &'a KvsValue, segments: &[PathSegment]) -> Result<&'a KvsValue, ErrorCode> {
+    segments
+        .iter()
+        .try_fold(value, |value, segment| match (segment, value) {
+            (PathSegment::Field(name), KvsValue::Object(map)) => {
+                map.get(name).ok_or(ErrorCode::KeyNotFound)
+            }
+            (PathSegment::Index(index), KvsValue::Array(array)) => {
+                array.get(*index).ok_or(ErrorCode::KeyNotFound)
+            }
+            _ => Err(ErrorCode::ConversionFailed),
+        })
+}
+
+/// Replace value at segments starting at a value, `None` removes an object field.
+///
+/// # Return Values
+///   * Ok: Previous value, `None` if an object field was added
+///   * `ErrorCode::KeyNotFound`: Parent of the value or array element not found
+///   * `ErrorCode::ConversionFailed`: Segment doesn't match the type of the value
+fn replace_at(
+    value: &mut KvsValue,
+    parents: &[PathSegment],
+    last: &PathSegment,
+    new_value: Option<KvsValue>,
+) -> Result<Option<KvsValue>, ErrorCode> {
+    let parent = parents
+        .iter()
+        .try_fold(value, |value, segment| match (segment, value) {
+            (PathSegment::Field(name), KvsValue::Object(map)) => {
+                map.get_mut(name).ok_or(ErrorCode::KeyNotFound)
+            }
+            (PathSegment::Index(index), KvsValue::Array(array)) => {
+                array.get_mut(*index).ok_or(ErrorCode::KeyNotFound)
+            }
+            _ => Err(ErrorCode::ConversionFailed),
+        })?;
+    match (last, parent, new_value) {
+        (PathSegment::Field(name), KvsValue::Object(map), Some(new_value)) => {
+            Ok(map.insert(name.clone(), new_value))
+        }
+        (PathSegment::Field(name), KvsValue::Object(map), None) => Ok(map.remove(name)),
+        (PathSegment::Index(index), KvsValue::Array(array), Some(new_value)) => array
+            .get_mut(*index)
+            .map(|element| Some(std::mem::replace(element, new_value)))
+            .ok_or(ErrorCode::KeyNotFound),
+        _ => Err(ErrorCode::ConversionFailed),
+    }
+}
+
+impl<Backend: KvsBackend, PathResolver: KvsPathResolver, Model: ValueModel>
+    GenericKvs<Backend, PathResolver, Model>
+{
+    /// Get a nested value by path
+    ///
+    /// Default values are used if the key was not written yet. Only the addressed value is cloned.
+    ///
+    /// # Parameters
+    ///   * `path`: Path of the value, e.g. `obj.sub-array[2]`
+    ///
+    /// # Return Values
+    ///   * Ok: Value at path
+    ///   * `ErrorCode::InvalidKey`: Malformed path
+    ///   * `ErrorCode::KeyNotFound`: Key, field or array element not found
+    ///   * `ErrorCode::ConversionFailed`: Path traverses a value that is no object or array
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    pub fn get_value_at_path(&self, path: &str) -> Result<KvsValue, ErrorCode> {
+        let (key, segments) = parse_path(path)?;
+        let mut data = self.lock()?;
+        data.materialize_key(&key)?;
+        let value = data
+            .kvs_map
+            .get(&key)
+            .or_else(|| data.defaults_map.get(&key))
+            .ok_or(ErrorCode::KeyNotFound)
+            .and_then(|value| value_at(value, &segments))
+            .inspect_err(|e| {
+                kvs_error!(
+                    key = key,
+                    "get_value_at_path could not resolve path {path}: {e}"
+                )
+            })?
+            .clone();
+        self.record_access(&mut data, &key, false);
+        Ok(value)
+    }
+
+    /// Assign a nested value by path
+    ///
+    /// The parent of the addressed value must exist, a missing object field is added. If the key
+    /// was not written yet, its default value is written with the nested value replaced.
+    ///
+    /// # Parameters
+    ///   * `path`: Path of the value, e.g. `obj.sub-array[2]`
+    ///   * `value`: Value to be set
+    ///
+    /// # Return Values
+    ///   * Ok: Value was assigned
+    ///   * `ErrorCode::InvalidKey`: Malformed path or key violates the key policy
+    ///   * `ErrorCode::KeyNotFound`: Key, parent or array element not found
+    ///   * `ErrorCode::ConversionFailed`: Path traverses a value that is no object or array
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    ///   * `ErrorCode::QuotaExceeded`: Value would exceed the maximum size, value size or key
+    ///     count, nothing was changed
+    ///   * `ErrorCode::TypeMismatch`: Strict typing is enabled and the addressed value has another
+    ///     type
+    ///   * `ErrorCode::ValidationFailed`: A validator rejected the resulting value of the key
+    pub fn set_value_at_path<V: KvsSerialize>(
+        &self,
+        path: &str,
+        value: V,
+    ) -> Result<(), ErrorCode> {
+        let (key, segments) = parse_path(path)?;
+        let Some((last, parents)) = segments.split_last() else {
+            return self.set_value(key, value);
+        };
+        self.parameters().key_policy.validate(&key)?;
+        let value = value.to_kvs_value();
+        let mut data = self.lock_keys([key.as_str()])?;
+        self.record_access(&mut data, &key, true);
+
+        // Storing an equal value doesn't require a flush.
+        let Some(current) = data
+            .kvs_map
+            .get(&key)
+            .or_else(|| data.defaults_map.get(&key))
+        else {
+            kvs_error!(key = key, "set_value_at_path could not find key: {key}");
+            return Err(ErrorCode::KeyNotFound);
+        };
+        let nested = value_at(current, &segments).ok();
+        if nested == Some(&value) {
+            return Ok(());
+        }
+        self.check_type(&key, nested, &value)?;
+
+        // Nested change of a default value writes the whole default value.
+        let from_defaults = !data.kvs_map.contains_key(&key);
+        if from_defaults {
+            let default = current.clone();
+            data.kvs_map.insert(key.clone(), default);
+        }
+
+        let result = match data.kvs_map.get_mut(&key) {
+            Some(root) => replace_at(root, parents, last, Some(value)),
+            None => Err(ErrorCode::KeyNotFound),
+        }
+        .and_then(|previous| {
+            let previous_len = data.kvs_map.len() - usize::from(from_defaults);
+            let result = data
+                .kvs_map
+                .get(&key)
+                .map_or(Ok(()), |root| self.check_value(&key, root))
+                .and_then(|()| self.check_limits(&data.kvs_map, &[&key], previous_len))
+                .and_then(|()| self.check_size(&data.kvs_map));
+            if result.is_err() && !from_defaults {
+                if let Some(root) = data.kvs_map.get_mut(&key) {
+                    let _ = replace_at(root, parents, last, previous);
+                }
+            }
+            result
+        });
+        match result {
+            Ok(()) => {
+                data.dirty = true;
+                self.journal_keys(&data, [key.as_str()])
+            }
+            Err(e) => {
+                if from_defaults {
+                    data.kvs_map.remove(&key);
+                }
+                kvs_error!(
+                    key = key,
+                    "set_value_at_path could not set path {path}: {e}"
+                );
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "json-backend"))]
+mod kvs_path_tests {
+    use crate::error_code::ErrorCode;
+    use crate::json_backend::JsonBackend;
+    use crate::kvs::{GenericKvs, KvsParameters};
+    use crate::kvs_api::{InstanceId, KvsApi};
+    use crate::kvs_builder::KvsData;
+    use crate::kvs_path::{parse_path, PathSegment};
+    use crate::kvs_value::{KvsMap, KvsValue};
+    use std::sync::{Arc, Mutex};
+
+    fn get_kvs(defaults_map: KvsMap, max_size: Option<usize>) -> GenericKvs<JsonBackend> {
+        let data = Arc::new(Mutex::new(KvsData::new(KvsMap::new(), defaults_map)));
+        let parameters = KvsParameters {
+            max_size,
+            ..KvsParameters::new(InstanceId(1))
+        };
+        GenericKvs::new(data, parameters)
+    }
+
+    fn config() -> KvsValue {
+        KvsValue::Object(KvsMap::from([
+            ("name".to_string(), KvsValue::from("ecu")),
+            (
+                "sub-array".to_string(),
+                KvsValue::Array(vec![
+                    KvsValue::I32(0),
+                    KvsValue::I32(1),
+                    KvsValue::Object(KvsMap::from([("mtu".to_string(), KvsValue::I32(1500))])),
+                ]),
+            ),
+        ]))
+    }
+
+    #[test]
+    fn test_parse_path() {
+        assert_eq!(parse_path("key").unwrap(), ("key".to_string(), vec![]));
+        assert_eq!(
+            parse_path("obj.sub-array[2][0].mtu").unwrap(),
+            (
+                "obj".to_string(),
+                vec![
+                    PathSegment::Field("sub-array".to_string()),
+                    PathSegment::Index(2),
+                    PathSegment::Index(0),
+                    PathSegment::Field("mtu".to_string()),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_path_invalid() {
+        for path in [
+            "", "obj.", ".obj", "obj[", "obj[x]", "obj[1]x", "[1]", "obj]", "obj..a",
+        ] {
+            assert!(
+                parse_path(path).is_err_and(|e| e == ErrorCode::InvalidKey),
+                "{path}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_get_value_at_path() {
+        let kvs = get_kvs(KvsMap::new(), None);
+        kvs.set_value("obj", config()).unwrap();
+
+        assert_eq!(kvs.get_value_at_path("obj").unwrap(), config());
+        assert_eq!(
+            kvs.get_value_at_path("obj.name").unwrap(),
+            KvsValue::from("ecu")
+        );
+        assert_eq!(
+            kvs.get_value_at_path("obj.sub-array[2].mtu").unwrap(),
+            KvsValue::I32(1500)
+        );
+    }
+
+    #[test]
+    fn test_get_value_at_path_errors() {
+        let kvs = get_kvs(KvsMap::new(), None);
+        kvs.set_value("obj", config()).unwrap();
+
+        assert!(kvs
+            .get_value_at_path("other.name")
+            .is_err_and(|e| e == ErrorCode::KeyNotFound));
+        assert!(kvs
+            .get_value_at_path("obj.other")
+            .is_err_and(|e| e == ErrorCode::KeyNotFound));
+        assert!(kvs
+            .get_value_at_path("obj.sub-array[3]")
+            .is_err_and(|e| e == ErrorCode::KeyNotFound));
+        assert!(kvs
+            .get_value_at_path("obj.name[0]")
+            .is_err_and(|e| e == ErrorCode::ConversionFailed));
+        assert!(kvs
+            .get_value_at_path("obj[0]")
+            .is_err_and(|e| e == ErrorCode::ConversionFailed));
+    }
+
+    #[test]
+    fn test_set_value_at_path() {
+        let kvs = get_kvs(KvsMap::new(), None);
+        kvs.set_value("obj", config()).unwrap();
+
+        kvs.set_value_at_path("obj.sub-array[2].mtu", 9000).unwrap();
+        kvs.set_value_at_path("obj.sub-array[0]", "first").unwrap();
+        kvs.set_value_at_path("obj.added", true).unwrap();
+
+        assert_eq!(
+            kvs.get_value_at_path("obj.sub-array[2].mtu").unwrap(),
+            KvsValue::I32(9000)
+        );
+        assert_eq!(
+            kvs.get_value_at_path("obj.sub-array[0]").unwrap(),
+            KvsValue::from("first")
+        );
+        assert_eq!(
+            kvs.get_value_at_path("obj.added").unwrap(),
+            KvsValue::Boolean(true)
+        );
+        assert!(kvs.is_dirty().unwrap());
+    }
+
+    #[test]
+    fn test_set_value_at_path_errors() {
+        let kvs = get_kvs(KvsMap::new(), None);
+        kvs.set_value("obj", config()).unwrap();
+
+        assert!(kvs
+            .set_value_at_path("other.name", 1)
+            .is_err_and(|e| e == ErrorCode::KeyNotFound));
+        assert!(kvs
+            .set_value_at_path("obj.missing.name", 1)
+            .is_err_and(|e| e == ErrorCode::KeyNotFound));
+        assert!(kvs
+            .set_value_at_path("obj.sub-array[3]", 1)
+            .is_err_and(|e| e == ErrorCode::KeyNotFound));
+        assert!(kvs
+            .set_value_at_path("obj.name.first", 1)
+            .is_err_and(|e| e == ErrorCode::ConversionFailed));
+        assert_eq!(kvs.get_value("obj").unwrap(), config());
+    }
+
+    #[test]
+    fn test_set_value_at_path_default() {
+        let kvs = get_kvs(KvsMap::from([("obj".to_string(), config())]), None);
+
+        kvs.set_value_at_path("obj.name", "gateway").unwrap();
+
+        assert!(!kvs.is_value_default("obj").unwrap());
+        assert_eq!(
+            kvs.get_value_at_path("obj.name").unwrap(),
+            KvsValue::from("gateway")
+        );
+        assert_eq!(
+            kvs.get_value_at_path("obj.sub-array[2].mtu").unwrap(),
+            KvsValue::I32(1500)
+        );
+        assert_eq!(kvs.get_default_value("obj").unwrap(), config());
+    }
+
+    #[test]
+    fn test_set_value_at_path_unchanged() {
+        let kvs = get_kvs(KvsMap::from([("obj".to_string(), config())]), None);
+
+        kvs.set_value_at_path("obj.name", "ecu").unwrap();
+
+        assert!(kvs.is_value_default("obj").unwrap());
+        assert!(!kvs.is_dirty().unwrap());
+    }
+
+    #[test]
+    fn test_set_value_at_path_max_size() {
+        let kvs = get_kvs(KvsMap::new(), Some(250));
+        kvs.set_value("obj", config()).unwrap();
+
+        assert!(kvs
+            .set_value_at_path("obj.name", "x".repeat(100))
+            .is_err_and(|e| e == ErrorCode::QuotaExceeded));
+        assert!(kvs
+            .set_value_at_path("obj.added", "x".repeat(100))
+            .is_err_and(|e| e == ErrorCode::QuotaExceeded));
+        assert_eq!(kvs.get_value("obj").unwrap(), config());
+    }
+}
@@ -0,0 +1,120 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Redaction of sensitive values.
+//!
+//! Keys set with
+//! [`GenericKvsBuilder::sensitive_keys`](crate::kvs_builder::GenericKvsBuilder::sensitive_keys)
+//! hold values which must not appear in diagnostics, e.g. credentials. Diagnostics of this crate
+//! never contain values, code printing values for humans, like tools or metrics exporters, formats
+//! them with [`GenericKvs::redacted`], which prints [`REDACTED`] for sensitive keys. Values are
+//! stored, read and exported unchanged.
+
+use crate::kvs::GenericKvs;
+use crate::kvs_backend::{KvsBackend, KvsPathResolver};
+use crate::kvs_value::{KvsValue, ValueModel};
+use std::fmt;
+
+/// Text printed instead of a sensitive value.
+pub const REDACTED: &str = "<redacted>";
+
+/// Value formatted as [`REDACTED`] if its key is sensitive, see [`GenericKvs::redacted`].
+#[derive(Clone, Copy)]
+pub struct KvsRedacted<'a> {
+    value: &'a KvsValue,
+    sensitive: bool,
+}
+
+impl fmt::Display for KvsRedacted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.sensitive {
+            f.write_str(REDACTED)
+        } else {
+            fmt::Display::fmt(self.value, f)
+        }
+    }
+}
+
+impl fmt::Debug for KvsRedacted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.sensitive {
+            f.write_str(REDACTED)
+        } else {
+            fmt::Debug::fmt(self.value, f)
+        }
+    }
+}
+
+impl<Backend: KvsBackend, PathResolver: KvsPathResolver, Model: ValueModel>
+    GenericKvs<Backend, PathResolver, Model>
+{
+    /// Check if a key holds a sensitive value
+    ///
+    /// # Parameters
+    ///   * `key`: Key to check
+    ///
+    /// # Return Values
+    ///   * `true` if the key was set as sensitive
+    pub fn is_key_sensitive(&self, key: &str) -> bool {
+        self.parameters().sensitive_keys.contains(key)
+    }
+
+    /// Format a value of a key for diagnostics
+    ///
+    /// # Parameters
+    ///   * `key`: Key of the value
+    ///   * `value`: Value to format
+    ///
+    /// # Return Values
+    ///   * Value formatting as [`REDACTED`] if the key is sensitive, as the value otherwise
+    pub fn redacted<'a>(&self, key: &str, value: &'a KvsValue) -> KvsRedacted<'a> {
+        KvsRedacted {
+            value,
+            sensitive: self.is_key_sensitive(key),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "json-backend"))]
+mod kvs_redact_tests {
+    use crate::json_backend::JsonBackend;
+    use crate::kvs_api::{InstanceId, KvsApi};
+    use crate::kvs_builder::kvs_builder_tests::lock_and_reset;
+    use crate::kvs_builder::GenericKvsBuilder;
+    use crate::kvs_redact::REDACTED;
+    use crate::kvs_value::KvsValue;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_redacted() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let kvs = GenericKvsBuilder::<JsonBackend>::new(InstanceId(1))
+            .dir(dir.path().to_string_lossy().to_string())
+            .sensitive_keys(["wifi_psk"])
+            .build()
+            .unwrap();
+        kvs.set_value("wifi_psk", "secret").unwrap();
+        kvs.set_value("wifi_ssid", "home").unwrap();
+        assert!(kvs.is_key_sensitive("wifi_psk"));
+        assert!(!kvs.is_key_sensitive("wifi_ssid"));
+
+        let psk = kvs.get_value("wifi_psk").unwrap();
+        assert_eq!(psk, KvsValue::String("secret".to_string()));
+        assert_eq!(kvs.redacted("wifi_psk", &psk).to_string(), REDACTED);
+        assert_eq!(format!("{:?}", kvs.redacted("wifi_psk", &psk)), REDACTED);
+        let ssid = kvs.get_value("wifi_ssid").unwrap();
+        assert_eq!(
+            format!("{:?}", kvs.redacted("wifi_ssid", &ssid)),
+            format!("{ssid:?}")
+        );
+    }
+}
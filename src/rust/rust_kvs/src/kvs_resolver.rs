@@ -0,0 +1,211 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! File locations resolved at runtime.
+//!
+//! File names and locations are defined by the `KvsPathResolver` type parameter of the instance.
+//! A resolver set with
+//! [`GenericKvsBuilder::path_resolver`](crate::kvs_builder::GenericKvsBuilder::path_resolver)
+//! overrides them without defining a new type, e.g. to place hash files on a separate integrity
+//! partition:
+//!
+//! ```
+//! use rust_kvs::kvs_resolver::KvsFile;
+//! use rust_kvs::prelude::*;
+//! use std::path::{Path, PathBuf};
+//!
+//! let builder = KvsBuilder::new(InstanceId(0)).path_resolver(
+//!     |_instance_id: InstanceId, file: KvsFile, path: PathBuf| match file {
+//!         KvsFile::Hash(_) => Path::new("/integrity").join(path.file_name().unwrap()),
+//!         _ => path,
+//!     },
+//! );
+//! ```
+//!
+//! The resolver receives the path of the type parameter and returns the path to use. Instance
+//! discovery only considers the locations of the type parameter.
+
+use crate::kvs::KvsParameters;
+use crate::kvs_api::{InstanceId, SnapshotId};
+use crate::kvs_backend::KvsPathResolver;
+use crate::kvs_journal;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// File of an instance.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KvsFile {
+    /// KVS file of a snapshot.
+    Kvs(SnapshotId),
+
+    /// Hash file of a snapshot.
+    Hash(SnapshotId),
+
+    /// Instance defaults file.
+    Defaults,
+
+    /// Global defaults file of the working directory.
+    GlobalDefaults,
+
+    /// Journal file, see [`kvs_journal`](crate::kvs_journal).
+    Journal,
+
+    /// Read-only view shared with other processes, see `kvs_shared_view`.
+    SharedView,
+}
+
+/// Resolver of file paths at runtime.
+///
+/// Implemented for functions taking instance ID, file and default path.
+pub trait KvsRuntimePathResolver: Send + Sync {
+    /// Resolve the path of a file.
+    ///
+    /// # Parameters
+    ///   * `instance_id`: Instance ID
+    ///   * `file`: File to resolve
+    ///   * `path`: Path of the `KvsPathResolver` type parameter
+    ///
+    /// # Return Values
+    ///   * Path to use
+    fn resolve(&self, instance_id: InstanceId, file: KvsFile, path: PathBuf) -> PathBuf;
+}
+
+impl<F> KvsRuntimePathResolver for F
+where
+    F: Fn(InstanceId, KvsFile, PathBuf) -> PathBuf + Send + Sync,
+{
+    fn resolve(&self, instance_id: InstanceId, file: KvsFile, path: PathBuf) -> PathBuf {
+        self(instance_id, file, path)
+    }
+}
+
+/// Runtime resolver set for an instance.
+///
+/// Resolvers are equal if they share the same object.
+#[derive(Clone)]
+pub struct KvsPathOverride(pub Arc<dyn KvsRuntimePathResolver>);
+
+impl PartialEq for KvsPathOverride {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl fmt::Debug for KvsPathOverride {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("KvsPathOverride")
+    }
+}
+
+impl KvsParameters {
+    /// Resolve the path of a file of the instance.
+    ///
+    /// # Parameters
+    ///   * `file`: File to resolve
+    ///
+    /// # Return Values
+    ///   * Path of the type parameter, replaced by the runtime resolver if set
+    pub(crate) fn file_path<PathResolver: KvsPathResolver>(&self, file: KvsFile) -> PathBuf {
+        let working_dir = &self.working_dir;
+        let instance_id = self.instance_id;
+        let path = match file {
+            KvsFile::Kvs(snapshot_id) => {
+                PathResolver::kvs_file_path(working_dir, instance_id, snapshot_id)
+            }
+            KvsFile::Hash(snapshot_id) => {
+                PathResolver::hash_file_path(working_dir, instance_id, snapshot_id)
+            }
+            KvsFile::Defaults => PathResolver::defaults_file_path(working_dir, instance_id),
+            KvsFile::GlobalDefaults => PathResolver::global_defaults_file_path(working_dir),
+            KvsFile::Journal => working_dir.join(kvs_journal::journal_file_name(instance_id)),
+            KvsFile::SharedView => working_dir.join(format!("kvs_{instance_id}.shm")),
+        };
+        match &self.path_override {
+            Some(resolver) => resolver.0.resolve(instance_id, file, path),
+            None => path,
+        }
+    }
+
+    /// Resolve the path of a KVS file of the instance.
+    pub(crate) fn kvs_file_path<PathResolver: KvsPathResolver>(
+        &self,
+        snapshot_id: SnapshotId,
+    ) -> PathBuf {
+        self.file_path::<PathResolver>(KvsFile::Kvs(snapshot_id))
+    }
+
+    /// Resolve the path of a hash file of the instance.
+    pub(crate) fn hash_file_path<PathResolver: KvsPathResolver>(
+        &self,
+        snapshot_id: SnapshotId,
+    ) -> PathBuf {
+        self.file_path::<PathResolver>(KvsFile::Hash(snapshot_id))
+    }
+
+    /// Resolve the path of the journal file of the instance.
+    pub(crate) fn journal_file_path<PathResolver: KvsPathResolver>(&self) -> PathBuf {
+        self.file_path::<PathResolver>(KvsFile::Journal)
+    }
+
+    /// Resolve the path of the shared view file of the instance.
+    #[cfg(feature = "shm-cache")]
+    pub(crate) fn shared_view_file_path<PathResolver: KvsPathResolver>(&self) -> PathBuf {
+        self.file_path::<PathResolver>(KvsFile::SharedView)
+    }
+}
+
+#[cfg(all(test, feature = "json-backend", feature = "snapshots"))]
+mod kvs_resolver_tests {
+    use crate::kvs_api::{InstanceId, KvsApi, SnapshotId};
+    use crate::kvs_builder::kvs_builder_tests::lock_and_reset;
+    use crate::kvs_resolver::KvsFile;
+    use crate::KvsBuilder;
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_path_resolver() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let integrity_dir = dir.path().join("integrity");
+        std::fs::create_dir(&integrity_dir).unwrap();
+        let hash_dir = integrity_dir.clone();
+
+        let kvs = KvsBuilder::new(InstanceId(0))
+            .dir(dir.path().to_string_lossy().to_string())
+            .path_resolver(
+                move |instance_id: InstanceId, file: KvsFile, path: PathBuf| match file {
+                    KvsFile::Hash(SnapshotId(id)) => {
+                        hash_dir.join(format!("{instance_id}_{id}.hash"))
+                    }
+                    _ => path,
+                },
+            )
+            .build()
+            .unwrap();
+        kvs.set_value("key", "value").unwrap();
+        kvs.flush().unwrap();
+        kvs.set_value("key", "changed").unwrap();
+        kvs.flush().unwrap();
+
+        assert_eq!(
+            kvs.get_hash_filename(SnapshotId(0)).unwrap(),
+            integrity_dir.join("0_0.hash")
+        );
+        assert!(integrity_dir.join("0_1.hash").exists());
+        assert!(dir.path().join("kvs_0_0.json").exists());
+        assert!(!dir.path().join("kvs_0_0.hash").exists());
+
+        kvs.snapshot_restore(SnapshotId(1)).unwrap();
+        assert_eq!(kvs.get_value_as::<String>("key").unwrap(), "value");
+    }
+}
@@ -0,0 +1,305 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Retention of snapshots on rotation.
+//!
+//! Each flush rotates the stored snapshots by one, the oldest snapshot is overwritten once all
+//! [`snapshot_max_count`](crate::kvs_api::KvsApi::snapshot_max_count) slots are used. A
+//! [`KvsRetentionPolicy`] set with
+//! [`GenericKvsBuilder::retention`](crate::kvs_builder::GenericKvsBuilder::retention) is consulted
+//! after each rotation and may discard further snapshots. A discarded slot isn't refilled by the
+//! next rotation, so older snapshots move on only when the slot in front of them is used again,
+//! e.g. [`KeepDaily`] keeps a snapshot of the previous day instead of three of today.
+//!
+//! Functions registered with [`GenericKvs::on_snapshot_discard`] are called before any snapshot
+//! is discarded, by overwriting or by the policy, so it can be archived elsewhere.
+
+use crate::error_code::ErrorCode;
+use crate::kvs::{FlushHooks, GenericKvs, KVS_MAX_SNAPSHOTS};
+use crate::kvs_api::{InstanceId, SnapshotId};
+use crate::kvs_backend::{KvsBackend, KvsPathResolver};
+use crate::kvs_log::kvs_debug;
+use crate::kvs_value::ValueModel;
+use std::collections::HashSet;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Stored snapshot considered by a retention policy.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KvsRetainedSnapshot {
+    /// Snapshot ID, 1 for the most recent snapshot.
+    pub snapshot_id: SnapshotId,
+
+    /// Modification time of the snapshot file, `None` if the backend doesn't store files.
+    pub modified: Option<SystemTime>,
+}
+
+/// Snapshot about to be discarded.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KvsDiscardedSnapshot {
+    /// Snapshot ID.
+    pub snapshot_id: SnapshotId,
+
+    /// Path of the KVS file.
+    pub kvs_path: PathBuf,
+
+    /// Path of the hash file.
+    pub hash_path: PathBuf,
+}
+
+/// Function called before a snapshot is discarded, see [`GenericKvs::on_snapshot_discard`].
+pub type KvsSnapshotDiscardFn = dyn Fn(InstanceId, &KvsDiscardedSnapshot) + Send + Sync;
+
+/// Policy deciding which snapshots are kept after rotation.
+pub trait KvsRetentionPolicy: Send + Sync {
+    /// Select snapshots to discard
+    ///
+    /// # Parameters
+    ///   * `snapshots`: Stored snapshots, most recent first, the current KVS isn't included
+    ///
+    /// # Return Values
+    ///   * IDs of the snapshots to discard, unknown IDs are ignored
+    fn discard(&self, snapshots: &[KvsRetainedSnapshot]) -> Vec<SnapshotId>;
+}
+
+/// Keep the `N` most recent snapshots.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KeepLastN(pub usize);
+
+impl KvsRetentionPolicy for KeepLastN {
+    fn discard(&self, snapshots: &[KvsRetainedSnapshot]) -> Vec<SnapshotId> {
+        snapshots
+            .iter()
+            .skip(self.0)
+            .map(|snapshot| snapshot.snapshot_id)
+            .collect()
+    }
+}
+
+/// Keep the most recent snapshot of each of the last `N` days with snapshots.
+///
+/// Days are counted in UTC. Snapshots without a modification time are kept.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KeepDaily(pub usize);
+
+impl KvsRetentionPolicy for KeepDaily {
+    fn discard(&self, snapshots: &[KvsRetainedSnapshot]) -> Vec<SnapshotId> {
+        let mut days = HashSet::new();
+        snapshots
+            .iter()
+            .filter(|snapshot| {
+                let Some(modified) = snapshot.modified else {
+                    return false;
+                };
+                let day = modified
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |since| since.as_secs() / 86_400);
+                // Only the first, most recent snapshot of a day is kept.
+                !(days.len() < self.0 && days.insert(day))
+            })
+            .map(|snapshot| snapshot.snapshot_id)
+            .collect()
+    }
+}
+
+/// Retention policy set for an instance.
+///
+/// Policies are equal if they share the same object.
+#[derive(Clone)]
+pub struct KvsRetention(pub Arc<dyn KvsRetentionPolicy>);
+
+impl PartialEq for KvsRetention {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl fmt::Debug for KvsRetention {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("KvsRetention")
+    }
+}
+
+impl<Backend: KvsBackend, PathResolver: KvsPathResolver, Model: ValueModel>
+    GenericKvs<Backend, PathResolver, Model>
+{
+    /// Register a function called before a snapshot is discarded
+    ///
+    /// Called on rotation for the oldest snapshot before it's overwritten and for snapshots
+    /// discarded by the retention policy, while the files still exist. Hooks are shared by all
+    /// handles of the instance, run while the instance is locked and must not access the instance.
+    ///
+    /// # Parameters
+    ///   * `hook`: Function called with the instance ID and the discarded snapshot
+    ///
+    /// # Return Values
+    ///   * Ok: Hook registered
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    pub fn on_snapshot_discard<F>(&self, hook: F) -> Result<(), ErrorCode>
+    where
+        F: Fn(InstanceId, &KvsDiscardedSnapshot) + Send + Sync + 'static,
+    {
+        self.lock()?.flush_hooks.discard.push(Arc::new(hook));
+        Ok(())
+    }
+
+    /// Snapshot files of a snapshot ID.
+    fn discarded_snapshot(&self, snapshot_id: SnapshotId) -> KvsDiscardedSnapshot {
+        KvsDiscardedSnapshot {
+            snapshot_id,
+            kvs_path: self.parameters().kvs_file_path::<PathResolver>(snapshot_id),
+            hash_path: self
+                .parameters()
+                .hash_file_path::<PathResolver>(snapshot_id),
+        }
+    }
+
+    /// Call the discard hooks if a stored snapshot is discarded.
+    ///
+    /// # Return Values
+    ///   * Snapshot files, `None` if the snapshot doesn't exist
+    pub(crate) fn notify_discard(
+        &self,
+        hooks: &FlushHooks,
+        snapshot_id: SnapshotId,
+    ) -> Option<KvsDiscardedSnapshot> {
+        let snapshot = self.discarded_snapshot(snapshot_id);
+        if !Backend::exists(&snapshot.kvs_path) {
+            return None;
+        }
+        for hook in &hooks.discard {
+            hook(self.parameters().instance_id, &snapshot);
+        }
+        Some(snapshot)
+    }
+
+    /// Discard snapshots selected by the retention policy.
+    pub(crate) fn apply_retention(&self, hooks: &FlushHooks) -> Result<(), ErrorCode> {
+        let Some(retention) = &self.parameters().retention else {
+            return Ok(());
+        };
+        let snapshots: Vec<KvsRetainedSnapshot> = (1..=KVS_MAX_SNAPSHOTS)
+            .map(SnapshotId)
+            .filter_map(|snapshot_id| {
+                let kvs_path = self.parameters().kvs_file_path::<PathResolver>(snapshot_id);
+                Backend::exists(&kvs_path).then(|| KvsRetainedSnapshot {
+                    snapshot_id,
+                    modified: Backend::modified(&kvs_path),
+                })
+            })
+            .collect();
+        for snapshot_id in retention.0.discard(&snapshots) {
+            if !snapshots.iter().any(|s| s.snapshot_id == snapshot_id) {
+                continue;
+            }
+            if let Some(snapshot) = self.notify_discard(hooks, snapshot_id) {
+                kvs_debug!(
+                    instance_id = self.parameters().instance_id,
+                    "retention discards snapshot {snapshot_id}"
+                );
+                Backend::remove_kvs(&snapshot.kvs_path, &snapshot.hash_path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "json-backend"))]
+mod kvs_retention_tests {
+    use crate::json_backend::JsonBackend;
+    use crate::kvs_api::{InstanceId, KvsApi, SnapshotId};
+    use crate::kvs_builder::kvs_builder_tests::lock_and_reset;
+    use crate::kvs_builder::GenericKvsBuilder;
+    use crate::kvs_retention::{KeepDaily, KeepLastN, KvsRetainedSnapshot, KvsRetentionPolicy};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, UNIX_EPOCH};
+    use tempfile::tempdir;
+
+    fn snapshots(days: &[Option<u64>]) -> Vec<KvsRetainedSnapshot> {
+        days.iter()
+            .enumerate()
+            .map(|(idx, day)| KvsRetainedSnapshot {
+                snapshot_id: SnapshotId(idx + 1),
+                modified: day.map(|day| UNIX_EPOCH + Duration::from_secs(day * 86_400 + 60)),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_policies() {
+        let all = snapshots(&[Some(2), Some(2), Some(1)]);
+        assert_eq!(KeepLastN(1).discard(&all), [SnapshotId(2), SnapshotId(3)]);
+        assert!(KeepLastN(3).discard(&all).is_empty());
+        assert_eq!(KeepDaily(2).discard(&all), [SnapshotId(2)]);
+        assert_eq!(KeepDaily(1).discard(&all), [SnapshotId(2), SnapshotId(3)]);
+        assert_eq!(
+            KeepDaily(0).discard(&snapshots(&[None, Some(1)])),
+            [SnapshotId(2)]
+        );
+    }
+
+    #[test]
+    fn test_retention_and_discard_hook() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let kvs = GenericKvsBuilder::<JsonBackend>::new(InstanceId(1))
+            .dir(dir.path().to_string_lossy().to_string())
+            .retention(KeepLastN(1))
+            .build()
+            .unwrap();
+        let discarded = Arc::new(Mutex::new(Vec::new()));
+        let discarded_hook = discarded.clone();
+        kvs.on_snapshot_discard(move |instance_id, snapshot| {
+            assert_eq!(instance_id, InstanceId(1));
+            assert!(snapshot.kvs_path.exists() && snapshot.hash_path.exists());
+            let content = std::fs::read_to_string(&snapshot.kvs_path).unwrap();
+            discarded_hook
+                .lock()
+                .unwrap()
+                .push((snapshot.snapshot_id, content));
+        })
+        .unwrap();
+
+        for value in 0..3 {
+            kvs.set_value("counter", value).unwrap();
+            kvs.flush().unwrap();
+        }
+        assert_eq!(kvs.snapshot_count(), 2);
+        let discarded = discarded.lock().unwrap();
+        assert_eq!(discarded.len(), 1);
+        assert_eq!(discarded[0].0, SnapshotId(2));
+        assert!(discarded[0].1.contains("\"v\":0"));
+    }
+
+    #[test]
+    fn test_overwritten_snapshot_discarded() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let kvs = GenericKvsBuilder::<JsonBackend>::new(InstanceId(1))
+            .dir(dir.path().to_string_lossy().to_string())
+            .build()
+            .unwrap();
+        let discarded = Arc::new(Mutex::new(Vec::new()));
+        let discarded_hook = discarded.clone();
+        kvs.on_snapshot_discard(move |_, snapshot| {
+            discarded_hook.lock().unwrap().push(snapshot.snapshot_id);
+        })
+        .unwrap();
+
+        for value in 0..6 {
+            kvs.set_value("counter", value).unwrap();
+            kvs.flush().unwrap();
+        }
+        assert_eq!(*discarded.lock().unwrap(), [SnapshotId(3), SnapshotId(3)]);
+    }
+}
@@ -0,0 +1,203 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Storing `serde` types as `KvsValue` trees.
+//!
+//! Structs and maps are stored as `Object`, sequences and tuples as `Array`, unit and `None` as
+//! `Null`. Numbers follow the mapping of the `serde-json` conversions, e.g. a `u8` field is stored
+//! as `I32`. Reading converts back to the narrower field type and fails if the stored value is out
+//! of range.
+//!
+//! Non-finite floats can't be represented and are stored as `Null`.
+
+use crate::error_code::ErrorCode;
+use crate::kvs::GenericKvs;
+use crate::kvs_api::KvsApi;
+use crate::kvs_backend::{KvsBackend, KvsPathResolver};
+use crate::kvs_log::kvs_error;
+use crate::kvs_value::{KvsValue, ValueModel};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+impl<Backend: KvsBackend, PathResolver: KvsPathResolver, Model: ValueModel>
+    GenericKvs<Backend, PathResolver, Model>
+{
+    /// Assign a `serde` serializable value to a given key
+    ///
+    /// # Parameters
+    ///   * `key`: Key to set value
+    ///   * `value`: Value to be serialized and stored
+    ///
+    /// # Return Values
+    ///   * Ok: Value was assigned to key
+    ///   * `ErrorCode::SerializationFailed`: Value couldn't be serialized
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    ///   * `ErrorCode::QuotaExceeded`: Value would exceed the maximum size
+    pub fn set_struct<S: Into<String>, T: Serialize>(
+        &self,
+        key: S,
+        value: &T,
+    ) -> Result<(), ErrorCode> {
+        let value = serde_json::to_value(value).map_err(|e| {
+            kvs_error!("set_struct could not serialize value: {e}");
+            ErrorCode::SerializationFailed
+        })?;
+        self.set_value(key, KvsValue::from(value))
+    }
+
+    /// Get the assigned value for a given key deserialized into a `serde` type
+    ///
+    /// Default values are returned if the key was not written yet.
+    ///
+    /// # Parameters
+    ///   * `key`: Key to retrieve the value from
+    ///
+    /// # Return Values
+    ///   * Ok: Deserialized value
+    ///   * `ErrorCode::ConversionFailed`: Stored value doesn't match the type
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    ///   * `ErrorCode::KeyNotFound`: Key wasn't found in KVS nor in defaults
+    pub fn get_struct<T: DeserializeOwned>(&self, key: &str) -> Result<T, ErrorCode> {
+        let value = self.get_value(key)?;
+        serde_json::from_value(serde_json::Value::from(value)).map_err(|e| {
+            kvs_error!(key = key, "get_struct could not deserialize key {key}: {e}");
+            ErrorCode::ConversionFailed
+        })
+    }
+}
+
+#[cfg(all(test, feature = "json-backend"))]
+mod kvs_serde_tests {
+    use crate::error_code::ErrorCode;
+    use crate::json_backend::JsonBackend;
+    use crate::kvs::{GenericKvs, KvsParameters};
+    use crate::kvs_api::{InstanceId, KvsApi};
+    use crate::kvs_builder::KvsData;
+    use crate::kvs_value::{KvsMap, KvsValue};
+    use serde::{Deserialize, Serialize};
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Interface {
+        name: String,
+        mtu: u16,
+        enabled: bool,
+        addresses: Vec<String>,
+        gateway: Option<String>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct NetworkConfig {
+        hostname: String,
+        interfaces: Vec<Interface>,
+        timeout: f64,
+    }
+
+    fn get_kvs(defaults_map: KvsMap) -> GenericKvs<JsonBackend> {
+        let data = Arc::new(Mutex::new(KvsData::new(KvsMap::new(), defaults_map)));
+        let parameters = KvsParameters::new(InstanceId(1));
+        GenericKvs::new(data, parameters)
+    }
+
+    fn network_config() -> NetworkConfig {
+        NetworkConfig {
+            hostname: "ecu".to_string(),
+            interfaces: vec![Interface {
+                name: "eth0".to_string(),
+                mtu: 1500,
+                enabled: true,
+                addresses: vec!["10.0.0.1".to_string()],
+                gateway: None,
+            }],
+            timeout: 2.5,
+        }
+    }
+
+    #[test]
+    fn test_set_get_struct() {
+        let kvs = get_kvs(KvsMap::new());
+        kvs.set_struct("network", &network_config()).unwrap();
+
+        let config: NetworkConfig = kvs.get_struct("network").unwrap();
+        assert_eq!(config, network_config());
+    }
+
+    #[test]
+    fn test_set_struct_object_tree() {
+        let kvs = get_kvs(KvsMap::new());
+        kvs.set_struct("network", &network_config()).unwrap();
+
+        let KvsValue::Object(network) = kvs.get_value("network").unwrap() else {
+            panic!("network is not an object");
+        };
+        assert_eq!(network["hostname"], KvsValue::from("ecu"));
+        assert_eq!(network["timeout"], KvsValue::F64(2.5));
+        let KvsValue::Array(interfaces) = &network["interfaces"] else {
+            panic!("interfaces is not an array");
+        };
+        let KvsValue::Object(interface) = &interfaces[0] else {
+            panic!("interface is not an object");
+        };
+        assert_eq!(interface["mtu"], KvsValue::I32(1500));
+        assert_eq!(interface["gateway"], KvsValue::Null);
+    }
+
+    #[test]
+    fn test_get_struct_default() {
+        let kvs = get_kvs(KvsMap::from([(
+            "interface".to_string(),
+            KvsValue::Object(KvsMap::from([
+                ("name".to_string(), KvsValue::from("eth1")),
+                ("mtu".to_string(), KvsValue::U32(9000)),
+                ("enabled".to_string(), KvsValue::from(false)),
+                ("addresses".to_string(), KvsValue::Array(vec![])),
+                ("gateway".to_string(), KvsValue::from("10.0.0.254")),
+            ])),
+        )]));
+
+        let interface: Interface = kvs.get_struct("interface").unwrap();
+        assert_eq!(interface.mtu, 9000);
+        assert_eq!(interface.gateway, Some("10.0.0.254".to_string()));
+    }
+
+    #[test]
+    fn test_get_struct_mismatch() {
+        let kvs = get_kvs(KvsMap::new());
+        kvs.set_value("interface", KvsValue::from("eth0")).unwrap();
+        assert!(kvs
+            .get_struct::<Interface>("interface")
+            .is_err_and(|e| e == ErrorCode::ConversionFailed));
+
+        kvs.set_value("mtu", KvsValue::I32(70000)).unwrap();
+        assert!(kvs
+            .get_struct::<u16>("mtu")
+            .is_err_and(|e| e == ErrorCode::ConversionFailed));
+    }
+
+    #[test]
+    fn test_get_struct_not_found() {
+        let kvs = get_kvs(KvsMap::new());
+        assert!(kvs
+            .get_struct::<Interface>("interface")
+            .is_err_and(|e| e == ErrorCode::KeyNotFound));
+    }
+
+    #[test]
+    fn test_set_struct_non_string_keys() {
+        let kvs = get_kvs(KvsMap::new());
+        let value = HashMap::from([((1, 2), "tuple key")]);
+        assert!(kvs
+            .set_struct("map", &value)
+            .is_err_and(|e| e == ErrorCode::SerializationFailed));
+        assert!(!kvs.key_exists("map").unwrap());
+    }
+}
@@ -0,0 +1,416 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Read-only view of an instance shared between processes.
+//!
+//! The writing process enables publishing with
+//! [`GenericKvsBuilder::shared_view`](crate::kvs_builder::GenericKvsBuilder::shared_view). The
+//! effective values, stored values over defaults, are then written to the view file
+//! `kvs_<instance_id>.shm` when the instance is opened and on every flush. Reading processes open
+//! it with [`KvsSharedView::open`], which maps the file read-only instead of loading it. The pages
+//! are shared by all readers through the page cache and a value is only parsed when its key is
+//! read. Placing the working directory, or the view file with
+//! [`path_resolver`](crate::kvs_builder::GenericKvsBuilder::path_resolver), on a `tmpfs` keeps
+//! the view in memory.
+//!
+//! A new view replaces the file atomically, mapped views keep their contents until
+//! [`KvsSharedView::refresh`] maps the new one.
+//!
+//! Layout, all integers little-endian:
+//! ```text
+//! magic "KVSSHM1\0" | generation u64 | hash u32 | count u32
+//! count * (key offset u32 | key length u32 | value offset u32 | value length u32), sorted by key
+//! keys and values, values as type-tagged JSON
+//! ```
+//! The hash covers everything following the header.
+
+use crate::error_code::ErrorCode;
+use crate::kvs::GenericKvs;
+use crate::kvs_api::InstanceId;
+use crate::kvs_backend::{KvsBackend, KvsPathResolver};
+use crate::kvs_builder::KvsData;
+use crate::kvs_log::{kvs_debug, kvs_error};
+use crate::kvs_storage::kvs_hash;
+use crate::kvs_value::{KvsDeserialize, KvsMap, KvsValue, ValueModel};
+use memmap2::Mmap;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tinyjson::JsonValue;
+
+/// Magic bytes starting a view file.
+const MAGIC: &[u8; 8] = b"KVSSHM1\0";
+
+/// Size of the header.
+const HEADER_LEN: usize = 24;
+
+/// Size of an index entry.
+const ENTRY_LEN: usize = 16;
+
+/// Generation of the last view written by this process.
+static LAST_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Read a little-endian `u32`, the offset must be in bounds.
+fn read_u32(buf: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+}
+
+/// Read a little-endian `u64`, the offset must be in bounds.
+fn read_u64(buf: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(buf[offset..offset + 8].try_into().unwrap())
+}
+
+/// Serialize the effective values of an instance to a view.
+///
+/// # Return Values
+///   * Ok: View file contents
+///   * `ErrorCode::JsonGeneratorError`: A value could not be serialized
+///   * `ErrorCode::OutOfStorageSpace`: View exceeds 4 GiB
+fn serialize(kvs_map: &KvsMap, defaults_map: &KvsMap) -> Result<Vec<u8>, ErrorCode> {
+    let mut effective: BTreeMap<&str, &KvsValue> = BTreeMap::new();
+    effective.extend(
+        defaults_map
+            .iter()
+            .map(|(key, value)| (key.as_str(), value)),
+    );
+    effective.extend(kvs_map.iter().map(|(key, value)| (key.as_str(), value)));
+
+    let data_start = HEADER_LEN + effective.len() * ENTRY_LEN;
+    let mut index = Vec::with_capacity(effective.len() * ENTRY_LEN);
+    let mut content = Vec::new();
+    let offset = |content: &Vec<u8>| {
+        u32::try_from(data_start + content.len()).map_err(|_| ErrorCode::OutOfStorageSpace)
+    };
+    for (key, value) in effective {
+        let value = JsonValue::from(value.clone()).stringify()?;
+        for part in [key.as_bytes(), value.as_bytes()] {
+            index.extend_from_slice(&offset(&content)?.to_le_bytes());
+            index.extend_from_slice(&(part.len() as u32).to_le_bytes());
+            content.extend_from_slice(part);
+        }
+        offset(&content)?;
+    }
+
+    // Generations increase also if views are written faster than the clock resolution.
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_nanos() as u64);
+    let next = |last: u64| last.max(now) + 1;
+    let generation = LAST_GENERATION
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |last| {
+            Some(next(last))
+        })
+        .map_or_else(next, next);
+
+    let count = (index.len() / ENTRY_LEN) as u32;
+    index.extend_from_slice(&content);
+    let mut buf = Vec::with_capacity(HEADER_LEN + index.len());
+    buf.extend_from_slice(MAGIC);
+    buf.extend_from_slice(&generation.to_le_bytes());
+    buf.extend_from_slice(&kvs_hash(&index).to_le_bytes());
+    buf.extend_from_slice(&count.to_le_bytes());
+    buf.extend_from_slice(&index);
+    Ok(buf)
+}
+
+/// Write a view file, replacing the previous view atomically.
+fn publish(path: &Path, kvs_map: &KvsMap, defaults_map: &KvsMap) -> Result<(), ErrorCode> {
+    let buf = serialize(kvs_map, defaults_map)?;
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+    fs::write(&tmp_path, buf)
+        .and_then(|()| fs::rename(&tmp_path, path))
+        .map_err(|e| ErrorCode::from(e).with_path(path))
+}
+
+/// Read-only view of an instance mapped from its view file.
+pub struct KvsSharedView {
+    /// View file.
+    path: PathBuf,
+
+    /// Mapped view file.
+    mmap: Mmap,
+
+    /// Count of keys.
+    count: usize,
+}
+
+impl KvsSharedView {
+    /// Path of the view file of an instance with default file names.
+    ///
+    /// # Parameters
+    ///   * `working_dir`: Working directory of the instance
+    ///   * `instance_id`: Instance ID
+    pub fn instance_path(working_dir: &Path, instance_id: InstanceId) -> PathBuf {
+        working_dir.join(format!("kvs_{instance_id}.shm"))
+    }
+
+    /// Map a view file
+    ///
+    /// The file is validated once, values are parsed when read.
+    ///
+    /// # Parameters
+    ///   * `path`: View file
+    ///
+    /// # Return Values
+    ///   * Ok: Mapped view
+    ///   * `ErrorCode::FileNotFound`: View file not found
+    ///   * `ErrorCode::ValidationFailed`: File isn't a valid view or its hash doesn't match
+    pub fn open(path: &Path) -> Result<Self, ErrorCode> {
+        let file = File::open(path).map_err(|e| ErrorCode::from(e).with_path(path))?;
+        // SAFETY: View files are replaced by renaming and never modified in place.
+        #[allow(unsafe_code)]
+        let mmap = unsafe { Mmap::map(&file) }.map_err(|e| ErrorCode::from(e).with_path(path))?;
+        let count = Self::validate(&mmap).map_err(|e| {
+            kvs_error!("invalid shared view {}", path.display());
+            e.with_path(path)
+        })?;
+        kvs_debug!("mapped shared view {} with {count} keys", path.display());
+        Ok(Self {
+            path: path.to_path_buf(),
+            mmap,
+            count,
+        })
+    }
+
+    /// Check the header, hash and index of a view.
+    ///
+    /// # Return Values
+    ///   * Ok: Count of keys
+    ///   * `ErrorCode::ValidationFailed`: View is invalid
+    fn validate(buf: &[u8]) -> Result<usize, ErrorCode> {
+        if buf.len() < HEADER_LEN || &buf[..8] != MAGIC {
+            return Err(ErrorCode::ValidationFailed);
+        }
+        if kvs_hash(&buf[HEADER_LEN..]) != read_u32(buf, 16) {
+            return Err(ErrorCode::ValidationFailed);
+        }
+        let count = read_u32(buf, 20) as usize;
+        let index_end = count
+            .checked_mul(ENTRY_LEN)
+            .and_then(|len| len.checked_add(HEADER_LEN))
+            .filter(|end| *end <= buf.len())
+            .ok_or(ErrorCode::ValidationFailed)?;
+        for entry in (HEADER_LEN..index_end).step_by(8) {
+            let start = read_u32(buf, entry) as usize;
+            let end = start + read_u32(buf, entry + 4) as usize;
+            if start < index_end || end > buf.len() {
+                return Err(ErrorCode::ValidationFailed);
+            }
+        }
+        for idx in 0..count {
+            if std::str::from_utf8(Self::part(buf, idx, 0)).is_err() {
+                return Err(ErrorCode::ValidationFailed);
+            }
+        }
+        Ok(count)
+    }
+
+    /// Key (`part` 0) or value (`part` 1) of an index entry of a validated view.
+    fn part(buf: &[u8], idx: usize, part: usize) -> &[u8] {
+        let entry = HEADER_LEN + idx * ENTRY_LEN + part * 8;
+        let start = read_u32(buf, entry) as usize;
+        &buf[start..start + read_u32(buf, entry + 4) as usize]
+    }
+
+    /// Key of an index entry.
+    fn key(&self, idx: usize) -> &str {
+        // Keys were validated on open.
+        std::str::from_utf8(Self::part(&self.mmap, idx, 0)).unwrap_or_default()
+    }
+
+    /// Find the index entry of a key.
+    fn find(&self, key: &str) -> Option<usize> {
+        let (mut low, mut high) = (0, self.count);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match self.key(mid).cmp(key) {
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Greater => high = mid,
+                std::cmp::Ordering::Equal => return Some(mid),
+            }
+        }
+        None
+    }
+
+    /// Generation of the mapped view, increasing with every published view.
+    pub fn generation(&self) -> u64 {
+        read_u64(&self.mmap, 8)
+    }
+
+    /// Count of keys in the view.
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    /// Whether the view has no keys.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Keys of the view, sorted.
+    pub fn keys(&self) -> Vec<String> {
+        (0..self.count)
+            .map(|idx| self.key(idx).to_string())
+            .collect()
+    }
+
+    /// Check if a key exists in the view.
+    pub fn key_exists(&self, key: &str) -> bool {
+        self.find(key).is_some()
+    }
+
+    /// Get the value of a key
+    ///
+    /// # Parameters
+    ///   * `key`: Key to retrieve the value from
+    ///
+    /// # Return Values
+    ///   * Ok: Value of the key
+    ///   * `ErrorCode::KeyNotFound`: Key not in the view
+    ///   * `ErrorCode::JsonParserError`: Value could not be parsed
+    pub fn get_value(&self, key: &str) -> Result<KvsValue, ErrorCode> {
+        let idx = self
+            .find(key)
+            .ok_or_else(|| ErrorCode::KeyNotFound.with_key(key))?;
+        let json = std::str::from_utf8(Self::part(&self.mmap, idx, 1))
+            .map_err(|_| ErrorCode::JsonParserError.with_key(key))?;
+        let value: JsonValue = json.parse().map_err(|e| ErrorCode::from(e).with_key(key))?;
+        Ok(KvsValue::from(value))
+    }
+
+    /// Get the value of a key converted to `T`
+    ///
+    /// # Parameters
+    ///   * `key`: Key to retrieve the value from
+    ///
+    /// # Return Values
+    ///   * Ok: Converted value
+    ///   * `ErrorCode::ConversionFailed`: Value has another type
+    ///   * Errors of [`KvsSharedView::get_value`]
+    pub fn get_value_as<T: KvsDeserialize>(&self, key: &str) -> Result<T, ErrorCode> {
+        T::from_kvs_value(&self.get_value(key)?).map_err(|e| e.with_key(key))
+    }
+
+    /// Map the view file again if a newer view was published
+    ///
+    /// # Return Values
+    ///   * Ok(true): Newer view mapped
+    ///   * Ok(false): View is up to date
+    ///   * Errors of [`KvsSharedView::open`], the current view is kept
+    pub fn refresh(&mut self) -> Result<bool, ErrorCode> {
+        let mut header = [0u8; HEADER_LEN];
+        let read = File::open(&self.path)
+            .and_then(|mut file| std::io::Read::read_exact(&mut file, &mut header));
+        if read.is_ok() && &header[..8] == MAGIC && read_u64(&header, 8) == self.generation() {
+            return Ok(false);
+        }
+        *self = Self::open(&self.path)?;
+        Ok(true)
+    }
+}
+
+impl<Backend: KvsBackend, PathResolver: KvsPathResolver, Model: ValueModel>
+    GenericKvs<Backend, PathResolver, Model>
+{
+    /// Publish the effective values to the view file if enabled.
+    ///
+    /// Failures are logged, the view is optional for the writing instance.
+    pub(crate) fn publish_shared_view(&self, data: &KvsData) {
+        let parameters = self.parameters();
+        if !parameters.shared_view {
+            return;
+        }
+        let path = parameters.shared_view_file_path::<PathResolver>();
+        if let Err(e) = publish(&path, &data.kvs_map, &data.defaults_map) {
+            kvs_error!(
+                instance_id = parameters.instance_id,
+                "publishing shared view failed: {e}"
+            );
+        }
+    }
+}
+
+#[cfg(all(test, feature = "json-backend"))]
+mod kvs_shared_view_tests {
+    use crate::error_code::ErrorCode;
+    use crate::json_backend::JsonBackend;
+    use crate::kvs_api::{InstanceId, KvsApi};
+    use crate::kvs_builder::kvs_builder_tests::lock_and_reset;
+    use crate::kvs_builder::GenericKvsBuilder;
+    use crate::kvs_shared_view::{serialize, KvsSharedView};
+    use crate::kvs_value::{KvsMap, KvsValue};
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_serialize_invalid() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("view.shm");
+        let kvs_map = KvsMap::from([("key".to_string(), KvsValue::from("value"))]);
+        let mut buf = serialize(&kvs_map, &KvsMap::new()).unwrap();
+        fs::write(&path, &buf).unwrap();
+        assert_eq!(
+            KvsSharedView::open(&path)
+                .unwrap()
+                .get_value("key")
+                .unwrap(),
+            KvsValue::from("value")
+        );
+
+        let last = buf.len() - 1;
+        buf[last] ^= 1;
+        fs::write(&path, &buf).unwrap();
+        assert!(KvsSharedView::open(&path).is_err_and(|e| e == ErrorCode::ValidationFailed));
+        fs::write(&path, b"KVSSHM1").unwrap();
+        assert!(KvsSharedView::open(&path).is_err_and(|e| e == ErrorCode::ValidationFailed));
+    }
+
+    #[test]
+    fn test_published_on_flush() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let path = KvsSharedView::instance_path(dir.path(), InstanceId(1));
+        let kvs = GenericKvsBuilder::<JsonBackend>::new(InstanceId(1))
+            .dir(dir.path().to_string_lossy().to_string())
+            .defaults_map(KvsMap::from([
+                ("timeout".to_string(), KvsValue::U32(10)),
+                ("name".to_string(), KvsValue::from("default")),
+            ]))
+            .shared_view(true)
+            .build()
+            .unwrap();
+
+        let mut view = KvsSharedView::open(&path).unwrap();
+        assert_eq!(view.keys(), ["name", "timeout"]);
+        assert!(!view.refresh().unwrap());
+
+        kvs.set_value("timeout", 20u32).unwrap();
+        kvs.set_value("list", vec![KvsValue::Null]).unwrap();
+        kvs.flush().unwrap();
+        assert_eq!(view.get_value_as::<u32>("timeout").unwrap(), 10);
+        assert!(view.refresh().unwrap());
+        assert_eq!(view.len(), 3);
+        assert_eq!(view.get_value_as::<u32>("timeout").unwrap(), 20);
+        assert_eq!(view.get_value_as::<String>("name").unwrap(), "default");
+        assert_eq!(
+            view.get_value("list").unwrap(),
+            KvsValue::Array(vec![KvsValue::Null])
+        );
+        assert!(view
+            .get_value("missing")
+            .is_err_and(|e| e == ErrorCode::KeyNotFound));
+        assert!(!view.key_exists("missing"));
+    }
+}
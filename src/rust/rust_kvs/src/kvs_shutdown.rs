@@ -0,0 +1,369 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Flushing of all open instances on process shutdown.
+//!
+//! With the `signal-flush` feature, [`install_signal_flush`] flushes all open instances when
+//! `SIGTERM` or `SIGINT` is received and terminates the process afterwards. Flushing is bounded by
+//! the shutdown window granted by the execution manager: instances still locked by other threads
+//! when the window elapses are skipped. A write which already started is always completed, the
+//! process never terminates while a KVS file is being written by the flush.
+//!
+//! [`install_panic_flush_hook`] flushes all modified instances when a panic occurs.
+
+use crate::error_code::ErrorCode;
+use crate::kvs_api::InstanceId;
+use crate::kvs_builder;
+use crate::kvs_log::kvs_error;
+use std::panic;
+use std::sync::TryLockError;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Interval between attempts to lock an instance in [`flush_all_within`].
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Flush result of an instance.
+pub type FlushResult = (InstanceId, Result<(), ErrorCode>);
+
+/// Flush all open instances.
+///
+/// Each instance is flushed with the backend and path resolver it was opened with.
+///
+/// # Return Values
+///   * Ok: Flush result of each open instance, ordered by instance ID
+///   * `ErrorCode::MutexLockFailed`: Instance pool lock failed
+pub fn flush_all() -> Result<Vec<FlushResult>, ErrorCode> {
+    let instances = kvs_builder::open_instances()?;
+    Ok(instances
+        .into_iter()
+        .map(|kvs_inner| (kvs_inner.parameters.instance_id, kvs_inner.flush(None)))
+        .collect())
+}
+
+/// Flush all open instances, waiting at most `timeout` for locked instances.
+///
+/// Instances which can't be locked before `timeout` elapses are skipped with
+/// `ErrorCode::Timeout`. A started flush is always completed, the call may return after `timeout`.
+///
+/// # Return Values
+///   * true: All instances flushed successfully within timeout
+///   * false: Flush failed or timed out
+pub fn flush_all_within(timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    let instances = loop {
+        if let Some(instances) = kvs_builder::try_open_instances() {
+            break instances;
+        }
+        if Instant::now() >= deadline {
+            kvs_error!("flushing open instances timed out");
+            return false;
+        }
+        thread::sleep(LOCK_RETRY_INTERVAL);
+    };
+
+    let mut success = true;
+    for kvs_inner in instances {
+        let instance_id = kvs_inner.parameters.instance_id;
+        let result = loop {
+            match kvs_inner.data.try_lock() {
+                Ok(mut data) => break kvs_inner.flush(Some(&mut data)),
+                Err(TryLockError::Poisoned(_)) => break kvs_inner.flush(None),
+                Err(TryLockError::WouldBlock) if Instant::now() >= deadline => {
+                    break Err(ErrorCode::Timeout)
+                }
+                Err(TryLockError::WouldBlock) => thread::sleep(LOCK_RETRY_INTERVAL),
+            }
+        };
+        if let Err(e) = result {
+            kvs_error!(
+                instance_id = instance_id,
+                "flushing instance {instance_id} failed: {e}"
+            );
+            success = false;
+        }
+    }
+    success
+}
+
+/// Flush all modified instances without blocking.
+///
+/// Instances which are locked or poisoned are skipped, as is the whole pool if it is locked.
+/// Each instance is flushed with the backend and path resolver it was opened with.
+pub fn flush_dirty() -> Vec<FlushResult> {
+    let Some(instances) = kvs_builder::try_open_instances() else {
+        return Vec::new();
+    };
+
+    let mut results = Vec::new();
+    for kvs_inner in instances {
+        let Ok(mut data) = kvs_inner.data.try_lock() else {
+            continue;
+        };
+        if !data.dirty {
+            continue;
+        }
+        results.push((
+            kvs_inner.parameters.instance_id,
+            kvs_inner.flush(Some(&mut data)),
+        ));
+    }
+    results
+}
+
+/// Install panic hook flushing all modified instances before unwinding.
+///
+/// Flushing is best-effort, see [`flush_dirty`]. The previously installed hook is called
+/// afterwards.
+pub fn install_panic_flush_hook() {
+    let previous_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        for (instance_id, result) in flush_dirty() {
+            if let Err(e) = result {
+                kvs_error!(
+                    instance_id = instance_id,
+                    "flushing instance {instance_id} on panic failed: {e:?}"
+                );
+            }
+        }
+        previous_hook(info);
+    }));
+}
+
+/// Installed signal flush handler.
+#[cfg(feature = "signal-flush")]
+pub struct SignalFlushHandle {
+    handle: signal_hook::iterator::Handle,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+#[cfg(feature = "signal-flush")]
+impl SignalFlushHandle {
+    /// Stop handling signals.
+    ///
+    /// Signals received afterwards are no longer turned into flushes.
+    pub fn uninstall(mut self) {
+        self.handle.close();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Install `SIGTERM` and `SIGINT` handlers flushing all open instances before exit.
+///
+/// After flushing, the default signal action is performed. Instances still locked when
+/// `shutdown_window` elapses are skipped, see [`flush_all_within`].
+///
+/// # Parameters
+///   * `shutdown_window`: Maximum time spent waiting for locked instances
+///
+/// # Return Values
+///   * Ok: Handle of installed handler
+///   * `ErrorCode::UnmappedError`: Signal handler registration failed
+#[cfg(feature = "signal-flush")]
+pub fn install_signal_flush(shutdown_window: Duration) -> Result<SignalFlushHandle, ErrorCode> {
+    use signal_hook::consts::{SIGINT, SIGTERM};
+    use signal_hook::iterator::Signals;
+
+    let mut signals = Signals::new([SIGTERM, SIGINT])?;
+    let handle = signals.handle();
+    let thread = thread::spawn(move || {
+        if let Some(signal) = signals.forever().next() {
+            flush_all_within(shutdown_window);
+            let _ = signal_hook::low_level::emulate_default_handler(signal);
+            std::process::exit(128 + signal);
+        }
+    });
+
+    Ok(SignalFlushHandle {
+        handle,
+        thread: Some(thread),
+    })
+}
+
+#[cfg(all(test, feature = "json-backend"))]
+mod kvs_shutdown_tests {
+    use crate::json_backend::JsonBackend;
+    use crate::kvs_api::{InstanceId, KvsApi, SnapshotId};
+    use crate::kvs_backend::KvsPathResolver;
+    use crate::kvs_builder::kvs_builder_tests::lock_and_reset;
+    use crate::kvs_builder::GenericKvsBuilder;
+    use crate::kvs_shutdown::{flush_all, flush_all_within, flush_dirty, install_panic_flush_hook};
+    use crate::kvs_value::KvsValue;
+    use crate::memory_backend::MemoryBackend;
+    use std::panic;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_flush_all() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+
+        for id in [1, 4] {
+            let kvs = GenericKvsBuilder::<JsonBackend>::new(InstanceId(id))
+                .dir(dir_string.clone())
+                .build()
+                .unwrap();
+            kvs.set_value("key", id as i32).unwrap();
+        }
+
+        let results = flush_all().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, InstanceId(1));
+        assert_eq!(results[1].0, InstanceId(4));
+        assert!(results.iter().all(|(_, r)| r.is_ok()));
+
+        let kvs = GenericKvsBuilder::<JsonBackend>::new(InstanceId(4))
+            .dir(dir_string)
+            .build()
+            .unwrap();
+        assert!(kvs.get_kvs_filename(SnapshotId(0)).is_ok());
+    }
+
+    #[test]
+    fn test_flush_all_backend() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        MemoryBackend::clear_dir(dir.path()).unwrap();
+        let kvs = GenericKvsBuilder::<MemoryBackend>::new(InstanceId(1))
+            .dir(dir.path().to_string_lossy().to_string())
+            .build()
+            .unwrap();
+        kvs.set_value("key", 1).unwrap();
+
+        let results = flush_all().unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].1.is_ok());
+
+        // Flushed with the backend the instance was opened with.
+        let kvs_path = MemoryBackend::kvs_file_path(dir.path(), InstanceId(1), SnapshotId(0));
+        let kvs_map = MemoryBackend::get(&kvs_path).unwrap().unwrap();
+        assert_eq!(kvs_map.get("key"), Some(&KvsValue::I32(1)));
+        assert!(!kvs_path.exists());
+        assert!(!JsonBackend::kvs_file_path(dir.path(), InstanceId(1), SnapshotId(0)).exists());
+
+        kvs.set_value("key", 2).unwrap();
+        let results = flush_dirty();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].1.is_ok());
+        let kvs_map = MemoryBackend::get(&kvs_path).unwrap().unwrap();
+        assert_eq!(kvs_map.get("key"), Some(&KvsValue::I32(2)));
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_flush_all_within() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let kvs = GenericKvsBuilder::<JsonBackend>::new(InstanceId(2))
+            .dir(dir.path().to_string_lossy().to_string())
+            .build()
+            .unwrap();
+        kvs.set_value("key", 1).unwrap();
+
+        assert!(flush_all_within(Duration::from_secs(10)));
+        assert!(kvs.get_kvs_filename(SnapshotId(0)).is_ok());
+    }
+
+    #[test]
+    fn test_flush_all_within_failure() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let kvs = GenericKvsBuilder::<JsonBackend>::new(InstanceId(3))
+            .dir(dir.path().join("missing").to_string_lossy().to_string())
+            .build()
+            .unwrap();
+        kvs.set_value("key", 1).unwrap();
+
+        assert!(!flush_all_within(Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn test_flush_all_within_locked() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+
+        let kvs: Vec<_> = (1..=2)
+            .map(|id| {
+                let kvs = GenericKvsBuilder::<JsonBackend>::new(InstanceId(id))
+                    .dir(dir_string.clone())
+                    .build()
+                    .unwrap();
+                kvs.set_value("key", id as i32).unwrap();
+                kvs
+            })
+            .collect();
+
+        // Locked instance is skipped after timeout, others are flushed.
+        let guard = kvs[0].data.lock().unwrap();
+        assert!(!flush_all_within(Duration::from_millis(20)));
+        drop(guard);
+        assert!(kvs[0].get_kvs_filename(SnapshotId(0)).is_err());
+        assert!(kvs[1].get_kvs_filename(SnapshotId(0)).is_ok());
+    }
+
+    #[test]
+    fn test_flush_dirty() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+
+        let kvs: Vec<_> = (1..=3)
+            .map(|id| {
+                GenericKvsBuilder::<JsonBackend>::new(InstanceId(id))
+                    .dir(dir_string.clone())
+                    .build()
+                    .unwrap()
+            })
+            .collect();
+        kvs[0].set_value("key", 1).unwrap();
+        kvs[2].set_value("key", 3).unwrap();
+
+        // Locked instance is skipped.
+        let guard = kvs[2].data.lock().unwrap();
+        let results = flush_dirty();
+        drop(guard);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, InstanceId(1));
+        assert!(results[0].1.is_ok());
+
+        // Flushed instance is no longer dirty.
+        let results = flush_dirty();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, InstanceId(3));
+        assert!(flush_dirty().is_empty());
+        assert!(kvs[1].get_kvs_filename(SnapshotId(0)).is_err());
+    }
+
+    #[test]
+    fn test_panic_flush_hook() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let kvs = GenericKvsBuilder::<JsonBackend>::new(InstanceId(5))
+            .dir(dir.path().to_string_lossy().to_string())
+            .build()
+            .unwrap();
+        kvs.set_value("key", 5).unwrap();
+
+        let default_hook = panic::take_hook();
+        install_panic_flush_hook();
+        let result = panic::catch_unwind(|| panic!("test panic"));
+        let _ = panic::take_hook();
+        panic::set_hook(default_hook);
+
+        assert!(result.is_err());
+        assert!(kvs.get_kvs_filename(SnapshotId(0)).is_ok());
+    }
+}
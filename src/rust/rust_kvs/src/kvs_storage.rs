@@ -0,0 +1,252 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Storage abstraction available without `std`.
+//!
+//! [`KvsStorage`] stores serialized snapshots together with their hash. Targets without a file
+//! system, e.g. RTOS-based controllers, implement it on top of their flash driver and get the same
+//! integrity check as the file-based backends.
+
+use crate::error_code::ErrorCode;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// Instance ID
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InstanceId(pub usize);
+
+/// Displays the name of named instances, the number otherwise.
+impl fmt::Display for InstanceId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        #[cfg(feature = "std")]
+        if let Some(name) = self.name() {
+            return f.write_str(&name);
+        }
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl InstanceId {
+    /// Get the instance ID of a named instance
+    ///
+    /// The name is registered on first use and keeps its ID for the lifetime of the process, see
+    /// [`kvs_instance_name`](crate::kvs_instance_name). Named IDs never equal numeric IDs and
+    /// don't take instance pool slots from them.
+    ///
+    /// # Parameters
+    ///   * `name`: Instance name, ASCII letters, digits, `-` and `_`, not numeric
+    ///
+    /// # Return Values
+    ///   * Ok: Instance ID
+    ///   * `ErrorCode::InvalidInstanceId`: Invalid name or no free instance ID
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    pub fn from_name(name: &str) -> Result<Self, ErrorCode> {
+        crate::kvs_instance_name::register(name)
+    }
+
+    /// Get the registered name of the instance ID, `None` for unnamed instances.
+    pub fn name(&self) -> Option<alloc::string::String> {
+        crate::kvs_instance_name::lookup(*self)
+    }
+}
+
+impl From<InstanceId> for usize {
+    fn from(value: InstanceId) -> Self {
+        value.0
+    }
+}
+
+/// Snapshot ID
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SnapshotId(pub usize);
+
+impl fmt::Display for SnapshotId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<SnapshotId> for usize {
+    fn from(value: SnapshotId) -> Self {
+        value.0
+    }
+}
+
+/// Compute the hash stored alongside serialized KVS data.
+pub fn kvs_hash(data: &[u8]) -> u32 {
+    adler32::RollingAdler32::from_buffer(data).hash()
+}
+
+/// Storage of serialized snapshots.
+pub trait KvsStorage {
+    /// Read a snapshot and its stored hash
+    ///
+    /// # Parameters
+    ///   * `instance_id`: Instance ID
+    ///   * `snapshot_id`: Snapshot ID
+    ///
+    /// # Return Values
+    ///   * Ok: Data and hash
+    ///   * `ErrorCode::FileNotFound`: Snapshot not stored
+    fn read(
+        &self,
+        instance_id: InstanceId,
+        snapshot_id: SnapshotId,
+    ) -> Result<(Vec<u8>, u32), ErrorCode>;
+
+    /// Write a snapshot and its hash
+    ///
+    /// # Parameters
+    ///   * `instance_id`: Instance ID
+    ///   * `snapshot_id`: Snapshot ID
+    ///   * `data`: Serialized data
+    ///   * `hash`: Hash of `data`
+    ///
+    /// # Return Values
+    ///   * Ok: Snapshot written
+    ///   * `ErrorCode::PhysicalStorageFailure`: Write failed
+    fn write(
+        &mut self,
+        instance_id: InstanceId,
+        snapshot_id: SnapshotId,
+        data: &[u8],
+        hash: u32,
+    ) -> Result<(), ErrorCode>;
+
+    /// Remove a snapshot
+    ///
+    /// # Parameters
+    ///   * `instance_id`: Instance ID
+    ///   * `snapshot_id`: Snapshot ID
+    ///
+    /// # Return Values
+    ///   * Ok: Snapshot removed
+    ///   * `ErrorCode::FileNotFound`: Snapshot not stored
+    fn remove(&mut self, instance_id: InstanceId, snapshot_id: SnapshotId)
+        -> Result<(), ErrorCode>;
+
+    /// Read a snapshot and verify its hash
+    ///
+    /// # Parameters
+    ///   * `instance_id`: Instance ID
+    ///   * `snapshot_id`: Snapshot ID
+    ///
+    /// # Return Values
+    ///   * Ok: Verified data
+    ///   * `ErrorCode::FileNotFound`: Snapshot not stored
+    ///   * `ErrorCode::ValidationFailed`: Hash mismatch
+    fn load(&self, instance_id: InstanceId, snapshot_id: SnapshotId) -> Result<Vec<u8>, ErrorCode> {
+        let (data, hash) = self.read(instance_id, snapshot_id)?;
+        if kvs_hash(&data) != hash {
+            return Err(ErrorCode::ValidationFailed);
+        }
+        Ok(data)
+    }
+
+    /// Write a snapshot with its computed hash
+    ///
+    /// # Parameters
+    ///   * `instance_id`: Instance ID
+    ///   * `snapshot_id`: Snapshot ID
+    ///   * `data`: Serialized data
+    ///
+    /// # Return Values
+    ///   * Ok: Snapshot written
+    ///   * `ErrorCode::PhysicalStorageFailure`: Write failed
+    fn store(
+        &mut self,
+        instance_id: InstanceId,
+        snapshot_id: SnapshotId,
+        data: &[u8],
+    ) -> Result<(), ErrorCode> {
+        self.write(instance_id, snapshot_id, data, kvs_hash(data))
+    }
+}
+
+#[cfg(test)]
+mod kvs_storage_tests {
+    use crate::error_code::ErrorCode;
+    use crate::kvs_storage::{kvs_hash, InstanceId, KvsStorage, SnapshotId};
+    use std::collections::BTreeMap;
+
+    #[derive(Default)]
+    struct FlashStorage {
+        slots: BTreeMap<(usize, usize), (Vec<u8>, u32)>,
+    }
+
+    impl KvsStorage for FlashStorage {
+        fn read(
+            &self,
+            instance_id: InstanceId,
+            snapshot_id: SnapshotId,
+        ) -> Result<(Vec<u8>, u32), ErrorCode> {
+            self.slots
+                .get(&(instance_id.0, snapshot_id.0))
+                .cloned()
+                .ok_or(ErrorCode::FileNotFound)
+        }
+
+        fn write(
+            &mut self,
+            instance_id: InstanceId,
+            snapshot_id: SnapshotId,
+            data: &[u8],
+            hash: u32,
+        ) -> Result<(), ErrorCode> {
+            self.slots
+                .insert((instance_id.0, snapshot_id.0), (data.to_vec(), hash));
+            Ok(())
+        }
+
+        fn remove(
+            &mut self,
+            instance_id: InstanceId,
+            snapshot_id: SnapshotId,
+        ) -> Result<(), ErrorCode> {
+            self.slots
+                .remove(&(instance_id.0, snapshot_id.0))
+                .map(|_| ())
+                .ok_or(ErrorCode::FileNotFound)
+        }
+    }
+
+    #[test]
+    fn test_store_load() {
+        let mut storage = FlashStorage::default();
+        storage
+            .store(InstanceId(1), SnapshotId(0), b"{\"key\":1}")
+            .unwrap();
+        assert_eq!(
+            storage.load(InstanceId(1), SnapshotId(0)).unwrap(),
+            b"{\"key\":1}"
+        );
+        assert_eq!(
+            storage.read(InstanceId(1), SnapshotId(0)).unwrap().1,
+            kvs_hash(b"{\"key\":1}")
+        );
+        assert!(storage
+            .load(InstanceId(2), SnapshotId(0))
+            .is_err_and(|e| e == ErrorCode::FileNotFound));
+
+        storage
+            .write(InstanceId(1), SnapshotId(1), b"{\"key\":2}", 0)
+            .unwrap();
+        assert!(storage
+            .load(InstanceId(1), SnapshotId(1))
+            .is_err_and(|e| e == ErrorCode::ValidationFailed));
+
+        storage.remove(InstanceId(1), SnapshotId(0)).unwrap();
+        assert!(storage
+            .remove(InstanceId(1), SnapshotId(0))
+            .is_err_and(|e| e == ErrorCode::FileNotFound));
+    }
+}
@@ -0,0 +1,181 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Read-modify-write transactions on a single KVS instance.
+//!
+//! [`KvsApi::transaction`](crate::kvs_api::KvsApi::transaction) runs a closure with a
+//! [`KvsTransaction`] while the instance data is locked. Writes are collected in the transaction
+//! and applied at once when the closure returns `Ok`, they are discarded when it returns an error.
+//! Other handles sharing the instance never observe a partially applied transaction.
+//!
+//! The instance is locked while the closure runs, calling the API of the same instance from within
+//! the closure deadlocks.
+
+use crate::error_code::ErrorCode;
+use crate::kvs_log::kvs_error;
+use crate::kvs_value::{KvsDeserialize, KvsMap, KvsSerialize, KvsValue};
+use std::collections::HashMap;
+
+/// Pending writes of a transaction, `None` marks a removed key.
+pub(crate) type KvsChanges = HashMap<String, Option<KvsValue>>;
+
+/// View of an instance with pending writes.
+pub struct KvsTransaction<'a> {
+    kvs_map: &'a KvsMap,
+    defaults_map: &'a KvsMap,
+    changes: KvsChanges,
+    reads: Vec<String>,
+}
+
+impl<'a> KvsTransaction<'a> {
+    pub(crate) fn new(kvs_map: &'a KvsMap, defaults_map: &'a KvsMap) -> Self {
+        Self {
+            kvs_map,
+            defaults_map,
+            changes: KvsChanges::new(),
+            reads: Vec::new(),
+        }
+    }
+
+    /// Pending writes and keys read successfully.
+    pub(crate) fn into_parts(self) -> (KvsChanges, Vec<String>) {
+        (self.changes, self.reads)
+    }
+
+    /// Value stored for a key including pending writes, defaults not included.
+    fn stored_value(&self, key: &str) -> Option<&KvsValue> {
+        match self.changes.get(key) {
+            Some(change) => change.as_ref(),
+            None => self.kvs_map.get(key),
+        }
+    }
+
+    /// Check if a key exists, defaults not included.
+    ///
+    /// # Parameters
+    ///   * `key`: Key to check for existence
+    pub fn key_exists(&self, key: &str) -> bool {
+        self.stored_value(key).is_some()
+    }
+
+    /// Get the assigned value for a given key, including pending writes.
+    ///
+    /// # Parameters
+    ///   * `key`: Key to retrieve the value from
+    ///
+    /// # Return Value
+    ///   * Ok: Value or default value if key was found
+    ///   * `ErrorCode::KeyNotFound`: Key wasn't found in KVS nor in defaults
+    pub fn get_value(&mut self, key: &str) -> Result<KvsValue, ErrorCode> {
+        let value = match self.stored_value(key) {
+            Some(value) => value.clone(),
+            None => match self.defaults_map.get(key) {
+                Some(value) => value.clone(),
+                None => {
+                    kvs_error!(key = key, "get_value could not find key: {key}");
+                    return Err(ErrorCode::KeyNotFound);
+                }
+            },
+        };
+        self.reads.push(key.to_string());
+        Ok(value)
+    }
+
+    /// Get the assigned value for a given key, including pending writes.
+    ///
+    /// # Parameters
+    ///   * `key`: Key to retrieve the value from
+    ///
+    /// # Return Value
+    ///   * Ok: Type specific value if key was found
+    ///   * `ErrorCode::ConversionFailed`: Type conversion failed
+    ///   * `ErrorCode::KeyNotFound`: Key wasn't found in KVS nor in defaults
+    pub fn get_value_as<T: KvsDeserialize>(&mut self, key: &str) -> Result<T, ErrorCode> {
+        T::from_kvs_value(&self.get_value(key)?)
+    }
+
+    /// Assign a value to a given key, applied on commit.
+    ///
+    /// # Parameters
+    ///   * `key`: Key to set value
+    ///   * `value`: Value to be set
+    pub fn set_value<S: Into<String>, V: KvsSerialize>(&mut self, key: S, value: V) {
+        self.changes.insert(key.into(), Some(value.to_kvs_value()));
+    }
+
+    /// Remove a key, applied on commit.
+    ///
+    /// # Parameters
+    ///   * `key`: Key to remove
+    ///
+    /// # Return Values
+    ///   * Ok: Key will be removed
+    ///   * `ErrorCode::KeyNotFound`: Key not found
+    pub fn remove_key(&mut self, key: &str) -> Result<(), ErrorCode> {
+        if !self.key_exists(key) {
+            return Err(ErrorCode::KeyNotFound);
+        }
+        self.changes.insert(key.to_string(), None);
+        Ok(())
+    }
+}
+
+/// Apply pending writes to a map.
+pub(crate) fn apply_changes(kvs_map: &mut KvsMap, changes: KvsChanges) {
+    for (key, change) in changes {
+        match change {
+            Some(value) => kvs_map.insert(key, value),
+            None => kvs_map.remove(&key),
+        };
+    }
+}
+
+#[cfg(test)]
+mod kvs_transaction_tests {
+    use crate::error_code::ErrorCode;
+    use crate::kvs_transaction::{apply_changes, KvsTransaction};
+    use crate::kvs_value::{KvsMap, KvsValue};
+
+    #[test]
+    fn test_pending_writes_visible() {
+        let kvs_map = KvsMap::from([("a".to_string(), KvsValue::I32(1))]);
+        let defaults_map = KvsMap::from([("b".to_string(), KvsValue::I32(2))]);
+        let mut txn = KvsTransaction::new(&kvs_map, &defaults_map);
+
+        assert_eq!(txn.get_value("b").unwrap(), KvsValue::I32(2));
+        txn.set_value("b", 3);
+        assert_eq!(txn.get_value_as::<i32>("b").unwrap(), 3);
+        txn.remove_key("a").unwrap();
+        assert!(!txn.key_exists("a"));
+        assert!(txn
+            .get_value("a")
+            .is_err_and(|e| e == ErrorCode::KeyNotFound));
+        assert!(txn
+            .remove_key("a")
+            .is_err_and(|e| e == ErrorCode::KeyNotFound));
+
+        let (changes, reads) = txn.into_parts();
+        assert_eq!(reads, vec!["b".to_string(), "b".to_string()]);
+        let mut kvs_map = kvs_map.clone();
+        apply_changes(&mut kvs_map, changes);
+        assert_eq!(kvs_map, KvsMap::from([("b".to_string(), KvsValue::I32(3))]));
+    }
+
+    #[test]
+    fn test_remove_default_only() {
+        let kvs_map = KvsMap::new();
+        let defaults_map = KvsMap::from([("a".to_string(), KvsValue::I32(1))]);
+        let mut txn = KvsTransaction::new(&kvs_map, &defaults_map);
+        assert!(txn
+            .remove_key("a")
+            .is_err_and(|e| e == ErrorCode::KeyNotFound));
+    }
+}
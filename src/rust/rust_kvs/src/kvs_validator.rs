@@ -0,0 +1,177 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Validation of written values.
+//!
+//! Validators set with
+//! [`GenericKvsBuilder::validator`](crate::kvs_builder::GenericKvsBuilder::validator) check
+//! values of keys matching a glob pattern, see
+//! [`KvsApi::get_keys_matching`](crate::kvs_api::KvsApi::get_keys_matching), whenever they are
+//! written: by `set_value`, `set_values`, path writes, transactions and multi-instance writes. A
+//! rejected write fails with `ErrorCode::ValidationFailed` and changes nothing.
+//!
+//! Nested writes validate the whole resulting value of the key. Values loaded from storage or
+//! defaults aren't validated.
+
+use crate::error_code::ErrorCode;
+use crate::kvs::GenericKvs;
+use crate::kvs_api::glob_match;
+use crate::kvs_backend::{KvsBackend, KvsPathResolver};
+use crate::kvs_checked;
+use crate::kvs_log::kvs_error;
+use crate::kvs_value::{KvsValue, ValueModel};
+use std::fmt;
+use std::sync::Arc;
+
+/// Function checking a value, returning the reason if it's rejected.
+pub type KvsValidateFn = dyn Fn(&KvsValue) -> Result<(), String> + Send + Sync;
+
+/// Validator of values of keys matching a pattern.
+///
+/// Validators are equal if they have the same pattern and share the same function.
+#[derive(Clone)]
+pub struct KvsValidator {
+    /// Glob pattern of validated keys.
+    pub pattern: String,
+
+    /// Validation function.
+    pub validate: Arc<KvsValidateFn>,
+}
+
+impl PartialEq for KvsValidator {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern == other.pattern && Arc::ptr_eq(&self.validate, &other.validate)
+    }
+}
+
+impl fmt::Debug for KvsValidator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KvsValidator")
+            .field("pattern", &self.pattern)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<Backend: KvsBackend, PathResolver: KvsPathResolver, Model: ValueModel>
+    GenericKvs<Backend, PathResolver, Model>
+{
+    /// Check a written value against the validators of its key
+    ///
+    /// # Parameters
+    ///   * `key`: Written key
+    ///   * `value`: Written value, checksums of integrity-critical values are ignored
+    ///
+    /// # Return Values
+    ///   * Ok: Value is accepted by all matching validators
+    ///   * `ErrorCode::ValidationFailed`: A validator rejected the value
+    pub(crate) fn check_value(&self, key: &str, value: &KvsValue) -> Result<(), ErrorCode> {
+        let value = kvs_checked::plain(value);
+        for validator in &self.parameters().validators {
+            if !glob_match(&validator.pattern, key) {
+                continue;
+            }
+            if let Err(reason) = (validator.validate)(value) {
+                kvs_error!(
+                    instance_id = self.parameters().instance_id,
+                    key = key,
+                    "value of key {key} rejected by validator {}: {reason}",
+                    validator.pattern
+                );
+                return Err(ErrorCode::ValidationFailed.with_key(key));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "json-backend"))]
+mod kvs_validator_tests {
+    use crate::error_code::ErrorCode;
+    use crate::json_backend::JsonBackend;
+    use crate::kvs_api::{InstanceId, KvsApi};
+    use crate::kvs_builder::kvs_builder_tests::lock_and_reset;
+    use crate::kvs_builder::GenericKvsBuilder;
+    use crate::kvs_value::{KvsMap, KvsValue};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_validator() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let kvs = GenericKvsBuilder::<JsonBackend>::new(InstanceId(1))
+            .dir(dir.path().to_string_lossy().to_string())
+            .validator("net.*.port", |value| match value {
+                KvsValue::I32(port) if (1..=65535).contains(port) => Ok(()),
+                _ => Err("port out of range".to_string()),
+            })
+            .validator("log_level", |value| match value {
+                KvsValue::String(level) if ["debug", "info"].contains(&level.as_str()) => Ok(()),
+                _ => Err("unknown log level".to_string()),
+            })
+            .build()
+            .unwrap();
+
+        kvs.set_value("net.eth0.port", 80).unwrap();
+        assert!(kvs
+            .set_value("net.eth0.port", 0)
+            .is_err_and(|e| e == ErrorCode::ValidationFailed));
+        assert_eq!(kvs.get_value("net.eth0.port").unwrap(), KvsValue::I32(80));
+        kvs.set_value_checked("log_level", "info").unwrap();
+        assert!(kvs
+            .set_value_checked("log_level", "trace")
+            .is_err_and(|e| e == ErrorCode::ValidationFailed));
+        kvs.set_value("other", 0).unwrap();
+
+        // Batch writes are rejected as a whole.
+        assert!(kvs
+            .set_values([
+                ("other".to_string(), KvsValue::I32(1)),
+                ("log_level".to_string(), KvsValue::from("trace")),
+            ])
+            .is_err_and(|e| e == ErrorCode::ValidationFailed));
+        assert_eq!(kvs.get_value("other").unwrap(), KvsValue::I32(0));
+        assert!(kvs
+            .transaction(|txn| {
+                txn.set_value("net.wlan0.port", 70000);
+                Ok(())
+            })
+            .is_err_and(|e| e == ErrorCode::ValidationFailed));
+        assert!(!kvs.key_exists("net.wlan0.port").unwrap());
+    }
+
+    #[test]
+    fn test_validator_nested() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let kvs = GenericKvsBuilder::<JsonBackend>::new(InstanceId(1))
+            .dir(dir.path().to_string_lossy().to_string())
+            .validator("limits", |value| match value {
+                KvsValue::Object(map) if map.values().all(|v| *v != KvsValue::Null) => Ok(()),
+                _ => Err("null limit".to_string()),
+            })
+            .build()
+            .unwrap();
+        kvs.set_value(
+            "limits",
+            KvsValue::Object(KvsMap::from([("max".to_string(), KvsValue::I32(1))])),
+        )
+        .unwrap();
+
+        assert!(kvs
+            .set_value_at_path("limits.max", KvsValue::Null)
+            .is_err_and(|e| e == ErrorCode::ValidationFailed));
+        assert_eq!(
+            kvs.get_value_at_path("limits.max").unwrap(),
+            KvsValue::I32(1)
+        );
+        kvs.set_value_at_path("limits.max", 2).unwrap();
+    }
+}
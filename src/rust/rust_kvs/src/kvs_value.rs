@@ -10,11 +10,21 @@
 // SPDX-License-Identifier: Apache-2.0
 
 // TryFrom<&KvsValue> for all supported types
-use std::convert::TryFrom;
+use crate::error_code::ErrorCode;
+use crate::kvs_log::kvs_error;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
 
 /// Key-value storage map type
+#[cfg(feature = "std")]
 pub type KvsMap = std::collections::HashMap<String, KvsValue>;
 
+/// Key-value storage map type, ordered map as `HashMap` requires `std`
+#[cfg(not(feature = "std"))]
+pub type KvsMap = alloc::collections::BTreeMap<String, KvsValue>;
+
 /// Key-value-storage value
 #[derive(Clone, Debug, PartialEq)]
 pub enum KvsValue {
@@ -87,7 +97,7 @@ impl From<()> for KvsValue {
 // Macro to implement TryFrom<&KvsValue> for T for each supported type/variant.
 macro_rules! impl_tryfrom_kvs_value_to_t {
     ($to:ty, $variant:ident) => {
-        impl std::convert::TryFrom<&KvsValue> for $to {
+        impl TryFrom<&KvsValue> for $to {
             type Error = String;
             fn try_from(value: &KvsValue) -> Result<Self, Self::Error> {
                 if let KvsValue::$variant(ref n) = value {
@@ -108,7 +118,10 @@ impl_tryfrom_kvs_value_to_t!(f64, F64);
 impl_tryfrom_kvs_value_to_t!(bool, Boolean);
 impl_tryfrom_kvs_value_to_t!(String, String);
 impl_tryfrom_kvs_value_to_t!(Vec<KvsValue>, Array);
+#[cfg(feature = "std")]
 impl_tryfrom_kvs_value_to_t!(std::collections::HashMap<String, KvsValue>, Object);
+#[cfg(not(feature = "std"))]
+impl_tryfrom_kvs_value_to_t!(KvsMap, Object);
 
 impl TryFrom<&KvsValue> for () {
     type Error = &'static str;
@@ -129,6 +142,169 @@ impl KvsValue {
     pub fn get<T: KvsValueGet>(&self) -> Option<&T> {
         T::get_inner_value(self)
     }
+
+    /// Get the type of the value.
+    pub fn kind(&self) -> KvsValueKind {
+        match self {
+            KvsValue::I32(_) => KvsValueKind::I32,
+            KvsValue::U32(_) => KvsValueKind::U32,
+            KvsValue::I64(_) => KvsValueKind::I64,
+            KvsValue::U64(_) => KvsValueKind::U64,
+            KvsValue::F64(_) => KvsValueKind::F64,
+            KvsValue::Boolean(_) => KvsValueKind::Boolean,
+            KvsValue::String(_) => KvsValueKind::String,
+            KvsValue::Null => KvsValueKind::Null,
+            KvsValue::Array(_) => KvsValueKind::Array,
+            KvsValue::Object(_) => KvsValueKind::Object,
+        }
+    }
+
+    /// Estimate the stored size of the value in bytes.
+    ///
+    /// Size of the value in the type-tagged format of the JSON backend, e.g.
+    /// `{"t":"i32","v":42}`, without compression. The actual size depends on the backend and the
+    /// float format.
+    pub fn serialized_size_hint(&self) -> usize {
+        let (tag, payload) = match self {
+            KvsValue::I32(n) => ("i32", display_len(n)),
+            KvsValue::U32(n) => ("u32", display_len(n)),
+            // Stored as string.
+            KvsValue::I64(n) => ("i64", display_len(n) + 2),
+            KvsValue::U64(n) => ("u64", display_len(n) + 2),
+            KvsValue::F64(n) => ("f64", display_len(n)),
+            KvsValue::Boolean(b) => ("bool", display_len(b)),
+            KvsValue::String(s) => ("str", json_string_len(s)),
+            KvsValue::Null => ("null", "null".len()),
+            KvsValue::Array(values) => (
+                "arr",
+                2 + values.len().saturating_sub(1)
+                    + values
+                        .iter()
+                        .map(KvsValue::serialized_size_hint)
+                        .sum::<usize>(),
+            ),
+            KvsValue::Object(map) => ("obj", map_size_hint(map)),
+        };
+        r#"{"t":"","v":}"#.len() + tag.len() + payload
+    }
+}
+
+/// Estimate the stored size of a map in bytes, see [`KvsValue::serialized_size_hint`].
+pub(crate) fn map_size_hint(map: &KvsMap) -> usize {
+    2 + map.len().saturating_sub(1)
+        + map
+            .iter()
+            .map(|(key, value)| json_string_len(key) + 1 + value.serialized_size_hint())
+            .sum::<usize>()
+}
+
+/// Length of the `Display` output of a value.
+fn display_len<T: fmt::Display>(value: &T) -> usize {
+    struct Counter(usize);
+
+    impl Write for Counter {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            self.0 += s.len();
+            Ok(())
+        }
+    }
+
+    let mut counter = Counter(0);
+    let _ = write!(counter, "{value}");
+    counter.0
+}
+
+/// Length of a string as quoted and escaped JSON string.
+fn json_string_len(s: &str) -> usize {
+    2 + s
+        .chars()
+        .map(|c| match c {
+            '"' | '\\' | '\n' | '\r' | '\t' | '\u{08}' | '\u{0c}' => 2,
+            c if (c as u32) < 0x20 => 6,
+            c => c.len_utf8(),
+        })
+        .sum::<usize>()
+}
+
+/// Type of a [`KvsValue`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KvsValueKind {
+    /// 32-bit signed integer
+    I32,
+
+    /// 32-bit unsigned integer
+    U32,
+
+    /// 64-bit signed integer
+    I64,
+
+    /// 64-bit unsigned integer
+    U64,
+
+    /// 64-bit float
+    F64,
+
+    /// Boolean
+    Boolean,
+
+    /// String
+    String,
+
+    /// Null
+    Null,
+
+    /// Array
+    Array,
+
+    /// Object
+    Object,
+}
+
+/// Displays the variant name, e.g. `I32`.
+impl fmt::Display for KvsValueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// Displays the value in JSON notation without type tags, e.g. `{"a": [1, true]}`.
+///
+/// Object entries are ordered by key.
+impl fmt::Display for KvsValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KvsValue::I32(n) => write!(f, "{n}"),
+            KvsValue::U32(n) => write!(f, "{n}"),
+            KvsValue::I64(n) => write!(f, "{n}"),
+            KvsValue::U64(n) => write!(f, "{n}"),
+            KvsValue::F64(n) => write!(f, "{n}"),
+            KvsValue::Boolean(b) => write!(f, "{b}"),
+            KvsValue::String(s) => write!(f, "{s:?}"),
+            KvsValue::Null => f.write_str("null"),
+            KvsValue::Array(values) => {
+                f.write_char('[')?;
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{value}")?;
+                }
+                f.write_char(']')
+            }
+            KvsValue::Object(map) => {
+                let mut entries: Vec<_> = map.iter().collect();
+                entries.sort_by(|a, b| a.0.cmp(b.0));
+                f.write_char('{')?;
+                for (index, (key, value)) in entries.into_iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{key:?}: {value}")?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
 }
 
 macro_rules! impl_kvs_get_inner_value {
@@ -151,7 +327,7 @@ impl_kvs_get_inner_value!(u64, U64);
 impl_kvs_get_inner_value!(bool, Boolean);
 impl_kvs_get_inner_value!(String, String);
 impl_kvs_get_inner_value!(Vec<KvsValue>, Array);
-impl_kvs_get_inner_value!(std::collections::HashMap<String, KvsValue>, Object);
+impl_kvs_get_inner_value!(KvsMap, Object);
 
 impl KvsValueGet for () {
     fn get_inner_value(v: &KvsValue) -> Option<&()> {
@@ -162,9 +338,182 @@ impl KvsValueGet for () {
     }
 }
 
+/// Conversion of a type into a `KvsValue`, used by `set_value`.
+///
+/// Implemented for all types convertible with `Into<KvsValue>`. User types implement it manually
+/// or with [`impl_kvs_object!`](crate::impl_kvs_object).
+pub trait KvsSerialize {
+    /// Convert into `KvsValue`.
+    fn to_kvs_value(self) -> KvsValue;
+}
+
+impl<T: Into<KvsValue>> KvsSerialize for T {
+    fn to_kvs_value(self) -> KvsValue {
+        self.into()
+    }
+}
+
+/// Conversion of a `KvsValue` into a type, used by `get_value_as`.
+///
+/// Implemented for all types convertible with `TryFrom<&KvsValue>`. User types implement it
+/// manually or with [`impl_kvs_object!`](crate::impl_kvs_object).
+pub trait KvsDeserialize: Sized {
+    /// Convert from `KvsValue`.
+    ///
+    /// # Return Values
+    ///   * Ok: Converted value
+    ///   * `ErrorCode::ConversionFailed`: Value has unexpected type or content
+    fn from_kvs_value(value: &KvsValue) -> Result<Self, ErrorCode>;
+}
+
+impl<T> KvsDeserialize for T
+where
+    for<'a> T: TryFrom<&'a KvsValue>,
+    for<'a> <T as TryFrom<&'a KvsValue>>::Error: fmt::Debug,
+{
+    fn from_kvs_value(value: &KvsValue) -> Result<Self, ErrorCode> {
+        T::try_from(value).map_err(|err| {
+            kvs_error!("could not convert KvsValue: {err:#?}");
+            ErrorCode::ConversionFailed
+        })
+    }
+}
+
+impl From<&KvsValue> for KvsValue {
+    fn from(value: &KvsValue) -> Self {
+        value.clone()
+    }
+}
+
+/// Value type exposed by an instance, see [`KvsApi::Value`](crate::kvs_api::KvsApi::Value).
+///
+/// Values are always stored as `KvsValue`, other value enums plug in by implementing
+/// [`KvsSerialize`] and [`KvsDeserialize`]. `KvsValue` itself is the default model.
+pub trait ValueModel: KvsSerialize + KvsDeserialize {}
+
+impl<T: KvsSerialize + KvsDeserialize> ValueModel for T {}
+
+/// Deserialize field of an object, used by [`impl_kvs_object!`](crate::impl_kvs_object).
+#[doc(hidden)]
+pub fn deserialize_field<T: KvsDeserialize>(map: &KvsMap, name: &str) -> Result<T, ErrorCode> {
+    match map.get(name) {
+        Some(value) => T::from_kvs_value(value),
+        None => {
+            kvs_error!("object field not found: {name}");
+            Err(ErrorCode::ConversionFailed)
+        }
+    }
+}
+
+/// Implement [`KvsSerialize`] and [`KvsDeserialize`] for a struct with named fields.
+///
+/// The struct is stored as `KvsValue::Object` with one entry per field. All fields must be listed
+/// and implement both traits.
+///
+/// ```
+/// use rust_kvs::impl_kvs_object;
+/// use rust_kvs::prelude::*;
+///
+/// struct Config {
+///     name: String,
+///     retries: u32,
+/// }
+///
+/// impl_kvs_object!(Config { name, retries });
+///
+/// let value = Config { name: "a".to_string(), retries: 3 }.to_kvs_value();
+/// let config = Config::from_kvs_value(&value).unwrap();
+/// assert_eq!(config.retries, 3);
+/// ```
+#[macro_export]
+macro_rules! impl_kvs_object {
+    ($type:ty { $($field:ident),* $(,)? }) => {
+        impl $crate::kvs_value::KvsSerialize for $type {
+            fn to_kvs_value(self) -> $crate::kvs_value::KvsValue {
+                let mut map = $crate::kvs_value::KvsMap::new();
+                $(
+                    map.insert(
+                        ::core::convert::Into::into(stringify!($field)),
+                        $crate::kvs_value::KvsSerialize::to_kvs_value(self.$field),
+                    );
+                )*
+                $crate::kvs_value::KvsValue::Object(map)
+            }
+        }
+
+        impl $crate::kvs_value::KvsDeserialize for $type {
+            fn from_kvs_value(
+                value: &$crate::kvs_value::KvsValue,
+            ) -> Result<Self, $crate::error_code::ErrorCode> {
+                let $crate::kvs_value::KvsValue::Object(map) = value else {
+                    $crate::kvs_log!(
+                        $crate::kvs_log::KvsLogLevel::Error,
+                        "KvsValue is not an object"
+                    );
+                    return Err($crate::error_code::ErrorCode::ConversionFailed);
+                };
+                Ok(Self {
+                    $($field: $crate::kvs_value::deserialize_field(map, stringify!($field))?,)*
+                })
+            }
+        }
+    };
+}
+
 #[cfg(test)]
 mod kvs_value_tests {
-    use crate::kvs_value::{KvsMap, KvsValue};
+    use crate::error_code::ErrorCode;
+    use crate::kvs_value::{KvsDeserialize, KvsMap, KvsSerialize, KvsValue, KvsValueKind};
+    #[cfg(feature = "std")]
+    use tinyjson::JsonValue;
+
+    fn nested_value() -> KvsValue {
+        KvsValue::Object(KvsMap::from([
+            ("number".to_string(), KvsValue::I32(-42)),
+            ("long".to_string(), KvsValue::U64(u64::MAX)),
+            ("float".to_string(), KvsValue::F64(1.5)),
+            (
+                "array".to_string(),
+                KvsValue::Array(vec![
+                    KvsValue::from("quote \" and\nnewline"),
+                    KvsValue::Null,
+                    KvsValue::Boolean(false),
+                ]),
+            ),
+            ("empty".to_string(), KvsValue::Array(vec![])),
+        ]))
+    }
+
+    #[test]
+    fn test_kind() {
+        assert_eq!(KvsValue::I64(1).kind(), KvsValueKind::I64);
+        assert_eq!(nested_value().kind(), KvsValueKind::Object);
+        assert_eq!(KvsValueKind::Boolean.to_string(), "Boolean");
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(KvsValue::from("a\"b").to_string(), r#""a\"b""#);
+        assert_eq!(
+            nested_value().to_string(),
+            r#"{"array": ["quote \" and\nnewline", null, false], "empty": [], "float": 1.5, "long": 18446744073709551615, "number": -42}"#
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_serialized_size_hint() {
+        for value in [
+            KvsValue::I32(-42),
+            KvsValue::U64(u64::MAX),
+            KvsValue::Boolean(true),
+            KvsValue::from("\u{1}é"),
+            nested_value(),
+        ] {
+            let stored = JsonValue::from(value.clone()).stringify().unwrap();
+            assert_eq!(value.serialized_size_hint(), stored.len(), "{stored}");
+        }
+    }
 
     #[test]
     fn test_i32_from_ok() {
@@ -474,6 +823,7 @@ mod kvs_value_tests {
         assert_eq!(KvsMap::try_from(&v).unwrap(), map);
     }
 
+    #[cfg(feature = "std")]
     #[test]
     fn test_kvsmap_tryfrom_invalid_type() {
         let v = KvsValue::from("");
@@ -497,4 +847,79 @@ mod kvs_value_tests {
         let v = KvsValue::from("");
         assert!(v.get::<KvsMap>().is_none());
     }
+
+    #[derive(Debug, PartialEq)]
+    struct Inner {
+        enabled: bool,
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Outer {
+        name: String,
+        count: u64,
+        inner: Inner,
+    }
+
+    impl_kvs_object!(Inner { enabled });
+    impl_kvs_object!(Outer { name, count, inner });
+
+    #[test]
+    fn test_serialize_primitive() {
+        assert_eq!(5i32.to_kvs_value(), KvsValue::I32(5));
+        assert_eq!(i32::from_kvs_value(&KvsValue::I32(5)), Ok(5));
+        assert_eq!(
+            i32::from_kvs_value(&KvsValue::Boolean(true)),
+            Err(ErrorCode::ConversionFailed)
+        );
+    }
+
+    #[test]
+    fn test_object_roundtrip() {
+        let outer = Outer {
+            name: "cfg".to_string(),
+            count: 7,
+            inner: Inner { enabled: true },
+        };
+        let value = outer.to_kvs_value();
+
+        let KvsValue::Object(map) = &value else {
+            panic!("not an object");
+        };
+        assert_eq!(map.len(), 3);
+        assert_eq!(map["count"], KvsValue::U64(7));
+        assert_eq!(
+            map["inner"],
+            KvsValue::Object(KvsMap::from([(
+                "enabled".to_string(),
+                KvsValue::Boolean(true)
+            )]))
+        );
+
+        let restored = Outer::from_kvs_value(&value).unwrap();
+        assert_eq!(
+            restored,
+            Outer {
+                name: "cfg".to_string(),
+                count: 7,
+                inner: Inner { enabled: true },
+            }
+        );
+    }
+
+    #[test]
+    fn test_object_missing_field() {
+        let value = KvsValue::Object(KvsMap::from([("name".to_string(), KvsValue::from("cfg"))]));
+        assert_eq!(
+            Outer::from_kvs_value(&value),
+            Err(ErrorCode::ConversionFailed)
+        );
+    }
+
+    #[test]
+    fn test_object_invalid_type() {
+        assert_eq!(
+            Inner::from_kvs_value(&KvsValue::I32(1)),
+            Err(ErrorCode::ConversionFailed)
+        );
+    }
 }
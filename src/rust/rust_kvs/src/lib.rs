@@ -40,6 +40,10 @@
 //! `Vec<KvsValue>`, `HashMap<String, KvsValue>` or `KvsValue`.
 //! Also `let value: f64 = kvs.get_value_as()` can be used.
 //!
+//! User types can be written and read as well by implementing [`KvsSerialize`](kvs_value::KvsSerialize)
+//! and [`KvsDeserialize`](kvs_value::KvsDeserialize), either manually or for structs with
+//! [`impl_kvs_object!`]. Such structs are stored as `Object`.
+//!
 //! If a `key` isn't available in the KVS a lookup into the defaults storage will be performed and
 //! if the `value` is found the default will be returned. The default value isn't stored when
 //! [`Kvs::flush`] is called unless it's explicitly written with [`Kvs::set_value`]. So when
@@ -57,7 +61,10 @@
 //! use std::collections::HashMap;
 //!
 //! fn main() -> Result<(), ErrorCode> {
+//!     # let dir = tempfile::tempdir()?;
+//!     # let dir = dir.path().to_string_lossy().to_string();
 //!     let kvs: Kvs = KvsBuilder::new(InstanceId(0))
+//!         .dir(dir)
 //!         .build()?;
 //!
 //!     kvs.set_value("number", 123.0)?;
@@ -94,6 +101,58 @@
 //! }
 //! ```
 //!
+//! ## Cargo Features
+//!
+//! [`bin_backend::BinBackend`] stores data in a compact binary format, faster to write than JSON
+//! for large instances. [`single_file_backend::SingleFileBackend`] stores the hash in the same
+//! file as the data, so they can't get out of sync. [`dual_bank_backend::DualBankBackend`] wraps
+//! a file-based backend and alternates writes between two banks to spread flash wear.
+//!
+//! Optional functionality is feature-gated and pulls in additional dependencies:
+//!   * `std` (default): Instances, builder and all file-based backends. Without it the crate is
+//!     `no_std` + `alloc` and only provides [`error_code`], [`kvs_value`] and the
+//!     [`kvs_storage::KvsStorage`] trait, for storage implemented by the target, e.g. on an RTOS.
+//!     All other features enable `std`.
+//!   * `json-backend` (default): [`JsonBackend`], the [`Kvs`] and [`KvsBuilder`] aliases,
+//!     [`single_file_backend::SingleFileBackend`], [`memory_backend::MemoryBackend`], [`kvs_patch`]
+//!     and defaults embedded as JSON. Enabled by the HTTP, S3 and SQLite backends, which use the
+//!     file names of the JSON backend.
+//!   * `snapshots` (default): Snapshot rotation on flush, snapshot creation, export, preview and
+//!     [`kvs_retention`]. Without it only the current KVS is stored and
+//!     [`KvsApi::snapshot_max_count`](kvs_api::KvsApi::snapshot_max_count) is 1.
+//!   * `defaults` (default): Changing and reloading defaults at runtime, including
+//!     [`defaults_watcher`]. Defaults files are loaded when opening an instance without it.
+//!   * `mock` (default): [`kvs_mock::MockKvs`] for tests of KVS users.
+//!   * `tooling` (default): [`kvs_admin`], [`kvs_discovery`], [`dotenv`] and [`protobuf`] for
+//!     tools inspecting and converting stored instances.
+//!   * `toml-backend`: [`toml_backend::TomlBackend`] storing data in TOML files editable by hand.
+//!   * `msgpack-backend`: [`msgpack_backend::MsgPackBackend`] storing data in MessagePack files.
+//!   * `cbor-backend`: [`cbor_backend::CborBackend`] storing data in CBOR files.
+//!   * `http-backend`: [`http_backend::HttpBackend`] storing data on an HTTP(S) service.
+//!   * `s3-backend`: [`s3_backend::S3Backend`] storing data in an S3-compatible bucket.
+//!   * `sqlite-backend`: [`sqlite_backend::SqliteBackend`] storing data in SQLite databases, writing
+//!     only changed keys on flush.
+//!   * `gzip`, `zstd`: [`KvsCompression`](kvs_api::KvsCompression) variants compressing stored
+//!     JSON files, selected with [`GenericKvsBuilder::compression`](kvs_builder::GenericKvsBuilder::compression).
+//!   * `shm-cache`: [`GenericKvsBuilder::shared_view`](kvs_builder::GenericKvsBuilder::shared_view)
+//!     publishing a memory-mapped view read by other processes with
+//!     [`kvs_shared_view::KvsSharedView`].
+//!   * `signal-flush`: [`kvs_shutdown::install_signal_flush`] flushing all open instances on
+//!     `SIGTERM`/`SIGINT`.
+//!   * `log`, `tracing`: Diagnostics are emitted as `log` records or `tracing` events, see
+//!     [`kvs_log`]. Without these features diagnostics are discarded.
+//!   * `serde-json`: `From` conversions between [`KvsValue`](kvs_value::KvsValue) and
+//!     `serde_json::Value`.
+//!   * `serde`: [`GenericKvs::set_struct`](kvs::GenericKvs::set_struct) and
+//!     [`GenericKvs::get_struct`](kvs::GenericKvs::get_struct) storing `serde` types as `Object`
+//!     trees, enables `serde-json`.
+//!   * `fuzzing`: [`kvs_fuzz::fuzz_load`] entry point for fuzz targets. `arbitrary` and `proptest`
+//!     add generators of [`KvsValue`](kvs_value::KvsValue) for property-based tests, both enable
+//!     `fuzzing`.
+//!
+//! Safety-critical deployments disable default features and enable only the certified ones, e.g.
+//! `default-features = false, features = ["json-backend"]`.
+//!
 //! ## Feature Coverage
 //!
 //! Feature and requirement definition:
@@ -111,16 +170,17 @@
 //!   * `FEAT_REQ__KVS__default_value_retrieval`
 //!   * `FEAT_REQ__KVS__persistency`
 //!   * `FEAT_REQ__KVS__integrity_check`
+//!   * `FEAT_REQ__KVS__maximum_size`
+//!   * `FEAT_REQ__KVS__versioning`: JSON format version ID, migration of older files
+//!   * `FEAT_REQ__KVS__cpp_rust_interoperability`: Files written by the C++ implementation are
+//!     read and vice versa
+//!   * `FEAT_REQ__KVS__tooling`: `kvs_tool` CLI and the `tooling` feature
 //!   * `STKH_REQ__30`: JSON storage format
 //!   * `STKH_REQ__8`: Defaults stored in JSON format
 //!   * `STKH_REQ__12`: Support storing data on non-volatile memory
 //!   * `STKH_REQ__13`: POSIX portability
 //!
 //! Currently unsupported features:
-//!   * `FEAT_REQ__KVS__maximum_size`
-//!   * `FEAT_REQ__KVS__cpp_rust_interoperability`
-//!   * `FEAT_REQ__KVS__versioning`: JSON version ID
-//!   * `FEAT_REQ__KVS__tooling`: Get/set CLI, JSON editor
 //!   * `STKH_REQ__350`: Safe key-value-store
 //!
 //! Additional info:
@@ -128,28 +188,148 @@
 //!     defines that `String` and `str` are always valid UTF-8.
 //!   * Feature `FEAT_REQ__KVS__supported_datatypes_values` is matched by using the same types that
 //!     the IPC will use for the Rust implementation.
-#![forbid(unsafe_code)]
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+// Mapping the shared view is the only unsafe code, allowed at its call site.
+#![cfg_attr(not(feature = "shm-cache"), forbid(unsafe_code))]
+#![cfg_attr(feature = "shm-cache", deny(unsafe_code))]
 #![cfg_attr(coverage_nightly, feature(coverage_attribute))]
 
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod bin_backend;
+#[cfg(feature = "std")]
+pub mod cached_backend;
+#[cfg(feature = "cbor-backend")]
+pub mod cbor_backend;
+#[cfg(feature = "defaults")]
+pub mod defaults_watcher;
+#[cfg(feature = "std")]
+pub mod dlt;
+#[cfg(feature = "tooling")]
+pub mod dotenv;
+#[cfg(feature = "std")]
+pub mod dual_bank_backend;
 pub mod error_code;
+#[cfg(feature = "http-backend")]
+pub mod http_backend;
+#[cfg(feature = "json-backend")]
 mod json_backend;
+#[cfg(feature = "std")]
+mod json_value;
+#[cfg(feature = "std")]
 pub mod kvs;
+#[cfg(feature = "tooling")]
+pub mod kvs_admin;
+#[cfg(feature = "std")]
 pub mod kvs_api;
+#[cfg(feature = "std")]
+pub mod kvs_autoflush;
+#[cfg(feature = "std")]
 mod kvs_backend;
+#[cfg(feature = "std")]
 pub mod kvs_builder;
+#[cfg(feature = "std")]
+pub mod kvs_checked;
+#[cfg(feature = "std")]
+pub mod kvs_clock;
+#[cfg(feature = "std")]
+pub mod kvs_compact;
+#[cfg(feature = "tooling")]
+pub mod kvs_discovery;
+#[cfg(feature = "std")]
+pub mod kvs_event;
+#[cfg(feature = "std")]
+pub mod kvs_fs;
+#[cfg(feature = "fuzzing")]
+pub mod kvs_fuzz;
+#[cfg(feature = "std")]
+pub mod kvs_instance_name;
+#[cfg(feature = "std")]
+pub mod kvs_journal;
+#[cfg(feature = "std")]
+pub mod kvs_lazy;
+#[cfg(feature = "std")]
+pub mod kvs_lock;
+pub mod kvs_log;
+#[cfg(feature = "std")]
+pub mod kvs_merge;
+#[cfg(feature = "std")]
+pub mod kvs_metrics;
+#[cfg(feature = "std")]
+pub mod kvs_migration;
+#[cfg(feature = "mock")]
 pub mod kvs_mock;
+#[cfg(feature = "std")]
+pub mod kvs_multi_write;
+#[cfg(feature = "std")]
+pub mod kvs_namespace;
+#[cfg(feature = "json-backend")]
+pub mod kvs_patch;
+#[cfg(feature = "std")]
+pub mod kvs_path;
+#[cfg(feature = "std")]
+pub mod kvs_redact;
+#[cfg(feature = "std")]
+pub mod kvs_resolver;
+#[cfg(feature = "snapshots")]
+pub mod kvs_retention;
+#[cfg(feature = "serde")]
+pub mod kvs_serde;
+#[cfg(feature = "shm-cache")]
+pub mod kvs_shared_view;
+#[cfg(feature = "std")]
+pub mod kvs_shutdown;
+pub mod kvs_storage;
+#[cfg(feature = "std")]
+pub mod kvs_transaction;
+#[cfg(feature = "std")]
+pub mod kvs_validator;
 pub mod kvs_value;
+#[cfg(feature = "json-backend")]
+pub mod memory_backend;
+#[cfg(feature = "msgpack-backend")]
+pub mod msgpack_backend;
+#[cfg(feature = "tooling")]
+pub mod protobuf;
+#[cfg(feature = "s3-backend")]
+pub mod s3_backend;
+#[cfg(feature = "serde-json")]
+mod serde_json_interop;
+#[cfg(feature = "json-backend")]
+pub mod single_file_backend;
+#[cfg(feature = "sqlite-backend")]
+pub mod sqlite_backend;
+#[cfg(feature = "toml-backend")]
+pub mod toml_backend;
 
-use json_backend::JsonBackend;
+#[cfg(feature = "json-backend")]
+pub use json_backend::{GenericJsonBackend, JsonBackend};
+#[cfg(feature = "json-backend")]
 pub type KvsBuilder = kvs_builder::GenericKvsBuilder<JsonBackend>;
+#[cfg(feature = "json-backend")]
 pub type Kvs = kvs::GenericKvs<JsonBackend>;
 
 /// Prelude module for convenient imports
 pub mod prelude {
-    pub use crate::error_code::ErrorCode;
+    pub use crate::error_code::{ErrorCode, KvsError};
+    #[cfg(feature = "std")]
     pub use crate::kvs::GenericKvs;
-    pub use crate::kvs_api::{InstanceId, KvsApi, KvsDefaults, KvsLoad, SnapshotId};
+    #[cfg(feature = "std")]
+    pub use crate::kvs_api::{
+        DuplicateKeyPolicy, FloatFormat, FlushOnExit, KeyCharset, KeyScope, KvsApi, KvsCompression,
+        KvsDefaults, KvsDirStrategy, KvsKeyPolicy, KvsLoad, SyncPolicy,
+    };
+    #[cfg(feature = "std")]
     pub use crate::kvs_builder::GenericKvsBuilder;
-    pub use crate::kvs_value::{KvsMap, KvsValue};
+    #[cfg(feature = "std")]
+    pub use crate::kvs_multi_write::MultiKvsWrite;
+    pub use crate::kvs_storage::{InstanceId, KvsStorage, SnapshotId};
+    #[cfg(feature = "std")]
+    pub use crate::kvs_transaction::KvsTransaction;
+    pub use crate::kvs_value::{
+        KvsDeserialize, KvsMap, KvsSerialize, KvsValue, KvsValueKind, ValueModel,
+    };
+    #[cfg(feature = "json-backend")]
     pub use crate::{Kvs, KvsBuilder};
 }
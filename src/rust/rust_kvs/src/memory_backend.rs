@@ -0,0 +1,393 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! In-memory backend.
+//!
+//! [`MemoryBackend`] keeps stored KVS, hash and defaults "files" in a process-global map instead of
+//! the filesystem. Paths are resolved like for the JSON backend, but are only used as keys of the
+//! map, the working directory doesn't need to exist.
+//!
+//! Data survives closing and reopening an instance within the same process, including snapshot
+//! rotation and restore, and is lost when the process exits. Use a distinct working directory per
+//! test to keep tests independent, and [`MemoryBackend::clear_dir`] to remove stored data.
+//!
+//! ```
+//! use rust_kvs::memory_backend::MemoryBackend;
+//! use rust_kvs::prelude::*;
+//!
+//! let kvs = GenericKvsBuilder::<MemoryBackend>::new(InstanceId(0))
+//!     .dir("volatile")
+//!     .build()?;
+//! kvs.set_value("counter", 1)?;
+//! kvs.flush()?;
+//! # Ok::<(), ErrorCode>(())
+//! ```
+
+use crate::error_code::ErrorCode;
+use crate::json_backend::JsonBackend;
+use crate::kvs_api::{InstanceId, SnapshotId, SyncPolicy};
+use crate::kvs_backend::{KvsBackend, KvsPathResolver};
+use crate::kvs_log::kvs_error;
+use crate::kvs_value::KvsMap;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex, MutexGuard};
+
+/// Content of a stored "file".
+#[derive(Clone)]
+enum MemoryFile {
+    Kvs(KvsMap),
+    Hash,
+}
+
+/// Stored "files" of all instances.
+static MEMORY_FILES: LazyLock<Mutex<HashMap<PathBuf, MemoryFile>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Lock stored "files".
+fn files<'a>() -> Result<MutexGuard<'a, HashMap<PathBuf, MemoryFile>>, ErrorCode> {
+    MEMORY_FILES.lock().map_err(|_| ErrorCode::MutexLockFailed)
+}
+
+/// KVS backend storing data in process memory.
+pub struct MemoryBackend;
+
+impl MemoryBackend {
+    /// Store KvsMap at given path, e.g. to provide defaults before an instance is opened.
+    ///
+    /// # Parameters
+    ///   * `path`: Path as resolved by `KvsPathResolver`
+    ///   * `kvs_map`: Content to store
+    ///
+    /// # Return Values
+    ///   * Ok: Content stored
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    pub fn insert(path: &Path, kvs_map: KvsMap) -> Result<(), ErrorCode> {
+        files()?.insert(path.to_path_buf(), MemoryFile::Kvs(kvs_map));
+        Ok(())
+    }
+
+    /// Get KvsMap stored at given path.
+    ///
+    /// # Parameters
+    ///   * `path`: Path as resolved by `KvsPathResolver`
+    ///
+    /// # Return Values
+    ///   * Ok(Some): Stored content
+    ///   * Ok(None): Nothing stored at path
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    pub fn get(path: &Path) -> Result<Option<KvsMap>, ErrorCode> {
+        Ok(match files()?.get(path) {
+            Some(MemoryFile::Kvs(kvs_map)) => Some(kvs_map.clone()),
+            _ => None,
+        })
+    }
+
+    /// Remove everything stored in a working directory.
+    ///
+    /// # Parameters
+    ///   * `working_dir`: Working directory of the instances
+    ///
+    /// # Return Values
+    ///   * Ok: Stored data removed
+    ///   * `ErrorCode::MutexLockFailed`: Mutex locking failed
+    pub fn clear_dir(working_dir: &Path) -> Result<(), ErrorCode> {
+        files()?.retain(|path, _| path.parent() != Some(working_dir));
+        Ok(())
+    }
+}
+
+impl KvsBackend for MemoryBackend {
+    fn load_kvs(kvs_path: &Path, hash_path: Option<&PathBuf>) -> Result<KvsMap, ErrorCode> {
+        let files = files()?;
+        let Some(MemoryFile::Kvs(kvs_map)) = files.get(kvs_path) else {
+            kvs_error!("KVS file not found: {kvs_path:?}");
+            return Err(ErrorCode::FileNotFound);
+        };
+        if let Some(hash_path) = hash_path {
+            if !matches!(files.get(hash_path), Some(MemoryFile::Hash)) {
+                kvs_error!("hash file not found: {hash_path:?}");
+                return Err(ErrorCode::KvsHashFileReadError);
+            }
+        }
+        Ok(kvs_map.clone())
+    }
+
+    fn save_kvs(
+        kvs_map: &KvsMap,
+        kvs_path: &Path,
+        hash_path: Option<&PathBuf>,
+    ) -> Result<(), ErrorCode> {
+        let mut files = files()?;
+        files.insert(kvs_path.to_path_buf(), MemoryFile::Kvs(kvs_map.clone()));
+        if let Some(hash_path) = hash_path {
+            files.insert(hash_path.clone(), MemoryFile::Hash);
+        }
+        Ok(())
+    }
+
+    fn exists(path: &Path) -> bool {
+        files().is_ok_and(|files| files.contains_key(path))
+    }
+
+    fn locks_working_dir() -> bool {
+        false
+    }
+
+    fn sync_kvs(
+        _kvs_path: &Path,
+        _hash_path: &Path,
+        _sync_policy: SyncPolicy,
+    ) -> Result<(), ErrorCode> {
+        Ok(())
+    }
+
+    fn move_kvs(
+        old_kvs_path: &Path,
+        old_hash_path: &Path,
+        new_kvs_path: &Path,
+        new_hash_path: &Path,
+    ) -> Result<(), ErrorCode> {
+        let mut files = files()?;
+        match (files.remove(old_kvs_path), files.remove(old_hash_path)) {
+            (Some(kvs_file), Some(hash_file)) => {
+                files.insert(new_hash_path.to_path_buf(), hash_file);
+                files.insert(new_kvs_path.to_path_buf(), kvs_file);
+                Ok(())
+            }
+            (None, None) => Ok(()),
+            // Either snapshot or hash file got removed.
+            _ => Err(ErrorCode::IntegrityCorrupted),
+        }
+    }
+
+    fn remove_kvs(kvs_path: &Path, hash_path: &Path) -> Result<(), ErrorCode> {
+        let mut files = files()?;
+        files.remove(kvs_path);
+        files.remove(hash_path);
+        Ok(())
+    }
+}
+
+impl KvsPathResolver for MemoryBackend {
+    fn kvs_file_name(instance_id: InstanceId, snapshot_id: SnapshotId) -> String {
+        JsonBackend::kvs_file_name(instance_id, snapshot_id)
+    }
+
+    fn kvs_file_path(
+        working_dir: &Path,
+        instance_id: InstanceId,
+        snapshot_id: SnapshotId,
+    ) -> PathBuf {
+        JsonBackend::kvs_file_path(working_dir, instance_id, snapshot_id)
+    }
+
+    fn hash_file_name(instance_id: InstanceId, snapshot_id: SnapshotId) -> String {
+        JsonBackend::hash_file_name(instance_id, snapshot_id)
+    }
+
+    fn hash_file_path(
+        working_dir: &Path,
+        instance_id: InstanceId,
+        snapshot_id: SnapshotId,
+    ) -> PathBuf {
+        JsonBackend::hash_file_path(working_dir, instance_id, snapshot_id)
+    }
+
+    fn defaults_file_name(instance_id: InstanceId) -> String {
+        JsonBackend::defaults_file_name(instance_id)
+    }
+
+    fn defaults_file_path(working_dir: &Path, instance_id: InstanceId) -> PathBuf {
+        JsonBackend::defaults_file_path(working_dir, instance_id)
+    }
+
+    fn global_defaults_file_name() -> String {
+        JsonBackend::global_defaults_file_name()
+    }
+
+    fn global_defaults_file_path(working_dir: &Path) -> PathBuf {
+        JsonBackend::global_defaults_file_path(working_dir)
+    }
+}
+
+#[cfg(test)]
+mod backend_tests {
+    use crate::error_code::ErrorCode;
+    use crate::kvs_backend::KvsBackend;
+    use crate::kvs_value::{KvsMap, KvsValue};
+    use crate::memory_backend::MemoryBackend;
+    use std::path::{Path, PathBuf};
+
+    fn create_kvs_files(working_dir: &Path) -> (PathBuf, PathBuf) {
+        let kvs_map = KvsMap::from([("k1".to_string(), KvsValue::from("v1"))]);
+        let kvs_path = working_dir.join("kvs.json");
+        let hash_path = working_dir.join("kvs.hash");
+        MemoryBackend::save_kvs(&kvs_map, &kvs_path, Some(&hash_path)).unwrap();
+        (kvs_path, hash_path)
+    }
+
+    #[test]
+    fn test_save_load_kvs() {
+        let (kvs_path, hash_path) = create_kvs_files(Path::new("mem_save_load"));
+        assert!(MemoryBackend::exists(&kvs_path));
+        assert!(MemoryBackend::exists(&hash_path));
+
+        let kvs_map = MemoryBackend::load_kvs(&kvs_path, Some(&hash_path)).unwrap();
+        assert_eq!(kvs_map["k1"], KvsValue::from("v1"));
+    }
+
+    #[test]
+    fn test_load_kvs_not_found() {
+        let working_dir = Path::new("mem_not_found");
+        assert!(MemoryBackend::load_kvs(&working_dir.join("kvs.json"), None)
+            .is_err_and(|e| e == ErrorCode::FileNotFound));
+
+        let kvs_path = working_dir.join("kvs.json");
+        MemoryBackend::save_kvs(&KvsMap::new(), &kvs_path, None).unwrap();
+        assert!(
+            MemoryBackend::load_kvs(&kvs_path, Some(&working_dir.join("kvs.hash")))
+                .is_err_and(|e| e == ErrorCode::KvsHashFileReadError)
+        );
+    }
+
+    #[test]
+    fn test_move_kvs() {
+        let working_dir = Path::new("mem_move");
+        let (kvs_path, hash_path) = create_kvs_files(working_dir);
+        let new_kvs_path = working_dir.join("kvs_1.json");
+        let new_hash_path = working_dir.join("kvs_1.hash");
+        MemoryBackend::move_kvs(&kvs_path, &hash_path, &new_kvs_path, &new_hash_path).unwrap();
+        assert!(!MemoryBackend::exists(&kvs_path));
+        assert!(!MemoryBackend::exists(&hash_path));
+        assert!(MemoryBackend::load_kvs(&new_kvs_path, Some(&new_hash_path)).is_ok());
+
+        // Nothing to move.
+        MemoryBackend::move_kvs(&kvs_path, &hash_path, &new_kvs_path, &new_hash_path).unwrap();
+        assert!(MemoryBackend::exists(&new_kvs_path));
+    }
+
+    #[test]
+    fn test_move_kvs_hash_missing() {
+        let working_dir = Path::new("mem_move_hash_missing");
+        let kvs_path = working_dir.join("kvs.json");
+        MemoryBackend::save_kvs(&KvsMap::new(), &kvs_path, None).unwrap();
+        assert!(MemoryBackend::move_kvs(
+            &kvs_path,
+            &working_dir.join("kvs.hash"),
+            &working_dir.join("kvs_1.json"),
+            &working_dir.join("kvs_1.hash"),
+        )
+        .is_err_and(|e| e == ErrorCode::IntegrityCorrupted));
+    }
+
+    #[test]
+    fn test_insert_get_clear_dir() {
+        let working_dir = Path::new("mem_clear_dir");
+        let other_dir = Path::new("mem_clear_dir_other");
+        let kvs_map = KvsMap::from([("k1".to_string(), KvsValue::I32(1))]);
+        MemoryBackend::insert(&working_dir.join("kvs.json"), kvs_map.clone()).unwrap();
+        MemoryBackend::insert(&other_dir.join("kvs.json"), kvs_map.clone()).unwrap();
+        assert_eq!(
+            MemoryBackend::get(&working_dir.join("kvs.json")).unwrap(),
+            Some(kvs_map)
+        );
+
+        MemoryBackend::clear_dir(working_dir).unwrap();
+        assert_eq!(
+            MemoryBackend::get(&working_dir.join("kvs.json")).unwrap(),
+            None
+        );
+        assert!(MemoryBackend::exists(&other_dir.join("kvs.json")));
+    }
+}
+
+#[cfg(test)]
+mod kvs_tests {
+    use crate::error_code::ErrorCode;
+    use crate::kvs_api::{InstanceId, KvsApi, KvsLoad};
+    #[cfg(feature = "snapshots")]
+    use crate::kvs_api::{KvsDefaults, SnapshotId};
+    #[cfg(feature = "snapshots")]
+    use crate::kvs_backend::KvsPathResolver;
+    use crate::kvs_builder::kvs_builder_tests::lock_and_reset;
+    use crate::kvs_builder::GenericKvsBuilder;
+    #[cfg(feature = "snapshots")]
+    use crate::kvs_value::{KvsMap, KvsValue};
+    use crate::memory_backend::MemoryBackend;
+    use std::path::Path;
+
+    #[cfg(feature = "snapshots")]
+    #[test]
+    fn test_flush_and_reopen() {
+        let _lock = lock_and_reset();
+        let working_dir = Path::new("mem_flush_and_reopen");
+        MemoryBackend::clear_dir(working_dir).unwrap();
+        MemoryBackend::insert(
+            &MemoryBackend::defaults_file_path(working_dir, InstanceId(1)),
+            KvsMap::from([("default".to_string(), KvsValue::I32(1))]),
+        )
+        .unwrap();
+
+        let kvs = GenericKvsBuilder::<MemoryBackend>::new(InstanceId(1))
+            .dir("mem_flush_and_reopen")
+            .defaults(KvsDefaults::Required)
+            .build()
+            .unwrap();
+        for idx in 0..3 {
+            kvs.set_value("counter", idx).unwrap();
+            kvs.flush().unwrap();
+        }
+        assert_eq!(kvs.snapshot_count(), 3);
+        kvs.snapshot_restore(SnapshotId(1)).unwrap();
+        assert_eq!(kvs.get_value_as::<i32>("counter").unwrap(), 1);
+        kvs.flush().unwrap();
+        drop(kvs);
+
+        // Reopen from the stored data.
+        drop(_lock);
+        let _lock = lock_and_reset();
+        let kvs = GenericKvsBuilder::<MemoryBackend>::new(InstanceId(1))
+            .dir("mem_flush_and_reopen")
+            .kvs_load(KvsLoad::Required)
+            .build()
+            .unwrap();
+        assert_eq!(kvs.get_value_as::<i32>("counter").unwrap(), 1);
+        assert_eq!(kvs.get_value_as::<i32>("default").unwrap(), 1);
+        assert!(kvs.get_kvs_filename(SnapshotId(1)).is_ok());
+        assert!(kvs
+            .get_kvs_filename(SnapshotId(4))
+            .is_err_and(|e| e == ErrorCode::FileNotFound));
+    }
+
+    #[test]
+    fn test_build_nothing_stored() {
+        let _lock = lock_and_reset();
+        let working_dir = Path::new("mem_nothing_stored");
+        MemoryBackend::clear_dir(working_dir).unwrap();
+
+        let kvs = GenericKvsBuilder::<MemoryBackend>::new(InstanceId(2))
+            .dir("mem_nothing_stored")
+            .build()
+            .unwrap();
+        assert!(kvs.get_all_keys().unwrap().is_empty());
+        assert_eq!(kvs.snapshot_count(), 0);
+        drop(kvs);
+
+        drop(_lock);
+        let _lock = lock_and_reset();
+        assert!(GenericKvsBuilder::<MemoryBackend>::new(InstanceId(2))
+            .dir("mem_nothing_stored")
+            .kvs_load(KvsLoad::Required)
+            .build()
+            .is_err_and(|e| e == ErrorCode::FileNotFound));
+    }
+}
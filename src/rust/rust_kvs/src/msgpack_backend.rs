@@ -0,0 +1,516 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::error_code::ErrorCode;
+use crate::kvs_api::{FloatFormat, InstanceId, SnapshotId};
+use crate::kvs_backend::{
+    check_extension, validate_hash, write_hash, KvsBackend, KvsPathResolver, MAX_NESTING_DEPTH,
+};
+use crate::kvs_fs::StdFs;
+use crate::kvs_log::kvs_error;
+use crate::kvs_value::{KvsMap, KvsValue};
+use rmp::encode::{self, ValueWriteError};
+use rmp::Marker;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// KvsValue is stored using native MessagePack types, the root object is a map.
+// Integer width is preserved by always using fixed-width markers on write:
+//   * `I32` -> int 32, `U32` -> uint 32, `I64` -> int 64, `U64` -> uint 64
+//   * `F64` -> float 64, `Boolean` -> bool, `String` -> str, `Null` -> nil
+//   * `Array` -> array, `Object` -> map with str keys
+//
+// Files written by other MessagePack libraries use the most compact integer encoding.
+// Such integers (fixint, int 8/16, uint 8/16) are read as `I32`, float 32 is read as `F64`.
+// Arrays and maps nested deeper than `MAX_NESTING_DEPTH` levels are rejected when reading.
+
+/// Cursor over MessagePack encoded data.
+struct Reader<'a> {
+    buf: &'a [u8],
+    depth: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ErrorCode> {
+        if self.buf.len() < len {
+            kvs_error!("MessagePack data truncated");
+            return Err(ErrorCode::SerializationFailed);
+        }
+        let (head, tail) = self.buf.split_at(len);
+        self.buf = tail;
+        Ok(head)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N], ErrorCode> {
+        Ok(self.take(N)?.try_into()?)
+    }
+
+    fn u8(&mut self) -> Result<u8, ErrorCode> {
+        Ok(self.take_array::<1>()?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, ErrorCode> {
+        Ok(u16::from_be_bytes(self.take_array()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, ErrorCode> {
+        Ok(u32::from_be_bytes(self.take_array()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, ErrorCode> {
+        Ok(u64::from_be_bytes(self.take_array()?))
+    }
+
+    fn string(&mut self, len: usize) -> Result<String, ErrorCode> {
+        Ok(String::from_utf8(self.take(len)?.to_vec())?)
+    }
+
+    fn enter(&mut self) -> Result<(), ErrorCode> {
+        if self.depth == MAX_NESTING_DEPTH {
+            kvs_error!("MessagePack data nested deeper than {MAX_NESTING_DEPTH} levels");
+            return Err(ErrorCode::SerializationFailed);
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn array(&mut self, len: usize) -> Result<KvsValue, ErrorCode> {
+        self.enter()?;
+        // Length is not trusted for preallocation, every element takes at least one byte.
+        let mut arr = Vec::with_capacity(len.min(self.buf.len()));
+        for _ in 0..len {
+            arr.push(self.value()?);
+        }
+        self.depth -= 1;
+        Ok(KvsValue::Array(arr))
+    }
+
+    fn map(&mut self, len: usize) -> Result<KvsMap, ErrorCode> {
+        self.enter()?;
+        let mut map = KvsMap::with_capacity(len.min(self.buf.len() / 2));
+        for _ in 0..len {
+            let key = match self.value()? {
+                KvsValue::String(key) => key,
+                _ => {
+                    kvs_error!("MessagePack map key is not a string");
+                    return Err(ErrorCode::SerializationFailed);
+                }
+            };
+            let value = self.value()?;
+            map.insert(key, value);
+        }
+        self.depth -= 1;
+        Ok(map)
+    }
+
+    fn value(&mut self) -> Result<KvsValue, ErrorCode> {
+        let value = match Marker::from_u8(self.u8()?) {
+            Marker::FixPos(v) => KvsValue::I32(i32::from(v)),
+            Marker::FixNeg(v) => KvsValue::I32(i32::from(v)),
+            Marker::U8 => KvsValue::I32(i32::from(self.u8()?)),
+            Marker::U16 => KvsValue::I32(i32::from(self.u16()?)),
+            Marker::I8 => KvsValue::I32(i32::from(self.u8()? as i8)),
+            Marker::I16 => KvsValue::I32(i32::from(self.u16()? as i16)),
+            Marker::I32 => KvsValue::I32(self.u32()? as i32),
+            Marker::U32 => KvsValue::U32(self.u32()?),
+            Marker::I64 => KvsValue::I64(self.u64()? as i64),
+            Marker::U64 => KvsValue::U64(self.u64()?),
+            Marker::F32 => KvsValue::F64(f64::from(f32::from_bits(self.u32()?))),
+            Marker::F64 => KvsValue::F64(f64::from_bits(self.u64()?)),
+            Marker::Null => KvsValue::Null,
+            Marker::True => KvsValue::Boolean(true),
+            Marker::False => KvsValue::Boolean(false),
+            Marker::FixStr(len) => KvsValue::String(self.string(len as usize)?),
+            Marker::Str8 => {
+                let len = self.u8()? as usize;
+                KvsValue::String(self.string(len)?)
+            }
+            Marker::Str16 => {
+                let len = self.u16()? as usize;
+                KvsValue::String(self.string(len)?)
+            }
+            Marker::Str32 => {
+                let len = self.u32()? as usize;
+                KvsValue::String(self.string(len)?)
+            }
+            Marker::FixArray(len) => self.array(len as usize)?,
+            Marker::Array16 => {
+                let len = self.u16()? as usize;
+                self.array(len)?
+            }
+            Marker::Array32 => {
+                let len = self.u32()? as usize;
+                self.array(len)?
+            }
+            Marker::FixMap(len) => KvsValue::Object(self.map(len as usize)?),
+            Marker::Map16 => {
+                let len = self.u16()? as usize;
+                KvsValue::Object(self.map(len)?)
+            }
+            Marker::Map32 => {
+                let len = self.u32()? as usize;
+                KvsValue::Object(self.map(len)?)
+            }
+            // Binary, extension and reserved types are not supported.
+            marker => {
+                kvs_error!("unsupported MessagePack marker: {marker:?}");
+                return Err(ErrorCode::SerializationFailed);
+            }
+        };
+        Ok(value)
+    }
+}
+
+/// Decode MessagePack data containing a map into `KvsMap`.
+fn decode(buf: &[u8]) -> Result<KvsMap, ErrorCode> {
+    let mut reader = Reader { buf, depth: 0 };
+    let kvs_map = match reader.value()? {
+        KvsValue::Object(kvs_map) => kvs_map,
+        _ => {
+            kvs_error!("MessagePack root is not a map");
+            return Err(ErrorCode::SerializationFailed);
+        }
+    };
+    if !reader.buf.is_empty() {
+        kvs_error!("MessagePack data contains trailing bytes");
+        return Err(ErrorCode::SerializationFailed);
+    }
+    Ok(kvs_map)
+}
+
+/// rmp::encode::ValueWriteError -> ErrorCode::SerializationFailed
+impl From<ValueWriteError<std::io::Error>> for ErrorCode {
+    fn from(cause: ValueWriteError<std::io::Error>) -> Self {
+        kvs_error!("MessagePack generator error: {cause}");
+        ErrorCode::SerializationFailed
+    }
+}
+
+/// Encode `KvsValue` into MessagePack data.
+fn encode_value(buf: &mut Vec<u8>, value: &KvsValue) -> Result<(), ErrorCode> {
+    match value {
+        KvsValue::I32(n) => encode::write_i32(buf, *n)?,
+        KvsValue::U32(n) => encode::write_u32(buf, *n)?,
+        KvsValue::I64(n) => encode::write_i64(buf, *n)?,
+        KvsValue::U64(n) => encode::write_u64(buf, *n)?,
+        KvsValue::F64(n) => encode::write_f64(buf, *n)?,
+        KvsValue::Boolean(b) => encode::write_bool(buf, *b)?,
+        KvsValue::String(s) => encode::write_str(buf, s)?,
+        KvsValue::Null => encode::write_nil(buf)?,
+        KvsValue::Array(arr) => {
+            encode::write_array_len(buf, len_u32(arr.len())?)?;
+            for element in arr {
+                encode_value(buf, element)?;
+            }
+        }
+        KvsValue::Object(map) => encode_map(buf, map)?,
+    }
+    Ok(())
+}
+
+/// Encode `KvsMap` into MessagePack map.
+fn encode_map(buf: &mut Vec<u8>, kvs_map: &KvsMap) -> Result<(), ErrorCode> {
+    encode::write_map_len(buf, len_u32(kvs_map.len())?)?;
+    for (key, value) in kvs_map {
+        encode::write_str(buf, key)?;
+        encode_value(buf, value)?;
+    }
+    Ok(())
+}
+
+/// MessagePack containers are limited to `u32::MAX` elements.
+fn len_u32(len: usize) -> Result<u32, ErrorCode> {
+    u32::try_from(len).map_err(|_| ErrorCode::SerializationFailed)
+}
+
+/// KVS backend implementation based on MessagePack.
+pub struct MsgPackBackend;
+
+impl KvsBackend for MsgPackBackend {
+    fn load_kvs(kvs_path: &Path, hash_path: Option<&PathBuf>) -> Result<KvsMap, ErrorCode> {
+        if !check_extension(kvs_path, "msgpack") {
+            return Err(ErrorCode::KvsFileReadError);
+        }
+        if hash_path.is_some_and(|p| !check_extension(p, "hash")) {
+            return Err(ErrorCode::KvsHashFileReadError);
+        }
+
+        // Load KVS file.
+        let bytes = fs::read(kvs_path)?;
+
+        // Perform hash check before decoding.
+        if let Some(hash_path) = hash_path {
+            validate_hash::<StdFs>(&bytes, hash_path)?;
+        }
+
+        decode(&bytes)
+    }
+
+    fn load_kvs_from_bytes(bytes: &[u8]) -> Result<KvsMap, ErrorCode> {
+        decode(bytes)
+    }
+
+    fn save_kvs(
+        kvs_map: &KvsMap,
+        kvs_path: &Path,
+        hash_path: Option<&PathBuf>,
+    ) -> Result<(), ErrorCode> {
+        // Validate extensions.
+        if !check_extension(kvs_path, "msgpack") {
+            return Err(ErrorCode::KvsFileReadError);
+        }
+        if hash_path.is_some_and(|p| !check_extension(p, "hash")) {
+            return Err(ErrorCode::KvsHashFileReadError);
+        }
+
+        // Encode and save to KVS file.
+        let mut bytes = Vec::new();
+        encode_map(&mut bytes, kvs_map)?;
+        fs::write(kvs_path, &bytes)?;
+
+        // Generate hash and save to hash file.
+        if let Some(hash_path) = hash_path {
+            write_hash::<StdFs>(&bytes, hash_path)?;
+        }
+
+        Ok(())
+    }
+
+    fn serialized_size(kvs_map: &KvsMap, _float_format: &FloatFormat) -> Result<usize, ErrorCode> {
+        let mut bytes = Vec::new();
+        encode_map(&mut bytes, kvs_map)?;
+        Ok(bytes.len())
+    }
+}
+
+/// KVS backend path resolver for `MsgPackBackend`.
+impl KvsPathResolver for MsgPackBackend {
+    fn kvs_file_name(instance_id: InstanceId, snapshot_id: SnapshotId) -> String {
+        format!("kvs_{instance_id}_{snapshot_id}.msgpack")
+    }
+
+    fn kvs_file_path(
+        working_dir: &Path,
+        instance_id: InstanceId,
+        snapshot_id: SnapshotId,
+    ) -> PathBuf {
+        working_dir.join(Self::kvs_file_name(instance_id, snapshot_id))
+    }
+
+    fn hash_file_name(instance_id: InstanceId, snapshot_id: SnapshotId) -> String {
+        format!("kvs_{instance_id}_{snapshot_id}.hash")
+    }
+
+    fn hash_file_path(
+        working_dir: &Path,
+        instance_id: InstanceId,
+        snapshot_id: SnapshotId,
+    ) -> PathBuf {
+        working_dir.join(Self::hash_file_name(instance_id, snapshot_id))
+    }
+
+    fn defaults_file_name(instance_id: InstanceId) -> String {
+        format!("kvs_{instance_id}_default.msgpack")
+    }
+
+    fn defaults_file_path(working_dir: &Path, instance_id: InstanceId) -> PathBuf {
+        working_dir.join(Self::defaults_file_name(instance_id))
+    }
+
+    fn global_defaults_file_name() -> String {
+        "kvs_global_default.msgpack".to_string()
+    }
+
+    fn global_defaults_file_path(working_dir: &Path) -> PathBuf {
+        working_dir.join(Self::global_defaults_file_name())
+    }
+}
+
+#[cfg(test)]
+mod msgpack_conversion_tests {
+    use crate::error_code::ErrorCode;
+    use crate::kvs_backend::MAX_NESTING_DEPTH;
+    use crate::kvs_value::{KvsMap, KvsValue};
+    use crate::msgpack_backend::{decode, encode_map};
+
+    fn roundtrip(kvs_map: &KvsMap) -> KvsMap {
+        let mut bytes = Vec::new();
+        encode_map(&mut bytes, kvs_map).unwrap();
+        decode(&bytes).unwrap()
+    }
+
+    #[test]
+    fn test_roundtrip_all_types() {
+        let kvs_map = KvsMap::from([
+            ("i32".to_string(), KvsValue::I32(i32::MIN)),
+            ("u32".to_string(), KvsValue::U32(1)),
+            ("i64".to_string(), KvsValue::I64(i64::MIN)),
+            ("u64".to_string(), KvsValue::U64(u64::MAX)),
+            ("f64".to_string(), KvsValue::F64(-432.1)),
+            ("bool".to_string(), KvsValue::Boolean(true)),
+            ("str".to_string(), KvsValue::from("example")),
+            ("null".to_string(), KvsValue::Null),
+            (
+                "arr".to_string(),
+                KvsValue::Array(vec![KvsValue::I32(1), KvsValue::from("two")]),
+            ),
+            (
+                "obj".to_string(),
+                KvsValue::Object(KvsMap::from([("x".to_string(), KvsValue::U64(0))])),
+            ),
+        ]);
+        assert_eq!(roundtrip(&kvs_map), kvs_map);
+    }
+
+    #[test]
+    fn test_fixed_width_integers() {
+        let mut bytes = Vec::new();
+        encode_map(
+            &mut bytes,
+            &KvsMap::from([("a".to_string(), KvsValue::U64(1))]),
+        )
+        .unwrap();
+        // fixmap(1), fixstr(1) "a", uint 64 marker, 8 bytes.
+        assert_eq!(bytes, vec![0x81, 0xa1, b'a', 0xcf, 0, 0, 0, 0, 0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn test_decode_compact_integers() {
+        // fixmap(2), "a" -> positive fixint 5, "b" -> int 8 -3
+        let bytes = vec![0x82, 0xa1, b'a', 0x05, 0xa1, b'b', 0xd0, 0xfd];
+        let kvs_map = decode(&bytes).unwrap();
+        assert_eq!(kvs_map["a"], KvsValue::I32(5));
+        assert_eq!(kvs_map["b"], KvsValue::I32(-3));
+    }
+
+    #[test]
+    fn test_decode_root_not_map() {
+        assert!(decode(&[0x90]).is_err_and(|e| e == ErrorCode::SerializationFailed));
+    }
+
+    #[test]
+    fn test_decode_truncated() {
+        // fixmap(1), str 32 with length larger than remaining data.
+        let bytes = vec![0x81, 0xdb, 0xff, 0xff, 0xff, 0xff];
+        assert!(decode(&bytes).is_err_and(|e| e == ErrorCode::SerializationFailed));
+    }
+
+    #[test]
+    fn test_decode_trailing_bytes() {
+        assert!(decode(&[0x80, 0xc0]).is_err_and(|e| e == ErrorCode::SerializationFailed));
+    }
+
+    #[test]
+    fn test_decode_non_string_key() {
+        assert!(decode(&[0x81, 0x01, 0xc0]).is_err_and(|e| e == ErrorCode::SerializationFailed));
+    }
+
+    #[test]
+    fn test_decode_nesting_depth() {
+        // fixmap(1), "a" -> nested fixarray(1) ending in nil.
+        let nested = |levels: usize| {
+            let mut bytes = vec![0x81, 0xa1, b'a'];
+            bytes.extend(std::iter::repeat_n(0x91, levels));
+            bytes.push(0xc0);
+            bytes
+        };
+        assert!(decode(&nested(MAX_NESTING_DEPTH - 1)).is_ok());
+        assert!(
+            decode(&nested(MAX_NESTING_DEPTH)).is_err_and(|e| e == ErrorCode::SerializationFailed)
+        );
+        assert!(decode(&[0x91; 1 << 20]).is_err_and(|e| e == ErrorCode::SerializationFailed));
+    }
+
+    #[test]
+    fn test_decode_unsupported_marker() {
+        // fixmap(1), "a" -> bin 8 with 0 length.
+        let bytes = vec![0x81, 0xa1, b'a', 0xc4, 0x00];
+        assert!(decode(&bytes).is_err_and(|e| e == ErrorCode::SerializationFailed));
+    }
+}
+
+#[cfg(test)]
+mod backend_tests {
+    use crate::error_code::ErrorCode;
+    use crate::kvs_backend::KvsBackend;
+    use crate::kvs_value::{KvsMap, KvsValue};
+    use crate::msgpack_backend::MsgPackBackend;
+    use std::path::{Path, PathBuf};
+    use tempfile::tempdir;
+
+    fn create_kvs_files(working_dir: &Path) -> (PathBuf, PathBuf) {
+        let kvs_map = KvsMap::from([
+            ("k1".to_string(), KvsValue::from("v1")),
+            ("k2".to_string(), KvsValue::from(true)),
+            ("k3".to_string(), KvsValue::from(123.4)),
+        ]);
+        let kvs_path = working_dir.join("kvs.msgpack");
+        let hash_path = working_dir.join("kvs.hash");
+        MsgPackBackend::save_kvs(&kvs_map, &kvs_path, Some(&hash_path)).unwrap();
+        (kvs_path, hash_path)
+    }
+
+    #[test]
+    fn test_load_kvs_hash_path_some_ok() {
+        let dir = tempdir().unwrap();
+        let (kvs_path, hash_path) = create_kvs_files(dir.path());
+
+        let kvs_map = MsgPackBackend::load_kvs(&kvs_path, Some(&hash_path)).unwrap();
+        assert_eq!(kvs_map.len(), 3);
+    }
+
+    #[test]
+    fn test_load_kvs_invalid_extension() {
+        let dir = tempdir().unwrap();
+        let kvs_path = dir.path().join("kvs.json");
+
+        assert!(MsgPackBackend::load_kvs(&kvs_path, None)
+            .is_err_and(|e| e == ErrorCode::KvsFileReadError));
+    }
+
+    #[test]
+    fn test_load_kvs_invalid_hash_content() {
+        let dir = tempdir().unwrap();
+        let (kvs_path, hash_path) = create_kvs_files(dir.path());
+        std::fs::write(&hash_path, vec![0x12, 0x34, 0x56, 0x78]).unwrap();
+
+        assert!(MsgPackBackend::load_kvs(&kvs_path, Some(&hash_path))
+            .is_err_and(|e| e == ErrorCode::ValidationFailed));
+    }
+
+    #[test]
+    fn test_save_kvs_invalid_extension() {
+        let dir = tempdir().unwrap();
+        let kvs_path = dir.path().join("kvs.invalid_ext");
+
+        assert!(MsgPackBackend::save_kvs(&KvsMap::new(), &kvs_path, None)
+            .is_err_and(|e| e == ErrorCode::KvsFileReadError));
+    }
+}
+
+#[cfg(test)]
+mod path_resolver_tests {
+    use crate::kvs_api::{InstanceId, SnapshotId};
+    use crate::kvs_backend::KvsPathResolver;
+    use crate::msgpack_backend::MsgPackBackend;
+
+    #[test]
+    fn test_kvs_file_name() {
+        let act_name = MsgPackBackend::kvs_file_name(InstanceId(123), SnapshotId(2));
+        assert_eq!(act_name, "kvs_123_2.msgpack");
+    }
+
+    #[test]
+    fn test_defaults_file_name() {
+        let act_name = MsgPackBackend::defaults_file_name(InstanceId(123));
+        assert_eq!(act_name, "kvs_123_default.msgpack");
+    }
+}
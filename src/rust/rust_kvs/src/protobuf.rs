@@ -0,0 +1,714 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Protobuf representation of KVS contents.
+//!
+//! A message schema is derived from a defaults map with [`ProtoSchema::from_defaults`]. Every
+//! top-level key becomes a field, field numbers are assigned in sorted key order starting at `1`:
+//!   * `I32` -> `int32`, `U32` -> `uint32`, `I64` -> `int64`, `U64` -> `uint64`
+//!   * `F64` -> `double`, `Boolean` -> `bool`, `String` -> `string`
+//!   * `Object` -> nested message
+//!   * `Array` -> `repeated` field of the element type, all elements must share one type
+//!
+//! `Null` values and empty arrays carry no type information and are left out of the schema.
+//! Keys that are no valid protobuf identifiers are sanitized, see [`ProtoField::name`].
+//!
+//! The schema can be exported as `.proto` file with [`ProtoSchema::to_proto`] and used to
+//! [`encode`](ProtoSchema::encode) and [`decode`](ProtoSchema::decode) messages in protobuf wire
+//! format. Entries present in the map are always encoded, also if they hold the protobuf default
+//! value, so a decoded map contains exactly the encoded keys.
+
+use crate::error_code::ErrorCode;
+use crate::kvs_log::kvs_error;
+use crate::kvs_value::{KvsMap, KvsValue};
+use std::collections::HashSet;
+use std::mem::discriminant;
+
+/// Wire type for `int32`, `uint32`, `int64`, `uint64` and `bool`.
+const WIRE_VARINT: u8 = 0;
+
+/// Wire type for `double`.
+const WIRE_FIXED64: u8 = 1;
+
+/// Wire type for `string`, nested messages and packed repeated fields.
+const WIRE_LEN: u8 = 2;
+
+/// Wire type for `float` and other 32-bit fixed-size values, only skipped on decode.
+const WIRE_FIXED32: u8 = 5;
+
+/// Protobuf field type.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProtoType {
+    Int32,
+    UInt32,
+    Int64,
+    UInt64,
+    Double,
+    Bool,
+    String,
+    Message(ProtoSchema),
+}
+
+impl ProtoType {
+    /// Type name used in `.proto` files.
+    fn proto_name(&self) -> &str {
+        match self {
+            ProtoType::Int32 => "int32",
+            ProtoType::UInt32 => "uint32",
+            ProtoType::Int64 => "int64",
+            ProtoType::UInt64 => "uint64",
+            ProtoType::Double => "double",
+            ProtoType::Bool => "bool",
+            ProtoType::String => "string",
+            ProtoType::Message(schema) => &schema.name,
+        }
+    }
+
+    fn wire_type(&self) -> u8 {
+        match self {
+            ProtoType::Double => WIRE_FIXED64,
+            ProtoType::String | ProtoType::Message(_) => WIRE_LEN,
+            _ => WIRE_VARINT,
+        }
+    }
+
+    /// Repeated numeric fields are packed.
+    fn is_packed(&self) -> bool {
+        self.wire_type() != WIRE_LEN
+    }
+}
+
+/// Protobuf message field.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProtoField {
+    /// KVS key of the field.
+    pub key: String,
+
+    /// Field name, the key with characters other than ASCII alphanumerics and `_` replaced by `_`
+    /// and prefixed with `k` if it doesn't start with an ASCII letter.
+    pub name: String,
+
+    /// Field number.
+    pub number: u32,
+
+    /// Field is `repeated`.
+    pub repeated: bool,
+
+    /// Field type.
+    pub kind: ProtoType,
+}
+
+/// Protobuf message schema.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProtoSchema {
+    name: String,
+    fields: Vec<ProtoField>,
+}
+
+impl ProtoSchema {
+    /// Derive message schema from a defaults map.
+    ///
+    /// Nested messages are named after the field name in CamelCase.
+    ///
+    /// # Parameters
+    ///   * `message_name`: Name of the message, must be a valid protobuf identifier
+    ///   * `defaults_map`: Map to derive the schema from
+    ///
+    /// # Return Values
+    ///   * Ok: Derived schema
+    ///   * `ErrorCode::ConversionFailed`: Invalid message name, array with mixed or nested types,
+    ///     or keys mapping to the same field or message name
+    pub fn from_defaults(message_name: &str, defaults_map: &KvsMap) -> Result<Self, ErrorCode> {
+        if !is_identifier(message_name) {
+            kvs_error!("invalid protobuf message name: {message_name}");
+            return Err(ErrorCode::ConversionFailed);
+        }
+
+        let mut keys: Vec<&String> = defaults_map.keys().collect();
+        keys.sort();
+
+        let mut names = HashSet::new();
+        let mut fields = Vec::new();
+        for key in keys {
+            let name = field_name(key);
+            let (kind, repeated) = match field_type(&name, &defaults_map[key])? {
+                Some(field_type) => field_type,
+                None => continue,
+            };
+
+            // Field names and nested message names share one scope.
+            let nested_name = match &kind {
+                ProtoType::Message(schema) => Some(schema.name.clone()),
+                _ => None,
+            };
+            for name in std::iter::once(name.clone()).chain(nested_name) {
+                if !names.insert(name.clone()) {
+                    kvs_error!("duplicate protobuf name {name} for key {key}");
+                    return Err(ErrorCode::ConversionFailed);
+                }
+            }
+
+            fields.push(ProtoField {
+                key: key.clone(),
+                name,
+                number: fields.len() as u32 + 1,
+                repeated,
+                kind,
+            });
+        }
+
+        Ok(Self {
+            name: message_name.to_string(),
+            fields,
+        })
+    }
+
+    /// Message name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Message fields ordered by field number.
+    pub fn fields(&self) -> &[ProtoField] {
+        &self.fields
+    }
+
+    /// Generate `proto3` file content describing the message.
+    pub fn to_proto(&self) -> String {
+        let mut output = String::from("syntax = \"proto3\";\n\n");
+        self.write_message(&mut output, 0);
+        output
+    }
+
+    fn write_message(&self, output: &mut String, depth: usize) {
+        let indent = "  ".repeat(depth);
+        output.push_str(&format!("{indent}message {} {{\n", self.name));
+        for field in &self.fields {
+            let label = if field.repeated { "repeated " } else { "" };
+            output.push_str(&format!(
+                "{indent}  {label}{} {} = {};\n",
+                field.kind.proto_name(),
+                field.name,
+                field.number
+            ));
+        }
+        for field in &self.fields {
+            if let ProtoType::Message(schema) = &field.kind {
+                output.push('\n');
+                schema.write_message(output, depth + 1);
+            }
+        }
+        output.push_str(&format!("{indent}}}\n"));
+    }
+
+    /// Encode map into protobuf message.
+    ///
+    /// Keys without a field in the schema are not encoded.
+    ///
+    /// # Parameters
+    ///   * `kvs_map`: Map to encode
+    ///
+    /// # Return Values
+    ///   * Ok: Encoded message
+    ///   * `ErrorCode::ConversionFailed`: Value doesn't match the field type
+    pub fn encode(&self, kvs_map: &KvsMap) -> Result<Vec<u8>, ErrorCode> {
+        let mut buf = Vec::new();
+        for field in &self.fields {
+            let Some(value) = kvs_map.get(&field.key) else {
+                continue;
+            };
+
+            if !field.repeated {
+                write_tag(&mut buf, field.number, field.kind.wire_type());
+                write_value(&mut buf, &field.kind, &field.key, value)?;
+                continue;
+            }
+
+            let KvsValue::Array(elements) = value else {
+                kvs_error!("protobuf field {} expects an array", field.key);
+                return Err(ErrorCode::ConversionFailed);
+            };
+            if field.kind.is_packed() {
+                let mut packed = Vec::new();
+                for element in elements {
+                    write_value(&mut packed, &field.kind, &field.key, element)?;
+                }
+                write_tag(&mut buf, field.number, WIRE_LEN);
+                write_varint(&mut buf, packed.len() as u64);
+                buf.extend_from_slice(&packed);
+            } else {
+                for element in elements {
+                    write_tag(&mut buf, field.number, WIRE_LEN);
+                    write_value(&mut buf, &field.kind, &field.key, element)?;
+                }
+            }
+        }
+        Ok(buf)
+    }
+
+    /// Decode protobuf message into a map.
+    ///
+    /// Unknown fields are skipped. Repeated numeric fields are accepted packed and unpacked.
+    ///
+    /// # Parameters
+    ///   * `buf`: Encoded message
+    ///
+    /// # Return Values
+    ///   * Ok: Decoded map
+    ///   * `ErrorCode::SerializationFailed`: Malformed message
+    pub fn decode(&self, buf: &[u8]) -> Result<KvsMap, ErrorCode> {
+        let mut reader = Reader { buf };
+        let mut kvs_map = KvsMap::new();
+
+        while !reader.buf.is_empty() {
+            let tag = reader.varint()?;
+            let number = tag >> 3;
+            let wire_type = (tag & 0x7) as u8;
+
+            let Some(field) = self.fields.iter().find(|f| u64::from(f.number) == number) else {
+                reader.skip(wire_type)?;
+                continue;
+            };
+
+            if field.repeated && field.kind.is_packed() && wire_type == WIRE_LEN {
+                let len = reader.len()?;
+                let mut packed = Reader {
+                    buf: reader.take(len)?,
+                };
+                while !packed.buf.is_empty() {
+                    let value = packed.value(&field.kind)?;
+                    push_element(&mut kvs_map, &field.key, value);
+                }
+                continue;
+            }
+
+            if wire_type != field.kind.wire_type() {
+                kvs_error!("unexpected wire type {wire_type} for field {number}");
+                return Err(ErrorCode::SerializationFailed);
+            }
+            let value = reader.value(&field.kind)?;
+            if field.repeated {
+                push_element(&mut kvs_map, &field.key, value);
+            } else {
+                kvs_map.insert(field.key.clone(), value);
+            }
+        }
+
+        Ok(kvs_map)
+    }
+}
+
+/// Check for a valid protobuf identifier.
+fn is_identifier(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Map KVS key to protobuf field name.
+fn field_name(key: &str) -> String {
+    let name: String = key
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if is_identifier(&name) {
+        name
+    } else {
+        format!("k{name}")
+    }
+}
+
+/// Map protobuf field name to nested message name.
+fn message_name(field_name: &str) -> String {
+    let mut name = String::with_capacity(field_name.len());
+    for part in field_name.split('_').filter(|p| !p.is_empty()) {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            name.push(first.to_ascii_uppercase());
+            name.extend(chars);
+        }
+    }
+    name
+}
+
+/// Derive field type and repeated flag from a value, `None` if the value carries no type.
+fn field_type(name: &str, value: &KvsValue) -> Result<Option<(ProtoType, bool)>, ErrorCode> {
+    let kind = match value {
+        KvsValue::I32(_) => ProtoType::Int32,
+        KvsValue::U32(_) => ProtoType::UInt32,
+        KvsValue::I64(_) => ProtoType::Int64,
+        KvsValue::U64(_) => ProtoType::UInt64,
+        KvsValue::F64(_) => ProtoType::Double,
+        KvsValue::Boolean(_) => ProtoType::Bool,
+        KvsValue::String(_) => ProtoType::String,
+        KvsValue::Null => return Ok(None),
+        KvsValue::Object(map) => {
+            ProtoType::Message(ProtoSchema::from_defaults(&message_name(name), map)?)
+        }
+        KvsValue::Array(elements) => {
+            let Some(first) = elements.first() else {
+                return Ok(None);
+            };
+            if elements
+                .iter()
+                .any(|e| discriminant(e) != discriminant(first))
+            {
+                kvs_error!("protobuf field {name} has mixed array types");
+                return Err(ErrorCode::ConversionFailed);
+            }
+            return match field_type(name, first)? {
+                Some((kind, false)) => Ok(Some((kind, true))),
+                _ => {
+                    kvs_error!("protobuf field {name} has unsupported array element type");
+                    Err(ErrorCode::ConversionFailed)
+                }
+            };
+        }
+    };
+    Ok(Some((kind, false)))
+}
+
+/// Append element to repeated field.
+fn push_element(kvs_map: &mut KvsMap, key: &str, value: KvsValue) {
+    let entry = kvs_map
+        .entry(key.to_string())
+        .or_insert_with(|| KvsValue::Array(Vec::new()));
+    if let KvsValue::Array(elements) = entry {
+        elements.push(value);
+    }
+}
+
+fn write_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn write_tag(buf: &mut Vec<u8>, number: u32, wire_type: u8) {
+    write_varint(buf, (u64::from(number) << 3) | u64::from(wire_type));
+}
+
+/// Write value payload without tag.
+fn write_value(
+    buf: &mut Vec<u8>,
+    kind: &ProtoType,
+    key: &str,
+    value: &KvsValue,
+) -> Result<(), ErrorCode> {
+    match (kind, value) {
+        // Negative `int32` values are sign-extended to 64 bit.
+        (ProtoType::Int32, KvsValue::I32(n)) => write_varint(buf, i64::from(*n) as u64),
+        (ProtoType::UInt32, KvsValue::U32(n)) => write_varint(buf, u64::from(*n)),
+        (ProtoType::Int64, KvsValue::I64(n)) => write_varint(buf, *n as u64),
+        (ProtoType::UInt64, KvsValue::U64(n)) => write_varint(buf, *n),
+        (ProtoType::Double, KvsValue::F64(n)) => buf.extend_from_slice(&n.to_le_bytes()),
+        (ProtoType::Bool, KvsValue::Boolean(b)) => write_varint(buf, u64::from(*b)),
+        (ProtoType::String, KvsValue::String(s)) => {
+            write_varint(buf, s.len() as u64);
+            buf.extend_from_slice(s.as_bytes());
+        }
+        (ProtoType::Message(schema), KvsValue::Object(map)) => {
+            let message = schema.encode(map)?;
+            write_varint(buf, message.len() as u64);
+            buf.extend_from_slice(&message);
+        }
+        _ => {
+            kvs_error!(
+                "value of key {key} doesn't match protobuf type {}",
+                kind.proto_name()
+            );
+            return Err(ErrorCode::ConversionFailed);
+        }
+    }
+    Ok(())
+}
+
+/// Cursor over protobuf encoded data.
+struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ErrorCode> {
+        if self.buf.len() < len {
+            kvs_error!("protobuf message truncated");
+            return Err(ErrorCode::SerializationFailed);
+        }
+        let (head, tail) = self.buf.split_at(len);
+        self.buf = tail;
+        Ok(head)
+    }
+
+    fn varint(&mut self) -> Result<u64, ErrorCode> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.take(1)?[0];
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        kvs_error!("protobuf varint too long");
+        Err(ErrorCode::SerializationFailed)
+    }
+
+    fn len(&mut self) -> Result<usize, ErrorCode> {
+        usize::try_from(self.varint()?).map_err(|_| ErrorCode::SerializationFailed)
+    }
+
+    fn skip(&mut self, wire_type: u8) -> Result<(), ErrorCode> {
+        match wire_type {
+            WIRE_VARINT => {
+                self.varint()?;
+            }
+            WIRE_FIXED64 => {
+                self.take(8)?;
+            }
+            WIRE_LEN => {
+                let len = self.len()?;
+                self.take(len)?;
+            }
+            WIRE_FIXED32 => {
+                self.take(4)?;
+            }
+            _ => {
+                kvs_error!("unsupported protobuf wire type {wire_type}");
+                return Err(ErrorCode::SerializationFailed);
+            }
+        }
+        Ok(())
+    }
+
+    /// Read value payload without tag.
+    fn value(&mut self, kind: &ProtoType) -> Result<KvsValue, ErrorCode> {
+        let value = match kind {
+            // Truncation matches the protobuf conversion rules for 32-bit types.
+            ProtoType::Int32 => KvsValue::I32(self.varint()? as i32),
+            ProtoType::UInt32 => KvsValue::U32(self.varint()? as u32),
+            ProtoType::Int64 => KvsValue::I64(self.varint()? as i64),
+            ProtoType::UInt64 => KvsValue::U64(self.varint()?),
+            ProtoType::Double => KvsValue::F64(f64::from_le_bytes(self.take(8)?.try_into()?)),
+            ProtoType::Bool => KvsValue::Boolean(self.varint()? != 0),
+            ProtoType::String => {
+                let len = self.len()?;
+                KvsValue::String(String::from_utf8(self.take(len)?.to_vec())?)
+            }
+            ProtoType::Message(schema) => {
+                let len = self.len()?;
+                KvsValue::Object(schema.decode(self.take(len)?)?)
+            }
+        };
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod protobuf_tests {
+    use crate::error_code::ErrorCode;
+    use crate::kvs_value::{KvsMap, KvsValue};
+    use crate::protobuf::{ProtoSchema, ProtoType};
+
+    fn defaults_map() -> KvsMap {
+        KvsMap::from([
+            ("count".to_string(), KvsValue::I32(0)),
+            ("enabled".to_string(), KvsValue::Boolean(false)),
+            ("ratio".to_string(), KvsValue::F64(0.5)),
+            ("big".to_string(), KvsValue::U64(0)),
+            ("unset".to_string(), KvsValue::Null),
+            (
+                "tags".to_string(),
+                KvsValue::Array(vec![KvsValue::from("a")]),
+            ),
+            (
+                "offsets".to_string(),
+                KvsValue::Array(vec![KvsValue::I64(0)]),
+            ),
+            (
+                "sub_config".to_string(),
+                KvsValue::Object(KvsMap::from([("name".to_string(), KvsValue::from("x"))])),
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_from_defaults() {
+        let schema = ProtoSchema::from_defaults("Config", &defaults_map()).unwrap();
+        assert_eq!(schema.name(), "Config");
+
+        let fields: Vec<(&str, u32, bool)> = schema
+            .fields()
+            .iter()
+            .map(|f| (f.name.as_str(), f.number, f.repeated))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                ("big", 1, false),
+                ("count", 2, false),
+                ("enabled", 3, false),
+                ("offsets", 4, true),
+                ("ratio", 5, false),
+                ("sub_config", 6, false),
+                ("tags", 7, true),
+            ]
+        );
+        assert_eq!(schema.fields()[3].kind, ProtoType::Int64);
+    }
+
+    #[test]
+    fn test_from_defaults_sanitized_names() {
+        let defaults_map = KvsMap::from([
+            ("1st-key".to_string(), KvsValue::I32(0)),
+            ("my.key".to_string(), KvsValue::I32(0)),
+        ]);
+        let schema = ProtoSchema::from_defaults("M", &defaults_map).unwrap();
+        assert_eq!(schema.fields()[0].name, "k1st_key");
+        assert_eq!(schema.fields()[0].key, "1st-key");
+        assert_eq!(schema.fields()[1].name, "my_key");
+    }
+
+    #[test]
+    fn test_from_defaults_duplicate_names() {
+        let defaults_map = KvsMap::from([
+            ("my.key".to_string(), KvsValue::I32(0)),
+            ("my_key".to_string(), KvsValue::I32(0)),
+        ]);
+        assert!(ProtoSchema::from_defaults("M", &defaults_map)
+            .is_err_and(|e| e == ErrorCode::ConversionFailed));
+    }
+
+    #[test]
+    fn test_from_defaults_mixed_array() {
+        let defaults_map = KvsMap::from([(
+            "arr".to_string(),
+            KvsValue::Array(vec![KvsValue::I32(0), KvsValue::from("a")]),
+        )]);
+        assert!(ProtoSchema::from_defaults("M", &defaults_map)
+            .is_err_and(|e| e == ErrorCode::ConversionFailed));
+    }
+
+    #[test]
+    fn test_from_defaults_invalid_message_name() {
+        assert!(ProtoSchema::from_defaults("1M", &KvsMap::new())
+            .is_err_and(|e| e == ErrorCode::ConversionFailed));
+    }
+
+    #[test]
+    fn test_to_proto() {
+        let schema = ProtoSchema::from_defaults("Config", &defaults_map()).unwrap();
+        assert_eq!(
+            schema.to_proto(),
+            "syntax = \"proto3\";\n\
+             \n\
+             message Config {\n\
+            \x20 uint64 big = 1;\n\
+            \x20 int32 count = 2;\n\
+            \x20 bool enabled = 3;\n\
+            \x20 repeated int64 offsets = 4;\n\
+            \x20 double ratio = 5;\n\
+            \x20 SubConfig sub_config = 6;\n\
+            \x20 repeated string tags = 7;\n\
+             \n\
+            \x20 message SubConfig {\n\
+            \x20   string name = 1;\n\
+            \x20 }\n\
+             }\n"
+        );
+    }
+
+    #[test]
+    fn test_encode_wire_format() {
+        let defaults_map = KvsMap::from([("a".to_string(), KvsValue::I32(0))]);
+        let schema = ProtoSchema::from_defaults("Test1", &defaults_map).unwrap();
+
+        let kvs_map = KvsMap::from([("a".to_string(), KvsValue::I32(150))]);
+        assert_eq!(schema.encode(&kvs_map).unwrap(), vec![0x08, 0x96, 0x01]);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let schema = ProtoSchema::from_defaults("Config", &defaults_map()).unwrap();
+        let kvs_map = KvsMap::from([
+            ("count".to_string(), KvsValue::I32(-5)),
+            ("enabled".to_string(), KvsValue::Boolean(false)),
+            ("ratio".to_string(), KvsValue::F64(-1.25)),
+            ("big".to_string(), KvsValue::U64(u64::MAX)),
+            (
+                "tags".to_string(),
+                KvsValue::Array(vec![KvsValue::from("x"), KvsValue::from("y")]),
+            ),
+            (
+                "offsets".to_string(),
+                KvsValue::Array(vec![KvsValue::I64(i64::MIN), KvsValue::I64(1)]),
+            ),
+            (
+                "sub_config".to_string(),
+                KvsValue::Object(KvsMap::from([(
+                    "name".to_string(),
+                    KvsValue::from("nested"),
+                )])),
+            ),
+        ]);
+
+        let encoded = schema.encode(&kvs_map).unwrap();
+        assert_eq!(schema.decode(&encoded).unwrap(), kvs_map);
+    }
+
+    #[test]
+    fn test_encode_type_mismatch() {
+        let schema = ProtoSchema::from_defaults("Config", &defaults_map()).unwrap();
+        let kvs_map = KvsMap::from([("count".to_string(), KvsValue::from("text"))]);
+        assert!(schema
+            .encode(&kvs_map)
+            .is_err_and(|e| e == ErrorCode::ConversionFailed));
+    }
+
+    #[test]
+    fn test_encode_skips_unknown_keys() {
+        let schema = ProtoSchema::from_defaults("Config", &defaults_map()).unwrap();
+        let kvs_map = KvsMap::from([("unknown".to_string(), KvsValue::I32(1))]);
+        assert!(schema.encode(&kvs_map).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_decode_unpacked_repeated_and_unknown_fields() {
+        let defaults_map =
+            KvsMap::from([("arr".to_string(), KvsValue::Array(vec![KvsValue::U32(0)]))]);
+        let schema = ProtoSchema::from_defaults("M", &defaults_map).unwrap();
+
+        // Field 1 unpacked twice, unknown field 2 with fixed32 and field 3 with length 1.
+        let buf = vec![0x08, 0x01, 0x08, 0x02, 0x15, 0, 0, 0, 0, 0x1a, 0x01, 0xff];
+        let kvs_map = schema.decode(&buf).unwrap();
+        assert_eq!(
+            kvs_map["arr"],
+            KvsValue::Array(vec![KvsValue::U32(1), KvsValue::U32(2)])
+        );
+    }
+
+    #[test]
+    fn test_decode_truncated() {
+        let schema = ProtoSchema::from_defaults("Config", &defaults_map()).unwrap();
+        // Field 7 (tags) with length 5 but only 1 byte of data.
+        assert!(schema
+            .decode(&[0x3a, 0x05, b'a'])
+            .is_err_and(|e| e == ErrorCode::SerializationFailed));
+    }
+
+    #[test]
+    fn test_decode_wire_type_mismatch() {
+        let schema = ProtoSchema::from_defaults("Config", &defaults_map()).unwrap();
+        // Field 2 (count) with length-delimited wire type.
+        assert!(schema
+            .decode(&[0x12, 0x00])
+            .is_err_and(|e| e == ErrorCode::SerializationFailed));
+    }
+}
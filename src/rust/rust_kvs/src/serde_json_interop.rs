@@ -0,0 +1,149 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Conversions between `KvsValue` and `serde_json::Value`.
+//!
+//! JSON numbers are mapped to the narrowest fitting `KvsValue`:
+//!   * Integers in `i32` range -> `I32`
+//!   * Other integers in `i64` range -> `I64`
+//!   * Integers above `i64::MAX` -> `U64`
+//!   * Numbers with fraction or exponent -> `F64`
+//!
+//! Non-finite `F64` values can't be represented in JSON and are converted to `Null`.
+
+use crate::kvs_value::{KvsMap, KvsValue};
+use serde_json::{Map, Number, Value};
+
+/// serde_json::Value -> KvsValue
+impl From<Value> for KvsValue {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Null => KvsValue::Null,
+            Value::Bool(b) => KvsValue::Boolean(b),
+            Value::Number(n) => {
+                if let Some(v) = n.as_i64() {
+                    match i32::try_from(v) {
+                        Ok(v) => KvsValue::I32(v),
+                        Err(_) => KvsValue::I64(v),
+                    }
+                } else if let Some(v) = n.as_u64() {
+                    KvsValue::U64(v)
+                } else {
+                    // Without `arbitrary_precision` every number is representable as `f64`.
+                    KvsValue::F64(n.as_f64().unwrap_or(f64::NAN))
+                }
+            }
+            Value::String(s) => KvsValue::String(s),
+            Value::Array(arr) => KvsValue::Array(arr.into_iter().map(KvsValue::from).collect()),
+            Value::Object(obj) => KvsValue::Object(
+                obj.into_iter()
+                    .map(|(k, v)| (k, KvsValue::from(v)))
+                    .collect::<KvsMap>(),
+            ),
+        }
+    }
+}
+
+/// KvsValue -> serde_json::Value
+impl From<KvsValue> for Value {
+    fn from(value: KvsValue) -> Self {
+        match value {
+            KvsValue::I32(n) => Value::Number(Number::from(n)),
+            KvsValue::U32(n) => Value::Number(Number::from(n)),
+            KvsValue::I64(n) => Value::Number(Number::from(n)),
+            KvsValue::U64(n) => Value::Number(Number::from(n)),
+            KvsValue::F64(n) => Number::from_f64(n).map_or(Value::Null, Value::Number),
+            KvsValue::Boolean(b) => Value::Bool(b),
+            KvsValue::String(s) => Value::String(s),
+            KvsValue::Null => Value::Null,
+            KvsValue::Array(arr) => Value::Array(arr.into_iter().map(Value::from).collect()),
+            KvsValue::Object(obj) => Value::Object(
+                obj.into_iter()
+                    .map(|(k, v)| (k, Value::from(v)))
+                    .collect::<Map<String, Value>>(),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod serde_json_interop_tests {
+    #[cfg(feature = "mock")]
+    use crate::kvs_api::KvsApi;
+    #[cfg(feature = "mock")]
+    use crate::kvs_mock::MockKvs;
+    use crate::kvs_value::{KvsMap, KvsValue};
+    use serde_json::{json, Value};
+
+    #[test]
+    fn test_from_json_numbers() {
+        assert_eq!(KvsValue::from(json!(-5)), KvsValue::I32(-5));
+        assert_eq!(KvsValue::from(json!(1i64 << 40)), KvsValue::I64(1 << 40));
+        assert_eq!(KvsValue::from(json!(u64::MAX)), KvsValue::U64(u64::MAX));
+        assert_eq!(KvsValue::from(json!(1.5)), KvsValue::F64(1.5));
+    }
+
+    #[test]
+    fn test_from_json_nested() {
+        let value = KvsValue::from(json!({
+            "arr": [true, null, "s"],
+            "obj": {"x": 1}
+        }));
+        assert_eq!(
+            value,
+            KvsValue::Object(KvsMap::from([
+                (
+                    "arr".to_string(),
+                    KvsValue::Array(vec![
+                        KvsValue::Boolean(true),
+                        KvsValue::Null,
+                        KvsValue::from("s")
+                    ])
+                ),
+                (
+                    "obj".to_string(),
+                    KvsValue::Object(KvsMap::from([("x".to_string(), KvsValue::I32(1))]))
+                ),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_to_json() {
+        let value = KvsValue::Object(KvsMap::from([
+            ("u32".to_string(), KvsValue::U32(7)),
+            ("i64".to_string(), KvsValue::I64(i64::MIN)),
+            ("f64".to_string(), KvsValue::F64(0.25)),
+            ("arr".to_string(), KvsValue::Array(vec![KvsValue::Null])),
+        ]));
+        assert_eq!(
+            Value::from(value),
+            json!({"u32": 7, "i64": i64::MIN, "f64": 0.25, "arr": [null]})
+        );
+    }
+
+    #[test]
+    fn test_to_json_non_finite() {
+        assert_eq!(Value::from(KvsValue::F64(f64::NAN)), Value::Null);
+        assert_eq!(Value::from(KvsValue::F64(f64::INFINITY)), Value::Null);
+    }
+
+    #[cfg(feature = "mock")]
+    #[test]
+    fn test_set_value_json() {
+        let kvs = MockKvs::default();
+        kvs.set_value("key", json!({"x": [1, 2]})).unwrap();
+        assert_eq!(
+            Value::from(kvs.get_value("key").unwrap()),
+            json!({"x": [1, 2]})
+        );
+    }
+}
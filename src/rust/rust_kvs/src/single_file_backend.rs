@@ -0,0 +1,363 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::error_code::ErrorCode;
+use crate::json_backend::JsonBackend;
+use crate::kvs_api::{DuplicateKeyPolicy, FloatFormat, InstanceId, SnapshotId};
+use crate::kvs_backend::{check_extension, KvsBackend, KvsPathResolver};
+use crate::kvs_log::kvs_error;
+use crate::kvs_value::KvsMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Container layout, the hash is stored in front of the data it covers:
+//   * magic `KVSC`
+//   * format version byte
+//   * Adler-32 hash of the payload, big-endian `u32` as in hash files
+//   * payload, KVS file content of `JsonBackend`
+//
+// A partially written or truncated file fails the hash check, the hash can't get lost separately.
+// Hash file paths passed to the backend are ignored, the path resolver returns the container path
+// for them so existence checks of KVS and hash file always agree.
+
+/// Magic at the start of every container.
+const MAGIC: &[u8; 4] = b"KVSC";
+
+/// Version of the container layout.
+const VERSION: u8 = 1;
+
+/// Length of magic, version and hash.
+const HEADER_LEN: usize = MAGIC.len() + 1 + 4;
+
+/// Wrap KVS file content into a container.
+fn encode(payload: &[u8]) -> Vec<u8> {
+    let hash = adler32::RollingAdler32::from_buffer(payload).hash();
+    let mut bytes = Vec::with_capacity(HEADER_LEN + payload.len());
+    bytes.extend_from_slice(MAGIC);
+    bytes.push(VERSION);
+    bytes.extend_from_slice(&hash.to_be_bytes());
+    bytes.extend_from_slice(payload);
+    bytes
+}
+
+/// Get validated KVS file content from a container.
+///
+/// # Return Values
+///   * Ok: Payload
+///   * `ErrorCode::ValidationFailed`: No container, truncated or hash mismatch
+///   * `ErrorCode::UnsupportedVersion`: Container layout version is not supported
+fn decode(bytes: &[u8]) -> Result<&[u8], ErrorCode> {
+    if bytes.len() < HEADER_LEN || &bytes[..MAGIC.len()] != MAGIC {
+        kvs_error!("KVS container header is missing");
+        return Err(ErrorCode::ValidationFailed);
+    }
+    let version = bytes[MAGIC.len()];
+    if version != VERSION {
+        kvs_error!("unsupported KVS container version: {version}");
+        return Err(ErrorCode::UnsupportedVersion);
+    }
+    let mut hash = [0; 4];
+    hash.copy_from_slice(&bytes[MAGIC.len() + 1..HEADER_LEN]);
+    let payload = &bytes[HEADER_LEN..];
+    if adler32::RollingAdler32::from_buffer(payload).hash() != u32::from_be_bytes(hash) {
+        kvs_error!("KVS container hash mismatch");
+        return Err(ErrorCode::ValidationFailed);
+    }
+    Ok(payload)
+}
+
+/// Get validated JSON content from a container.
+///
+/// # Return Values
+///   * Ok: JSON content
+///   * `ErrorCode::JsonParserError`: Payload is not valid UTF-8
+///   * Errors of [`decode`]
+fn payload(bytes: &[u8]) -> Result<&str, ErrorCode> {
+    std::str::from_utf8(decode(bytes)?).map_err(|e| {
+        kvs_error!("KVS container payload is not valid UTF-8: {e}");
+        ErrorCode::JsonParserError
+    })
+}
+
+/// KVS backend storing the JSON data and its hash together in one container file.
+///
+/// Defaults files are plain JSON files as used by [`JsonBackend`], so they can still be written
+/// by hand. Compression is not supported.
+pub struct SingleFileBackend;
+
+impl KvsBackend for SingleFileBackend {
+    fn load_kvs(kvs_path: &Path, hash_path: Option<&PathBuf>) -> Result<KvsMap, ErrorCode> {
+        Self::load_kvs_with_policy(kvs_path, hash_path, DuplicateKeyPolicy::default())
+    }
+
+    fn load_kvs_with_policy(
+        kvs_path: &Path,
+        hash_path: Option<&PathBuf>,
+        duplicate_keys: DuplicateKeyPolicy,
+    ) -> Result<KvsMap, ErrorCode> {
+        // Defaults files.
+        if check_extension(kvs_path, "json") {
+            return JsonBackend::load_kvs_with_policy(kvs_path, hash_path, duplicate_keys);
+        }
+        if !check_extension(kvs_path, "kvs") {
+            return Err(ErrorCode::KvsFileReadError);
+        }
+
+        let bytes = fs::read(kvs_path)?;
+        JsonBackend::kvs_map_from_str(payload(&bytes)?, duplicate_keys)
+    }
+
+    fn load_kvs_from_bytes(bytes: &[u8]) -> Result<KvsMap, ErrorCode> {
+        JsonBackend::kvs_map_from_str(payload(bytes)?, DuplicateKeyPolicy::default())
+    }
+
+    fn save_kvs(
+        kvs_map: &KvsMap,
+        kvs_path: &Path,
+        hash_path: Option<&PathBuf>,
+    ) -> Result<(), ErrorCode> {
+        Self::save_kvs_formatted(kvs_map, kvs_path, hash_path, &FloatFormat::Plain)
+    }
+
+    fn save_kvs_formatted(
+        kvs_map: &KvsMap,
+        kvs_path: &Path,
+        _hash_path: Option<&PathBuf>,
+        float_format: &FloatFormat,
+    ) -> Result<(), ErrorCode> {
+        if !check_extension(kvs_path, "kvs") {
+            return Err(ErrorCode::KvsFileReadError);
+        }
+
+        let json_str = JsonBackend::kvs_map_to_string(kvs_map, float_format)?;
+        fs::write(kvs_path, encode(json_str.as_bytes()))?;
+        Ok(())
+    }
+
+    fn serialized_size(kvs_map: &KvsMap, float_format: &FloatFormat) -> Result<usize, ErrorCode> {
+        Ok(HEADER_LEN + JsonBackend::kvs_map_to_string(kvs_map, float_format)?.len())
+    }
+
+    fn move_kvs(
+        old_kvs_path: &Path,
+        _old_hash_path: &Path,
+        new_kvs_path: &Path,
+        _new_hash_path: &Path,
+    ) -> Result<(), ErrorCode> {
+        if old_kvs_path.exists() {
+            fs::rename(old_kvs_path, new_kvs_path)?;
+        }
+        Ok(())
+    }
+
+    fn remove_kvs(kvs_path: &Path, _hash_path: &Path) -> Result<(), ErrorCode> {
+        match fs::remove_file(kvs_path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// KVS backend path resolver for `SingleFileBackend`.
+impl KvsPathResolver for SingleFileBackend {
+    fn kvs_file_name(instance_id: InstanceId, snapshot_id: SnapshotId) -> String {
+        format!("kvs_{instance_id}_{snapshot_id}.kvs")
+    }
+
+    fn kvs_file_path(
+        working_dir: &Path,
+        instance_id: InstanceId,
+        snapshot_id: SnapshotId,
+    ) -> PathBuf {
+        working_dir.join(Self::kvs_file_name(instance_id, snapshot_id))
+    }
+
+    /// Hash is part of the container.
+    fn hash_file_name(instance_id: InstanceId, snapshot_id: SnapshotId) -> String {
+        Self::kvs_file_name(instance_id, snapshot_id)
+    }
+
+    fn hash_file_path(
+        working_dir: &Path,
+        instance_id: InstanceId,
+        snapshot_id: SnapshotId,
+    ) -> PathBuf {
+        working_dir.join(Self::hash_file_name(instance_id, snapshot_id))
+    }
+
+    fn defaults_file_name(instance_id: InstanceId) -> String {
+        JsonBackend::defaults_file_name(instance_id)
+    }
+
+    fn defaults_file_path(working_dir: &Path, instance_id: InstanceId) -> PathBuf {
+        working_dir.join(Self::defaults_file_name(instance_id))
+    }
+
+    fn global_defaults_file_name() -> String {
+        JsonBackend::global_defaults_file_name()
+    }
+
+    fn global_defaults_file_path(working_dir: &Path) -> PathBuf {
+        working_dir.join(Self::global_defaults_file_name())
+    }
+}
+
+#[cfg(test)]
+mod container_tests {
+    use crate::error_code::ErrorCode;
+    use crate::single_file_backend::{decode, encode, HEADER_LEN};
+
+    #[test]
+    fn test_roundtrip() {
+        let bytes = encode(b"{}");
+        assert_eq!(bytes.len(), HEADER_LEN + 2);
+        assert_eq!(&bytes[..5], b"KVSC\x01");
+        assert_eq!(decode(&bytes).unwrap(), b"{}");
+    }
+
+    #[test]
+    fn test_decode_truncated() {
+        let bytes = encode(b"{\"a\":1}");
+        assert!(decode(&bytes[..bytes.len() - 1]).is_err_and(|e| e == ErrorCode::ValidationFailed));
+        assert!(decode(&bytes[..HEADER_LEN - 1]).is_err_and(|e| e == ErrorCode::ValidationFailed));
+    }
+
+    #[test]
+    fn test_decode_plain_json() {
+        assert!(
+            decode(b"{\"t\":\"obj\",\"v\":{}}").is_err_and(|e| e == ErrorCode::ValidationFailed)
+        );
+    }
+
+    #[test]
+    fn test_decode_unsupported_version() {
+        let mut bytes = encode(b"{}");
+        bytes[4] = 2;
+        assert!(decode(&bytes).is_err_and(|e| e == ErrorCode::UnsupportedVersion));
+    }
+}
+
+#[cfg(test)]
+mod backend_tests {
+    use crate::error_code::ErrorCode;
+    use crate::json_backend::JsonBackend;
+    use crate::kvs::GenericKvs;
+    use crate::kvs_api::{InstanceId, KvsApi, KvsDefaults, KvsLoad, SnapshotId};
+    use crate::kvs_backend::{KvsBackend, KvsPathResolver};
+    use crate::kvs_builder::kvs_builder_tests::lock_and_reset;
+    use crate::kvs_builder::GenericKvsBuilder;
+    use crate::kvs_value::{KvsMap, KvsValue};
+    use crate::single_file_backend::SingleFileBackend;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    fn kvs_map() -> KvsMap {
+        KvsMap::from([
+            ("k1".to_string(), KvsValue::from("v1")),
+            ("k2".to_string(), KvsValue::from(true)),
+            ("k3".to_string(), KvsValue::from(123.4)),
+        ])
+    }
+
+    fn build(working_dir: &Path, instance_id: InstanceId) -> GenericKvs<SingleFileBackend> {
+        GenericKvsBuilder::<SingleFileBackend>::new(instance_id)
+            .dir(working_dir.to_string_lossy().to_string())
+            .defaults(KvsDefaults::Optional)
+            .kvs_load(KvsLoad::Optional)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_save_load_kvs() {
+        let dir = tempdir().unwrap();
+        let kvs_path = dir.path().join("kvs.kvs");
+        SingleFileBackend::save_kvs(&kvs_map(), &kvs_path, None).unwrap();
+
+        assert_eq!(
+            SingleFileBackend::load_kvs(&kvs_path, None).unwrap(),
+            kvs_map()
+        );
+        assert!(!dir.path().join("kvs.hash").exists());
+    }
+
+    #[test]
+    fn test_load_kvs_corrupted() {
+        let dir = tempdir().unwrap();
+        let kvs_path = dir.path().join("kvs.kvs");
+        SingleFileBackend::save_kvs(&kvs_map(), &kvs_path, None).unwrap();
+        let mut bytes = std::fs::read(&kvs_path).unwrap();
+        let last = bytes.len() - 2;
+        bytes[last] ^= 0x01;
+        std::fs::write(&kvs_path, bytes).unwrap();
+
+        assert!(SingleFileBackend::load_kvs(&kvs_path, None)
+            .is_err_and(|e| e == ErrorCode::ValidationFailed));
+    }
+
+    #[test]
+    fn test_load_kvs_invalid_extension() {
+        let dir = tempdir().unwrap();
+        let kvs_path = dir.path().join("kvs.bin");
+
+        assert!(SingleFileBackend::load_kvs(&kvs_path, None)
+            .is_err_and(|e| e == ErrorCode::KvsFileReadError));
+        assert!(SingleFileBackend::save_kvs(&kvs_map(), &kvs_path, None)
+            .is_err_and(|e| e == ErrorCode::KvsFileReadError));
+    }
+
+    #[test]
+    fn test_flush_snapshots_single_file() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let instance_id = InstanceId(2);
+        JsonBackend::save_kvs(
+            &KvsMap::from([("default".to_string(), KvsValue::I32(1))]),
+            &SingleFileBackend::defaults_file_path(dir.path(), instance_id),
+            None,
+        )
+        .unwrap();
+
+        let kvs = build(dir.path(), instance_id);
+        kvs.set_value("key", "first").unwrap();
+        kvs.flush().unwrap();
+        kvs.set_value("key", "second").unwrap();
+        kvs.flush().unwrap();
+        drop(kvs);
+        drop(_lock);
+        let _lock = lock_and_reset();
+
+        let files: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .filter(|name| name.ends_with(".kvs") || name.ends_with(".hash"))
+            .collect();
+        assert_eq!(files.len(), if cfg!(feature = "snapshots") { 2 } else { 1 });
+        #[cfg(feature = "snapshots")]
+        assert!(SingleFileBackend::kvs_file_path(dir.path(), instance_id, SnapshotId(1)).exists());
+
+        let kvs = build(dir.path(), instance_id);
+        assert_eq!(kvs.get_value_as::<String>("key").unwrap(), "second");
+        assert_eq!(kvs.get_value_as::<i32>("default").unwrap(), 1);
+    }
+
+    #[test]
+    fn test_defaults_file_name() {
+        assert_eq!(
+            SingleFileBackend::defaults_file_name(InstanceId(123)),
+            "kvs_123_default.json"
+        );
+        assert_eq!(
+            SingleFileBackend::hash_file_name(InstanceId(123), SnapshotId(2)),
+            "kvs_123_2.kvs"
+        );
+    }
+}
@@ -0,0 +1,622 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! SQLite backend.
+//!
+//! [`SqliteBackend`] stores every snapshot in an SQLite database with one row per key. Saving only
+//! writes the keys changed since the database was last loaded or saved by this process, instead
+//! of serializing the whole map on every flush. Defaults files are kept in JSON format and handled
+//! like [`JsonBackend`] does.
+//!
+//! Database layout:
+//!   * `kvs(key, value)`: one row per key, value is the t-tagged JSON of the value as written by
+//!     the JSON backend
+//!   * `meta(name, value)`: `generation`, incremented by every save, and `checksum` of all rows
+//!
+//! The checksum is the wrapping sum of the Adler-32 hashes of all rows, so it's updated per
+//! changed row. It's stored in the hash file and validated on load.
+//!
+//! Databases modified by other processes are detected by their generation and checksum, the
+//! next save then rewrites all rows.
+
+use crate::error_code::ErrorCode;
+use crate::json_backend::JsonBackend;
+use crate::kvs_api::{DuplicateKeyPolicy, InstanceId, SnapshotId};
+use crate::kvs_backend::{check_extension, KvsBackend, KvsPathResolver};
+use crate::kvs_log::kvs_error;
+use crate::kvs_value::{KvsMap, KvsValue};
+use rusqlite::{Connection, ErrorCode as SqliteErrorCode, OpenFlags, OptionalExtension};
+use rusqlite::{Transaction, TransactionBehavior};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex, MutexGuard, PoisonError};
+use tinyjson::JsonValue;
+
+/// rusqlite::Error -> ErrorCode
+impl From<rusqlite::Error> for ErrorCode {
+    fn from(cause: rusqlite::Error) -> Self {
+        kvs_error!("SQLite error: {cause}");
+        match cause.sqlite_error_code() {
+            Some(SqliteErrorCode::DatabaseBusy | SqliteErrorCode::DatabaseLocked) => {
+                ErrorCode::ResourceBusy
+            }
+            Some(SqliteErrorCode::DatabaseCorrupt | SqliteErrorCode::NotADatabase) => {
+                ErrorCode::IntegrityCorrupted
+            }
+            Some(SqliteErrorCode::DiskFull) => ErrorCode::OutOfStorageSpace,
+            Some(SqliteErrorCode::ReadOnly) => ErrorCode::ReadOnly,
+            Some(SqliteErrorCode::PermissionDenied) => ErrorCode::PermissionDenied,
+            Some(SqliteErrorCode::CannotOpen) => ErrorCode::FileNotFound,
+            Some(_) => ErrorCode::PhysicalStorageFailure,
+            None => ErrorCode::SerializationFailed,
+        }
+    }
+}
+
+/// Database state last loaded or saved by this process.
+struct SavedState {
+    generation: i64,
+    checksum: u32,
+    kvs_map: KvsMap,
+}
+
+/// Saved states by database path.
+static SAVED_STATES: LazyLock<Mutex<HashMap<PathBuf, SavedState>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+impl From<PoisonError<MutexGuard<'_, HashMap<PathBuf, SavedState>>>> for ErrorCode {
+    fn from(_cause: PoisonError<MutexGuard<'_, HashMap<PathBuf, SavedState>>>) -> Self {
+        ErrorCode::MutexLockFailed
+    }
+}
+
+/// Check if path is a JSON defaults file.
+fn is_json(path: &Path) -> bool {
+    check_extension(path, "json")
+}
+
+/// Hash of a single row.
+fn row_hash(key: &str, value: &str) -> u32 {
+    let mut hash = adler32::RollingAdler32::from_buffer(key.as_bytes());
+    hash.update(0);
+    hash.update_buffer(value.as_bytes());
+    hash.hash()
+}
+
+fn encode_value(value: &KvsValue) -> Result<String, ErrorCode> {
+    Ok(JsonValue::from(value.clone()).stringify()?)
+}
+
+fn decode_value(value: &str) -> Result<KvsValue, ErrorCode> {
+    Ok(KvsValue::from(value.parse::<JsonValue>()?))
+}
+
+/// Open database, creating the tables if needed.
+fn open(kvs_path: &Path) -> Result<Connection, ErrorCode> {
+    let conn = Connection::open(kvs_path)?;
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS kvs (key TEXT PRIMARY KEY NOT NULL, value TEXT NOT NULL);
+         CREATE TABLE IF NOT EXISTS meta (name TEXT PRIMARY KEY NOT NULL, value INTEGER NOT NULL);",
+    )?;
+    Ok(conn)
+}
+
+/// Read generation and checksum, `None` for a database never saved.
+fn read_meta(conn: &Connection) -> Result<Option<(i64, u32)>, ErrorCode> {
+    let read = |name: &str| -> Result<Option<i64>, ErrorCode> {
+        Ok(conn
+            .query_row("SELECT value FROM meta WHERE name = ?1", [name], |row| {
+                row.get(0)
+            })
+            .optional()?)
+    };
+    match (read("generation")?, read("checksum")?) {
+        (Some(generation), Some(checksum)) => {
+            let checksum = u32::try_from(checksum).map_err(|_| ErrorCode::IntegrityCorrupted)?;
+            Ok(Some((generation, checksum)))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn write_meta(txn: &Transaction, generation: i64, checksum: u32) -> Result<(), ErrorCode> {
+    let mut stmt = txn.prepare("INSERT OR REPLACE INTO meta (name, value) VALUES (?1, ?2)")?;
+    stmt.execute(("generation", generation))?;
+    stmt.execute(("checksum", i64::from(checksum)))?;
+    Ok(())
+}
+
+/// Insert or replace a row, updating the checksum.
+fn write_row(
+    txn: &Transaction,
+    key: &str,
+    value: &KvsValue,
+    checksum: &mut u32,
+) -> Result<(), ErrorCode> {
+    remove_row(txn, key, checksum)?;
+    let value = encode_value(value)?;
+    txn.prepare_cached("INSERT INTO kvs (key, value) VALUES (?1, ?2)")?
+        .execute((key, &value))?;
+    *checksum = checksum.wrapping_add(row_hash(key, &value));
+    Ok(())
+}
+
+/// Remove a row if it exists, updating the checksum.
+fn remove_row(txn: &Transaction, key: &str, checksum: &mut u32) -> Result<(), ErrorCode> {
+    let old_value: Option<String> = txn
+        .prepare_cached("DELETE FROM kvs WHERE key = ?1 RETURNING value")?
+        .query_row([key], |row| row.get(0))
+        .optional()?;
+    if let Some(old_value) = old_value {
+        *checksum = checksum.wrapping_sub(row_hash(key, &old_value));
+    }
+    Ok(())
+}
+
+/// Read the checksum stored in a hash file.
+fn read_hash(hash_path: &Path) -> Result<u32, ErrorCode> {
+    let hash_bytes = fs::read(hash_path).map_err(|_| ErrorCode::KvsHashFileReadError)?;
+    let hash_bytes: [u8; 4] = hash_bytes
+        .try_into()
+        .map_err(|_| ErrorCode::ValidationFailed)?;
+    Ok(u32::from_be_bytes(hash_bytes))
+}
+
+/// KVS backend implementation based on SQLite.
+pub struct SqliteBackend;
+
+impl SqliteBackend {
+    fn load_db(kvs_path: &Path, hash_path: Option<&PathBuf>) -> Result<KvsMap, ErrorCode> {
+        if !check_extension(kvs_path, "db") {
+            return Err(ErrorCode::KvsFileReadError);
+        }
+        if hash_path.is_some_and(|p| !check_extension(p, "hash")) {
+            return Err(ErrorCode::KvsHashFileReadError);
+        }
+        // Opening would create a missing database.
+        if !kvs_path.exists() {
+            return Err(ErrorCode::FileNotFound);
+        }
+
+        let conn = Connection::open_with_flags(kvs_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        let meta = read_meta(&conn)?;
+        let mut stmt = conn.prepare("SELECT key, value FROM kvs")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+
+        // Perform hash check before decoding.
+        let mut checksum = 0u32;
+        let mut stored = Vec::new();
+        for row in rows {
+            let (key, value) = row?;
+            checksum = checksum.wrapping_add(row_hash(&key, &value));
+            stored.push((key, value));
+        }
+        if let Some(hash_path) = hash_path {
+            if read_hash(hash_path)? != checksum {
+                return Err(ErrorCode::ValidationFailed);
+            }
+        }
+
+        let mut kvs_map = KvsMap::with_capacity(stored.len());
+        for (key, value) in stored {
+            kvs_map.insert(key, decode_value(&value)?);
+        }
+
+        if let Some((generation, stored_checksum)) = meta {
+            SAVED_STATES.lock()?.insert(
+                kvs_path.to_path_buf(),
+                SavedState {
+                    generation,
+                    checksum: stored_checksum,
+                    kvs_map: kvs_map.clone(),
+                },
+            );
+        }
+        Ok(kvs_map)
+    }
+
+    fn save_db(
+        kvs_map: &KvsMap,
+        kvs_path: &Path,
+        hash_path: Option<&PathBuf>,
+    ) -> Result<(), ErrorCode> {
+        if !check_extension(kvs_path, "db") {
+            return Err(ErrorCode::KvsFileReadError);
+        }
+        if hash_path.is_some_and(|p| !check_extension(p, "hash")) {
+            return Err(ErrorCode::KvsHashFileReadError);
+        }
+
+        let saved = SAVED_STATES.lock()?.remove(kvs_path);
+        let mut conn = open(kvs_path)?;
+        let txn = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let meta = read_meta(&txn)?;
+
+        // Database changed since it was last seen, rewrite all rows.
+        let saved = saved.filter(|s| meta == Some((s.generation, s.checksum)));
+        let checksum = match saved {
+            Some(saved) => {
+                let mut checksum = saved.checksum;
+                for (key, value) in kvs_map {
+                    if saved.kvs_map.get(key) != Some(value) {
+                        write_row(&txn, key, value, &mut checksum)?;
+                    }
+                }
+                for key in saved.kvs_map.keys() {
+                    if !kvs_map.contains_key(key) {
+                        remove_row(&txn, key, &mut checksum)?;
+                    }
+                }
+                checksum
+            }
+            None => {
+                txn.execute("DELETE FROM kvs", [])?;
+                let mut checksum = 0;
+                for (key, value) in kvs_map {
+                    write_row(&txn, key, value, &mut checksum)?;
+                }
+                checksum
+            }
+        };
+        let generation = meta.map_or(0, |(generation, _)| generation) + 1;
+        write_meta(&txn, generation, checksum)?;
+        txn.commit()?;
+
+        if let Some(hash_path) = hash_path {
+            fs::write(hash_path, checksum.to_be_bytes())?;
+        }
+
+        SAVED_STATES.lock()?.insert(
+            kvs_path.to_path_buf(),
+            SavedState {
+                generation,
+                checksum,
+                kvs_map: kvs_map.clone(),
+            },
+        );
+        Ok(())
+    }
+}
+
+impl KvsBackend for SqliteBackend {
+    fn load_kvs(kvs_path: &Path, hash_path: Option<&PathBuf>) -> Result<KvsMap, ErrorCode> {
+        Self::load_kvs_with_policy(kvs_path, hash_path, DuplicateKeyPolicy::default())
+    }
+
+    fn load_kvs_with_policy(
+        kvs_path: &Path,
+        hash_path: Option<&PathBuf>,
+        duplicate_keys: DuplicateKeyPolicy,
+    ) -> Result<KvsMap, ErrorCode> {
+        if is_json(kvs_path) {
+            return JsonBackend::load_kvs_with_policy(kvs_path, hash_path, duplicate_keys);
+        }
+        Self::load_db(kvs_path, hash_path)
+    }
+
+    fn save_kvs(
+        kvs_map: &KvsMap,
+        kvs_path: &Path,
+        hash_path: Option<&PathBuf>,
+    ) -> Result<(), ErrorCode> {
+        if is_json(kvs_path) {
+            return JsonBackend::save_kvs(kvs_map, kvs_path, hash_path);
+        }
+        Self::save_db(kvs_map, kvs_path, hash_path)
+    }
+
+    /// The database last saved by this process is copied instead of renamed, so it keeps being
+    /// updated incrementally.
+    fn move_kvs(
+        old_kvs_path: &Path,
+        old_hash_path: &Path,
+        new_kvs_path: &Path,
+        new_hash_path: &Path,
+    ) -> Result<(), ErrorCode> {
+        match (old_kvs_path.exists(), old_hash_path.exists()) {
+            (true, true) => {
+                let mut saved_states = SAVED_STATES.lock()?;
+                saved_states.remove(new_kvs_path);
+                if saved_states.contains_key(old_kvs_path) {
+                    fs::copy(old_hash_path, new_hash_path)?;
+                    fs::copy(old_kvs_path, new_kvs_path)?;
+                } else {
+                    fs::rename(old_hash_path, new_hash_path)?;
+                    fs::rename(old_kvs_path, new_kvs_path)?;
+                }
+                Ok(())
+            }
+            (false, false) => Ok(()),
+            // Either snapshot or hash file got removed.
+            _ => Err(ErrorCode::IntegrityCorrupted),
+        }
+    }
+
+    fn remove_kvs(kvs_path: &Path, hash_path: &Path) -> Result<(), ErrorCode> {
+        SAVED_STATES.lock()?.remove(kvs_path);
+        for path in [kvs_path, hash_path] {
+            match fs::remove_file(path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// KVS backend path resolver for `SqliteBackend`.
+///
+/// Defaults file names are equal to `JsonBackend` file names.
+impl KvsPathResolver for SqliteBackend {
+    fn kvs_file_name(instance_id: InstanceId, snapshot_id: SnapshotId) -> String {
+        format!("kvs_{instance_id}_{snapshot_id}.db")
+    }
+
+    fn kvs_file_path(
+        working_dir: &Path,
+        instance_id: InstanceId,
+        snapshot_id: SnapshotId,
+    ) -> PathBuf {
+        working_dir.join(Self::kvs_file_name(instance_id, snapshot_id))
+    }
+
+    fn hash_file_name(instance_id: InstanceId, snapshot_id: SnapshotId) -> String {
+        format!("kvs_{instance_id}_{snapshot_id}.hash")
+    }
+
+    fn hash_file_path(
+        working_dir: &Path,
+        instance_id: InstanceId,
+        snapshot_id: SnapshotId,
+    ) -> PathBuf {
+        working_dir.join(Self::hash_file_name(instance_id, snapshot_id))
+    }
+
+    fn defaults_file_name(instance_id: InstanceId) -> String {
+        JsonBackend::defaults_file_name(instance_id)
+    }
+
+    fn defaults_file_path(working_dir: &Path, instance_id: InstanceId) -> PathBuf {
+        JsonBackend::defaults_file_path(working_dir, instance_id)
+    }
+
+    fn global_defaults_file_name() -> String {
+        JsonBackend::global_defaults_file_name()
+    }
+
+    fn global_defaults_file_path(working_dir: &Path) -> PathBuf {
+        JsonBackend::global_defaults_file_path(working_dir)
+    }
+}
+
+#[cfg(test)]
+mod backend_tests {
+    use crate::error_code::ErrorCode;
+    use crate::kvs_backend::KvsBackend;
+    use crate::kvs_value::{KvsMap, KvsValue};
+    use crate::sqlite_backend::SqliteBackend;
+    use rusqlite::Connection;
+    use std::path::{Path, PathBuf};
+    use tempfile::tempdir;
+
+    fn kvs_map() -> KvsMap {
+        KvsMap::from([
+            ("k1".to_string(), KvsValue::from("v1")),
+            ("k2".to_string(), KvsValue::from(true)),
+            ("k3".to_string(), KvsValue::from(123.4)),
+            ("k4".to_string(), KvsValue::U64(u64::MAX)),
+            (
+                "k5".to_string(),
+                KvsValue::from(vec![KvsValue::I32(-1), KvsValue::Null]),
+            ),
+            (
+                "k6".to_string(),
+                KvsValue::from(KvsMap::from([("x".to_string(), KvsValue::I64(5))])),
+            ),
+        ])
+    }
+
+    fn create_kvs_files(working_dir: &Path) -> (PathBuf, PathBuf) {
+        let kvs_path = working_dir.join("kvs.db");
+        let hash_path = working_dir.join("kvs.hash");
+        SqliteBackend::save_kvs(&kvs_map(), &kvs_path, Some(&hash_path)).unwrap();
+        (kvs_path, hash_path)
+    }
+
+    /// Stored value text of a key.
+    fn stored_value(kvs_path: &Path, key: &str) -> Option<String> {
+        Connection::open(kvs_path)
+            .unwrap()
+            .query_row("SELECT value FROM kvs WHERE key = ?1", [key], |row| {
+                row.get(0)
+            })
+            .ok()
+    }
+
+    #[test]
+    fn test_load_kvs_hash_path_some_ok() {
+        let dir = tempdir().unwrap();
+        let (kvs_path, hash_path) = create_kvs_files(dir.path());
+
+        let kvs_map = SqliteBackend::load_kvs(&kvs_path, Some(&hash_path)).unwrap();
+        assert_eq!(kvs_map, self::kvs_map());
+    }
+
+    #[test]
+    fn test_save_kvs_incremental() {
+        let dir = tempdir().unwrap();
+        let (kvs_path, hash_path) = create_kvs_files(dir.path());
+
+        let mut kvs_map = kvs_map();
+        kvs_map.insert("k1".to_string(), KvsValue::from("changed"));
+        kvs_map.insert("new".to_string(), KvsValue::I32(7));
+        kvs_map.remove("k2");
+        SqliteBackend::save_kvs(&kvs_map, &kvs_path, Some(&hash_path)).unwrap();
+
+        assert!(stored_value(&kvs_path, "k1").unwrap().contains("changed"));
+        assert!(stored_value(&kvs_path, "k2").is_none());
+        assert_eq!(
+            SqliteBackend::load_kvs(&kvs_path, Some(&hash_path)).unwrap(),
+            kvs_map
+        );
+    }
+
+    #[test]
+    fn test_save_kvs_external_change_rewrites() {
+        let dir = tempdir().unwrap();
+        let (kvs_path, hash_path) = create_kvs_files(dir.path());
+
+        // Another process changed the database.
+        Connection::open(&kvs_path)
+            .unwrap()
+            .execute_batch(
+                "DELETE FROM kvs WHERE key = 'k1';
+                 UPDATE meta SET value = value + 1 WHERE name = 'generation';",
+            )
+            .unwrap();
+
+        SqliteBackend::save_kvs(&kvs_map(), &kvs_path, Some(&hash_path)).unwrap();
+        assert_eq!(
+            SqliteBackend::load_kvs(&kvs_path, Some(&hash_path)).unwrap(),
+            kvs_map()
+        );
+    }
+
+    #[test]
+    fn test_load_kvs_invalid_hash_content() {
+        let dir = tempdir().unwrap();
+        let (kvs_path, hash_path) = create_kvs_files(dir.path());
+        std::fs::write(&hash_path, vec![0x12, 0x34, 0x56, 0x78]).unwrap();
+
+        assert!(SqliteBackend::load_kvs(&kvs_path, Some(&hash_path))
+            .is_err_and(|e| e == ErrorCode::ValidationFailed));
+    }
+
+    #[test]
+    fn test_load_kvs_not_found() {
+        let dir = tempdir().unwrap();
+        let kvs_path = dir.path().join("kvs.db");
+
+        assert!(
+            SqliteBackend::load_kvs(&kvs_path, None).is_err_and(|e| e == ErrorCode::FileNotFound)
+        );
+        assert!(!kvs_path.exists());
+    }
+
+    #[test]
+    fn test_load_kvs_not_a_database() {
+        let dir = tempdir().unwrap();
+        let kvs_path = dir.path().join("kvs.db");
+        std::fs::write(
+            &kvs_path,
+            "not a database, but long enough to contain a header",
+        )
+        .unwrap();
+
+        assert!(SqliteBackend::load_kvs(&kvs_path, None)
+            .is_err_and(|e| e == ErrorCode::IntegrityCorrupted));
+    }
+
+    #[test]
+    fn test_json_defaults() {
+        let dir = tempdir().unwrap();
+        let defaults_path = dir.path().join("defaults.json");
+        SqliteBackend::save_kvs(&kvs_map(), &defaults_path, None).unwrap();
+
+        assert!(std::fs::read_to_string(&defaults_path)
+            .unwrap()
+            .starts_with('{'));
+        assert_eq!(
+            SqliteBackend::load_kvs(&defaults_path, None).unwrap(),
+            kvs_map()
+        );
+    }
+
+    #[test]
+    fn test_save_kvs_invalid_extension() {
+        let dir = tempdir().unwrap();
+        let kvs_path = dir.path().join("kvs.invalid_ext");
+
+        assert!(SqliteBackend::save_kvs(&KvsMap::new(), &kvs_path, None)
+            .is_err_and(|e| e == ErrorCode::KvsFileReadError));
+    }
+}
+
+#[cfg(all(test, feature = "snapshots"))]
+mod kvs_tests {
+    use crate::json_backend::JsonBackend;
+    use crate::kvs_api::{InstanceId, KvsApi, SnapshotId};
+    use crate::kvs_backend::{KvsBackend, KvsPathResolver};
+    use crate::kvs_builder::kvs_builder_tests::lock_and_reset;
+    use crate::kvs_builder::GenericKvsBuilder;
+    use crate::kvs_value::{KvsMap, KvsValue};
+    use crate::sqlite_backend::SqliteBackend;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_flush_and_reopen() {
+        let _lock = lock_and_reset();
+        let dir = tempdir().unwrap();
+        let dir_string = dir.path().to_string_lossy().to_string();
+        JsonBackend::save_kvs(
+            &KvsMap::from([("default".to_string(), KvsValue::I32(1))]),
+            &SqliteBackend::defaults_file_path(dir.path(), InstanceId(1)),
+            None,
+        )
+        .unwrap();
+
+        let kvs = GenericKvsBuilder::<SqliteBackend>::new(InstanceId(1))
+            .dir(dir_string.clone())
+            .build()
+            .unwrap();
+        for idx in 0..3 {
+            kvs.set_value("counter", idx).unwrap();
+            kvs.flush().unwrap();
+        }
+        assert_eq!(kvs.snapshot_count(), 3);
+        kvs.snapshot_restore(SnapshotId(1)).unwrap();
+        assert_eq!(kvs.get_value_as::<i32>("counter").unwrap(), 1);
+        kvs.flush().unwrap();
+        drop(kvs);
+
+        // Reopen from the stored files.
+        drop(_lock);
+        let _lock = lock_and_reset();
+        let kvs = GenericKvsBuilder::<SqliteBackend>::new(InstanceId(1))
+            .dir(dir_string)
+            .build()
+            .unwrap();
+        assert_eq!(kvs.get_value_as::<i32>("counter").unwrap(), 1);
+        assert_eq!(kvs.get_value_as::<i32>("default").unwrap(), 1);
+    }
+}
+
+#[cfg(test)]
+mod path_resolver_tests {
+    use crate::kvs_api::{InstanceId, SnapshotId};
+    use crate::kvs_backend::KvsPathResolver;
+    use crate::sqlite_backend::SqliteBackend;
+
+    #[test]
+    fn test_kvs_file_name() {
+        let act_name = SqliteBackend::kvs_file_name(InstanceId(123), SnapshotId(2));
+        assert_eq!(act_name, "kvs_123_2.db");
+    }
+
+    #[test]
+    fn test_defaults_file_name() {
+        let act_name = SqliteBackend::defaults_file_name(InstanceId(123));
+        assert_eq!(act_name, "kvs_123_default.json");
+    }
+}
@@ -0,0 +1,513 @@
+// Copyright (c) 2025 Contributors to the Eclipse Foundation
+//
+// See the NOTICE file(s) distributed with this work for additional
+// information regarding copyright ownership.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Apache License Version 2.0 which is available at
+// <https://www.apache.org/licenses/LICENSE-2.0>
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::error_code::ErrorCode;
+use crate::kvs_api::{FloatFormat, InstanceId, SnapshotId};
+use crate::kvs_backend::{check_extension, validate_hash, write_hash, KvsBackend, KvsPathResolver};
+use crate::kvs_fs::StdFs;
+use crate::kvs_log::kvs_error;
+use crate::kvs_value::{KvsMap, KvsValue};
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+// Example of how KvsValue is stored in the TOML file (typed tables, same tags as JSON backend):
+//
+//   my_int = { t = "i32", v = 42 }
+//   my_float = { t = "f64", v = 3.1415 }
+//   my_bool = { t = "bool", v = true }
+//   my_string = { t = "str", v = "hello" }
+//   my_array = { t = "arr", v = [ ... ] }
+//   my_object = { t = "obj", v = { ... } }
+//   my_null = { t = "null" }
+//
+// TOML has no null value, therefore `v` is omitted for `null`.
+// TOML integers are signed 64-bit, therefore `u64` values above `i64::MAX` are stored as strings.
+//
+// To keep files editable by hand, values with a native TOML representation are stored untagged:
+//
+//   my_int = 42                               # I32, integers in i32 range
+//   my_long = 9007199254740993                # I64, integers outside i32 range
+//   my_float = 3.1415                         # F64, TOML floats always contain `.` or exponent
+//   my_bool = true                            # Boolean
+//   my_string = "hello"                       # String
+//   my_array = [1, "two"]                     # Array
+//   my_object = { a = 1 }                     # Object, also written as `[my_object]` table
+//
+// Typed tables of all types are accepted when loading. A table containing only a string `t` and
+// optionally `v` is a typed table, objects of that shape are stored typed.
+
+/// Check whether a table has the shape of a typed table.
+fn is_tagged(table: &Table) -> bool {
+    matches!(table.get("t"), Some(Value::String(_))) && table.keys().all(|k| k == "t" || k == "v")
+}
+
+/// Backend-specific TOML value -> KvsValue conversion.
+fn from_toml(value: Value) -> KvsValue {
+    let mut table = match value {
+        Value::Integer(v) => return i32::try_from(v).map_or(KvsValue::I64(v), KvsValue::I32),
+        Value::Float(v) => return KvsValue::F64(v),
+        Value::Boolean(v) => return KvsValue::Boolean(v),
+        Value::String(v) => return KvsValue::String(v),
+        Value::Datetime(v) => return KvsValue::String(v.to_string()),
+        Value::Array(v) => return KvsValue::Array(v.into_iter().map(from_toml).collect()),
+        Value::Table(table) if is_tagged(&table) => table,
+        Value::Table(table) => return KvsValue::Object(from_toml_table(table)),
+    };
+
+    let type_str = match table.remove("t") {
+        Some(Value::String(type_str)) => type_str,
+        _ => return KvsValue::Null,
+    };
+
+    match (type_str.as_str(), table.remove("v")) {
+        ("i32", Some(Value::Integer(v))) => i32::try_from(v).map_or(KvsValue::Null, KvsValue::I32),
+        ("u32", Some(Value::Integer(v))) => u32::try_from(v).map_or(KvsValue::Null, KvsValue::U32),
+        ("i64", Some(Value::Integer(v))) => KvsValue::I64(v),
+        ("u64", Some(Value::Integer(v))) => u64::try_from(v).map_or(KvsValue::Null, KvsValue::U64),
+        ("u64", Some(Value::String(v))) => v.parse().map_or(KvsValue::Null, KvsValue::U64),
+        ("f64", Some(Value::Float(v))) => KvsValue::F64(v),
+        ("bool", Some(Value::Boolean(v))) => KvsValue::Boolean(v),
+        ("str", Some(Value::String(v))) => KvsValue::String(v),
+        ("null", None) => KvsValue::Null,
+        ("arr", Some(Value::Array(v))) => KvsValue::Array(v.into_iter().map(from_toml).collect()),
+        ("obj", Some(Value::Table(v))) => KvsValue::Object(from_toml_table(v)),
+        // Remaining types can be handled with Null.
+        _ => KvsValue::Null,
+    }
+}
+
+/// Backend-specific TOML table -> KvsMap conversion.
+fn from_toml_table(table: Table) -> KvsMap {
+    table.into_iter().map(|(k, v)| (k, from_toml(v))).collect()
+}
+
+/// Backend-specific KvsValue -> TOML value conversion.
+fn to_toml(value: &KvsValue) -> Value {
+    // Values loaded back unchanged from their native representation are stored untagged.
+    match value {
+        KvsValue::I32(n) => return Value::Integer(i64::from(*n)),
+        KvsValue::I64(n) if i32::try_from(*n).is_err() => return Value::Integer(*n),
+        KvsValue::F64(n) => return Value::Float(*n),
+        KvsValue::Boolean(b) => return Value::Boolean(*b),
+        KvsValue::String(s) => return Value::String(s.clone()),
+        KvsValue::Array(arr) => return Value::Array(arr.iter().map(to_toml).collect()),
+        KvsValue::Object(map) => {
+            let table = to_toml_table(map);
+            if !is_tagged(&table) {
+                return Value::Table(table);
+            }
+        }
+        _ => {}
+    }
+
+    let (type_str, value) = match value {
+        KvsValue::I32(n) => ("i32", Some(Value::Integer(i64::from(*n)))),
+        KvsValue::U32(n) => ("u32", Some(Value::Integer(i64::from(*n)))),
+        KvsValue::I64(n) => ("i64", Some(Value::Integer(*n))),
+        KvsValue::U64(n) => match i64::try_from(*n) {
+            Ok(n) => ("u64", Some(Value::Integer(n))),
+            Err(_) => ("u64", Some(Value::String(n.to_string()))),
+        },
+        KvsValue::F64(n) => ("f64", Some(Value::Float(*n))),
+        KvsValue::Boolean(b) => ("bool", Some(Value::Boolean(*b))),
+        KvsValue::String(s) => ("str", Some(Value::String(s.clone()))),
+        KvsValue::Null => ("null", None),
+        KvsValue::Array(arr) => ("arr", Some(Value::Array(arr.iter().map(to_toml).collect()))),
+        KvsValue::Object(map) => ("obj", Some(Value::Table(to_toml_table(map)))),
+    };
+
+    let mut table = Table::new();
+    table.insert("t".to_string(), Value::String(type_str.to_string()));
+    if let Some(value) = value {
+        table.insert("v".to_string(), value);
+    }
+    Value::Table(table)
+}
+
+/// Backend-specific KvsMap -> TOML table conversion.
+fn to_toml_table(kvs_map: &KvsMap) -> Table {
+    kvs_map
+        .iter()
+        .map(|(k, v)| (k.clone(), to_toml(v)))
+        .collect()
+}
+
+/// toml::de::Error -> ErrorCode::SerializationFailed
+impl From<toml::de::Error> for ErrorCode {
+    fn from(cause: toml::de::Error) -> Self {
+        kvs_error!("TOML parser error: {}", cause.message());
+        ErrorCode::SerializationFailed
+    }
+}
+
+/// toml::ser::Error -> ErrorCode::SerializationFailed
+impl From<toml::ser::Error> for ErrorCode {
+    fn from(cause: toml::ser::Error) -> Self {
+        kvs_error!("TOML generator error: {cause}");
+        ErrorCode::SerializationFailed
+    }
+}
+
+/// KVS backend implementation based on TOML.
+pub struct TomlBackend;
+
+impl KvsBackend for TomlBackend {
+    fn load_kvs(kvs_path: &Path, hash_path: Option<&PathBuf>) -> Result<KvsMap, ErrorCode> {
+        if !check_extension(kvs_path, "toml") {
+            return Err(ErrorCode::KvsFileReadError);
+        }
+        if hash_path.is_some_and(|p| !check_extension(p, "hash")) {
+            return Err(ErrorCode::KvsHashFileReadError);
+        }
+
+        // Load KVS file and parse from string to `Table`.
+        let toml_str = fs::read_to_string(kvs_path)?;
+        let table: Table = toml_str.parse()?;
+
+        // Perform hash check.
+        if let Some(hash_path) = hash_path {
+            validate_hash::<StdFs>(toml_str.as_bytes(), hash_path)?;
+        }
+
+        Ok(from_toml_table(table))
+    }
+
+    fn load_kvs_from_bytes(bytes: &[u8]) -> Result<KvsMap, ErrorCode> {
+        let table: Table = String::from_utf8(bytes.to_vec())?.parse()?;
+        Ok(from_toml_table(table))
+    }
+
+    fn save_kvs(
+        kvs_map: &KvsMap,
+        kvs_path: &Path,
+        hash_path: Option<&PathBuf>,
+    ) -> Result<(), ErrorCode> {
+        // Validate extensions.
+        if !check_extension(kvs_path, "toml") {
+            return Err(ErrorCode::KvsFileReadError);
+        }
+        if hash_path.is_some_and(|p| !check_extension(p, "hash")) {
+            return Err(ErrorCode::KvsHashFileReadError);
+        }
+
+        // Stringify `Table` and save to KVS file.
+        let toml_str = toml::to_string(&to_toml_table(kvs_map))?;
+        fs::write(kvs_path, &toml_str)?;
+
+        // Generate hash and save to hash file.
+        if let Some(hash_path) = hash_path {
+            write_hash::<StdFs>(toml_str.as_bytes(), hash_path)?;
+        }
+
+        Ok(())
+    }
+
+    fn serialized_size(kvs_map: &KvsMap, _float_format: &FloatFormat) -> Result<usize, ErrorCode> {
+        Ok(toml::to_string(&to_toml_table(kvs_map))?.len())
+    }
+}
+
+/// KVS backend path resolver for `TomlBackend`.
+impl KvsPathResolver for TomlBackend {
+    fn kvs_file_name(instance_id: InstanceId, snapshot_id: SnapshotId) -> String {
+        format!("kvs_{instance_id}_{snapshot_id}.toml")
+    }
+
+    fn kvs_file_path(
+        working_dir: &Path,
+        instance_id: InstanceId,
+        snapshot_id: SnapshotId,
+    ) -> PathBuf {
+        working_dir.join(Self::kvs_file_name(instance_id, snapshot_id))
+    }
+
+    fn hash_file_name(instance_id: InstanceId, snapshot_id: SnapshotId) -> String {
+        format!("kvs_{instance_id}_{snapshot_id}.hash")
+    }
+
+    fn hash_file_path(
+        working_dir: &Path,
+        instance_id: InstanceId,
+        snapshot_id: SnapshotId,
+    ) -> PathBuf {
+        working_dir.join(Self::hash_file_name(instance_id, snapshot_id))
+    }
+
+    fn defaults_file_name(instance_id: InstanceId) -> String {
+        format!("kvs_{instance_id}_default.toml")
+    }
+
+    fn defaults_file_path(working_dir: &Path, instance_id: InstanceId) -> PathBuf {
+        working_dir.join(Self::defaults_file_name(instance_id))
+    }
+
+    fn global_defaults_file_name() -> String {
+        "kvs_global_default.toml".to_string()
+    }
+
+    fn global_defaults_file_path(working_dir: &Path) -> PathBuf {
+        working_dir.join(Self::global_defaults_file_name())
+    }
+}
+
+#[cfg(test)]
+mod toml_conversion_tests {
+    use crate::kvs_value::{KvsMap, KvsValue};
+    use crate::toml_backend::{from_toml, to_toml};
+    use toml::{Table, Value};
+
+    fn tagged(type_str: &str, value: Option<Value>) -> Value {
+        let mut table = Table::new();
+        table.insert("t".to_string(), Value::String(type_str.to_string()));
+        if let Some(value) = value {
+            table.insert("v".to_string(), value);
+        }
+        Value::Table(table)
+    }
+
+    #[test]
+    fn test_roundtrip_all_types() {
+        let kv = KvsValue::Object(KvsMap::from([
+            ("i32".to_string(), KvsValue::I32(i32::MIN)),
+            ("u32".to_string(), KvsValue::U32(u32::MAX)),
+            ("i64".to_string(), KvsValue::I64(i64::MIN)),
+            ("u64".to_string(), KvsValue::U64(u64::MAX)),
+            ("f64".to_string(), KvsValue::F64(-432.1)),
+            ("bool".to_string(), KvsValue::Boolean(true)),
+            ("str".to_string(), KvsValue::from("example")),
+            ("null".to_string(), KvsValue::Null),
+            (
+                "arr".to_string(),
+                KvsValue::Array(vec![KvsValue::I32(1), KvsValue::from("two")]),
+            ),
+            ("i64_small".to_string(), KvsValue::I64(5)),
+            ("f64_whole".to_string(), KvsValue::F64(2.0)),
+            (
+                "tag_like".to_string(),
+                KvsValue::Object(KvsMap::from([
+                    ("t".to_string(), KvsValue::from("x")),
+                    ("v".to_string(), KvsValue::I32(1)),
+                ])),
+            ),
+        ]));
+        assert_eq!(from_toml(to_toml(&kv)), kv);
+    }
+
+    #[test]
+    fn test_native_values() {
+        assert_eq!(to_toml(&KvsValue::I32(5)), Value::Integer(5));
+        assert_eq!(to_toml(&KvsValue::I64(i64::MAX)), Value::Integer(i64::MAX));
+        assert_eq!(
+            to_toml(&KvsValue::I64(5)),
+            tagged("i64", Some(Value::Integer(5)))
+        );
+        assert_eq!(to_toml(&KvsValue::from("abc")), Value::from("abc"));
+    }
+
+    #[test]
+    fn test_u64_small_as_integer() {
+        assert_eq!(
+            to_toml(&KvsValue::U64(123)),
+            tagged("u64", Some(Value::Integer(123)))
+        );
+    }
+
+    #[test]
+    fn test_u64_large_as_string() {
+        assert_eq!(
+            to_toml(&KvsValue::U64(u64::MAX)),
+            tagged("u64", Some(Value::String(u64::MAX.to_string())))
+        );
+    }
+
+    #[test]
+    fn test_null_without_value() {
+        assert_eq!(to_toml(&KvsValue::Null), tagged("null", None));
+    }
+
+    #[test]
+    fn test_i32_out_of_range() {
+        let tv = tagged("i32", Some(Value::Integer(i64::MAX)));
+        assert_eq!(from_toml(tv), KvsValue::Null);
+    }
+
+    #[test]
+    fn test_invalid_type() {
+        let tv = tagged("bool", Some(Value::String("true".to_string())));
+        assert_eq!(from_toml(tv), KvsValue::Null);
+    }
+
+    #[test]
+    fn test_untagged_value() {
+        assert_eq!(from_toml(Value::Integer(1)), KvsValue::I32(1));
+        assert_eq!(
+            from_toml(Value::Integer(i64::from(i32::MAX) + 1)),
+            KvsValue::I64(i64::from(i32::MAX) + 1)
+        );
+    }
+}
+
+#[cfg(test)]
+mod backend_tests {
+    use crate::error_code::ErrorCode;
+    use crate::kvs_backend::KvsBackend;
+    use crate::kvs_value::{KvsMap, KvsValue};
+    use crate::toml_backend::TomlBackend;
+    use std::path::{Path, PathBuf};
+    use tempfile::tempdir;
+
+    fn create_kvs_files(working_dir: &Path) -> (PathBuf, PathBuf) {
+        let kvs_map = KvsMap::from([
+            ("k1".to_string(), KvsValue::from("v1")),
+            ("k2".to_string(), KvsValue::from(true)),
+            ("k3".to_string(), KvsValue::from(123.4)),
+        ]);
+        let kvs_path = working_dir.join("kvs.toml");
+        let hash_path = working_dir.join("kvs.hash");
+        TomlBackend::save_kvs(&kvs_map, &kvs_path, Some(&hash_path)).unwrap();
+        (kvs_path, hash_path)
+    }
+
+    #[test]
+    fn test_load_kvs_hash_path_some_ok() {
+        let dir = tempdir().unwrap();
+        let (kvs_path, hash_path) = create_kvs_files(dir.path());
+
+        let kvs_map = TomlBackend::load_kvs(&kvs_path, Some(&hash_path)).unwrap();
+        assert_eq!(kvs_map.len(), 3);
+        assert_eq!(kvs_map["k3"], KvsValue::F64(123.4));
+    }
+
+    #[test]
+    fn test_load_kvs_hand_written() {
+        let dir = tempdir().unwrap();
+        let kvs_path = dir.path().join("kvs.toml");
+        std::fs::write(
+            &kvs_path,
+            "counter = { t = \"u32\", v = 5 }\n\n[name]\nt = \"str\"\nv = \"abc\"\n",
+        )
+        .unwrap();
+
+        let kvs_map = TomlBackend::load_kvs(&kvs_path, None).unwrap();
+        assert_eq!(kvs_map["counter"], KvsValue::U32(5));
+        assert_eq!(kvs_map["name"], KvsValue::from("abc"));
+    }
+
+    #[test]
+    fn test_load_kvs_hand_written_plain() {
+        let dir = tempdir().unwrap();
+        let kvs_path = dir.path().join("kvs.toml");
+        std::fs::write(
+            &kvs_path,
+            "gain = 1.5\nretries = 3\nname = \"ecu\"\n\n[calibration]\noffsets = [1, -2]\n",
+        )
+        .unwrap();
+
+        let kvs_map = TomlBackend::load_kvs(&kvs_path, None).unwrap();
+        assert_eq!(kvs_map["gain"], KvsValue::F64(1.5));
+        assert_eq!(kvs_map["retries"], KvsValue::I32(3));
+        assert_eq!(kvs_map["name"], KvsValue::from("ecu"));
+        assert_eq!(
+            kvs_map["calibration"],
+            KvsValue::Object(KvsMap::from([(
+                "offsets".to_string(),
+                KvsValue::Array(vec![KvsValue::I32(1), KvsValue::I32(-2)])
+            )]))
+        );
+    }
+
+    #[test]
+    fn test_save_kvs_plain() {
+        let dir = tempdir().unwrap();
+        let kvs_path = dir.path().join("kvs.toml");
+        let kvs_map = KvsMap::from([
+            ("retries".to_string(), KvsValue::I32(3)),
+            (
+                "calibration".to_string(),
+                KvsValue::Object(KvsMap::from([("gain".to_string(), KvsValue::F64(1.5))])),
+            ),
+        ]);
+        TomlBackend::save_kvs(&kvs_map, &kvs_path, None).unwrap();
+
+        let toml_str = std::fs::read_to_string(&kvs_path).unwrap();
+        assert!(toml_str.contains("retries = 3"), "{toml_str}");
+        assert!(toml_str.contains("gain = 1.5"), "{toml_str}");
+        assert_eq!(TomlBackend::load_kvs(&kvs_path, None).unwrap(), kvs_map);
+    }
+
+    #[test]
+    fn test_load_kvs_invalid_extension() {
+        let dir = tempdir().unwrap();
+        let kvs_path = dir.path().join("kvs.json");
+
+        assert!(
+            TomlBackend::load_kvs(&kvs_path, None).is_err_and(|e| e == ErrorCode::KvsFileReadError)
+        );
+    }
+
+    #[test]
+    fn test_load_kvs_malformed() {
+        let dir = tempdir().unwrap();
+        let kvs_path = dir.path().join("kvs.toml");
+        std::fs::write(&kvs_path, "key = ").unwrap();
+
+        assert!(TomlBackend::load_kvs(&kvs_path, None)
+            .is_err_and(|e| e == ErrorCode::SerializationFailed));
+    }
+
+    #[test]
+    fn test_load_kvs_invalid_hash_content() {
+        let dir = tempdir().unwrap();
+        let (kvs_path, hash_path) = create_kvs_files(dir.path());
+        std::fs::write(&hash_path, vec![0x12, 0x34, 0x56, 0x78]).unwrap();
+
+        assert!(TomlBackend::load_kvs(&kvs_path, Some(&hash_path))
+            .is_err_and(|e| e == ErrorCode::ValidationFailed));
+    }
+
+    #[test]
+    fn test_save_kvs_hash_path_some_invalid_extension() {
+        let dir = tempdir().unwrap();
+        let kvs_path = dir.path().join("kvs.toml");
+        let hash_path = dir.path().join("kvs.invalid_ext");
+
+        assert!(
+            TomlBackend::save_kvs(&KvsMap::new(), &kvs_path, Some(&hash_path))
+                .is_err_and(|e| e == ErrorCode::KvsHashFileReadError)
+        );
+    }
+}
+
+#[cfg(test)]
+mod path_resolver_tests {
+    use crate::kvs_api::{InstanceId, SnapshotId};
+    use crate::kvs_backend::KvsPathResolver;
+    use crate::toml_backend::TomlBackend;
+
+    #[test]
+    fn test_kvs_file_name() {
+        let act_name = TomlBackend::kvs_file_name(InstanceId(123), SnapshotId(2));
+        assert_eq!(act_name, "kvs_123_2.toml");
+    }
+
+    #[test]
+    fn test_hash_file_name() {
+        let act_name = TomlBackend::hash_file_name(InstanceId(123), SnapshotId(2));
+        assert_eq!(act_name, "kvs_123_2.hash");
+    }
+
+    #[test]
+    fn test_defaults_file_name() {
+        let act_name = TomlBackend::defaults_file_name(InstanceId(123));
+        assert_eq!(act_name, "kvs_123_default.toml");
+    }
+}
@@ -24,11 +24,20 @@
 //!
 //!    Options:
 //!    -h, --help          Show this help message and exit
-//!    -o, --operation     Specify the operation to perform (setkey, getkey, removekey, listkeys, reset, snapshotcount, snapshotmaxcount, snapshotrestore, getkvsfilename, gethashfilename, createtestdata)
-//!    -k, --key           Specify the key to operate on (for key operations)
+//!    -o, --operation     Specify the operation to perform (setkey, getkey, removekey, resetkey, listkeys, reset, snapshotcount, snapshotmaxcount, snapshotrestore, snapshotcreate, snapshotprune, snapshotexport, snapshotimport, getkvsfilename, gethashfilename, createtestdata, listinstances, export, import, setbulk, diff, migrate)
+//!    -k, --key           Specify the key to operate on (for key operations, repeatable for export/import)
 //!    -p, --payload       Specify the value to write (for set operations)
-//!    -s, --snapshotid    Specify the snapshot ID for Snapshot operations
-//!    -d, --directory     Specify the directory of the Key-Files (default is current directory)
+//!    -s, --snapshotid    Specify the snapshot ID for Snapshot operations (repeatable for diff)
+//!        --keep          Specify the count of snapshots to keep besides the current KVS (for snapshotprune)
+//!    -f, --file          Specify the JSON file for export/import/setbulk and snapshotexport/snapshotimport operations
+//!        --dry-run       Only print the changes (for setbulk)
+//!        --raw           Print only the value, strings without quotes (for getkey)
+//!        --expect-type   Fail with TypeMismatch if the value has another type (for getkey: i32, u32, i64, u64, f64, bool, str, null, arr, obj)
+//!    -d, --directory     Specify the directory of the Key-Files (default is $KVS_DATA_DIR, the platform data directory or the current directory)
+//!    -i, --instance      Specify the instance ID to operate on (default is 0)
+//!        --sensitive     Specify a key whose value is printed as <redacted> (repeatable, see below)
+//!        --no-lock       Don't take the directory lock (see below)
+//!        --json          Print the result as JSON instead of human-readable text (see below)
 //!
 //!    ---------------------------------------
 //!
@@ -36,6 +45,9 @@
 //!
 //!    Read a Key and show value:
 //!        kvs_tool -o getkey -k MyKey
+//!        kvs_tool -o getkey -k MyKey -i 2
+//!        kvs_tool -o getkey -k WifiPsk --sensitive WifiPsk
+//!        VALUE=$(kvs_tool -o getkey -k MyKey --raw --expect-type str)
 //!
 //!    Write a Key and use the <payload> as the data source:
 //!        kvs_tool -o setkey  -k MyKey -p 'Hello World' (automatically detects following types: Number, Boolean, String, Null, Object, Array)
@@ -47,8 +59,12 @@
 //!    Delete a key:
 //!        kvs_tool -o removekey -k MyKey
 //!
+//!    Reset a key to its default value (keys without a default value are removed):
+//!        kvs_tool -o resetkey -k MyKey
+//!
 //!    List Keys:
 //!        kvs_tool -o listkeys
+//!        kvs_tool -o listkeys --json
 //!
 //!    Reset KVS:
 //!        kvs_tool -o reset
@@ -59,12 +75,45 @@
 //!    Snapshot Restore:
 //!        kvs_tool -o snapshotrestore -s 1
 //!
+//!    Snapshot Create (rotates snapshots and flushes the current KVS, also if unchanged):
+//!        kvs_tool -o snapshotcreate
+//!
+//!    Snapshot Prune (removes all snapshots except the current KVS and the given count):
+//!        kvs_tool -o snapshotprune --keep 1
+//!
+//!    Snapshot Export (copies a validated snapshot and a new hash file, here backup.hash):
+//!        kvs_tool -o snapshotexport -s 1 -f backup.json
+//!
+//!    Snapshot Import (validates the file against its hash and writes it as current KVS):
+//!        kvs_tool -o snapshotimport -f backup.json
+//!
 //!    Get KVS Filename:
 //!        kvs_tool -o getkvsfilename -s 1
 //!
 //!    Get Hash Filename:
 //!        kvs_tool -o gethashfilename -s 1
 //!
+//!    List Instances with key counts, snapshot counts and file sizes:
+//!        kvs_tool -o listinstances -d /path/to/kvs
+//!
+//!    Export all stored Keys (or only the given Keys) to a plain JSON file:
+//!        kvs_tool -o export -f golden.json
+//!        kvs_tool -o export -f golden.json -k MyKey -k OtherKey
+//!
+//!    Import Keys from a plain JSON file (types are validated against existing values):
+//!        kvs_tool -o import -f golden.json
+//!
+//!    Set all Keys of a plain JSON file at once (converted like setkey payloads):
+//!        kvs_tool -o setbulk -f values.json
+//!        kvs_tool -o setbulk -f values.json --dry-run
+//!
+//!    Show added/removed/changed Keys from a snapshot to the current KVS or to another snapshot:
+//!        kvs_tool -o diff -s 1
+//!        kvs_tool -o diff -s 2 -s 1
+//!
+//!    Rewrite legacy files without type tags in the tagged format with a new hash file:
+//!        kvs_tool -o migrate -i 2
+//!
 //!    ---------------------------------------
 //!
 //!    Create Test Data:
@@ -72,10 +121,68 @@
 //!
 //! ```
 //!
+//! ## Export/Import
+//!
+//! Export writes a plain JSON object without the type tags of the KVS files, e.g.
+//! `{"MyKey":15,"Other":[true,"x"]}`. Import reads such a file and converts every value to the type
+//! of the currently stored or default value of the key, e.g. a number is stored as `I32` if the key
+//! holds an `I32`. Values with a mismatching type or out of range numbers fail the import before
+//! any key is written. Keys without stored or default value are imported like `setkey` payloads.
+//!
+//! ## Migration
+//!
+//! Stores written before type tags were introduced contain plain JSON, e.g. `{"MyKey":15}`. The
+//! KVS loads them with inferred types, numbers become `F64`, but they may lack a hash file.
+//! `migrate` rewrites the current KVS and all snapshots of the instance that are untagged in the
+//! tagged format and writes a new hash file, tagged files are left unchanged.
+//!
+//! ## JSON Output
+//!
+//! With `--json` the decorative and descriptive output is suppressed and a successful operation
+//! prints a single line with a JSON object on stdout, e.g. `{"keys":["MyKey","Other"]}` for
+//! `listkeys` or `{"snapshot_count":2}` for `snapshotcount`. Values are untagged like in exported
+//! files, `getkey` and `setkey` add the value type in `"type"`. Errors are still printed to stderr
+//! and reported by the exit code.
+//!
+//! ## Sensitive Keys
+//!
+//! Values of keys given with `--sensitive` are printed as `<redacted>` in all output, including
+//! `--raw` and `--json`, so the tool can be run by scripts whose output is logged. Exported files
+//! still contain the values.
+//!
+//! ## Raw Output
+//!
+//! `getkey --raw` prints only the stored or default value on a single line for shell capture.
+//! Strings are printed without quotes, other values in JSON notation without type tags, e.g. `15`
+//! or `[456, false, "Second"]`, integers are printed exactly. `--raw` takes precedence over `--json`.
+//! With `--expect-type` the operation fails with `TypeMismatch` if the value has another type, the
+//! type names are the tags of the KVS files.
+//!
+//! ## Directory Lock
+//!
+//! Before the instance is opened the inter-process lock of the directory
+//! (`rust_kvs::kvs_lock::KvsDirLock`) is taken and held until the operation is finished, so the tool
+//! never interleaves with a flush of an application using the same directory. If the lock is held
+//! the tool waits for it. `--no-lock` skips locking, e.g. for read-only directories.
+//!
+//! ## Exit Codes
+//!
+//! On success the tool exits with 0, otherwise with the stable numeric code of the `ErrorCode`
+//! (`ErrorCode::code`), e.g. 15 for `KeyNotFound`.
+//!
 
 use pico_args::Arguments;
+use rust_kvs::kvs_discovery::KvsFileInfo;
+use rust_kvs::kvs_lock::KvsDirLock;
+use rust_kvs::kvs_log::{set_log_sink, StderrLogSink};
+use rust_kvs::kvs_redact::REDACTED;
 use rust_kvs::prelude::*;
+use rust_kvs::JsonBackend;
 use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::Arc;
 use tinyjson::JsonValue;
 
 /// Defines the available operation modes for key and file management.
@@ -84,14 +191,57 @@ enum OperationMode {
     SetKey,
     GetKey,
     RemoveKey,
+    ResetKey,
     ListKeys,
     Reset,
     SnapshotCount,
     SnapshotMaxCount,
     SnapshotRestore,
+    SnapshotCreate,
+    SnapshotPrune,
+    SnapshotExport,
+    SnapshotImport,
     GetKvsFilename,
     GetHashFilename,
     CreateTestData,
+    ListInstances,
+    Export,
+    Import,
+    SetBulk,
+    Diff,
+    Migrate,
+}
+
+/// Output of operations, human-readable text or a JSON result object (`--json`).
+struct Output {
+    json: bool,
+}
+
+impl Output {
+    /// Prints a line of human-readable output, nothing in JSON mode.
+    fn line(&self, text: impl Display) {
+        if !self.json {
+            println!("{text}");
+        }
+    }
+
+    /// Prints the result object of an operation as a single JSON line in JSON mode, nothing
+    /// otherwise.
+    fn result<const N: usize>(&self, fields: [(&str, JsonValue); N]) -> Result<(), ErrorCode> {
+        if !self.json {
+            return Ok(());
+        }
+        let obj = fields
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect();
+        let json = JsonValue::Object(obj).stringify().map_err(|e| {
+            eprintln!("Error: JSON generation failed: {e}");
+            ErrorCode::JsonGeneratorError
+        })?;
+        println!("{json}");
+        Ok(())
+    }
 }
 
 /// Converts a TinyJSON value to a KVS value.
@@ -115,12 +265,184 @@ fn from_tinyjson(value: &JsonValue) -> KvsValue {
     }
 }
 
+/// Converts a KVS value to an untagged TinyJSON value.
+fn to_tinyjson(value: &KvsValue) -> JsonValue {
+    match value {
+        KvsValue::I32(n) => JsonValue::Number(f64::from(*n)),
+        KvsValue::U32(n) => JsonValue::Number(f64::from(*n)),
+        KvsValue::I64(n) => JsonValue::Number(*n as f64),
+        KvsValue::U64(n) => JsonValue::Number(*n as f64),
+        KvsValue::F64(n) => JsonValue::Number(*n),
+        KvsValue::Boolean(b) => JsonValue::Boolean(*b),
+        KvsValue::String(s) => JsonValue::String(s.clone()),
+        KvsValue::Null => JsonValue::Null,
+        KvsValue::Array(arr) => JsonValue::Array(arr.iter().map(to_tinyjson).collect()),
+        KvsValue::Object(obj) => JsonValue::Object(
+            obj.iter()
+                .map(|(k, v)| (k.clone(), to_tinyjson(v)))
+                .collect(),
+        ),
+    }
+}
+
+/// Converts a KVS value of a key to an untagged TinyJSON value, `"<redacted>"` if the key is
+/// sensitive.
+fn to_tinyjson_redacted(kvs: &Kvs, key: &str, value: &KvsValue) -> JsonValue {
+    if kvs.is_key_sensitive(key) {
+        JsonValue::String(REDACTED.to_string())
+    } else {
+        to_tinyjson(value)
+    }
+}
+
+/// Converts an optional KVS value of a key like [`to_tinyjson_redacted`], `null` if not set.
+fn to_tinyjson_opt(kvs: &Kvs, key: &str, value: Option<&KvsValue>) -> JsonValue {
+    value.map_or(JsonValue::Null, |value| {
+        to_tinyjson_redacted(kvs, key, value)
+    })
+}
+
+/// Converts a KVS value to its type name, `null` if not set.
+fn kind_json(value: Option<&KvsValue>) -> JsonValue {
+    value.map_or(JsonValue::Null, |value| {
+        JsonValue::String(value.kind().to_string())
+    })
+}
+
+/// Converts a file info to a TinyJSON object with path and size.
+fn file_json(file: &KvsFileInfo) -> JsonValue {
+    JsonValue::Object(HashMap::from([
+        (
+            "path".to_string(),
+            JsonValue::String(file.path.display().to_string()),
+        ),
+        ("size".to_string(), JsonValue::Number(file.size as f64)),
+    ]))
+}
+
+/// Converts a list of strings to a TinyJSON array.
+fn strings_json<S: ToString>(strings: &[S]) -> JsonValue {
+    JsonValue::Array(
+        strings
+            .iter()
+            .map(|s| JsonValue::String(s.to_string()))
+            .collect(),
+    )
+}
+
+/// Converts an untagged TinyJSON value to a KVS value of the same type as `reference`.
+/// Without reference the value is converted like a `setkey` payload.
+/// `path` names the value in error messages.
+fn from_tinyjson_typed(
+    value: &JsonValue,
+    reference: Option<&KvsValue>,
+    path: &str,
+) -> Result<KvsValue, ErrorCode> {
+    let mismatch = |expected: &str| {
+        eprintln!("Error: Value of '{path}' doesn't match stored type {expected}");
+        ErrorCode::ConversionFailed
+    };
+    let Some(reference) = reference else {
+        return Ok(from_tinyjson(value));
+    };
+    match (reference, value) {
+        (KvsValue::I32(_), JsonValue::Number(n)) => integer(*n, i32::MIN as f64, i32::MAX as f64)
+            .map(|n| KvsValue::I32(n as i32))
+            .ok_or_else(|| mismatch("i32")),
+        (KvsValue::U32(_), JsonValue::Number(n)) => integer(*n, 0.0, u32::MAX as f64)
+            .map(|n| KvsValue::U32(n as u32))
+            .ok_or_else(|| mismatch("u32")),
+        (KvsValue::I64(_), JsonValue::Number(n)) => integer(*n, i64::MIN as f64, i64::MAX as f64)
+            .map(|n| KvsValue::I64(n as i64))
+            .ok_or_else(|| mismatch("i64")),
+        (KvsValue::U64(_), JsonValue::Number(n)) => integer(*n, 0.0, u64::MAX as f64)
+            .map(|n| KvsValue::U64(n as u64))
+            .ok_or_else(|| mismatch("u64")),
+        (KvsValue::F64(_), JsonValue::Number(n)) => Ok(KvsValue::F64(*n)),
+        (KvsValue::Boolean(_), JsonValue::Boolean(b)) => Ok(KvsValue::Boolean(*b)),
+        (KvsValue::String(_), JsonValue::String(s)) => Ok(KvsValue::String(s.clone())),
+        (KvsValue::Null, JsonValue::Null) => Ok(KvsValue::Null),
+        (KvsValue::Array(reference), JsonValue::Array(arr)) => arr
+            .iter()
+            .enumerate()
+            .map(|(i, v)| from_tinyjson_typed(v, reference.get(i), &format!("{path}[{i}]")))
+            .collect::<Result<Vec<_>, _>>()
+            .map(KvsValue::Array),
+        (KvsValue::Object(reference), JsonValue::Object(obj)) => obj
+            .iter()
+            .map(|(k, v)| {
+                from_tinyjson_typed(v, reference.get(k), &format!("{path}.{k}"))
+                    .map(|v| (k.clone(), v))
+            })
+            .collect::<Result<HashMap<_, _>, _>>()
+            .map(KvsValue::Object),
+        (KvsValue::I32(_), _) => Err(mismatch("i32")),
+        (KvsValue::U32(_), _) => Err(mismatch("u32")),
+        (KvsValue::I64(_), _) => Err(mismatch("i64")),
+        (KvsValue::U64(_), _) => Err(mismatch("u64")),
+        (KvsValue::F64(_), _) => Err(mismatch("f64")),
+        (KvsValue::Boolean(_), _) => Err(mismatch("bool")),
+        (KvsValue::String(_), _) => Err(mismatch("string")),
+        (KvsValue::Null, _) => Err(mismatch("null")),
+        (KvsValue::Array(_), _) => Err(mismatch("array")),
+        (KvsValue::Object(_), _) => Err(mismatch("object")),
+    }
+}
+
+/// Returns the number if it's an integer within `min..=max`.
+fn integer(n: f64, min: f64, max: f64) -> Option<f64> {
+    (n.fract() == 0.0 && n >= min && n <= max).then_some(n)
+}
+
+/// Parses a value type name as used in the type tags of KVS files, e.g. `i32` or `str`.
+fn parse_kind(name: &str) -> Result<KvsValueKind, ErrorCode> {
+    match name {
+        "i32" => Ok(KvsValueKind::I32),
+        "u32" => Ok(KvsValueKind::U32),
+        "i64" => Ok(KvsValueKind::I64),
+        "u64" => Ok(KvsValueKind::U64),
+        "f64" => Ok(KvsValueKind::F64),
+        "bool" => Ok(KvsValueKind::Boolean),
+        "str" => Ok(KvsValueKind::String),
+        "null" => Ok(KvsValueKind::Null),
+        "arr" => Ok(KvsValueKind::Array),
+        "obj" => Ok(KvsValueKind::Object),
+        _ => {
+            eprintln!(
+                "Error: Unknown type '{name}' (i32, u32, i64, u64, f64, bool, str, null, arr, obj)"
+            );
+            Err(ErrorCode::UnmappedError)
+        }
+    }
+}
+
+/// Formats a value for `--raw` output, strings without quotes.
+fn raw_value(value: &KvsValue) -> String {
+    match value {
+        KvsValue::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// Reads the file argument of export/import operations.
+fn file_arg(args: &mut Arguments) -> Result<String, ErrorCode> {
+    match args.opt_value_from_str("--file") {
+        Ok(Some(val)) => Ok(val),
+        Ok(None) | Err(_) => match args.opt_value_from_str("-f") {
+            Ok(Some(val)) => Ok(val),
+            _ => {
+                eprintln!("Error: File (-f or --file) needs to be specified!");
+                Err(ErrorCode::UnmappedError)
+            }
+        },
+    }
+}
+
 /// Gets the key-value pair from the KVS and prints it to the console.
 /// This function checks if the key exists and if it is a default value.
 /// It also prints the default value.
-fn _getkey(kvs: Kvs, mut args: Arguments) -> Result<(), ErrorCode> {
-    println!("----------------------");
-
+/// With `--raw` only the value is printed, `--expect-type` checks the type of the value.
+fn _getkey(kvs: Kvs, out: &Output, mut args: Arguments) -> Result<(), ErrorCode> {
     let key: String = match args.opt_value_from_str("--key") {
         Ok(Some(val)) => val,
         Ok(None) | Err(_) => match args.opt_value_from_str("-k") {
@@ -131,49 +453,104 @@ fn _getkey(kvs: Kvs, mut args: Arguments) -> Result<(), ErrorCode> {
             }
         },
     };
-    println!("Read Key {}", &key);
+    let raw = args.contains("--raw");
+    let expect_type = match args.opt_value_from_str::<_, String>("--expect-type") {
+        Ok(Some(name)) => Some(parse_kind(&name)?),
+        Ok(None) => None,
+        Err(_) => {
+            eprintln!("Error: Type (--expect-type) needs a value!");
+            return Err(ErrorCode::UnmappedError);
+        }
+    };
+    let check_type = |value: &KvsValue| match expect_type {
+        Some(kind) if value.kind() != kind => {
+            eprintln!(
+                "Error: Value of '{key}' has type {}, expected {kind}",
+                value.kind()
+            );
+            Err(ErrorCode::TypeMismatch)
+        }
+        _ => Ok(()),
+    };
+
+    // Only the value is printed, also if it's the default.
+    if raw {
+        let value = kvs.get_value(&key).map_err(|e| {
+            eprintln!("Get Key Error: {e}");
+            e
+        })?;
+        check_type(&value)?;
+        if kvs.is_key_sensitive(&key) {
+            println!("{REDACTED}");
+        } else {
+            println!("{}", raw_value(&value));
+        }
+        return Ok(());
+    }
+    out.line("----------------------");
+    out.line(format!("Read Key {}", &key));
 
     let key_exist = kvs.key_exists(&key).map_err(|e| {
-        eprintln!("KVS get:key_exists failed: {e:?}");
+        eprintln!("KVS get:key_exists failed: {e}");
         e
     })?;
 
     let is_default = kvs.is_value_default(&key).map_err(|e| {
-        eprintln!("KVS get:is_value_default failed: {e:?}");
+        eprintln!("KVS get:is_value_default failed: {e}");
         e
     })?;
 
-    if key_exist {
-        println!("Key '{key}' exists!");
+    let value = if key_exist {
+        out.line(format!("Key '{key}' exists!"));
         match kvs.get_value(&key) {
             Ok(value) => {
-                println!("Key Value: {value:?}");
+                out.line(format!("Key Value: {:?}", kvs.redacted(&key, &value)));
+                out.line(format!("Key Type: {}", value.kind()));
+                Some(value)
             }
             Err(e) => {
-                eprintln!("Get Key Error: {e:?}");
+                eprintln!("Get Key Error: {e}");
+                None
             }
-        };
+        }
     } else {
-        println!("Key '{key}' does not exist!");
+        out.line(format!("Key '{key}' does not exist!"));
         if is_default {
-            println!("Key is default value!");
+            out.line("Key is default value!");
         } else {
-            println!("Key is not default value!");
+            out.line("Key is not default value!");
             return Err(ErrorCode::KeyNotFound);
         }
-    }
+        None
+    };
 
-    match kvs.get_default_value(&key) {
+    let default_value = match kvs.get_default_value(&key) {
         Ok(value) => {
-            println!("Default Value: {value:?}");
+            out.line(format!("Default Value: {:?}", kvs.redacted(&key, &value)));
+            Some(value)
         }
         Err(e) => {
-            eprintln!("Default Value Error: {e:?}");
+            eprintln!("Default Value Error: {e}");
+            None
         }
     };
 
-    println!("----------------------");
-    Ok(())
+    if let Some(value) = value.as_ref().or(default_value.as_ref()) {
+        check_type(value)?;
+    }
+
+    out.line("----------------------");
+    out.result([
+        ("key", JsonValue::String(key.clone())),
+        ("exists", JsonValue::Boolean(key_exist)),
+        ("is_default", JsonValue::Boolean(is_default)),
+        ("value", to_tinyjson_opt(&kvs, &key, value.as_ref())),
+        ("type", kind_json(value.as_ref())),
+        (
+            "default_value",
+            to_tinyjson_opt(&kvs, &key, default_value.as_ref()),
+        ),
+    ])
 }
 
 /// Sets a key-value pair in the KVS.
@@ -181,9 +558,9 @@ fn _getkey(kvs: Kvs, mut args: Arguments) -> Result<(), ErrorCode> {
 /// If the payload is a valid JSON string, it will be parsed and stored as a KVSValue.
 /// If the payload is not provided, it will store a null value.
 /// If the payload is not a valid JSON string, it will be stored as a string.
-fn _setkey(kvs: Kvs, mut args: Arguments) -> Result<(), ErrorCode> {
-    println!("----------------------");
-    println!("Set Key");
+fn _setkey(kvs: Kvs, out: &Output, mut args: Arguments) -> Result<(), ErrorCode> {
+    out.line("----------------------");
+    out.line("Set Key");
     let key: String = match args.opt_value_from_str("--key") {
         Ok(Some(val)) => val,
         Ok(None) | Err(_) => match args.opt_value_from_str("-k") {
@@ -203,38 +580,44 @@ fn _setkey(kvs: Kvs, mut args: Arguments) -> Result<(), ErrorCode> {
         },
     };
 
-    match value_str {
+    let kvs_val = match value_str {
         Some(value) => {
             if let Ok(json_val) = value.parse::<JsonValue>() {
                 let kvs_val = from_tinyjson(&json_val);
-                println!("Key:'{}' \nParsed as JSON Value: {:?}", &key, kvs_val);
-                kvs.set_value(key, kvs_val).map_err(|e| {
-                    eprintln!("KVS set failed: {e:?}");
-                    e
-                })?;
+                out.line(format!(
+                    "Key:'{}' \nParsed as JSON Value: {:?}",
+                    &key,
+                    kvs.redacted(&key, &kvs_val)
+                ));
+                kvs_val
             } else {
-                println!("Key:'{}' \nParsed as String Value: {}", &key, value);
-                kvs.set_value(key, KvsValue::String(value)).map_err(|e| {
-                    eprintln!("KVS set failed: {e:?}");
-                    e
-                })?;
+                let kvs_val = KvsValue::String(value);
+                out.line(format!(
+                    "Key:'{}' \nParsed as String Value: {}",
+                    &key,
+                    kvs.redacted(&key, &kvs_val)
+                ));
+                kvs_val
             }
         }
-        None => {
-            kvs.set_value(key, KvsValue::Null).map_err(|e| {
-                eprintln!("KVS set failed: {e:?}");
-                e
-            })?;
-        }
-    }
+        None => KvsValue::Null,
+    };
+    kvs.set_value(&key, kvs_val.clone()).map_err(|e| {
+        eprintln!("KVS set failed: {e}");
+        e
+    })?;
     kvs.flush()?;
-    println!("----------------------");
-    Ok(())
+    out.line("----------------------");
+    out.result([
+        ("key", JsonValue::String(key.clone())),
+        ("value", to_tinyjson_redacted(&kvs, &key, &kvs_val)),
+        ("type", kind_json(Some(&kvs_val))),
+    ])
 }
 
 /// Removes a key-value pair from the KVS.
-fn _removekey(kvs: Kvs, mut args: Arguments) -> Result<(), ErrorCode> {
-    println!("----------------------");
+fn _removekey(kvs: Kvs, out: &Output, mut args: Arguments) -> Result<(), ErrorCode> {
+    out.line("----------------------");
     let key: String = match args.opt_value_from_str("--key") {
         Ok(Some(val)) => val,
         Ok(None) | Err(_) => match args.opt_value_from_str("-k") {
@@ -245,73 +628,163 @@ fn _removekey(kvs: Kvs, mut args: Arguments) -> Result<(), ErrorCode> {
             }
         },
     };
-    println!("Remove Key {}", &key);
+    out.line(format!("Remove Key {}", &key));
     kvs.remove_key(&key).map_err(|e| {
-        eprintln!("KVS remove failed: {e:?}");
+        eprintln!("KVS remove failed: {e}");
+        e
+    })?;
+    kvs.flush()?;
+    out.line("----------------------");
+    out.result([("key", JsonValue::String(key))])
+}
+
+/// Resets a key to its default value, keys without a default value are removed.
+fn _resetkey(kvs: Kvs, out: &Output, mut args: Arguments) -> Result<(), ErrorCode> {
+    out.line("----------------------");
+    let key: String = match args.opt_value_from_str("--key") {
+        Ok(Some(val)) => val,
+        Ok(None) | Err(_) => match args.opt_value_from_str("-k") {
+            Ok(Some(val)) => val,
+            _ => {
+                eprintln!("Error: Key (-k or --key) needs to be specified!");
+                return Err(ErrorCode::UnmappedError);
+            }
+        },
+    };
+    out.line(format!("Reset Key {}", &key));
+    let has_default = kvs.reset_key_or_remove(&key).map_err(|e| {
+        eprintln!("KVS reset failed: {e}");
         e
     })?;
     kvs.flush()?;
-    println!("----------------------");
-    Ok(())
+    if has_default {
+        out.line("Reset to default value");
+    } else {
+        out.line("No default value, key removed");
+    }
+    out.line("----------------------");
+    out.result([
+        ("key", JsonValue::String(key)),
+        ("default", JsonValue::Boolean(has_default)),
+    ])
 }
 
 /// Lists all keys in the KVS.
 /// It retrieves all keys and prints them to the console.
-fn _listkeys(kvs: Kvs) -> Result<(), ErrorCode> {
-    println!("----------------------");
-    println!("List Keys");
+fn _listkeys(kvs: Kvs, out: &Output) -> Result<(), ErrorCode> {
+    out.line("----------------------");
+    out.line("List Keys");
     let keys = kvs.get_all_keys().map_err(|e| {
-        eprintln!("KVS list failed: {e:?}");
+        eprintln!("KVS list failed: {e}");
         e
     })?;
 
-    for key in keys {
-        println!("{key}");
+    for key in &keys {
+        out.line(key);
     }
 
-    println!("----------------------");
-    Ok(())
+    out.line("----------------------");
+    out.result([("keys", strings_json(&keys))])
 }
 
 /// Resets the KVS by removing all keys and values.
-fn _reset(kvs: Kvs) -> Result<(), ErrorCode> {
-    println!("----------------------");
-    println!("Reset KVS");
+fn _reset(kvs: Kvs, out: &Output) -> Result<(), ErrorCode> {
+    out.line("----------------------");
+    out.line("Reset KVS");
     kvs.reset().map_err(|e| {
-        eprintln!("KVS set failed: {e:?}");
+        eprintln!("KVS set failed: {e}");
         e
     })?;
     kvs.flush()?;
-    println!("----------------------");
-    Ok(())
+    out.line("----------------------");
+    out.result([])
 }
 
 /// Retrieves the snapshot count from the KVS.
-fn _snapshotcount(kvs: Kvs) -> Result<(), ErrorCode> {
-    println!("----------------------");
-    println!("Snapshot Count");
+fn _snapshotcount(kvs: Kvs, out: &Output) -> Result<(), ErrorCode> {
+    out.line("----------------------");
+    out.line("Snapshot Count");
     let count = kvs.snapshot_count();
-    println!("Snapshot Count: {count}");
-    println!("----------------------");
-    Ok(())
+    out.line(format!("Snapshot Count: {count}"));
+    out.line("----------------------");
+    out.result([("snapshot_count", JsonValue::Number(count as f64))])
 }
 
 /// Retrieves the maximum snapshot count from the KVS.
-fn _snapshotmaxcount(_kvs: Kvs) -> Result<(), ErrorCode> {
-    println!("----------------------");
-    println!("Snapshots Max Count");
+fn _snapshotmaxcount(_kvs: Kvs, out: &Output) -> Result<(), ErrorCode> {
+    out.line("----------------------");
+    out.line("Snapshots Max Count");
     let max = Kvs::snapshot_max_count();
-    println!("Snapshots Maximum Count: {max}");
-    println!("----------------------");
-    Ok(())
+    out.line(format!("Snapshots Maximum Count: {max}"));
+    out.line("----------------------");
+    out.result([("snapshot_max_count", JsonValue::Number(max as f64))])
 }
 
 /// Restores a snapshot in the KVS.
 /// It takes a snapshot ID as an argument and restores the KVS to that snapshot.
-fn _snapshotrestore(kvs: Kvs, mut args: Arguments) -> Result<(), ErrorCode> {
-    println!("----------------------");
-    println!("Snapshot Restore");
+fn _snapshotrestore(kvs: Kvs, out: &Output, mut args: Arguments) -> Result<(), ErrorCode> {
+    out.line("----------------------");
+    out.line("Snapshot Restore");
+
+    let snapshot_id: u32 = match args.opt_value_from_str("--snapshotid") {
+        Ok(Some(val)) => val,
+        Ok(None) | Err(_) => match args.opt_value_from_str("-s") {
+            Ok(Some(val)) => val,
+            _ => {
+                eprintln!("Error: Snapshot ID (-s or --snapshotid) needs to be specified!");
+                return Err(ErrorCode::UnmappedError);
+            }
+        },
+    };
+    out.line(format!("Restore Snapshot {}", &snapshot_id));
+    kvs.snapshot_restore(SnapshotId(snapshot_id as usize))
+        .map_err(|e| {
+            eprintln!("KVS restore failed: {e}");
+            e
+        })?;
+    kvs.flush()?;
+    out.line("----------------------");
+    out.result([("snapshot_id", JsonValue::Number(f64::from(snapshot_id)))])
+}
+
+/// Creates a snapshot by rotating the snapshots and flushing the current KVS.
+fn _snapshotcreate(kvs: Kvs, out: &Output) -> Result<(), ErrorCode> {
+    out.line("----------------------");
+    out.line("Snapshot Create");
+    kvs.snapshot_create().map_err(|e| {
+        eprintln!("KVS snapshot create failed: {e}");
+        e
+    })?;
+    let count = kvs.snapshot_count();
+    out.line(format!("Snapshot Count: {count}"));
+    out.line("----------------------");
+    out.result([("snapshot_count", JsonValue::Number(count as f64))])
+}
 
+/// Removes snapshots exceeding the count given by `--keep`.
+fn _snapshotprune(kvs: Kvs, out: &Output, mut args: Arguments) -> Result<(), ErrorCode> {
+    out.line("----------------------");
+    out.line("Snapshot Prune");
+    let keep: usize = match args.opt_value_from_str("--keep") {
+        Ok(Some(val)) => val,
+        _ => {
+            eprintln!("Error: Count of snapshots to keep (--keep) needs to be specified!");
+            return Err(ErrorCode::UnmappedError);
+        }
+    };
+    let removed = kvs.snapshot_prune(keep).map_err(|e| {
+        eprintln!("KVS snapshot prune failed: {e}");
+        e
+    })?;
+    out.line(format!("Removed Snapshots: {removed}"));
+    out.line("----------------------");
+    out.result([("removed", JsonValue::Number(removed as f64))])
+}
+
+/// Exports a snapshot with a new hash file to the path given by `--file`.
+fn _snapshotexport(kvs: Kvs, out: &Output, mut args: Arguments) -> Result<(), ErrorCode> {
+    out.line("----------------------");
+    out.line("Snapshot Export");
     let snapshot_id: u32 = match args.opt_value_from_str("--snapshotid") {
         Ok(Some(val)) => val,
         Ok(None) | Err(_) => match args.opt_value_from_str("-s") {
@@ -322,21 +795,40 @@ fn _snapshotrestore(kvs: Kvs, mut args: Arguments) -> Result<(), ErrorCode> {
             }
         },
     };
-    println!("Restore Snapshot {}", &snapshot_id);
+    let file = file_arg(&mut args)?;
     let snapshot_id = SnapshotId(snapshot_id as usize);
-    kvs.snapshot_restore(snapshot_id).map_err(|e| {
-        eprintln!("KVS restore failed: {e:?}");
+    kvs.export_snapshot(snapshot_id, Path::new(&file))
+        .map_err(|e| {
+            eprintln!("KVS snapshot export failed: {e}");
+            e
+        })?;
+    out.line(format!("Exported Snapshot {snapshot_id} to {file}"));
+    out.line("----------------------");
+    out.result([
+        ("snapshot_id", JsonValue::Number(snapshot_id.0 as f64)),
+        ("file", JsonValue::String(file)),
+    ])
+}
+
+/// Imports an exported snapshot from the path given by `--file` and flushes it as current KVS.
+fn _snapshotimport(kvs: Kvs, out: &Output, mut args: Arguments) -> Result<(), ErrorCode> {
+    out.line("----------------------");
+    out.line("Snapshot Import");
+    let file = file_arg(&mut args)?;
+    kvs.import_snapshot(Path::new(&file)).map_err(|e| {
+        eprintln!("KVS snapshot import failed: {e}");
         e
     })?;
     kvs.flush()?;
-    println!("----------------------");
-    Ok(())
+    out.line(format!("Imported {file}"));
+    out.line("----------------------");
+    out.result([("file", JsonValue::String(file))])
 }
 
 /// Retrieves the KVS filename for a given snapshot ID.
-fn _getkvsfilename(kvs: Kvs, mut args: Arguments) -> Result<(), ErrorCode> {
-    println!("----------------------");
-    println!("Get KVS Filename");
+fn _getkvsfilename(kvs: Kvs, out: &Output, mut args: Arguments) -> Result<(), ErrorCode> {
+    out.line("----------------------");
+    out.line("Get KVS Filename");
     let snapshot_id: u32 = match args.opt_value_from_str("--snapshotid") {
         Ok(Some(val)) => val,
         Ok(None) | Err(_) => match args.opt_value_from_str("-s") {
@@ -349,15 +841,18 @@ fn _getkvsfilename(kvs: Kvs, mut args: Arguments) -> Result<(), ErrorCode> {
     };
     let snapshot_id = SnapshotId(snapshot_id as usize);
     let filename = kvs.get_kvs_filename(snapshot_id)?;
-    println!("KVS Filename: {}", filename.display());
-    println!("----------------------");
-    Ok(())
+    out.line(format!("KVS Filename: {}", filename.display()));
+    out.line("----------------------");
+    out.result([(
+        "kvs_filename",
+        JsonValue::String(filename.display().to_string()),
+    )])
 }
 
 /// Retrieves the hash filename for a given snapshot ID.
-fn _gethashfilename(kvs: Kvs, mut args: Arguments) -> Result<(), ErrorCode> {
-    println!("----------------------");
-    println!("Get Hash Filename");
+fn _gethashfilename(kvs: Kvs, out: &Output, mut args: Arguments) -> Result<(), ErrorCode> {
+    out.line("----------------------");
+    out.line("Get Hash Filename");
 
     let snapshot_id: u32 = match args.opt_value_from_str("--snapshotid") {
         Ok(Some(val)) => val,
@@ -370,31 +865,34 @@ fn _gethashfilename(kvs: Kvs, mut args: Arguments) -> Result<(), ErrorCode> {
         },
     };
     let snapshot_id = SnapshotId(snapshot_id as usize);
-    let filename = kvs.get_hash_filename(snapshot_id);
-    println!("Hash Filename: {}", filename?.display());
-    println!("----------------------");
-    Ok(())
+    let filename = kvs.get_hash_filename(snapshot_id)?;
+    out.line(format!("Hash Filename: {}", filename.display()));
+    out.line("----------------------");
+    out.result([(
+        "hash_filename",
+        JsonValue::String(filename.display().to_string()),
+    )])
 }
 
 /// Creates test data in the KVS based on the example code from the KVS.
-fn _createtestdata(kvs: Kvs) -> Result<(), ErrorCode> {
-    println!("----------------------");
-    println!("Create Test Data");
+fn _createtestdata(kvs: Kvs, out: &Output) -> Result<(), ErrorCode> {
+    out.line("----------------------");
+    out.line("Create Test Data");
 
     kvs.set_value("number", 123.0).map_err(|e| {
-        eprintln!("KVS Create Test Data Error (number): {e:?}");
+        eprintln!("KVS Create Test Data Error (number): {e}");
         e
     })?;
     kvs.set_value("bool", true).map_err(|e| {
-        eprintln!("KVS Create Test Data Error (bool): {e:?}");
+        eprintln!("KVS Create Test Data Error (bool): {e}");
         e
     })?;
     kvs.set_value("string", "First".to_string()).map_err(|e| {
-        eprintln!("KVS Create Test Data Error (string): {e:?}");
+        eprintln!("KVS Create Test Data Error (string): {e}");
         e
     })?;
     kvs.set_value("null", ()).map_err(|e| {
-        eprintln!("KVS Create Test Data Error (null): {e:?}");
+        eprintln!("KVS Create Test Data Error (null): {e}");
         e
     })?;
     kvs.set_value(
@@ -406,7 +904,7 @@ fn _createtestdata(kvs: Kvs) -> Result<(), ErrorCode> {
         ],
     )
     .map_err(|e| {
-        eprintln!("KVS Create Test Data Error (array): {e:?}");
+        eprintln!("KVS Create Test Data Error (array): {e}");
         e
     })?;
     kvs.set_value(
@@ -427,17 +925,407 @@ fn _createtestdata(kvs: Kvs) -> Result<(), ErrorCode> {
         ]),
     )
     .map_err(|e| {
-        eprintln!("KVS Create Test Data Error (object): {e:?}");
+        eprintln!("KVS Create Test Data Error (object): {e}");
         e
     })?;
     kvs.flush()?;
-    println!("Done!");
-    println!("----------------------");
-    Ok(())
+    out.line("Done!");
+    out.line("----------------------");
+    out.result([])
+}
+
+/// Lists all KVS instances found in the directory.
+/// It prints the key count, the snapshot count and the file sizes of every instance.
+/// Instances that can't be opened are listed without key count.
+fn _listinstances(kvs: Kvs, out: &Output) -> Result<(), ErrorCode> {
+    out.line("----------------------");
+    let directory = &kvs.parameters().working_dir;
+    out.line(format!("List Instances in {}", directory.display()));
+    let instances = Kvs::discover_instances(directory).map_err(|e| {
+        eprintln!("KVS instance discovery failed: {e}");
+        e
+    })?;
+
+    if instances.is_empty() {
+        out.line("No instances found!");
+    }
+
+    let mut instances_json = Vec::with_capacity(instances.len());
+    for instance in instances {
+        let key_count = instance_builder(instance.instance_id)
+            .dir(directory.to_string_lossy())
+            .try_build()
+            .map_err(|report| report.error)
+            .and_then(|kvs| kvs.get_all_keys())
+            .map(|keys| keys.len());
+        let keys = match &key_count {
+            Ok(count) => format!("{count} key(s)"),
+            Err(e) => {
+                eprintln!("Opening instance {} failed: {e}", instance.instance_id);
+                "unknown keys".to_string()
+            }
+        };
+        out.line(format!(
+            "Instance {}: {keys}, {} snapshot(s), {} bytes total",
+            instance.instance_id,
+            instance.snapshot_count(),
+            instance.total_size()
+        ));
+        for snapshot in &instance.snapshots {
+            let hash_size = match &snapshot.hash_file {
+                Some(hash_file) => format!("{} bytes", hash_file.size),
+                None => "missing".to_string(),
+            };
+            out.line(format!(
+                "  Snapshot {}: {} ({} bytes), hash: {}",
+                snapshot.snapshot_id,
+                snapshot.kvs_file.path.display(),
+                snapshot.kvs_file.size,
+                hash_size
+            ));
+        }
+        if let Some(defaults_file) = &instance.defaults_file {
+            out.line(format!(
+                "  Defaults: {} ({} bytes)",
+                defaults_file.path.display(),
+                defaults_file.size
+            ));
+        }
+
+        let snapshots = instance
+            .snapshots
+            .iter()
+            .map(|snapshot| {
+                JsonValue::Object(HashMap::from([
+                    (
+                        "snapshot_id".to_string(),
+                        JsonValue::Number(snapshot.snapshot_id.0 as f64),
+                    ),
+                    ("kvs_file".to_string(), file_json(&snapshot.kvs_file)),
+                    (
+                        "hash_file".to_string(),
+                        snapshot
+                            .hash_file
+                            .as_ref()
+                            .map_or(JsonValue::Null, file_json),
+                    ),
+                ]))
+            })
+            .collect();
+        instances_json.push(JsonValue::Object(HashMap::from([
+            (
+                "instance_id".to_string(),
+                JsonValue::Number(instance.instance_id.0 as f64),
+            ),
+            (
+                "key_count".to_string(),
+                key_count.map_or(JsonValue::Null, |count| JsonValue::Number(count as f64)),
+            ),
+            (
+                "total_size".to_string(),
+                JsonValue::Number(instance.total_size() as f64),
+            ),
+            ("snapshots".to_string(), JsonValue::Array(snapshots)),
+            (
+                "defaults_file".to_string(),
+                instance
+                    .defaults_file
+                    .as_ref()
+                    .map_or(JsonValue::Null, file_json),
+            ),
+        ])));
+    }
+
+    out.line("----------------------");
+    out.result([("instances", JsonValue::Array(instances_json))])
+}
+
+/// Exports the KVS to a plain JSON file without type tags.
+/// Without keys all stored keys are exported, given keys are exported including defaults.
+fn _export(kvs: Kvs, out: &Output, mut args: Arguments) -> Result<(), ErrorCode> {
+    out.line("----------------------");
+    out.line("Export");
+    let file = file_arg(&mut args)?;
+    let mut keys: Vec<String> = args.values_from_str(["-k", "--key"]).unwrap_or_default();
+    if keys.is_empty() {
+        keys = kvs.get_all_keys().map_err(|e| {
+            eprintln!("KVS list failed: {e}");
+            e
+        })?;
+    }
+    keys.sort();
+
+    let mut obj = HashMap::new();
+    for key in &keys {
+        let value = kvs.get_value(key).map_err(|e| {
+            eprintln!("KVS get failed for key '{key}': {e}");
+            e
+        })?;
+        out.line(format!("Export Key '{key}'"));
+        obj.insert(key.clone(), to_tinyjson(&value));
+    }
+
+    let json = JsonValue::Object(obj).format().map_err(|e| {
+        eprintln!("Error: JSON generation failed: {e}");
+        ErrorCode::JsonGeneratorError
+    })?;
+    std::fs::write(&file, json).map_err(|e| {
+        eprintln!("Error: Writing {file} failed: {e}");
+        ErrorCode::from(e)
+    })?;
+    out.line(format!("Exported to {file}"));
+    out.line("----------------------");
+    out.result([
+        ("file", JsonValue::String(file)),
+        ("keys", strings_json(&keys)),
+    ])
+}
+
+/// Imports keys from a plain JSON file without type tags.
+/// Values are validated against the types of the stored or default values before any key is
+/// written. Given keys restrict the import to these keys.
+fn _import(kvs: Kvs, out: &Output, mut args: Arguments) -> Result<(), ErrorCode> {
+    out.line("----------------------");
+    out.line("Import");
+    let file = file_arg(&mut args)?;
+    let keys: Vec<String> = args.values_from_str(["-k", "--key"]).unwrap_or_default();
+
+    let mut obj = read_object(&file)?;
+    if !keys.is_empty() {
+        if let Some(key) = keys.iter().find(|key| !obj.contains_key(*key)) {
+            eprintln!("Error: Key '{key}' not found in {file}!");
+            return Err(ErrorCode::KeyNotFound);
+        }
+        obj.retain(|key, _| keys.contains(key));
+    }
+
+    let mut values = Vec::new();
+    for (key, value) in &obj {
+        let reference = kvs.get_value(key).ok();
+        values.push((
+            key.clone(),
+            from_tinyjson_typed(value, reference.as_ref(), key)?,
+        ));
+    }
+    values.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut imported = Vec::with_capacity(values.len());
+    for (key, value) in values {
+        out.line(format!(
+            "Import Key '{key}': {:?}",
+            kvs.redacted(&key, &value)
+        ));
+        kvs.set_value(&key, value).map_err(|e| {
+            eprintln!("KVS set failed: {e}");
+            e
+        })?;
+        imported.push(key);
+    }
+    kvs.flush()?;
+    out.line("----------------------");
+    out.result([
+        ("file", JsonValue::String(file)),
+        ("keys", strings_json(&imported)),
+    ])
+}
+
+/// Sets all top-level entries of a plain JSON file as keys, converted like `setkey` payloads.
+/// With `--dry-run` the changes are only printed.
+fn _setbulk(kvs: Kvs, out: &Output, mut args: Arguments) -> Result<(), ErrorCode> {
+    out.line("----------------------");
+    out.line("Set Bulk");
+    let dry_run = args.contains("--dry-run");
+    let file = file_arg(&mut args)?;
+    let obj = read_object(&file)?;
+
+    let mut values: Vec<(String, KvsValue)> = obj
+        .iter()
+        .map(|(key, value)| (key.clone(), from_tinyjson(value)))
+        .collect();
+    values.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut changed = Vec::new();
+    for (key, value) in &values {
+        let old_value = if kvs.key_exists(key)? {
+            Some(kvs.get_value(key)?)
+        } else {
+            None
+        };
+        match old_value {
+            Some(old_value) if old_value == *value => continue,
+            Some(old_value) => out.line(format!(
+                "~ {key}: {:?} -> {:?}",
+                kvs.redacted(key, &old_value),
+                kvs.redacted(key, value)
+            )),
+            None => out.line(format!("+ {key}: {:?}", kvs.redacted(key, value))),
+        }
+        changed.push(key.clone());
+    }
+    out.line(format!(
+        "{} of {} keys changed",
+        changed.len(),
+        values.len()
+    ));
+
+    if !dry_run && !changed.is_empty() {
+        kvs.set_values(values).map_err(|e| {
+            eprintln!("KVS set failed: {e}");
+            e
+        })?;
+        kvs.flush()?;
+    }
+    out.line("----------------------");
+    out.result([
+        ("file", JsonValue::String(file)),
+        ("changed", strings_json(&changed)),
+        ("dry_run", JsonValue::Boolean(dry_run)),
+    ])
+}
+
+/// Reads a file containing a plain JSON object.
+fn read_object(file: &str) -> Result<HashMap<String, JsonValue>, ErrorCode> {
+    let content = std::fs::read_to_string(file).map_err(|e| {
+        eprintln!("Error: Reading {file} failed: {e}");
+        ErrorCode::from(e)
+    })?;
+    match content.parse::<JsonValue>() {
+        Ok(JsonValue::Object(obj)) => Ok(obj),
+        Ok(_) => {
+            eprintln!("Error: {file} doesn't contain a JSON object!");
+            Err(ErrorCode::JsonParserError)
+        }
+        Err(e) => {
+            eprintln!("Error: Parsing {file} failed: {e}");
+            Err(ErrorCode::JsonParserError)
+        }
+    }
+}
+
+/// Reads all stored key-value pairs of the KVS.
+fn stored_values(kvs: &Kvs) -> Result<HashMap<String, KvsValue>, ErrorCode> {
+    let mut values = HashMap::new();
+    for key in kvs.get_all_keys()? {
+        let value = kvs.get_value(&key)?;
+        values.insert(key, value);
+    }
+    Ok(values)
+}
+
+/// Reads all key-value pairs of a snapshot, snapshot 0 being the current KVS.
+fn snapshot_values(kvs: &Kvs, snapshot_id: usize) -> Result<HashMap<String, KvsValue>, ErrorCode> {
+    if snapshot_id == 0 {
+        return stored_values(kvs);
+    }
+    kvs.snapshot_preview(SnapshotId(snapshot_id)).map_err(|e| {
+        eprintln!("KVS load of snapshot {snapshot_id} failed: {e}");
+        e
+    })
+}
+
+/// Prints the keys added, removed and changed from one snapshot to another.
+/// With a single snapshot ID the snapshot is compared to the current KVS.
+fn _diff(kvs: Kvs, out: &Output, mut args: Arguments) -> Result<(), ErrorCode> {
+    out.line("----------------------");
+    out.line("Snapshot Diff");
+    let snapshot_ids: Vec<usize> = args
+        .values_from_str(["-s", "--snapshotid"])
+        .unwrap_or_default();
+    let (old_id, new_id) = match snapshot_ids[..] {
+        [old_id] => (old_id, 0),
+        [old_id, new_id] => (old_id, new_id),
+        _ => {
+            eprintln!("Error: One or two Snapshot IDs (-s or --snapshotid) need to be specified!");
+            return Err(ErrorCode::UnmappedError);
+        }
+    };
+
+    let old = snapshot_values(&kvs, old_id)?;
+    let new = snapshot_values(&kvs, new_id)?;
+    out.line(format!(
+        "Snapshot {old_id} -> Snapshot {new_id} (0 is current)"
+    ));
+
+    let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    let (mut added, mut removed, mut changed) = (HashMap::new(), HashMap::new(), HashMap::new());
+    for key in keys {
+        match (old.get(key), new.get(key)) {
+            (None, Some(value)) => {
+                added.insert(key.clone(), to_tinyjson_redacted(&kvs, key, value));
+                out.line(format!("+ {key}: {:?}", kvs.redacted(key, value)));
+            }
+            (Some(value), None) => {
+                removed.insert(key.clone(), to_tinyjson_redacted(&kvs, key, value));
+                out.line(format!("- {key}: {:?}", kvs.redacted(key, value)));
+            }
+            (Some(old_value), Some(new_value)) if old_value != new_value => {
+                let change = HashMap::from([
+                    (
+                        "old".to_string(),
+                        to_tinyjson_redacted(&kvs, key, old_value),
+                    ),
+                    (
+                        "new".to_string(),
+                        to_tinyjson_redacted(&kvs, key, new_value),
+                    ),
+                ]);
+                changed.insert(key.clone(), JsonValue::Object(change));
+                out.line(format!(
+                    "~ {key}: {:?} -> {:?}",
+                    kvs.redacted(key, old_value),
+                    kvs.redacted(key, new_value)
+                ));
+            }
+            _ => {}
+        }
+    }
+    out.line(format!(
+        "{} added, {} removed, {} changed",
+        added.len(),
+        removed.len(),
+        changed.len()
+    ));
+    out.line("----------------------");
+    out.result([
+        ("old_snapshot_id", JsonValue::Number(old_id as f64)),
+        ("new_snapshot_id", JsonValue::Number(new_id as f64)),
+        ("added", JsonValue::Object(added)),
+        ("removed", JsonValue::Object(removed)),
+        ("changed", JsonValue::Object(changed)),
+    ])
+}
+
+/// Rewrites stored snapshots without type tags in the tagged format with a new hash file.
+fn _migrate(kvs: Kvs, out: &Output) -> Result<(), ErrorCode> {
+    out.line("----------------------");
+    out.line("Migrate");
+    let mut migrated = Vec::new();
+    for snapshot_id in 0..=Kvs::snapshot_max_count() {
+        let kvs_path = match kvs.get_kvs_filename(SnapshotId(snapshot_id)) {
+            Ok(kvs_path) => kvs_path,
+            Err(ErrorCode::FileNotFound) => continue,
+            Err(e) => return Err(e),
+        };
+        let hash_path = kvs_path.with_extension("hash");
+        let changed = JsonBackend::migrate_untagged(&kvs_path, &hash_path).map_err(|e| {
+            eprintln!("KVS migrate of {} failed: {e}", kvs_path.display());
+            e
+        })?;
+        if changed {
+            out.line(format!("Migrated: {}", kvs_path.display()));
+            migrated.push(kvs_path.display().to_string());
+        } else {
+            out.line(format!("Already tagged: {}", kvs_path.display()));
+        }
+    }
+    out.line("----------------------");
+    out.result([("migrated", strings_json(&migrated))])
 }
 
 /// Main function to run the KVS tool command line interface.
-fn main() -> Result<(), ErrorCode> {
+fn run() -> Result<(), ErrorCode> {
     let mut args = Arguments::from_env();
 
     if args.contains(["-h", "--help"]) {
@@ -454,12 +1342,29 @@ fn main() -> Result<(), ErrorCode> {
         Options:
         -h, --help          Show this help message and exit
         -o, --operation     Specify the operation to perform (setkey, getkey, removekey, 
-                            listkeys, reset, snapshotcount, snapshotmaxcount, snapshotrestore, 
-                            getkvsfilename, gethashfilename, createtestdata)
-        -k, --key           Specify the key to operate on (for key operations)
+                            resetkey, listkeys, reset, snapshotcount, snapshotmaxcount, snapshotrestore, 
+                            snapshotcreate, snapshotprune, snapshotexport, snapshotimport,
+                            getkvsfilename, gethashfilename, createtestdata, listinstances,
+                            export, import, setbulk, diff, migrate)
+        -k, --key           Specify the key to operate on (for key operations,
+                            repeatable for export/import)
         -p, --payload       Specify the value to write (for set operations)
         -s, --snapshotid    Specify the snapshot ID for Snapshot operations
-        -d, --directory     Specify the directory of the Key-Files (default is current directory)
+                            (repeatable for diff)
+            --keep          Specify the count of snapshots to keep besides the current KVS
+                            (for snapshotprune)
+        -f, --file          Specify the JSON file for export/import/setbulk and
+                            snapshotexport/snapshotimport operations
+            --dry-run       Only print the changes (for setbulk)
+            --raw           Print only the value, strings without quotes (for getkey)
+            --expect-type   Fail with TypeMismatch if the value has another type (for getkey:
+                            i32, u32, i64, u64, f64, bool, str, null, arr, obj)
+        -d, --directory     Specify the directory of the Key-Files (default is $KVS_DATA_DIR, the
+                            platform data directory or the current directory)
+        -i, --instance      Specify the instance ID to operate on (default is 0)
+            --sensitive     Specify a key whose value is printed as <redacted> (repeatable)
+            --no-lock       Don't take the directory lock held by flushing applications
+            --json          Print the result as a single JSON object line
 
         ---------------------------------------
 
@@ -467,6 +1372,9 @@ fn main() -> Result<(), ErrorCode> {
 
         Read a Key and show value:
             kvs_tool -o getkey -k MyKey
+            kvs_tool -o getkey -k MyKey -i 2
+            kvs_tool -o getkey -k WifiPsk --sensitive WifiPsk
+            VALUE=$(kvs_tool -o getkey -k MyKey --raw --expect-type str)
 
         Write a Key and use the <payload> as the data source:
             (automatically detects following types: Number, Boolean, String, Null, Object, Array)
@@ -479,8 +1387,12 @@ fn main() -> Result<(), ErrorCode> {
         Delete a key:
             kvs_tool -o removekey -k MyKey
 
+        Reset a key to its default value (keys without a default value are removed):
+            kvs_tool -o resetkey -k MyKey
+
         List Keys:
             kvs_tool -o listkeys
+            kvs_tool -o listkeys --json
 
         Reset KVS:
             kvs_tool -o reset
@@ -491,12 +1403,45 @@ fn main() -> Result<(), ErrorCode> {
         Snapshot Restore:
             kvs_tool -o snapshotrestore -s 1
 
+        Snapshot Create (rotates snapshots and flushes the current KVS, also if unchanged):
+            kvs_tool -o snapshotcreate
+
+        Snapshot Prune (removes all snapshots except the current KVS and the given count):
+            kvs_tool -o snapshotprune --keep 1
+
+        Snapshot Export (copies a validated snapshot and a new hash file, here backup.hash):
+            kvs_tool -o snapshotexport -s 1 -f backup.json
+
+        Snapshot Import (validates the file against its hash and writes it as current KVS):
+            kvs_tool -o snapshotimport -f backup.json
+
         Get KVS Filename:
             kvs_tool -o getkvsfilename -s 1
 
         Get Hash Filename:
             kvs_tool -o gethashfilename -s 1
 
+        List Instances with key counts, snapshot counts and file sizes:
+            kvs_tool -o listinstances -d /path/to/kvs
+
+        Export all stored Keys (or only the given Keys) to a plain JSON file:
+            kvs_tool -o export -f golden.json
+            kvs_tool -o export -f golden.json -k MyKey -k OtherKey
+
+        Import Keys from a plain JSON file (types are validated against existing values):
+            kvs_tool -o import -f golden.json
+
+        Set all Keys of a plain JSON file at once (converted like setkey payloads):
+            kvs_tool -o setbulk -f values.json
+            kvs_tool -o setbulk -f values.json --dry-run
+
+        Show added/removed/changed Keys from a snapshot to the current KVS or to another snapshot:
+            kvs_tool -o diff -s 1
+            kvs_tool -o diff -s 2 -s 1
+
+        Rewrite legacy files without type tags in the tagged format with a new hash file:
+            kvs_tool -o migrate -i 2
+
         ---------------------------------------
 
         Create Test Data:
@@ -515,22 +1460,27 @@ fn main() -> Result<(), ErrorCode> {
             _ => None,
         },
     };
+    let directory = match directory {
+        Some(directory) => Some(directory),
+        None => resolve_directory()?,
+    };
 
-    let builder = KvsBuilder::new(InstanceId(0))
-        .defaults(KvsDefaults::Optional)
-        .kvs_load(KvsLoad::Optional);
+    let out = Output {
+        json: args.contains("--json"),
+    };
 
-    let builder = if let Some(dir) = directory {
-        builder.dir(dir)
+    // Lock is released after the instance is dropped.
+    let _dir_lock = if args.contains("--no-lock") {
+        None
     } else {
-        builder
+        Some(lock_directory(directory.as_deref().unwrap_or_default())?)
     };
 
-    let kvs = match builder.build() {
-        Ok(kvs) => kvs,
+    let instance_id: usize = match args.opt_value_from_str(["-i", "--instance"]) {
+        Ok(val) => val.unwrap_or_default(),
         Err(e) => {
-            eprintln!("Error opening KVS: {e:?}");
-            return Err(e);
+            eprintln!("Error: Invalid instance ID (-i or --instance): {e}");
+            return Err(ErrorCode::InvalidInstanceId);
         }
     };
 
@@ -551,69 +1501,200 @@ fn main() -> Result<(), ErrorCode> {
             "getkey" => OperationMode::GetKey,
             "setkey" => OperationMode::SetKey,
             "removekey" => OperationMode::RemoveKey,
+            "resetkey" => OperationMode::ResetKey,
             "listkeys" => OperationMode::ListKeys,
             "reset" => OperationMode::Reset,
             "createtestdata" => OperationMode::CreateTestData,
             "snapshotcount" => OperationMode::SnapshotCount,
             "snapshotmaxcount" => OperationMode::SnapshotMaxCount,
             "snapshotrestore" => OperationMode::SnapshotRestore,
+            "snapshotcreate" => OperationMode::SnapshotCreate,
+            "snapshotprune" => OperationMode::SnapshotPrune,
+            "snapshotexport" => OperationMode::SnapshotExport,
+            "snapshotimport" => OperationMode::SnapshotImport,
             "getkvsfilename" => OperationMode::GetKvsFilename,
             "gethashfilename" => OperationMode::GetHashFilename,
+            "listinstances" => OperationMode::ListInstances,
+            "export" => OperationMode::Export,
+            "import" => OperationMode::Import,
+            "setbulk" => OperationMode::SetBulk,
+            "diff" => OperationMode::Diff,
+            "migrate" => OperationMode::Migrate,
             _ => OperationMode::Invalid,
         },
         None => OperationMode::Invalid,
     };
 
+    let sensitive_keys: Vec<String> = args.values_from_str("--sensitive").unwrap_or_default();
+
+    let builder = instance_builder(InstanceId(instance_id)).sensitive_keys(sensitive_keys);
+    // Legacy files may lack a hash file, they are migrated without loading the instance.
+    let builder = if matches!(op_mode, OperationMode::Migrate) {
+        builder.kvs_load(KvsLoad::Ignored)
+    } else {
+        builder
+    };
+    let builder = if let Some(dir) = directory {
+        builder.dir(dir)
+    } else {
+        builder
+    };
+
+    let kvs = match builder.try_build() {
+        Ok(kvs) => kvs,
+        Err(report) => {
+            eprintln!("Error {report}");
+            return Err(report.error);
+        }
+    };
+
     match op_mode {
         OperationMode::GetKey => {
-            _getkey(kvs, args)?;
+            _getkey(kvs, &out, args)?;
             Ok(())
         }
         OperationMode::SetKey => {
-            _setkey(kvs, args)?;
+            _setkey(kvs, &out, args)?;
             Ok(())
         }
         OperationMode::RemoveKey => {
-            _removekey(kvs, args)?;
+            _removekey(kvs, &out, args)?;
+            Ok(())
+        }
+        OperationMode::ResetKey => {
+            _resetkey(kvs, &out, args)?;
             Ok(())
         }
         OperationMode::ListKeys => {
-            _listkeys(kvs)?;
+            _listkeys(kvs, &out)?;
             Ok(())
         }
         OperationMode::Reset => {
-            _reset(kvs)?;
+            _reset(kvs, &out)?;
             Ok(())
         }
         OperationMode::SnapshotCount => {
-            _snapshotcount(kvs)?;
+            _snapshotcount(kvs, &out)?;
             Ok(())
         }
         OperationMode::SnapshotMaxCount => {
-            _snapshotmaxcount(kvs)?;
+            _snapshotmaxcount(kvs, &out)?;
             Ok(())
         }
         OperationMode::SnapshotRestore => {
-            _snapshotrestore(kvs, args)?;
+            _snapshotrestore(kvs, &out, args)?;
+            Ok(())
+        }
+        OperationMode::SnapshotCreate => {
+            _snapshotcreate(kvs, &out)?;
+            Ok(())
+        }
+        OperationMode::SnapshotPrune => {
+            _snapshotprune(kvs, &out, args)?;
+            Ok(())
+        }
+        OperationMode::SnapshotExport => {
+            _snapshotexport(kvs, &out, args)?;
+            Ok(())
+        }
+        OperationMode::SnapshotImport => {
+            _snapshotimport(kvs, &out, args)?;
             Ok(())
         }
         OperationMode::GetKvsFilename => {
-            _getkvsfilename(kvs, args)?;
+            _getkvsfilename(kvs, &out, args)?;
             Ok(())
         }
         OperationMode::GetHashFilename => {
-            _gethashfilename(kvs, args)?;
+            _gethashfilename(kvs, &out, args)?;
             Ok(())
         }
         OperationMode::CreateTestData => {
-            _createtestdata(kvs)?;
+            _createtestdata(kvs, &out)?;
+            Ok(())
+        }
+        OperationMode::ListInstances => {
+            _listinstances(kvs, &out)?;
+            Ok(())
+        }
+        OperationMode::Export => {
+            _export(kvs, &out, args)?;
+            Ok(())
+        }
+        OperationMode::Import => {
+            _import(kvs, &out, args)?;
+            Ok(())
+        }
+        OperationMode::SetBulk => {
+            _setbulk(kvs, &out, args)?;
+            Ok(())
+        }
+        OperationMode::Diff => {
+            _diff(kvs, &out, args)?;
+            Ok(())
+        }
+        OperationMode::Migrate => {
+            _migrate(kvs, &out)?;
             Ok(())
         }
         OperationMode::Invalid => {
-            println!("----------------------");
+            out.line("----------------------");
             eprintln!("Invalid operation specified. Use -o or --operation to specify a valid operation. (See -h or --help for more information)");
-            println!("----------------------");
+            out.line("----------------------");
             Err(ErrorCode::UnmappedError)
         }
     }
 }
+
+/// Creates the builder used to open instances.
+/// Operations flush explicitly, in-memory changes like restored snapshots are discarded.
+fn instance_builder(instance_id: InstanceId) -> KvsBuilder {
+    KvsBuilder::new(instance_id)
+        .defaults(KvsDefaults::Optional)
+        .kvs_load(KvsLoad::Optional)
+        .flush_on_exit(FlushOnExit::No)
+}
+
+/// Resolves the directory from `KVS_DATA_DIR` or the platform data directory, creating it.
+fn resolve_directory() -> Result<Option<String>, ErrorCode> {
+    let Some(directory) = KvsDirStrategy::Environment.resolve() else {
+        return Ok(None);
+    };
+    std::fs::create_dir_all(&directory).map_err(|e| {
+        eprintln!(
+            "Error: Creating directory {} failed: {e}",
+            directory.display()
+        );
+        ErrorCode::from(e)
+    })?;
+    Ok(Some(directory.to_string_lossy().into_owned()))
+}
+
+/// Takes the directory lock, waiting if it's held by another process.
+fn lock_directory(directory: &str) -> Result<KvsDirLock, ErrorCode> {
+    let working_dir = Path::new(directory);
+    let lock = match KvsDirLock::try_acquire(working_dir) {
+        Ok(Some(lock)) => Ok(lock),
+        Ok(None) => {
+            eprintln!("Waiting for lock of directory (use --no-lock to skip)...");
+            KvsDirLock::acquire(working_dir)
+        }
+        Err(e) => Err(e),
+    };
+    lock.map_err(|e| {
+        eprintln!("Error: Locking directory failed: {e}");
+        e
+    })
+}
+
+/// Exit code is the numeric error code, see `ErrorCode::code`.
+fn main() -> ExitCode {
+    set_log_sink(Arc::new(StderrLogSink));
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            ExitCode::from(u8::try_from(e.code()).unwrap_or(u8::MAX))
+        }
+    }
+}
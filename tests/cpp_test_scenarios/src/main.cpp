@@ -7,6 +7,7 @@
 #include "scenario.hpp"
 #include "test_basic.hpp"
 #include "test_context.hpp"
+#include "test_interop.hpp"
 
 int main(int argc, char** argv) {
     try {
@@ -16,8 +17,15 @@ int main(int argc, char** argv) {
         Scenario::Ptr basic_scenario{new BasicScenario{}};
         ScenarioGroup::Ptr basic_group{new ScenarioGroupImpl{"basic", {basic_scenario}, {}}};
 
+        // CIT group.
+        Scenario::Ptr interop_write_scenario{new InteropWriteScenario{}};
+        Scenario::Ptr interop_read_scenario{new InteropReadScenario{}};
+        ScenarioGroup::Ptr interop_group{
+            new ScenarioGroupImpl{"interop", {interop_write_scenario, interop_read_scenario}, {}}};
+        ScenarioGroup::Ptr cit_group{new ScenarioGroupImpl{"cit", {}, {interop_group}}};
+
         // Root group.
-        ScenarioGroup::Ptr root_group{new ScenarioGroupImpl{"root", {}, {basic_group}}};
+        ScenarioGroup::Ptr root_group{new ScenarioGroupImpl{"root", {}, {basic_group, cit_group}}};
 
         // Run.
         TestContext test_context{root_group};
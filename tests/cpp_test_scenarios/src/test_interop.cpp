@@ -0,0 +1,120 @@
+#include "test_interop.hpp"
+
+#include <iostream>
+#include <kvs.hpp>
+#include <kvsbuilder.hpp>
+#include <utility>
+#include <vector>
+
+#include "score/json/json_parser.h"
+#include "score/result/result.h"
+#include "tracing.hpp"
+
+namespace {
+
+using score::mw::per::kvs::InstanceId;
+using score::mw::per::kvs::Kvs;
+using score::mw::per::kvs::KvsBuilder;
+using score::mw::per::kvs::KvsValue;
+
+struct KvsParameters {
+    uint64_t instance_id;
+    std::optional<bool> need_kvs;
+    std::optional<std::string> dir;
+};
+
+KvsParameters map_to_params(const std::string& data) {
+    using namespace score::json;
+
+    JsonParser parser;
+    auto any_res{parser.FromBuffer(data)};
+    if (!any_res) {
+        throw std::runtime_error{"Failed to parse JSON data"};
+    }
+    const auto& map_root{any_res.value().As<Object>().value().get().at("kvs_parameters")};
+    const auto& obj_root{map_root.As<Object>().value().get()};
+
+    KvsParameters params;
+    params.instance_id = obj_root.at("instance_id").As<double>().value();
+    if (obj_root.find("need_kvs") != obj_root.end()) {
+        params.need_kvs = obj_root.at("need_kvs").As<bool>().value();
+    }
+    if (obj_root.find("dir") != obj_root.end()) {
+        params.dir = obj_root.at("dir").As<std::string>().value();
+    }
+
+    return params;
+}
+
+Kvs create_kvs(const KvsParameters& params) {
+    KvsBuilder builder{InstanceId{params.instance_id}};
+    if (params.need_kvs.has_value()) {
+        builder = builder.need_kvs_flag(*params.need_kvs);
+    }
+    if (params.dir.has_value()) {
+        builder = builder.dir(std::string{*params.dir});
+    }
+
+    auto build_result{builder.build()};
+    if (!build_result) {
+        throw std::runtime_error{"Failed to create KVS instance"};
+    }
+    return std::move(*build_result);
+}
+
+// Values shared with Rust test scenarios.
+// 64-bit values are limited to range exactly representable by `double`.
+std::vector<std::pair<std::string, KvsValue>> interop_values() {
+    std::vector<std::pair<std::string, KvsValue>> values;
+    values.emplace_back("i32", KvsValue{int32_t{-321}});
+    values.emplace_back("u32", KvsValue{uint32_t{1234}});
+    values.emplace_back("i64", KvsValue{-(int64_t{1} << 40)});
+    values.emplace_back("u64", KvsValue{uint64_t{1} << 40});
+    values.emplace_back("f64", KvsValue{123.456});
+    values.emplace_back("bool", KvsValue{true});
+    values.emplace_back("str", KvsValue{"example"});
+    values.emplace_back("null", KvsValue{nullptr});
+    return values;
+}
+
+const std::string kTargetName{"cpp_test_scenarios::cit::interop"};
+
+}  // namespace
+
+std::string InteropWriteScenario::name() const { return "write"; }
+
+void InteropWriteScenario::run(const std::optional<std::string>& input) const {
+    auto params{map_to_params(*input)};
+    Kvs kvs{create_kvs(params)};
+
+    for (const auto& [key, value] : interop_values()) {
+        if (!kvs.set_value(key, value)) {
+            throw std::runtime_error{"Failed to set value: " + key};
+        }
+    }
+    if (!kvs.flush()) {
+        throw std::runtime_error{"Failed to flush"};
+    }
+
+    TRACING_INFO(kTargetName, std::pair{std::string{"flushed"}, true});
+}
+
+std::string InteropReadScenario::name() const { return "read"; }
+
+void InteropReadScenario::run(const std::optional<std::string>& input) const {
+    auto params{map_to_params(*input)};
+    Kvs kvs{create_kvs(params)};
+
+    for (const auto& [key, expected] : interop_values()) {
+        auto get_value_result{kvs.get_value(key)};
+        if (!get_value_result) {
+            throw std::runtime_error{"Failed to get value: " + key};
+        }
+        const auto& value{get_value_result.value()};
+        if (value.getType() != expected.getType() || value.getValue() != expected.getValue()) {
+            throw std::runtime_error{"Value mismatch: " + key};
+        }
+
+        TRACING_INFO(kTargetName, std::pair{std::string{"key"}, key});
+    }
+}
@@ -0,0 +1,24 @@
+#pragma once
+
+#include <optional>
+#include <string>
+
+#include "scenario.hpp"
+
+class InteropWriteScenario final : public Scenario {
+   public:
+    ~InteropWriteScenario() final = default;
+
+    std::string name() const final;
+
+    void run(const std::optional<std::string>& input) const final;
+};
+
+class InteropReadScenario final : public Scenario {
+   public:
+    ~InteropReadScenario() final = default;
+
+    std::string name() const final;
+
+    void run(const std::optional<std::string>& input) const final;
+};
@@ -0,0 +1,132 @@
+import json
+import subprocess
+import zlib
+from pathlib import Path
+from typing import Any
+
+import pytest
+from testing_utils import LogContainer, ScenarioResult
+
+from .common import CommonScenario, ResultCode
+
+# Store is read by "version", the other implementation writes it.
+pytestmark = pytest.mark.parametrize("version", ["rust", "cpp"], scope="class")
+
+INSTANCE_ID = 3
+KEYS = ["i32", "u32", "i64", "u64", "f64", "bool", "str", "null"]
+
+
+def other_version(version: str) -> str:
+    return "cpp" if version == "rust" else "rust"
+
+
+def kvs_parameters(version: str, temp_dir: Path, need_kvs: bool) -> dict[str, Any]:
+    """
+    Create KVS parameters in the format expected by test scenarios of given version.
+    """
+    params: dict[str, Any] = {"instance_id": INSTANCE_ID, "dir": str(temp_dir)}
+    if need_kvs:
+        if version == "rust":
+            params["kvs_load"] = "required"
+        else:
+            params["need_kvs"] = True
+    return {"kvs_parameters": params}
+
+
+def write_by_other(
+    request: pytest.FixtureRequest, version: str, temp_dir: Path
+) -> Path:
+    """
+    Write store using the other implementation's test scenario executable.
+    """
+    writer_version = other_version(version)
+    writer_path = request.config.getoption(f"--{writer_version}-target-path")
+    if writer_path is None:
+        pytest.skip(f"--{writer_version}-target-path not provided")
+
+    writer_config = kvs_parameters(writer_version, temp_dir, need_kvs=False)
+    command = [
+        str(writer_path),
+        "--name",
+        "cit.interop.write",
+        "--input",
+        json.dumps(writer_config),
+    ]
+    timeout = request.config.getoption("--default-execution-timeout")
+    result = subprocess.run(command, capture_output=True, timeout=timeout)
+    assert result.returncode == ResultCode.SUCCESS, result.stderr.decode()
+    return temp_dir
+
+
+@pytest.mark.PartiallyVerifies([])
+@pytest.mark.FullyVerifies([])
+@pytest.mark.Description(
+    "Verifies that a store written by one implementation (C++ or Rust) is read with identical values and a valid hash by the other."
+)
+@pytest.mark.TestType("requirements-based")
+@pytest.mark.DerivationTechnique("requirements-based")
+class TestInteropReadWrittenByOther(CommonScenario):
+    @pytest.fixture(scope="class")
+    def scenario_name(self) -> str:
+        return "cit.interop.read"
+
+    @pytest.fixture(scope="class")
+    def written_store(
+        self, request: pytest.FixtureRequest, version: str, temp_dir: Path
+    ) -> Path:
+        return write_by_other(request, version, temp_dir)
+
+    @pytest.fixture(scope="class")
+    def test_config(self, version: str, written_store: Path) -> dict[str, Any]:
+        return kvs_parameters(version, written_store, need_kvs=True)
+
+    def test_values_read(self, results: ScenarioResult, logs_info_level: LogContainer):
+        assert results.return_code == ResultCode.SUCCESS
+
+        for key in KEYS:
+            assert logs_info_level.find_log("key", value=key) is not None
+
+    def test_hash_compatible(self, written_store: Path):
+        kvs_path = written_store / f"kvs_{INSTANCE_ID}_0.json"
+        hash_path = written_store / f"kvs_{INSTANCE_ID}_0.hash"
+
+        # Hash file contains Adler-32 of KVS file as big-endian 32-bit value.
+        expected_hash = zlib.adler32(kvs_path.read_bytes()).to_bytes(4, "big")
+        assert hash_path.read_bytes() == expected_hash
+
+
+@pytest.mark.PartiallyVerifies([])
+@pytest.mark.FullyVerifies([])
+@pytest.mark.Description(
+    "Verifies that a store written by one implementation (C++ or Rust) is rejected by the other when its hash doesn't match."
+)
+@pytest.mark.TestType("requirements-based")
+@pytest.mark.DerivationTechnique("requirements-based")
+class TestInteropHashCheckedByOther(CommonScenario):
+    @pytest.fixture(scope="class")
+    def scenario_name(self) -> str:
+        return "cit.interop.read"
+
+    @pytest.fixture(scope="class")
+    def written_store(
+        self, request: pytest.FixtureRequest, version: str, temp_dir: Path
+    ) -> Path:
+        """
+        Write store using the other implementation and invert its hash.
+        """
+        store = write_by_other(request, version, temp_dir)
+        hash_path = store / f"kvs_{INSTANCE_ID}_0.hash"
+        hash_path.write_bytes(bytes(b ^ 0xFF for b in hash_path.read_bytes()))
+        return store
+
+    @pytest.fixture(scope="class")
+    def test_config(self, version: str, written_store: Path) -> dict[str, Any]:
+        return kvs_parameters(version, written_store, need_kvs=True)
+
+    def test_hash_rejected(
+        self, results: ScenarioResult, logs_info_level: LogContainer
+    ):
+        assert results.return_code != ResultCode.SUCCESS
+
+        for key in KEYS:
+            assert logs_info_level.find_log("key", value=key) is None
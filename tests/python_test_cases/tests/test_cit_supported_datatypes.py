@@ -106,7 +106,8 @@ class TestSupportedDatatypesValues_I64(TestSupportedDatatypesValues):
         return "i64"
 
     def exp_value(self) -> Any:
-        return -123456789
+        # 64-bit integers are stored as strings to preserve precision.
+        return "-123456789"
 
 
 class TestSupportedDatatypesValues_U64(TestSupportedDatatypesValues):
@@ -114,7 +115,7 @@ class TestSupportedDatatypesValues_U64(TestSupportedDatatypesValues):
         return "u64"
 
     def exp_value(self) -> Any:
-        return 123456789
+        return "123456789"
 
 
 class TestSupportedDatatypesValues_F64(TestSupportedDatatypesValues):
@@ -0,0 +1,89 @@
+//! C++/Rust interoperability scenarios.
+//!
+//! Same scenarios are implemented by C++ test scenarios.
+//! Store written by one implementation must be readable by the other.
+
+use crate::helpers::kvs_instance::kvs_instance;
+use crate::helpers::kvs_parameters::KvsParameters;
+use crate::helpers::to_str;
+use rust_kvs::prelude::*;
+use test_scenarios_rust::scenario::{Scenario, ScenarioGroup, ScenarioGroupImpl};
+use tracing::info;
+
+/// Values shared with C++ test scenarios.
+/// 64-bit values are limited to range exactly representable by `f64`.
+fn interop_values() -> Vec<(&'static str, KvsValue)> {
+    vec![
+        ("i32", KvsValue::I32(-321)),
+        ("u32", KvsValue::U32(1234)),
+        ("i64", KvsValue::I64(-(1 << 40))),
+        ("u64", KvsValue::U64(1 << 40)),
+        ("f64", KvsValue::F64(123.456)),
+        ("bool", KvsValue::Boolean(true)),
+        ("str", KvsValue::String("example".to_string())),
+        ("null", KvsValue::Null),
+    ]
+}
+
+/// Write values and flush.
+struct Write;
+
+impl Scenario for Write {
+    fn name(&self) -> &str {
+        "write"
+    }
+
+    fn run(&self, input: Option<String>) -> Result<(), String> {
+        let input_string = input.as_ref().expect("Test input is expected");
+        let params = KvsParameters::from_json(input_string).expect("Failed to parse parameters");
+        let kvs = kvs_instance(params).expect("Failed to create KVS instance");
+
+        for (key, value) in interop_values() {
+            kvs.set_value(key, value).expect("Failed to set value");
+        }
+        kvs.flush().expect("Failed to flush");
+
+        let snapshot_id = SnapshotId(0);
+        let kvs_path_result = kvs.get_kvs_filename(snapshot_id);
+        let hash_path_result = kvs.get_hash_filename(snapshot_id);
+        info!(
+            kvs_path = format!("{kvs_path_result:?}"),
+            hash_path = format!("{hash_path_result:?}")
+        );
+
+        Ok(())
+    }
+}
+
+/// Read and check values.
+struct Read;
+
+impl Scenario for Read {
+    fn name(&self) -> &str {
+        "read"
+    }
+
+    fn run(&self, input: Option<String>) -> Result<(), String> {
+        let input_string = input.as_ref().expect("Test input is expected");
+        let params = KvsParameters::from_json(input_string).expect("Failed to parse parameters");
+        let kvs = kvs_instance(params).map_err(|e| format!("Failed to open KVS: {e:?}"))?;
+
+        for (key, expected) in interop_values() {
+            let value = kvs.get_value(key);
+            info!(key, value = to_str(&value));
+            if value.as_ref() != Ok(&expected) {
+                return Err(format!("Value mismatch for key {key}: {value:?}"));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub fn interop_group() -> Box<dyn ScenarioGroup> {
+    Box::new(ScenarioGroupImpl::new(
+        "interop",
+        vec![Box::new(Write), Box::new(Read)],
+        vec![],
+    ))
+}
@@ -1,4 +1,5 @@
 use crate::cit::default_values::default_values_group;
+use crate::cit::interop::interop_group;
 use crate::cit::multiple_kvs::multiple_kvs_group;
 use crate::cit::persistency::persistency_group;
 use crate::cit::snapshots::snapshots_group;
@@ -6,6 +7,7 @@ use crate::cit::supported_datatypes::supported_datatypes_group;
 use test_scenarios_rust::scenario::{ScenarioGroup, ScenarioGroupImpl};
 
 mod default_values;
+mod interop;
 mod multiple_kvs;
 mod persistency;
 mod snapshots;
@@ -17,6 +19,7 @@ pub fn cit_scenario_group() -> Box<dyn ScenarioGroup> {
         vec![],
         vec![
             default_values_group(),
+            interop_group(),
             multiple_kvs_group(),
             persistency_group(),
             snapshots_group(),
@@ -69,6 +69,7 @@ where
             "ignored" => KvsLoad::Ignored,
             "optional" => KvsLoad::Optional,
             "required" => KvsLoad::Required,
+            "recover_from_snapshot" => KvsLoad::RecoverFromSnapshot,
             _ => return Err(de::Error::custom("Invalid \"kvs_load\" mode")),
         };
         return Ok(Some(value));
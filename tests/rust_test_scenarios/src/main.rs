@@ -1,3 +1,5 @@
+use rust_kvs::kvs_log::{set_log_sink, StderrLogSink};
+use std::sync::Arc;
 use test_scenarios_rust::cli::run_cli_app;
 use test_scenarios_rust::scenario::ScenarioGroupImpl;
 use test_scenarios_rust::test_context::TestContext;
@@ -10,6 +12,9 @@ use crate::test_basic::BasicScenario;
 fn main() {
     let raw_arguments: Vec<String> = std::env::args().collect();
 
+    // Diagnostics of the KVS are checked on stderr.
+    set_log_sink(Arc::new(StderrLogSink));
+
     // Basic group.
     let basic_scenario = Box::new(BasicScenario);
     let basic_group = Box::new(ScenarioGroupImpl::new(